@@ -0,0 +1,141 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Throughput benchmarks for the scanning engine
+//!
+//! Run with `cargo bench -p huginn-core`. The `scan` benchmarks time whole scans of a mock
+//! [`Farm`] of loopback hosts, so a change to connection concurrency or the raw-socket engine
+//! shows up as a change in ports scanned per second; the TCP SYN scan is skipped without raw
+//! sockets. The rest time the per-probe and per-result work that large scans repeat millions
+//! of times. `huginn bench` runs the same farm scans with a configuration's settings.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use huginn_core::farm::Farm;
+use huginn_core::formatters;
+use huginn_core::normalize;
+use huginn_core::plugins::raw::{self, Ipv4Header};
+use huginn_core::ports::PortSpec;
+use huginn_core::{HuginnError, ScanResult, ScanType, Scanner, Statistics};
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+/// Hosts in the farm
+const HOSTS: usize = 4;
+
+/// Ports scanned on each host; every hundredth is open
+const PORTS: &str = "31000-31999";
+
+/// Results rendered by the per-result benchmarks
+const RESULTS: usize = 10_000;
+
+/// Whether a scan type can run here: the TCP SYN scan needs raw sockets
+fn available(runtime: &Runtime, scan_type: ScanType) -> bool {
+	let scanner = Scanner::builder()
+		.targets(["127.0.0.1"])
+		.scan_types([scan_type])
+		.ports("1".parse::<PortSpec>().ok())
+		.timeout(Duration::from_millis(100))
+		.plugins(huginn_core::plugins::builtin())
+		.progress(false)
+		.build();
+	runtime.block_on(scanner.run()).is_ok_and(|results| {
+		!results.iter().any(|r| r.is_error() && r.details.as_deref().is_some_and(|d| d.starts_with("permission_denied")))
+	})
+}
+
+fn scans(c: &mut Criterion) {
+	let runtime = Runtime::new().unwrap();
+	let ports: PortSpec = PORTS.parse().unwrap();
+	let open: Vec<u16> = ports.ports().iter().copied().step_by(100).collect();
+	let farm = runtime.block_on(Farm::start(HOSTS, &open)).expect("the mock target farm needs free loopback ports");
+
+	let mut group = c.benchmark_group("scan");
+	group.sample_size(10);
+	group.throughput(Throughput::Elements((HOSTS * ports.ports().len()) as u64));
+	for scan_type in [ScanType::TcpConnect, ScanType::TcpSyn] {
+		if !available(&runtime, scan_type) {
+			eprintln!("Skipping the {} benchmark: it needs raw sockets", scan_type);
+			continue;
+		}
+		let scanner = Scanner::builder()
+			.targets(farm.targets().iter().cloned())
+			.scan_types([scan_type])
+			.ports(ports.clone())
+			.timeout(Duration::from_millis(500))
+			.retries(0)
+			.plugins(huginn_core::plugins::builtin())
+			.progress(false)
+			.build();
+		group.bench_function(BenchmarkId::from_parameter(scan_type), |b| {
+			b.to_async(&runtime).iter(|| async {
+				let results = scanner.run().await.unwrap();
+				assert_eq!(results.iter().filter(|r| r.status == "open").count(), HOSTS * open.len());
+				results
+			})
+		});
+	}
+	group.finish();
+}
+
+fn probes(c: &mut Criterion) {
+	let (source, destination) = (Ipv4Addr::new(192, 0, 2, 1), Ipv4Addr::new(192, 0, 2, 2));
+	let mut buffer = Vec::with_capacity(1500);
+	let mut group = c.benchmark_group("probe");
+	group.throughput(Throughput::Elements(1));
+	group.bench_function("tcp_syn", |b| {
+		b.iter(|| {
+			buffer.clear();
+			let header = Ipv4Header {
+				source,
+				destination,
+				protocol: raw::PROTOCOL_TCP,
+				ttl: 64,
+				identification: 1,
+			};
+			header.write(&mut buffer, 20);
+			raw::write_tcp_segment(&mut buffer, source, destination, (40000, black_box(443)), 1, 0x02, &[]);
+			black_box(&buffer);
+		})
+	});
+	group.finish();
+}
+
+/// Results like those of a large scan: a few hosts, mostly closed ports
+fn results() -> Vec<ScanResult> {
+	(0..RESULTS)
+		.map(|i| {
+			let status = if i % 100 == 0 { "open" } else { "closed" };
+			ScanResult::new(format!("10.0.0.{}", i % 64 + 1), "tcp_syn", status).with_port((i / 64) as u16 + 1)
+		})
+		.collect()
+}
+
+fn reports(c: &mut Criterion) {
+	let results = results();
+	let statistics = Statistics::from_results(&results);
+	let mut group = c.benchmark_group("results");
+	group.throughput(Throughput::Elements(RESULTS as u64));
+	group.bench_function("normalize", |b| b.iter(|| normalize::normalize(results.clone(), &HashMap::new())));
+	group.bench_function("statistics", |b| b.iter(|| Statistics::from_results(black_box(&results))));
+	for format in ["json", "text"] {
+		let formatter = formatters::get(format).unwrap();
+		group.bench_function(BenchmarkId::new("format", format), |b| {
+			b.iter(|| formatter.format_with_statistics(&results, &statistics))
+		});
+		let mut out = Vec::new();
+		group.bench_function(BenchmarkId::new("write_stream", format), |b| {
+			b.iter(|| {
+				out.clear();
+				let mut read = results.iter().cloned().map(Ok::<_, HuginnError>);
+				formatter.write_stream(&mut read, &statistics, &mut out)
+			})
+		});
+	}
+	group.finish();
+}
+
+criterion_group!(benches, scans, probes, reports);
+criterion_main!(benches);
@@ -0,0 +1,154 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! MITRE ATT&CK technique tagging
+//!
+//! Plugins tag the findings an adversary could use with the IDs of the ATT&CK techniques they
+//! are relevant to, in [`ScanResult::techniques`]. An open RDP port, for example, is relevant to
+//! Remote Desktop Protocol lateral movement, `T1021.001`. The built-in port scans tag open ports
+//! of remote services with [`for_port`], and [`coverage`] summarizes which techniques a set of
+//! results touches for reports.
+
+use crate::plugins::ScanResult;
+use serde::Serialize;
+
+/// Techniques that Huginn tags findings with, and their names
+const TECHNIQUES: &[(&str, &str)] = &[
+	("T0888", "Remote System Information Discovery"),
+	("T1016", "System Network Configuration Discovery"),
+	("T1021.001", "Remote Services: Remote Desktop Protocol"),
+	("T1021.002", "Remote Services: SMB/Windows Admin Shares"),
+	("T1021.004", "Remote Services: SSH"),
+	("T1021.005", "Remote Services: VNC"),
+	("T1021.006", "Remote Services: Windows Remote Management"),
+	("T1040", "Network Sniffing"),
+	("T1056.001", "Input Capture: Keylogging"),
+	("T1068", "Exploitation for Privilege Escalation"),
+	("T1078.001", "Valid Accounts: Default Accounts"),
+	("T1087", "Account Discovery"),
+	("T1090", "Proxy"),
+	("T1110", "Brute Force"),
+	("T1113", "Screen Capture"),
+	("T1133", "External Remote Services"),
+	("T1190", "Exploit Public-Facing Application"),
+	("T1498.002", "Network Denial of Service: Reflection Amplification"),
+	("T1552.005", "Unsecured Credentials: Cloud Instance Metadata API"),
+	("T1552.007", "Unsecured Credentials: Container API"),
+	("T1557", "Adversary-in-the-Middle"),
+	("T1584.001", "Compromise Infrastructure: Domains"),
+	("T1584.002", "Compromise Infrastructure: DNS Server"),
+	("T1588.004", "Obtain Capabilities: Digital Certificates"),
+	("T1592.002", "Gather Victim Host Information: Software"),
+	("T1595.003", "Active Scanning: Wordlist Scanning"),
+	("T1599", "Network Boundary Bridging"),
+	("T1602.002", "Data from Configuration Repository: Network Device Configuration Dump"),
+	("T1610", "Deploy Container"),
+	("T1613", "Container and Resource Discovery"),
+	("T1672", "Email Spoofing"),
+];
+
+/// Techniques an open port of a well-known remote service is relevant to
+const PORTS: &[(u16, &[&str])] = &[
+	(22, &["T1021.004", "T1133"]),
+	(445, &["T1021.002"]),
+	(3389, &["T1021.001", "T1133"]),
+	(5900, &["T1021.005", "T1133"]),
+	(5985, &["T1021.006"]),
+	(5986, &["T1021.006"]),
+];
+
+/// Techniques that an open port is relevant to, if it is a well-known remote service port
+pub fn for_port(port: u16) -> Vec<String> {
+	PORTS
+		.iter()
+		.find(|(known, _)| *known == port)
+		.map(|(_, techniques)| techniques.iter().map(|t| t.to_string()).collect())
+		.unwrap_or_default()
+}
+
+/// Name of a technique, if Huginn knows it
+pub fn name(technique: &str) -> Option<&'static str> {
+	TECHNIQUES.iter().find(|(id, _)| *id == technique).map(|(_, name)| *name)
+}
+
+/// Whether text is a technique or sub-technique ID, such as `T1133` or `T1021.001`
+pub fn is_technique(id: &str) -> bool {
+	let digits = |s: &str, n: usize| s.len() == n && s.bytes().all(|b| b.is_ascii_digit());
+	let Some(rest) = id.strip_prefix('T') else {
+		return false;
+	};
+	match rest.split_once('.') {
+		Some((technique, sub)) => digits(technique, 4) && digits(sub, 3),
+		None => digits(rest, 4),
+	}
+}
+
+/// Findings tagged with one technique
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Coverage {
+	/// Technique ID
+	pub technique: String,
+	/// Technique name, when Huginn knows it
+	pub name: Option<&'static str>,
+	/// Results tagged with the technique
+	pub findings: usize,
+	/// Hosts with a result tagged with the technique, in scan order
+	pub hosts: Vec<String>,
+}
+
+/// Summarize the techniques the results are tagged with, ordered by technique ID
+pub fn coverage(results: &[ScanResult]) -> Vec<Coverage> {
+	let mut summary: Vec<Coverage> = Vec::new();
+	for result in results {
+		for technique in &result.techniques {
+			let index = match summary.iter().position(|c| &c.technique == technique) {
+				Some(index) => index,
+				None => {
+					summary.push(Coverage {
+						technique: technique.clone(),
+						name: name(technique),
+						findings: 0,
+						hosts: Vec::new(),
+					});
+					summary.len() - 1
+				},
+			};
+			let entry = &mut summary[index];
+			entry.findings += 1;
+			if !entry.hosts.contains(&result.target) {
+				entry.hosts.push(result.target.clone());
+			}
+		}
+	}
+	summary.sort_by(|a, b| a.technique.cmp(&b.technique));
+	summary
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_is_technique() {
+		assert!(is_technique("T1133"));
+		assert!(is_technique("T1021.001"));
+		assert!(!is_technique("T1021.1"));
+		assert!(!is_technique("TA0008"));
+		assert!(!is_technique("t1133"));
+	}
+
+	#[test]
+	fn test_coverage() {
+		let results = [("10.0.0.1", 3389), ("10.0.0.2", 3389), ("10.0.0.2", 22), ("10.0.0.2", 80)]
+			.map(|(target, port)| {
+				ScanResult::new(target, "tcp_connect", "open").with_port(port).with_techniques(for_port(port))
+			});
+		let summary = coverage(&results);
+		let techniques: Vec<&str> = summary.iter().map(|c| c.technique.as_str()).collect();
+		assert_eq!(techniques, ["T1021.001", "T1021.004", "T1133"]);
+		assert_eq!(summary[0].name, Some("Remote Services: Remote Desktop Protocol"));
+		assert_eq!(summary[0].hosts, ["10.0.0.1", "10.0.0.2"]);
+		assert_eq!(summary[2].findings, 3);
+		assert_eq!(summary[2].hosts, ["10.0.0.1", "10.0.0.2"]);
+	}
+}
@@ -0,0 +1,151 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Credentials for authenticated checks
+//!
+//! A scan carries the credentials it may use as a [`Credentials`] set, by name, which plugins read
+//! from [`ScanContext::credentials`](crate::ScanContext::credentials). Secrets are held as
+//! [`Secret`]s, which never show up in debug output, so contexts and results can be logged
+//! without leaking them. Where the secrets come from is up to the caller; the `huginn` command
+//! line reads them from environment variables, files, or the system keyring.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A password, token, or private key
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+	/// Wrap a secret value
+	pub fn new(value: impl Into<String>) -> Self {
+		Self(value.into())
+	}
+
+	/// The secret value, for the one place that sends it
+	pub fn expose(&self) -> &str {
+		&self.0
+	}
+}
+
+impl fmt::Debug for Secret {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("Secret([REDACTED])")
+	}
+}
+
+/// Authentication protocol of an SNMPv3 user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthProtocol {
+	/// HMAC-SHA-96 (RFC 3414)
+	Sha,
+	/// HMAC-SHA-256-192 (RFC 7860)
+	Sha256,
+}
+
+/// Privacy protocol of an SNMPv3 user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrivacyProtocol {
+	/// AES-128 in CFB mode (RFC 3826)
+	Aes,
+}
+
+/// A credential that authenticated checks log in with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Credential {
+	/// SSH login, with a private key, a password, or both
+	Ssh {
+		/// User name
+		user: String,
+		/// Private key, in OpenSSH or PEM format
+		key: Option<Secret>,
+		/// Passphrase the private key is encrypted with
+		passphrase: Option<Secret>,
+		/// Password
+		password: Option<Secret>,
+	},
+	/// Bearer token for an API
+	Token {
+		/// Token value
+		token: Secret,
+	},
+	/// SNMPv3 user; without authentication it can only read what noAuthNoPriv allows
+	SnmpV3 {
+		/// User name
+		user: String,
+		/// Authentication protocol and password
+		auth: Option<(AuthProtocol, Secret)>,
+		/// Privacy protocol and password; needs authentication
+		privacy: Option<(PrivacyProtocol, Secret)>,
+	},
+}
+
+impl Credential {
+	/// Kind of credential, as written in configuration files
+	pub fn kind(&self) -> &'static str {
+		match self {
+			Credential::Ssh { .. } => "ssh",
+			Credential::Token { .. } => "token",
+			Credential::SnmpV3 { .. } => "snmpv3",
+		}
+	}
+}
+
+/// The credentials a scan may use, by name
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Credentials(BTreeMap<String, Credential>);
+
+impl Credentials {
+	/// Add a credential, replacing any with the same name
+	pub fn insert(&mut self, name: impl Into<String>, credential: Credential) {
+		self.0.insert(name.into(), credential);
+	}
+
+	/// The credential with a name
+	pub fn get(&self, name: &str) -> Option<&Credential> {
+		self.0.get(name)
+	}
+
+	/// Every credential with its name, sorted by name
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &Credential)> {
+		self.0.iter().map(|(name, credential)| (name.as_str(), credential))
+	}
+
+	/// Whether there are no credentials
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+}
+
+impl<N: Into<String>> FromIterator<(N, Credential)> for Credentials {
+	fn from_iter<I: IntoIterator<Item = (N, Credential)>>(iter: I) -> Self {
+		Self(iter.into_iter().map(|(name, credential)| (name.into(), credential)).collect())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_secrets_are_redacted() {
+		let credentials: Credentials = [(
+			"switches",
+			Credential::SnmpV3 {
+				user: "monitor".to_string(),
+				auth: Some((AuthProtocol::Sha, Secret::new("hunter22"))),
+				privacy: None,
+			},
+		)]
+		.into_iter()
+		.collect();
+		let debug = format!("{:?}", credentials);
+		assert!(!debug.contains("hunter22"), "{}", debug);
+		assert!(debug.contains("monitor"));
+		assert_eq!(credentials.get("switches").map(Credential::kind), Some("snmpv3"));
+		assert_eq!(credentials.iter().count(), 1);
+	}
+}
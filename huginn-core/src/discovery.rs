@@ -0,0 +1,384 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Host discovery
+//!
+//! Before port scans run, the scanner can check which targets are up and skip the port scans of
+//! the rest (see [`crate::ScannerBuilder::discovery`]). On a sparse range that replaces probing
+//! every port of every address with a few probes of most of them. A target is up once it answers
+//! any of the enabled [`Method`]s, which are tried at the same time; each target gets a
+//! `discovery` result saying which method it answered, or that it answered none.
+//!
+//! Echo requests are often filtered, so besides echo, SYN, and ARP there are methods that
+//! firewalls tend to let through: a TCP ACK, which stateless filters pass as part of an
+//! established connection; a UDP datagram to an unused port; and the ICMP timestamp and address
+//! mask requests. The ACK, timestamp, and address mask methods need raw sockets.
+
+use crate::error::HuginnError;
+use crate::plugins::ping::PingScanPlugin;
+use crate::plugins::raw::{self, ACK, Ipv4Header, PROTOCOL_TCP, RST, TcpReply};
+use crate::plugins::tcp_syn::TcpSynScanPlugin;
+use crate::plugins::{Plugin, ScanContext};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use socket2::Protocol;
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use tokio::time::Instant;
+
+/// Ports that TCP SYN discovery probes
+const SYN_PORTS: &[u16] = &[80, 443];
+
+/// Port that TCP ACK discovery probes
+const ACK_PORT: u16 = 80;
+
+/// Port that UDP discovery probes, which is unlikely to be in use
+const UDP_PORT: u16 = 40125;
+
+/// ICMP types of the timestamp request and reply
+const ICMP_TIMESTAMP: (u8, u8) = (13, 14);
+
+/// ICMP types of the address mask request and reply
+const ICMP_ADDRESS_MASK: (u8, u8) = (17, 18);
+
+/// A way of finding out whether a host is up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Method {
+	/// ICMP echo request, as the ping scan sends
+	Echo,
+	/// TCP SYN to ports 80 and 443, where a SYN-ACK and a reset both mean the host is up
+	Syn,
+	/// ARP resolution, for targets on the local network (Linux only)
+	Arp,
+	/// TCP ACK to port 80, which a host answers with a reset
+	Ack,
+	/// UDP datagram to an unused port, which a host answers with ICMP port unreachable
+	Udp,
+	/// ICMP timestamp request
+	Timestamp,
+	/// ICMP address mask request
+	Netmask,
+}
+
+impl Method {
+	/// Whether the method always sends over raw sockets; echo needs them only where unprivileged
+	/// ICMP datagram sockets are not allowed, and SYN falls back to connection attempts
+	pub fn needs_raw_sockets(&self) -> bool {
+		matches!(self, Method::Ack | Method::Timestamp | Method::Netmask)
+	}
+
+	/// What the method sends, for result details
+	fn describe(&self) -> &'static str {
+		match self {
+			Method::Echo => "ICMP echo",
+			Method::Syn => "TCP SYN to 80/443",
+			Method::Arp => "ARP",
+			Method::Ack => "TCP ACK to 80",
+			Method::Udp => "UDP to 40125",
+			Method::Timestamp => "ICMP timestamp",
+			Method::Netmask => "ICMP address mask",
+		}
+	}
+}
+
+impl fmt::Display for Method {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Method::Echo => "echo",
+			Method::Syn => "syn",
+			Method::Arp => "arp",
+			Method::Ack => "ack",
+			Method::Udp => "udp",
+			Method::Timestamp => "timestamp",
+			Method::Netmask => "netmask",
+		})
+	}
+}
+
+impl FromStr for Method {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		ALL_METHODS
+			.iter()
+			.copied()
+			.find(|method| method.to_string() == name)
+			.ok_or_else(|| format!("unknown discovery method {:?}; expected one of echo, syn, arp, ack, udp, timestamp, netmask", name))
+	}
+}
+
+/// Every discovery method
+const ALL_METHODS: &[Method] = &[
+	Method::Echo,
+	Method::Syn,
+	Method::Arp,
+	Method::Ack,
+	Method::Udp,
+	Method::Timestamp,
+	Method::Netmask,
+];
+
+/// Methods used when discovery is enabled without choosing any
+pub const DEFAULT_METHODS: &[Method] = &[Method::Echo, Method::Syn, Method::Arp];
+
+/// Whether the target answers an ICMP echo request
+async fn echo(target: &str, context: &ScanContext) -> Result<bool, HuginnError> {
+	let results = PingScanPlugin.scan(target, context).await?;
+	Ok(results.iter().any(|r| r.status == "up"))
+}
+
+/// Whether the target answers a SYN to a discovery port, with a connection attempt when raw
+/// sockets are not permitted
+async fn syn(target: &str, context: &ScanContext) -> Result<bool, HuginnError> {
+	let ports = SYN_PORTS.iter().map(u16::to_string).collect::<Vec<_>>().join(",");
+	let context = ScanContext {
+		ports: Some(ports.parse().map_err(HuginnError::Config)?),
+		..context.clone()
+	};
+	match TcpSynScanPlugin.scan(target, &context).await {
+		Ok(results) => Ok(results.iter().any(|r| r.status == "open" || r.status == "closed")),
+		Err(HuginnError::PermissionDenied(_)) => {
+			for &port in SYN_PORTS {
+				context.probes.sent();
+				match tokio::time::timeout(context.timeout, context.connect(target, port)).await {
+					Ok(Ok(_)) => return Ok(true),
+					Ok(Err(e)) if e.kind() == io::ErrorKind::ConnectionRefused => return Ok(true),
+					_ => {},
+				}
+			}
+			Ok(false)
+		},
+		Err(e) => Err(e),
+	}
+}
+
+/// Whether the target answers a TCP ACK with a reset
+async fn ack(target: &str, context: &ScanContext) -> Result<bool, HuginnError> {
+	let address = raw::resolve(target, context).await?;
+	let source = raw::source_address(address, context)?;
+	let sender = raw::socket(Protocol::from(raw::PROTOCOL_RAW), context)?;
+	let receiver = raw::socket(Protocol::TCP, context)?;
+	let port = raw::ephemeral_port();
+	let segment = raw::tcp_segment(source, address, (port, ACK_PORT), raw::random() as u32, ACK, &[]);
+	let packet = Ipv4Header {
+		source,
+		destination: address,
+		protocol: PROTOCOL_TCP,
+		ttl: context.raw.ttl.unwrap_or(raw::DEFAULT_TTL),
+		identification: raw::random() as u16,
+	}
+	.packet(&segment);
+
+	let answered = context
+		.probe(|| async {
+			raw::send(&sender, &packet, address, &context.raw).await?;
+			let mut buffer = [0; 1500];
+			loop {
+				let (length, _) = receiver.recv_from(&mut buffer).await?;
+				if let Some(reply) = TcpReply::parse(&buffer[..length])
+					&& reply.source == address
+					&& (reply.source_port, reply.destination_port) == (ACK_PORT, port)
+					&& reply.flags & RST != 0
+				{
+					return Ok::<_, io::Error>(());
+				}
+			}
+		})
+		.await;
+	Ok(matches!(answered, Some(Ok(()))))
+}
+
+/// Whether the target answers a UDP datagram to an unused port, with either data or, as the
+/// kernel reports on a connected socket, ICMP port unreachable
+async fn udp(target: &str, context: &ScanContext) -> Result<bool, HuginnError> {
+	let address = raw::resolve(target, context).await?;
+	let destination = SocketAddr::from((address, UDP_PORT));
+	let socket = context.transport.udp(destination, context).await?;
+	let answered = context
+		.probe(|| async {
+			socket.send_to(&[], destination).await?;
+			socket.recv_from(&mut [0; 512]).await
+		})
+		.await;
+	Ok(match answered {
+		Some(Ok(_)) => true,
+		Some(Err(e)) => e.kind() == io::ErrorKind::ConnectionRefused,
+		None => false,
+	})
+}
+
+/// Whether the target answers an ICMP request of the given type with its reply type
+async fn icmp(target: &str, (request, reply): (u8, u8), context: &ScanContext) -> Result<bool, HuginnError> {
+	let address = raw::resolve(target, context).await?;
+	let socket = raw::socket(Protocol::ICMPV4, context)?;
+	let identifier = raw::random() as u16;
+	// Timestamp requests carry three timestamps and address mask requests one mask
+	let body = if request == ICMP_TIMESTAMP.0 { 12 } else { 4 };
+	let mut packet = vec![request, 0, 0, 0];
+	packet.extend_from_slice(&identifier.to_be_bytes());
+	packet.extend_from_slice(&[0, 0]);
+	packet.resize(8 + body, 0);
+	let sum = raw::checksum(&packet);
+	packet[2..4].copy_from_slice(&sum.to_be_bytes());
+
+	let answered = context
+		.probe(|| async {
+			socket.send_to(&packet, (address, 0)).await?;
+			let mut buffer = [0; 1500];
+			loop {
+				let (length, _) = socket.recv_from(&mut buffer).await?;
+				let received = &buffer[..length];
+				let header = usize::from(received[0] & 0x0F) * 4;
+				let Some(icmp) = received.get(header..header + 8) else {
+					continue;
+				};
+				if received.get(12..16) == Some(&address.octets()[..]) && icmp[0] == reply && icmp[4..6] == identifier.to_be_bytes() {
+					return Ok::<_, io::Error>(());
+				}
+			}
+		})
+		.await;
+	Ok(matches!(answered, Some(Ok(()))))
+}
+
+/// Whether the kernel resolves the target's hardware address once a datagram is sent to it
+///
+/// Sending to an address on the local network makes the kernel send an ARP request for it, and
+/// the answer appears in the neighbor table. Addresses on other networks never appear there.
+#[cfg(target_os = "linux")]
+async fn arp(target: &str, context: &ScanContext) -> Result<bool, HuginnError> {
+	/// Flag of a neighbor table entry whose hardware address is known
+	const COMPLETE: u32 = 0x2;
+
+	let address = SocketAddr::from((raw::resolve(target, context).await?, 9));
+	let socket = context.transport.udp(address, context).await?;
+	context.probes.sent();
+	socket.send_to(&[], address).await?;
+
+	let deadline = Instant::now() + context.timeout;
+	let needle = address.ip().to_string();
+	loop {
+		let table = tokio::fs::read_to_string("/proc/net/arp").await?;
+		let resolved = table.lines().skip(1).any(|line| {
+			let fields: Vec<&str> = line.split_whitespace().collect();
+			fields.len() > 2
+				&& fields[0] == needle
+				&& u32::from_str_radix(fields[2].trim_start_matches("0x"), 16).is_ok_and(|flags| flags & COMPLETE != 0)
+		});
+		if resolved {
+			return Ok(true);
+		}
+		if Instant::now() >= deadline {
+			return Ok(false);
+		}
+		tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+	}
+}
+
+/// ARP discovery reads the Linux neighbor table, so it finds nothing elsewhere
+#[cfg(not(target_os = "linux"))]
+async fn arp(_target: &str, _context: &ScanContext) -> Result<bool, HuginnError> {
+	Ok(false)
+}
+
+/// Try the methods on the target at the same time, returning the first it answered, or `None`
+/// when it answered none
+///
+/// A method that fails, for example for lack of privileges, tells nothing about the target, so it
+/// is warned about rather than counted as unanswered; when every method fails, discovery fails.
+pub async fn discover(
+	target: &str,
+	methods: &[Method],
+	context: &ScanContext,
+) -> Result<Option<Method>, HuginnError> {
+	let run = |method: Method| async move {
+		if !methods.contains(&method) {
+			return None;
+		}
+		Some(match method {
+			Method::Echo => echo(target, context).await,
+			Method::Syn => syn(target, context).await,
+			Method::Arp => arp(target, context).await,
+			Method::Ack => ack(target, context).await,
+			Method::Udp => udp(target, context).await,
+			Method::Timestamp => icmp(target, ICMP_TIMESTAMP, context).await,
+			Method::Netmask => icmp(target, ICMP_ADDRESS_MASK, context).await,
+		})
+	};
+	let outcomes = tokio::join!(
+		run(Method::Echo),
+		run(Method::Syn),
+		run(Method::Arp),
+		run(Method::Ack),
+		run(Method::Udp),
+		run(Method::Timestamp),
+		run(Method::Netmask),
+	);
+	let outcomes = [outcomes.0, outcomes.1, outcomes.2, outcomes.3, outcomes.4, outcomes.5, outcomes.6];
+
+	let (mut answered, mut unanswered, mut failure) = (None, false, None);
+	for (&method, outcome) in ALL_METHODS.iter().zip(outcomes) {
+		match outcome {
+			Some(Ok(true)) => answered = answered.or(Some(method)),
+			Some(Ok(false)) => unanswered = true,
+			Some(Err(e)) => {
+				warn!("{} discovery of {} failed: {}", method, target, e);
+				failure = Some(e);
+			},
+			None => {},
+		}
+	}
+	match failure {
+		Some(e) if answered.is_none() && !unanswered => Err(e),
+		_ => Ok(answered),
+	}
+}
+
+/// Details of a discovery result
+pub fn details(answered: Option<Method>, methods: &[Method]) -> String {
+	match answered {
+		Some(method) => format!("answered {}", method.describe()),
+		None => {
+			let tried: Vec<&str> = methods.iter().map(Method::describe).collect();
+			format!("no answer to {}", tried.join(", "))
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::plugins::ScanType;
+	use crate::scanner::Scanner;
+	use std::time::Duration;
+
+	#[tokio::test]
+	async fn test_discover_loopback() {
+		let context = Scanner::builder().timeout(Duration::from_millis(500)).context(ScanType::Ping);
+		// Loopback answers a SYN to a closed port with a reset, raw socket or not
+		assert_eq!(discover("127.0.0.1", &[Method::Syn], &context).await.unwrap(), Some(Method::Syn));
+		assert_eq!(discover("127.0.0.1", &[], &context).await.unwrap(), None);
+		// Loopback answers UDP to an unused port with port unreachable
+		assert_eq!(discover("127.0.0.1", &[Method::Udp], &context).await.unwrap(), Some(Method::Udp));
+		// A method that cannot run fails discovery rather than finding the host down
+		let unresolved = discover("host.invalid", &[Method::Ack], &context).await;
+		assert!(matches!(unresolved, Err(HuginnError::Unresolved { .. })), "{:?}", unresolved);
+	}
+
+	#[test]
+	fn test_method_names() {
+		for method in ALL_METHODS {
+			assert_eq!(method.to_string().parse::<Method>().unwrap(), *method);
+		}
+		assert!("pe".parse::<Method>().is_err());
+	}
+
+	#[test]
+	fn test_details() {
+		assert_eq!(details(Some(Method::Syn), DEFAULT_METHODS), "answered TCP SYN to 80/443");
+		assert_eq!(details(None, &[Method::Echo, Method::Arp]), "no answer to ICMP echo, ARP");
+	}
+}
@@ -0,0 +1,124 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Errors for Huginn
+//!
+//! [`HuginnError`] is the error type of the scanner, plugins, target expansion, and formatters,
+//! so callers can branch on what went wrong instead of matching message text.
+
+use std::error::Error;
+use std::io;
+use std::time::Duration;
+
+/// Error from another library, kept as the source of a [`HuginnError`]
+pub type BoxError = Box<dyn Error + Send + Sync>;
+
+/// What went wrong in a scan or while rendering its results
+#[derive(Debug, thiserror::Error)]
+pub enum HuginnError {
+	/// Settings that cannot be used, such as a source address of the wrong family
+	#[error("{0}")]
+	Config(String),
+	/// A target that is not a valid host, address, or CIDR block, or is too large to expand
+	#[error("invalid target: {reason}")]
+	TargetParse {
+		/// Target as given
+		target: String,
+		/// Why it was refused
+		reason: String,
+	},
+	/// A host name that did not resolve to an address
+	#[error("{target} did not resolve to an address")]
+	Unresolved {
+		/// Host name that was looked up
+		target: String,
+	},
+	/// Every attempt at a probe that needed an answer timed out
+	#[error("no answer from {target} within {timeout:?}")]
+	ProbeTimeout {
+		/// Target that was probed
+		target: String,
+		/// Time allowed for each attempt
+		timeout: Duration,
+	},
+	/// The scan needs privileges this process lacks, such as raw sockets
+	#[error("{0}")]
+	PermissionDenied(String),
+	/// A plugin failed for a reason of its own
+	#[error("{plugin}: {source}")]
+	Plugin {
+		/// Plugin name
+		plugin: String,
+		/// Underlying error
+		#[source]
+		source: BoxError,
+	},
+	/// Results could not be rendered in an output format
+	#[error("cannot render {format} output: {source}")]
+	Format {
+		/// Output format identifier
+		format: String,
+		/// Underlying error
+		#[source]
+		source: BoxError,
+	},
+	/// Network or file I/O failed
+	#[error(transparent)]
+	Io(#[from] io::Error),
+}
+
+impl HuginnError {
+	/// Short identifier of the error kind, as recorded in error results
+	pub fn kind(&self) -> &'static str {
+		match self {
+			HuginnError::Config(_) => "config",
+			HuginnError::TargetParse { .. } => "target_parse",
+			HuginnError::Unresolved { .. } => "unresolved",
+			HuginnError::ProbeTimeout { .. } => "probe_timeout",
+			HuginnError::PermissionDenied(_) => "permission_denied",
+			HuginnError::Plugin { .. } => "plugin",
+			HuginnError::Format { .. } => "format",
+			HuginnError::Io(_) => "io",
+		}
+	}
+
+	/// Wrap an error raised inside a plugin
+	pub fn plugin(plugin: impl Into<String>, source: impl Into<BoxError>) -> Self {
+		HuginnError::Plugin {
+			plugin: plugin.into(),
+			source: source.into(),
+		}
+	}
+
+	/// Wrap an error raised while rendering an output format
+	pub fn format(format: impl Into<String>, source: impl Into<BoxError>) -> Self {
+		HuginnError::Format {
+			format: format.into(),
+			source: source.into(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_messages() {
+		let error = HuginnError::plugin("Banner Grabber", "connection reset");
+		assert_eq!(error.to_string(), "Banner Grabber: connection reset");
+		assert_eq!(error.source().unwrap().to_string(), "connection reset");
+		assert_eq!(error.kind(), "plugin");
+
+		let error: HuginnError = io::Error::new(io::ErrorKind::ConnectionRefused, "refused").into();
+		assert!(matches!(&error, HuginnError::Io(e) if e.kind() == io::ErrorKind::ConnectionRefused));
+		assert_eq!(error.to_string(), "refused");
+
+		let error = HuginnError::ProbeTimeout {
+			target: "example.com".to_string(),
+			timeout: Duration::from_secs(3),
+		};
+		assert_eq!(error.to_string(), "no answer from example.com within 3s");
+		assert_eq!(error.kind(), "probe_timeout");
+	}
+}
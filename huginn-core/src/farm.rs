@@ -0,0 +1,114 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Mock target farm for benchmarks
+//!
+//! A [`Farm`] stands up a set of hosts on loopback addresses, 127.0.0.1 onward, each listening on
+//! the same TCP ports and answering UDP datagrams sent to them, so the scan types and the engine
+//! behind them can be timed against known results without touching a real network. Every other
+//! port is closed. Linux routes all of 127.0.0.0/8 to loopback; other systems need an alias for
+//! each address past 127.0.0.1.
+
+use std::io;
+use std::net::Ipv4Addr;
+use tokio::net::{TcpListener, UdpSocket};
+use tokio::task::JoinHandle;
+
+/// Most hosts a farm can hold, one per loopback address from 127.0.0.1 to 127.0.0.254
+pub const MAX_HOSTS: usize = 254;
+
+/// Loopback hosts listening on a set of ports, until the farm is dropped
+pub struct Farm {
+	targets: Vec<String>,
+	open: Vec<u16>,
+	tasks: Vec<JoinHandle<()>>,
+}
+
+impl Farm {
+	/// Start `hosts` hosts, each accepting TCP connections and echoing UDP datagrams on every
+	/// port in `open`
+	pub async fn start(hosts: usize, open: &[u16]) -> io::Result<Self> {
+		if hosts == 0 || hosts > MAX_HOSTS {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("a farm holds from 1 to {} hosts", MAX_HOSTS),
+			));
+		}
+		let mut farm = Self {
+			targets: Vec::with_capacity(hosts),
+			open: open.to_vec(),
+			tasks: Vec::with_capacity(hosts * open.len() * 2),
+		};
+		for host in 1..=hosts as u8 {
+			let address = Ipv4Addr::new(127, 0, 0, host);
+			for &port in open {
+				let listener = TcpListener::bind((address, port)).await.map_err(|e| bound(e, address, port))?;
+				farm.tasks.push(tokio::spawn(accept(listener)));
+				let socket = UdpSocket::bind((address, port)).await.map_err(|e| bound(e, address, port))?;
+				farm.tasks.push(tokio::spawn(echo(socket)));
+			}
+			farm.targets.push(address.to_string());
+		}
+		Ok(farm)
+	}
+
+	/// Addresses of the hosts
+	pub fn targets(&self) -> &[String] {
+		&self.targets
+	}
+
+	/// Ports open on every host
+	pub fn open(&self) -> &[u16] {
+		&self.open
+	}
+}
+
+impl Drop for Farm {
+	fn drop(&mut self) {
+		for task in &self.tasks {
+			task.abort();
+		}
+	}
+}
+
+/// Name the address and port that could not be listened on
+fn bound(error: io::Error, address: Ipv4Addr, port: u16) -> io::Error {
+	io::Error::new(error.kind(), format!("cannot listen on {}:{}: {}", address, port, error))
+}
+
+/// Accept connections and close them at once
+async fn accept(listener: TcpListener) {
+	while listener.accept().await.is_ok() {}
+}
+
+/// Send every datagram back to where it came from
+async fn echo(socket: UdpSocket) {
+	let mut buffer = vec![0; 65535];
+	while let Ok((length, from)) = socket.recv_from(&mut buffer).await {
+		let _ = socket.send_to(&buffer[..length], from).await;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn test_farm() {
+		let probe = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+		let port = probe.local_addr().unwrap().port();
+		drop(probe);
+
+		let farm = Farm::start(2, &[port]).await.unwrap();
+		assert_eq!(farm.targets(), ["127.0.0.1", "127.0.0.2"]);
+		tokio::net::TcpStream::connect(("127.0.0.2", port)).await.unwrap();
+
+		let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+		socket.send_to(b"ping", ("127.0.0.1", port)).await.unwrap();
+		let mut buffer = [0; 4];
+		let length = socket.recv(&mut buffer).await.unwrap();
+		assert_eq!(&buffer[..length], b"ping");
+
+		assert!(Farm::start(0, &[port]).await.is_err());
+	}
+}
@@ -0,0 +1,78 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Output formatters for Huginn
+//!
+//! This module defines the formatter trait and the registry used to turn scan results into
+//! report documents.
+
+pub mod cyclonedx;
+pub mod dot;
+pub mod hosts;
+pub mod json;
+pub mod template;
+pub mod text;
+
+use crate::error::HuginnError;
+use crate::plugins::ScanResult;
+use crate::statistics::Statistics;
+use std::io::Write;
+
+/// Formatter trait that all output formats must implement
+pub trait Formatter: Send + Sync {
+	/// Get the output format identifier
+	fn name(&self) -> String;
+
+	/// Render the scan results as a document
+	fn format(&self, results: &[ScanResult]) -> Result<String, HuginnError>;
+
+	/// Render the scan results with statistics about the run that produced them
+	///
+	/// Formats without a statistics summary render the results alone.
+	fn format_with_statistics(&self, results: &[ScanResult], _statistics: &Statistics) -> Result<String, HuginnError> {
+		self.format(results)
+	}
+
+	/// Write results read one at a time, such as from a [`Spool`](crate::spool::Spool), with
+	/// statistics about the run that produced them
+	///
+	/// Formats that must see every result before writing any collect them and render them with
+	/// [`Formatter::format_with_statistics`]; the others write each result as it is read.
+	fn write_stream(
+		&self,
+		results: &mut dyn Iterator<Item = Result<ScanResult, HuginnError>>,
+		statistics: &Statistics,
+		out: &mut dyn Write,
+	) -> Result<(), HuginnError> {
+		let results = results.collect::<Result<Vec<_>, _>>()?;
+		out.write_all(self.format_with_statistics(&results, statistics)?.as_bytes())?;
+		Ok(())
+	}
+}
+
+/// One line per failed scan, such as `10.0.0.1 ping: permission_denied: ...`, for error summaries
+pub fn error_summary(results: &[ScanResult]) -> Vec<String> {
+	results
+		.iter()
+		.filter(|result| result.is_error())
+		.map(|result| format!("{} {}: {}", result.target, result.scan_type, result.details.as_deref().unwrap_or("failed")))
+		.collect()
+}
+
+/// Look up a formatter by its output format identifier
+pub fn get(name: &str) -> Option<Box<dyn Formatter>> {
+	match name {
+		"json" => Some(Box::new(json::JsonFormatter { statistics: false })),
+		"json-statistics" => Some(Box::new(json::JsonFormatter { statistics: true })),
+		"cyclonedx" => Some(Box::new(cyclonedx::CycloneDxFormatter)),
+		"dot" => Some(Box::new(dot::DotFormatter)),
+		"text" => Some(Box::new(text::TextFormatter)),
+		"hosts" => Some(Box::new(hosts::HostsFormatter {
+			encoding: hosts::Encoding::Json,
+		})),
+		"hosts-yaml" => Some(Box::new(hosts::HostsFormatter {
+			encoding: hosts::Encoding::Yaml,
+		})),
+		_ => None,
+	}
+}
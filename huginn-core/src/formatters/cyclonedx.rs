@@ -0,0 +1,155 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! CycloneDX asset inventory formatter
+//!
+//! Converts scanned hosts and identified services into a CycloneDX 1.5 BOM document so results
+//! can be fed to vulnerability correlation tooling that already consumes BOM formats. Failed scans
+//! are listed as `huginn:error` properties of the BOM metadata.
+
+use crate::error::HuginnError;
+use crate::formatters::{self, Formatter};
+use crate::plugins::ScanResult;
+use serde_json::{Value, json};
+
+/// CycloneDX specification version emitted by this formatter
+const SPEC_VERSION: &str = "1.5";
+
+/// CycloneDX asset inventory formatter
+pub struct CycloneDxFormatter;
+
+impl CycloneDxFormatter {
+	/// Build the BOM reference for a host component
+	fn host_ref(target: &str) -> String {
+		format!("host:{}", target)
+	}
+
+	/// Build the BOM reference for a service discovered on a host
+	fn service_ref(result: &ScanResult, service: &str) -> String {
+		match result.port {
+			Some(port) => format!("service:{}:{}:{}", result.target, port, service),
+			None => format!("service:{}:{}", result.target, service),
+		}
+	}
+
+	/// Build the CycloneDX service object for a result with an identified service
+	fn service(result: &ScanResult, service: &str) -> Value {
+		let mut value = json!({
+			"bom-ref": Self::service_ref(result, service),
+			"name": service,
+			"properties": [
+				{ "name": "huginn:host", "value": result.target },
+				{ "name": "huginn:scan_type", "value": result.scan_type },
+			],
+		});
+		if let Some(version) = &result.version {
+			value["version"] = json!(version);
+		}
+		if let Some(port) = result.port {
+			value["endpoints"] = json!([format!("{}:{}", result.target, port)]);
+		}
+		value
+	}
+
+	/// Build the complete BOM document
+	fn bom(results: &[ScanResult]) -> Value {
+		let mut hosts: Vec<&str> = Vec::new();
+		for result in results {
+			if !hosts.contains(&result.target.as_str()) {
+				hosts.push(&result.target);
+			}
+		}
+
+		let components: Vec<Value> = hosts
+			.iter()
+			.map(|host| {
+				let properties: Vec<Value> = results
+					.iter()
+					.filter(|r| r.target == *host)
+					.map(|r| json!({ "name": format!("huginn:{}", r.scan_type), "value": r.status }))
+					.collect();
+				json!({
+					"type": "device",
+					"bom-ref": Self::host_ref(host),
+					"name": host,
+					"properties": properties,
+				})
+			})
+			.collect();
+
+		let services: Vec<Value> = results
+			.iter()
+			.filter_map(|r| r.service.as_deref().map(|s| Self::service(r, s)))
+			.collect();
+
+		let dependencies: Vec<Value> = hosts
+			.iter()
+			.map(|host| {
+				let depends_on: Vec<String> = results
+					.iter()
+					.filter(|r| r.target == *host)
+					.filter_map(|r| r.service.as_deref().map(|s| Self::service_ref(r, s)))
+					.collect();
+				json!({ "ref": Self::host_ref(host), "dependsOn": depends_on })
+			})
+			.collect();
+
+		let errors: Vec<Value> = formatters::error_summary(results)
+			.into_iter()
+			.map(|error| json!({ "name": "huginn:error", "value": error }))
+			.collect();
+
+		json!({
+			"bomFormat": "CycloneDX",
+			"specVersion": SPEC_VERSION,
+			"version": 1,
+			"metadata": {
+				"timestamp": chrono::Utc::now().to_rfc3339(),
+				"tools": {
+					"components": [{
+						"type": "application",
+						"name": "huginn",
+						"version": env!("CARGO_PKG_VERSION"),
+					}],
+				},
+				"properties": errors,
+			},
+			"components": components,
+			"services": services,
+			"dependencies": dependencies,
+		})
+	}
+}
+
+impl Formatter for CycloneDxFormatter {
+	fn name(&self) -> String {
+		"cyclonedx".to_string()
+	}
+
+	fn format(&self, results: &[ScanResult]) -> Result<String, HuginnError> {
+		serde_json::to_string_pretty(&Self::bom(results)).map_err(|e| HuginnError::format(self.name(), e))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_bom_groups_services_by_host() {
+		let results = vec![
+			ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22).with_service("ssh").with_version("1.0"),
+			ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(80).with_service("http").with_version("1.0"),
+			ScanResult::new("10.0.0.2", "tcp_connect", "open"),
+		];
+		let bom = CycloneDxFormatter::bom(&results);
+
+		assert_eq!(bom["bomFormat"], "CycloneDX");
+		assert_eq!(bom["components"].as_array().unwrap().len(), 2);
+		assert_eq!(bom["services"].as_array().unwrap().len(), 2);
+		assert_eq!(bom["services"][0]["endpoints"][0], "10.0.0.1:22");
+		assert_eq!(bom["services"][0]["version"], "1.0");
+		assert_eq!(bom["dependencies"][0]["dependsOn"].as_array().unwrap().len(), 2);
+		assert_eq!(bom["dependencies"][1]["dependsOn"].as_array().unwrap().len(), 0);
+	}
+}
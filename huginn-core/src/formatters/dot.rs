@@ -0,0 +1,176 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Graphviz DOT network map formatter
+//!
+//! Renders scanned targets and their open services as a Graphviz graph. Failed scans are listed in
+//! a note apart from the map.
+
+use crate::error::HuginnError;
+use crate::formatters::{self, Formatter};
+use crate::plugins::ScanResult;
+use std::fmt::Write;
+
+/// Node identifier of the scanner, which no host or service identifier can take, as none contains
+/// an `@`
+const SCANNER: &str = "@scanner";
+
+/// Node identifier of the note listing failed scans
+const ERRORS: &str = "@errors";
+
+/// Graphviz DOT network map formatter
+pub struct DotFormatter;
+
+impl DotFormatter {
+	/// Escape a string for use inside a quoted DOT identifier, with line breaks as DOT newlines
+	fn escape(value: &str) -> String {
+		value
+			.replace('\\', "\\\\")
+			.replace('"', "\\\"")
+			.replace("\r\n", "\\n")
+			.replace(['\n', '\r'], "\\n")
+	}
+
+	/// Quote a string as a DOT identifier
+	fn quote(value: &str) -> String {
+		format!("\"{}\"", Self::escape(value))
+	}
+
+	/// Build the node identifier for a service on a host
+	fn service_node(result: &ScanResult) -> String {
+		match result.port {
+			Some(port) => format!("{}:{}", result.target, port),
+			None => format!("{}:{}", result.target, result.scan_type),
+		}
+	}
+
+	/// Build the label for a service node
+	fn service_label(result: &ScanResult) -> String {
+		let mut label = match result.port {
+			Some(port) => format!("{}/{}", port, result.scan_type),
+			None => result.scan_type.clone(),
+		};
+		if let Some(service) = &result.service {
+			label.push_str(&format!("\n{}", service));
+			if let Some(version) = &result.version {
+				label.push_str(&format!(" {}", version));
+			}
+		}
+		label
+	}
+
+	/// Render the graph
+	fn graph(results: &[ScanResult]) -> Result<String, std::fmt::Error> {
+		let mut out = String::new();
+		let mut hosts: Vec<&str> = Vec::new();
+		let mut edges: Vec<(String, String)> = Vec::new();
+
+		writeln!(out, "digraph huginn {{")?;
+		writeln!(out, "\trankdir=LR;")?;
+		writeln!(out, "\t{} [shape=box, style=bold, label=\"huginn\"];", Self::quote(SCANNER))?;
+
+		for result in results {
+			if !hosts.contains(&result.target.as_str()) {
+				hosts.push(&result.target);
+				writeln!(out, "\t{} [shape=box];", Self::quote(&result.target))?;
+			}
+
+			if result.status == "open" {
+				let node = Self::service_node(result);
+				writeln!(
+					out,
+					"\t{} [shape=ellipse, label={}];",
+					Self::quote(&node),
+					Self::quote(&Self::service_label(result))
+				)?;
+				edges.push((result.target.clone(), node));
+			}
+		}
+
+		for host in &hosts {
+			edges.push((SCANNER.to_string(), host.to_string()));
+		}
+
+		// Ports seen by several scan types give duplicate edges
+		edges.sort_unstable();
+		edges.dedup();
+		for (from, to) in &edges {
+			writeln!(out, "\t{} -> {};", Self::quote(from), Self::quote(to))?;
+		}
+
+		let errors = formatters::error_summary(results);
+		if !errors.is_empty() {
+			let lines: Vec<String> = errors.iter().map(|error| Self::escape(error)).collect();
+			let label =
+				format!("\"Errors ({} failed scans)\\l{}\\l\"", errors.len(), lines.join("\\l"));
+			writeln!(out, "\t{} [shape=note, color=red, label={}];", Self::quote(ERRORS), label)?;
+		}
+		writeln!(out, "}}")?;
+		Ok(out)
+	}
+}
+
+impl Formatter for DotFormatter {
+	fn name(&self) -> String {
+		"dot".to_string()
+	}
+
+	fn format(&self, results: &[ScanResult]) -> Result<String, HuginnError> {
+		Self::graph(results).map_err(|e| HuginnError::format(self.name(), e))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_graph_renders_services() {
+		let results = vec![
+			ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22).with_service("ssh"),
+			ScanResult::new("10.0.0.1", "tcp_connect", "closed").with_port(23),
+		];
+		let graph = DotFormatter::graph(&results).unwrap();
+
+		assert!(graph.starts_with("digraph huginn {"));
+		assert!(graph.contains("\"@scanner\" -> \"10.0.0.1\";"));
+		assert!(graph.contains("\"10.0.0.1\" -> \"10.0.0.1:22\";"));
+		assert!(graph.contains("label=\"22/tcp_connect\\nssh\""));
+		assert!(!graph.contains("10.0.0.1:23"));
+	}
+
+	#[test]
+	fn test_graph_edges_are_unique() {
+		let results = vec![
+			ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22),
+			ScanResult::new("huginn", "tcp_connect", "open").with_port(22),
+			ScanResult::new("10.0.0.1", "tcp_syn", "open").with_port(22),
+		];
+		let graph = DotFormatter::graph(&results).unwrap();
+
+		assert_eq!(graph.matches("\"10.0.0.1\" -> \"10.0.0.1:22\";").count(), 1);
+		// A host named like the scanner is a node of its own
+		assert!(graph.contains("\"@scanner\" -> \"huginn\";"));
+		assert!(graph.contains("\"huginn\" -> \"huginn:22\";"));
+	}
+
+	#[test]
+	fn test_graph_lists_errors() {
+		let up = ScanResult::new("10.0.0.1", "ping", "up");
+		assert!(!DotFormatter::graph(&[up]).unwrap().contains("shape=note"));
+
+		let failed =
+			ScanResult::new("10.0.0.1", "ping", "error").with_details("config: bad \"source\"");
+		let graph = DotFormatter::graph(&[failed]).unwrap();
+		assert!(graph.contains(
+			"\t\"@errors\" [shape=note, color=red, label=\"Errors (1 failed scans)\\l10.0.0.1 \
+			ping: config: bad \\\"source\\\"\\l\"];"
+		));
+	}
+
+	#[test]
+	fn test_quote_escapes() {
+		assert_eq!(DotFormatter::quote("a\"b"), "\"a\\\"b\"");
+		assert_eq!(DotFormatter::quote("a\nb\r\nc\rd"), "\"a\\nb\\nc\\nd\"");
+	}
+}
@@ -0,0 +1,210 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Host-grouped output formatter
+//!
+//! Groups the scan results by host, for asset inventories that want one record per host rather
+//! than a flat list of results. Each host lists its ports, the services identified on them, its
+//! findings (results with a severity), and any failed scans. The document is written as JSON
+//! (`hosts`) or YAML (`hosts-yaml`), with the run's statistics alongside the hosts.
+
+use crate::error::HuginnError;
+use crate::formatters::Formatter;
+use crate::plugins::{ScanResult, Severity};
+use crate::statistics::Statistics;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Encoding of the host-grouped document
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+	/// JSON
+	Json,
+	/// YAML
+	Yaml,
+}
+
+/// Host-grouped output formatter
+pub struct HostsFormatter {
+	/// Encoding of the document
+	pub encoding: Encoding,
+}
+
+/// A port probed on a host
+#[derive(Serialize)]
+struct Port<'a> {
+	port: u16,
+	scan_type: &'a str,
+	status: &'a str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	service: Option<&'a str>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	version: Option<&'a str>,
+	/// ATT&CK techniques the open port is relevant to
+	#[serde(skip_serializing_if = "<[_]>::is_empty")]
+	techniques: &'a [String],
+}
+
+/// A service identified on a host
+#[derive(Serialize)]
+struct Service<'a> {
+	name: &'a str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	version: Option<&'a str>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	port: Option<u16>,
+}
+
+/// A result with a severity
+#[derive(Serialize)]
+struct Finding<'a> {
+	severity: Severity,
+	scan_type: &'a str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	port: Option<u16>,
+	status: &'a str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	details: Option<&'a str>,
+}
+
+/// Everything found on one host
+#[derive(Serialize)]
+struct Host<'a> {
+	host: &'a str,
+	/// Host status reported by a host-level scan such as ping
+	#[serde(skip_serializing_if = "Option::is_none")]
+	status: Option<&'a str>,
+	ports: Vec<Port<'a>>,
+	services: Vec<Service<'a>>,
+	findings: Vec<Finding<'a>>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	errors: Vec<&'a str>,
+}
+
+/// Document written by the formatter
+#[derive(Serialize)]
+struct Document<'a> {
+	hosts: Vec<Host<'a>>,
+	statistics: &'a Statistics,
+}
+
+impl<'a> Host<'a> {
+	/// A host with nothing found yet
+	fn new(host: &'a str) -> Self {
+		Host {
+			host,
+			status: None,
+			ports: Vec::new(),
+			services: Vec::new(),
+			findings: Vec::new(),
+			errors: Vec::new(),
+		}
+	}
+
+	/// Add one of the host's results
+	fn add(&mut self, result: &'a ScanResult) {
+		if result.is_error() {
+			self.errors.push(result.details.as_deref().unwrap_or("failed"));
+			return;
+		}
+		match result.port {
+			Some(port) => self.ports.push(Port {
+				port,
+				scan_type: &result.scan_type,
+				status: &result.status,
+				service: result.service.as_deref(),
+				version: result.version.as_deref(),
+				techniques: &result.techniques,
+			}),
+			// Any scan that found the host up outweighs one that did not
+			None if self.status.is_none() || result.status == "up" => self.status = Some(&result.status),
+			None => {},
+		}
+		if let Some(name) = result.service.as_deref() {
+			self.services.push(Service {
+				name,
+				version: result.version.as_deref(),
+				port: result.port,
+			});
+		}
+		if let Some(severity) = result.severity {
+			self.findings.push(Finding {
+				severity,
+				scan_type: &result.scan_type,
+				port: result.port,
+				status: &result.status,
+				details: result.details.as_deref(),
+			});
+		}
+	}
+}
+
+impl HostsFormatter {
+	/// Group the results by host, in scan order
+	fn document<'a>(results: &'a [ScanResult], statistics: &'a Statistics) -> Document<'a> {
+		let mut hosts: Vec<Host<'a>> = Vec::new();
+		let mut index: HashMap<&str, usize> = HashMap::new();
+		for result in results {
+			let i = *index.entry(&result.target).or_insert_with(|| {
+				hosts.push(Host::new(&result.target));
+				hosts.len() - 1
+			});
+			hosts[i].add(result);
+		}
+		Document { hosts, statistics }
+	}
+}
+
+impl Formatter for HostsFormatter {
+	fn name(&self) -> String {
+		match self.encoding {
+			Encoding::Json => "hosts".to_string(),
+			Encoding::Yaml => "hosts-yaml".to_string(),
+		}
+	}
+
+	fn format(&self, results: &[ScanResult]) -> Result<String, HuginnError> {
+		self.format_with_statistics(results, &Statistics::from_results(results))
+	}
+
+	fn format_with_statistics(&self, results: &[ScanResult], statistics: &Statistics) -> Result<String, HuginnError> {
+		let document = Self::document(results, statistics);
+		match self.encoding {
+			Encoding::Json => serde_json::to_string_pretty(&document).map_err(|e| HuginnError::format(self.name(), e)),
+			Encoding::Yaml => serde_norway::to_string(&document).map_err(|e| HuginnError::format(self.name(), e)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::Value;
+
+	#[test]
+	fn test_group_by_host() {
+		let mut ssh = ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22);
+		ssh.service = Some("ssh".to_string());
+		ssh.version = Some("OpenSSH 7.2".to_string());
+		ssh.severity = Some(Severity::Medium);
+		let mut failed = ScanResult::new("10.0.0.2", "ping", "error");
+		failed.details = Some("unresolved: 10.0.0.2 did not resolve to an address".to_string());
+		let closed = ScanResult::new("10.0.0.1", "tcp_connect", "closed").with_port(80);
+		let results = [ScanResult::new("10.0.0.1", "ping", "up"), ssh, closed, failed];
+
+		let formatter = HostsFormatter { encoding: Encoding::Json };
+		let document: Value = serde_json::from_str(&formatter.format(&results).unwrap()).unwrap();
+		let hosts = document["hosts"].as_array().unwrap();
+		assert_eq!(hosts.len(), 2);
+		assert_eq!(hosts[0]["status"], "up");
+		assert_eq!(hosts[0]["ports"].as_array().unwrap().len(), 2);
+		assert_eq!(hosts[0]["services"][0]["version"], "OpenSSH 7.2");
+		assert_eq!(hosts[0]["findings"][0]["severity"], "medium");
+		assert!(hosts[0].get("errors").is_none());
+		assert_eq!(hosts[1]["errors"][0], "unresolved: 10.0.0.2 did not resolve to an address");
+		assert_eq!(document["statistics"]["ports_open"], 1);
+
+		let yaml = HostsFormatter { encoding: Encoding::Yaml }.format(&results).unwrap();
+		assert!(yaml.starts_with("hosts:\n- host: 10.0.0.1\n  status: up\n"));
+	}
+}
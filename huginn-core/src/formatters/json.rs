@@ -0,0 +1,132 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! JSON output formatter
+//!
+//! Serializes the scan results as a flat JSON array (`json`), or as a document with the array
+//! under `results` and the run's statistics under `statistics` (`json-statistics`). Failed scans
+//! appear in the list as results with the `error` status.
+
+use crate::error::HuginnError;
+use crate::formatters::Formatter;
+use crate::plugins::ScanResult;
+use crate::statistics::Statistics;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+/// JSON output formatter
+pub struct JsonFormatter {
+	/// Whether the results are wrapped in a document with the run's statistics
+	pub statistics: bool,
+}
+
+/// Document written by the JSON formatter
+#[derive(Serialize, Deserialize)]
+struct Document<R> {
+	results: R,
+	#[serde(default)]
+	statistics: Statistics,
+}
+
+/// Read results written by the JSON formatter, or saved as a bare JSON array of results
+pub fn parse(text: &str) -> Result<Vec<ScanResult>, serde_json::Error> {
+	if text.trim_start().starts_with('[') {
+		return serde_json::from_str(text);
+	}
+	serde_json::from_str::<Document<Vec<ScanResult>>>(text).map(|document| document.results)
+}
+
+/// A value pretty-printed on its own, with each line after the first indented to its depth in
+/// the document
+fn indented<T: Serialize>(value: &T, depth: &str) -> Result<String, serde_json::Error> {
+	Ok(serde_json::to_string_pretty(value)?.replace('\n', &format!("\n{}", depth)))
+}
+
+impl Formatter for JsonFormatter {
+	fn name(&self) -> String {
+		if self.statistics { "json-statistics" } else { "json" }.to_string()
+	}
+
+	fn format(&self, results: &[ScanResult]) -> Result<String, HuginnError> {
+		self.format_with_statistics(results, &Statistics::from_results(results))
+	}
+
+	fn format_with_statistics(&self, results: &[ScanResult], statistics: &Statistics) -> Result<String, HuginnError> {
+		let text = if self.statistics {
+			let document = Document {
+				results,
+				statistics: statistics.clone(),
+			};
+			serde_json::to_string_pretty(&document)
+		} else {
+			serde_json::to_string_pretty(results)
+		};
+		text.map_err(|e| HuginnError::format(self.name(), e))
+	}
+
+	/// Writes the same document as [`Formatter::format_with_statistics`], a result at a time
+	fn write_stream(
+		&self,
+		results: &mut dyn Iterator<Item = Result<ScanResult, HuginnError>>,
+		statistics: &Statistics,
+		out: &mut dyn Write,
+	) -> Result<(), HuginnError> {
+		// The array is a level deeper inside the document
+		let depth = if self.statistics { "  " } else { "" };
+		if self.statistics {
+			out.write_all(b"{\n  \"results\": ")?;
+		}
+		out.write_all(b"[")?;
+		let mut first = true;
+		for result in results {
+			let result = result?;
+			out.write_all(format!("{}\n{}  ", if first { "" } else { "," }, depth).as_bytes())?;
+			let text = indented(&result, &format!("{}  ", depth)).map_err(|e| HuginnError::format(self.name(), e))?;
+			out.write_all(text.as_bytes())?;
+			first = false;
+		}
+		out.write_all(if first { "]".to_string() } else { format!("\n{}]", depth) }.as_bytes())?;
+		if self.statistics {
+			out.write_all(b",\n  \"statistics\": ")?;
+			out.write_all(indented(statistics, "  ").map_err(|e| HuginnError::format(self.name(), e))?.as_bytes())?;
+			out.write_all(b"\n}")?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_round_trip() {
+		let result = ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22);
+		let text = JsonFormatter { statistics: false }.format(std::slice::from_ref(&result)).unwrap();
+		let array: serde_json::Value = serde_json::from_str(&text).unwrap();
+		assert_eq!(array[0]["port"], 22);
+		assert_eq!(parse(&text).unwrap()[0].port, Some(22));
+
+		let text = JsonFormatter { statistics: true }.format(&[result]).unwrap();
+		let document: serde_json::Value = serde_json::from_str(&text).unwrap();
+		assert_eq!(document["statistics"]["ports_open"], 1);
+		assert_eq!(parse(&text).unwrap()[0].port, Some(22));
+		assert!(parse("{}").is_err());
+	}
+
+	#[test]
+	fn test_write_stream() {
+		let result = ScanResult::new("10.0.0.1", "tcp_connect", "open")
+			.with_port(22)
+			.with_service("ssh")
+			.with_techniques(["T1046"]);
+		for formatter in [JsonFormatter { statistics: false }, JsonFormatter { statistics: true }] {
+			for results in [Vec::new(), vec![result.clone(), result.clone()]] {
+				let statistics = Statistics::from_results(&results);
+				let mut out = Vec::new();
+				formatter.write_stream(&mut results.iter().cloned().map(Ok), &statistics, &mut out).unwrap();
+				assert_eq!(String::from_utf8(out).unwrap(), formatter.format(&results).unwrap());
+			}
+		}
+	}
+}
@@ -0,0 +1,212 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Template-driven report formatter
+//!
+//! Renders scan results through a user-supplied Tera template so teams can define their own
+//! report layouts without code changes. The template context exposes:
+//!
+//! - `results`: the flat list of scan results
+//! - `targets`: the distinct scanned targets, in scan order
+//! - `errors`: one line per failed scan, such as `10.0.0.1 ping: permission_denied: ...`
+//! - `statistics`: the run's statistics, with the fields of [`Statistics`]
+//! - `attack`: ATT&CK coverage, one entry per technique the results are tagged with, with the
+//!   fields of [`Coverage`](crate::attack::Coverage)
+//! - `screenshots`: the web page screenshots the results point to that are still on disk, each
+//!   with its `target`, `port`, `url`, file `path`, and the PNG as a `data` URI to embed in HTML,
+//!   as in `<img src="{{ s.data }}" width="320">`. Only PNG files inside the directory given to
+//!   [`TemplateFormatter::screenshots`] are embedded, since results files may come from anywhere.
+//! - `generated_at`: RFC 3339 timestamp of the report
+//! - `version`: the Huginn version that produced the report
+
+use crate::attack;
+use crate::error::HuginnError;
+use crate::formatters::{self, Formatter};
+use crate::plugins::ScanResult;
+use crate::statistics::Statistics;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use log::warn;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tera::{Context, Tera};
+
+/// Name the user template is registered under
+const TEMPLATE_NAME: &str = "report";
+
+/// Signature every PNG file starts with
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// A screenshot of a web page, as saved by the `web_screenshot` scan type
+#[derive(Debug, Serialize)]
+struct Screenshot<'a> {
+	target: &'a str,
+	port: Option<u16>,
+	url: &'a str,
+	path: String,
+	/// The PNG file as a `data:` URI
+	data: String,
+}
+
+/// The PNG file at a path, if it is inside the screenshot directory
+fn read_png(directory: &Path, path: &Path) -> Option<(PathBuf, Vec<u8>)> {
+	let path = path.canonicalize().ok()?;
+	if !path.starts_with(directory) {
+		warn!("Not embedding {}, which is outside {}", path.display(), directory.display());
+		return None;
+	}
+	let png = std::fs::read(&path).ok()?;
+	png.starts_with(PNG_SIGNATURE).then_some((path, png))
+}
+
+/// The screenshots the results point to, skipping files that no longer exist or are not PNG files
+/// inside the screenshot directory
+///
+/// A screenshot result's details start with the page's URL.
+fn screenshots<'a>(results: &'a [ScanResult], directory: Option<&Path>) -> Vec<Screenshot<'a>> {
+	let Some(directory) = directory.and_then(|directory| directory.canonicalize().ok()) else {
+		return Vec::new();
+	};
+	results
+		.iter()
+		.filter(|result| result.scan_type == "web_screenshot")
+		.filter_map(|result| {
+			let url = result.details.as_deref()?.split("; ").next()?;
+			let (path, png) = read_png(&directory, result.screenshot.as_deref()?)?;
+			Some(Screenshot {
+				target: &result.target,
+				port: result.port,
+				url,
+				path: path.display().to_string(),
+				data: format!("data:image/png;base64,{}", STANDARD.encode(png)),
+			})
+		})
+		.collect()
+}
+
+/// Template-driven report formatter
+pub struct TemplateFormatter {
+	tera: Tera,
+	/// Directory screenshots are embedded from
+	screenshots: Option<PathBuf>,
+}
+
+impl TemplateFormatter {
+	/// Create a formatter from template source text
+	pub fn new(source: &str) -> Result<Self, HuginnError> {
+		let mut tera = Tera::default();
+		tera.add_raw_template(TEMPLATE_NAME, source).map_err(|e| HuginnError::format("template", e))?;
+		Ok(Self { tera, screenshots: None })
+	}
+
+	/// Embed the screenshots saved in a directory, as by the `web_screenshot` scan type
+	pub fn screenshots(mut self, directory: &Path) -> Self {
+		self.screenshots = Some(directory.to_path_buf());
+		self
+	}
+
+	/// Create a formatter from a template file
+	pub fn from_file(path: &Path) -> Result<Self, HuginnError> {
+		let source = std::fs::read_to_string(path)
+			.map_err(|e| HuginnError::Config(format!("Failed to read template {}: {}", path.display(), e)))?;
+		Self::new(&source)
+	}
+}
+
+impl Formatter for TemplateFormatter {
+	fn name(&self) -> String {
+		"template".to_string()
+	}
+
+	fn format(&self, results: &[ScanResult]) -> Result<String, HuginnError> {
+		self.format_with_statistics(results, &Statistics::from_results(results))
+	}
+
+	fn format_with_statistics(&self, results: &[ScanResult], statistics: &Statistics) -> Result<String, HuginnError> {
+		let mut targets: Vec<&str> = Vec::new();
+		for result in results {
+			if !targets.contains(&result.target.as_str()) {
+				targets.push(&result.target);
+			}
+		}
+
+		let mut context = Context::new();
+		context.insert("results", results);
+		context.insert("targets", &targets);
+		context.insert("errors", &formatters::error_summary(results));
+		context.insert("statistics", statistics);
+		context.insert("attack", &attack::coverage(results));
+		context.insert("screenshots", &screenshots(results, self.screenshots.as_deref()));
+		context.insert("generated_at", &chrono::Utc::now().to_rfc3339());
+		context.insert("version", env!("CARGO_PKG_VERSION"));
+		self.tera.render(TEMPLATE_NAME, &context).map_err(|e| HuginnError::format(self.name(), e))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_render_results() {
+		let formatter = TemplateFormatter::new(
+			"{% for t in targets %}{{ t }}:{% for r in results %}{% if r.target == t %} {{ r.port }}/{{ r.status }}{% endif %}{% endfor %}\n{% endfor %}",
+		)
+		.unwrap();
+		let result = |port| {
+			ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(port).with_techniques(attack::for_port(port))
+		};
+
+		let report = formatter.format(&[result(22), result(80)]).unwrap();
+		assert_eq!(report, "10.0.0.1: 22/open 80/open\n");
+
+		let summary = TemplateFormatter::new("{{ statistics.ports_open }} open").unwrap();
+		assert_eq!(summary.format(&[result(22), result(80)]).unwrap(), "2 open");
+
+		let coverage =
+			TemplateFormatter::new("{% for t in attack %}| {{ t.technique }} | {{ t.name }} | {{ t.hosts | length }} |\n{% endfor %}")
+				.unwrap();
+		assert_eq!(
+			coverage.format(&[result(22), result(80)]).unwrap(),
+			"| T1021.004 | Remote Services: SSH | 1 |\n| T1133 | External Remote Services | 1 |\n"
+		);
+	}
+
+	#[test]
+	fn test_embeds_screenshots() {
+		let directory = std::env::temp_dir().join(format!("huginn-template-{}", std::process::id()));
+		std::fs::create_dir_all(&directory).unwrap();
+		let png = directory.join("10.0.0.1_80.png");
+		std::fs::write(&png, b"\x89PNG\r\n\x1a\n").unwrap();
+		let text = directory.join("10.0.0.1_81.png");
+		std::fs::write(&text, b"not a PNG").unwrap();
+		let outside = std::env::temp_dir().join(format!("huginn-template-{}.png", std::process::id()));
+		std::fs::write(&outside, b"\x89PNG\r\n\x1a\n").unwrap();
+		let result = |details: &str, screenshot: Option<&Path>| ScanResult {
+			screenshot: screenshot.map(Path::to_path_buf),
+			..ScanResult::new("10.0.0.1", "web_screenshot", "open").with_port(80).with_service("http").with_details(details)
+		};
+		let results = [
+			result("http://10.0.0.1/; title \"Admin\"", Some(&png)),
+			result("http://10.0.0.1:8080/; no screenshot: chromium failed", None),
+			result("http://10.0.0.1:81/", Some(&text)),
+			result("http://10.0.0.1:82/", Some(&outside)),
+			result("http://10.0.0.1:83/", Some(&directory.join("../").join(outside.file_name().unwrap()))),
+		];
+
+		let source = "{% for s in screenshots %}<a href=\"{{ s.url }}\"><img src=\"{{ s.data }}\"></a>{% endfor %}";
+		assert_eq!(TemplateFormatter::new(source).unwrap().format(&results).unwrap(), "");
+		let formatter = TemplateFormatter::new(source).unwrap().screenshots(&directory);
+		assert_eq!(
+			formatter.format(&results).unwrap(),
+			"<a href=\"http://10.0.0.1/\"><img src=\"data:image/png;base64,iVBORw0KGgo=\"></a>"
+		);
+		std::fs::remove_dir_all(&directory).unwrap();
+		std::fs::remove_file(&outside).unwrap();
+	}
+
+	#[test]
+	fn test_invalid_template() {
+		assert!(TemplateFormatter::new("{% for %}").is_err());
+	}
+}
@@ -0,0 +1,142 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Plain text output formatter
+//!
+//! Renders one human-readable line per scan result, grouped by target, followed by a summary of
+//! any scans that failed and the run's statistics.
+
+use crate::error::HuginnError;
+use crate::formatters::{self, Formatter};
+use crate::plugins::ScanResult;
+use crate::statistics::Statistics;
+use std::fmt::{self, Write};
+use std::io;
+
+/// Plain text output formatter
+pub struct TextFormatter;
+
+impl TextFormatter {
+	/// Render the results as text
+	fn render(results: &[ScanResult], statistics: &Statistics) -> Result<String, fmt::Error> {
+		let mut out = String::new();
+		let mut targets: Vec<&str> = Vec::new();
+		for result in results {
+			if !targets.contains(&result.target.as_str()) {
+				targets.push(&result.target);
+			}
+		}
+
+		for target in targets {
+			writeln!(out, "{}", target)?;
+			for result in results.iter().filter(|r| r.target == target) {
+				Self::line(&mut out, result)?;
+			}
+		}
+
+		Self::summary(&mut out, &formatters::error_summary(results), statistics)?;
+		Ok(out)
+	}
+
+	/// Render one result's line
+	fn line(out: &mut String, result: &ScanResult) -> fmt::Result {
+		let probe = match result.port {
+			Some(port) => format!("{}/{}", port, result.scan_type),
+			None => result.scan_type.clone(),
+		};
+		write!(out, "  {:<20} {:<16}", probe, result.status)?;
+		if let Some(service) = &result.service {
+			write!(out, " {}", service)?;
+			if let Some(version) = &result.version {
+				write!(out, " {}", version)?;
+			}
+		}
+		if let Some(details) = &result.details {
+			write!(out, " ({})", details)?;
+		}
+		writeln!(out)
+	}
+
+	/// Render the failed scans and the statistics that follow the results
+	fn summary(out: &mut String, errors: &[String], statistics: &Statistics) -> fmt::Result {
+		if !errors.is_empty() {
+			writeln!(out, "\nErrors ({} failed scans)", errors.len())?;
+			for error in errors {
+				writeln!(out, "  {}", error)?;
+			}
+		}
+		write!(out, "\n{}", statistics)
+	}
+}
+
+impl Formatter for TextFormatter {
+	fn name(&self) -> String {
+		"text".to_string()
+	}
+
+	fn format(&self, results: &[ScanResult]) -> Result<String, HuginnError> {
+		self.format_with_statistics(results, &Statistics::from_results(results))
+	}
+
+	fn format_with_statistics(&self, results: &[ScanResult], statistics: &Statistics) -> Result<String, HuginnError> {
+		Self::render(results, statistics).map_err(|e| HuginnError::format(self.name(), e))
+	}
+
+	/// Results are listed under their target as they are read, so a target whose results are
+	/// not consecutive is listed again
+	fn write_stream(
+		&self,
+		results: &mut dyn Iterator<Item = Result<ScanResult, HuginnError>>,
+		statistics: &Statistics,
+		out: &mut dyn io::Write,
+	) -> Result<(), HuginnError> {
+		let mut target: Option<String> = None;
+		let mut errors = Vec::new();
+		let mut text = String::new();
+		for result in results {
+			let result = result?;
+			text.clear();
+			if target.as_ref() != Some(&result.target) {
+				writeln!(text, "{}", result.target).map_err(|e| HuginnError::format(self.name(), e))?;
+				target = Some(result.target.clone());
+			}
+			Self::line(&mut text, &result).map_err(|e| HuginnError::format(self.name(), e))?;
+			out.write_all(text.as_bytes())?;
+			errors.extend(formatters::error_summary(std::slice::from_ref(&result)));
+		}
+		text.clear();
+		Self::summary(&mut text, &errors, statistics).map_err(|e| HuginnError::format(self.name(), e))?;
+		out.write_all(text.as_bytes())?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_error_summary() {
+		let up = ScanResult::new("10.0.0.1", "ping", "up");
+		assert!(!TextFormatter.format(std::slice::from_ref(&up)).unwrap().contains("failed scans"));
+
+		let failed = ScanResult::new("10.0.0.1", "tcp_syn", "error").with_details("permission_denied: raw sockets need root");
+		let text = TextFormatter.format(&[up, failed]).unwrap();
+		assert!(text.contains(
+			"\nErrors (1 failed scans)\n  10.0.0.1 tcp_syn: permission_denied: raw sockets need root\n\nSummary\n"
+		));
+		assert!(text.contains("  Errors       1\n"));
+	}
+
+	#[test]
+	fn test_write_stream() {
+		let results = [
+			ScanResult::new("10.0.0.1", "ping", "up"),
+			ScanResult::new("10.0.0.1", "tcp_syn", "error").with_details("permission_denied: raw sockets need root"),
+		];
+		let statistics = Statistics::from_results(&results);
+		let mut out = Vec::new();
+		TextFormatter.write_stream(&mut results.iter().cloned().map(Ok), &statistics, &mut out).unwrap();
+		assert_eq!(String::from_utf8(out).unwrap(), TextFormatter.format(&results).unwrap());
+	}
+}
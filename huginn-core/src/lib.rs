@@ -0,0 +1,65 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Huginn scanning engine
+//!
+//! The scanner, plugins, target parsing, and result formatters behind the `huginn` command line,
+//! for Rust projects that embed Huginn scanning instead of shelling out. Build a [`Scanner`]
+//! with [`ScannerBuilder`], run it, and render the [`ScanResult`]s with a [`formatters`] format:
+//!
+//! ```no_run
+//! use huginn_core::ports::PortSpec;
+//! use huginn_core::{ScanType, Scanner, formatters, plugins, targets};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let scanner = Scanner::builder()
+//!     .targets(targets::expand(&["192.0.2.0/30".to_string()])?)
+//!     .scan_types([ScanType::Ping, ScanType::TcpConnect])
+//!     .ports("22,80,443".parse::<PortSpec>()?)
+//!     .plugins(plugins::builtin())
+//!     .build();
+//! let results = scanner.run().await?;
+//! print!("{}", formatters::get("text").unwrap().format(&results)?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! To react while a scan runs, for example to update a display, register an [`Observer`] with
+//! [`ScannerBuilder::observer`]; it receives a [`ScanEvent`] as each host starts, each open port
+//! is found, each plugin finishes or fails, and progress advances. The sending half of a Tokio
+//! channel is an observer, so events can be handled on another task.
+//!
+//! Fallible operations return a [`HuginnError`], so callers can tell, for example, an invalid
+//! target from a plugin that lacks the privileges it needs without matching on message text.
+//!
+//! Only the engine lives here; the configuration file, outputs, history, and the rest of the
+//! command line stay in the `huginn` package.
+
+pub mod attack;
+pub mod credentials;
+#[cfg(feature = "core-scan")]
+pub mod discovery;
+pub mod error;
+#[cfg(feature = "farm")]
+pub mod farm;
+pub mod formatters;
+pub mod limits;
+pub mod normalize;
+pub mod plugins;
+pub mod ports;
+pub mod progress;
+pub mod proxy;
+pub mod scanner;
+pub mod sinks;
+pub mod spool;
+pub mod statistics;
+pub mod targets;
+#[cfg(feature = "testbed")]
+pub mod testbed;
+pub mod transport;
+
+pub use error::HuginnError;
+pub use plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+pub use scanner::{Observer, ScanEvent, Scanner, ScannerBuilder};
+pub use sinks::ResultSink;
+pub use statistics::Statistics;
@@ -0,0 +1,88 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Process resource limits
+//!
+//! Every TCP connection a scan holds open uses a file descriptor, and a process that runs out
+//! fails each further `connect` with EMFILE ("too many open files"). The scanner sizes its
+//! connection pool from the descriptor limit instead, leaving room for the descriptors the rest
+//! of the process needs, and [`raise_descriptor_limit`] lifts the soft limit to the hard limit
+//! for scans that want a larger pool.
+
+use crate::error::HuginnError;
+use std::io;
+
+/// Descriptors kept free for logs, outputs, name resolution, and raw sockets
+const RESERVED: u64 = 64;
+
+/// Most connections held open at once, however high the descriptor limit
+pub const MAX_CONNECTIONS: usize = 4096;
+
+/// The soft limit on open file descriptors, or `None` when it is unlimited or unknown
+#[cfg(unix)]
+pub fn descriptor_limit() -> Option<u64> {
+	use nix::sys::resource::{RLIM_INFINITY, Resource, getrlimit};
+
+	getrlimit(Resource::RLIMIT_NOFILE).ok().map(|(soft, _)| soft).filter(|&soft| soft != RLIM_INFINITY)
+}
+
+/// The soft limit on open file descriptors, or `None` when it is unlimited or unknown
+#[cfg(not(unix))]
+pub fn descriptor_limit() -> Option<u64> {
+	None
+}
+
+/// Raise the soft limit on open file descriptors to the hard limit, returning the new limit
+#[cfg(unix)]
+pub fn raise_descriptor_limit() -> Result<u64, HuginnError> {
+	use nix::sys::resource::{RLIM_INFINITY, Resource, getrlimit, setrlimit};
+
+	let (soft, hard) = getrlimit(Resource::RLIMIT_NOFILE).map_err(io::Error::from)?;
+	// macOS refuses an unlimited soft limit, so stop at a limit the pool can never reach
+	let target = if hard == RLIM_INFINITY { (MAX_CONNECTIONS as u64 + RESERVED).max(soft) } else { hard };
+	if target > soft {
+		setrlimit(Resource::RLIMIT_NOFILE, target, hard).map_err(io::Error::from)?;
+	}
+	Ok(target.max(soft))
+}
+
+/// Raise the soft limit on open file descriptors to the hard limit, returning the new limit
+#[cfg(not(unix))]
+pub fn raise_descriptor_limit() -> Result<u64, HuginnError> {
+	Err(HuginnError::Config("file descriptor limits can only be raised on Unix".to_string()))
+}
+
+/// Whether an error means the process or system ran out of file descriptors
+#[cfg(unix)]
+pub fn is_exhausted(error: &io::Error) -> bool {
+	use nix::errno::Errno;
+
+	matches!(error.raw_os_error().map(Errno::from_raw), Some(Errno::EMFILE | Errno::ENFILE))
+}
+
+/// Whether an error means the process or system ran out of file descriptors
+#[cfg(not(unix))]
+pub fn is_exhausted(error: &io::Error) -> bool {
+	// WSAEMFILE
+	error.raw_os_error() == Some(10024)
+}
+
+/// Most connections that can be held open at once under a descriptor limit
+pub fn connection_budget(limit: Option<u64>) -> usize {
+	limit.map_or(MAX_CONNECTIONS, |limit| {
+		usize::try_from(limit.saturating_sub(RESERVED)).unwrap_or(usize::MAX).clamp(1, MAX_CONNECTIONS)
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_connection_budget() {
+		assert_eq!(connection_budget(Some(1024)), 960);
+		assert_eq!(connection_budget(Some(16)), 1);
+		assert_eq!(connection_budget(Some(1 << 20)), MAX_CONNECTIONS);
+		assert_eq!(connection_budget(None), MAX_CONNECTIONS);
+	}
+}
@@ -0,0 +1,126 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Result normalization for Huginn
+//!
+//! Results are cleaned up after a scan, before they are formatted or stored. Results for a host
+//! name that resolves to an address scanned in the same run are reported under the address, so
+//! one asset is not listed twice. Identical findings, with the same target, scan type, port, and
+//! status, are then merged into one that keeps every detail either of them had.
+
+use crate::plugins::ScanResult;
+use log::debug;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use tokio::net::lookup_host;
+
+/// Map each host name among the targets to an address that is also a target, if it resolves to one
+pub async fn aliases(targets: &[String]) -> HashMap<String, String> {
+	let addresses: HashSet<IpAddr> = targets.iter().filter_map(|t| t.parse().ok()).collect();
+	let mut aliases = HashMap::new();
+	if addresses.is_empty() {
+		return aliases;
+	}
+	for name in targets.iter().filter(|t| t.parse::<IpAddr>().is_err()) {
+		let Ok(resolved) = lookup_host((name.as_str(), 0)).await else {
+			continue;
+		};
+		if let Some(address) = resolved.map(|a| a.ip()).find(|a| addresses.contains(a)) {
+			debug!("Reporting results for {} under {}", name, address);
+			aliases.insert(name.clone(), address.to_string());
+		}
+	}
+	aliases
+}
+
+/// Add details to a result's details, skipping any `; `-separated part it already has
+pub fn add_details(result: &mut ScanResult, details: String) {
+	let Some(existing) = &mut result.details else {
+		result.details = Some(details);
+		return;
+	};
+	for part in details.split("; ") {
+		if !existing.split("; ").any(|known| known == part) {
+			existing.push_str("; ");
+			existing.push_str(part);
+		}
+	}
+}
+
+/// Merge a duplicate of a finding into the kept result, filling in what it lacks and combining
+/// their details
+pub fn merge_into(kept: &mut ScanResult, duplicate: ScanResult) {
+	kept.severity = kept.severity.max(duplicate.severity);
+	kept.service = kept.service.take().or(duplicate.service);
+	kept.version = kept.version.take().or(duplicate.version);
+	for technique in duplicate.techniques {
+		if !kept.techniques.contains(&technique) {
+			kept.techniques.push(technique);
+		}
+	}
+	if let Some(details) = duplicate.details {
+		add_details(kept, details);
+	}
+}
+
+/// Report host names under their aliased addresses and merge identical findings, keeping the
+/// order in which findings first appeared
+pub fn normalize(results: Vec<ScanResult>, aliases: &HashMap<String, String>) -> Vec<ScanResult> {
+	let mut normalized: Vec<ScanResult> = Vec::with_capacity(results.len());
+	let mut seen: HashMap<(String, String, Option<u16>, String), usize> = HashMap::new();
+
+	for mut result in results {
+		if let Some(address) = aliases.get(&result.target) {
+			let name = std::mem::replace(&mut result.target, address.clone());
+			add_details(&mut result, format!("scanned as {}", name));
+		}
+		let key = (result.target.clone(), result.scan_type.clone(), result.port, result.status.clone());
+		match seen.get(&key) {
+			Some(&index) => merge_into(&mut normalized[index], result),
+			None => {
+				seen.insert(key, normalized.len());
+				normalized.push(result);
+			},
+		}
+	}
+	normalized
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::plugins::Severity;
+
+	#[test]
+	fn test_normalize() {
+		let mut banner = ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22).with_details("SSH-2.0");
+		banner.severity = Some(Severity::Low);
+		banner.service = Some("ssh".to_string());
+		let results = vec![
+			ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22),
+			ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(80),
+			banner,
+			ScanResult::new("db.example.com", "tcp_connect", "open").with_port(22).with_details("SSH-2.0"),
+			ScanResult::new("db.example.com", "tcp_connect", "closed").with_port(443),
+			ScanResult::new("10.0.0.1", "tcp_connect", "filtered").with_port(22),
+		];
+		let aliases = HashMap::from([("db.example.com".to_string(), "10.0.0.1".to_string())]);
+
+		let normalized = normalize(results, &aliases);
+		assert_eq!(normalized.len(), 4);
+		assert!(normalized.iter().all(|r| r.target == "10.0.0.1"));
+		assert_eq!(normalized[0].service.as_deref(), Some("ssh"));
+		assert_eq!(normalized[0].severity, Some(Severity::Low));
+		assert_eq!(normalized[0].details.as_deref(), Some("SSH-2.0; scanned as db.example.com"));
+		assert_eq!(normalized[2].port, Some(443));
+		assert_eq!(normalized[3].status, "filtered");
+	}
+
+	#[tokio::test]
+	async fn test_aliases() {
+		let targets = ["localhost".to_string(), "127.0.0.1".to_string(), "192.0.2.1".to_string()];
+		let found = aliases(&targets).await;
+		assert!(found.get("localhost").is_none_or(|address| address == "127.0.0.1"));
+		assert!(aliases(&["localhost".to_string()]).await.is_empty());
+	}
+}
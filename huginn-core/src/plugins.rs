@@ -0,0 +1,721 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Plugin system for Huginn
+//!
+//! This module defines the plugin trait and provides a framework for implementing
+//! different types of scanning plugins.
+
+#[cfg(feature = "web-checks")]
+pub mod api;
+#[cfg(feature = "web-checks")]
+pub mod cloud_metadata;
+#[cfg(feature = "web-checks")]
+pub mod container_expose;
+#[cfg(feature = "web-checks")]
+pub mod content_discovery;
+#[cfg(feature = "web-checks")]
+pub mod default_creds;
+#[cfg(feature = "core-scan")]
+pub mod engine;
+#[cfg(feature = "web-checks")]
+pub mod http;
+#[cfg(feature = "ics")]
+pub mod ics;
+#[cfg(feature = "core-scan")]
+pub mod idle;
+#[cfg(feature = "web-checks")]
+pub mod legacy_services;
+#[cfg(feature = "web-checks")]
+pub mod open_proxy;
+#[cfg(feature = "web-checks")]
+pub mod open_resolver;
+#[cfg(feature = "passive")]
+pub mod passive;
+#[cfg(feature = "core-scan")]
+pub mod ping;
+#[cfg(feature = "core-scan")]
+pub mod raw;
+#[cfg(feature = "web-checks")]
+pub mod remote_display;
+#[cfg(feature = "web-checks")]
+pub mod sip;
+#[cfg(feature = "web-checks")]
+pub mod snmp;
+#[cfg(feature = "web-checks")]
+pub mod snmp_inventory;
+#[cfg(feature = "web-checks")]
+pub mod ssh_audit_auth;
+#[cfg(feature = "core-scan")]
+pub mod tcp_connect;
+#[cfg(feature = "core-scan")]
+pub mod tcp_syn;
+#[cfg(feature = "web-checks")]
+pub mod tls;
+#[cfg(feature = "core-scan")]
+pub mod udp;
+#[cfg(feature = "web-checks")]
+pub mod upnp;
+#[cfg(feature = "web-checks")]
+pub mod usm;
+#[cfg(feature = "web-checks")]
+pub mod web_platforms;
+#[cfg(feature = "web-checks")]
+pub mod web_screenshot;
+#[cfg(feature = "web-checks")]
+pub mod well_known;
+
+use crate::credentials::Credentials;
+use crate::error::HuginnError;
+use crate::ports::PortSpec;
+use crate::progress::Probes;
+use crate::proxy::Proxy;
+use crate::transport::{Stream, Transport};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Severity of a finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+	/// Informational observation
+	Info,
+	/// Low risk
+	Low,
+	/// Medium risk
+	Medium,
+	/// High risk
+	High,
+	/// Critical risk
+	Critical,
+}
+
+impl Severity {
+	/// Severity of a CVSS base score, by the CVSS v3 qualitative ratings
+	pub fn from_cvss(score: f64) -> Self {
+		match score {
+			score if score >= 9.0 => Severity::Critical,
+			score if score >= 7.0 => Severity::High,
+			score if score >= 4.0 => Severity::Medium,
+			score if score > 0.0 => Severity::Low,
+			_ => Severity::Info,
+		}
+	}
+}
+
+impl std::str::FromStr for Severity {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_ascii_lowercase().as_str() {
+			"info" => Ok(Severity::Info),
+			"low" => Ok(Severity::Low),
+			"medium" => Ok(Severity::Medium),
+			"high" => Ok(Severity::High),
+			"critical" => Ok(Severity::Critical),
+			other => Err(format!("Unknown severity: {}", other)),
+		}
+	}
+}
+
+impl std::fmt::Display for Severity {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let name = match self {
+			Severity::Info => "info",
+			Severity::Low => "low",
+			Severity::Medium => "medium",
+			Severity::High => "high",
+			Severity::Critical => "critical",
+		};
+		f.write_str(name)
+	}
+}
+
+/// Kind of scan a plugin performs, as named in `scan_types`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanType {
+	/// ICMP echo host discovery
+	Ping,
+	/// Full TCP connect port scan
+	TcpConnect,
+	/// Half-open TCP SYN port scan
+	TcpSyn,
+	/// UDP port scan
+	Udp,
+	/// Discovery from captured traffic, without sending probes
+	Passive,
+	/// TCP port scan spoofed from a zombie host
+	Idle,
+	/// Subdomain enumeration of a domain target
+	Subdomains,
+	/// Web server fingerprinting
+	Http,
+	/// TLS server fingerprinting
+	Tls,
+	/// gRPC and REST API discovery
+	Api,
+	/// Container management APIs exposed without credentials
+	ContainerExpose,
+	/// Cloud instance metadata reachable through web ports
+	CloudMetadata,
+	/// Industrial control system device identification
+	Ics,
+	/// SIP server enumeration
+	Sip,
+	/// VNC and X11 display exposure
+	RemoteDisplay,
+	/// UPnP Internet Gateway Device exposure
+	Upnp,
+	/// Legacy clear-text services such as telnet and TFTP
+	LegacyServices,
+	/// Email security posture grading of a domain target
+	MailPosture,
+	/// DNSSEC signing and chain of trust of a domain target
+	Dnssec,
+	/// CAA records and dangling DNS records open to takeover
+	DnsHygiene,
+	/// HTTP and SOCKS proxies that relay for anyone
+	OpenProxy,
+	/// DNS resolvers that recurse for anyone
+	OpenResolver,
+	/// Vendor default logins on admin interfaces, tried only when authorized
+	DefaultCreds,
+	/// SNMPv3 engine discovery and system inventory with configured credentials
+	SnmpInventory,
+	/// SSH server settings, listening sockets, and pending updates read by logging in
+	SshAuditAuth,
+	/// Screenshots of the front pages of web servers
+	WebScreenshot,
+	/// Unlinked paths and virtual hosts of web servers, found from wordlists
+	ContentDiscovery,
+}
+
+impl std::fmt::Display for ScanType {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let name = match self {
+			ScanType::Ping => "ping",
+			ScanType::TcpConnect => "tcp_connect",
+			ScanType::TcpSyn => "tcp_syn",
+			ScanType::Udp => "udp",
+			ScanType::Passive => "passive",
+			ScanType::Idle => "idle",
+			ScanType::Subdomains => "subdomains",
+			ScanType::Http => "http",
+			ScanType::Tls => "tls",
+			ScanType::Api => "api",
+			ScanType::ContainerExpose => "container_expose",
+			ScanType::CloudMetadata => "cloud_metadata",
+			ScanType::Ics => "ics",
+			ScanType::Sip => "sip",
+			ScanType::RemoteDisplay => "remote_display",
+			ScanType::Upnp => "upnp",
+			ScanType::LegacyServices => "legacy_services",
+			ScanType::MailPosture => "mail_posture",
+			ScanType::Dnssec => "dnssec",
+			ScanType::DnsHygiene => "dns_hygiene",
+			ScanType::OpenProxy => "open_proxy",
+			ScanType::OpenResolver => "open_resolver",
+			ScanType::DefaultCreds => "default_creds",
+			ScanType::SnmpInventory => "snmp_inventory",
+			ScanType::SshAuditAuth => "ssh_audit_auth",
+			ScanType::WebScreenshot => "web_screenshot",
+			ScanType::ContentDiscovery => "content_discovery",
+		};
+		f.write_str(name)
+	}
+}
+
+impl std::str::FromStr for ScanType {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		ScanType::ALL.into_iter().find(|scan_type| scan_type.to_string() == name).ok_or_else(|| {
+			let names: Vec<String> = ScanType::ALL.iter().map(ToString::to_string).collect();
+			format!("unknown scan type {:?}; expected one of {}", name, names.join(", "))
+		})
+	}
+}
+
+impl ScanType {
+	/// Every scan type, in the order they are listed
+	pub const ALL: [ScanType; 27] = [
+		ScanType::Ping,
+		ScanType::TcpConnect,
+		ScanType::TcpSyn,
+		ScanType::Udp,
+		ScanType::Passive,
+		ScanType::Idle,
+		ScanType::Subdomains,
+		ScanType::Http,
+		ScanType::Tls,
+		ScanType::Api,
+		ScanType::ContainerExpose,
+		ScanType::CloudMetadata,
+		ScanType::Ics,
+		ScanType::Sip,
+		ScanType::RemoteDisplay,
+		ScanType::Upnp,
+		ScanType::LegacyServices,
+		ScanType::MailPosture,
+		ScanType::Dnssec,
+		ScanType::DnsHygiene,
+		ScanType::OpenProxy,
+		ScanType::OpenResolver,
+		ScanType::DefaultCreds,
+		ScanType::SnmpInventory,
+		ScanType::SshAuditAuth,
+		ScanType::WebScreenshot,
+		ScanType::ContentDiscovery,
+	];
+
+	/// Timing the scan type uses in place of the global settings, under its own overrides
+	///
+	/// Industrial devices can fail under probing that servers shrug off, so `ics` makes one retry
+	/// and pauses before each probe, whatever the global timing.
+	pub fn default_timing(self) -> TimingOverride {
+		match self {
+			ScanType::Ics => TimingOverride {
+				retries: Some(1),
+				scan_delay: Some(Duration::from_secs(1)),
+				..TimingOverride::default()
+			},
+			_ => TimingOverride::default(),
+		}
+	}
+}
+
+/// Status of a result that records a failed scan
+pub const ERROR_STATUS: &str = "error";
+
+/// TCP ports probed by the port scans when no port spec is configured
+#[cfg(feature = "core-scan")]
+pub(crate) const DEFAULT_TCP_PORTS: &[u16] = &[
+	21, 22, 23, 25, 53, 80, 110, 111, 135, 139, 143, 443, 445, 993, 995, 1723, 3306, 3389, 5900, 8080,
+];
+
+/// Status of a result that reports another host to scan, named by its target (see
+/// [`Plugin::finds_hosts`])
+pub const FOUND_STATUS: &str = "found";
+
+/// Scan result information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanResult {
+	/// Target that was scanned
+	pub target: String,
+	/// Type of scan performed
+	pub scan_type: String,
+	/// Port the result applies to, if any
+	pub port: Option<u16>,
+	/// Status or result of the scan
+	pub status: String,
+	/// Severity when the result is a finding
+	pub severity: Option<Severity>,
+	/// Identified service name
+	pub service: Option<String>,
+	/// Identified service version
+	pub version: Option<String>,
+	/// Additional details
+	pub details: Option<String>,
+	/// MITRE ATT&CK technique IDs the finding is relevant to, such as `T1021.001` (see
+	/// [`attack`](crate::attack))
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub techniques: Vec<String>,
+	/// PNG screenshot saved for the result by the `web_screenshot` scan type
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub screenshot: Option<PathBuf>,
+	/// Round-trip time of the reply the result was found from, such as a ping's echo reply
+	#[serde(rename = "rtt_ms", default, with = "crate::statistics::millis", skip_serializing_if = "Option::is_none")]
+	pub rtt: Option<Duration>,
+}
+
+impl ScanResult {
+	/// A result with a target, scan type, and status, and no other fields; the `with_` methods
+	/// fill in the rest
+	pub fn new(target: impl Into<String>, scan_type: impl Into<String>, status: impl Into<String>) -> Self {
+		ScanResult {
+			target: target.into(),
+			scan_type: scan_type.into(),
+			port: None,
+			status: status.into(),
+			severity: None,
+			service: None,
+			version: None,
+			details: None,
+			techniques: Vec::new(),
+			screenshot: None,
+			rtt: None,
+		}
+	}
+
+	/// The result with its port set
+	pub fn with_port(mut self, port: impl Into<Option<u16>>) -> Self {
+		self.port = port.into();
+		self
+	}
+
+	/// The result with its severity set
+	pub fn with_severity(mut self, severity: impl Into<Option<Severity>>) -> Self {
+		self.severity = severity.into();
+		self
+	}
+
+	/// The result with its service set
+	pub fn with_service(mut self, service: impl Into<String>) -> Self {
+		self.service = Some(service.into());
+		self
+	}
+
+	/// The result with its service version set
+	pub fn with_version(mut self, version: impl Into<String>) -> Self {
+		self.version = Some(version.into());
+		self
+	}
+
+	/// The result with its details set
+	pub fn with_details(mut self, details: impl Into<String>) -> Self {
+		self.details = Some(details.into());
+		self
+	}
+
+	/// The result with its ATT&CK technique IDs set
+	pub fn with_techniques<T: Into<String>>(mut self, techniques: impl IntoIterator<Item = T>) -> Self {
+		self.techniques = techniques.into_iter().map(Into::into).collect();
+		self
+	}
+
+	/// The result with its round-trip time set
+	pub fn with_rtt(mut self, rtt: impl Into<Option<Duration>>) -> Self {
+		self.rtt = rtt.into();
+		self
+	}
+
+	/// Record a failed scan as a result, so it is not mistaken for a scan that found nothing
+	///
+	/// The status is `error`, and the details are the error kind followed by the message, such as
+	/// `unresolved: example.invalid did not resolve to an address`.
+	pub fn failed(target: &str, scan_type: ScanType, error: &HuginnError) -> Self {
+		ScanResult::new(target, scan_type.to_string(), ERROR_STATUS).with_details(format!("{}: {}", error.kind(), error))
+	}
+
+	/// Whether the result records a failed scan
+	pub fn is_error(&self) -> bool {
+		self.status == ERROR_STATUS
+	}
+}
+
+/// Per-plugin timing overrides; unset values fall back to the scan type's default timing, and then
+/// to the global settings
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TimingOverride {
+	/// Time to wait for a response to each probe
+	#[serde(default, with = "humantime_serde")]
+	pub timeout: Option<Duration>,
+	/// Extra attempts after a probe times out
+	pub retries: Option<u32>,
+	/// Pause before each probe
+	#[serde(default, with = "humantime_serde")]
+	pub scan_delay: Option<Duration>,
+	/// Factor the wait for a reply grows by with each retransmission
+	pub backoff: Option<f64>,
+}
+
+/// Shaping of the probes that raw-socket scans build themselves
+#[derive(Debug, Default, Clone)]
+pub struct RawOptions {
+	/// Source port of every probe, instead of a random one
+	pub source_port: Option<u16>,
+	/// Addresses that an identical probe is also sent from, to hide the real source among them
+	pub decoys: Vec<Ipv4Addr>,
+	/// Time to live of the probes
+	pub ttl: Option<u8>,
+	/// Random bytes appended to each probe
+	pub data_length: usize,
+	/// Largest IP payload of each fragment; probes are sent unfragmented when unset
+	pub mtu: Option<usize>,
+}
+
+impl RawOptions {
+	/// Most random bytes that can be appended to a probe and still fit in an Ethernet frame
+	pub const MAX_DATA_LENGTH: usize = 1400;
+
+	/// Largest fragment payload that fits in an Ethernet frame
+	pub const MAX_MTU: usize = 1480;
+
+	/// Problems with the options, each as the option's name and what is wrong with it
+	pub fn problems(&self) -> Vec<(&'static str, String)> {
+		let mut problems = Vec::new();
+		if self.data_length > Self::MAX_DATA_LENGTH {
+			problems.push(("data_length", format!("at most {} bytes can be appended", Self::MAX_DATA_LENGTH)));
+		}
+		// Fragment offsets count 8-byte units, so other sizes cannot be sent as given
+		if let Some(mtu) = self.mtu
+			&& (mtu == 0 || mtu % 8 != 0 || mtu > Self::MAX_MTU)
+		{
+			problems.push(("mtu", format!("must be a multiple of 8 from 8 to {}", Self::MAX_MTU)));
+		}
+		if self.ttl == Some(0) {
+			problems.push(("ttl", "a time to live of 0 is dropped by the first hop".to_string()));
+		}
+		problems
+	}
+
+	/// Check that probes shaped by the options can be sent, reporting the first problem as
+	/// [`HuginnError::Config`]
+	pub fn check(&self) -> Result<(), HuginnError> {
+		match self.problems().into_iter().next() {
+			Some((name, problem)) => Err(HuginnError::Config(format!("{}: {}", name, problem))),
+			None => Ok(()),
+		}
+	}
+}
+
+/// Settings a plugin receives for each scan, resolved by the scanner for its scan type
+#[derive(Debug, Clone)]
+pub struct ScanContext {
+	/// Ports to probe; plugins fall back to their own defaults when unset
+	pub ports: Option<PortSpec>,
+	/// Time to wait for a response to each probe
+	pub timeout: Duration,
+	/// Extra attempts after a probe times out
+	pub retries: u32,
+	/// Pause before each probe
+	pub scan_delay: Duration,
+	/// Factor the wait for a reply grows by with each retransmission, for plugins that retransmit
+	pub backoff: f64,
+	/// Network interface that probes are sent from
+	pub interface: Option<String>,
+	/// Source address that probes are sent from
+	pub source_ip: Option<IpAddr>,
+	/// Proxy that TCP connections are tunneled through
+	pub proxy: Option<Proxy>,
+	/// Most TCP connections held open at once, within the file descriptor limit
+	pub max_connections: usize,
+	/// Time that passive plugins listen for traffic
+	pub capture_duration: Duration,
+	/// Saved capture that passive plugins read instead of listening on an interface
+	pub capture_file: Option<PathBuf>,
+	/// Shaping of raw-socket probes
+	pub raw: RawOptions,
+	/// Zombie that idle scans are spoofed from, as `HOST[:PORT]`
+	pub zombie: Option<String>,
+	/// Domain that open proxy and open resolver checks ask targets to reach
+	pub canary: String,
+	/// Credentials that authenticated checks may log in with, by name
+	pub credentials: Arc<Credentials>,
+	/// How probes reach the network
+	pub transport: Arc<dyn Transport>,
+	/// Count of the probes sent by the scan, which plugins add each probe to
+	pub probes: Probes,
+}
+
+impl ScanContext {
+	/// Ports to probe: those of the port spec, or else the plugin's defaults
+	pub fn ports_or<'a>(&'a self, defaults: &'a [u16]) -> &'a [u16] {
+		self.ports.as_ref().map_or(defaults, PortSpec::ports)
+	}
+
+	/// Resolve a target to the address it is probed at, preferring the source address's family
+	pub async fn resolve(&self, target: &str) -> Result<IpAddr, HuginnError> {
+		let unresolved = || HuginnError::Unresolved { target: target.to_string() };
+		let addresses = self.transport.resolve(target).await.map_err(|_| unresolved())?;
+		addresses
+			.iter()
+			.find(|a| self.source_ip.is_none_or(|source| source.is_ipv4() == a.is_ipv4()))
+			.or(addresses.first())
+			.copied()
+			.ok_or_else(unresolved)
+	}
+
+	/// Open a TCP connection to `host:port`, through the proxy when one is configured
+	///
+	/// Host names are resolved by the proxy when tunneling, and locally otherwise.
+	pub async fn connect(&self, host: &str, port: u16) -> io::Result<Box<dyn Stream>> {
+		match &self.proxy {
+			Some(proxy) => {
+				let mut stream = self.connect_direct(&proxy.host, proxy.port).await?;
+				proxy.handshake(&mut stream, host, port).await?;
+				Ok(stream)
+			},
+			None => self.connect_direct(host, port).await,
+		}
+	}
+
+	/// Open a TCP connection from the configured interface and source address
+	async fn connect_direct(&self, host: &str, port: u16) -> io::Result<Box<dyn Stream>> {
+		// Prefer an address in the source address's family
+		let addresses = self.transport.resolve(host).await?;
+		let address = addresses
+			.iter()
+			.find(|a| self.source_ip.is_none_or(|source| source.is_ipv4() == a.is_ipv4()))
+			.or(addresses.first())
+			.map(|&ip| SocketAddr::new(ip, port))
+			.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} did not resolve to an address", host)))?;
+		if let Some(source) = self.source_ip
+			&& source.is_ipv4() != address.is_ipv4()
+		{
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("source address {} cannot reach {}", source, address),
+			));
+		}
+		self.transport.connect(address, self).await
+	}
+
+	/// The error for a probe of the target that timed out on every attempt, when the scan cannot
+	/// go on without its answer
+	pub fn timed_out(&self, target: &str) -> HuginnError {
+		HuginnError::ProbeTimeout {
+			target: target.to_string(),
+			timeout: self.timeout,
+		}
+	}
+
+	/// Run a probe under the configured delay, timeout, and retries
+	///
+	/// Returns the probe's outcome, or `None` when every attempt timed out.
+	pub async fn probe<F, Fut, T>(&self, mut probe: F) -> Option<T>
+	where
+		F: FnMut() -> Fut,
+		Fut: Future<Output = T>,
+	{
+		for _ in 0..=self.retries {
+			if !self.scan_delay.is_zero() {
+				tokio::time::sleep(self.scan_delay).await;
+			}
+			self.probes.sent();
+			if let Ok(outcome) = tokio::time::timeout(self.timeout, probe()).await {
+				return Some(outcome);
+			}
+		}
+		None
+	}
+}
+
+/// Plugin trait that all scanning plugins must implement
+#[async_trait]
+pub trait Plugin: Send + Sync {
+	/// Get the name of the plugin
+	fn name(&self) -> String;
+
+	/// Get the scan type identifier
+	fn scan_type(&self) -> ScanType;
+
+	/// One-line description of what the plugin checks
+	fn description(&self) -> String;
+
+	/// Configuration settings that change how the plugin probes
+	fn settings(&self) -> &'static [&'static str] {
+		&["timeout", "retries", "scan_delay", "plugin_timing"]
+	}
+
+	/// Whether every probe the plugin sends can be tunneled through a TCP proxy
+	///
+	/// Plugins that are not proxy capable are skipped when a proxy is configured, so they never
+	/// reveal the scanning host. UDP, and so QUIC, cannot be tunneled through a TCP proxy: a
+	/// plugin that also sends UDP probes, such as `sip` or HTTP/3 in `http`, is proxy capable
+	/// only if it leaves them out while [`ScanContext::proxy`] is set.
+	fn proxy_capable(&self) -> bool {
+		false
+	}
+
+	/// Number of probes the plugin sends to each target, for planning a scan
+	fn probes_per_target(&self, _context: &ScanContext) -> usize {
+		1
+	}
+
+	/// Whether the plugin scans ports, and so is skipped for targets that host discovery finds down
+	fn scans_ports(&self) -> bool {
+		false
+	}
+
+	/// Whether the plugin finds other hosts, reported as results with the [`FOUND_STATUS`] status
+	///
+	/// The scanner scans each host found this way after the configured targets, with every
+	/// enabled plugin that does not itself find hosts.
+	fn finds_hosts(&self) -> bool {
+		false
+	}
+
+	/// Whether the plugin only listens, sending nothing at all
+	///
+	/// When every enabled plugin is passive, the scanner sends nothing either: it skips host
+	/// discovery and the DNS lookups that normalize host names.
+	fn passive(&self) -> bool {
+		false
+	}
+
+	/// Perform the scan on the target
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError>;
+}
+
+/// Create all built-in plugins enabled in this build
+///
+/// The opt-in [`default_creds::DefaultCredsPlugin`] and [`ssh_audit_auth::SshAuditAuthPlugin`],
+/// which log in, are left out; callers add them themselves once the operator has confirmed they
+/// are authorized to. So are [`web_screenshot::WebScreenshotPlugin`], which needs to be told where
+/// its browser is and where to save screenshots, and
+/// [`content_discovery::ContentDiscoveryPlugin`], which needs wordlists.
+pub fn builtin() -> Vec<Box<dyn Plugin>> {
+	vec![
+		#[cfg(feature = "core-scan")]
+		Box::new(ping::PingScanPlugin),
+		#[cfg(feature = "core-scan")]
+		Box::new(tcp_connect::TcpConnectScanPlugin),
+		#[cfg(feature = "core-scan")]
+		Box::new(tcp_syn::TcpSynScanPlugin),
+		#[cfg(feature = "core-scan")]
+		Box::new(idle::IdleScanPlugin::default()),
+		#[cfg(feature = "core-scan")]
+		Box::new(udp::UdpScanPlugin),
+		#[cfg(feature = "passive")]
+		Box::new(passive::PassiveScanPlugin::default()),
+		#[cfg(feature = "web-checks")]
+		Box::new(http::HttpPlugin),
+		#[cfg(feature = "web-checks")]
+		Box::new(tls::TlsPlugin),
+		#[cfg(feature = "web-checks")]
+		Box::new(api::ApiPlugin),
+		#[cfg(feature = "web-checks")]
+		Box::new(container_expose::ContainerExposePlugin),
+		#[cfg(feature = "web-checks")]
+		Box::new(cloud_metadata::CloudMetadataPlugin),
+		#[cfg(feature = "ics")]
+		Box::new(ics::IcsPlugin),
+		#[cfg(feature = "web-checks")]
+		Box::new(sip::SipPlugin),
+		#[cfg(feature = "web-checks")]
+		Box::new(remote_display::RemoteDisplayPlugin),
+		#[cfg(feature = "web-checks")]
+		Box::new(upnp::UpnpPlugin),
+		#[cfg(feature = "web-checks")]
+		Box::new(legacy_services::LegacyServicesPlugin),
+		#[cfg(feature = "web-checks")]
+		Box::new(open_proxy::OpenProxyPlugin::default()),
+		#[cfg(feature = "web-checks")]
+		Box::new(open_resolver::OpenResolverPlugin),
+		#[cfg(feature = "web-checks")]
+		Box::new(snmp_inventory::SnmpInventoryPlugin),
+	]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_scan_type_names() {
+		for scan_type in ScanType::ALL {
+			assert_eq!(scan_type.to_string().parse::<ScanType>(), Ok(scan_type));
+		}
+		let error = "tcp".parse::<ScanType>().unwrap_err();
+		assert!(error.starts_with("unknown scan type \"tcp\"; expected one of ping, tcp_connect, "));
+		assert!(error.ends_with(", content_discovery"));
+	}
+}
@@ -0,0 +1,608 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! API surface plugin
+//!
+//! Finds the APIs served on each port and names their methods and endpoints:
+//!
+//! - gRPC services, found by calling the standard health check over HTTP/2, with TLS or in the
+//!   clear, and taking an `application/grpc` answer, whatever its status, as a gRPC server. When
+//!   the server enables reflection, the services it lists and their methods are reported as a
+//!   low-severity `grpc_reflection` finding, since reflection hands a map of the API to anyone.
+//! - REST APIs that publish an OpenAPI or Swagger document at one of the usual paths, such as
+//!   `/swagger.json` and `/v3/api-docs`. The first document found is reported as an `openapi`
+//!   finding, with its title and the method and path of each endpoint.
+
+use crate::error::HuginnError;
+use crate::plugins::http::{self, Clients, USER_AGENT};
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+use async_trait::async_trait;
+use bytes::Bytes;
+use h2::client::SendRequest;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::io;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Ports probed when no port spec is configured
+const DEFAULT_PORTS: &[u16] = &[80, 443, 3000, 5000, 8000, 8080, 8443, 9090, 50051];
+
+/// Method called to find gRPC servers
+const HEALTH_CHECK: &str = "/grpc.health.v1.Health/Check";
+
+/// Methods of the reflection service, newest first
+const REFLECTION: &[&str] = &[
+	"/grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
+	"/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
+];
+
+/// Paths OpenAPI and Swagger documents are commonly published at
+const OPENAPI_PATHS: &[&str] = &[
+	"/openapi.json",
+	"/swagger.json",
+	"/v3/api-docs",
+	"/v2/api-docs",
+	"/swagger/v1/swagger.json",
+	"/api-docs",
+	"/api/openapi.json",
+	"/api/swagger.json",
+	"/openapi.yaml",
+	"/swagger.yaml",
+];
+
+/// HTTP methods an OpenAPI path item can describe
+const OPENAPI_METHODS: &[&str] = &["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// Largest gRPC response read
+const MAX_RESPONSE: usize = 4 << 20;
+
+/// Most method and endpoint names listed in a finding
+const MAX_NAMES: usize = 100;
+
+/// Read a protobuf varint from the front of data
+fn varint(data: &mut &[u8]) -> Option<u64> {
+	let mut value = 0u64;
+	for shift in (0..64).step_by(7) {
+		let (&byte, rest) = data.split_first()?;
+		*data = rest;
+		value |= u64::from(byte & 0x7f) << shift;
+		if byte & 0x80 == 0 {
+			return Some(value);
+		}
+	}
+	None
+}
+
+/// The length-delimited fields of a protobuf message, as field number and value, up to the first
+/// malformed one
+fn fields(mut data: &[u8]) -> Vec<(u64, &[u8])> {
+	let mut fields = Vec::new();
+	while let Some(key) = varint(&mut data) {
+		let skip = match key & 7 {
+			0 => varint(&mut data).map(|_| 0),
+			1 => Some(8),
+			2 => {
+				let Some(value) = varint(&mut data).and_then(|length| data.get(..usize::try_from(length).ok()?)) else {
+					break;
+				};
+				fields.push((key >> 3, value));
+				Some(value.len())
+			},
+			5 => Some(4),
+			_ => None,
+		};
+		match skip.and_then(|skip| data.get(skip..)) {
+			Some(rest) => data = rest,
+			None => break,
+		}
+	}
+	fields
+}
+
+/// The values of a field, wherever it repeats
+fn field_values(data: &[u8], number: u64) -> impl Iterator<Item = &[u8]> {
+	fields(data).into_iter().filter(move |(n, _)| *n == number).map(|(_, value)| value)
+}
+
+/// The first value of a string field
+fn string_field(data: &[u8], number: u64) -> Option<&str> {
+	field_values(data, number).next().and_then(|value| std::str::from_utf8(value).ok())
+}
+
+/// A length-delimited protobuf field
+fn encode_field(number: u64, value: &[u8]) -> Vec<u8> {
+	let mut encoded = Vec::new();
+	for mut varint in [number << 3 | 2, value.len() as u64] {
+		while varint >= 0x80 {
+			encoded.push(varint as u8 | 0x80);
+			varint >>= 7;
+		}
+		encoded.push(varint as u8);
+	}
+	encoded.extend_from_slice(value);
+	encoded
+}
+
+/// Names of the services in a ServerReflectionResponse to a `list_services` request
+fn listed_services(response: &[u8]) -> Vec<String> {
+	field_values(response, 6)
+		.flat_map(|list| field_values(list, 1))
+		.filter_map(|service| string_field(service, 1))
+		.map(str::to_string)
+		.collect()
+}
+
+/// Methods of the services in a ServerReflectionResponse to a `file_containing_symbol` request,
+/// as `package.Service/Method`
+fn described_methods(response: &[u8]) -> Vec<String> {
+	let mut methods = Vec::new();
+	for file in field_values(response, 4).flat_map(|files| field_values(files, 1)) {
+		let package = string_field(file, 2).map(|package| format!("{}.", package)).unwrap_or_default();
+		for service in field_values(file, 6) {
+			let name = string_field(service, 1).unwrap_or_default();
+			for method in field_values(service, 2).filter_map(|method| string_field(method, 1)) {
+				methods.push(format!("{}{}/{}", package, name, method));
+			}
+		}
+	}
+	methods
+}
+
+/// Answer to a gRPC call
+struct Reply {
+	/// Whether the answer was a gRPC one
+	grpc: bool,
+	/// The `grpc-status` of the call
+	status: Option<u32>,
+	/// Messages answered
+	messages: Vec<Vec<u8>>,
+}
+
+/// Open an HTTP/2 connection to a port, over TLS or in the clear
+async fn connect(target: &str, port: u16, tls: bool, clients: &Clients, context: &ScanContext) -> io::Result<SendRequest<Bytes>> {
+	let stream = context.connect(target, port).await?;
+	let (client, connection) = if tls {
+		let stream = clients.h2.connect(http::server_name(target)?, stream).await?;
+		if stream.get_ref().1.alpn_protocol() != Some(b"h2") {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "the server did not pick HTTP/2"));
+		}
+		let (client, connection) = h2::client::handshake(stream).await.map_err(io::Error::other)?;
+		(client, tokio::spawn(async move { connection.await.is_ok() }))
+	} else {
+		let (client, connection) = h2::client::handshake(stream).await.map_err(io::Error::other)?;
+		(client, tokio::spawn(async move { connection.await.is_ok() }))
+	};
+	// The connection closes once the last handle to it is dropped
+	drop(connection);
+	Ok(client)
+}
+
+/// Call a gRPC method with one message
+async fn call(client: &SendRequest<Bytes>, authority: &str, tls: bool, method: &str, message: &[u8]) -> io::Result<Reply> {
+	let scheme = if tls { "https" } else { "http" };
+	let request = ::http::Request::post(format!("{}://{}{}", scheme, authority, method))
+		.header("content-type", "application/grpc")
+		.header("te", "trailers")
+		.header("user-agent", USER_AGENT)
+		.body(())
+		.map_err(io::Error::other)?;
+	let mut client = client.clone().ready().await.map_err(io::Error::other)?;
+	let (response, mut stream) = client.send_request(request, false).map_err(io::Error::other)?;
+	let mut frame = vec![0];
+	frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+	frame.extend_from_slice(message);
+	stream.send_data(Bytes::from(frame), true).map_err(io::Error::other)?;
+
+	let response = response.await.map_err(io::Error::other)?;
+	let grpc_status = |headers: &::http::HeaderMap| headers.get("grpc-status")?.to_str().ok()?.parse().ok();
+	let grpc = response
+		.headers()
+		.get("content-type")
+		.and_then(|kind| kind.to_str().ok())
+		.is_some_and(|kind| kind.starts_with("application/grpc"));
+	// A call that fails at once answers with its status in the headers
+	let mut status = grpc_status(response.headers());
+	let mut body = response.into_body();
+	let mut data = Vec::new();
+	while let Some(chunk) = body.data().await {
+		let chunk = chunk.map_err(io::Error::other)?;
+		let _ = body.flow_control().release_capacity(chunk.len());
+		data.extend_from_slice(&chunk);
+		if data.len() > MAX_RESPONSE {
+			break;
+		}
+	}
+	if let Ok(Some(trailers)) = body.trailers().await {
+		status = status.or_else(|| grpc_status(&trailers));
+	}
+
+	let mut messages = Vec::new();
+	let mut rest = &data[..];
+	while let Some(header) = rest.get(..5) {
+		let length = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+		let Some(message) = rest.get(5..5 + length) else {
+			break;
+		};
+		messages.push(message.to_vec());
+		rest = &rest[5 + length..];
+	}
+	Ok(Reply { grpc, status, messages })
+}
+
+/// Methods the reflection service of a gRPC server lists, or services where it describes none,
+/// or `None` when reflection is off
+async fn reflect(client: &SendRequest<Bytes>, authority: &str, tls: bool, context: &ScanContext) -> Option<Vec<String>> {
+	for method in REFLECTION {
+		let ask = |message: Vec<u8>| async move {
+			let reply = tokio::time::timeout(context.timeout, call(client, authority, tls, method, &message)).await;
+			reply.ok()?.ok().filter(|reply| reply.status == Some(0))
+		};
+		let Some(reply) = ask(encode_field(7, b"")).await else {
+			continue;
+		};
+		let services: Vec<String> = reply
+			.messages
+			.iter()
+			.flat_map(|message| listed_services(message))
+			.filter(|service| !service.starts_with("grpc.reflection."))
+			.collect();
+		let mut names = BTreeSet::new();
+		for service in services {
+			let methods: Vec<String> = match ask(encode_field(4, service.as_bytes())).await {
+				Some(reply) => reply.messages.iter().flat_map(|message| described_methods(message)).collect(),
+				None => Vec::new(),
+			};
+			let prefix = format!("{}/", service);
+			let own: Vec<String> = methods.into_iter().filter(|method| method.starts_with(&prefix)).collect();
+			if own.is_empty() {
+				names.insert(service);
+			} else {
+				names.extend(own);
+			}
+		}
+		return Some(names.into_iter().collect());
+	}
+	None
+}
+
+/// Names listed in a finding, up to [`MAX_NAMES`] of them
+fn name_list(names: &[String]) -> String {
+	let mut list = names.iter().take(MAX_NAMES).cloned().collect::<Vec<_>>().join(", ");
+	if names.len() > MAX_NAMES {
+		list.push_str(&format!(", and {} more", names.len() - MAX_NAMES));
+	}
+	list
+}
+
+/// Results for a gRPC server on a port, or `None` when there is none
+async fn grpc(target: &str, port: u16, clients: &Clients, context: &ScanContext) -> Option<Vec<ScanResult>> {
+	let authority = http::authority(target, port, http::TLS_PORTS.contains(&port));
+	let order = if http::TLS_PORTS.contains(&port) { [true, false] } else { [false, true] };
+	for tls in order {
+		let health = || async {
+			let client = connect(target, port, tls, clients, context).await?;
+			let reply = call(&client, &authority, tls, HEALTH_CHECK, &[]).await?;
+			Ok::<_, io::Error>((client, reply))
+		};
+		let (client, reply) = match context.probe(health).await {
+			Some(Ok((client, reply))) if reply.grpc => (client, reply),
+			Some(Err(e)) if e.kind() == io::ErrorKind::ConnectionRefused => return None,
+			_ => continue,
+		};
+
+		let transport = if tls { "over TLS" } else { "in the clear" };
+		let health = match reply.status {
+			Some(0) => "; health check passes",
+			_ => "",
+		};
+		let mut results = vec![ScanResult {
+			target: target.to_string(),
+			scan_type: ScanType::Api.to_string(),
+			port: Some(port),
+			status: "open".to_string(),
+			severity: None,
+			service: Some("grpc".to_string()),
+			version: None,
+			details: Some(format!("gRPC {}{}", transport, health)),
+			techniques: vec!["T1190".to_string()],
+			screenshot: None,
+			rtt: None,
+		}];
+		if let Some(names) = reflect(&client, &authority, tls, context).await {
+			results.push(ScanResult {
+				target: target.to_string(),
+				scan_type: ScanType::Api.to_string(),
+				port: Some(port),
+				status: "grpc_reflection".to_string(),
+				severity: Some(Severity::Low),
+				service: Some("grpc".to_string()),
+				version: None,
+				details: Some(format!("Reflection is enabled and lists {}", name_list(&names))),
+				techniques: vec!["T1190".to_string()],
+				screenshot: None,
+				rtt: None,
+			});
+		}
+		return Some(results);
+	}
+	None
+}
+
+/// Title, version, and endpoints of an OpenAPI or Swagger document, or `None` when it is not one
+fn openapi(document: &Value) -> Option<(String, Vec<String>)> {
+	let version = document
+		.get("openapi")
+		.map(|version| format!("OpenAPI {}", version.as_str().unwrap_or_default()))
+		.or_else(|| document.get("swagger").map(|version| format!("Swagger {}", version.as_str().unwrap_or_default())))?;
+	let paths = document.get("paths")?.as_object()?;
+	let info = document.get("info");
+	let title = [info.and_then(|info| info.get("title")), info.and_then(|info| info.get("version"))]
+		.iter()
+		.filter_map(|value| value.and_then(Value::as_str))
+		.collect::<Vec<_>>()
+		.join(" ");
+	let describe = if title.is_empty() { version } else { format!("{} document for {:?}", version, title) };
+	let mut endpoints = Vec::new();
+	for (path, item) in paths {
+		for method in OPENAPI_METHODS.iter().filter(|&&method| item.get(method).is_some()) {
+			endpoints.push(format!("{} {}", method.to_ascii_uppercase(), path));
+		}
+	}
+	Some((describe, endpoints))
+}
+
+/// Finding for the first OpenAPI document published on a port
+async fn openapi_document(target: &str, port: u16, clients: &Clients, context: &ScanContext) -> Option<ScanResult> {
+	let (tls, _) = http::front_page(target, port, &clients.http1, context).await?;
+	for path in OPENAPI_PATHS {
+		let Some(Ok(response)) = context.probe(|| http::get(target, port, tls, path, context)).await else {
+			continue;
+		};
+		if response.status != 200 {
+			continue;
+		}
+		let document = if path.ends_with(".yaml") {
+			serde_norway::from_slice::<Value>(&response.body).ok()
+		} else {
+			serde_json::from_slice::<Value>(&response.body).ok()
+		};
+		let Some((describe, endpoints)) = document.as_ref().and_then(openapi) else {
+			continue;
+		};
+		let details = if endpoints.is_empty() {
+			format!("{} at {}", describe, path)
+		} else {
+			format!("{} at {} with endpoints {}", describe, path, name_list(&endpoints))
+		};
+		return Some(ScanResult {
+			target: target.to_string(),
+			scan_type: ScanType::Api.to_string(),
+			port: Some(port),
+			status: "openapi".to_string(),
+			severity: Some(Severity::Info),
+			service: Some(if tls.is_some() { "https" } else { "http" }.to_string()),
+			version: None,
+			details: Some(details),
+			techniques: vec!["T1190".to_string()],
+			screenshot: None,
+			rtt: None,
+		});
+	}
+	None
+}
+
+/// API surface plugin
+pub struct ApiPlugin;
+
+#[async_trait]
+impl Plugin for ApiPlugin {
+	fn name(&self) -> String {
+		"API Surface Scanner".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::Api
+	}
+
+	fn description(&self) -> String {
+		"Finds gRPC services and OpenAPI documents, and lists their methods and endpoints".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"proxy",
+			"max_connections",
+		]
+	}
+
+	fn proxy_capable(&self) -> bool {
+		true
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		// The gRPC health check, the front page, and each document path
+		context.ports_or(DEFAULT_PORTS).len() * (2 + OPENAPI_PATHS.len())
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		// Resolve up front to fail early, but connect by name so that virtual hosts answer
+		if context.proxy.is_none() {
+			context.resolve(target).await?;
+		}
+
+		let clients = Arc::new(Clients::new()?);
+		let (target, context) = (Arc::new(target.to_string()), Arc::new(context.clone()));
+		// A probe delay paces the probes one after another
+		let width = if context.scan_delay.is_zero() { context.max_connections } else { 1 };
+		let permits = Arc::new(Semaphore::new(width));
+		let mut probes = JoinSet::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			let (target, context, clients, permits) = (target.clone(), context.clone(), clients.clone(), permits.clone());
+			probes.spawn(async move {
+				let _permit = permits.acquire_owned().await;
+				let mut results = grpc(&target, port, &clients, &context).await.unwrap_or_default();
+				results.extend(openapi_document(&target, port, &clients, &context).await);
+				results
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(joined) = probes.join_next().await {
+			results.extend(joined.map_err(io::Error::other)?);
+		}
+		results.sort_by_key(|r| r.port);
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ports::PortSpec;
+	use crate::scanner::Scanner;
+	use std::time::Duration;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+	use tokio::net::TcpListener;
+
+	const DOCUMENT: &str = r#"{"openapi": "3.0.1", "info": {"title": "Pets", "version": "1.2"},
+		"paths": {"/pets": {"get": {}, "post": {}, "parameters": []}, "/pets/{id}": {"delete": {}}}}"#;
+
+	/// A FileDescriptorProto declaring a service with methods
+	fn file(package: &str, service: &str, methods: &[&str]) -> Vec<u8> {
+		let mut descriptor = encode_field(1, service.as_bytes());
+		for method in methods {
+			descriptor.extend(encode_field(2, &encode_field(1, method.as_bytes())));
+		}
+		[encode_field(2, package.as_bytes()), encode_field(6, &descriptor)].concat()
+	}
+
+	/// Answer a reflection request as a server with one service
+	fn reflection(request: &[u8]) -> Vec<u8> {
+		if let Some(symbol) = string_field(request, 4) {
+			let file = file("shop.v1", "Orders", &["Get", "List"]);
+			return encode_field(4, &encode_field(1, &file)).into_iter().chain(encode_field(9, symbol.as_bytes())).collect();
+		}
+		let services = ["shop.v1.Orders", "grpc.reflection.v1.ServerReflection"]
+			.iter()
+			.flat_map(|name| encode_field(1, &encode_field(1, name.as_bytes())))
+			.collect::<Vec<_>>();
+		encode_field(6, &services)
+	}
+
+	/// Serve gRPC in the clear: a health check that passes, and reflection over v1alpha only
+	async fn serve_grpc(listener: TcpListener) {
+		while let Ok((stream, _)) = listener.accept().await {
+			tokio::spawn(async move {
+				let Ok(mut connection) = h2::server::handshake(stream).await else {
+					return;
+				};
+				while let Some(Ok((request, mut respond))) = connection.accept().await {
+					let path = request.uri().path().to_string();
+					let mut body = request.into_body();
+					let mut data = Vec::new();
+					while let Some(Ok(chunk)) = body.data().await {
+						data.extend_from_slice(&chunk);
+					}
+					let (status, reply) = match path.as_str() {
+						HEALTH_CHECK => ("0", Some(encode_field(1, &[]))),
+						"/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo" => ("0", Some(reflection(&data[5..]))),
+						_ => ("12", None),
+					};
+					let response = ::http::Response::builder().header("content-type", "application/grpc").body(()).unwrap();
+					let mut stream = respond.send_response(response, false).unwrap();
+					if let Some(reply) = reply {
+						let mut frame = vec![0];
+						frame.extend_from_slice(&(reply.len() as u32).to_be_bytes());
+						frame.extend_from_slice(&reply);
+						stream.send_data(Bytes::from(frame), false).unwrap();
+					}
+					let mut trailers = ::http::HeaderMap::new();
+					trailers.insert("grpc-status", status.parse().unwrap());
+					stream.send_trailers(trailers).unwrap();
+				}
+			});
+		}
+	}
+
+	#[test]
+	fn test_protobuf() {
+		let message = [vec![0x08, 0x96, 0x01], encode_field(2, b"hello"), vec![0x1d, 1, 2, 3, 4], encode_field(2, b"")].concat();
+		assert_eq!(fields(&message), [(2, &b"hello"[..]), (2, &b""[..])]);
+		assert_eq!(fields(&[0x12, 0x05, b'h']), []);
+		assert_eq!(encode_field(7, b""), [0x3a, 0x00]);
+		assert_eq!(encode_field(1, &[0; 200])[..3], [0x0a, 0xc8, 0x01]);
+
+		let listed = reflection(&encode_field(7, b""));
+		assert_eq!(listed_services(&listed), ["shop.v1.Orders", "grpc.reflection.v1.ServerReflection"]);
+		let described = reflection(&encode_field(4, b"shop.v1.Orders"));
+		assert_eq!(described_methods(&described), ["shop.v1.Orders/Get", "shop.v1.Orders/List"]);
+	}
+
+	#[test]
+	fn test_openapi() {
+		let (describe, endpoints) = openapi(&serde_json::from_str(DOCUMENT).unwrap()).unwrap();
+		assert_eq!(describe, "OpenAPI 3.0.1 document for \"Pets 1.2\"");
+		assert_eq!(endpoints, ["GET /pets", "POST /pets", "DELETE /pets/{id}"]);
+		let swagger: Value = serde_norway::from_str("swagger: '2.0'\npaths:\n  /health:\n    get: {}\n").unwrap();
+		assert_eq!(openapi(&swagger).unwrap(), ("Swagger 2.0".to_string(), vec!["GET /health".to_string()]));
+		assert!(openapi(&serde_json::json!({"paths": {}})).is_none());
+		assert_eq!(name_list(&vec!["x".to_string(); MAX_NAMES + 2]).matches(", and 2 more").count(), 1);
+	}
+
+	#[tokio::test]
+	async fn test_finds_apis() {
+		let grpc = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let grpc_port = grpc.local_addr().unwrap().port();
+		tokio::spawn(serve_grpc(grpc));
+
+		let rest = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let rest_port = rest.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Ok((mut stream, _)) = rest.accept().await {
+				let mut request = vec![0; 4096];
+				let length = stream.read(&mut request).await.unwrap_or_default();
+				let (status, body) = if request[..length].starts_with(b"GET /v3/api-docs ") {
+					("200 OK", DOCUMENT)
+				} else if request[..length].starts_with(b"GET / ") {
+					("200 OK", "{}")
+				} else {
+					("404 Not Found", "")
+				};
+				let response = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\n\r\n{}", status, body.len(), body);
+				let _ = stream.write_all(response.as_bytes()).await;
+			}
+		});
+
+		let context = Scanner::builder()
+			.ports(format!("{},{}", grpc_port, rest_port).parse::<PortSpec>().unwrap())
+			.timeout(Duration::from_millis(500))
+			.context(ScanType::Api);
+		let results = ApiPlugin.scan("127.0.0.1", &context).await.unwrap();
+		let mut found: Vec<_> = results.iter().map(|r| (r.port, r.status.as_str(), r.details.as_deref().unwrap())).collect();
+		found.sort();
+		let mut expected = vec![
+			(Some(grpc_port), "open", "gRPC in the clear; health check passes"),
+			(Some(grpc_port), "grpc_reflection", "Reflection is enabled and lists shop.v1.Orders/Get, shop.v1.Orders/List"),
+			(
+				Some(rest_port),
+				"openapi",
+				"OpenAPI 3.0.1 document for \"Pets 1.2\" at /v3/api-docs with endpoints GET /pets, POST /pets, DELETE /pets/{id}",
+			),
+		];
+		expected.sort();
+		assert_eq!(found, expected);
+	}
+}
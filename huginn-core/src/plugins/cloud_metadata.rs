@@ -0,0 +1,389 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Cloud metadata exposure plugin
+//!
+//! Checks whether the web ports of a target pass requests on to the instance metadata service
+//! of their cloud, at `169.254.169.254`, which hands the instance's credentials to whoever asks
+//! from inside it. Each port is asked in three ways:
+//!
+//! - as a forward proxy, with the absolute URL of the service in the request line
+//! - as a reverse proxy that routes by host, with the service as the `Host`
+//! - as a `CONNECT` proxy, with a tunnel to the service on port 80
+//!
+//! Each way asks for the index of the AWS and the Azure metadata, with the headers the Google
+//! Cloud and Azure services require, and nothing else: no credentials are read. A port that
+//! reaches the service is reported as a `metadata_exposed` finding, critical when the service
+//! answers with its metadata and high when it refuses the request, as AWS does without an IMDSv2
+//! session token, since the path to it is there for a request that carries one.
+
+use crate::error::HuginnError;
+use crate::plugins::http::{self, Clients, Response, USER_AGENT};
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::io;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_rustls::TlsConnector;
+
+/// Ports probed when no port spec is configured
+const DEFAULT_PORTS: &[u16] = &[80, 443, 3128, 8000, 8080, 8443, 8888];
+
+/// Address of the instance metadata service
+const METADATA_HOST: &str = "169.254.169.254";
+
+/// Metadata indexes asked for: the AWS one, and the Azure one
+const METADATA_PATHS: &[&str] = &["/latest/meta-data/", "/metadata/instance?api-version=2021-02-01"];
+
+/// Headers the Google Cloud and Azure services require of metadata requests
+const METADATA_HEADERS: &str = "Metadata-Flavor: Google\r\nMetadata: true\r\n";
+
+/// Largest `CONNECT` response head read
+const MAX_HEAD: usize = 8192;
+
+/// Way a port is asked to pass a request on to the metadata service
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Route {
+	/// Absolute URL in the request line
+	Forward,
+	/// Metadata service as the `Host`
+	Host,
+	/// `CONNECT` tunnel
+	Connect,
+}
+
+impl Route {
+	/// What a port that passes the request on does
+	fn describe(self) -> String {
+		match self {
+			Route::Forward => format!("forwards requests for http://{}/ as an open proxy", METADATA_HOST),
+			Route::Host => format!("passes requests with Host {} on as a reverse proxy", METADATA_HOST),
+			Route::Connect => format!("tunnels CONNECT requests to {}:80", METADATA_HOST),
+		}
+	}
+}
+
+/// Cloud whose metadata service answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cloud {
+	Aws,
+	Azure,
+	Gcp,
+}
+
+impl Cloud {
+	fn name(self) -> &'static str {
+		match self {
+			Cloud::Aws => "AWS",
+			Cloud::Azure => "Azure",
+			Cloud::Gcp => "Google Cloud",
+		}
+	}
+}
+
+/// The cloud whose metadata service gave a response, and whether it answered with its
+/// metadata, or `None` when the response is not from one
+fn recognize(response: &Response) -> Option<(Cloud, bool)> {
+	let ok = response.status == 200;
+	// Google's service marks every response, refusals included
+	if response.header("metadata-flavor").is_some_and(|flavor| flavor.eq_ignore_ascii_case("Google")) {
+		return Some((Cloud::Gcp, ok));
+	}
+	let body = String::from_utf8_lossy(&response.body);
+	let aws_index = body.lines().any(|line| matches!(line.trim(), "ami-id" | "instance-id"));
+	if response.header("server") == Some("EC2ws") || (ok && aws_index) {
+		return Some((Cloud::Aws, ok && aws_index));
+	}
+	let azure = serde_json::from_slice::<Value>(&response.body)
+		.ok()
+		.is_some_and(|body| body.get("compute").and_then(|compute| compute.get("vmId")).is_some());
+	(ok && azure).then_some((Cloud::Azure, true))
+}
+
+/// Request for a metadata index, with the request line for a route
+fn request(route: Route, path: &str) -> String {
+	let target = match route {
+		Route::Forward => format!("http://{}{}", METADATA_HOST, path),
+		Route::Host | Route::Connect => path.to_string(),
+	};
+	format!("{}{}Connection: close\r\n\r\n", http::head("GET", METADATA_HOST, 80, false, &target), METADATA_HEADERS)
+}
+
+/// Open a `CONNECT` tunnel to the metadata service over a stream, and send a request through it
+async fn tunnel(mut stream: impl AsyncRead + AsyncWrite + Unpin, request: &str) -> io::Result<Response> {
+	let connect = format!(
+		"CONNECT {0}:80 HTTP/1.1\r\nHost: {0}:80\r\nUser-Agent: {1}\r\n\r\n",
+		METADATA_HOST, USER_AGENT
+	);
+	stream.write_all(connect.as_bytes()).await?;
+	stream.flush().await?;
+	let mut head = Vec::new();
+	while !head.ends_with(b"\r\n\r\n") {
+		if head.len() >= MAX_HEAD || stream.read_u8().await.map(|byte| head.push(byte)).is_err() {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "incomplete CONNECT response"));
+		}
+	}
+	let status = String::from_utf8_lossy(&head).split(' ').nth(1).unwrap_or_default().to_string();
+	if !head.starts_with(b"HTTP/") || !status.starts_with('2') {
+		return Err(io::Error::new(io::ErrorKind::PermissionDenied, "CONNECT refused"));
+	}
+	http::exchange(stream, request).await
+}
+
+/// Ask a port to pass a request for a metadata index on by a route
+async fn ask(
+	target: &str,
+	port: u16,
+	tls: Option<&TlsConnector>,
+	route: Route,
+	path: &str,
+	context: &ScanContext,
+) -> io::Result<Response> {
+	let request = request(route, path);
+	if route != Route::Connect {
+		return http::round_trip(target, port, tls, &request, context).await;
+	}
+	let stream = context.connect(target, port).await?;
+	match tls {
+		Some(connector) => tunnel(connector.connect(http::server_name(target)?, stream).await?, &request).await,
+		None => tunnel(stream, &request).await,
+	}
+}
+
+/// Findings for the routes by which a web port reaches a metadata service
+async fn exposure(target: &str, port: u16, clients: &Clients, context: &ScanContext) -> Vec<ScanResult> {
+	let Some((tls, _)) = http::front_page(target, port, &clients.http1, context).await else {
+		return Vec::new();
+	};
+	let mut results = Vec::new();
+	for route in [Route::Forward, Route::Host, Route::Connect] {
+		let mut reached = None;
+		for path in METADATA_PATHS {
+			if let Some(Ok(response)) = context.probe(|| ask(target, port, tls, route, path, context)).await
+				&& let Some((cloud, answered)) = recognize(&response)
+			{
+				reached = Some((cloud, answered));
+				if answered {
+					break;
+				}
+			}
+		}
+		let Some((cloud, answered)) = reached else {
+			continue;
+		};
+		let (outcome, severity) = if answered {
+			("which answers with its metadata, including the instance's credentials", Severity::Critical)
+		} else {
+			("which refuses the request as sent, but is reachable for one that meets its requirements", Severity::High)
+		};
+		results.push(ScanResult {
+			target: target.to_string(),
+			scan_type: ScanType::CloudMetadata.to_string(),
+			port: Some(port),
+			status: "metadata_exposed".to_string(),
+			severity: Some(severity),
+			service: Some(if tls.is_some() { "https" } else { "http" }.to_string()),
+			version: None,
+			details: Some(format!(
+				"Port {} and reaches the {} instance metadata service, {}",
+				route.describe(),
+				cloud.name(),
+				outcome
+			)),
+			techniques: vec!["T1090".to_string(), "T1552.005".to_string()],
+			screenshot: None,
+			rtt: None,
+		});
+	}
+	results
+}
+
+/// Cloud metadata exposure plugin
+pub struct CloudMetadataPlugin;
+
+#[async_trait]
+impl Plugin for CloudMetadataPlugin {
+	fn name(&self) -> String {
+		"Cloud Metadata Exposure Scanner".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::CloudMetadata
+	}
+
+	fn description(&self) -> String {
+		"Finds web ports that pass requests on to the cloud instance metadata service".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"proxy",
+			"max_connections",
+		]
+	}
+
+	fn proxy_capable(&self) -> bool {
+		true
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		// The front page over both schemes, and each index by each route
+		context.ports_or(DEFAULT_PORTS).len() * (2 + 3 * METADATA_PATHS.len())
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		// Resolve up front to fail early, but connect by name so that virtual hosts answer
+		if context.proxy.is_none() {
+			context.resolve(target).await?;
+		}
+
+		let clients = Arc::new(Clients::new()?);
+		let (target, context) = (Arc::new(target.to_string()), Arc::new(context.clone()));
+		// A probe delay paces the probes one after another
+		let width = if context.scan_delay.is_zero() { context.max_connections } else { 1 };
+		let permits = Arc::new(Semaphore::new(width));
+		let mut probes = JoinSet::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			let (target, context, clients, permits) = (target.clone(), context.clone(), clients.clone(), permits.clone());
+			probes.spawn(async move {
+				let _permit = permits.acquire_owned().await;
+				exposure(&target, port, &clients, &context).await
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(joined) = probes.join_next().await {
+			results.extend(joined.map_err(io::Error::other)?);
+		}
+		results.sort_by_key(|r| r.port);
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ports::PortSpec;
+	use crate::scanner::Scanner;
+	use std::time::Duration;
+	use tokio::net::{TcpListener, TcpStream};
+
+	const AWS_INDEX: &str = "HTTP/1.1 200 OK\r\nServer: EC2ws\r\nContent-Length: 27\r\n\r\nami-id\nhostname\ninstance-id";
+
+	const GCP_REFUSAL: &str = "HTTP/1.1 404 Not Found\r\nMetadata-Flavor: Google\r\nContent-Length: 0\r\n\r\n";
+
+	const PAGE: &str = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+
+	fn response(status: u16, headers: &[(&str, &str)], body: &str) -> Response {
+		Response {
+			version: "HTTP/1.1".to_string(),
+			status,
+			reason: String::new(),
+			headers: headers.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+			body: body.as_bytes().to_vec(),
+		}
+	}
+
+	/// Read a request head
+	async fn head(stream: &mut TcpStream) -> String {
+		let mut head = Vec::new();
+		while !head.ends_with(b"\r\n\r\n") {
+			match stream.read_u8().await {
+				Ok(byte) => head.push(byte),
+				Err(_) => break,
+			}
+		}
+		String::from_utf8_lossy(&head).to_string()
+	}
+
+	/// Serve a web port that answers requests with a head starting with a prefix as given, and
+	/// others with a page of its own
+	async fn serve(prefix: &'static str, answer: &'static str) -> u16 {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Ok((mut stream, _)) = listener.accept().await {
+				let mut request = head(&mut stream).await;
+				if request.starts_with("CONNECT ") {
+					if !prefix.starts_with("CONNECT ") {
+						let _ = stream.write_all(b"HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\n\r\n").await;
+						continue;
+					}
+					let _ = stream.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").await;
+					request = format!("CONNECT {}", head(&mut stream).await);
+				}
+				let reply = if request.starts_with(prefix) { answer } else { PAGE };
+				let _ = stream.write_all(reply.as_bytes()).await;
+			}
+		});
+		port
+	}
+
+	#[test]
+	fn test_recognize() {
+		let aws = response(200, &[("Server", "EC2ws")], "ami-id\ninstance-id\n");
+		assert_eq!(recognize(&aws), Some((Cloud::Aws, true)));
+		assert_eq!(recognize(&response(401, &[("Server", "EC2ws")], "")), Some((Cloud::Aws, false)));
+		let gcp = response(403, &[("Metadata-Flavor", "Google")], "Missing header");
+		assert_eq!(recognize(&gcp), Some((Cloud::Gcp, false)));
+		let azure = response(200, &[], r#"{"compute": {"vmId": "02aab8a4", "location": "westus"}}"#);
+		assert_eq!(recognize(&azure), Some((Cloud::Azure, true)));
+		assert_eq!(recognize(&response(200, &[], "<html>instance-id</html>")), None);
+		assert_eq!(recognize(&response(404, &[], "ami-id")), None);
+
+		assert_eq!(
+			request(Route::Forward, "/latest/meta-data/").lines().next(),
+			Some("GET http://169.254.169.254/latest/meta-data/ HTTP/1.1")
+		);
+		assert!(request(Route::Host, "/latest/meta-data/").contains("\r\nHost: 169.254.169.254\r\n"));
+	}
+
+	#[tokio::test]
+	async fn test_finds_routes() {
+		let forward = serve("GET http://169.254.169.254/latest/meta-data/ ", AWS_INDEX).await;
+		let host = serve("GET /metadata/instance?api-version=2021-02-01 HTTP/1.1\r\nHost: 169.254.169.254\r\n", GCP_REFUSAL).await;
+		let connect = serve("CONNECT GET /latest/meta-data/ ", AWS_INDEX).await;
+		let plain = serve("POST ", PAGE).await;
+
+		let context = Scanner::builder()
+			.ports(format!("{},{},{},{}", forward, host, connect, plain).parse::<PortSpec>().unwrap())
+			.timeout(Duration::from_millis(500))
+			.context(ScanType::CloudMetadata);
+		let results = CloudMetadataPlugin.scan("127.0.0.1", &context).await.unwrap();
+		let mut found: Vec<_> = results.iter().map(|r| (r.port, r.severity, r.details.as_deref().unwrap())).collect();
+		found.sort();
+		let mut expected = vec![
+			(
+				Some(forward),
+				Some(Severity::Critical),
+				"Port forwards requests for http://169.254.169.254/ as an open proxy and reaches the AWS instance metadata service, which answers with its metadata, including the instance's credentials",
+			),
+			(
+				Some(host),
+				Some(Severity::High),
+				"Port passes requests with Host 169.254.169.254 on as a reverse proxy and reaches the Google Cloud instance metadata service, which refuses the request as sent, but is reachable for one that meets its requirements",
+			),
+			(
+				Some(connect),
+				Some(Severity::Critical),
+				"Port tunnels CONNECT requests to 169.254.169.254:80 and reaches the AWS instance metadata service, which answers with its metadata, including the instance's credentials",
+			),
+		];
+		expected.sort();
+		assert_eq!(found, expected);
+	}
+}
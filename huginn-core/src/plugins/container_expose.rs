@@ -0,0 +1,462 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Container API exposure plugin
+//!
+//! Finds the container management APIs on each port and how much of them answers without
+//! credentials, using read-only requests only:
+//!
+//! - the Docker Engine API, by its `/version`, whose `/info` answering means anyone can run a
+//!   privileged container, which is root on the host
+//! - etcd, by its `/version`, whose keys, which in a Kubernetes cluster hold every Secret, are
+//!   counted through the v3 gateway without reading them
+//! - the Kubernetes API server, by its `/version` or its `Status` answer to it, which is asked to
+//!   list namespaces
+//! - the kubelet, by its `Unauthorized` answer or its pod list at `/pods`, where the read-only
+//!   port serves the specs of the node's pods and the authenticated port, if it answers
+//!   anonymously, also runs commands in them
+//!
+//! Each API found is reported as an open port, with the access obtained in its details, and
+//! access without credentials as an `exposed` finding. Daemons that verify client certificates
+//! reject the TLS handshake, so they are not reported.
+
+use crate::error::HuginnError;
+use crate::plugins::http::{self, Clients, Response};
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::io;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_rustls::TlsConnector;
+
+/// Ports probed when no port spec is configured
+const DEFAULT_PORTS: &[u16] = &[2375, 2376, 2379, 6443, 8080, 8443, 10250, 10255];
+
+/// Ports tried over TLS first
+const TLS_PORTS: &[u16] = &[443, 2376, 2379, 6443, 8443, 10250];
+
+/// Range of every etcd key, for the v3 gateway, which counts them without returning any
+const ETCD_COUNT: &str = r#"{"key": "AA==", "range_end": "AA==", "count_only": true}"#;
+
+/// Container API found on a port
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Api {
+	Docker,
+	Etcd,
+	Kubernetes,
+	Kubelet,
+}
+
+impl Api {
+	/// Service name the API is reported as
+	fn service(self) -> &'static str {
+		match self {
+			Api::Docker => "docker",
+			Api::Etcd => "etcd",
+			Api::Kubernetes => "kubernetes",
+			Api::Kubelet => "kubelet",
+		}
+	}
+
+	/// Techniques access to the API without credentials is relevant to
+	fn techniques(self) -> Vec<String> {
+		let techniques: &[&str] = match self {
+			Api::Docker => &["T1610", "T1613"],
+			Api::Etcd => &["T1552.007"],
+			Api::Kubernetes | Api::Kubelet => &["T1552.007", "T1613"],
+		};
+		techniques.iter().map(|t| t.to_string()).collect()
+	}
+}
+
+/// The body of a response, as JSON
+fn json(response: &Response) -> Option<Value> {
+	serde_json::from_slice(&response.body).ok()
+}
+
+/// Text of a JSON member
+fn text<'a>(value: &'a Value, name: &str) -> Option<&'a str> {
+	value.get(name).and_then(Value::as_str)
+}
+
+/// Number of items in a Kubernetes list
+fn items(value: &Value) -> Option<usize> {
+	value.get("items")?.as_array().map(Vec::len)
+}
+
+/// The API and its version, if known, that answered a request for `/version`
+fn identify(response: &Response) -> Option<(Api, Option<String>)> {
+	let body = json(response);
+	if let Some(body) = &body {
+		if let Some(version) = text(body, "ApiVersion").and(text(body, "Version")) {
+			return Some((Api::Docker, Some(version.to_string())));
+		}
+		if let Some(version) = text(body, "etcdserver") {
+			return Some((Api::Etcd, Some(version.to_string())));
+		}
+		if let Some(version) = text(body, "gitVersion") {
+			return Some((Api::Kubernetes, Some(version.to_string())));
+		}
+		// An API server that refuses anonymous requests answers with a Status
+		if text(body, "kind") == Some("Status") && matches!(response.status, 401 | 403) {
+			return Some((Api::Kubernetes, None));
+		}
+	}
+	let unauthorized = response.status == 401 && String::from_utf8_lossy(&response.body).trim() == "Unauthorized";
+	unauthorized.then_some((Api::Kubelet, None))
+}
+
+/// The scheme a web port answers over, and its answer to a request for a path
+async fn first_answer<'a>(
+	target: &str,
+	port: u16,
+	connector: &'a TlsConnector,
+	path: &str,
+	context: &ScanContext,
+) -> Option<(Option<&'a TlsConnector>, Response)> {
+	let schemes = if TLS_PORTS.contains(&port) { [Some(connector), None] } else { [None, Some(connector)] };
+	for tls in schemes {
+		match context.probe(|| http::get(target, port, tls, path, context)).await {
+			Some(Ok(response)) => return Some((tls, response)),
+			Some(Err(e)) if e.kind() == io::ErrorKind::ConnectionRefused => return None,
+			_ => {},
+		}
+	}
+	None
+}
+
+/// GET a path as JSON, if it answers 200 with JSON
+async fn get_json(target: &str, port: u16, tls: Option<&TlsConnector>, path: &str, context: &ScanContext) -> Option<Value> {
+	match context.probe(|| http::get(target, port, tls, path, context)).await {
+		Some(Ok(response)) if response.status == 200 => json(&response),
+		_ => None,
+	}
+}
+
+/// Access to an API without credentials, and its severity, or `None` when it requires them
+async fn access(
+	api: Api,
+	target: &str,
+	port: u16,
+	tls: Option<&TlsConnector>,
+	context: &ScanContext,
+) -> Option<(String, Severity)> {
+	match api {
+		Api::Docker => {
+			let info = get_json(target, port, tls, "/info", context).await?;
+			let count = |name| info.get(name).and_then(Value::as_u64).unwrap_or_default();
+			let host = text(&info, "Name").map(|name| format!(" on host {}", name)).unwrap_or_default();
+			Some((
+				format!(
+					"Docker Engine API answers without authentication, with {} containers and {} images{}; anyone can start a privileged container, which is root on the host",
+					count("Containers"),
+					count("Images"),
+					host
+				),
+				Severity::Critical,
+			))
+		},
+		Api::Etcd => {
+			let range = context.probe(|| http::post_json(target, port, tls, "/v3/kv/range", ETCD_COUNT, context)).await;
+			let Some(Ok(response)) = range else {
+				return None;
+			};
+			let body = json(&response).filter(|body| response.status == 200 && body.get("header").is_some())?;
+			// The gateway writes 64-bit integers as strings, and leaves out zero ones
+			let keys = match body.get("count") {
+				Some(Value::String(count)) => count.parse().unwrap_or_default(),
+				Some(count) => count.as_u64().unwrap_or_default(),
+				None => 0,
+			};
+			Some((
+				format!(
+					"etcd answers without authentication and holds {} keys, readable by anyone; in a Kubernetes cluster they include every Secret",
+					keys
+				),
+				Severity::Critical,
+			))
+		},
+		Api::Kubernetes => {
+			let namespaces = get_json(target, port, tls, "/api/v1/namespaces", context).await?;
+			Some((
+				format!(
+					"Kubernetes API server grants anonymous requests access, and lists {} namespaces to them",
+					items(&namespaces).unwrap_or_default()
+				),
+				Severity::Critical,
+			))
+		},
+		Api::Kubelet => {
+			let pods = items(&get_json(target, port, tls, "/pods", context).await?)?;
+			Some(match tls {
+				Some(_) => (
+					format!(
+						"kubelet API answers anonymous requests and lists {} pods; anyone can run commands in them",
+						pods
+					),
+					Severity::Critical,
+				),
+				None => (
+					format!(
+						"read-only kubelet port lists {} pods with their specs, including environment variables",
+						pods
+					),
+					Severity::High,
+				),
+			})
+		},
+	}
+}
+
+/// Results for the container API on a port, if there is one
+async fn expose(target: &str, port: u16, clients: &Clients, context: &ScanContext) -> Vec<ScanResult> {
+	let Some((tls, response)) = first_answer(target, port, &clients.http1, "/version", context).await else {
+		return Vec::new();
+	};
+	let identified = match identify(&response) {
+		Some(identified) => Some(identified),
+		// The read-only kubelet port has no version to identify it by
+		None if response.status == 404 => get_json(target, port, tls, "/pods", context)
+			.await
+			.filter(|pods| items(pods).is_some())
+			.map(|_| (Api::Kubelet, None)),
+		None => None,
+	};
+	let Some((api, version)) = identified else {
+		return Vec::new();
+	};
+
+	let exposed = access(api, target, port, tls, context).await;
+	let details = match (&exposed, api, response.status) {
+		(Some(_), _, _) => "accessible without credentials",
+		(None, Api::Kubernetes, 403) => "anonymous requests are authenticated but not authorized",
+		(None, Api::Docker | Api::Etcd, _) => "version readable without credentials",
+		(None, _, _) => "credentials required",
+	};
+	let mut results = vec![ScanResult {
+		target: target.to_string(),
+		scan_type: ScanType::ContainerExpose.to_string(),
+		port: Some(port),
+		status: "open".to_string(),
+		severity: None,
+		service: Some(api.service().to_string()),
+		version,
+		details: Some(format!("{} over {}; {}", api.service(), if tls.is_some() { "TLS" } else { "HTTP" }, details)),
+		techniques: vec!["T1613".to_string()],
+		screenshot: None,
+		rtt: None,
+	}];
+	if let Some((details, severity)) = exposed {
+		results.push(ScanResult {
+			target: target.to_string(),
+			scan_type: ScanType::ContainerExpose.to_string(),
+			port: Some(port),
+			status: "exposed".to_string(),
+			severity: Some(severity),
+			service: Some(api.service().to_string()),
+			version: None,
+			details: Some(details),
+			techniques: api.techniques(),
+			screenshot: None,
+			rtt: None,
+		});
+	}
+	results
+}
+
+/// Container API exposure plugin
+pub struct ContainerExposePlugin;
+
+#[async_trait]
+impl Plugin for ContainerExposePlugin {
+	fn name(&self) -> String {
+		"Container API Exposure Scanner".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::ContainerExpose
+	}
+
+	fn description(&self) -> String {
+		"Finds Docker, etcd, Kubernetes API server, and kubelet APIs that answer without credentials".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"proxy",
+			"max_connections",
+		]
+	}
+
+	fn proxy_capable(&self) -> bool {
+		true
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		// The version over both schemes, the pod list, and the access check
+		context.ports_or(DEFAULT_PORTS).len() * 4
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		// Resolve up front to fail early, but connect by name so that virtual hosts answer
+		if context.proxy.is_none() {
+			context.resolve(target).await?;
+		}
+
+		let clients = Arc::new(Clients::new()?);
+		let (target, context) = (Arc::new(target.to_string()), Arc::new(context.clone()));
+		// A probe delay paces the probes one after another
+		let width = if context.scan_delay.is_zero() { context.max_connections } else { 1 };
+		let permits = Arc::new(Semaphore::new(width));
+		let mut probes = JoinSet::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			let (target, context, clients, permits) = (target.clone(), context.clone(), clients.clone(), permits.clone());
+			probes.spawn(async move {
+				let _permit = permits.acquire_owned().await;
+				expose(&target, port, &clients, &context).await
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(joined) = probes.join_next().await {
+			results.extend(joined.map_err(io::Error::other)?);
+		}
+		results.sort_by_key(|r| r.port);
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ports::PortSpec;
+	use crate::scanner::Scanner;
+	use std::time::Duration;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+	use tokio::net::TcpListener;
+
+	/// Serve canned answers over plain HTTP, by request line
+	async fn serve(answers: &'static [(&'static str, &'static str, &'static str)]) -> u16 {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Ok((mut stream, _)) = listener.accept().await {
+				let mut request = vec![0; 4096];
+				let length = stream.read(&mut request).await.unwrap_or_default();
+				let request = String::from_utf8_lossy(&request[..length]).to_string();
+				let (status, body) = answers
+					.iter()
+					.find(|(line, _, _)| request.starts_with(&format!("{} ", line)))
+					.map_or(("404 Not Found", "404 page not found"), |(_, status, body)| (*status, *body));
+				let response = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\n\r\n{}", status, body.len(), body);
+				let _ = stream.write_all(response.as_bytes()).await;
+			}
+		});
+		port
+	}
+
+	fn response(status: u16, body: &str) -> Response {
+		Response {
+			version: "HTTP/1.1".to_string(),
+			status,
+			reason: String::new(),
+			headers: Vec::new(),
+			body: body.as_bytes().to_vec(),
+		}
+	}
+
+	#[test]
+	fn test_identify() {
+		let docker = response(200, r#"{"Version": "27.3.1", "ApiVersion": "1.47", "Os": "linux"}"#);
+		assert_eq!(identify(&docker), Some((Api::Docker, Some("27.3.1".to_string()))));
+		let etcd = response(200, r#"{"etcdserver": "3.5.16", "etcdcluster": "3.5.0"}"#);
+		assert_eq!(identify(&etcd), Some((Api::Etcd, Some("3.5.16".to_string()))));
+		let kubernetes = response(200, r#"{"major": "1", "minor": "31", "gitVersion": "v1.31.2"}"#);
+		assert_eq!(identify(&kubernetes), Some((Api::Kubernetes, Some("v1.31.2".to_string()))));
+		let status = response(401, r#"{"kind": "Status", "status": "Failure", "code": 401}"#);
+		assert_eq!(identify(&status), Some((Api::Kubernetes, None)));
+		assert_eq!(identify(&response(401, "Unauthorized\n")), Some((Api::Kubelet, None)));
+		assert_eq!(identify(&response(401, "Login required")), None);
+		assert_eq!(identify(&response(200, r#"{"version": "1.0"}"#)), None);
+	}
+
+	#[tokio::test]
+	async fn test_finds_exposed_apis() {
+		let docker = serve(&[
+			("GET /version", "200 OK", r#"{"Version": "27.3.1", "ApiVersion": "1.47"}"#),
+			("GET /info", "200 OK", r#"{"Containers": 3, "Images": 7, "Name": "build01"}"#),
+		])
+		.await;
+		let etcd = serve(&[
+			("GET /version", "200 OK", r#"{"etcdserver": "3.5.16", "etcdcluster": "3.5.0"}"#),
+			("POST /v3/kv/range", "200 OK", r#"{"header": {"revision": "9"}, "count": "42"}"#),
+		])
+		.await;
+		let kubernetes = serve(&[(
+			"GET /version",
+			"403 Forbidden",
+			r#"{"kind": "Status", "reason": "Forbidden", "code": 403}"#,
+		)])
+		.await;
+		let kubelet = serve(&[("GET /pods", "200 OK", r#"{"kind": "PodList", "items": [{}, {}]}"#)]).await;
+
+		let context = Scanner::builder()
+			.ports(format!("{},{},{},{}", docker, etcd, kubernetes, kubelet).parse::<PortSpec>().unwrap())
+			.timeout(Duration::from_millis(500))
+			.context(ScanType::ContainerExpose);
+		let results = ContainerExposePlugin.scan("127.0.0.1", &context).await.unwrap();
+		let mut found: Vec<_> = results
+			.iter()
+			.map(|r| (r.port, r.status.as_str(), r.severity, r.version.as_deref(), r.details.as_deref().unwrap()))
+			.collect();
+		found.sort_by_key(|(port, status, ..)| (*port, *status));
+		let mut expected = vec![
+			(Some(docker), "open", None, Some("27.3.1"), "docker over HTTP; accessible without credentials"),
+			(
+				Some(docker),
+				"exposed",
+				Some(Severity::Critical),
+				None,
+				"Docker Engine API answers without authentication, with 3 containers and 7 images on host build01; anyone can start a privileged container, which is root on the host",
+			),
+			(Some(etcd), "open", None, Some("3.5.16"), "etcd over HTTP; accessible without credentials"),
+			(
+				Some(etcd),
+				"exposed",
+				Some(Severity::Critical),
+				None,
+				"etcd answers without authentication and holds 42 keys, readable by anyone; in a Kubernetes cluster they include every Secret",
+			),
+			(
+				Some(kubernetes),
+				"open",
+				None,
+				None,
+				"kubernetes over HTTP; anonymous requests are authenticated but not authorized",
+			),
+			(Some(kubelet), "open", None, None, "kubelet over HTTP; accessible without credentials"),
+			(
+				Some(kubelet),
+				"exposed",
+				Some(Severity::High),
+				None,
+				"read-only kubelet port lists 2 pods with their specs, including environment variables",
+			),
+		];
+		expected.sort_by_key(|(port, status, ..)| (*port, *status));
+		assert_eq!(found, expected);
+	}
+}
@@ -0,0 +1,537 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Content discovery plugin
+//!
+//! Requests the paths in a user-supplied wordlist from each web server on the configured ports
+//! (80, 443, 8000, 8080, and 8443 by default), along with the paths its `robots.txt` disallows,
+//! and asks for the front page under each host name in a second wordlist, to find the content
+//! and virtual hosts a site does not link to. Labels without a dot are taken as subdomains of a
+//! domain target.
+//!
+//! Many servers answer every path with the same page, or redirect every unknown path to a login
+//! form, so a `404` status alone cannot tell what exists. Before the wordlist, each server is
+//! asked for paths, and a host name, that cannot exist; a response counts as a discovery only
+//! when it differs from those in its status, its redirect target, or its page, with the
+//! requested path left out. Short pages must be identical to match, while longer ones need only
+//! a similar length and simhash, so that timestamps and tokens do not set them apart. Virtual
+//! hosts must also differ from the site the target itself serves.
+//!
+//! Requests to each target are spaced to at most
+//! [`ContentDiscoveryConfig::requests_per_second`], and a server that yields more than
+//! [`ContentDiscoveryConfig::max_results`] discoveries is given up on, as it most likely answers
+//! in ways the baseline does not capture. Each discovery is reported as an informational `path`
+//! or `vhost` result. The plugin sends many requests, so it is left out of
+//! [`crate::plugins::builtin`] and runs only when added with its wordlists.
+
+use crate::error::HuginnError;
+use crate::plugins::http::{self, Clients, Response};
+use crate::plugins::tls::random_bytes;
+use crate::plugins::well_known;
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+use async_trait::async_trait;
+use log::warn;
+use serde::Deserialize;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore};
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+use tokio_rustls::TlsConnector;
+
+/// Ports probed when no port spec is configured
+const DEFAULT_PORTS: &[u16] = &[80, 443, 8000, 8080, 8443];
+
+/// ATT&CK technique for probing for content with wordlists
+const TECHNIQUE: &str = "T1595.003";
+
+/// Statuses of responses that never count as discoveries
+const MISSING: &[u16] = &[400, 404, 410, 429];
+
+/// Most simhash bits in which a page may differ from a baseline page and still match it
+const MAX_DISTANCE: u32 = 3;
+
+/// Shortest page, in bytes, compared by similarity rather than exactly
+const MIN_FUZZY_LENGTH: usize = 512;
+
+/// Settings of the `content_discovery` scan type
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContentDiscoveryConfig {
+	/// File of paths to request, one per line
+	pub wordlist: Option<PathBuf>,
+	/// File of host names to request the front page as, one per line; labels without a dot are
+	/// subdomains of a domain target
+	pub vhosts: Option<PathBuf>,
+	/// Also request the paths each site's robots.txt disallows
+	pub robots: bool,
+	/// Most requests per second to each target
+	pub requests_per_second: u32,
+	/// Most discoveries reported for each web server
+	pub max_results: usize,
+}
+
+impl Default for ContentDiscoveryConfig {
+	fn default() -> Self {
+		Self {
+			wordlist: None,
+			vhosts: None,
+			robots: true,
+			requests_per_second: 10,
+			max_results: 100,
+		}
+	}
+}
+
+/// Entries of a wordlist, without blank lines and comments
+fn words(path: &Path) -> Result<Vec<String>, String> {
+	let text =
+		std::fs::read_to_string(path).map_err(|e| format!("Failed to read wordlist {}: {}", path.display(), e))?;
+	Ok(text
+		.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.map(str::to_string)
+		.collect())
+}
+
+/// A wordlist entry as a path, with its leading slash
+fn path(entry: &str) -> String {
+	if entry.starts_with('/') { entry.to_string() } else { format!("/{}", entry) }
+}
+
+/// A wordlist entry as a host name, under a domain target when it is a bare label
+fn host_name(entry: &str, target: &str) -> String {
+	if entry.contains('.') || target.parse::<IpAddr>().is_ok() {
+		entry.to_ascii_lowercase()
+	} else {
+		format!("{}.{}", entry, target.trim_end_matches('.')).to_ascii_lowercase()
+	}
+}
+
+/// A random name that no site has
+fn nonce() -> String {
+	let token: String = random_bytes(6).iter().map(|byte| format!("{:02x}", byte)).collect();
+	format!("huginn-{}", token)
+}
+
+/// Spaces requests at least an interval apart
+struct Pacer {
+	interval: Duration,
+	next: Mutex<Instant>,
+}
+
+impl Pacer {
+	fn new(requests_per_second: u32) -> Self {
+		Self {
+			interval: Duration::from_secs(1) / requests_per_second.max(1),
+			next: Mutex::new(Instant::now()),
+		}
+	}
+
+	/// Wait for the next request's turn
+	async fn wait(&self) {
+		let at = {
+			let mut next = self.next.lock().await;
+			let at = (*next).max(Instant::now());
+			*next = at + self.interval;
+			at
+		};
+		tokio::time::sleep_until(at).await;
+	}
+}
+
+/// What sets a response apart, with the path or host name it was asked for left out
+#[derive(Debug, Clone, PartialEq)]
+struct Signature {
+	status: u16,
+	location: Option<String>,
+	length: usize,
+	digest: u64,
+	simhash: u64,
+}
+
+impl Signature {
+	fn new(response: &Response, asked: &str) -> Self {
+		let page = String::from_utf8_lossy(&response.body).replace(asked, "");
+		Self {
+			status: response.status,
+			location: response.header("location").map(|location| location.replace(asked, "")),
+			length: page.len(),
+			digest: {
+				let mut hasher = DefaultHasher::new();
+				page.hash(&mut hasher);
+				hasher.finish()
+			},
+			simhash: http::simhash(&page),
+		}
+	}
+
+	/// Whether two responses are the same page, give or take what varies between requests, such
+	/// as timestamps and tokens
+	fn matches(&self, other: &Signature) -> bool {
+		// A simhash only tells pages with enough words apart, so short pages must be identical
+		let similar = self.length.min(other.length) >= MIN_FUZZY_LENGTH
+			&& self.length.abs_diff(other.length) <= self.length.max(other.length) / 20
+			&& (self.simhash ^ other.simhash).count_ones() <= MAX_DISTANCE;
+		self.status == other.status && self.location == other.location && (self.digest == other.digest || similar)
+	}
+}
+
+/// Whether a response is a discovery, rather than a miss or one of the baseline's pages
+fn discovered(signature: &Signature, baseline: &[Signature]) -> bool {
+	!MISSING.contains(&signature.status)
+		&& signature.status < 500
+		&& !baseline.iter().any(|known| known.matches(signature))
+}
+
+/// Details of a discovered response
+fn describe(asked: &str, response: &Response) -> String {
+	let mut details = vec![format!("{} {}", response.status, response.reason).trim_end().to_string()];
+	if let Some(location) = response.header("location").filter(|_| (300..400).contains(&response.status)) {
+		details.push(format!("redirects to {}", location));
+	}
+	details.push(format!("{} bytes", response.body.len()));
+	if let Some(title) = http::title(&String::from_utf8_lossy(&response.body)) {
+		details.push(format!("title {:?}", title));
+	}
+	format!("{}: {}", asked, details.join(", "))
+}
+
+/// GET the front page of a web port as a virtual host
+async fn get_as(
+	target: &str,
+	port: u16,
+	tls: Option<&TlsConnector>,
+	host: &str,
+	context: &ScanContext,
+) -> io::Result<Response> {
+	let stream = context.connect(target, port).await?;
+	let request = format!("{}Connection: close\r\n\r\n", http::head("GET", host, port, tls.is_some(), "/"));
+	match tls {
+		Some(connector) => http::exchange(connector.connect(http::server_name(host)?, stream).await?, &request).await,
+		None => http::exchange(stream, &request).await,
+	}
+}
+
+/// What one web server is asked for
+struct Words {
+	paths: Vec<String>,
+	vhosts: Vec<String>,
+}
+
+/// Discoveries on the web server on a port, if one answers
+async fn discover(
+	config: &ContentDiscoveryConfig,
+	words: &Words,
+	target: &str,
+	port: u16,
+	clients: &Clients,
+	pacer: &Pacer,
+	context: &ScanContext,
+) -> Vec<ScanResult> {
+	pacer.wait().await;
+	let Some((tls, front)) = http::front_page(target, port, &clients.http1, context).await else {
+		return Vec::new();
+	};
+	let fetch = async |path: &str| {
+		pacer.wait().await;
+		match context.probe(|| http::get(target, port, tls, path, context)).await {
+			Some(Ok(response)) => Some(response),
+			_ => None,
+		}
+	};
+	let fetch_as = async |host: &str| {
+		pacer.wait().await;
+		match context.probe(|| get_as(target, port, tls, host, context)).await {
+			Some(Ok(response)) => Some(response),
+			_ => None,
+		}
+	};
+
+	let mut paths = words.paths.clone();
+	if config.robots
+		&& let Some(robots) = fetch("/robots.txt").await.filter(|response| response.status == 200)
+	{
+		let robots = String::from_utf8_lossy(&robots.body);
+		for disallowed in well_known::disallowed(&robots) {
+			// Patterns name no single path
+			if !disallowed.contains(['*', '$']) && !paths.iter().any(|known| known == disallowed) {
+				paths.push(disallowed.to_string());
+			}
+		}
+	}
+
+	let mut path_baseline = Vec::new();
+	for suffix in ["", "/", ".php"] {
+		let missing = format!("/{}{}", nonce(), suffix);
+		if let Some(response) = fetch(&missing).await {
+			path_baseline.push(Signature::new(&response, &missing));
+		}
+	}
+	let mut host_baseline = vec![Signature::new(&front, target)];
+	if !words.vhosts.is_empty() {
+		let missing = host_name(&nonce(), target);
+		if let Some(response) = fetch_as(&missing).await {
+			host_baseline.push(Signature::new(&response, &missing));
+		}
+	}
+
+	let scheme = if tls.is_some() { "https" } else { "http" };
+	let result = |status: &str, details: String| ScanResult {
+		target: target.to_string(),
+		scan_type: ScanType::ContentDiscovery.to_string(),
+		port: Some(port),
+		status: status.to_string(),
+		severity: Some(Severity::Info),
+		service: Some(scheme.to_string()),
+		version: None,
+		details: Some(details),
+		techniques: vec![TECHNIQUE.to_string()],
+		screenshot: None,
+		rtt: None,
+	};
+	let mut results = Vec::new();
+	let asks = paths.iter().map(|path| (false, path.as_str())).chain(words.vhosts.iter().map(|host| (true, host.as_str())));
+	for (vhost, asked) in asks {
+		let (response, baseline) = if vhost {
+			(fetch_as(asked).await, &host_baseline)
+		} else {
+			(fetch(asked).await, &path_baseline)
+		};
+		let Some(response) = response else {
+			continue;
+		};
+		if !discovered(&Signature::new(&response, asked), baseline) {
+			continue;
+		}
+		if results.len() == config.max_results {
+			warn!(
+				"Stopped content discovery on {}:{} after {} discoveries; the server likely answers every request alike",
+				target, port, config.max_results
+			);
+			break;
+		}
+		results.push(result(if vhost { "vhost" } else { "path" }, describe(asked, &response)));
+	}
+	results
+}
+
+/// Wordlist-driven content and virtual host discovery plugin
+pub struct ContentDiscoveryPlugin {
+	config: ContentDiscoveryConfig,
+	/// Entries of the path and virtual host wordlists, or why they could not be read
+	lists: Result<(Vec<String>, Vec<String>), String>,
+}
+
+impl Default for ContentDiscoveryPlugin {
+	fn default() -> Self {
+		Self::new(ContentDiscoveryConfig::default())
+	}
+}
+
+impl ContentDiscoveryPlugin {
+	/// Create the plugin with its settings, reading its wordlists once for every target
+	pub fn new(config: ContentDiscoveryConfig) -> Self {
+		let read = |list: &Option<PathBuf>| list.as_deref().map(words).transpose().map(Option::unwrap_or_default);
+		let lists = read(&config.wordlist).and_then(|paths| Ok((paths, read(&config.vhosts)?)));
+		Self { config, lists }
+	}
+
+	/// The paths and host names to ask a target's web servers for
+	fn words(&self, target: &str) -> Result<Words, HuginnError> {
+		let (paths, vhosts) = self.lists.as_ref().map_err(|e| HuginnError::Config(e.clone()))?;
+		let mut paths: Vec<String> = paths.iter().map(|entry| path(entry)).collect();
+		let mut vhosts: Vec<String> = vhosts.iter().map(|entry| host_name(entry, target)).collect();
+		for list in [&mut paths, &mut vhosts] {
+			let mut seen = std::collections::HashSet::new();
+			list.retain(|entry| seen.insert(entry.clone()));
+		}
+		Ok(Words { paths, vhosts })
+	}
+}
+
+#[async_trait]
+impl Plugin for ContentDiscoveryPlugin {
+	fn name(&self) -> String {
+		"Content Discoverer".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::ContentDiscovery
+	}
+
+	fn description(&self) -> String {
+		"Finds unlinked paths and virtual hosts on web servers from wordlists, telling real pages from catch-all ones"
+			.to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"content_discovery",
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"proxy",
+		]
+	}
+
+	fn proxy_capable(&self) -> bool {
+		true
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		// The front page, robots.txt, the baseline, and every word
+		let words = self.words("example.com").map_or(0, |words| words.paths.len() + words.vhosts.len());
+		context.ports_or(DEFAULT_PORTS).len() * (6 + words)
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		let words = Arc::new(self.words(target)?);
+		if words.paths.is_empty() && words.vhosts.is_empty() {
+			return Err(HuginnError::Config(
+				"content_discovery needs a wordlist of paths or virtual hosts".to_string(),
+			));
+		}
+		// Resolve up front to fail early, but connect by name so that virtual hosts answer
+		if context.proxy.is_none() {
+			context.resolve(target).await?;
+		}
+
+		let config = Arc::new(self.config.clone());
+		let clients = Arc::new(Clients::new()?);
+		let pacer = Arc::new(Pacer::new(self.config.requests_per_second));
+		let (target, context) = (Arc::new(target.to_string()), Arc::new(context.clone()));
+		let permits = Arc::new(Semaphore::new(context.max_connections));
+		let mut probes = JoinSet::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			let (config, words, clients, pacer) = (config.clone(), words.clone(), clients.clone(), pacer.clone());
+			let (target, context, permits) = (target.clone(), context.clone(), permits.clone());
+			probes.spawn(async move {
+				let _permit = permits.acquire_owned().await;
+				discover(&config, &words, &target, port, &clients, &pacer, &context).await
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(joined) = probes.join_next().await {
+			results.extend(joined.map_err(io::Error::other)?);
+		}
+		results.sort_by_key(|r| r.port);
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ports::PortSpec;
+	use crate::scanner::Scanner;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+	use tokio::net::TcpListener;
+
+	#[test]
+	fn test_signatures() {
+		let response = |status, location: Option<&str>, body: &str| Response {
+			version: "HTTP/1.1".to_string(),
+			status,
+			reason: String::new(),
+			headers: location.map(|location| ("Location".to_string(), location.to_string())).into_iter().collect(),
+			body: body.as_bytes().to_vec(),
+		};
+		// A catch-all page that echoes the path matches whatever path is asked for
+		let echo = |path: &str| response(200, None, &format!("<h1>Sorry, {} was not found on this server</h1>", path));
+		let baseline = [Signature::new(&echo("/huginn-0011"), "/huginn-0011")];
+		assert!(!discovered(&Signature::new(&echo("/a/much/longer/path"), "/a/much/longer/path"), &baseline));
+		assert!(discovered(&Signature::new(&response(200, None, &"x".repeat(4096)), "/backup.zip"), &baseline));
+		assert!(!discovered(&Signature::new(&response(404, None, ""), "/admin"), &[]));
+
+		// Longer pages that differ only in a timestamp are the same page
+		let dated = |stamp: &str| response(200, None, &format!("<p>{}</p>{}", stamp, "<p>Welcome to the portal</p>".repeat(40)));
+		assert!(!discovered(&Signature::new(&dated("12:00:01"), "/x"), &[Signature::new(&dated("12:00:02"), "/y")]));
+
+		// So does a redirect of every unknown path to a login page that names it
+		let login = |path: &str| response(302, Some(&format!("/login?next={}", path)), "");
+		let baseline = [Signature::new(&login("/huginn-0011"), "/huginn-0011")];
+		assert!(!discovered(&Signature::new(&login("/admin"), "/admin"), &baseline));
+		assert!(discovered(&Signature::new(&response(302, Some("/admin/"), ""), "/admin"), &baseline));
+
+		assert_eq!(host_name("dev", "example.com."), "dev.example.com");
+		assert_eq!(host_name("Intranet.Corp", "10.0.0.1"), "intranet.corp");
+		assert_eq!(path("admin/"), "/admin/");
+	}
+
+	#[tokio::test]
+	async fn test_discovers_content() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Ok((mut stream, _)) = listener.accept().await {
+				let mut request = vec![0; 4096];
+				let length = stream.read(&mut request).await.unwrap_or_default();
+				let request = String::from_utf8_lossy(&request[..length]).into_owned();
+				let path = request.split_whitespace().nth(1).unwrap_or_default().to_string();
+				let body = if request.contains("\r\nHost: dev.example.test") {
+					"<title>Staging</title>".to_string()
+				} else if path == "/" {
+					"<title>Home</title>".to_string()
+				} else if path == "/robots.txt" {
+					"User-agent: *\nDisallow: /private/\nDisallow: /*.bak$\n".to_string()
+				} else if path == "/admin/" || path == "/private/" {
+					format!("<title>Restricted</title>{}", "x".repeat(500))
+				} else {
+					// Every other path gets a soft 404
+					format!("<p>{} is not here</p>", path)
+				};
+				let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+				let _ = stream.write_all(response.as_bytes()).await;
+			}
+		});
+
+		let directory = std::env::temp_dir();
+		let (wordlist, vhosts) = (
+			directory.join(format!("huginn-paths-{}", std::process::id())),
+			directory.join(format!("huginn-vhosts-{}", std::process::id())),
+		);
+		std::fs::write(&wordlist, "# common paths\nadmin/\nold-site\n").unwrap();
+		std::fs::write(&vhosts, "dev.example.test\nwww.example.test\n").unwrap();
+		let plugin = ContentDiscoveryPlugin::new(ContentDiscoveryConfig {
+			wordlist: Some(wordlist.clone()),
+			vhosts: Some(vhosts.clone()),
+			requests_per_second: 1000,
+			..ContentDiscoveryConfig::default()
+		});
+		let context = Scanner::builder()
+			.ports(port.to_string().parse::<PortSpec>().unwrap())
+			.timeout(Duration::from_secs(2))
+			.context(ScanType::ContentDiscovery);
+
+		let results = plugin.scan("127.0.0.1", &context).await.unwrap();
+		let found: Vec<_> = results.iter().map(|r| (r.status.as_str(), r.details.as_deref().unwrap())).collect();
+		assert_eq!(
+			found,
+			[
+				("path", "/admin/: 200 OK, 525 bytes, title \"Restricted\""),
+				("path", "/private/: 200 OK, 525 bytes, title \"Restricted\""),
+				("vhost", "dev.example.test: 200 OK, 22 bytes, title \"Staging\""),
+			]
+		);
+		std::fs::remove_file(&wordlist).unwrap();
+		std::fs::remove_file(&vhosts).unwrap();
+
+		// The wordlists were read when the plugin was created
+		assert_eq!(plugin.words("127.0.0.1").unwrap().paths, ["/admin/", "/old-site"]);
+	}
+}
@@ -0,0 +1,517 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Default credential check plugin
+//!
+//! Tries a small curated list of vendor default credentials against the admin interfaces it
+//! finds, chosen by port:
+//!
+//! - SNMP on 161, over UDP, by a `GetRequest` for `sysDescr.0` with each default community
+//! - telnet on 23 and 2323, by logging in at the login prompt the server opens with
+//! - HTTP basic authentication on every other port, when the front page asks for it
+//!
+//! Unlike every other scan type, this one tries to log in, which can lock accounts and raises
+//! alerts, so it is opt-in twice over: it is left out of [`crate::plugins::builtin`], and it
+//! refuses to scan until [`DefaultCredsConfig::authorized`] confirms that the operator is
+//! authorized to try logins on the targets. At most `max_attempts` logins are tried per protocol
+//! on each port, the most common defaults first, and none after one succeeds.
+//!
+//! Each interface found is reported as open, and each default that works as a
+//! `default_credentials` finding, critical for logins and high for SNMP communities.
+
+use crate::error::HuginnError;
+use crate::plugins::http::{self, Clients, Response};
+use crate::plugins::snmp;
+use crate::plugins::tls::random_bytes;
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Deserialize;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_rustls::TlsConnector;
+
+/// Ports probed when no port spec is configured
+const DEFAULT_PORTS: &[u16] = &[23, 80, 161, 443, 2323, 8080, 8443];
+
+/// Logins tried per protocol on each port when not configured
+const MAX_ATTEMPTS: usize = 3;
+
+/// Largest answer read
+const MAX_ANSWER: usize = 8192;
+
+/// Telnet's interpret-as-command byte, which starts every negotiation
+const IAC: u8 = 0xff;
+
+/// Vendor defaults, the most common first, as user and password; SNMP communities have no user
+const CREDENTIALS: &[(Protocol, &str, &str)] = &[
+	(Protocol::Snmp, "", "public"),
+	(Protocol::Snmp, "", "private"),
+	(Protocol::Snmp, "", "cisco"),
+	(Protocol::Telnet, "admin", "admin"),
+	(Protocol::Telnet, "root", ""),
+	(Protocol::Telnet, "root", "root"),
+	(Protocol::Telnet, "admin", "password"),
+	(Protocol::Telnet, "support", "support"),
+	(Protocol::Telnet, "cisco", "cisco"),
+	(Protocol::Http, "admin", "admin"),
+	(Protocol::Http, "admin", ""),
+	(Protocol::Http, "admin", "password"),
+	(Protocol::Http, "root", "root"),
+	(Protocol::Http, "admin", "1234"),
+	(Protocol::Http, "tomcat", "tomcat"),
+	(Protocol::Http, "ubnt", "ubnt"),
+];
+
+/// Settings of the `default_creds` scan type
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DefaultCredsConfig {
+	/// Confirms that the operator is authorized to try logins on every target; nothing is tried
+	/// without it
+	pub authorized: bool,
+	/// Most logins tried per protocol on each port
+	pub max_attempts: usize,
+}
+
+impl Default for DefaultCredsConfig {
+	fn default() -> Self {
+		Self {
+			authorized: false,
+			max_attempts: MAX_ATTEMPTS,
+		}
+	}
+}
+
+/// Protocol logins are tried over on a port
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Protocol {
+	Http,
+	Telnet,
+	Snmp,
+}
+
+impl Protocol {
+	/// Protocol tried on a port
+	fn on_port(port: u16) -> Protocol {
+		match port {
+			23 | 2323 => Protocol::Telnet,
+			161 => Protocol::Snmp,
+			_ => Protocol::Http,
+		}
+	}
+}
+
+/// A password as it is reported
+fn describe_password(password: &str) -> String {
+	if password.is_empty() { "an empty password".to_string() } else { format!("the password {:?}", password) }
+}
+
+/// Realm of a response that asks for HTTP basic authentication, or `None` when it does not
+fn basic_realm(response: &Response) -> Option<String> {
+	if response.status != 401 {
+		return None;
+	}
+	response
+		.headers
+		.iter()
+		.filter(|(name, _)| name.eq_ignore_ascii_case("www-authenticate"))
+		.map(|(_, challenge)| challenge.trim())
+		.find(|challenge| challenge.get(..5).is_some_and(|scheme| scheme.eq_ignore_ascii_case("basic")))
+		.map(|challenge| {
+			let lower = challenge.to_ascii_lowercase();
+			let realm = lower.find("realm=\"").map(|start| &challenge[start + 7..]);
+			realm.and_then(|realm| realm.split('"').next()).unwrap_or_default().to_string()
+		})
+}
+
+/// Whether a port's HTTP basic authentication accepts a login
+async fn basic_login(
+	target: &str,
+	port: u16,
+	tls: Option<&TlsConnector>,
+	user: &str,
+	password: &str,
+	context: &ScanContext,
+) -> io::Result<bool> {
+	let headers = format!(
+		"Authorization: Basic {}\r\nConnection: close\r\n",
+		STANDARD.encode(format!("{}:{}", user, password))
+	);
+	let response = http::send(target, port, tls, "/", &headers, context).await?;
+	Ok((200..400).contains(&response.status))
+}
+
+/// Text of a telnet server's output, with negotiations left out, and the answer that refuses
+/// every option it offers or asks for
+fn negotiate(data: &[u8]) -> (String, Vec<u8>) {
+	let (mut text, mut refusals) = (Vec::new(), Vec::new());
+	let mut position = 0;
+	while position < data.len() {
+		match (data[position], data.get(position + 1), data.get(position + 2)) {
+			// Subnegotiations run to IAC SE
+			(IAC, Some(250), _) => {
+				let end = data[position..].windows(2).position(|pair| pair == [IAC, 240]);
+				position = end.map_or(data.len(), |end| position + end + 2);
+			},
+			// WILL is refused with DONT, and DO with WONT
+			(IAC, Some(251), Some(&option)) => {
+				refusals.extend_from_slice(&[IAC, 254, option]);
+				position += 3;
+			},
+			(IAC, Some(253), Some(&option)) => {
+				refusals.extend_from_slice(&[IAC, 252, option]);
+				position += 3;
+			},
+			(IAC, Some(252 | 254), _) => position += 3,
+			(IAC, Some(&IAC), _) => {
+				text.push(IAC);
+				position += 2;
+			},
+			(IAC, _, _) => position += 2,
+			(byte, _, _) => {
+				text.push(byte);
+				position += 1;
+			},
+		}
+	}
+	(String::from_utf8_lossy(&text).into_owned(), refusals)
+}
+
+/// Last line of a server's output that is not blank, in lower case
+fn last_line(text: &str) -> String {
+	text.lines().rev().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default().to_ascii_lowercase()
+}
+
+/// Whether a server's output ends by asking for a user name
+fn asks_user(text: &str) -> bool {
+	let line = last_line(text);
+	["login:", "username:", "user:"].iter().any(|prompt| line.ends_with(prompt))
+}
+
+/// Whether a server's output ends by asking for a password
+fn asks_password(text: &str) -> bool {
+	last_line(text).ends_with("password:")
+}
+
+/// Whether the output after a password shows the login accepted, or `None` until it shows
+/// either way
+fn accepted(text: &str) -> Option<bool> {
+	let lower = text.to_ascii_lowercase();
+	let refused = ["incorrect", "failed", "invalid", "denied"].iter().any(|word| lower.contains(word));
+	if refused || asks_user(text) || asks_password(text) {
+		return Some(false);
+	}
+	let line = last_line(text);
+	['#', '$', '>', '%'].iter().any(|&shell| line.ends_with(shell)).then_some(true)
+}
+
+/// Read a telnet server's output until it is done, refusing the options it negotiates
+async fn expect(
+	stream: &mut (impl AsyncRead + AsyncWrite + Unpin),
+	done: impl Fn(&str) -> bool,
+) -> io::Result<String> {
+	let (mut text, mut buffer) = (String::new(), vec![0; MAX_ANSWER]);
+	while !done(&text) {
+		let length = stream.read(&mut buffer).await?;
+		if length == 0 || text.len() >= MAX_ANSWER {
+			return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "telnet server stopped before it was done"));
+		}
+		let (more, refusals) = negotiate(&buffer[..length]);
+		if !refusals.is_empty() {
+			stream.write_all(&refusals).await?;
+		}
+		text.push_str(&more);
+	}
+	Ok(text)
+}
+
+/// Whether a telnet server accepts a login
+async fn telnet_login(mut stream: impl AsyncRead + AsyncWrite + Unpin, user: &str, password: &str) -> io::Result<bool> {
+	let prompt = expect(&mut stream, |text| asks_user(text) || asks_password(text)).await?;
+	// Some devices only ask for a password
+	if !asks_password(&prompt) {
+		stream.write_all(format!("{}\r\n", user).as_bytes()).await?;
+		expect(&mut stream, asks_password).await?;
+	}
+	stream.write_all(format!("{}\r\n", password).as_bytes()).await?;
+	let answer = expect(&mut stream, |text| accepted(text).is_some()).await?;
+	Ok(accepted(&answer) == Some(true))
+}
+
+/// The system description an SNMP agent answers a community with
+async fn snmp_get(target: &str, port: u16, community: &str, context: &ScanContext) -> io::Result<String> {
+	let address = SocketAddr::new(context.resolve(target).await.map_err(io::Error::other)?, port);
+	let socket = context.transport.udp(address, context).await?;
+	let id = random_bytes(4);
+	let id = i32::from_be_bytes([id[0] & 0x7f, id[1], id[2], id[3]]);
+	socket.send_to(&snmp::get_request(community, id, snmp::SYS_DESCR), address).await?;
+	let mut buffer = vec![0; MAX_ANSWER];
+	loop {
+		let (length, from) = socket.recv_from(&mut buffer).await?;
+		if from == address
+			&& let Some(binding) = snmp::response_value(&buffer[..length], id)
+		{
+			return Ok(String::from_utf8_lossy(&binding.value).split_whitespace().collect::<Vec<_>>().join(" "));
+		}
+	}
+}
+
+/// Results for the admin interface on a port, and the defaults it accepts, if there is one
+async fn inspect(
+	target: &str,
+	port: u16,
+	protocol: Protocol,
+	max_attempts: usize,
+	clients: &Clients,
+	context: &ScanContext,
+) -> Vec<ScanResult> {
+	let defaults = CREDENTIALS.iter().filter(|(p, ..)| *p == protocol).map(|&(_, user, password)| (user, password)).take(max_attempts);
+	let result = |status: &str, severity, service: &str, details: String| ScanResult {
+		target: target.to_string(),
+		scan_type: ScanType::DefaultCreds.to_string(),
+		port: Some(port),
+		status: status.to_string(),
+		severity,
+		service: Some(service.to_string()),
+		version: None,
+		details: Some(details),
+		techniques: if severity.is_some() { vec!["T1078.001".to_string()] } else { Vec::new() },
+		screenshot: None,
+		rtt: None,
+	};
+
+	match protocol {
+		Protocol::Snmp if context.proxy.is_some() => Vec::new(),
+		Protocol::Snmp => {
+			for (_, community) in defaults {
+				if let Some(Ok(description)) = context.probe(|| snmp_get(target, port, community, context)).await {
+					return vec![
+						result("open", None, "snmp", format!("SNMP agent: {}", description)),
+						result(
+							"default_credentials",
+							Some(Severity::High),
+							"snmp",
+							format!("SNMP agent answers the vendor default community {:?}", community),
+						),
+					];
+				}
+			}
+			Vec::new()
+		},
+		Protocol::Telnet => {
+			let mut results = Vec::new();
+			for (user, password) in defaults {
+				let login = || async { telnet_login(context.connect(target, port).await?, user, password).await };
+				let Some(Ok(accepted)) = context.probe(login).await else {
+					continue;
+				};
+				if results.is_empty() {
+					results.push(result("open", None, "telnet", "telnet login prompt".to_string()));
+				}
+				if accepted {
+					results.push(result(
+						"default_credentials",
+						Some(Severity::Critical),
+						"telnet",
+						format!("telnet accepts the vendor default login {:?} with {}", user, describe_password(password)),
+					));
+					break;
+				}
+			}
+			results
+		},
+		Protocol::Http => {
+			let Some((tls, page)) = http::front_page(target, port, &clients.http1, context).await else {
+				return Vec::new();
+			};
+			let Some(realm) = basic_realm(&page) else {
+				return Vec::new();
+			};
+			let service = if tls.is_some() { "https" } else { "http" };
+			let mut results = vec![result(
+				"open",
+				None,
+				service,
+				format!("admin interface asks for HTTP basic authentication for the realm {:?}", realm),
+			)];
+			for (user, password) in defaults {
+				if let Some(Ok(true)) = context.probe(|| basic_login(target, port, tls, user, password, context)).await {
+					results.push(result(
+						"default_credentials",
+						Some(Severity::Critical),
+						service,
+						format!(
+							"HTTP basic authentication accepts the vendor default login {:?} with {}",
+							user,
+							describe_password(password)
+						),
+					));
+					break;
+				}
+			}
+			results
+		},
+	}
+}
+
+/// Default credential check plugin
+#[derive(Default)]
+pub struct DefaultCredsPlugin {
+	config: DefaultCredsConfig,
+}
+
+impl DefaultCredsPlugin {
+	/// A plugin with the given settings
+	pub fn new(config: DefaultCredsConfig) -> Self {
+		Self { config }
+	}
+}
+
+#[async_trait]
+impl Plugin for DefaultCredsPlugin {
+	fn name(&self) -> String {
+		"Default Credential Checker".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::DefaultCreds
+	}
+
+	fn description(&self) -> String {
+		"Tries vendor default logins on HTTP basic, telnet, and SNMP admin interfaces (opt-in)".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"default_creds",
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"proxy",
+			"max_connections",
+		]
+	}
+
+	fn proxy_capable(&self) -> bool {
+		true
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		context.ports_or(DEFAULT_PORTS).len() * (self.config.max_attempts + 1)
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		if !self.config.authorized {
+			return Err(HuginnError::Config(
+				"default_creds tries logins on its targets; set authorized = true in the default_creds section once you are authorized to"
+					.to_string(),
+			));
+		}
+		// Resolve up front to fail early, but connect by name so that virtual hosts answer
+		if context.proxy.is_none() {
+			context.resolve(target).await?;
+		}
+
+		let clients = Arc::new(Clients::new()?);
+		let (target, context) = (Arc::new(target.to_string()), Arc::new(context.clone()));
+		let max_attempts = self.config.max_attempts;
+		// A probe delay paces the probes one after another
+		let width = if context.scan_delay.is_zero() { context.max_connections } else { 1 };
+		let permits = Arc::new(Semaphore::new(width));
+		let mut probes = JoinSet::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			let (target, context, clients, permits) = (target.clone(), context.clone(), clients.clone(), permits.clone());
+			probes.spawn(async move {
+				let _permit = permits.acquire_owned().await;
+				inspect(&target, port, Protocol::on_port(port), max_attempts, &clients, &context).await
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(joined) = probes.join_next().await {
+			results.extend(joined.map_err(io::Error::other)?);
+		}
+		results.sort_by_key(|r| r.port);
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scanner::Scanner;
+	use std::time::Duration;
+	use tokio::io::{AsyncBufReadExt, BufReader};
+	use tokio::net::TcpListener;
+
+	#[test]
+	fn test_telnet_output() {
+		let (text, refusals) = negotiate(b"\xff\xfd\x18\xff\xfb\x01\xff\xfa\x18\x01\xff\xf0\r\nRouter login: ");
+		assert_eq!(text, "\r\nRouter login: ");
+		assert_eq!(refusals, [IAC, 252, 0x18, IAC, 254, 0x01]);
+		assert!(asks_user(&text));
+		assert!(asks_password("\r\nPassword: "));
+
+		assert_eq!(accepted("\r\n\r\nBusyBox v1.31.1 built-in shell (ash)\r\n# "), Some(true));
+		assert_eq!(accepted("\r\nLogin incorrect\r\n"), Some(false));
+		assert_eq!(accepted("\r\nRouter login: "), Some(false));
+		assert_eq!(accepted("\r\nLast login: Mon"), None);
+	}
+
+	#[test]
+	fn test_basic_realm() {
+		let page = |head: &str| http::parse(format!("{}\r\nContent-Length: 0\r\n\r\n", head).as_bytes(), true).unwrap();
+		let challenge = page("HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Basic realm=\"NETGEAR DGN2200\"");
+		assert_eq!(basic_realm(&challenge).as_deref(), Some("NETGEAR DGN2200"));
+		assert_eq!(basic_realm(&page("HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Digest realm=\"x\"")), None);
+		assert_eq!(basic_realm(&page("HTTP/1.1 200 OK")), None);
+	}
+
+	#[tokio::test]
+	async fn test_finds_defaults() {
+		let context = Scanner::builder().timeout(Duration::from_millis(500)).retries(0).context(ScanType::DefaultCreds);
+		let clients = Clients::new().unwrap();
+
+		// A telnet server that lets root in without a password
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Ok((stream, _)) = listener.accept().await {
+				let mut stream = BufReader::new(stream);
+				let (mut user, mut password) = (String::new(), String::new());
+				stream.write_all(b"\r\nRouter login: ").await.unwrap();
+				stream.read_line(&mut user).await.unwrap();
+				stream.write_all(b"Password: ").await.unwrap();
+				stream.read_line(&mut password).await.unwrap();
+				let answer: &[u8] = if user.trim() == "root" && password.trim().is_empty() { b"\r\n# " } else { b"\r\nLogin incorrect\r\n" };
+				stream.write_all(answer).await.unwrap();
+			}
+		});
+		let results = inspect("127.0.0.1", port, Protocol::Telnet, 3, &clients, &context).await;
+		let statuses: Vec<_> = results.iter().map(|r| (r.status.as_str(), r.severity)).collect();
+		assert_eq!(statuses, [("open", None), ("default_credentials", Some(Severity::Critical))]);
+		assert_eq!(
+			results[1].details.as_deref(),
+			Some("telnet accepts the vendor default login \"root\" with an empty password")
+		);
+		assert_eq!(results[1].techniques, ["T1078.001"]);
+
+		// The attempts stop at the limit
+		let results = inspect("127.0.0.1", port, Protocol::Telnet, 1, &clients, &context).await;
+		assert_eq!(results.iter().map(|r| r.status.as_str()).collect::<Vec<_>>(), ["open"]);
+	}
+}
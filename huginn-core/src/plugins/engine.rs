@@ -0,0 +1,344 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Batched raw-socket send and receive engine
+//!
+//! The SYN and raw UDP scans push tens of thousands of probes at a target, faster than they
+//! could await a send and a reply for each one. An [`Engine`] separates the two halves:
+//!
+//! - Probes are built into a ring of reusable buffers, and the ring is written out a batch at
+//!   a time, with one `sendmmsg` call per batch where the platform has it, waiting only when
+//!   the socket's send buffer is full or the interface queue pushes back. Fragmented probes
+//!   are copied into new buffers, one per fragment.
+//! - A single receiver task drains every reply socket as soon as packets arrive, so replies
+//!   that come in while probes are still being sent are not dropped from a full receive
+//!   buffer, and hands the packets that pass the [`Filter`], and any ICMP errors, to the scan.
+//!   At most [`REPLY_QUEUE`] packets wait for the scan; beyond that, further replies wait in
+//!   the socket's receive buffer instead.
+//!
+//! Each packet goes to the destination in its own IP header, so one engine can sweep many
+//! targets.
+
+use crate::error::HuginnError;
+use crate::plugins::ScanContext;
+use crate::plugins::raw::{self, PROTOCOL_ICMP, PROTOCOL_TCP, PROTOCOL_UDP};
+use crate::progress::Probes;
+use socket2::{Protocol, SockRef};
+use std::io;
+use std::net::Ipv4Addr;
+use std::task::Poll;
+use std::time::Duration;
+use tokio::io::Interest;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// Packets built before the batch is written out
+const BATCH: usize = 64;
+
+/// Room reserved in each buffer of the ring: an Ethernet frame
+const PACKET_SIZE: usize = 1500;
+
+/// Received packets held for the scan before the receiver task stops draining the sockets
+pub const REPLY_QUEUE: usize = 4096;
+
+/// Receive buffer requested for each reply socket, to absorb bursts of replies
+const RECEIVE_BUFFER: usize = 4 << 20;
+
+/// Pause before resending a packet the interface queue had no room for
+const CONGESTION_PAUSE: Duration = Duration::from_millis(1);
+
+/// Which received packets an [`Engine`] hands to the scan, besides ICMP errors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+	/// Packets from one target
+	From(Ipv4Addr),
+	/// TCP and UDP packets to one local port, from any target
+	ToPort(u16),
+}
+
+impl Filter {
+	/// Whether a received IPv4 packet passes the filter; ICMP errors always do, since a router on
+	/// the way may send them about a probe
+	fn passes(self, packet: &[u8]) -> bool {
+		if packet.len() < 20 {
+			return false;
+		}
+		if packet[9] == PROTOCOL_ICMP {
+			return true;
+		}
+		match self {
+			Filter::From(target) => packet[12..16] == target.octets(),
+			Filter::ToPort(port) => {
+				let header = usize::from(packet[0] & 0x0F) * 4;
+				matches!(packet[9], PROTOCOL_TCP | PROTOCOL_UDP)
+					&& packet.get(header + 2..header + 4) == Some(&port.to_be_bytes()[..])
+			},
+		}
+	}
+}
+
+/// Sends batches of raw IPv4 packets and receives the replies
+pub struct Engine {
+	sender: UdpSocket,
+	ring: Vec<Vec<u8>>,
+	queued: usize,
+	mtu: Option<usize>,
+	probes: Probes,
+	replies: Receiver<io::Result<Vec<u8>>>,
+	receiver: JoinHandle<()>,
+}
+
+impl Engine {
+	/// Open the raw sockets, and start receiving the replies that arrive over the given
+	/// protocols and pass the filter
+	///
+	/// A lack of privileges is reported as [`HuginnError::PermissionDenied`].
+	pub fn start(protocols: &[Protocol], filter: Filter, context: &ScanContext) -> Result<Self, HuginnError> {
+		let sender = raw::socket(Protocol::from(raw::PROTOCOL_RAW), context)?;
+		let sockets = protocols
+			.iter()
+			.map(|&protocol| {
+				let socket = raw::socket(protocol, context)?;
+				SockRef::from(&socket).set_recv_buffer_size(RECEIVE_BUFFER)?;
+				Ok(socket)
+			})
+			.collect::<Result<Vec<_>, HuginnError>>()?;
+		let (replies, received) = mpsc::channel(REPLY_QUEUE);
+		Ok(Self {
+			sender,
+			ring: (0..BATCH).map(|_| Vec::with_capacity(PACKET_SIZE)).collect(),
+			queued: 0,
+			mtu: context.raw.mtu,
+			probes: context.probes.clone(),
+			replies: received,
+			receiver: tokio::spawn(receive(sockets, filter, replies)),
+		})
+	}
+
+	/// Build a packet into the next buffer of the ring, writing out the batch once the ring is
+	/// full
+	///
+	/// `build` receives an empty buffer and appends a complete IPv4 packet to it, which is sent
+	/// to the destination in its header.
+	pub async fn queue(&mut self, build: impl FnOnce(&mut Vec<u8>)) -> io::Result<()> {
+		let buffer = &mut self.ring[self.queued];
+		buffer.clear();
+		build(buffer);
+		self.queued += 1;
+		self.probes.sent();
+		if self.queued == self.ring.len() {
+			self.flush().await?;
+		}
+		Ok(())
+	}
+
+	/// Write out the packets queued so far, fragmented when the options ask for it
+	pub async fn flush(&mut self) -> io::Result<()> {
+		let queued = &self.ring[..self.queued];
+		let fragments: Vec<Vec<u8>> = match self.mtu {
+			Some(mtu) => queued.iter().flat_map(|packet| raw::fragment(packet, mtu)).collect(),
+			None => Vec::new(),
+		};
+		let packets: Vec<&[u8]> = match self.mtu {
+			Some(_) => fragments.iter().map(Vec::as_slice).collect(),
+			None => queued.iter().map(Vec::as_slice).collect(),
+		};
+		let mut sent = 0;
+		while sent < packets.len() {
+			match self.sender.try_io(Interest::WRITABLE, || send_batch(&self.sender, &packets[sent..])) {
+				Ok(count) => sent += count,
+				Err(e) if e.kind() == io::ErrorKind::WouldBlock => self.sender.writable().await?,
+				Err(e) if is_congested(&e) => tokio::time::sleep(CONGESTION_PAUSE).await,
+				Err(e) => return Err(e),
+			}
+		}
+		self.queued = 0;
+		Ok(())
+	}
+
+	/// The next packet that passed the filter, received before the deadline; queued packets
+	/// are written out first
+	pub async fn reply(&mut self, deadline: Instant) -> io::Result<Option<Vec<u8>>> {
+		self.flush().await?;
+		match tokio::time::timeout_at(deadline, self.replies.recv()).await {
+			Err(_) => Ok(None),
+			Ok(Some(packet)) => packet.map(Some),
+			Ok(None) => Err(stopped()),
+		}
+	}
+
+	/// A packet that passed the filter and is already waiting, without writing out the queue
+	pub fn try_reply(&mut self) -> io::Result<Option<Vec<u8>>> {
+		match self.replies.try_recv() {
+			Ok(packet) => packet.map(Some),
+			Err(TryRecvError::Empty) => Ok(None),
+			Err(TryRecvError::Disconnected) => Err(stopped()),
+		}
+	}
+}
+
+impl Drop for Engine {
+	fn drop(&mut self) {
+		self.receiver.abort();
+	}
+}
+
+/// Send as many of the packets as the socket takes in one `sendmmsg` call, each to the
+/// destination in its header, returning how many were sent
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd", target_os = "netbsd"))]
+fn send_batch(socket: &UdpSocket, packets: &[&[u8]]) -> io::Result<usize> {
+	use nix::sys::socket::{ControlMessage, MsgFlags, MultiHeaders, SockaddrIn, sendmmsg};
+	use std::io::IoSlice;
+	use std::os::fd::AsRawFd;
+
+	let slices: Vec<[IoSlice; 1]> = packets.iter().map(|packet| [IoSlice::new(packet)]).collect();
+	let destinations: Vec<Option<SockaddrIn>> = packets
+		.iter()
+		.map(|packet| Some(SockaddrIn::new(packet[16], packet[17], packet[18], packet[19], 0)))
+		.collect();
+	let mut headers = MultiHeaders::preallocate(packets.len(), None);
+	let control: [ControlMessage; 0] = [];
+	let sent = sendmmsg(socket.as_raw_fd(), &mut headers, &slices, destinations, control, MsgFlags::empty())?;
+	Ok(sent.count())
+}
+
+/// Send the first of the packets to the destination in its header, returning how many were
+/// sent
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd", target_os = "netbsd")))]
+fn send_batch(socket: &UdpSocket, packets: &[&[u8]]) -> io::Result<usize> {
+	use std::net::SocketAddr;
+
+	let packet = packets[0];
+	let destination = SocketAddr::from((Ipv4Addr::new(packet[16], packet[17], packet[18], packet[19]), 0));
+	socket.try_send_to(packet, destination)?;
+	Ok(1)
+}
+
+/// Whether a send failed because the interface queue is full, rather than for good
+#[cfg(unix)]
+fn is_congested(error: &io::Error) -> bool {
+	error.raw_os_error() == Some(nix::errno::Errno::ENOBUFS as i32)
+}
+
+/// Whether a send failed because the interface queue is full, rather than for good
+#[cfg(not(unix))]
+fn is_congested(_error: &io::Error) -> bool {
+	false
+}
+
+/// The error when the receiver task has gone away
+fn stopped() -> io::Error {
+	io::Error::other("the raw packet receiver stopped")
+}
+
+/// Drain every socket whenever any has packets waiting, forwarding those that pass the filter
+/// until the engine goes away
+async fn receive(sockets: Vec<UdpSocket>, filter: Filter, replies: Sender<io::Result<Vec<u8>>>) {
+	let mut buffer = vec![0; 65535];
+	loop {
+		let ready = std::future::poll_fn(|cx| {
+			for socket in &sockets {
+				if let Poll::Ready(ready) = socket.poll_recv_ready(cx) {
+					return Poll::Ready(ready);
+				}
+			}
+			Poll::Pending
+		})
+		.await;
+		if let Err(e) = ready {
+			let _ = replies.send(Err(e)).await;
+			return;
+		}
+		for socket in &sockets {
+			loop {
+				match socket.try_recv(&mut buffer) {
+					Ok(length) if filter.passes(&buffer[..length]) => {
+						if replies.send(Ok(buffer[..length].to_vec())).await.is_err() {
+							return;
+						}
+					},
+					Ok(_) => {},
+					Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+					Err(e) => {
+						let _ = replies.send(Err(e)).await;
+						return;
+					},
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::plugins::ScanType;
+	use crate::plugins::raw::{Ipv4Header, UdpReply};
+	use crate::scanner::Scanner;
+
+	#[test]
+	fn test_filter() {
+		let (ours, theirs) = (Ipv4Addr::new(192, 0, 2, 1), Ipv4Addr::new(192, 0, 2, 2));
+		let mut packet = Vec::new();
+		Ipv4Header {
+			source: theirs,
+			destination: ours,
+			protocol: PROTOCOL_UDP,
+			ttl: 64,
+			identification: 1,
+		}
+		.write(&mut packet, 8);
+		raw::write_udp_datagram(&mut packet, theirs, ours, (53, 40000), &[]);
+		assert!(Filter::From(theirs).passes(&packet));
+		assert!(!Filter::From(ours).passes(&packet));
+		assert!(Filter::ToPort(40000).passes(&packet));
+		assert!(!Filter::ToPort(53).passes(&packet));
+		packet[9] = PROTOCOL_ICMP;
+		assert!(Filter::ToPort(53).passes(&packet));
+	}
+
+	#[tokio::test]
+	async fn test_batches_reach_target() {
+		let context = Scanner::builder().context(ScanType::Udp);
+		let target = Ipv4Addr::LOCALHOST;
+		let mut engine = match Engine::start(&[Protocol::UDP], Filter::From(target), &context) {
+			Ok(engine) => engine,
+			// Raw sockets are not permitted in this environment
+			Err(e) => return assert!(matches!(e, HuginnError::PermissionDenied(_)), "{}", e),
+		};
+		let listener = std::net::UdpSocket::bind((target, 0)).unwrap();
+		let port = listener.local_addr().unwrap().port();
+
+		// More than a batch, so the ring is written out and reused
+		let count = BATCH * 2 + 3;
+		for sequence in 0..count as u16 {
+			engine
+				.queue(|buffer| {
+					let header = Ipv4Header {
+						source: target,
+						destination: target,
+						protocol: PROTOCOL_UDP,
+						ttl: 64,
+						identification: sequence,
+					};
+					header.write(buffer, 8 + 2);
+					raw::write_udp_datagram(buffer, target, target, (9, port), &sequence.to_be_bytes());
+				})
+				.await
+				.unwrap();
+		}
+
+		// On loopback the probes themselves come back to the reply socket
+		let deadline = Instant::now() + Duration::from_secs(2);
+		let mut seen = 0;
+		while seen < count {
+			let packet = engine.reply(deadline).await.unwrap().expect("every probe arrives");
+			if UdpReply::parse(&packet).is_some_and(|datagram| datagram.destination_port == port) {
+				seen += 1;
+			}
+		}
+	}
+}
@@ -0,0 +1,945 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! HTTP fingerprinting plugin
+//!
+//! Fetches the front page of each web port, over HTTPS or plain HTTP, and reports the port as
+//! open with the `Server` header as its version. The details carry fingerprints for looking the
+//! site up in public fingerprint databases, in the query syntax Shodan uses:
+//!
+//! - `http.favicon.hash`, the MurmurHash3 of the base64-encoded favicon, as Shodan and FOFA
+//!   index it, which identifies the product behind a port even where its pages are branded
+//! - `http.html_hash`, the MurmurHash3 of the front page
+//! - `simhash`, a 64-bit similarity hash of the words of the front page, which differs in only a
+//!   few bits between pages that are nearly alike, such as one product's login page on two hosts
+//!
+//! The favicon is the one the page links to, or `/favicon.ico`. Certificates are not verified,
+//! so that self-signed and expired ones do not hide a site, and redirects are not followed.
+//!
+//! The details also list the protocol versions each HTTPS port actually serves: `h2` when the
+//! server picks HTTP/2 offered by ALPN, and `h3` when a QUIC handshake offering HTTP/3 completes
+//! on the same UDP port, or on the port its `Alt-Svc` header advertises for `h3`.
+//!
+//! Each web port is also asked for a WebSocket upgrade at the paths WebSocket services are
+//! commonly served at, first from a foreign origin and then from the site's own. An endpoint that
+//! upgrades a request without credentials is reported as a `websocket` finding: of medium
+//! severity when it accepts any origin, since any web page can then open it as its visitors
+//! (cross-site WebSocket hijacking), and of low severity when it checks the origin.
+//!
+//! Finally, the files a site publishes about itself are harvested by
+//! [`well_known`](super::well_known): the paths `/robots.txt` disallows and the sitemaps it names
+//! (`robots_txt`), the pages `/sitemap.xml` lists (`sitemap`), and the vulnerability disclosure
+//! contacts in `/.well-known/security.txt` (`security_txt`), each reported without a severity.
+//! The CMS or framework behind the site, and its version where it shows, is recognized by
+//! [`web_platforms`](super::web_platforms) and reported as a `platform` result.
+
+use crate::error::HuginnError;
+use crate::plugins::tls::random_bytes;
+use crate::plugins::{web_platforms, well_known};
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::io;
+use std::net::{Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use quinn::crypto::rustls::{HandshakeData, QuicClientConfig};
+use tokio_rustls::TlsConnector;
+use tokio_rustls::rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use tokio_rustls::rustls::crypto::{self, CryptoProvider, ring};
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{self, ClientConfig, DigitallySignedStruct, SignatureScheme};
+
+/// Ports probed when no port spec is configured
+const DEFAULT_PORTS: &[u16] = &[80, 443, 8000, 8080, 8443];
+
+/// Ports tried over HTTPS before plain HTTP
+pub(crate) const TLS_PORTS: &[u16] = &[443, 4443, 8443, 9443];
+
+/// Largest response read, headers included
+const MAX_RESPONSE: usize = 1 << 20;
+
+/// Longest page title reported, in characters
+const MAX_TITLE: usize = 100;
+
+/// Favicon fetched when the page links to none
+const FAVICON: &str = "/favicon.ico";
+
+/// Paths WebSocket endpoints are commonly served at
+const WEBSOCKET_PATHS: &[&str] = &["/ws", "/websocket", "/socket.io/?EIO=4&transport=websocket"];
+
+/// Origin of a site that no WebSocket endpoint should trust
+const FOREIGN_ORIGIN: &str = "https://huginn.invalid";
+
+/// User agent of every request
+pub(crate) const USER_AGENT: &str = concat!("huginn/", env!("CARGO_PKG_VERSION"));
+
+/// Certificate verifier that accepts every certificate, checking only that the handshake is
+/// signed by the key it presents
+#[derive(Debug)]
+struct AnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AnyCertificate {
+	fn verify_server_cert(
+		&self,
+		_end_entity: &CertificateDer<'_>,
+		_intermediates: &[CertificateDer<'_>],
+		_server_name: &ServerName<'_>,
+		_ocsp_response: &[u8],
+		_now: UnixTime,
+	) -> Result<ServerCertVerified, rustls::Error> {
+		Ok(ServerCertVerified::assertion())
+	}
+
+	fn verify_tls12_signature(
+		&self,
+		message: &[u8],
+		certificate: &CertificateDer<'_>,
+		signature: &DigitallySignedStruct,
+	) -> Result<HandshakeSignatureValid, rustls::Error> {
+		crypto::verify_tls12_signature(message, certificate, signature, &self.0.signature_verification_algorithms)
+	}
+
+	fn verify_tls13_signature(
+		&self,
+		message: &[u8],
+		certificate: &CertificateDer<'_>,
+		signature: &DigitallySignedStruct,
+	) -> Result<HandshakeSignatureValid, rustls::Error> {
+		crypto::verify_tls13_signature(message, certificate, signature, &self.0.signature_verification_algorithms)
+	}
+
+	fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+		self.0.signature_verification_algorithms.supported_schemes()
+	}
+}
+
+/// TLS client configuration that accepts every certificate, offering protocols by ALPN
+pub(crate) fn client_config(protocols: &[&[u8]]) -> io::Result<ClientConfig> {
+	let provider = Arc::new(ring::default_provider());
+	let mut config = ClientConfig::builder_with_provider(provider.clone())
+		.with_safe_default_protocol_versions()
+		.map_err(io::Error::other)?
+		.dangerous()
+		.with_custom_certificate_verifier(Arc::new(AnyCertificate(provider)))
+		.with_no_client_auth();
+	config.alpn_protocols = protocols.iter().map(|protocol| protocol.to_vec()).collect();
+	Ok(config)
+}
+
+/// TLS clients of a scan
+pub(crate) struct Clients {
+	/// For requests, which are always HTTP/1.1
+	pub(crate) http1: TlsConnector,
+	/// For asking servers whether they serve HTTP/2
+	pub(crate) h2: TlsConnector,
+	/// For QUIC handshakes offering HTTP/3
+	pub(crate) h3: quinn::ClientConfig,
+}
+
+impl Clients {
+	pub(crate) fn new() -> io::Result<Self> {
+		let h3 = QuicClientConfig::try_from(client_config(&[b"h3"])?).map_err(io::Error::other)?;
+		Ok(Self {
+			http1: TlsConnector::from(Arc::new(client_config(&[b"http/1.1"])?)),
+			h2: TlsConnector::from(Arc::new(client_config(&[b"h2", b"http/1.1"])?)),
+			h3: quinn::ClientConfig::new(Arc::new(h3)),
+		})
+	}
+}
+
+/// A response to a request
+#[derive(Debug)]
+pub(crate) struct Response {
+	pub(crate) version: String,
+	pub(crate) status: u16,
+	pub(crate) reason: String,
+	pub(crate) headers: Vec<(String, String)>,
+	pub(crate) body: Vec<u8>,
+}
+
+impl Response {
+	/// Value of a header, by case-insensitive name
+	pub(crate) fn header(&self, name: &str) -> Option<&str> {
+		self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+	}
+}
+
+/// Position of the first occurrence of `needle` in `data`
+fn find(data: &[u8], needle: &[u8]) -> Option<usize> {
+	data.windows(needle.len()).position(|window| window == needle)
+}
+
+/// Decode a chunked body, and whether its last chunk arrived
+fn dechunk(mut data: &[u8]) -> (Vec<u8>, bool) {
+	let mut body = Vec::new();
+	loop {
+		let Some(end) = find(data, b"\r\n") else {
+			return (body, false);
+		};
+		let size = std::str::from_utf8(&data[..end])
+			.ok()
+			.and_then(|line| usize::from_str_radix(line.split(';').next().unwrap_or_default().trim(), 16).ok());
+		let Some(size) = size else {
+			return (body, false);
+		};
+		data = &data[end + 2..];
+		if size == 0 {
+			return (body, true);
+		}
+		body.extend_from_slice(&data[..size.min(data.len())]);
+		if data.len() < size + 2 {
+			return (body, false);
+		}
+		data = &data[size + 2..];
+	}
+}
+
+/// Parse a response, or `None` while more of it is to come; at the end of the stream, a
+/// truncated body is taken as it is
+pub(crate) fn parse(raw: &[u8], end: bool) -> Option<Response> {
+	let head_end = find(raw, b"\r\n\r\n")?;
+	let head = String::from_utf8_lossy(&raw[..head_end]);
+	let mut lines = head.split("\r\n");
+	let mut status_line = lines.next()?.splitn(3, ' ');
+	let version = status_line.next().filter(|version| version.starts_with("HTTP/"))?.to_string();
+	let status = status_line.next()?.parse().ok()?;
+	let reason = status_line.next().unwrap_or_default().trim().to_string();
+	let headers = lines
+		.filter_map(|line| line.split_once(':'))
+		.map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+		.collect();
+	let mut response = Response {
+		version,
+		status,
+		reason,
+		headers,
+		body: Vec::new(),
+	};
+
+	let data = &raw[head_end + 4..];
+	let chunked = response.header("transfer-encoding").is_some_and(|coding| coding.eq_ignore_ascii_case("chunked"));
+	let length = response.header("content-length").and_then(|length| length.parse::<usize>().ok());
+	// Informational responses, such as a protocol switch, and responses without content end
+	// with their head
+	let complete = if matches!(response.status, 100..=199 | 204 | 304) {
+		true
+	} else if chunked {
+		let (body, complete) = dechunk(data);
+		response.body = body;
+		complete
+	} else if let Some(length) = length {
+		response.body = data[..length.min(data.len())].to_vec();
+		data.len() >= length
+	} else {
+		response.body = data.to_vec();
+		false
+	};
+	(complete || end).then_some(response)
+}
+
+/// Send a request and read the response
+pub(crate) async fn exchange(mut stream: impl AsyncRead + AsyncWrite + Unpin, request: &str) -> io::Result<Response> {
+	stream.write_all(request.as_bytes()).await?;
+	stream.flush().await?;
+	let mut raw = Vec::new();
+	let mut buffer = [0; 8192];
+	loop {
+		match stream.read(&mut buffer).await {
+			Ok(0) => break,
+			Ok(length) => raw.extend_from_slice(&buffer[..length]),
+			// Many servers close TLS connections without a close_notify
+			Err(_) if !raw.is_empty() => break,
+			Err(e) => return Err(e),
+		}
+		if raw.len() >= 5 && !raw.starts_with(b"HTTP/") {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "not an HTTP response"));
+		}
+		if raw.len() >= MAX_RESPONSE {
+			break;
+		}
+		if let Some(response) = parse(&raw, false) {
+			return Ok(response);
+		}
+	}
+	parse(&raw, true).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "incomplete HTTP response"))
+}
+
+/// Host and port of a request's `Host` header, without the port when it is the default
+pub(crate) fn authority(host: &str, port: u16, tls: bool) -> String {
+	let host = if host.parse::<Ipv6Addr>().is_ok() { format!("[{}]", host) } else { host.to_string() };
+	if port == if tls { 443 } else { 80 } { host } else { format!("{}:{}", host, port) }
+}
+
+/// Server name of a host, for TLS
+pub(crate) fn server_name(host: &str) -> io::Result<ServerName<'static>> {
+	ServerName::try_from(host.to_string()).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Request line and standard headers of a request
+pub(crate) fn head(method: &str, host: &str, port: u16, tls: bool, path: &str) -> String {
+	format!(
+		"{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: {}\r\nAccept: */*\r\n",
+		method,
+		path,
+		authority(host, port, tls),
+		USER_AGENT
+	)
+}
+
+/// Send a request to a web port and read the response
+pub(crate) async fn round_trip(
+	host: &str,
+	port: u16,
+	tls: Option<&TlsConnector>,
+	request: &str,
+	context: &ScanContext,
+) -> io::Result<Response> {
+	let stream = context.connect(host, port).await?;
+	match tls {
+		Some(connector) => exchange(connector.connect(server_name(host)?, stream).await?, request).await,
+		None => exchange(stream, request).await,
+	}
+}
+
+/// GET a path from a web port, with headers added to the standard ones
+pub(crate) async fn send(
+	host: &str,
+	port: u16,
+	tls: Option<&TlsConnector>,
+	path: &str,
+	headers: &str,
+	context: &ScanContext,
+) -> io::Result<Response> {
+	let request = format!("{}{}\r\n", head("GET", host, port, tls.is_some(), path), headers);
+	round_trip(host, port, tls, &request, context).await
+}
+
+/// POST a JSON body to a path of a web port
+pub(crate) async fn post_json(
+	host: &str,
+	port: u16,
+	tls: Option<&TlsConnector>,
+	path: &str,
+	body: &str,
+	context: &ScanContext,
+) -> io::Result<Response> {
+	let request = format!(
+		"{}Connection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+		head("POST", host, port, tls.is_some(), path),
+		body.len(),
+		body
+	);
+	round_trip(host, port, tls, &request, context).await
+}
+
+/// GET a path from a web port
+pub(crate) async fn get(
+	host: &str,
+	port: u16,
+	tls: Option<&TlsConnector>,
+	path: &str,
+	context: &ScanContext,
+) -> io::Result<Response> {
+	send(host, port, tls, path, "Connection: close\r\n", context).await
+}
+
+/// Whether a path upgrades a WebSocket handshake from an origin
+async fn upgrades(
+	host: &str,
+	port: u16,
+	tls: Option<&TlsConnector>,
+	path: &str,
+	origin: &str,
+	context: &ScanContext,
+) -> bool {
+	let headers = format!(
+		"Connection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: {}\r\nOrigin: {}\r\n",
+		STANDARD.encode(random_bytes(16)),
+		origin
+	);
+	let upgraded = |response: &Response| {
+		response.status == 101 && response.header("upgrade").is_some_and(|protocol| protocol.eq_ignore_ascii_case("websocket"))
+	};
+	matches!(context.probe(|| send(host, port, tls, path, &headers, context)).await, Some(Ok(response)) if upgraded(&response))
+}
+
+/// Findings for the WebSocket endpoints of a site
+async fn websockets(target: &str, port: u16, tls: Option<&TlsConnector>, context: &ScanContext) -> Vec<ScanResult> {
+	let scheme = if tls.is_some() { "https" } else { "http" };
+	let own = format!("{}://{}", scheme, authority(target, port, tls.is_some()));
+	let mut findings = Vec::new();
+	for path in WEBSOCKET_PATHS {
+		let (severity, behavior) = if upgrades(target, port, tls, path, FOREIGN_ORIGIN, context).await {
+			(
+				Severity::Medium,
+				"accepts connections without credentials from any origin, so any web page can open it as its visitors",
+			)
+		} else if upgrades(target, port, tls, path, &own, context).await {
+			(Severity::Low, "accepts connections without credentials, from its own origin only")
+		} else {
+			continue;
+		};
+		findings.push(ScanResult {
+			target: target.to_string(),
+			scan_type: ScanType::Http.to_string(),
+			port: Some(port),
+			status: "websocket".to_string(),
+			severity: Some(severity),
+			service: Some(if tls.is_some() { "wss" } else { "ws" }.to_string()),
+			version: None,
+			details: Some(format!("WebSocket endpoint {} {}", path, behavior)),
+			techniques: vec!["T1190".to_string()],
+			screenshot: None,
+			rtt: None,
+		});
+	}
+	findings
+}
+
+/// Whether the TLS server on a port picks HTTP/2 when offered it by ALPN
+async fn serves_h2(host: &str, port: u16, connector: &TlsConnector, context: &ScanContext) -> bool {
+	let handshake = || async {
+		let stream = connector.connect(server_name(host)?, context.connect(host, port).await?).await?;
+		Ok::<_, io::Error>(stream.get_ref().1.alpn_protocol() == Some(b"h2"))
+	};
+	matches!(context.probe(handshake).await, Some(Ok(true)))
+}
+
+/// UDP port that an `Alt-Svc` header advertises HTTP/3 on, such as 443 in `h3=":443"; ma=86400`
+fn alt_svc_h3(header: &str) -> Option<u16> {
+	header.split(',').find_map(|service| {
+		let (protocol, authority) = service.split(';').next()?.split_once('=')?;
+		let authority = authority.trim().trim_matches('"');
+		(protocol.trim() == "h3").then(|| authority.rsplit_once(':')?.1.parse().ok()).flatten()
+	})
+}
+
+/// Whether a QUIC handshake offering HTTP/3 completes on a UDP port
+async fn serves_h3(host: &str, port: u16, config: &quinn::ClientConfig, context: &ScanContext) -> bool {
+	let Ok(address) = context.resolve(host).await else {
+		return false;
+	};
+	let Ok(socket) = context.transport.quic_socket(SocketAddr::new(address, port), context) else {
+		return false;
+	};
+	let Some(runtime) = quinn::default_runtime() else {
+		return false;
+	};
+	let Ok(mut endpoint) = quinn::Endpoint::new(quinn::EndpointConfig::default(), None, socket, runtime) else {
+		return false;
+	};
+	endpoint.set_default_client_config(config.clone());
+	let handshake = || async { endpoint.connect(SocketAddr::new(address, port), host).ok()?.await.ok() };
+	let Some(Some(connection)) = context.probe(handshake).await else {
+		return false;
+	};
+	let h3 = connection
+		.handshake_data()
+		.and_then(|data| data.downcast::<HandshakeData>().ok())
+		.is_some_and(|data| data.protocol.as_deref() == Some(b"h3"));
+	connection.close(0u32.into(), b"");
+	h3
+}
+
+/// MurmurHash3 (x86, 32-bit) of the data with seed 0, as the signed value that Python's `mmh3`
+/// gives and fingerprint databases list
+fn mmh3(data: &[u8]) -> i32 {
+	const C1: u32 = 0xcc9e_2d51;
+	const C2: u32 = 0x1b87_3593;
+	let scramble = |k: u32| k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+
+	let mut hash = 0u32;
+	let blocks = data.chunks_exact(4);
+	let tail = blocks.remainder();
+	for block in blocks {
+		hash ^= scramble(u32::from_le_bytes([block[0], block[1], block[2], block[3]]));
+		hash = hash.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+	}
+	if !tail.is_empty() {
+		hash ^= scramble(tail.iter().rev().fold(0, |k, &byte| k << 8 | u32::from(byte)));
+	}
+
+	hash ^= data.len() as u32;
+	hash ^= hash >> 16;
+	hash = hash.wrapping_mul(0x85eb_ca6b);
+	hash ^= hash >> 13;
+	hash = hash.wrapping_mul(0xc2b2_ae35);
+	hash ^= hash >> 16;
+	hash as i32
+}
+
+/// Favicon hash as Shodan computes it: the MurmurHash3 of the icon in base64, with a newline
+/// after every 76 characters
+fn favicon_hash(icon: &[u8]) -> i32 {
+	let encoded = STANDARD.encode(icon);
+	let mut wrapped = Vec::with_capacity(encoded.len() + encoded.len() / 76 + 1);
+	for line in encoded.as_bytes().chunks(76) {
+		wrapped.extend_from_slice(line);
+		wrapped.push(b'\n');
+	}
+	mmh3(&wrapped)
+}
+
+/// 64-bit FNV-1a hash
+fn fnv1a(data: &[u8]) -> u64 {
+	data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// 64-bit simhash of the words of a page, markup included
+pub(crate) fn simhash(text: &str) -> u64 {
+	let mut weights = [0i64; 64];
+	for word in text.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()) {
+		let hash = fnv1a(word.to_lowercase().as_bytes());
+		for (bit, weight) in weights.iter_mut().enumerate() {
+			*weight += if hash >> bit & 1 == 1 { 1 } else { -1 };
+		}
+	}
+	weights.iter().enumerate().filter(|(_, weight)| **weight > 0).fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+/// Value of an attribute of an HTML tag
+pub(crate) fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+	let lower = tag.to_ascii_lowercase();
+	let mut from = 0;
+	while let Some(at) = lower[from..].find(name).map(|i| from + i) {
+		from = at + name.len();
+		let rest = lower[from..].trim_start();
+		if !lower[..at].ends_with(|c: char| c.is_ascii_whitespace()) || !rest.starts_with('=') {
+			continue;
+		}
+		let value = tag[tag.len() - rest.len() + 1..].trim_start();
+		return Some(match value.chars().next() {
+			Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+			_ => value.split(|c: char| c.is_ascii_whitespace() || c == '>').next().unwrap_or_default(),
+		});
+	}
+	None
+}
+
+/// Target of the first `<link rel="icon">` of a page
+fn icon_link(html: &str) -> Option<&str> {
+	let lower = html.to_ascii_lowercase();
+	let mut from = 0;
+	while let Some(start) = lower[from..].find("<link").map(|i| from + i) {
+		let end = lower[start..].find('>').map_or(lower.len(), |i| start + i);
+		let tag = &html[start..end];
+		let icon = attribute(tag, "rel").is_some_and(|rel| rel.split_whitespace().any(|r| r.eq_ignore_ascii_case("icon")));
+		if let Some(href) = attribute(tag, "href").filter(|_| icon) {
+			return Some(href);
+		}
+		from = end;
+	}
+	None
+}
+
+/// Where a page's favicon is
+#[derive(Debug, PartialEq)]
+enum Icon {
+	/// Path on the same site
+	Path(String),
+	/// Inline in a `data:` URL
+	Inline(Vec<u8>),
+}
+
+/// The favicon of a page; icons on other sites are not fetched, so the site's own
+/// `/favicon.ico` stands in for them
+fn icon(html: &str) -> Icon {
+	let fallback = Icon::Path(FAVICON.to_string());
+	let Some(href) = icon_link(html).map(str::trim) else {
+		return fallback;
+	};
+	if let Some(data) = href.strip_prefix("data:") {
+		return data
+			.split_once(";base64,")
+			.and_then(|(_, data)| STANDARD.decode(data).ok())
+			.map_or(fallback, Icon::Inline);
+	}
+	let href = href.split('#').next().unwrap_or_default();
+	if href.is_empty() || href.starts_with("//") || href.contains("://") {
+		return fallback;
+	}
+	Icon::Path(if href.starts_with('/') { href.to_string() } else { format!("/{}", href) })
+}
+
+/// Title of a page, with its whitespace collapsed
+pub(crate) fn title(html: &str) -> Option<String> {
+	let lower = html.to_ascii_lowercase();
+	let start = lower.find("<title")?;
+	let start = start + lower[start..].find('>')? + 1;
+	let end = start + lower[start..].find("</title")?;
+	let title = html[start..end].split_whitespace().collect::<Vec<_>>().join(" ");
+	(!title.is_empty()).then(|| title.chars().take(MAX_TITLE).collect())
+}
+
+/// The front page of the web server on a port, and the TLS client it answered over, if any, or
+/// `None` when no web server answers there
+pub(crate) async fn front_page<'a>(
+	target: &str,
+	port: u16,
+	connector: &'a TlsConnector,
+	context: &ScanContext,
+) -> Option<(Option<&'a TlsConnector>, Response)> {
+	let schemes = if TLS_PORTS.contains(&port) { [Some(connector), None] } else { [None, Some(connector)] };
+	for tls in schemes {
+		match context.probe(|| get(target, port, tls, "/", context)).await {
+			Some(Ok(response)) => return Some((tls, response)),
+			Some(Err(e)) if e.kind() == io::ErrorKind::ConnectionRefused => return None,
+			_ => {},
+		}
+	}
+	None
+}
+
+/// Fingerprint the site on a port and the platform behind it, find its WebSocket endpoints, and
+/// harvest its well-known files; nothing is reported when no web server answers there
+async fn fingerprint(target: &str, port: u16, clients: &Clients, context: &ScanContext) -> Vec<ScanResult> {
+	let Some((tls, page)) = front_page(target, port, &clients.http1, context).await else {
+		return Vec::new();
+	};
+
+	let html = String::from_utf8_lossy(&page.body);
+	let favicon = match icon(&html) {
+		Icon::Inline(data) => Some(data),
+		Icon::Path(path) => match context.probe(|| get(target, port, tls, &path, context)).await {
+			Some(Ok(response)) if response.status == 200 && !response.body.is_empty() => {
+				let html = response.header("content-type").is_some_and(|kind| kind.contains("html"));
+				(!html).then_some(response.body)
+			},
+			_ => None,
+		},
+	};
+
+	let mut protocols = vec![page.version.to_ascii_lowercase()];
+	if tls.is_some() {
+		if serves_h2(target, port, &clients.h2, context).await {
+			protocols.push("h2".to_string());
+		}
+		let h3_port = page.header("alt-svc").and_then(alt_svc_h3).unwrap_or(port);
+		if context.proxy.is_none() && serves_h3(target, h3_port, &clients.h3, context).await {
+			protocols.push("h3".to_string());
+		}
+	}
+
+	let mut details = vec![format!("{} {}", page.status, page.reason).trim_end().to_string()];
+	if let Some(title) = title(&html) {
+		details.push(format!("title {:?}", title));
+	}
+	if let Some(location) = page.header("location").filter(|_| (300..400).contains(&page.status)) {
+		details.push(format!("redirects to {}", location));
+	}
+	details.push(format!("protocols {}", protocols.join(", ")));
+	if let Some(favicon) = favicon {
+		details.push(format!("http.favicon.hash:{}", favicon_hash(&favicon)));
+	}
+	details.push(format!("http.html_hash:{}", mmh3(&page.body)));
+	details.push(format!("simhash:{:016x}", simhash(&html)));
+
+	let mut results = vec![ScanResult {
+		target: target.to_string(),
+		scan_type: ScanType::Http.to_string(),
+		port: Some(port),
+		status: "open".to_string(),
+		severity: None,
+		service: Some(if tls.is_some() { "https" } else { "http" }.to_string()),
+		version: page.header("server").map(str::to_string),
+		details: Some(details.join("; ")),
+		techniques: vec!["T1190".to_string()],
+		screenshot: None,
+		rtt: None,
+	}];
+	results.extend(web_platforms::identify(target, port, tls, &page, context).await);
+	results.extend(websockets(target, port, tls, context).await);
+	results.extend(well_known::harvest(target, port, tls, context).await);
+	results
+}
+
+/// HTTP fingerprinting plugin
+pub struct HttpPlugin;
+
+#[async_trait]
+impl Plugin for HttpPlugin {
+	fn name(&self) -> String {
+		"HTTP Fingerprinter".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::Http
+	}
+
+	fn description(&self) -> String {
+		"Fingerprints web servers and the CMSs and frameworks behind them by their headers, cookies, assets, favicon hash, and page hashes, and harvests their robots.txt, sitemap, and security.txt".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"proxy",
+			"max_connections",
+		]
+	}
+
+	fn proxy_capable(&self) -> bool {
+		true
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		// The page over both schemes, the favicon, the HTTP/2 and HTTP/3 handshakes, the platform
+		// probes, two WebSocket handshakes at each path, and the well-known files
+		context.ports_or(DEFAULT_PORTS).len() * (5 + web_platforms::PROBES + 2 * WEBSOCKET_PATHS.len() + well_known::PATHS.len())
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		// Resolve up front to fail early, but connect by name so that virtual hosts answer
+		if context.proxy.is_none() {
+			context.resolve(target).await?;
+		}
+
+		let clients = Arc::new(Clients::new()?);
+		let (target, context) = (Arc::new(target.to_string()), Arc::new(context.clone()));
+		// A probe delay paces the probes one after another
+		let width = if context.scan_delay.is_zero() { context.max_connections } else { 1 };
+		let permits = Arc::new(Semaphore::new(width));
+		let mut probes = JoinSet::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			let (target, context, clients, permits) = (target.clone(), context.clone(), clients.clone(), permits.clone());
+			probes.spawn(async move {
+				let _permit = permits.acquire_owned().await;
+				fingerprint(&target, port, &clients, &context).await
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(joined) = probes.join_next().await {
+			results.extend(joined.map_err(io::Error::other)?);
+		}
+		results.sort_by_key(|r| r.port);
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ports::PortSpec;
+	use crate::scanner::Scanner;
+	use std::time::Duration;
+	use tokio::net::TcpListener;
+
+	const PAGE: &str = "<html><head><title>\n  Router  login </title><link rel=\"shortcut icon\" href=\"static/icon.png#v2\"></head></html>";
+
+	#[test]
+	fn test_mmh3() {
+		assert_eq!(mmh3(b""), 0);
+		assert_eq!(mmh3(b"hello"), 613153351);
+		assert_eq!(mmh3(b"The quick brown fox jumps over the lazy dog"), 776992547);
+		// Shodan wraps the base64 at 76 characters and ends it with a newline
+		assert_eq!(favicon_hash(b"hello"), mmh3(b"aGVsbG8=\n"));
+		let icon = [7u8; 60];
+		let encoded = STANDARD.encode(icon);
+		assert_eq!(favicon_hash(&icon), mmh3(format!("{}\n{}\n", &encoded[..76], &encoded[76..]).as_bytes()));
+	}
+
+	#[test]
+	fn test_simhash() {
+		let page = "<html><body><h1>Welcome to the admin console</h1><p>Please sign in to continue</p></body></html>";
+		let similar = page.replace("continue", "proceed");
+		let other = "User-agent: *\nDisallow: /private/\nSitemap: https://example.com/sitemap.xml";
+		let distance = |a: &str, b: &str| (simhash(a) ^ simhash(b)).count_ones();
+		assert_eq!(simhash(""), 0);
+		assert_eq!(simhash(page), simhash(&page.to_uppercase()));
+		assert!(distance(page, &similar) < distance(page, other));
+	}
+
+	#[test]
+	fn test_page() {
+		assert_eq!(title(PAGE).as_deref(), Some("Router login"));
+		assert_eq!(title("<title></title>"), None);
+		assert_eq!(icon(PAGE), Icon::Path("/static/icon.png".to_string()));
+		assert_eq!(icon("<link rel=apple-touch-icon href=/a.png><link href='/b.ico' REL='icon'>"), Icon::Path("/b.ico".to_string()));
+		assert_eq!(icon("<link rel=icon href=data:image/png;base64,aGVsbG8=>"), Icon::Inline(b"hello".to_vec()));
+		assert_eq!(icon("<link rel=icon href=https://cdn.example.com/icon.png>"), Icon::Path(FAVICON.to_string()));
+		assert_eq!(icon("<html></html>"), Icon::Path(FAVICON.to_string()));
+	}
+
+	#[test]
+	fn test_alt_svc_h3() {
+		assert_eq!(alt_svc_h3("h3=\":443\"; ma=86400, h3-29=\":443\""), Some(443));
+		assert_eq!(alt_svc_h3("h3-29=\":8443\", h3=\"alt.example.com:4433\""), Some(4433));
+		assert_eq!(alt_svc_h3("h2=\":443\""), None);
+		assert_eq!(alt_svc_h3("clear"), None);
+	}
+
+	#[test]
+	fn test_parse() {
+		let chunked = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6;x=y\r\n world\r\n0\r\n\r\n";
+		let response = parse(chunked, false).unwrap();
+		assert_eq!((response.status, response.reason.as_str()), (200, "OK"));
+		assert_eq!(response.body, b"hello world");
+		assert!(parse(&chunked[..chunked.len() - 6], false).is_none());
+
+		let switch = parse(b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\r\n\x81\x05hello", false).unwrap();
+		assert_eq!((switch.status, switch.body.len()), (101, 0));
+
+		let sized = b"HTTP/1.0 404 Not Found\r\ncontent-length: 4\r\n\r\nnope, and more";
+		assert_eq!(parse(sized, false).unwrap().body, b"nope");
+		assert!(parse(&sized[..sized.len() - 12], false).is_none());
+		assert_eq!(parse(&sized[..sized.len() - 12], true).unwrap().body, b"no");
+		assert!(parse(b"SSH-2.0-OpenSSH_9.6\r\n\r\n", true).is_none());
+	}
+
+	#[tokio::test]
+	async fn test_fingerprints_site() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Ok((mut stream, _)) = listener.accept().await {
+				let mut request = vec![0; 4096];
+				let length = stream.read(&mut request).await.unwrap_or_default();
+				let request = String::from_utf8_lossy(&request[..length]).into_owned();
+				// The endpoint at /ws checks the origin; the Socket.IO one does not
+				let own_origin = request.contains(&format!("Origin: http://127.0.0.1:{}\r\n", port));
+				if request.starts_with("GET /ws ") && own_origin || request.starts_with("GET /socket.io/") {
+					let _ = stream.write_all(b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\r\n").await;
+					continue;
+				}
+				let (kind, body) = if request.starts_with("GET /static/icon.png ") {
+					("image/png", b"\x89PNG icon".to_vec())
+				} else if request.starts_with("GET / ") {
+					("text/html", PAGE.as_bytes().to_vec())
+				} else {
+					// Plain HTTP is answered whatever comes, so TLS handshakes fail on it
+					("text/html", b"not found".to_vec())
+				};
+				let head = format!(
+					"HTTP/1.1 200 OK\r\nServer: lighttpd/1.4.59\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n",
+					kind,
+					body.len()
+				);
+				let _ = stream.write_all(&[head.as_bytes(), &body].concat()).await;
+			}
+		});
+		let closed = {
+			let probe = TcpListener::bind("127.0.0.1:0").await.unwrap();
+			probe.local_addr().unwrap().port()
+		};
+
+		let context = Scanner::builder()
+			.ports(format!("{},{}", port, closed).parse::<PortSpec>().unwrap())
+			.timeout(Duration::from_secs(2))
+			.context(ScanType::Http);
+		let results = HttpPlugin.scan("127.0.0.1", &context).await.unwrap();
+		assert_eq!(results.len(), 3);
+		let result = &results[0];
+		assert_eq!((result.port, result.service.as_deref()), (Some(port), Some("http")));
+		assert_eq!(result.version.as_deref(), Some("lighttpd/1.4.59"));
+		assert_eq!(
+			result.details.as_deref(),
+			Some(
+				format!(
+					"200 OK; title \"Router login\"; protocols http/1.1; http.favicon.hash:{}; http.html_hash:{}; simhash:{:016x}",
+					favicon_hash(b"\x89PNG icon"),
+					mmh3(PAGE.as_bytes()),
+					simhash(PAGE)
+				)
+				.as_str()
+			)
+		);
+
+		let websockets: Vec<_> = results[1..].iter().map(|r| (r.status.as_str(), r.severity, r.details.as_deref().unwrap())).collect();
+		assert_eq!(
+			websockets,
+			[
+				(
+					"websocket",
+					Some(Severity::Low),
+					"WebSocket endpoint /ws accepts connections without credentials, from its own origin only"
+				),
+				(
+					"websocket",
+					Some(Severity::Medium),
+					"WebSocket endpoint /socket.io/?EIO=4&transport=websocket accepts connections without credentials from any origin, so any web page can open it as its visitors"
+				),
+			]
+		);
+	}
+
+	#[cfg(feature = "testbed")]
+	#[tokio::test]
+	async fn test_newer_protocols() {
+		use crate::testbed;
+		use quinn::crypto::rustls::QuicServerConfig;
+
+		let mut tls = testbed::tls_config().unwrap();
+		tls.alpn_protocols = vec![b"h2".to_vec()];
+		let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(tls));
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Ok((stream, _)) = listener.accept().await {
+				let _ = acceptor.accept(stream).await;
+			}
+		});
+
+		let mut tls = testbed::tls_config().unwrap();
+		tls.alpn_protocols = vec![b"h3".to_vec()];
+		let quic = quinn::ServerConfig::with_crypto(Arc::new(QuicServerConfig::try_from(tls).unwrap()));
+		let endpoint = quinn::Endpoint::server(quic, "127.0.0.1:0".parse().unwrap()).unwrap();
+		let quic_port = endpoint.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Some(incoming) = endpoint.accept().await {
+				let _ = incoming.await;
+			}
+		});
+
+		let clients = Clients::new().unwrap();
+		let context = Scanner::builder().timeout(Duration::from_secs(2)).context(ScanType::Http);
+		assert!(serves_h2("127.0.0.1", port, &clients.h2, &context).await);
+		// A server that only speaks HTTP/2 refuses a client that only offers HTTP/1.1
+		assert!(!serves_h2("127.0.0.1", port, &clients.http1, &context).await);
+		assert!(serves_h3("127.0.0.1", quic_port, &clients.h3, &context).await);
+	}
+
+	#[cfg(feature = "testbed")]
+	#[tokio::test]
+	async fn test_fingerprints_testbed() {
+		use crate::testbed::{Service, Testbed};
+
+		let testbed = Testbed::start("127.0.0.1".parse().unwrap(), 0).await.unwrap();
+		let (http, https) = (testbed.port(Service::Http), testbed.port(Service::Https));
+		let context = Scanner::builder()
+			.ports(format!("{},{},{}", http, https, testbed.port(Service::Ssh)).parse::<PortSpec>().unwrap())
+			.timeout(Duration::from_millis(500))
+			.context(ScanType::Http);
+		let results = HttpPlugin.scan("127.0.0.1", &context).await.unwrap();
+		let (open, harvested): (Vec<_>, Vec<_>) = results.iter().partition(|r| r.status == "open");
+		let found: Vec<_> = open.iter().map(|r| (r.port, r.service.as_deref())).collect();
+		let mut expected = [(Some(http), Some("http")), (Some(https), Some("https"))];
+		expected.sort();
+		assert_eq!(found, expected);
+		for result in &open {
+			assert_eq!(result.version.as_deref(), Some("nginx/1.24.0 (Ubuntu)"));
+			let details = result.details.as_deref().unwrap();
+			assert!(details.starts_with("200 OK; title \"Huginn testbed\"; protocols http/1.1; http.html_hash:"), "{}", details);
+		}
+		// The testbed's robots.txt is found on both ports, and its 404s are not mistaken for files
+		for result in &harvested {
+			assert_eq!(result.status, "robots_txt");
+			assert_eq!(result.details.as_deref(), Some("/robots.txt: 1 disallowed paths: /admin/"));
+		}
+		assert_eq!(harvested.len(), 2);
+	}
+}
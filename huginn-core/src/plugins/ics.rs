@@ -0,0 +1,703 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Industrial control system identification plugin
+//!
+//! Identifies the operational technology devices on the registered ports of four protocols,
+//! with the read-only requests their own engineering tools use to identify a device:
+//!
+//! - Modbus/TCP on port 502: Read Device Identification, for the vendor, product code, and
+//!   revision
+//! - S7comm on port 102: a COTP connection and S7 communication setup, then reads of the module
+//!   and component identification lists, for the order numbers, firmware, name, and serial
+//!   number
+//! - DNP3 on port 20000: a link status request to the link addresses outstations commonly use,
+//!   for the address that answers
+//! - BACnet/IP on UDP port 47808: ReadProperty of the device object, for its instance, name,
+//!   vendor, model, and firmware
+//!
+//! Nothing is written and no program or process data is read. OT devices can be fragile, so
+//! ports are probed one at a time, each protocol stops at its first unanswered request, and the
+//! scan type's own timing (see [`ScanType::default_timing`]) allows one retry with a pause before
+//! each probe. BACnet runs over UDP, so it is not probed when a proxy is configured.
+
+use crate::error::HuginnError;
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType};
+use async_trait::async_trait;
+use std::hash::{BuildHasher, RandomState};
+use std::io;
+use std::net::SocketAddr;
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+/// Ports probed when no port spec is configured, one for each protocol
+const DEFAULT_PORTS: &[u16] = &[102, 502, 20000, 47808];
+
+/// COTP connection requests, to the rack 0 slot 2 TSAP and then to the one some CPUs expect
+const COTP_CONNECT: &[&[u8]] = &[
+	&[
+		0x03, 0x00, 0x00, 0x16, 0x11, 0xe0, 0x00, 0x00, 0x00, 0x14, 0x00, 0xc1, 0x02, 0x01, 0x00, 0xc2, 0x02, 0x01, 0x02, 0xc0, 0x01, 0x0a,
+	],
+	&[
+		0x03, 0x00, 0x00, 0x16, 0x11, 0xe0, 0x00, 0x00, 0x00, 0x05, 0x00, 0xc1, 0x02, 0x01, 0x00, 0xc2, 0x02, 0x02, 0x00, 0xc0, 0x01, 0x0a,
+	],
+];
+
+/// S7 communication setup
+const S7_SETUP: &[u8] = &[
+	0x03, 0x00, 0x00, 0x19, 0x02, 0xf0, 0x80, 0x32, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0xf0, 0x00, 0x00, 0x01, 0x00, 0x01,
+	0x01, 0xe0,
+];
+
+/// Link addresses DNP3 outstations commonly use
+const DNP3_ADDRESSES: &[u16] = &[1, 10, 100];
+
+/// Link address the DNP3 requests are sent from, the one masters commonly use
+const DNP3_MASTER: u16 = 3;
+
+/// BACnet device properties read, and what they are reported as
+const BACNET_PROPERTIES: &[(u8, &str)] = &[
+	(77, "name"),
+	(121, "vendor"),
+	(70, "model"),
+	(44, "firmware"),
+	(12, "application"),
+];
+
+/// Protocol identified on a port
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Protocol {
+	S7,
+	Modbus,
+	Dnp3,
+	Bacnet,
+}
+
+impl Protocol {
+	/// Protocol registered on a port
+	fn on_port(port: u16) -> Option<Self> {
+		match port {
+			102 => Some(Protocol::S7),
+			502 => Some(Protocol::Modbus),
+			20000 => Some(Protocol::Dnp3),
+			47808 => Some(Protocol::Bacnet),
+			_ => None,
+		}
+	}
+
+	/// Service name the protocol is reported as
+	fn service(self) -> &'static str {
+		match self {
+			Protocol::S7 => "s7comm",
+			Protocol::Modbus => "modbus",
+			Protocol::Dnp3 => "dnp3",
+			Protocol::Bacnet => "bacnet",
+		}
+	}
+
+	/// Most probes identifying a device sends
+	fn probes(self) -> usize {
+		match self {
+			Protocol::S7 => COTP_CONNECT.len(),
+			Protocol::Modbus => 1,
+			Protocol::Dnp3 => DNP3_ADDRESSES.len(),
+			Protocol::Bacnet => BACNET_PROPERTIES.len(),
+		}
+	}
+}
+
+/// Identity a device gave
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Identity {
+	version: Option<String>,
+	details: Vec<String>,
+}
+
+/// Text of a fixed-length field, without its padding
+fn text(field: &[u8]) -> String {
+	String::from_utf8_lossy(field).trim_matches(|c: char| c == '\0' || c.is_whitespace()).to_string()
+}
+
+/// Modbus Read Device Identification request for the basic objects
+fn modbus_request(transaction: u16) -> [u8; 11] {
+	let [high, low] = transaction.to_be_bytes();
+	[high, low, 0x00, 0x00, 0x00, 0x05, 0x00, 0x2b, 0x0e, 0x01, 0x00]
+}
+
+/// Identity in a Modbus reply to [`modbus_request`], or `None` when it does not answer it
+fn modbus_identity(reply: &[u8], transaction: u16) -> Option<Identity> {
+	if reply.len() < 9 || reply[..2] != transaction.to_be_bytes() || reply[2..4] != [0, 0] {
+		return None;
+	}
+	let mut identity = Identity::default();
+	match reply[7] {
+		0x2b if reply.len() >= 14 && reply[8] == 0x0e => {
+			let mut objects = &reply[14..];
+			for _ in 0..reply[13] {
+				let [id, length, ref rest @ ..] = *objects else {
+					break;
+				};
+				let Some(value) = rest.get(..usize::from(length)) else {
+					break;
+				};
+				let value = text(value);
+				match id {
+					0 => identity.details.push(format!("vendor {}", value)),
+					1 => identity.details.push(format!("product {}", value)),
+					2 => {
+						identity.details.push(format!("revision {}", value));
+						identity.version = Some(value);
+					},
+					_ => {},
+				}
+				objects = &rest[usize::from(length)..];
+			}
+		},
+		0xab => identity.details.push(format!("device identification unsupported (exception {})", reply[8])),
+		_ => return None,
+	}
+	Some(identity)
+}
+
+/// Read a Modbus reply
+async fn read_modbus(stream: &mut (impl AsyncRead + Unpin)) -> io::Result<Vec<u8>> {
+	let mut reply = vec![0; 7];
+	stream.read_exact(&mut reply).await?;
+	let length = usize::from(u16::from_be_bytes([reply[4], reply[5]]));
+	reply.resize(6 + length.max(1), 0);
+	stream.read_exact(&mut reply[7..]).await?;
+	Ok(reply)
+}
+
+/// Identify a Modbus device
+async fn modbus(target: &str, port: u16, context: &ScanContext) -> Option<Identity> {
+	// The transaction only needs to differ from one probe to the next
+	let transaction = RandomState::new().hash_one(Instant::now()) as u16;
+	let exchange = || async {
+		let mut stream = context.connect(target, port).await?;
+		stream.write_all(&modbus_request(transaction)).await?;
+		read_modbus(&mut stream).await
+	};
+	let reply = context.probe(exchange).await?.ok()?;
+	modbus_identity(&reply, transaction)
+}
+
+/// Read an ISO-on-TCP (TPKT) packet
+async fn read_tpkt(stream: &mut (impl AsyncRead + Unpin)) -> io::Result<Vec<u8>> {
+	let mut packet = vec![0; 4];
+	stream.read_exact(&mut packet).await?;
+	if packet[0] != 0x03 {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "not a TPKT packet"));
+	}
+	let length = usize::from(u16::from_be_bytes([packet[2], packet[3]]));
+	packet.resize(length.max(4), 0);
+	stream.read_exact(&mut packet[4..]).await?;
+	Ok(packet)
+}
+
+/// S7 request to read all records of a system status list
+fn szl_request(id: u16) -> Vec<u8> {
+	let mut request = vec![
+		0x03, 0x00, 0x00, 0x21, 0x02, 0xf0, 0x80, 0x32, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x08, 0x00, 0x01, 0x12, 0x04, 0x11,
+		0x44, 0x01, 0x00, 0xff, 0x09, 0x00, 0x04,
+	];
+	request.extend_from_slice(&id.to_be_bytes());
+	request.extend_from_slice(&[0x00, 0x01]);
+	request
+}
+
+/// Records of a system status list in an S7 reply to [`szl_request`], as index and record
+fn szl_records(reply: &[u8]) -> Vec<(u16, &[u8])> {
+	let word = |at: usize| reply.get(at..at + 2).map(|bytes| usize::from(u16::from_be_bytes([bytes[0], bytes[1]])));
+	// TPKT and COTP headers, then the S7 user data header
+	if reply.get(7) != Some(&0x32) || reply.get(8) != Some(&0x07) {
+		return Vec::new();
+	}
+	let Some(data) = word(13).map(|parameters| 17 + parameters) else {
+		return Vec::new();
+	};
+	if reply.get(data) != Some(&0xff) {
+		return Vec::new();
+	}
+	let (Some(length), Some(count)) = (word(data + 8), word(data + 10)) else {
+		return Vec::new();
+	};
+	let records = reply.get(data + 12..).unwrap_or_default();
+	records
+		.chunks_exact(length.max(2))
+		.take(count)
+		.map(|record| (u16::from_be_bytes([record[0], record[1]]), &record[2..]))
+		.collect()
+}
+
+/// Identity in the module and component identification lists of an S7 CPU
+fn s7_identity(modules: &[u8], components: &[u8]) -> Identity {
+	let mut identity = Identity::default();
+	for (index, record) in szl_records(modules) {
+		match index {
+			1 => identity.details.push(format!("module {}", text(record.get(..20).unwrap_or(record)))),
+			6 => identity.details.push(format!("hardware {}", text(record.get(..20).unwrap_or(record)))),
+			7 if record.len() >= 26 => {
+				let version = format!("{}.{}.{}", record[23], record[24], record[25]);
+				identity.details.push(format!("firmware {}", version));
+				identity.version = Some(version);
+			},
+			_ => {},
+		}
+	}
+	for (index, record) in szl_records(components) {
+		let label = match index {
+			1 => "name",
+			2 => "module name",
+			3 => "plant",
+			5 => "serial",
+			7 => "module type",
+			_ => continue,
+		};
+		let value = text(record);
+		if !value.is_empty() {
+			identity.details.push(format!("{} {}", label, value));
+		}
+	}
+	identity
+}
+
+/// Identify an S7 CPU over one connection, or `None` when the connection is refused
+async fn s7_session(target: &str, port: u16, connect: &[u8], context: &ScanContext) -> io::Result<Option<Identity>> {
+	let mut stream = context.connect(target, port).await?;
+	stream.write_all(connect).await?;
+	// Connection confirm
+	if read_tpkt(&mut stream).await?.get(5) != Some(&0xd0) {
+		return Ok(None);
+	}
+	stream.write_all(S7_SETUP).await?;
+	let setup = read_tpkt(&mut stream).await?;
+	if setup.get(7) != Some(&0x32) || setup.get(8) != Some(&0x03) {
+		return Ok(None);
+	}
+	stream.write_all(&szl_request(0x0011)).await?;
+	let modules = read_tpkt(&mut stream).await?;
+	stream.write_all(&szl_request(0x001c)).await?;
+	let components = read_tpkt(&mut stream).await.unwrap_or_default();
+	Ok(Some(s7_identity(&modules, &components)))
+}
+
+/// Identify an S7 CPU
+async fn s7(target: &str, port: u16, context: &ScanContext) -> Option<Identity> {
+	for connect in COTP_CONNECT {
+		match context.probe(|| s7_session(target, port, connect, context)).await? {
+			Ok(Some(identity)) => return Some(identity),
+			Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => return None,
+			_ => {},
+		}
+	}
+	None
+}
+
+/// CRC of a DNP3 link layer block
+fn dnp3_crc(data: &[u8]) -> u16 {
+	let mut crc = 0u16;
+	for &byte in data {
+		crc ^= u16::from(byte);
+		for _ in 0..8 {
+			crc = if crc & 1 == 1 { (crc >> 1) ^ 0xa6bc } else { crc >> 1 };
+		}
+	}
+	!crc
+}
+
+/// DNP3 link status request to an outstation
+fn dnp3_request(destination: u16) -> [u8; 10] {
+	let [destination_low, destination_high] = destination.to_le_bytes();
+	let [source_low, source_high] = DNP3_MASTER.to_le_bytes();
+	let header = [0x05, 0x64, 0x05, 0xc9, destination_low, destination_high, source_low, source_high];
+	let [crc_low, crc_high] = dnp3_crc(&header).to_le_bytes();
+	let mut request = [0; 10];
+	request[..8].copy_from_slice(&header);
+	request[8..].copy_from_slice(&[crc_low, crc_high]);
+	request
+}
+
+/// Link address of the outstation that sent a DNP3 link layer reply to the master, or `None`
+/// when it is not one
+fn dnp3_outstation(reply: &[u8]) -> Option<u16> {
+	let header = reply.get(..10)?;
+	let valid = header[..2] == [0x05, 0x64]
+		&& header[8..] == dnp3_crc(&header[..8]).to_le_bytes()
+		// From a secondary station, to the master
+		&& header[3] & 0xc0 == 0
+		&& u16::from_le_bytes([header[4], header[5]]) == DNP3_MASTER;
+	valid.then(|| u16::from_le_bytes([header[6], header[7]]))
+}
+
+/// Identify a DNP3 outstation
+async fn dnp3(target: &str, port: u16, context: &ScanContext) -> Option<Identity> {
+	for &address in DNP3_ADDRESSES {
+		let exchange = || async {
+			let mut stream = context.connect(target, port).await?;
+			stream.write_all(&dnp3_request(address)).await?;
+			let mut reply = [0; 10];
+			stream.read_exact(&mut reply).await?;
+			Ok::<_, io::Error>(reply)
+		};
+		match context.probe(exchange).await {
+			Some(Ok(reply)) => {
+				let outstation = dnp3_outstation(&reply)?;
+				return Some(Identity {
+					version: None,
+					details: vec![format!("outstation at link address {}", outstation)],
+				});
+			},
+			Some(Err(e)) if e.kind() != io::ErrorKind::UnexpectedEof => return None,
+			// Outstations ignore requests to other addresses
+			_ => {},
+		}
+	}
+	None
+}
+
+/// BACnet/IP ReadProperty request for a property of the device object, whatever its instance
+fn bacnet_request(invoke: u8, property: u8) -> [u8; 17] {
+	[
+		0x81, 0x0a, 0x00, 0x11, 0x01, 0x04, 0x00, 0x05, invoke, 0x0c, 0x0c, 0x02, 0x3f, 0xff, 0xff, 0x19, property,
+	]
+}
+
+/// Answer in a BACnet/IP reply to [`bacnet_request`]: the device instance and the property's
+/// text, when it is acknowledged, or `None` when it does not answer the request
+fn bacnet_answer(reply: &[u8], invoke: u8) -> Option<Option<(u32, Option<String>)>> {
+	if reply.first() != Some(&0x81) || reply.get(4) != Some(&0x01) {
+		return None;
+	}
+	let control = *reply.get(5)?;
+	// Network layer messages carry no APDU
+	if control & 0x80 != 0 {
+		return None;
+	}
+	let mut at = 6;
+	let skip_address = |at: &mut usize| -> Option<()> {
+		*at += 3 + usize::from(*reply.get(*at + 2)?);
+		Some(())
+	};
+	if control & 0x20 != 0 {
+		skip_address(&mut at)?;
+	}
+	if control & 0x08 != 0 {
+		skip_address(&mut at)?;
+	}
+	if control & 0x20 != 0 {
+		at += 1;
+	}
+	let apdu = reply.get(at..)?;
+	match apdu.first()? >> 4 {
+		// Complex acknowledgement of ReadProperty
+		3 if apdu.get(1) == Some(&invoke) && apdu.get(2) == Some(&0x0c) => {},
+		// Error, reject, or abort
+		5..=7 if apdu.get(1) == Some(&invoke) => return Some(None),
+		_ => return None,
+	}
+	let object = apdu.get(3..8).filter(|object| object[0] == 0x0c)?;
+	let instance = u32::from_be_bytes([object[1], object[2], object[3], object[4]]) & 0x3f_ffff;
+	let mut rest = apdu.get(8..)?;
+	// Property identifier, then the opening tag of the value
+	let property_length = usize::from(rest.first()? & 0x07);
+	rest = rest.get(1 + property_length..)?;
+	if rest.first() != Some(&0x3e) {
+		return Some(Some((instance, None)));
+	}
+	let tag = *rest.get(1)?;
+	let (length, value) = match tag & 0x07 {
+		5 => match *rest.get(2)? {
+			254 => (usize::from(u16::from_be_bytes([*rest.get(3)?, *rest.get(4)?])), rest.get(5..)?),
+			length => (usize::from(length), rest.get(3..)?),
+		},
+		length => (usize::from(length), rest.get(2..)?),
+	};
+	// Character strings start with their encoding
+	let text = (tag >> 4 == 7 && length > 0).then(|| text(value.get(1..length).unwrap_or_default()));
+	Some(Some((instance, text)))
+}
+
+/// Identify a BACnet device
+async fn bacnet(target: &str, port: u16, context: &ScanContext) -> Option<Identity> {
+	let address = SocketAddr::new(context.resolve(target).await.ok()?, port);
+	let socket = context.transport.udp(address, context).await.ok()?;
+	let mut identity = Identity::default();
+	let mut instance = None;
+	for (invoke, &(property, label)) in (1..).zip(BACNET_PROPERTIES) {
+		let exchange = || async {
+			socket.send_to(&bacnet_request(invoke, property), address).await?;
+			let mut buffer = [0; 1500];
+			loop {
+				let (length, _) = socket.recv_from(&mut buffer).await?;
+				if let Some(answer) = bacnet_answer(&buffer[..length], invoke) {
+					return Ok::<_, io::Error>(answer);
+				}
+			}
+		};
+		// A device that does not answer is not asked again
+		let Some(Ok(answer)) = context.probe(exchange).await else {
+			break;
+		};
+		let Some((device, value)) = answer else {
+			continue;
+		};
+		instance.get_or_insert(device);
+		if let Some(value) = value.filter(|value| !value.is_empty()) {
+			if label == "firmware" {
+				identity.version = Some(value.clone());
+			}
+			identity.details.push(format!("{} {}", label, value));
+		}
+	}
+	let instance = instance?;
+	identity.details.insert(0, format!("device instance {}", instance));
+	Some(identity)
+}
+
+/// Industrial control system identification plugin
+pub struct IcsPlugin;
+
+#[async_trait]
+impl Plugin for IcsPlugin {
+	fn name(&self) -> String {
+		"ICS Protocol Identifier".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::Ics
+	}
+
+	fn description(&self) -> String {
+		"Identifies Modbus, S7, DNP3, and BACnet devices with read-only requests".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"proxy",
+		]
+	}
+
+	fn proxy_capable(&self) -> bool {
+		true
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		context.ports_or(DEFAULT_PORTS).iter().filter_map(|&port| Protocol::on_port(port)).map(Protocol::probes).sum()
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		context.resolve(target).await?;
+
+		// One port at a time, so that a device only ever handles one request
+		let mut results = Vec::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			let Some(protocol) = Protocol::on_port(port) else {
+				continue;
+			};
+			let identity = match protocol {
+				Protocol::S7 => s7(target, port, context).await,
+				Protocol::Modbus => modbus(target, port, context).await,
+				Protocol::Dnp3 => dnp3(target, port, context).await,
+				Protocol::Bacnet if context.proxy.is_none() => bacnet(target, port, context).await,
+				Protocol::Bacnet => None,
+			};
+			let Some(identity) = identity else {
+				continue;
+			};
+			results.push(ScanResult {
+				target: target.to_string(),
+				scan_type: ScanType::Ics.to_string(),
+				port: Some(port),
+				status: "open".to_string(),
+				severity: None,
+				service: Some(protocol.service().to_string()),
+				version: identity.version,
+				details: (!identity.details.is_empty()).then(|| identity.details.join("; ")),
+				techniques: vec!["T0888".to_string()],
+				screenshot: None,
+				rtt: None,
+			});
+		}
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::plugins::TimingOverride;
+	use crate::scanner::Scanner;
+	use std::time::Duration;
+	use tokio::net::{TcpListener, UdpSocket};
+
+	/// An S7 system status list reply with records
+	fn szl_reply(id: u16, records: &[Vec<u8>]) -> Vec<u8> {
+		let mut data = vec![0xff, 0x09, 0x00, 0x00];
+		data.extend_from_slice(&id.to_be_bytes());
+		data.extend_from_slice(&[0x00, 0x00]);
+		data.extend_from_slice(&(records[0].len() as u16).to_be_bytes());
+		data.extend_from_slice(&(records.len() as u16).to_be_bytes());
+		data.extend(records.concat());
+		let parameters = [0x00, 0x01, 0x12, 0x08, 0x12, 0x84, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00];
+		let mut reply = vec![0x03, 0x00, 0x00, 0x00, 0x02, 0xf0, 0x80, 0x32, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 12];
+		reply.extend_from_slice(&(data.len() as u16).to_be_bytes());
+		reply.extend_from_slice(&parameters);
+		reply.extend(data);
+		let length = reply.len() as u16;
+		reply[2..4].copy_from_slice(&length.to_be_bytes());
+		reply
+	}
+
+	/// A system status list record: an index, then a padded text field and trailing bytes
+	fn record(index: u16, text: &str, width: usize, trailer: &[u8]) -> Vec<u8> {
+		let mut record = index.to_be_bytes().to_vec();
+		record.extend(format!("{:width$}", text, width = width).into_bytes());
+		record.extend_from_slice(trailer);
+		record
+	}
+
+	#[test]
+	fn test_modbus() {
+		let mut reply = vec![0x12, 0x34, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2b, 0x0e, 0x01, 0x01, 0x00, 0x00, 0x03];
+		for (id, value) in [(0u8, "Schneider Electric"), (1, "BMX P34 2020"), (2, "v2.70")] {
+			reply.extend_from_slice(&[id, value.len() as u8]);
+			reply.extend_from_slice(value.as_bytes());
+		}
+		let identity = modbus_identity(&reply, 0x1234).unwrap();
+		assert_eq!(identity.version.as_deref(), Some("v2.70"));
+		assert_eq!(identity.details, ["vendor Schneider Electric", "product BMX P34 2020", "revision v2.70"]);
+		assert_eq!(modbus_identity(&reply, 0x1235), None);
+
+		let exception = [0x12, 0x34, 0x00, 0x00, 0x00, 0x03, 0x00, 0xab, 0x01];
+		assert_eq!(modbus_identity(&exception, 0x1234).unwrap().details, ["device identification unsupported (exception 1)"]);
+		assert_eq!(modbus_request(0x1234), [0x12, 0x34, 0x00, 0x00, 0x00, 0x05, 0x00, 0x2b, 0x0e, 0x01, 0x00]);
+	}
+
+	#[test]
+	fn test_s7() {
+		let modules = szl_reply(
+			0x0011,
+			&[
+				record(1, "6ES7 315-2EH14-0AB0", 20, &[0, 0xc0, 0, 4, 0, 1]),
+				record(6, "6ES7 315-2EH14-0AB0", 20, &[0, 0xc0, 0, 4, 0, 1]),
+				record(7, "", 20, &[0, 0xc0, 0x56, 3, 2, 6]),
+			],
+		);
+		let components = szl_reply(
+			0x001c,
+			&[
+				record(1, "SNAP7-SERVER", 32, &[]),
+				record(4, "Original Siemens Equipment", 32, &[]),
+				record(5, "S C-C2UR28922012", 32, &[]),
+				record(3, "", 32, &[]),
+			],
+		);
+		assert_eq!(szl_request(0x001c).len(), 33);
+		let identity = s7_identity(&modules, &components);
+		assert_eq!(identity.version.as_deref(), Some("3.2.6"));
+		assert_eq!(
+			identity.details,
+			[
+				"module 6ES7 315-2EH14-0AB0",
+				"hardware 6ES7 315-2EH14-0AB0",
+				"firmware 3.2.6",
+				"name SNAP7-SERVER",
+				"serial S C-C2UR28922012",
+			]
+		);
+		assert!(szl_records(&modules[..20]).is_empty());
+	}
+
+	#[test]
+	fn test_dnp3() {
+		assert_eq!(dnp3_crc(&[0x05, 0x64, 0x05, 0xc0, 0x01, 0x00, 0x00, 0x04]).to_le_bytes(), [0xe9, 0x21]);
+		let request = dnp3_request(10);
+		assert_eq!(request[..8], [0x05, 0x64, 0x05, 0xc9, 0x0a, 0x00, 0x03, 0x00]);
+
+		let mut reply = [0x05, 0x64, 0x05, 0x0b, 0x03, 0x00, 0x0a, 0x00, 0, 0];
+		let crc = dnp3_crc(&reply[..8]).to_le_bytes();
+		reply[8..].copy_from_slice(&crc);
+		assert_eq!(dnp3_outstation(&reply), Some(10));
+		// Our own request, echoed back, is not an answer
+		assert_eq!(dnp3_outstation(&request), None);
+	}
+
+	#[test]
+	fn test_bacnet() {
+		// Routed reply from device 260001 with its vendor name
+		let mut reply = vec![0x81, 0x0a, 0x00, 0x00, 0x01, 0x08, 0x00, 0x05, 0x01, 0x2a];
+		reply.extend_from_slice(&[0x30, 0x02, 0x0c, 0x0c, 0x02, 0x03, 0xf7, 0xa1, 0x19, 0x79, 0x3e, 0x75, 0x0a, 0x00]);
+		reply.extend_from_slice(b"Honeywell");
+		reply.push(0x3f);
+		assert_eq!(bacnet_answer(&reply, 2), Some(Some((260001, Some("Honeywell".to_string())))));
+		assert_eq!(bacnet_answer(&reply, 3), None);
+		let error = [0x81, 0x0a, 0x00, 0x0b, 0x01, 0x00, 0x50, 0x02, 0x0c, 0x91, 0x02];
+		assert_eq!(bacnet_answer(&error, 2), Some(None));
+		assert_eq!(bacnet_request(2, 121)[8], 2);
+	}
+
+	#[test]
+	fn test_default_timing() {
+		let context = Scanner::builder().retries(5).context(ScanType::Ics);
+		assert_eq!(context.retries, 1);
+		assert!(!context.scan_delay.is_zero());
+		assert_eq!(IcsPlugin.probes_per_target(&context), 2 + 1 + 3 + 5);
+	}
+
+	#[tokio::test]
+	async fn test_identifies_devices() {
+		let timing = TimingOverride {
+			retries: Some(0),
+			scan_delay: Some(Duration::ZERO),
+			..TimingOverride::default()
+		};
+		let context = Scanner::builder().timeout(Duration::from_millis(500)).timing(ScanType::Ics, timing).context(ScanType::Ics);
+
+		// A Modbus device that answers with its identity
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			let (mut stream, _) = listener.accept().await.unwrap();
+			let mut request = [0; 11];
+			stream.read_exact(&mut request).await.unwrap();
+			let mut reply = request[..2].to_vec();
+			reply.extend_from_slice(&[0x00, 0x00, 0x00, 0x0e, 0x00, 0x2b, 0x0e, 0x01, 0x01, 0x00, 0x00, 0x01, 0x00, 0x04]);
+			reply.extend_from_slice(b"ACME");
+			stream.write_all(&reply).await.unwrap();
+		});
+		let identity = modbus("127.0.0.1", port, &context).await.unwrap();
+		assert_eq!(identity.details, ["vendor ACME"]);
+
+		// A BACnet device that knows only its name
+		let device = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+		let port = device.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			let mut buffer = [0; 1500];
+			while let Ok((_, peer)) = device.recv_from(&mut buffer).await {
+				let (invoke, property) = (buffer[8], buffer[16]);
+				let reply = if property == 77 {
+					let mut reply = vec![0x81, 0x0a, 0x00, 0x00, 0x01, 0x00, 0x30, invoke, 0x0c, 0x0c, 0x02, 0x00, 0x00, 0x07, 0x19, 77];
+					reply.extend_from_slice(&[0x3e, 0x74, 0x00, b'A', b'H', b'U', 0x3f]);
+					reply
+				} else {
+					vec![0x81, 0x0a, 0x00, 0x0b, 0x01, 0x00, 0x50, invoke, 0x0c, 0x91, 0x02]
+				};
+				device.send_to(&reply, peer).await.unwrap();
+			}
+		});
+		let identity = bacnet("127.0.0.1", port, &context).await.unwrap();
+		assert_eq!(identity.details, ["device instance 7", "name AHU"]);
+	}
+}
@@ -0,0 +1,348 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Idle (zombie) scan plugin
+//!
+//! Scans a target without sending it a single packet from Huginn's own address. Each SYN is
+//! spoofed from a third host, the zombie, and the target's answer is read from the zombie's IP
+//! identification counter: the zombie answers a SYN-ACK from an open port with a reset, using up
+//! one identification value, and ignores a reset from a closed port. Reading the counter before
+//! and after each SYN tells the two apart: an increase of two means open, and of one means closed
+//! or filtered, which an idle scan cannot distinguish.
+//!
+//! This only works through a zombie with a global, incrementing counter and little traffic of
+//! its own, so the zombie is tested before the first target is scanned and the scan fails when
+//! it is unsuitable. Ports whose counter moved by more than two on every attempt are reported as
+//! `unknown`.
+
+use crate::attack;
+use crate::error::HuginnError;
+use crate::plugins::raw::{self, ACK, Ipv4Header, PROTOCOL_TCP, RST, SYN, TcpReply};
+use crate::plugins::{DEFAULT_TCP_PORTS, Plugin, ScanContext, ScanResult, ScanType};
+use async_trait::async_trait;
+use log::{debug, info};
+use socket2::Protocol;
+use std::fmt;
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::sync::OnceCell;
+use tokio::time::Instant;
+
+/// Zombie port probed when the zombie is given without one
+const DEFAULT_ZOMBIE_PORT: u16 = 80;
+
+/// Reads of the zombie's counter when testing it
+const TEST_PROBES: usize = 6;
+
+/// Largest counter increase between two test reads of a zombie that is idle enough
+const MAX_INCREMENT: u16 = 5;
+
+/// Shortest time the target's answer is given to reach the zombie
+const MIN_SETTLE: Duration = Duration::from_millis(100);
+
+/// How a host assigns IP identification values
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sequence {
+	/// A global counter, usable for idle scans
+	Incremental,
+	/// A global counter written in the wrong byte order, usable once swapped back
+	ByteSwapped,
+	/// Always zero, as hosts that set Don't Fragment may do
+	Zero,
+	/// Random or per-destination values, which reveal nothing
+	Random,
+}
+
+impl fmt::Display for Sequence {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Sequence::Incremental => "incremental",
+			Sequence::ByteSwapped => "byte-swapped incremental",
+			Sequence::Zero => "all zeros",
+			Sequence::Random => "random",
+		})
+	}
+}
+
+/// Classify the identification values of consecutive replies
+fn classify(ids: &[u16]) -> Sequence {
+	let increments: Vec<u16> = ids.windows(2).map(|pair| pair[1].wrapping_sub(pair[0])).collect();
+	let small = |increment: u16| (1..=MAX_INCREMENT).contains(&increment);
+	if ids.iter().all(|&id| id == 0) {
+		Sequence::Zero
+	} else if increments.iter().all(|&i| small(i)) {
+		Sequence::Incremental
+	} else if ids.windows(2).all(|pair| small(pair[1].swap_bytes().wrapping_sub(pair[0].swap_bytes()))) {
+		Sequence::ByteSwapped
+	} else {
+		Sequence::Random
+	}
+}
+
+/// Parse a zombie given as `HOST[:PORT]`
+pub fn parse_zombie(spec: &str) -> Result<(&str, u16), HuginnError> {
+	match spec.rsplit_once(':') {
+		Some((host, port)) => port
+			.parse()
+			.map(|port| (host, port))
+			.map_err(|_| HuginnError::Config(format!("invalid zombie port in {:?}", spec))),
+		None => Ok((spec, DEFAULT_ZOMBIE_PORT)),
+	}
+}
+
+/// Raw sockets and the address that zombie probes are sent from
+struct Prober {
+	source: Ipv4Addr,
+	sender: UdpSocket,
+	receiver: UdpSocket,
+}
+
+impl Prober {
+	fn open(zombie: Ipv4Addr, context: &ScanContext) -> Result<Self, HuginnError> {
+		Ok(Self {
+			source: raw::source_address(zombie, context)?,
+			sender: raw::socket(Protocol::from(raw::PROTOCOL_RAW), context)?,
+			receiver: raw::socket(Protocol::TCP, context)?,
+		})
+	}
+
+	/// Read the zombie's counter from the reset it answers an unsolicited SYN-ACK with
+	async fn counter(&self, zombie: SocketAddrV4, context: &ScanContext) -> Result<Option<u16>, HuginnError> {
+		let port = raw::ephemeral_port();
+		let segment = raw::tcp_segment(self.source, *zombie.ip(), (port, zombie.port()), raw::random() as u32, SYN | ACK, &[]);
+		let packet = Ipv4Header {
+			source: self.source,
+			destination: *zombie.ip(),
+			protocol: PROTOCOL_TCP,
+			ttl: raw::DEFAULT_TTL,
+			identification: raw::random() as u16,
+		}
+		.packet(&segment);
+		context.probes.sent();
+		self.sender.send_to(&packet, (*zombie.ip(), 0)).await?;
+
+		let deadline = Instant::now() + context.timeout;
+		let mut buffer = [0; 1500];
+		while let Ok(received) = tokio::time::timeout_at(deadline, self.receiver.recv_from(&mut buffer)).await {
+			let (length, _) = received?;
+			if let Some(reply) = TcpReply::parse(&buffer[..length])
+				&& reply.source == *zombie.ip()
+				&& reply.source_port == zombie.port()
+				&& reply.destination_port == port
+				&& reply.flags & RST != 0
+			{
+				return Ok(Some(reply.identification));
+			}
+		}
+		Ok(None)
+	}
+
+	/// Send the target a SYN that appears to come from the zombie
+	async fn spoof(&self, zombie: SocketAddrV4, target: Ipv4Addr, port: u16, context: &ScanContext) -> Result<(), HuginnError> {
+		let options = &context.raw;
+		let padding = raw::random_bytes(options.data_length);
+		let segment = raw::tcp_segment(*zombie.ip(), target, (zombie.port(), port), raw::random() as u32, SYN, &padding);
+		let packet = Ipv4Header {
+			source: *zombie.ip(),
+			destination: target,
+			protocol: PROTOCOL_TCP,
+			ttl: options.ttl.unwrap_or(raw::DEFAULT_TTL),
+			identification: raw::random() as u16,
+		}
+		.packet(&segment);
+		context.probes.sent();
+		raw::send(&self.sender, &packet, target, options).await?;
+		Ok(())
+	}
+}
+
+/// A zombie that passed the suitability test
+#[derive(Debug, Clone, Copy)]
+struct Zombie {
+	address: SocketAddrV4,
+	/// Whether its counter is written in the wrong byte order
+	swapped: bool,
+	/// Time the target's answer is given to reach the zombie before its counter is read again
+	settle: Duration,
+}
+
+impl Zombie {
+	/// Resolve the zombie and check that its counter can be used for idle scans
+	async fn test(spec: &str, context: &ScanContext) -> Result<Self, HuginnError> {
+		let (host, port) = parse_zombie(spec)?;
+		let address = SocketAddrV4::new(raw::resolve(host, context).await?, port);
+		let prober = Prober::open(*address.ip(), context)?;
+
+		let mut ids = Vec::new();
+		let mut round_trip = Duration::ZERO;
+		for _ in 0..TEST_PROBES {
+			let sent = Instant::now();
+			if let Some(id) = prober.counter(address, context).await? {
+				ids.push(id);
+				round_trip = round_trip.max(sent.elapsed());
+			}
+		}
+		if ids.len() < TEST_PROBES / 2 + 1 {
+			return Err(HuginnError::Config(format!(
+				"zombie {} answered {} of {} probes; it may be down or firewalled",
+				address,
+				ids.len(),
+				TEST_PROBES
+			)));
+		}
+		let sequence = classify(&ids);
+		debug!("Zombie {} IP IDs {:?} are {}", address, ids, sequence);
+		if !matches!(sequence, Sequence::Incremental | Sequence::ByteSwapped) {
+			return Err(HuginnError::Config(format!(
+				"zombie {} is unsuitable for idle scans: its IP ID sequence is {}",
+				address, sequence
+			)));
+		}
+		let settle = (round_trip * 4).max(MIN_SETTLE).min(context.timeout);
+		info!("Using {} as the idle scan zombie ({} IP IDs)", address, sequence);
+		Ok(Self {
+			address,
+			swapped: sequence == Sequence::ByteSwapped,
+			settle,
+		})
+	}
+
+	/// How far the counter advanced between two reads
+	fn increment(&self, before: u16, after: u16) -> u16 {
+		if self.swapped {
+			after.swap_bytes().wrapping_sub(before.swap_bytes())
+		} else {
+			after.wrapping_sub(before)
+		}
+	}
+}
+
+/// Idle scan plugin
+#[derive(Default)]
+pub struct IdleScanPlugin {
+	/// Zombie tested before the first target is scanned
+	zombie: OnceCell<Zombie>,
+}
+
+impl IdleScanPlugin {
+	/// Status of one port, with details when it could not be determined
+	async fn port_status(
+		&self,
+		prober: &Prober,
+		zombie: &Zombie,
+		target: Ipv4Addr,
+		port: u16,
+		context: &ScanContext,
+	) -> Result<(&'static str, Option<String>), HuginnError> {
+		let mut details = "the zombie stopped answering".to_string();
+		for _ in 0..=context.retries {
+			let Some(before) = prober.counter(zombie.address, context).await? else {
+				continue;
+			};
+			prober.spoof(zombie.address, target, port, context).await?;
+			tokio::time::sleep(zombie.settle).await;
+			let Some(after) = prober.counter(zombie.address, context).await? else {
+				continue;
+			};
+			match zombie.increment(before, after) {
+				1 => return Ok(("closed|filtered", None)),
+				2 => return Ok(("open", None)),
+				increment => details = format!("the zombie's IP ID advanced by {}; it is not idle", increment),
+			}
+		}
+		Ok(("unknown", Some(details)))
+	}
+}
+
+#[async_trait]
+impl Plugin for IdleScanPlugin {
+	fn name(&self) -> String {
+		"Idle Scanner".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::Idle
+	}
+
+	fn description(&self) -> String {
+		"TCP scan spoofed from a zombie host, reading port status from its IP ID counter".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"zombie",
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"ttl",
+			"data_length",
+			"mtu",
+		]
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		context.ports_or(DEFAULT_TCP_PORTS).len() * 3
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		let spec = context
+			.zombie
+			.as_deref()
+			.ok_or_else(|| HuginnError::Config("idle scans need a zombie host (zombie or --zombie)".to_string()))?;
+		let zombie = *self.zombie.get_or_try_init(|| Zombie::test(spec, context)).await?;
+		let address = raw::resolve(target, context).await?;
+		let prober = Prober::open(*zombie.address.ip(), context)?;
+
+		let mut results = Vec::new();
+		for &port in context.ports_or(DEFAULT_TCP_PORTS) {
+			if !context.scan_delay.is_zero() {
+				tokio::time::sleep(context.scan_delay).await;
+			}
+			let (status, details) = self.port_status(&prober, &zombie, address, port, context).await?;
+			results.push(ScanResult {
+				target: target.to_string(),
+				scan_type: self.scan_type().to_string(),
+				port: Some(port),
+				status: status.to_string(),
+				severity: None,
+				service: None,
+				version: None,
+				details,
+				techniques: if status == "open" { attack::for_port(port) } else { Vec::new() },
+				screenshot: None,
+				rtt: None,
+			});
+		}
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_classify() {
+		assert_eq!(classify(&[100, 101, 102, 104, 105, 106]), Sequence::Incremental);
+		assert_eq!(classify(&[65534, 65535, 0, 1]), Sequence::Incremental);
+		assert_eq!(classify(&[0x0100, 0x0200, 0x0300, 0x0500]), Sequence::ByteSwapped);
+		assert_eq!(classify(&[0, 0, 0, 0]), Sequence::Zero);
+		assert_eq!(classify(&[4817, 60211, 1322, 33987]), Sequence::Random);
+	}
+
+	#[test]
+	fn test_parse_zombie() {
+		assert_eq!(parse_zombie("192.0.2.7").unwrap(), ("192.0.2.7", 80));
+		assert_eq!(parse_zombie("printer.example.com:631").unwrap(), ("printer.example.com", 631));
+		assert!(parse_zombie("192.0.2.7:http").is_err());
+	}
+}
@@ -0,0 +1,397 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Legacy clear-text service detection plugin
+//!
+//! Confirms the legacy services that have no place on a modern network by a harmless probe of
+//! each, chosen by port:
+//!
+//! - TFTP on 69, over UDP, by a read request for a file that does not exist, answered with an
+//!   error from a port of the server's own
+//! - finger on 79, by the query for who is logged in
+//! - rlogin on 513 and rsh on 514, by their handshake for a made-up user, answered with a null
+//!   byte to go on or an error, before any password is asked for or command run
+//! - telnet on 23 and every other port, by the option negotiation or login prompt the server
+//!   opens with
+//!
+//! Each service confirmed is reported as open and as a high `legacy_service` finding.
+
+use crate::error::HuginnError;
+use crate::plugins::tls::random_bytes;
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+use async_trait::async_trait;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Ports probed when no port spec is configured
+const DEFAULT_PORTS: &[u16] = &[23, 69, 79, 513, 514];
+
+/// Largest answer read
+const MAX_ANSWER: usize = 8192;
+
+/// Telnet's interpret-as-command byte, which starts every negotiation
+const IAC: u8 = 0xff;
+
+/// Legacy service probed for on a port
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Service {
+	Telnet,
+	Tftp,
+	Finger,
+	Rlogin,
+	Rsh,
+}
+
+impl Service {
+	/// Service probed for on a port
+	fn on_port(port: u16) -> Service {
+		match port {
+			69 => Service::Tftp,
+			79 => Service::Finger,
+			513 => Service::Rlogin,
+			514 => Service::Rsh,
+			_ => Service::Telnet,
+		}
+	}
+
+	/// Name the service is reported as
+	fn name(self) -> &'static str {
+		match self {
+			Service::Telnet => "telnet",
+			Service::Tftp => "tftp",
+			Service::Finger => "finger",
+			Service::Rlogin => "rlogin",
+			Service::Rsh => "rsh",
+		}
+	}
+
+	/// Why the service is a risk
+	fn risk(self) -> &'static str {
+		match self {
+			Service::Telnet => "telnet sends logins and everything typed over it in clear text, readable by anyone on the path",
+			Service::Tftp => {
+				"TFTP reads and writes files without authentication, so anyone who guesses a file name, such as a device configuration, can fetch it"
+			},
+			Service::Finger => "finger tells anyone the accounts on the host and who is logged in",
+			Service::Rlogin => {
+				"rlogin sends logins in clear text and lets users of trusted hosts in without a password, trusting hosts by an address that can be spoofed"
+			},
+			Service::Rsh => {
+				"rsh runs commands for users of trusted hosts without a password, trusting hosts by an address that can be spoofed"
+			},
+		}
+	}
+
+	/// Techniques the service is relevant to
+	fn techniques(self) -> Vec<String> {
+		let techniques: &[&str] = match self {
+			Service::Telnet | Service::Rlogin | Service::Rsh => &["T1040", "T1133"],
+			Service::Tftp => &["T1602.002"],
+			Service::Finger => &["T1087"],
+		};
+		techniques.iter().map(|t| t.to_string()).collect()
+	}
+}
+
+/// A made-up user or file name, which the host has none of
+fn made_up() -> String {
+	let token: String = random_bytes(6).iter().map(|byte| format!("{:02x}", byte)).collect();
+	format!("huginn-{}", token)
+}
+
+/// Printable text of an answer, with telnet negotiations left out and lines joined
+fn text(data: &[u8]) -> String {
+	let mut printable = Vec::new();
+	let mut position = 0;
+	while position < data.len() {
+		match (data[position], data.get(position + 1)) {
+			// Subnegotiations run to IAC SE
+			(IAC, Some(250)) => {
+				let end = data[position..].windows(2).position(|pair| pair == [IAC, 240]);
+				position = end.map_or(data.len(), |end| position + end + 2);
+			},
+			(IAC, Some(251..=254)) => position += 3,
+			(IAC, _) => position += 2,
+			(byte, _) => {
+				printable.push(if byte.is_ascii_graphic() { byte } else { b' ' });
+				position += 1;
+			},
+		}
+	}
+	String::from_utf8_lossy(&printable).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Whether a server's opening negotiates telnet options
+fn negotiates(data: &[u8]) -> bool {
+	data.windows(2).any(|pair| pair[0] == IAC && matches!(pair[1], 250..=254))
+}
+
+/// A description, followed by the text a server sent, if any
+fn with_text(description: &str, text: &str) -> String {
+	if text.is_empty() { description.to_string() } else { format!("{}: {}", description, text) }
+}
+
+/// Details of the service a server's answer confirms, or `None` when it confirms none
+fn confirm(service: Service, data: &[u8]) -> Option<String> {
+	match service {
+		Service::Telnet => {
+			let text = text(data);
+			let lower = text.to_ascii_lowercase();
+			let prompts = ["login:", "username:", "password:"].iter().any(|prompt| lower.contains(prompt));
+			(negotiates(data) || prompts).then(|| with_text("telnet server", &text))
+		},
+		Service::Tftp => match data {
+			[0, 3, ..] => Some("read request for a made-up file answered with data".to_string()),
+			[0, 5, high, low, message @ ..] => Some(with_text(
+				&format!("read request for a made-up file answered with error {}", u16::from_be_bytes([*high, *low])),
+				&text(message.split(|&byte| byte == 0).next().unwrap_or_default()),
+			)),
+			_ => None,
+		},
+		Service::Finger => {
+			let printable = data.iter().all(|byte| byte.is_ascii_graphic() || byte.is_ascii_whitespace());
+			let first = data.split(|&byte| byte == b'\n').map(text).find(|line| !line.is_empty());
+			first.filter(|_| printable).map(|line| format!("answers who is logged in: {}", line))
+		},
+		Service::Rlogin | Service::Rsh => match data.split_first() {
+			Some((0, _)) => Some(format!("{} handshake accepted", service.name())),
+			Some((1, message)) => Some(with_text(&format!("{} handshake refused", service.name()), &text(message))),
+			_ => None,
+		},
+	}
+}
+
+/// The request that opens a probe of a service over TCP
+fn request(service: Service) -> Vec<u8> {
+	match service {
+		Service::Telnet | Service::Tftp => Vec::new(),
+		Service::Finger => b"\r\n".to_vec(),
+		// Client user, server user, and terminal; the server user is made up so that no trusted
+		// user is logged in
+		Service::Rlogin => format!("\0huginn\0{}\0xterm/38400\0", made_up()).into_bytes(),
+		// No stderr port, then client user, server user, and command, which is never run for a
+		// made-up user
+		Service::Rsh => format!("0\0huginn\0{}\0true\0", made_up()).into_bytes(),
+	}
+}
+
+/// Send a service's request over a connection and read the answer, to its end for finger,
+/// which closes after it, and as far as the first read for the others, which wait for more
+async fn converse(mut stream: impl AsyncRead + AsyncWrite + Unpin, service: Service) -> io::Result<Vec<u8>> {
+	let request = request(service);
+	if !request.is_empty() {
+		stream.write_all(&request).await?;
+		stream.flush().await?;
+	}
+	let mut data = Vec::new();
+	if service == Service::Finger {
+		stream.take(MAX_ANSWER as u64).read_to_end(&mut data).await?;
+	} else {
+		let mut buffer = vec![0; MAX_ANSWER];
+		let length = stream.read(&mut buffer).await?;
+		data.extend_from_slice(&buffer[..length]);
+	}
+	Ok(data)
+}
+
+/// TFTP read request for a made-up file, and the answer from any port of the server
+async fn tftp(target: &str, port: u16, context: &ScanContext) -> io::Result<Vec<u8>> {
+	let address = SocketAddr::new(context.resolve(target).await.map_err(io::Error::other)?, port);
+	let socket = context.transport.udp_unconnected(address, context).await?;
+	let mut request = vec![0, 1];
+	request.extend_from_slice(made_up().as_bytes());
+	request.extend_from_slice(b"\0octet\0");
+	socket.send_to(&request, address).await?;
+	let mut buffer = vec![0; MAX_ANSWER];
+	loop {
+		let (length, from) = socket.recv_from(&mut buffer).await?;
+		if from.ip() == address.ip() {
+			return Ok(buffer[..length].to_vec());
+		}
+	}
+}
+
+/// Results for the legacy service on a port, if it is confirmed
+async fn inspect(target: &str, port: u16, context: &ScanContext) -> Vec<ScanResult> {
+	let service = Service::on_port(port);
+	let answer = match service {
+		Service::Tftp if context.proxy.is_some() => return Vec::new(),
+		Service::Tftp => context.probe(|| tftp(target, port, context)).await,
+		_ => context.probe(|| async { converse(context.connect(target, port).await?, service).await }).await,
+	};
+	let Some(details) = answer.and_then(Result::ok).and_then(|data| confirm(service, &data)) else {
+		return Vec::new();
+	};
+	vec![
+		ScanResult {
+			target: target.to_string(),
+			scan_type: ScanType::LegacyServices.to_string(),
+			port: Some(port),
+			status: "open".to_string(),
+			severity: None,
+			service: Some(service.name().to_string()),
+			version: None,
+			details: Some(details),
+			techniques: Vec::new(),
+			screenshot: None,
+			rtt: None,
+		},
+		ScanResult {
+			target: target.to_string(),
+			scan_type: ScanType::LegacyServices.to_string(),
+			port: Some(port),
+			status: "legacy_service".to_string(),
+			severity: Some(Severity::High),
+			service: Some(service.name().to_string()),
+			version: None,
+			details: Some(service.risk().to_string()),
+			techniques: service.techniques(),
+			screenshot: None,
+			rtt: None,
+		},
+	]
+}
+
+/// Legacy clear-text service detection plugin
+pub struct LegacyServicesPlugin;
+
+#[async_trait]
+impl Plugin for LegacyServicesPlugin {
+	fn name(&self) -> String {
+		"Legacy Service Detector".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::LegacyServices
+	}
+
+	fn description(&self) -> String {
+		"Confirms TFTP, telnet, rlogin, rsh, and finger services with a harmless probe of each".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"proxy",
+			"max_connections",
+		]
+	}
+
+	fn proxy_capable(&self) -> bool {
+		true
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		context.ports_or(DEFAULT_PORTS).len()
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		// Resolve up front to fail early, but connect by name
+		if context.proxy.is_none() {
+			context.resolve(target).await?;
+		}
+
+		let (target, context) = (Arc::new(target.to_string()), Arc::new(context.clone()));
+		// A probe delay paces the probes one after another
+		let width = if context.scan_delay.is_zero() { context.max_connections } else { 1 };
+		let permits = Arc::new(Semaphore::new(width));
+		let mut probes = JoinSet::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			let (target, context, permits) = (target.clone(), context.clone(), permits.clone());
+			probes.spawn(async move {
+				let _permit = permits.acquire_owned().await;
+				inspect(&target, port, &context).await
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(joined) = probes.join_next().await {
+			results.extend(joined.map_err(io::Error::other)?);
+		}
+		results.sort_by_key(|r| r.port);
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scanner::Scanner;
+	use std::time::Duration;
+	use tokio::net::{TcpListener, UdpSocket};
+
+	#[test]
+	fn test_confirm() {
+		let telnet = b"\xff\xfd\x18\xff\xfd\x20\xff\xfa\x18\x01\xff\xf0\r\nUbuntu 22.04 LTS\r\nhost login: ";
+		assert_eq!(confirm(Service::Telnet, telnet).as_deref(), Some("telnet server: Ubuntu 22.04 LTS host login:"));
+		assert_eq!(confirm(Service::Telnet, b"SSH-2.0-OpenSSH_9.6\r\n"), None);
+
+		let error = b"\0\x05\0\x01File not found\0";
+		assert_eq!(
+			confirm(Service::Tftp, error).as_deref(),
+			Some("read request for a made-up file answered with error 1: File not found")
+		);
+		assert_eq!(confirm(Service::Tftp, b"\0\x04\0\x01"), None);
+
+		let finger = b"Login     Name       Tty      Idle  Login Time\r\nroot      root      *tty1        2d  Mar 3 09:12\r\n";
+		assert_eq!(
+			confirm(Service::Finger, finger).as_deref(),
+			Some("answers who is logged in: Login Name Tty Idle Login Time")
+		);
+		assert_eq!(confirm(Service::Finger, b"\x16\x03\x01\0"), None);
+
+		assert_eq!(confirm(Service::Rsh, b"\x01Permission denied.\n").as_deref(), Some("rsh handshake refused: Permission denied."));
+		assert_eq!(confirm(Service::Rlogin, b"\0").as_deref(), Some("rlogin handshake accepted"));
+		assert_eq!(confirm(Service::Rlogin, b""), None);
+	}
+
+	#[tokio::test]
+	async fn test_finds_services() {
+		let context = Scanner::builder().timeout(Duration::from_millis(500)).retries(0).context(ScanType::LegacyServices);
+
+		// A telnet server on a port of its own
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Ok((mut stream, _)) = listener.accept().await {
+				let _ = stream.write_all(b"\xff\xfb\x01\xff\xfb\x03Router login: ").await;
+			}
+		});
+		let results = inspect("127.0.0.1", port, &context).await;
+		let statuses: Vec<_> = results.iter().map(|r| (r.status.as_str(), r.service.as_deref(), r.severity)).collect();
+		assert_eq!(
+			statuses,
+			[("open", Some("telnet"), None), ("legacy_service", Some("telnet"), Some(Severity::High))]
+		);
+		assert_eq!(results[0].details.as_deref(), Some("telnet server: Router login:"));
+		assert_eq!(results[1].techniques, ["T1040", "T1133"]);
+
+		// A TFTP server, which answers from a port of its own
+		let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+		let port = server.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			let mut buffer = [0; 512];
+			while let Ok((length, peer)) = server.recv_from(&mut buffer).await {
+				assert!(buffer[..length].starts_with(b"\0\x01huginn-") && buffer[..length].ends_with(b"\0octet\0"));
+				let transfer = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+				transfer.send_to(b"\0\x05\0\x01File not found\0", peer).await.unwrap();
+			}
+		});
+		let answer = tftp("127.0.0.1", port, &context).await.unwrap();
+		assert!(confirm(Service::Tftp, &answer).is_some());
+	}
+}
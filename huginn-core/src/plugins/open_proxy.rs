@@ -0,0 +1,429 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Open proxy detection plugin
+//!
+//! Asks each port to fetch the front page of the canary domain (`canary`, example.com by
+//! default) the ways a proxy can be asked to:
+//!
+//! - an HTTP forward request, `GET http://canary/`
+//! - an HTTP `CONNECT canary:80` tunnel, when the port speaks HTTP but does not forward
+//! - a SOCKS5 connection without authentication, then a SOCKS4a one, when it does not speak HTTP
+//!
+//! A port relays when the page that comes back is the one Huginn fetches from the canary itself,
+//! with the same status and title, so web servers that answer every request with a page of their
+//! own are not mistaken for proxies. Each relay is reported as open and as a high `open_proxy`
+//! finding; proxies that require authentication, or that speak SOCKS but refuse to reach the
+//! canary, are reported as open without a finding. With a canary whose web server logs the
+//! operator can read, every relayed request also shows up there, from the proxy's address.
+
+use crate::error::HuginnError;
+use crate::plugins::http::{self, Response};
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+use crate::proxy::{Proxy, ProxyKind};
+use async_trait::async_trait;
+use std::io;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::{OnceCell, Semaphore};
+use tokio::task::JoinSet;
+
+/// Ports probed when no port spec is configured
+const DEFAULT_PORTS: &[u16] = &[80, 1080, 3128, 8000, 8080, 8118, 8888, 9050];
+
+/// Bytes of a page without a title that must match
+const PAGE_START: usize = 256;
+
+/// The canary's front page, as far as it identifies the page
+#[derive(Debug, Clone, PartialEq)]
+struct Page {
+	status: u16,
+	title: Option<String>,
+	start: Vec<u8>,
+}
+
+impl Page {
+	fn of(response: &Response) -> Self {
+		Self {
+			status: response.status,
+			title: http::title(&String::from_utf8_lossy(&response.body)),
+			start: response.body[..response.body.len().min(PAGE_START)].to_vec(),
+		}
+	}
+
+	/// Whether a response is this page: the same status, and the same title, or the same start
+	/// when the page has no title
+	fn is(&self, response: &Response) -> bool {
+		let other = Page::of(response);
+		self.status == other.status
+			&& match &self.title {
+				Some(_) => self.title == other.title,
+				None => self.start == other.start,
+			}
+	}
+}
+
+/// Way a proxy is asked to relay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Protocol {
+	Forward,
+	Connect,
+	Socks5,
+	Socks4,
+}
+
+impl Protocol {
+	/// Service a proxy speaking the protocol is reported as
+	fn service(self) -> &'static str {
+		match self {
+			Protocol::Forward | Protocol::Connect => "http-proxy",
+			Protocol::Socks5 => "socks5",
+			Protocol::Socks4 => "socks4",
+		}
+	}
+
+	/// What a proxy that relays by the protocol does
+	fn relay(self, canary: &str) -> String {
+		match self {
+			Protocol::Forward => format!("forwards HTTP requests for http://{}/ without authentication", canary),
+			Protocol::Connect => format!("tunnels HTTP CONNECT requests to {}:80 without authentication", canary),
+			Protocol::Socks5 => format!("relays SOCKS5 connections to {}:80 without authentication", canary),
+			Protocol::Socks4 => format!("relays SOCKS4a connections to {}:80 without authentication", canary),
+		}
+	}
+}
+
+/// What a proxy did with a request for the canary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+	/// Fetched the canary's page
+	Relays(Protocol),
+	/// Asked for credentials
+	Authenticates(Protocol),
+	/// Spoke the protocol but did not fetch the canary's page
+	Refuses(Protocol),
+}
+
+/// A request for the canary's front page, as a proxy is sent it when `forward`, or as the
+/// canary itself is sent it
+fn page_request(canary: &str, forward: bool) -> String {
+	let path = if forward { format!("http://{}/", canary) } else { "/".to_string() };
+	format!("{}Connection: close\r\n\r\n", http::head("GET", canary, 80, false, &path))
+}
+
+/// The canary's front page, fetched over an HTTP forward request to a port
+async fn forward(target: &str, port: u16, canary: &str, context: &ScanContext) -> io::Result<Response> {
+	http::exchange(context.connect(target, port).await?, &page_request(canary, true)).await
+}
+
+/// The canary's front page, fetched through an HTTP CONNECT tunnel opened by a port
+async fn tunnel(target: &str, port: u16, canary: &str, context: &ScanContext) -> io::Result<Response> {
+	let mut stream = context.connect(target, port).await?;
+	Proxy::new(ProxyKind::Http, target, port).handshake(&mut stream, canary, 80).await?;
+	http::exchange(stream, &page_request(canary, false)).await
+}
+
+/// The authentication method a SOCKS5 server picks when offered none, or `None` when the port
+/// does not speak SOCKS5
+async fn socks5_method(target: &str, port: u16, context: &ScanContext) -> io::Result<Option<u8>> {
+	let mut stream = context.connect(target, port).await?;
+	stream.write_all(&[0x05, 0x01, 0x00]).await?;
+	let mut choice = [0; 2];
+	stream.read_exact(&mut choice).await?;
+	Ok((choice[0] == 0x05).then_some(choice[1]))
+}
+
+/// The canary's front page, fetched through a SOCKS5 connection opened by a port
+async fn socks5(target: &str, port: u16, canary: &str, context: &ScanContext) -> io::Result<Response> {
+	let mut stream = context.connect(target, port).await?;
+	Proxy::new(ProxyKind::Socks5, target, port).handshake(&mut stream, canary, 80).await?;
+	http::exchange(stream, &page_request(canary, false)).await
+}
+
+/// The canary's front page, fetched through a SOCKS4a connection opened by a port, or `None`
+/// when the port refuses the connection
+async fn socks4(target: &str, port: u16, canary: &str, context: &ScanContext) -> io::Result<Option<Response>> {
+	let mut stream = context.connect(target, port).await?;
+	// Connect to port 80 of the invalid address 0.0.0.1, which asks the proxy to resolve the
+	// host name that follows the user ID
+	let mut request = vec![0x04, 0x01, 0x00, 0x50, 0x00, 0x00, 0x00, 0x01];
+	request.extend_from_slice(b"huginn\0");
+	request.extend_from_slice(canary.as_bytes());
+	request.push(0);
+	stream.write_all(&request).await?;
+	let mut reply = [0; 8];
+	stream.read_exact(&mut reply).await?;
+	match reply {
+		[0x00, 0x5a, ..] => Ok(Some(http::exchange(stream, &page_request(canary, false)).await?)),
+		[0x00, 0x5b..=0x5d, ..] => Ok(None),
+		_ => Err(io::Error::new(io::ErrorKind::InvalidData, "not a SOCKS4 proxy")),
+	}
+}
+
+/// What the port does with requests for the canary, or `None` when it speaks no proxy protocol
+async fn verdict(target: &str, port: u16, canary: &str, reference: &Page, context: &ScanContext) -> Option<Verdict> {
+	match context.probe(|| forward(target, port, canary, context)).await {
+		Some(Ok(response)) if response.status == 407 => return Some(Verdict::Authenticates(Protocol::Forward)),
+		Some(Ok(response)) if reference.is(&response) => return Some(Verdict::Relays(Protocol::Forward)),
+		// A web server that does not forward may still tunnel
+		Some(Ok(_)) => {
+			let tunneled = context.probe(|| tunnel(target, port, canary, context)).await.and_then(Result::ok);
+			return tunneled.filter(|response| reference.is(response)).map(|_| Verdict::Relays(Protocol::Connect));
+		},
+		Some(Err(e)) if e.kind() == io::ErrorKind::ConnectionRefused => return None,
+		_ => {},
+	}
+
+	match context.probe(|| socks5_method(target, port, context)).await.and_then(Result::ok).flatten() {
+		Some(0x00) => {
+			let relayed = context.probe(|| socks5(target, port, canary, context)).await.and_then(Result::ok);
+			return Some(if relayed.is_some_and(|response| reference.is(&response)) {
+				Verdict::Relays(Protocol::Socks5)
+			} else {
+				Verdict::Refuses(Protocol::Socks5)
+			});
+		},
+		Some(_) => return Some(Verdict::Authenticates(Protocol::Socks5)),
+		None => {},
+	}
+
+	match context.probe(|| socks4(target, port, canary, context)).await.and_then(Result::ok)? {
+		Some(response) if reference.is(&response) => Some(Verdict::Relays(Protocol::Socks4)),
+		_ => Some(Verdict::Refuses(Protocol::Socks4)),
+	}
+}
+
+/// Results for the proxy on a port, if there is one
+async fn inspect(target: &str, port: u16, reference: &Page, context: &ScanContext) -> Vec<ScanResult> {
+	let canary = context.canary.as_str();
+	let Some(verdict) = verdict(target, port, canary, reference, context).await else {
+		return Vec::new();
+	};
+	let (protocol, details) = match verdict {
+		Verdict::Relays(protocol) => (protocol, protocol.relay(canary)),
+		Verdict::Authenticates(protocol) => (protocol, "proxy requires authentication".to_string()),
+		Verdict::Refuses(protocol) => (protocol, format!("proxy refused to reach {}", canary)),
+	};
+	let result = |status: &str, severity, details: String, techniques: Vec<String>| ScanResult {
+		target: target.to_string(),
+		scan_type: ScanType::OpenProxy.to_string(),
+		port: Some(port),
+		status: status.to_string(),
+		severity,
+		service: Some(protocol.service().to_string()),
+		version: None,
+		details: Some(details),
+		techniques,
+		screenshot: None,
+		rtt: None,
+	};
+
+	let mut results = vec![result("open", None, details.clone(), Vec::new())];
+	if matches!(verdict, Verdict::Relays(_)) {
+		results.push(result(
+			"open_proxy",
+			Some(Severity::High),
+			format!("{}, so anyone can send attacks, spam, and abuse from the host's address", details),
+			vec!["T1090".to_string()],
+		));
+	}
+	results
+}
+
+/// Open proxy detection plugin
+#[derive(Default)]
+pub struct OpenProxyPlugin {
+	/// The canary's front page, fetched before the first target is scanned
+	reference: OnceCell<Page>,
+}
+
+impl OpenProxyPlugin {
+	/// The canary's front page, as Huginn fetches it directly
+	async fn reference(&self, context: &ScanContext) -> Result<&Page, HuginnError> {
+		self.reference
+			.get_or_try_init(|| async {
+				let canary = context.canary.as_str();
+				let fetch = || async { http::exchange(context.connect(canary, 80).await?, &page_request(canary, false)).await };
+				match context.probe(fetch).await {
+					Some(Ok(response)) => Ok(Page::of(&response)),
+					Some(Err(e)) => Err(HuginnError::Plugin {
+						plugin: self.name(),
+						source: format!("cannot fetch the front page of the canary {}: {}", canary, e).into(),
+					}),
+					None => Err(context.timed_out(canary)),
+				}
+			})
+			.await
+	}
+}
+
+#[async_trait]
+impl Plugin for OpenProxyPlugin {
+	fn name(&self) -> String {
+		"Open Proxy Detector".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::OpenProxy
+	}
+
+	fn description(&self) -> String {
+		"Finds HTTP and SOCKS proxies that relay requests for a canary domain without authentication".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"canary",
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"proxy",
+			"max_connections",
+		]
+	}
+
+	fn proxy_capable(&self) -> bool {
+		true
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		context.ports_or(DEFAULT_PORTS).len()
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		// Resolve up front to fail early, but connect by name
+		if context.proxy.is_none() {
+			context.resolve(target).await?;
+		}
+		let reference = Arc::new(self.reference(context).await?.clone());
+
+		let (target, context) = (Arc::new(target.to_string()), Arc::new(context.clone()));
+		// A probe delay paces the probes one after another
+		let width = if context.scan_delay.is_zero() { context.max_connections } else { 1 };
+		let permits = Arc::new(Semaphore::new(width));
+		let mut probes = JoinSet::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			let (target, context, permits, reference) = (target.clone(), context.clone(), permits.clone(), reference.clone());
+			probes.spawn(async move {
+				let _permit = permits.acquire_owned().await;
+				inspect(&target, port, &reference, &context).await
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(joined) = probes.join_next().await {
+			results.extend(joined.map_err(io::Error::other)?);
+		}
+		results.sort_by_key(|r| r.port);
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scanner::Scanner;
+	use std::time::Duration;
+	use tokio::io::AsyncRead;
+	use tokio::net::TcpListener;
+
+	const CANARY: &str = "<html><head><title>Canary</title></head><body>huginn</body></html>";
+
+	fn response(status: u16, body: &str) -> String {
+		format!("HTTP/1.1 {} OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body)
+	}
+
+	/// Read from a stream until the end of a request's head
+	async fn read_head(stream: &mut (impl AsyncRead + Unpin)) -> String {
+		let mut head = Vec::new();
+		while !head.ends_with(b"\r\n\r\n") {
+			head.push(stream.read_u8().await.unwrap());
+		}
+		String::from_utf8(head).unwrap()
+	}
+
+	#[test]
+	fn test_page() {
+		let reference = Page::of(&http::parse(response(200, CANARY).as_bytes(), true).unwrap());
+		assert_eq!(reference.title.as_deref(), Some("Canary"));
+		assert!(reference.is(&http::parse(response(200, CANARY).as_bytes(), true).unwrap()));
+		let other = "<html><head><title>Welcome to nginx!</title></head></html>";
+		assert!(!reference.is(&http::parse(response(200, other).as_bytes(), true).unwrap()));
+		assert!(!reference.is(&http::parse(response(404, CANARY).as_bytes(), true).unwrap()));
+	}
+
+	#[tokio::test]
+	async fn test_finds_proxies() {
+		let context = Scanner::builder()
+			.timeout(Duration::from_millis(500))
+			.retries(0)
+			.canary("canary.test")
+			.context(ScanType::OpenProxy);
+		let reference = Page::of(&http::parse(response(200, CANARY).as_bytes(), true).unwrap());
+
+		// A forward proxy, which fetches whatever absolute URL it is asked for
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Ok((mut stream, _)) = listener.accept().await {
+				let head = read_head(&mut stream).await;
+				assert!(head.starts_with("GET http://canary.test/ HTTP/1.1\r\nHost: canary.test\r\n"));
+				let _ = stream.write_all(response(200, CANARY).as_bytes()).await;
+			}
+		});
+		let results = inspect("127.0.0.1", port, &reference, &context).await;
+		let statuses: Vec<_> = results.iter().map(|r| (r.status.as_str(), r.service.as_deref(), r.severity)).collect();
+		assert_eq!(
+			statuses,
+			[("open", Some("http-proxy"), None), ("open_proxy", Some("http-proxy"), Some(Severity::High))]
+		);
+		assert_eq!(
+			results[0].details.as_deref(),
+			Some("forwards HTTP requests for http://canary.test/ without authentication")
+		);
+		assert_eq!(results[1].techniques, ["T1090"]);
+
+		// A web server that answers every request with its own page is not a proxy
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Ok((mut stream, _)) = listener.accept().await {
+				read_head(&mut stream).await;
+				let _ = stream.write_all(response(200, "<title>Router</title>").as_bytes()).await;
+			}
+		});
+		assert!(inspect("127.0.0.1", port, &reference, &context).await.is_empty());
+
+		// A SOCKS5 proxy without authentication
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Ok((mut stream, _)) = listener.accept().await {
+				let mut greeting = [0; 3];
+				if stream.read_exact(&mut greeting).await.is_err() || greeting != [0x05, 0x01, 0x00] {
+					continue;
+				}
+				stream.write_all(&[0x05, 0x00]).await.unwrap();
+				let mut request = [0; 5 + 11 + 2];
+				if stream.read_exact(&mut request).await.is_err() {
+					continue;
+				}
+				assert_eq!(&request[5..16], b"canary.test");
+				stream.write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0]).await.unwrap();
+				read_head(&mut stream).await;
+				stream.write_all(response(200, CANARY).as_bytes()).await.unwrap();
+			}
+		});
+		let results = inspect("127.0.0.1", port, &reference, &context).await;
+		let statuses: Vec<_> = results.iter().map(|r| (r.status.as_str(), r.service.as_deref())).collect();
+		assert_eq!(statuses, [("open", Some("socks5")), ("open_proxy", Some("socks5"))]);
+	}
+}
@@ -0,0 +1,290 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Open resolver detection plugin
+//!
+//! Asks each port, over UDP, to resolve a name under the canary domain (`canary`, example.com by
+//! default) that nobody has asked for before: `huginn-<random>.<canary>`, with recursion desired.
+//! A server that recurses for anyone answers it with recursion available and without authority,
+//! with the name's address or an NXDOMAIN from the canary's name servers. Each such server is
+//! reported as open and as a medium `open_resolver` finding, since open resolvers amplify
+//! reflection attacks; servers that refuse recursion, or fail at it, are reported as open without
+//! a finding. With a canary whose name server logs the operator can read, every recursive lookup
+//! also shows up there, from the resolver's address.
+
+use crate::error::HuginnError;
+use crate::plugins::tls::random_bytes;
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+use async_trait::async_trait;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Ports probed when no port spec is configured
+const DEFAULT_PORTS: &[u16] = &[53];
+
+/// Largest answer read
+const MAX_ANSWER: usize = 4096;
+
+/// Header flag of a response
+const QR: u16 = 0x8000;
+
+/// Header flag of an authoritative answer
+const AA: u16 = 0x0400;
+
+/// Header flag of a query that asks for recursion
+const RD: u16 = 0x0100;
+
+/// Header flag of an answer from a server that offers recursion
+const RA: u16 = 0x0080;
+
+/// A name under the canary that nobody has asked for before, so no resolver has it cached
+fn fresh_name(canary: &str) -> String {
+	let token: String = random_bytes(6).iter().map(|byte| format!("{:02x}", byte)).collect();
+	format!("huginn-{}.{}", token, canary.trim_end_matches('.'))
+}
+
+/// A recursive query for a name's address
+fn query(id: u16, name: &str) -> Vec<u8> {
+	let mut message = Vec::with_capacity(name.len() + 18);
+	message.extend_from_slice(&id.to_be_bytes());
+	message.extend_from_slice(&RD.to_be_bytes());
+	// One question, and no answer, authority, or additional records
+	message.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+	for label in name.split('.') {
+		message.push(label.len() as u8);
+		message.extend_from_slice(label.as_bytes());
+	}
+	message.push(0);
+	// Type A, class IN
+	message.extend_from_slice(&[0, 1, 0, 1]);
+	message
+}
+
+/// How a name server answered a recursive query
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Answer {
+	/// Resolved the name, or found that it does not exist, for anyone
+	Recurses(&'static str),
+	/// Answered, but not by recursing
+	Declines(String),
+}
+
+/// Response codes, by number
+fn rcode(code: u16) -> String {
+	match code {
+		0 => "NOERROR".to_string(),
+		1 => "FORMERR".to_string(),
+		2 => "SERVFAIL".to_string(),
+		3 => "NXDOMAIN".to_string(),
+		4 => "NOTIMP".to_string(),
+		5 => "REFUSED".to_string(),
+		code => format!("RCODE {}", code),
+	}
+}
+
+/// How a server answered the query with an ID, or `None` when the data is not its answer
+fn answer(data: &[u8], id: u16) -> Option<Answer> {
+	if data.len() < 12 || u16::from_be_bytes([data[0], data[1]]) != id {
+		return None;
+	}
+	let flags = u16::from_be_bytes([data[2], data[3]]);
+	if flags & QR == 0 {
+		return None;
+	}
+	let answers = u16::from_be_bytes([data[6], data[7]]);
+	let code = flags & 0x000f;
+	let recursed = flags & RA != 0 && flags & AA == 0;
+	Some(match code {
+		0 if recursed && answers > 0 => Answer::Recurses("resolved"),
+		3 if recursed => Answer::Recurses("answered NXDOMAIN for"),
+		5 => Answer::Declines("refuses recursion".to_string()),
+		_ if flags & RA == 0 => Answer::Declines(format!("answers without recursion ({})", rcode(code))),
+		_ => Answer::Declines(format!("recursion failed ({})", rcode(code))),
+	})
+}
+
+/// Send the query for a name to a port and read the answer
+async fn ask(target: &str, port: u16, id: u16, name: &str, context: &ScanContext) -> io::Result<Answer> {
+	let address = SocketAddr::new(context.resolve(target).await.map_err(io::Error::other)?, port);
+	let socket = context.transport.udp(address, context).await?;
+	socket.send_to(&query(id, name), address).await?;
+	let mut buffer = vec![0; MAX_ANSWER];
+	loop {
+		let (length, from) = socket.recv_from(&mut buffer).await?;
+		if from == address
+			&& let Some(answer) = answer(&buffer[..length], id)
+		{
+			return Ok(answer);
+		}
+	}
+}
+
+/// Results for the name server on a port, if one answers
+async fn inspect(target: &str, port: u16, context: &ScanContext) -> Vec<ScanResult> {
+	let name = fresh_name(&context.canary);
+	let id = random_bytes(2);
+	let id = u16::from_be_bytes([id[0], id[1]]);
+	let Some(Ok(answer)) = context.probe(|| ask(target, port, id, &name, context)).await else {
+		return Vec::new();
+	};
+	let result = |status: &str, severity, details: String, techniques: Vec<String>| ScanResult {
+		target: target.to_string(),
+		scan_type: ScanType::OpenResolver.to_string(),
+		port: Some(port),
+		status: status.to_string(),
+		severity,
+		service: Some("domain".to_string()),
+		version: None,
+		details: Some(details),
+		techniques,
+		screenshot: None,
+		rtt: None,
+	};
+	match answer {
+		Answer::Recurses(outcome) => {
+			let details = format!("recursive resolver {} {} for anyone", outcome, name);
+			vec![
+				result("open", None, details.clone(), Vec::new()),
+				result(
+					"open_resolver",
+					Some(Severity::Medium),
+					format!("{}, so it can be used to amplify reflection attacks and to probe what others resolve", details),
+					vec!["T1498.002".to_string()],
+				),
+			]
+		},
+		Answer::Declines(details) => vec![result("open", None, format!("name server {}", details), Vec::new())],
+	}
+}
+
+/// Open resolver detection plugin
+pub struct OpenResolverPlugin;
+
+#[async_trait]
+impl Plugin for OpenResolverPlugin {
+	fn name(&self) -> String {
+		"Open Resolver Detector".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::OpenResolver
+	}
+
+	fn description(&self) -> String {
+		"Finds DNS servers that resolve names under a canary domain for anyone".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"canary",
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+		]
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		context.ports_or(DEFAULT_PORTS).len()
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		context.resolve(target).await?;
+
+		let (target, context) = (Arc::new(target.to_string()), Arc::new(context.clone()));
+		// A probe delay paces the probes one after another
+		let width = if context.scan_delay.is_zero() { context.max_connections } else { 1 };
+		let permits = Arc::new(Semaphore::new(width));
+		let mut probes = JoinSet::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			let (target, context, permits) = (target.clone(), context.clone(), permits.clone());
+			probes.spawn(async move {
+				let _permit = permits.acquire_owned().await;
+				inspect(&target, port, &context).await
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(joined) = probes.join_next().await {
+			results.extend(joined.map_err(io::Error::other)?);
+		}
+		results.sort_by_key(|r| r.port);
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scanner::Scanner;
+	use std::time::Duration;
+	use tokio::net::UdpSocket;
+
+	/// An answer to a query, with the given flags and answer count
+	fn reply(query: &[u8], flags: u16, answers: u16) -> Vec<u8> {
+		let mut reply = query.to_vec();
+		reply[2..4].copy_from_slice(&flags.to_be_bytes());
+		reply[6..8].copy_from_slice(&answers.to_be_bytes());
+		reply
+	}
+
+	#[test]
+	fn test_answer() {
+		let query = query(0x1234, "huginn-00.canary.test");
+		assert_eq!(&query[..4], &[0x12, 0x34, 0x01, 0x00]);
+		assert_eq!(&query[12..22], b"\x09huginn-00");
+
+		assert_eq!(answer(&reply(&query, QR | RD | RA, 1), 0x1234), Some(Answer::Recurses("resolved")));
+		assert_eq!(answer(&reply(&query, QR | RD | RA | 3, 0), 0x1234), Some(Answer::Recurses("answered NXDOMAIN for")));
+		assert_eq!(
+			answer(&reply(&query, QR | RD | RA | 5, 0), 0x1234),
+			Some(Answer::Declines("refuses recursion".to_string()))
+		);
+		// An authoritative server for the canary answers without recursing
+		assert_eq!(
+			answer(&reply(&query, QR | AA | RD | 3, 0), 0x1234),
+			Some(Answer::Declines("answers without recursion (NXDOMAIN)".to_string()))
+		);
+		assert_eq!(
+			answer(&reply(&query, QR | RD | RA | 2, 0), 0x1234),
+			Some(Answer::Declines("recursion failed (SERVFAIL)".to_string()))
+		);
+		assert_eq!(answer(&reply(&query, QR | RD | RA, 1), 0x4321), None);
+		assert_eq!(answer(&query, 0x1234), None);
+	}
+
+	#[tokio::test]
+	async fn test_finds_resolvers() {
+		let context = Scanner::builder()
+			.timeout(Duration::from_millis(500))
+			.retries(0)
+			.canary("canary.test")
+			.context(ScanType::OpenResolver);
+
+		// A resolver that recurses for anyone, and finds the fresh name does not exist
+		let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+		let port = server.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			let mut buffer = [0; 512];
+			while let Ok((length, peer)) = server.recv_from(&mut buffer).await {
+				assert!(buffer[..length].windows(13).any(|label| label == b"\x06canary\x04test\0"));
+				server.send_to(&reply(&buffer[..length], QR | RD | RA | 3, 0), peer).await.unwrap();
+			}
+		});
+		let results = inspect("127.0.0.1", port, &context).await;
+		let statuses: Vec<_> = results.iter().map(|r| (r.status.as_str(), r.severity)).collect();
+		assert_eq!(statuses, [("open", None), ("open_resolver", Some(Severity::Medium))]);
+		assert!(results[0].details.as_deref().unwrap().starts_with("recursive resolver answered NXDOMAIN for huginn-"));
+		assert_eq!(results[1].techniques, ["T1498.002"]);
+	}
+}
@@ -0,0 +1,411 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Passive discovery plugin
+//!
+//! Listens on a network interface for the configured capture duration and infers, from the
+//! traffic it observes, which hosts are live, which services they offer, and how much they talk,
+//! without sending a single probe. It is meant for networks where active scanning is forbidden.
+//!
+//! A host is live once it sends a packet or answers ARP. A TCP port is open once the host is
+//! seen completing a handshake from it (a SYN-ACK), and a UDP port is counted as a service once
+//! the host sends from a port below 1024 to an ephemeral one. Traffic is captured once, the first
+//! time the plugin is run, and every target is answered from that capture; targets that were not
+//! seen have no results.
+//!
+//! Listening on an interface needs capture privileges. Given a saved capture instead, in the
+//! classic pcap format with Ethernet frames, the plugin reads it and needs none.
+
+use crate::attack;
+use crate::error::HuginnError;
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType};
+use async_trait::async_trait;
+use log::{debug, info};
+use pnet_datalink::{Channel, NetworkInterface};
+use pnet_packet::Packet;
+use pnet_packet::arp::ArpPacket;
+use pnet_packet::ethernet::{EtherTypes, EthernetPacket};
+use pnet_packet::ip::{IpNextHeaderProtocol, IpNextHeaderProtocols};
+use pnet_packet::ipv4::Ipv4Packet;
+use pnet_packet::ipv6::Ipv6Packet;
+use pnet_packet::tcp::{TcpFlags, TcpPacket};
+use pnet_packet::udp::UdpPacket;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io;
+use std::net::IpAddr;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+/// Lowest ephemeral port; UDP sent from a lower port to one at or above it is taken as a reply
+const EPHEMERAL_PORTS: u16 = 1024;
+
+/// What was seen of one host
+#[derive(Debug, Default)]
+struct Host {
+	packets: u64,
+	bytes: u64,
+	peers: HashSet<IpAddr>,
+	mac: Option<String>,
+	tcp: BTreeSet<u16>,
+	udp: BTreeSet<u16>,
+}
+
+/// Everything inferred from a capture
+#[derive(Debug, Default)]
+struct Observations {
+	hosts: HashMap<IpAddr, Host>,
+	duration: Duration,
+}
+
+impl Observations {
+	/// Record a packet sent by a host
+	fn sent(&mut self, source: IpAddr, destination: IpAddr, length: usize, mac: Option<String>) {
+		let host = self.hosts.entry(source).or_default();
+		host.packets += 1;
+		host.bytes += length as u64;
+		host.peers.insert(destination);
+		host.mac = host.mac.take().or(mac);
+	}
+
+	/// Record the transport-layer facts of a packet a host sent
+	fn transport(&mut self, source: IpAddr, protocol: IpNextHeaderProtocol, payload: &[u8]) {
+		let host = self.hosts.entry(source).or_default();
+		match protocol {
+			IpNextHeaderProtocols::Tcp => {
+				if let Some(tcp) = TcpPacket::new(payload)
+					&& tcp.get_flags() & (TcpFlags::SYN | TcpFlags::ACK) == TcpFlags::SYN | TcpFlags::ACK
+				{
+					host.tcp.insert(tcp.get_source());
+				}
+			},
+			IpNextHeaderProtocols::Udp => {
+				if let Some(udp) = UdpPacket::new(payload)
+					&& udp.get_source() < EPHEMERAL_PORTS
+					&& udp.get_destination() >= EPHEMERAL_PORTS
+				{
+					host.udp.insert(udp.get_source());
+				}
+			},
+			_ => {},
+		}
+	}
+
+	/// Record what an Ethernet frame reveals
+	fn observe(&mut self, frame: &[u8]) {
+		let Some(ethernet) = EthernetPacket::new(frame) else {
+			return;
+		};
+		let mac = Some(ethernet.get_source().to_string());
+		match ethernet.get_ethertype() {
+			EtherTypes::Ipv4 => {
+				if let Some(ip) = Ipv4Packet::new(ethernet.payload()) {
+					let source = IpAddr::V4(ip.get_source());
+					self.sent(source, IpAddr::V4(ip.get_destination()), frame.len(), mac);
+					self.transport(source, ip.get_next_level_protocol(), ip.payload());
+				}
+			},
+			EtherTypes::Ipv6 => {
+				if let Some(ip) = Ipv6Packet::new(ethernet.payload()) {
+					let source = IpAddr::V6(ip.get_source());
+					self.sent(source, IpAddr::V6(ip.get_destination()), frame.len(), mac);
+					self.transport(source, ip.get_next_header(), ip.payload());
+				}
+			},
+			EtherTypes::Arp => {
+				if let Some(arp) = ArpPacket::new(ethernet.payload()) {
+					let source = IpAddr::V4(arp.get_sender_proto_addr());
+					if !source.is_unspecified() {
+						self.sent(source, IpAddr::V4(arp.get_target_proto_addr()), frame.len(), mac);
+					}
+				}
+			},
+			_ => {},
+		}
+	}
+
+	/// Results for a target: nothing when it was not seen
+	fn results(&self, target: &str, address: IpAddr) -> Vec<ScanResult> {
+		let Some(host) = self.hosts.get(&address) else {
+			return Vec::new();
+		};
+		let result = |port: Option<u16>, status: &str, details: String| ScanResult {
+			target: target.to_string(),
+			scan_type: ScanType::Passive.to_string(),
+			port,
+			status: status.to_string(),
+			severity: None,
+			service: None,
+			version: None,
+			details: Some(details),
+			techniques: port.map(attack::for_port).unwrap_or_default(),
+			screenshot: None,
+			rtt: None,
+		};
+
+		let mut details = format!(
+			"sent {} packets ({} bytes) to {} peers in {:.0?}",
+			host.packets,
+			host.bytes,
+			host.peers.len(),
+			self.duration
+		);
+		if let Some(mac) = &host.mac {
+			details.push_str(&format!(" from {}", mac));
+		}
+		let mut results = vec![result(None, "up", details)];
+		results.extend(host.tcp.iter().map(|&port| result(Some(port), "open", "tcp handshake observed".to_string())));
+		results.extend(host.udp.iter().map(|&port| result(Some(port), "open", "udp replies observed".to_string())));
+		results
+	}
+}
+
+/// The interface to capture on: the named one, or else the first that is up, is not loopback,
+/// and has an address
+fn interface(name: Option<&str>) -> Result<NetworkInterface, HuginnError> {
+	let interfaces = pnet_datalink::interfaces();
+	match name {
+		Some(name) => interfaces
+			.into_iter()
+			.find(|i| i.name == name)
+			.ok_or_else(|| HuginnError::Config(format!("no network interface named {}", name))),
+		None => interfaces
+			.into_iter()
+			.find(|i| i.is_up() && !i.is_loopback() && !i.ips.is_empty())
+			.ok_or_else(|| HuginnError::Config("no network interface is up to capture on".to_string())),
+	}
+}
+
+/// Capture traffic on an interface for a duration
+fn capture(interface: &NetworkInterface, duration: Duration) -> Result<Observations, HuginnError> {
+	let config = pnet_datalink::Config {
+		read_buffer_size: 65536,
+		read_timeout: Some(Duration::from_millis(100)),
+		promiscuous: true,
+		..Default::default()
+	};
+	let mut receiver = match pnet_datalink::channel(interface, config) {
+		Ok(Channel::Ethernet(_, receiver)) => receiver,
+		Ok(_) => return Err(HuginnError::Config(format!("{} is not an Ethernet interface", interface.name))),
+		Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+			return Err(HuginnError::PermissionDenied(format!(
+				"capturing on {} needs root or CAP_NET_RAW",
+				interface.name
+			)));
+		},
+		Err(e) => return Err(e.into()),
+	};
+
+	let started = Instant::now();
+	let mut observations = Observations::default();
+	while started.elapsed() < duration {
+		match receiver.next() {
+			Ok(frame) => observations.observe(frame),
+			Err(e) if matches!(e.kind(), io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted) => {},
+			Err(e) => return Err(e.into()),
+		}
+	}
+	observations.duration = started.elapsed();
+	Ok(observations)
+}
+
+/// Link type of Ethernet frames in a pcap capture
+const LINKTYPE_ETHERNET: u32 = 1;
+
+/// Observe every frame of a classic pcap capture, timed from its first frame to its last
+fn replay(capture: &[u8]) -> Result<Observations, String> {
+	let word = |bytes: &[u8], big: bool| {
+		let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+		if big { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+	};
+	if capture.len() < 24 {
+		return Err("too short for a pcap header".to_string());
+	}
+	// The magic number gives the byte order and whether timestamps are in nanoseconds
+	let (big, nanos) = match word(capture, true) {
+		0xa1b2c3d4 => (true, false),
+		0xa1b23c4d => (true, true),
+		0xd4c3b2a1 => (false, false),
+		0x4d3cb2a1 => (false, true),
+		_ => return Err("not a pcap capture; pcapng is not supported".to_string()),
+	};
+	let link = word(&capture[20..], big);
+	if link != LINKTYPE_ETHERNET {
+		return Err(format!("link type {} is not Ethernet", link));
+	}
+
+	let mut observations = Observations::default();
+	let mut span: Option<(Duration, Duration)> = None;
+	let mut rest = &capture[24..];
+	while rest.len() >= 16 {
+		let fraction = word(&rest[4..], big);
+		let time = Duration::from_secs(word(rest, big).into())
+			+ if nanos { Duration::from_nanos(fraction.into()) } else { Duration::from_micros(fraction.into()) };
+		let length = word(&rest[8..], big) as usize;
+		let Some(frame) = rest.get(16..16 + length) else {
+			return Err("truncated frame".to_string());
+		};
+		observations.observe(frame);
+		span = Some(span.map_or((time, time), |(first, _)| (first, time)));
+		rest = &rest[16 + length..];
+	}
+	observations.duration = span.map_or(Duration::ZERO, |(first, last)| last.saturating_sub(first));
+	Ok(observations)
+}
+
+/// Read a saved capture
+fn read(path: &Path) -> Result<Observations, HuginnError> {
+	let capture = std::fs::read(path)?;
+	replay(&capture).map_err(|reason| HuginnError::Config(format!("cannot read capture {}: {}", path.display(), reason)))
+}
+
+/// Passive discovery plugin
+#[derive(Default)]
+pub struct PassiveScanPlugin {
+	observations: OnceCell<Observations>,
+}
+
+#[async_trait]
+impl Plugin for PassiveScanPlugin {
+	fn name(&self) -> String {
+		"Passive Scanner".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::Passive
+	}
+
+	fn description(&self) -> String {
+		"Live hosts, services, and talkers inferred from captured traffic, without sending probes".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&["capture_duration", "capture_file", "interface"]
+	}
+
+	fn passive(&self) -> bool {
+		true
+	}
+
+	// Nothing is sent, so nothing can reveal the scanning host
+	fn proxy_capable(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, _context: &ScanContext) -> usize {
+		0
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		let address: IpAddr = target.parse().map_err(|_| HuginnError::TargetParse {
+			target: target.to_string(),
+			reason: format!("passive scans only recognize addresses, not {}", target),
+		})?;
+		let observations = self
+			.observations
+			.get_or_try_init(|| async {
+				if let Some(path) = &context.capture_file {
+					info!("Reading traffic from {}", path.display());
+					let observations = read(path)?;
+					debug!("Observed {} hosts", observations.hosts.len());
+					return Ok(observations);
+				}
+				let interface = interface(context.interface.as_deref())?;
+				let duration = context.capture_duration;
+				info!("Capturing traffic on {} for {:?}", interface.name, duration);
+				let observations = tokio::task::spawn_blocking(move || capture(&interface, duration))
+					.await
+					.map_err(|e| HuginnError::Io(io::Error::other(e)))??;
+				debug!("Observed {} hosts", observations.hosts.len());
+				Ok::<_, HuginnError>(observations)
+			})
+			.await?;
+		Ok(observations.results(target, address))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pnet_packet::ethernet::MutableEthernetPacket;
+	use pnet_packet::ipv4::MutableIpv4Packet;
+	use pnet_packet::tcp::MutableTcpPacket;
+	use pnet_packet::udp::MutableUdpPacket;
+	use std::net::Ipv4Addr;
+
+	/// An Ethernet frame carrying an IPv4 packet with a TCP or UDP header
+	fn frame(source: [u8; 4], destination: [u8; 4], protocol: IpNextHeaderProtocol, ports: (u16, u16), flags: u8) -> Vec<u8> {
+		let mut bytes = vec![0u8; 14 + 20 + 20];
+		let mut ethernet = MutableEthernetPacket::new(&mut bytes).unwrap();
+		ethernet.set_ethertype(EtherTypes::Ipv4);
+		let mut ip = MutableIpv4Packet::new(&mut bytes[14..]).unwrap();
+		ip.set_version(4);
+		ip.set_header_length(5);
+		ip.set_total_length(40);
+		ip.set_next_level_protocol(protocol);
+		ip.set_source(Ipv4Addr::from(source));
+		ip.set_destination(Ipv4Addr::from(destination));
+		if protocol == IpNextHeaderProtocols::Tcp {
+			let mut tcp = MutableTcpPacket::new(&mut bytes[34..]).unwrap();
+			tcp.set_source(ports.0);
+			tcp.set_destination(ports.1);
+			tcp.set_flags(flags);
+		} else {
+			let mut udp = MutableUdpPacket::new(&mut bytes[34..]).unwrap();
+			udp.set_source(ports.0);
+			udp.set_destination(ports.1);
+		}
+		bytes
+	}
+
+	#[test]
+	fn test_observe() {
+		let server = [10, 0, 0, 1];
+		let client = [10, 0, 0, 2];
+		let mut observations = Observations::default();
+		observations.observe(&frame(client, server, IpNextHeaderProtocols::Tcp, (50000, 3389), TcpFlags::SYN));
+		observations.observe(&frame(server, client, IpNextHeaderProtocols::Tcp, (3389, 50000), TcpFlags::SYN | TcpFlags::ACK));
+		observations.observe(&frame(server, client, IpNextHeaderProtocols::Udp, (53, 40000), 0));
+		observations.observe(&frame(client, server, IpNextHeaderProtocols::Udp, (40000, 53), 0));
+		observations.observe(&[0u8; 4]);
+
+		let results = observations.results("10.0.0.1", IpAddr::from(server));
+		assert_eq!(results.len(), 3);
+		assert_eq!(results[0].status, "up");
+		assert!(results[0].details.as_deref().unwrap().starts_with("sent 2 packets (108 bytes) to 1 peers"));
+		assert_eq!((results[1].port, results[1].status.as_str()), (Some(3389), "open"));
+		assert_eq!(results[1].techniques, ["T1021.001", "T1133"]);
+		assert_eq!((results[2].port, results[2].details.as_deref()), (Some(53), Some("udp replies observed")));
+
+		let client_results = observations.results("10.0.0.2", IpAddr::from(client));
+		assert_eq!(client_results.len(), 1);
+		assert!(observations.results("10.0.0.3", "10.0.0.3".parse().unwrap()).is_empty());
+	}
+
+	#[test]
+	fn test_replay() {
+		let server = [10, 0, 0, 1];
+		let client = [10, 0, 0, 2];
+		let mut capture = Vec::new();
+		for word in [0xa1b2c3d4u32, 0x0004_0002, 0, 0, 65535, LINKTYPE_ETHERNET] {
+			capture.extend(word.to_le_bytes());
+		}
+		for (seconds, frame) in [
+			(100u32, frame(client, server, IpNextHeaderProtocols::Tcp, (50000, 22), TcpFlags::SYN)),
+			(102, frame(server, client, IpNextHeaderProtocols::Tcp, (22, 50000), TcpFlags::SYN | TcpFlags::ACK)),
+		] {
+			for word in [seconds, 500_000, frame.len() as u32, frame.len() as u32] {
+				capture.extend(word.to_le_bytes());
+			}
+			capture.extend(frame);
+		}
+
+		let observations = replay(&capture).unwrap();
+		assert_eq!(observations.duration, Duration::from_secs(2));
+		let results = observations.results("10.0.0.1", IpAddr::from(server));
+		assert_eq!((results[1].port, results[1].status.as_str()), (Some(22), "open"));
+
+		assert!(replay(&capture[..capture.len() - 1]).unwrap_err().contains("truncated"));
+		assert!(replay(b"\n\r\r\n0000000000000000000000").unwrap_err().contains("pcapng"));
+	}
+}
@@ -0,0 +1,215 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Ping scan plugin
+//!
+//! Checks which hosts are up by sending ICMP echo requests. A raw ICMP socket is used when the
+//! process may open one; otherwise the plugin falls back to an unprivileged ICMP datagram socket,
+//! which Linux allows for groups in `net.ipv4.ping_group_range` and macOS allows for everyone.
+//! Each result reports which socket was used.
+
+use crate::error::HuginnError;
+use crate::plugins::raw::checksum;
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType};
+use crate::transport::Datagram;
+use async_trait::async_trait;
+use log::debug;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::time::{Duration, Instant};
+
+pub use crate::transport::SocketMode;
+
+/// Payload carried by every echo request
+const PAYLOAD: &[u8] = b"huginn";
+
+/// Sequence number of the next echo request
+static SEQUENCE: AtomicU16 = AtomicU16::new(0);
+
+/// Identifier carried by echo requests on raw sockets
+fn identifier() -> u16 {
+	std::process::id() as u16
+}
+
+/// Build an echo request
+///
+/// The kernel fills in the ICMPv6 checksum, which covers a pseudo-header, so it is only computed
+/// here for ICMPv4.
+fn echo_request(ipv4: bool, identifier: u16, sequence: u16) -> Vec<u8> {
+	let mut packet = vec![if ipv4 { 8 } else { 128 }, 0, 0, 0];
+	packet.extend_from_slice(&identifier.to_be_bytes());
+	packet.extend_from_slice(&sequence.to_be_bytes());
+	packet.extend_from_slice(PAYLOAD);
+	if ipv4 {
+		let sum = checksum(&packet);
+		packet[2..4].copy_from_slice(&sum.to_be_bytes());
+	}
+	packet
+}
+
+/// Whether a received packet is the reply to an echo request
+///
+/// Raw ICMPv4 sockets deliver the IP header too, and so do datagram sockets on macOS; a packet
+/// starting with an IPv4 version nibble has one, since no ICMP type is in that range. Datagram
+/// sockets rewrite the identifier and only deliver replies to their own requests, so it is only
+/// checked on raw sockets.
+fn is_reply(packet: &[u8], ipv4: bool, mode: SocketMode, sequence: u16) -> bool {
+	let offset = match packet.first() {
+		Some(&first) if ipv4 && (mode == SocketMode::Raw || first >> 4 == 4) => usize::from(first & 0x0F) * 4,
+		_ => 0,
+	};
+	let Some(icmp) = packet.get(offset..).filter(|icmp| icmp.len() >= 8) else {
+		return false;
+	};
+	let echo_reply = if ipv4 { 0 } else { 129 };
+	icmp[0] == echo_reply
+		&& icmp[6..8] == sequence.to_be_bytes()
+		&& (mode == SocketMode::Datagram || icmp[4..6] == identifier().to_be_bytes())
+}
+
+/// Send one echo request and wait for its reply, returning the round-trip time
+async fn echo(socket: &dyn Datagram, mode: SocketMode, address: IpAddr) -> io::Result<Duration> {
+	let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+	let request = echo_request(address.is_ipv4(), identifier(), sequence);
+	let sent = Instant::now();
+	socket.send_to(&request, SocketAddr::new(address, 0)).await?;
+
+	let mut buffer = [0; 1500];
+	loop {
+		let (len, from) = socket.recv_from(&mut buffer).await?;
+		if from.ip() == address && is_reply(&buffer[..len], address.is_ipv4(), mode, sequence) {
+			return Ok(sent.elapsed());
+		}
+	}
+}
+
+/// Ping scan plugin
+pub struct PingScanPlugin;
+
+#[async_trait]
+impl Plugin for PingScanPlugin {
+	fn name(&self) -> String {
+		"Ping Scanner".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::Ping
+	}
+
+	fn description(&self) -> String {
+		"Checks whether hosts are up with ICMP echo requests".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&["timeout", "retries", "scan_delay", "plugin_timing", "interface", "source_ip"]
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		let address = context.resolve(target).await?;
+		if let Some(source) = context.source_ip
+			&& source.is_ipv4() != address.is_ipv4()
+		{
+			return Err(HuginnError::Config(format!("source address {} cannot reach {}", source, address)));
+		}
+
+		let (socket, mode) = context.transport.icmp(address, context).await?;
+		debug!("Pinging {} over a {} ICMP socket", address, mode);
+		let (status, details, rtt) = match context.probe(|| echo(socket.as_ref(), mode, address)).await {
+			Some(Ok(rtt)) => {
+				let details = format!("echo reply in {:.3} ms ({} socket)", rtt.as_secs_f64() * 1000.0, mode);
+				("up", details, Some(rtt))
+			},
+			Some(Err(e)) => return Err(e.into()),
+			None => ("down", format!("no echo reply ({} socket)", mode), None),
+		};
+
+		Ok(vec![ScanResult {
+			target: target.to_string(),
+			scan_type: self.scan_type().to_string(),
+			port: None,
+			status: status.to_string(),
+			severity: None,
+			service: None,
+			version: None,
+			details: Some(details),
+			techniques: Vec::new(),
+			screenshot: None,
+			rtt,
+		}])
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scanner::Scanner;
+	use crate::transport::mock::MockNetwork;
+	use std::sync::Arc;
+
+	#[test]
+	fn test_echo_request() {
+		let packet = echo_request(true, 0x1234, 7);
+		assert_eq!(packet[0], 8);
+		assert_eq!(packet[4..8], [0x12, 0x34, 0, 7]);
+		assert_eq!(checksum(&packet), 0);
+		assert_eq!(echo_request(false, 0x1234, 7)[..4], [128, 0, 0, 0]);
+	}
+
+	#[test]
+	fn test_is_reply() {
+		let mut reply = echo_request(true, identifier(), 9);
+		reply[0] = 0;
+		assert!(is_reply(&reply, true, SocketMode::Datagram, 9));
+		assert!(!is_reply(&reply, true, SocketMode::Datagram, 10));
+
+		// Raw IPv4 sockets deliver the 20-byte IP header first
+		let mut raw = vec![0x45];
+		raw.resize(20, 0);
+		raw.extend_from_slice(&reply);
+		assert!(is_reply(&raw, true, SocketMode::Raw, 9));
+		// as do datagram sockets on macOS
+		assert!(is_reply(&raw, true, SocketMode::Datagram, 9));
+
+		let mut other = echo_request(true, identifier().wrapping_add(1), 9);
+		other[0] = 0;
+		assert!(!is_reply(&other, true, SocketMode::Raw, 9));
+		assert!(is_reply(&other, true, SocketMode::Datagram, 9));
+
+		let request = echo_request(true, identifier(), 9);
+		assert!(!is_reply(&request, true, SocketMode::Datagram, 9));
+		assert!(!is_reply(&[0, 0, 0], true, SocketMode::Datagram, 9));
+	}
+
+	#[tokio::test]
+	async fn test_ping_loopback() {
+		let context = Scanner::builder().context(ScanType::Ping);
+		match PingScanPlugin.scan("127.0.0.1", &context).await {
+			Ok(results) => {
+				assert_eq!(results[0].status, "up", "{:?}", results[0].details);
+				assert!(results[0].details.as_ref().unwrap().contains("socket)"));
+			},
+			// Neither socket kind is permitted in this environment
+			Err(e) => {
+				assert!(matches!(e, HuginnError::PermissionDenied(_)), "{}", e);
+				assert!(e.to_string().contains("ping_group_range"), "{}", e);
+			},
+		}
+	}
+
+	#[tokio::test]
+	async fn test_mock_network() {
+		let network = MockNetwork::new().host([192, 0, 2, 30]);
+		let context = Scanner::builder()
+			.timeout(Duration::from_millis(20))
+			.transport(Arc::new(network))
+			.context(ScanType::Ping);
+
+		let results = PingScanPlugin.scan("192.0.2.30", &context).await.unwrap();
+		assert_eq!(results[0].status, "up");
+		assert!(results[0].details.as_ref().unwrap().ends_with("(datagram socket)"));
+		let results = PingScanPlugin.scan("192.0.2.31", &context).await.unwrap();
+		assert_eq!(results[0].status, "down");
+		assert_eq!(results[0].details.as_deref(), Some("no echo reply (datagram socket)"));
+	}
+}
@@ -0,0 +1,502 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Raw packet construction for the raw-socket scans
+//!
+//! Builds the IPv4, TCP, and UDP headers that the SYN, idle, and UDP scans send over a raw socket,
+//! where the IP header is supplied by Huginn rather than the kernel. That is what lets the probes
+//! carry a chosen source port and TTL, padding, and decoy source addresses, and be split into IP
+//! fragments with [`fragment`]. The `write_*` functions append to a buffer, so that the
+//! [`Engine`](crate::plugins::engine::Engine) can reuse its buffers from packet to packet.
+
+use crate::error::HuginnError;
+use crate::plugins::{RawOptions, ScanContext};
+use socket2::{Domain, Protocol, SockRef, Socket, Type};
+use std::hash::{BuildHasher, RandomState};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use tokio::net::UdpSocket;
+
+/// IP protocol number of ICMP
+pub const PROTOCOL_ICMP: u8 = 1;
+/// IP protocol number of TCP
+pub const PROTOCOL_TCP: u8 = 6;
+/// IP protocol number of UDP
+pub const PROTOCOL_UDP: u8 = 17;
+/// Time to live of probes when none is configured
+pub const DEFAULT_TTL: u8 = 64;
+
+/// Protocol of a raw socket that sends packets with their IP header included
+pub const PROTOCOL_RAW: i32 = 255;
+
+/// TCP SYN flag
+pub const SYN: u8 = 0x02;
+/// TCP RST flag
+pub const RST: u8 = 0x04;
+/// TCP ACK flag
+pub const ACK: u8 = 0x10;
+
+/// Internet checksum (RFC 1071)
+pub fn checksum(data: &[u8]) -> u16 {
+	pseudo_checksum(&[], data)
+}
+
+/// Internet checksum of data preceded by a pseudo-header, which must be of even length
+fn pseudo_checksum(pseudo: &[u8], data: &[u8]) -> u16 {
+	let mut sum: u32 = pseudo
+		.chunks(2)
+		.chain(data.chunks(2))
+		.map(|pair| u32::from(u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)])))
+		.sum();
+	while sum > 0xFFFF {
+		sum = (sum & 0xFFFF) + (sum >> 16);
+	}
+	!(sum as u16)
+}
+
+/// The TCP and UDP pseudo-header: addresses, protocol, and length of the segment or datagram
+fn pseudo_header(source: Ipv4Addr, destination: Ipv4Addr, protocol: u8, length: usize) -> [u8; 12] {
+	let mut header = [0; 12];
+	header[..4].copy_from_slice(&source.octets());
+	header[4..8].copy_from_slice(&destination.octets());
+	header[9] = protocol;
+	header[10..].copy_from_slice(&(length as u16).to_be_bytes());
+	header
+}
+
+/// A random number, for sequence numbers, ports, and padding
+pub fn random() -> u64 {
+	RandomState::new().hash_one(std::time::SystemTime::now())
+}
+
+/// A random port from the range operating systems hand out as source ports
+pub fn ephemeral_port() -> u16 {
+	32768 + (random() % 28000) as u16
+}
+
+/// Random bytes
+pub fn random_bytes(length: usize) -> Vec<u8> {
+	(0..length.div_ceil(8)).flat_map(|_| random().to_be_bytes()).take(length).collect()
+}
+
+/// Bind a socket to a network interface
+pub fn bind_device(socket: &Socket, interface: &str) -> io::Result<()> {
+	crate::transport::bind_device(SockRef::from(socket), interface)
+}
+
+/// Resolve a target to the IPv4 address that raw-socket scans probe
+pub async fn resolve(target: &str, context: &ScanContext) -> Result<Ipv4Addr, HuginnError> {
+	context
+		.transport
+		.resolve(target)
+		.await
+		.map_err(|_| HuginnError::Unresolved { target: target.to_string() })?
+		.into_iter()
+		.find_map(|address| match address {
+			IpAddr::V4(address) => Some(address),
+			IpAddr::V6(_) => None,
+		})
+		.ok_or_else(|| HuginnError::TargetParse {
+			target: target.to_string(),
+			reason: format!("raw-socket scans need an IPv4 address, and {} has none", target),
+		})
+}
+
+/// Open a raw IPv4 socket on the configured interface, wrapped for asynchronous use
+///
+/// A lack of privileges is reported as [`HuginnError::PermissionDenied`].
+pub fn socket(protocol: Protocol, context: &ScanContext) -> Result<UdpSocket, HuginnError> {
+	if !context.transport.raw_sockets() {
+		return Err(HuginnError::PermissionDenied("raw sockets are not available over this transport".to_string()));
+	}
+	let socket = Socket::new(Domain::IPV4, Type::RAW, Some(protocol)).map_err(|e| match e.kind() {
+		io::ErrorKind::PermissionDenied => {
+			HuginnError::PermissionDenied("raw-socket scans need root or CAP_NET_RAW".to_string())
+		},
+		_ => e.into(),
+	})?;
+	if let Some(interface) = &context.interface {
+		bind_device(&socket, interface)?;
+	}
+	socket.set_nonblocking(true)?;
+	Ok(UdpSocket::from_std(std::net::UdpSocket::from(socket))?)
+}
+
+/// The address probes are sent from: the configured one, or else the one the system would route
+/// from, found without sending anything
+pub fn source_address(destination: Ipv4Addr, context: &ScanContext) -> Result<Ipv4Addr, HuginnError> {
+	match context.source_ip {
+		Some(IpAddr::V4(source)) => Ok(source),
+		Some(source) => Err(HuginnError::Config(format!("source address {} cannot reach {}", source, destination))),
+		None => {
+			let socket = std::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+			socket.connect((destination, 9))?;
+			match socket.local_addr()?.ip() {
+				IpAddr::V4(source) => Ok(source),
+				IpAddr::V6(_) => Err(HuginnError::Config(format!("no IPv4 route to {}", destination))),
+			}
+		},
+	}
+}
+
+/// Send a packet built by [`Ipv4Header::packet`] over a [`PROTOCOL_RAW`] socket, fragmented
+/// when the options ask for it
+pub async fn send(socket: &UdpSocket, packet: &[u8], destination: Ipv4Addr, options: &RawOptions) -> io::Result<()> {
+	let packets = match options.mtu {
+		Some(mtu) => fragment(packet, mtu),
+		None => vec![packet.to_vec()],
+	};
+	for packet in packets {
+		socket.send_to(&packet, SocketAddr::new(IpAddr::V4(destination), 0)).await?;
+	}
+	Ok(())
+}
+
+/// Fields of an IPv4 header
+#[derive(Debug, Clone, Copy)]
+pub struct Ipv4Header {
+	/// Source address
+	pub source: Ipv4Addr,
+	/// Destination address
+	pub destination: Ipv4Addr,
+	/// Protocol of the payload
+	pub protocol: u8,
+	/// Time to live
+	pub ttl: u8,
+	/// Identification
+	pub identification: u16,
+}
+
+impl Ipv4Header {
+	/// The header, without options, followed by the payload
+	pub fn packet(&self, payload: &[u8]) -> Vec<u8> {
+		let mut packet = Vec::with_capacity(20 + payload.len());
+		self.write(&mut packet, payload.len());
+		packet.extend_from_slice(payload);
+		packet
+	}
+
+	/// Append the header, without options, for a payload of the given length; the payload is
+	/// appended after it
+	pub fn write(&self, buffer: &mut Vec<u8>, payload_length: usize) {
+		debug_assert!(payload_length <= usize::from(u16::MAX) - 20, "IPv4 payload too long");
+		let start = buffer.len();
+		buffer.extend_from_slice(&[0x45, 0]);
+		buffer.extend_from_slice(&((20 + payload_length) as u16).to_be_bytes());
+		buffer.extend_from_slice(&self.identification.to_be_bytes());
+		buffer.extend_from_slice(&[0, 0, self.ttl, self.protocol, 0, 0]);
+		buffer.extend_from_slice(&self.source.octets());
+		buffer.extend_from_slice(&self.destination.octets());
+		let sum = checksum(&buffer[start..]);
+		buffer[start + 10..start + 12].copy_from_slice(&sum.to_be_bytes());
+	}
+}
+
+/// Split an IPv4 packet built by [`Ipv4Header::packet`] into fragments carrying at most `mtu`
+/// bytes of its payload each, rounded down to a multiple of 8 as fragment offsets require
+///
+/// Packets whose payload already fits are returned whole.
+pub fn fragment(packet: &[u8], mtu: usize) -> Vec<Vec<u8>> {
+	let (header, payload) = packet.split_at(20);
+	let size = (mtu / 8 * 8).max(8);
+	if payload.len() <= size {
+		return vec![packet.to_vec()];
+	}
+	let count = payload.len().div_ceil(size);
+	payload
+		.chunks(size)
+		.enumerate()
+		.map(|(i, chunk)| {
+			let mut fragment = header.to_vec();
+			let more_fragments = if i + 1 < count { 0x2000 } else { 0 };
+			let flags = more_fragments | (i * size / 8) as u16;
+			fragment[2..4].copy_from_slice(&((20 + chunk.len()) as u16).to_be_bytes());
+			fragment[6..8].copy_from_slice(&flags.to_be_bytes());
+			fragment[10..12].copy_from_slice(&[0, 0]);
+			let sum = checksum(&fragment);
+			fragment[10..12].copy_from_slice(&sum.to_be_bytes());
+			fragment.extend_from_slice(chunk);
+			fragment
+		})
+		.collect()
+}
+
+/// Build a TCP segment with no options, checksummed for the addresses it travels between
+pub fn tcp_segment(
+	source: Ipv4Addr,
+	destination: Ipv4Addr,
+	ports: (u16, u16),
+	sequence: u32,
+	flags: u8,
+	payload: &[u8],
+) -> Vec<u8> {
+	let mut segment = Vec::with_capacity(20 + payload.len());
+	write_tcp_segment(&mut segment, source, destination, ports, sequence, flags, payload);
+	segment
+}
+
+/// Append a TCP segment built as by [`tcp_segment`]
+pub fn write_tcp_segment(
+	buffer: &mut Vec<u8>,
+	source: Ipv4Addr,
+	destination: Ipv4Addr,
+	ports: (u16, u16),
+	sequence: u32,
+	flags: u8,
+	payload: &[u8],
+) {
+	let start = buffer.len();
+	buffer.extend_from_slice(&ports.0.to_be_bytes());
+	buffer.extend_from_slice(&ports.1.to_be_bytes());
+	buffer.extend_from_slice(&sequence.to_be_bytes());
+	buffer.extend_from_slice(&[0, 0, 0, 0, 5 << 4, flags]);
+	buffer.extend_from_slice(&1024u16.to_be_bytes());
+	buffer.extend_from_slice(&[0, 0, 0, 0]);
+	buffer.extend_from_slice(payload);
+
+	let pseudo = pseudo_header(source, destination, PROTOCOL_TCP, buffer.len() - start);
+	let sum = pseudo_checksum(&pseudo, &buffer[start..]);
+	buffer[start + 16..start + 18].copy_from_slice(&sum.to_be_bytes());
+}
+
+/// Append a UDP datagram, checksummed for the addresses it travels between
+pub fn write_udp_datagram(buffer: &mut Vec<u8>, source: Ipv4Addr, destination: Ipv4Addr, ports: (u16, u16), payload: &[u8]) {
+	let start = buffer.len();
+	let length = 8 + payload.len();
+	buffer.extend_from_slice(&ports.0.to_be_bytes());
+	buffer.extend_from_slice(&ports.1.to_be_bytes());
+	buffer.extend_from_slice(&(length as u16).to_be_bytes());
+	buffer.extend_from_slice(&[0, 0]);
+	buffer.extend_from_slice(payload);
+
+	let pseudo = pseudo_header(source, destination, PROTOCOL_UDP, length);
+	// A computed checksum of zero is sent as all ones, since zero means no checksum
+	let sum = match pseudo_checksum(&pseudo, &buffer[start..]) {
+		0 => 0xFFFF,
+		sum => sum,
+	};
+	buffer[start + 6..start + 8].copy_from_slice(&sum.to_be_bytes());
+}
+
+/// A TCP segment received in an IPv4 packet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TcpReply {
+	/// Sender address
+	pub source: Ipv4Addr,
+	/// IP identification
+	pub identification: u16,
+	/// Sender port
+	pub source_port: u16,
+	/// Receiving port
+	pub destination_port: u16,
+	/// Acknowledgment number
+	pub acknowledgment: u32,
+	/// TCP flags
+	pub flags: u8,
+}
+
+impl TcpReply {
+	/// Parse an IPv4 packet carrying a TCP segment, as a raw TCP socket delivers it
+	pub fn parse(packet: &[u8]) -> Option<Self> {
+		let header = usize::from(packet.first()? & 0x0F) * 4;
+		if packet.first()? >> 4 != 4 || *packet.get(9)? != PROTOCOL_TCP {
+			return None;
+		}
+		let source = Ipv4Addr::new(packet[12], packet[13], packet[14], packet[15]);
+		let tcp = packet.get(header..header + 20)?;
+		Some(Self {
+			source,
+			identification: u16::from_be_bytes([packet[4], packet[5]]),
+			source_port: u16::from_be_bytes([tcp[0], tcp[1]]),
+			destination_port: u16::from_be_bytes([tcp[2], tcp[3]]),
+			acknowledgment: u32::from_be_bytes([tcp[8], tcp[9], tcp[10], tcp[11]]),
+			flags: tcp[13],
+		})
+	}
+}
+
+/// The IPv4 source address, protocol, and payload of a packet
+fn ipv4_payload(packet: &[u8]) -> Option<(Ipv4Addr, u8, &[u8])> {
+	let header = usize::from(packet.first()? & 0x0F) * 4;
+	if packet.first()? >> 4 != 4 || header < 20 {
+		return None;
+	}
+	let source = Ipv4Addr::new(*packet.get(12)?, packet[13], packet[14], packet[15]);
+	Some((source, packet[9], packet.get(header..)?))
+}
+
+/// A UDP datagram received in an IPv4 packet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UdpReply<'a> {
+	/// Sender address
+	pub source: Ipv4Addr,
+	/// Sender port
+	pub source_port: u16,
+	/// Receiving port
+	pub destination_port: u16,
+	/// Datagram payload
+	pub payload: &'a [u8],
+}
+
+impl<'a> UdpReply<'a> {
+	/// Parse an IPv4 packet carrying a UDP datagram, as a raw UDP socket delivers it
+	pub fn parse(packet: &'a [u8]) -> Option<Self> {
+		let (source, protocol, udp) = ipv4_payload(packet)?;
+		if protocol != PROTOCOL_UDP || udp.len() < 8 {
+			return None;
+		}
+		let length = usize::from(u16::from_be_bytes([udp[4], udp[5]])).clamp(8, udp.len());
+		Some(Self {
+			source,
+			source_port: u16::from_be_bytes([udp[0], udp[1]]),
+			destination_port: u16::from_be_bytes([udp[2], udp[3]]),
+			payload: &udp[8..length],
+		})
+	}
+}
+
+/// An ICMP destination unreachable message about a probe, received in an IPv4 packet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unreachable {
+	/// Address of the host or router that reported it
+	pub reporter: Ipv4Addr,
+	/// Unreachable code; 3 is port unreachable
+	pub code: u8,
+	/// Destination of the probe
+	pub destination: Ipv4Addr,
+	/// Protocol of the probe
+	pub protocol: u8,
+	/// Source port of the probe
+	pub source_port: u16,
+	/// Destination port of the probe
+	pub destination_port: u16,
+}
+
+impl Unreachable {
+	/// Parse an IPv4 packet carrying an ICMP destination unreachable message, as a raw ICMP socket
+	/// delivers it
+	pub fn parse(packet: &[u8]) -> Option<Self> {
+		let (reporter, protocol, icmp) = ipv4_payload(packet)?;
+		if protocol != PROTOCOL_ICMP || *icmp.first()? != 3 {
+			return None;
+		}
+		// The message quotes the probe's IP header and the first 8 bytes of its payload
+		let quoted = icmp.get(8..)?;
+		let header = usize::from(quoted.first()? & 0x0F) * 4;
+		let ports = quoted.get(header..header + 4)?;
+		Some(Self {
+			reporter,
+			code: icmp[1],
+			destination: Ipv4Addr::new(*quoted.get(16)?, quoted[17], quoted[18], quoted[19]),
+			protocol: quoted[9],
+			source_port: u16::from_be_bytes([ports[0], ports[1]]),
+			destination_port: u16::from_be_bytes([ports[2], ports[3]]),
+		})
+	}
+
+	/// What the code says is unreachable
+	pub fn reason(&self) -> &'static str {
+		match self.code {
+			0 => "network unreachable",
+			1 => "host unreachable",
+			2 => "protocol unreachable",
+			3 => "port unreachable",
+			9 | 10 | 13 => "communication administratively prohibited",
+			_ => "destination unreachable",
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_syn_packet() {
+		let source = Ipv4Addr::new(192, 0, 2, 1);
+		let destination = Ipv4Addr::new(192, 0, 2, 2);
+		let segment = tcp_segment(source, destination, (40000, 22), 0x01020304, SYN, b"pad");
+		let header = Ipv4Header {
+			source,
+			destination,
+			protocol: PROTOCOL_TCP,
+			ttl: 37,
+			identification: 7,
+		};
+		let packet = header.packet(&segment);
+		assert_eq!(packet.len(), 20 + 20 + 3);
+		assert_eq!(checksum(&packet[..20]), 0);
+		assert_eq!((packet[8], packet[9]), (37, PROTOCOL_TCP));
+
+		let mut pseudo = [source.octets(), destination.octets()].concat();
+		pseudo.extend_from_slice(&[0, PROTOCOL_TCP, 0, segment.len() as u8]);
+		pseudo.extend_from_slice(&segment);
+		assert_eq!(checksum(&pseudo), 0);
+
+		let reply = TcpReply::parse(&packet).unwrap();
+		assert_eq!((reply.source, reply.source_port, reply.destination_port), (source, 40000, 22));
+		assert_eq!(reply.flags, SYN);
+		assert!(TcpReply::parse(&packet[..30]).is_none());
+		assert_eq!(random_bytes(11).len(), 11);
+	}
+
+	#[test]
+	fn test_udp_and_unreachable() {
+		let source = Ipv4Addr::new(192, 0, 2, 1);
+		let destination = Ipv4Addr::new(192, 0, 2, 2);
+		let mut probe = Vec::new();
+		let header = Ipv4Header {
+			source,
+			destination,
+			protocol: PROTOCOL_UDP,
+			ttl: 64,
+			identification: 9,
+		};
+		header.write(&mut probe, 8 + 5);
+		write_udp_datagram(&mut probe, source, destination, (40000, 53), b"query");
+		assert_eq!(checksum(&probe[..20]), 0);
+		assert_eq!(pseudo_checksum(&pseudo_header(source, destination, PROTOCOL_UDP, 13), &probe[20..]), 0);
+
+		let datagram = UdpReply::parse(&probe).unwrap();
+		assert_eq!((datagram.source, datagram.source_port, datagram.destination_port), (source, 40000, 53));
+		assert_eq!(datagram.payload, b"query");
+
+		// Port unreachable from the destination, quoting the probe
+		let mut icmp = vec![3, 3, 0, 0, 0, 0, 0, 0];
+		icmp.extend_from_slice(&probe[..28]);
+		let reply = Ipv4Header {
+			source: destination,
+			destination: source,
+			protocol: PROTOCOL_ICMP,
+			ttl: 64,
+			identification: 1,
+		}
+		.packet(&icmp);
+		let unreachable = Unreachable::parse(&reply).unwrap();
+		assert_eq!(unreachable.reporter, destination);
+		assert_eq!((unreachable.destination, unreachable.protocol), (destination, PROTOCOL_UDP));
+		assert_eq!((unreachable.source_port, unreachable.destination_port), (40000, 53));
+		assert_eq!(unreachable.reason(), "port unreachable");
+		assert!(UdpReply::parse(&reply).is_none());
+		assert!(Unreachable::parse(&probe).is_none());
+	}
+
+	#[test]
+	fn test_fragment() {
+		let header = Ipv4Header {
+			source: Ipv4Addr::new(192, 0, 2, 1),
+			destination: Ipv4Addr::new(192, 0, 2, 2),
+			protocol: PROTOCOL_TCP,
+			ttl: 64,
+			identification: 0x1234,
+		};
+		let packet = header.packet(&[7; 28]);
+		let fragments = fragment(&packet, 12);
+		let sizes: Vec<usize> = fragments.iter().map(|f| f.len()).collect();
+		assert_eq!(sizes, [28, 28, 28, 24]);
+		let flags: Vec<u16> = fragments.iter().map(|f| u16::from_be_bytes([f[6], f[7]])).collect();
+		assert_eq!(flags, [0x2000, 0x2001, 0x2002, 0x0003]);
+		assert!(fragments.iter().all(|f| checksum(&f[..20]) == 0 && f[4..6] == [0x12, 0x34]));
+		assert_eq!(fragment(&packet, 1480), [packet]);
+	}
+}
@@ -0,0 +1,407 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! VNC and X11 exposure plugin
+//!
+//! Finds the remote displays on each port and whether they require authentication, without
+//! ever opening a session:
+//!
+//! - VNC servers greet with their RFB protocol version. The version is answered, and the
+//!   security types the server then offers are read, but none is chosen. A server that offers
+//!   `None` lets anyone view and control its desktop.
+//! - X11 servers, on ports 6000 to 6063, are sent a connection setup with no authorization.
+//!   One that accepts it lets anyone capture the screen and keystrokes and inject input; one
+//!   that refuses it says why, such as `No protocol specified`.
+//!
+//! Each display found is reported as an open port, with the protocol version and what the
+//! server asked for in its details, and a display open to anyone as an `unauthenticated`
+//! finding.
+
+use crate::attack;
+use crate::error::HuginnError;
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+use async_trait::async_trait;
+use std::io;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Ports probed when no port spec is configured: the first ten VNC and X11 displays
+const DEFAULT_PORTS: &[u16] = &[
+	5900, 5901, 5902, 5903, 5904, 5905, 5906, 5907, 5908, 5909, 6000, 6001, 6002, 6003, 6004, 6005, 6006, 6007, 6008, 6009,
+];
+
+/// Ports of VNC displays; servers on other ports are found by their greeting
+const VNC_PORTS: RangeInclusive<u16> = 5900..=5999;
+
+/// Ports of X11 displays, which wait for the client to speak first
+const X11_PORTS: RangeInclusive<u16> = 6000..=6063;
+
+/// X11 connection setup, little-endian, for protocol 11.0 with no authorization
+const X11_SETUP: [u8; 12] = [0x6c, 0x00, 0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+
+/// Largest X11 setup reply read
+const MAX_X11_REPLY: usize = 65536;
+
+/// Name of a VNC security type
+fn security_type(code: u8) -> String {
+	match code {
+		1 => "None".to_string(),
+		2 => "VNC Authentication".to_string(),
+		5 => "RA2".to_string(),
+		6 => "RA2ne".to_string(),
+		16 => "Tight".to_string(),
+		18 => "TLS".to_string(),
+		19 => "VeNCrypt".to_string(),
+		30 => "Apple Remote Desktop".to_string(),
+		code => format!("type {}", code),
+	}
+}
+
+/// What a remote display said about itself
+#[derive(Debug, PartialEq, Eq)]
+struct Display {
+	/// Protocol version, and the vendor for X11
+	version: String,
+	/// What the server asked for, or why it refused
+	details: String,
+	/// Whether anyone can use the display
+	open: bool,
+}
+
+/// Read a length-prefixed VNC failure reason
+async fn vnc_reason(stream: &mut (impl AsyncRead + Unpin)) -> io::Result<String> {
+	let length = stream.read_u32().await?.min(1024) as usize;
+	let mut reason = vec![0; length];
+	stream.read_exact(&mut reason).await?;
+	Ok(String::from_utf8_lossy(&reason).trim().to_string())
+}
+
+/// Read a VNC server's greeting and the security types it offers, or `None` when the port does
+/// not greet as a VNC server
+async fn vnc(mut stream: impl AsyncRead + AsyncWrite + Unpin) -> io::Result<Option<Display>> {
+	let mut greeting = [0; 12];
+	stream.read_exact(&mut greeting).await?;
+	let Some(version) = std::str::from_utf8(&greeting)
+		.ok()
+		.and_then(|greeting| greeting.strip_prefix("RFB "))
+		.and_then(|version| version.strip_suffix('\n'))
+		.and_then(|version| version.split_once('.'))
+		.and_then(|(major, minor)| Some((major.parse::<u32>().ok()?, minor.parse::<u32>().ok()?)))
+	else {
+		return Ok(None);
+	};
+	let version_text = format!("RFB {}.{}", version.0, version.1);
+	// Speak the newest version both sides know; 3.3 servers choose the security type themselves
+	let legacy = version < (3, 7);
+	stream.write_all(if legacy { b"RFB 003.003\n" } else { b"RFB 003.008\n" }).await?;
+
+	let offered = if legacy {
+		match stream.read_u32().await? {
+			0 => Err(vnc_reason(&mut stream).await?),
+			code => Ok(vec![u8::try_from(code).unwrap_or(u8::MAX)]),
+		}
+	} else {
+		match stream.read_u8().await? {
+			0 => Err(vnc_reason(&mut stream).await?),
+			count => {
+				let mut types = vec![0; usize::from(count)];
+				stream.read_exact(&mut types).await?;
+				Ok(types)
+			},
+		}
+	};
+	let display = match offered {
+		Ok(types) => Display {
+			version: version_text,
+			details: format!("security types {}", types.iter().map(|&code| security_type(code)).collect::<Vec<_>>().join(", ")),
+			open: types.contains(&1),
+		},
+		Err(reason) => Display {
+			version: version_text,
+			details: format!("connection refused: {}", reason),
+			open: false,
+		},
+	};
+	Ok(Some(display))
+}
+
+/// Send an X11 connection setup without authorization and read the server's answer, or `None`
+/// when the port does not answer as an X server
+async fn x11(mut stream: impl AsyncRead + AsyncWrite + Unpin) -> io::Result<Option<Display>> {
+	stream.write_all(&X11_SETUP).await?;
+	let mut head = [0; 8];
+	stream.read_exact(&mut head).await?;
+	let version = format!("X11 {}.{}", u16::from_le_bytes([head[2], head[3]]), u16::from_le_bytes([head[4], head[5]]));
+	let length = (usize::from(u16::from_le_bytes([head[6], head[7]])) * 4).min(MAX_X11_REPLY);
+	let mut data = vec![0; length];
+	stream.read_exact(&mut data).await?;
+	if head[2..4] != [11, 0] {
+		return Ok(None);
+	}
+	let display = match head[0] {
+		0 => {
+			let reason = data.get(..usize::from(head[1])).unwrap_or(&data);
+			Display {
+				version,
+				details: format!("access control enabled: {}", String::from_utf8_lossy(reason).trim()),
+				open: false,
+			}
+		},
+		1 => {
+			let release = data.get(..4).map_or(0, |release| u32::from_le_bytes([release[0], release[1], release[2], release[3]]));
+			let vendor_length = data.get(16..18).map_or(0, |length| usize::from(u16::from_le_bytes([length[0], length[1]])));
+			let vendor = data.get(32..32 + vendor_length).map(String::from_utf8_lossy).unwrap_or_default();
+			Display {
+				version: format!("{} ({} {})", version, vendor.trim(), release),
+				details: "access control disabled".to_string(),
+				open: true,
+			}
+		},
+		_ => Display {
+			version,
+			details: "authorization required".to_string(),
+			open: false,
+		},
+	};
+	Ok(Some(display))
+}
+
+/// Results for the remote display on a port, if there is one
+async fn expose(target: &str, port: u16, context: &ScanContext) -> Vec<ScanResult> {
+	let x11_port = X11_PORTS.contains(&port);
+	let probe = || async {
+		let stream = context.connect(target, port).await?;
+		if x11_port { x11(stream).await } else { vnc(stream).await }
+	};
+	let Some(Ok(Some(display))) = context.probe(probe).await else {
+		return Vec::new();
+	};
+
+	let (service, number) = if x11_port {
+		("x11", port - X11_PORTS.start())
+	} else {
+		("vnc", port.saturating_sub(*VNC_PORTS.start()))
+	};
+	let name = if x11_port || VNC_PORTS.contains(&port) { format!("display :{}", number) } else { format!("port {}", port) };
+	let mut results = vec![ScanResult {
+		target: target.to_string(),
+		scan_type: ScanType::RemoteDisplay.to_string(),
+		port: Some(port),
+		status: "open".to_string(),
+		severity: None,
+		service: Some(service.to_string()),
+		version: Some(display.version),
+		details: Some(format!("{} {}; {}", service.to_uppercase(), name, display.details)),
+		techniques: attack::for_port(port),
+		screenshot: None,
+		rtt: None,
+	}];
+	if display.open {
+		let (details, techniques) = if x11_port {
+			(
+				format!("X server on {} accepts connections without authentication, so anyone can capture its screen and keystrokes and inject input", name),
+				vec!["T1056.001".to_string(), "T1113".to_string()],
+			)
+		} else {
+			(
+				format!("VNC server on {} offers the None security type, so anyone can view and control its desktop", name),
+				vec!["T1021.005".to_string()],
+			)
+		};
+		results.push(ScanResult {
+			target: target.to_string(),
+			scan_type: ScanType::RemoteDisplay.to_string(),
+			port: Some(port),
+			status: "unauthenticated".to_string(),
+			severity: Some(Severity::Critical),
+			service: Some(service.to_string()),
+			version: None,
+			details: Some(details),
+			techniques,
+			screenshot: None,
+			rtt: None,
+		});
+	}
+	results
+}
+
+/// VNC and X11 exposure plugin
+pub struct RemoteDisplayPlugin;
+
+#[async_trait]
+impl Plugin for RemoteDisplayPlugin {
+	fn name(&self) -> String {
+		"Remote Display Scanner".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::RemoteDisplay
+	}
+
+	fn description(&self) -> String {
+		"Finds VNC and X11 displays and whether they require authentication".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"proxy",
+			"max_connections",
+		]
+	}
+
+	fn proxy_capable(&self) -> bool {
+		true
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		context.ports_or(DEFAULT_PORTS).len()
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		// Resolve up front to fail early, but connect by name
+		if context.proxy.is_none() {
+			context.resolve(target).await?;
+		}
+
+		let (target, context) = (Arc::new(target.to_string()), Arc::new(context.clone()));
+		// A probe delay paces the probes one after another
+		let width = if context.scan_delay.is_zero() { context.max_connections } else { 1 };
+		let permits = Arc::new(Semaphore::new(width));
+		let mut probes = JoinSet::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			let (target, context, permits) = (target.clone(), context.clone(), permits.clone());
+			probes.spawn(async move {
+				let _permit = permits.acquire_owned().await;
+				expose(&target, port, &context).await
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(joined) = probes.join_next().await {
+			results.extend(joined.map_err(io::Error::other)?);
+		}
+		results.sort_by_key(|r| r.port);
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ports::PortSpec;
+	use crate::scanner::Scanner;
+	use std::time::Duration;
+	use tokio::io::duplex;
+	use tokio::net::TcpListener;
+
+	/// Run a probe against a server that sends its part of the handshake up front, and return
+	/// what the probe sent
+	async fn converse<F, Fut>(server: &[u8], probe: F) -> (io::Result<Option<Display>>, Vec<u8>)
+	where
+		F: FnOnce(tokio::io::DuplexStream) -> Fut,
+		Fut: Future<Output = io::Result<Option<Display>>>,
+	{
+		let (client, mut peer) = duplex(4096);
+		peer.write_all(server).await.unwrap();
+		let outcome = probe(client).await;
+		let mut sent = Vec::new();
+		let _ = peer.read_to_end(&mut sent).await;
+		(outcome, sent)
+	}
+
+	#[tokio::test]
+	async fn test_vnc() {
+		let (display, sent) = converse(b"RFB 003.008\n\x02\x01\x02", vnc).await;
+		assert_eq!(sent, b"RFB 003.008\n");
+		assert_eq!(
+			display.unwrap(),
+			Some(Display {
+				version: "RFB 3.8".to_string(),
+				details: "security types None, VNC Authentication".to_string(),
+				open: true,
+			})
+		);
+
+		let (display, sent) = converse(b"RFB 003.003\n\x00\x00\x00\x02", vnc).await;
+		assert_eq!(sent, b"RFB 003.003\n");
+		assert_eq!(display.unwrap().unwrap().details, "security types VNC Authentication");
+
+		let (display, _) = converse(b"RFB 004.001\n\x00\x00\x00\x00\x0aToo many 1", vnc).await;
+		assert_eq!(display.unwrap().unwrap().details, "connection refused: Too many 1");
+
+		let (display, _) = converse(b"SSH-2.0-OpenSSH_9.6\r\n", vnc).await;
+		assert_eq!(display.unwrap(), None);
+	}
+
+	#[tokio::test]
+	async fn test_x11() {
+		let reason = b"No protocol specified\n";
+		let mut refusal = vec![0, reason.len() as u8, 11, 0, 0, 0, 6, 0];
+		refusal.extend_from_slice(reason);
+		refusal.resize(8 + 24, 0);
+		let (display, sent) = converse(&refusal, x11).await;
+		assert_eq!(sent, X11_SETUP);
+		let display = display.unwrap().unwrap();
+		assert_eq!((display.details.as_str(), display.open), ("access control enabled: No protocol specified", false));
+
+		let vendor = b"The X.Org Foundation";
+		let mut accepted = vec![1, 0, 11, 0, 0, 0, 0, 0];
+		let mut data = 12_101_011u32.to_le_bytes().to_vec();
+		data.extend_from_slice(&[0; 12]);
+		data.extend_from_slice(&(vendor.len() as u16).to_le_bytes());
+		data.extend_from_slice(&[0; 14]);
+		data.extend_from_slice(vendor);
+		data.resize(data.len().div_ceil(4) * 4, 0);
+		accepted[6..8].copy_from_slice(&((data.len() / 4) as u16).to_le_bytes());
+		accepted.extend(data);
+		let (display, _) = converse(&accepted, x11).await;
+		let display = display.unwrap().unwrap();
+		assert_eq!(display.version, "X11 11.0 (The X.Org Foundation 12101011)");
+		assert!(display.open);
+	}
+
+	#[tokio::test]
+	async fn test_finds_open_vnc() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Ok((mut stream, _)) = listener.accept().await {
+				let _ = stream.write_all(b"RFB 003.008\n").await;
+				let mut version = [0; 12];
+				let _ = stream.read_exact(&mut version).await;
+				let _ = stream.write_all(&[1, 1]).await;
+			}
+		});
+
+		let context = Scanner::builder()
+			.ports(port.to_string().parse::<PortSpec>().unwrap())
+			.timeout(Duration::from_millis(500))
+			.context(ScanType::RemoteDisplay);
+		let results = RemoteDisplayPlugin.scan("127.0.0.1", &context).await.unwrap();
+		let found: Vec<_> = results.iter().map(|r| (r.status.as_str(), r.details.clone().unwrap())).collect();
+		assert_eq!(
+			found,
+			[
+				("open", format!("VNC port {}; security types None", port)),
+				(
+					"unauthenticated",
+					format!("VNC server on port {} offers the None security type, so anyone can view and control its desktop", port)
+				),
+			]
+		);
+	}
+}
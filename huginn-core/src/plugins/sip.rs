@@ -0,0 +1,448 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! SIP enumeration plugin
+//!
+//! Sends an `OPTIONS` request to the SIP service on each port, over UDP and TCP on 5060 and
+//! over TLS on 5061, and reports each transport that answers as open, with the server's agent
+//! as its version and the methods and extensions it allows in the details.
+//!
+//! Each answering transport is then sent a `REGISTER` for a made-up user, with `Expires: 0` and
+//! a contact that was never bound, which asks the registrar to remove a binding that does not
+//! exist and so leaves nothing behind. A registrar that accepts it without a challenge is
+//! reported as a `sip_register` finding, since anyone can then register as its users and take
+//! their calls.
+
+use crate::error::HuginnError;
+use crate::plugins::http::{self, USER_AGENT};
+use crate::plugins::tls::random_bytes;
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+use async_trait::async_trait;
+use std::io;
+use std::net::{Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_rustls::TlsConnector;
+
+/// Ports probed when no port spec is configured
+const DEFAULT_PORTS: &[u16] = &[5060, 5061];
+
+/// Ports SIP is served over TLS on
+const TLS_PORTS: &[u16] = &[5061];
+
+/// Largest SIP message read
+const MAX_MESSAGE: usize = 65536;
+
+/// Transport a SIP request is sent over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transport {
+	Udp,
+	Tcp,
+	Tls,
+}
+
+impl Transport {
+	/// Name of the transport in a `Via` header
+	fn name(self) -> &'static str {
+		match self {
+			Transport::Udp => "UDP",
+			Transport::Tcp => "TCP",
+			Transport::Tls => "TLS",
+		}
+	}
+
+	/// Transports tried on a port
+	fn on_port(port: u16) -> &'static [Transport] {
+		if TLS_PORTS.contains(&port) { &[Transport::Tls] } else { &[Transport::Udp, Transport::Tcp] }
+	}
+}
+
+/// Random token for tags, branches, and call IDs
+fn token() -> String {
+	random_bytes(8).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Host part of a SIP URI
+fn uri_host(target: &str) -> String {
+	if target.parse::<Ipv6Addr>().is_ok() { format!("[{}]", target) } else { target.to_string() }
+}
+
+/// A SIP request, and its call ID
+fn request(method: &str, target: &str, transport: Transport) -> (String, String) {
+	let host = uri_host(target);
+	let uri = format!("sip:{}", host);
+	let call_id = format!("{}@huginn.invalid", token());
+	let (user, extra) = match method {
+		// A user name no registrar knows, and a binding that was never made, so that nothing of a
+		// real user's is touched
+		"REGISTER" => (format!("huginn-{}", token()), "Contact: <sip:huginn@huginn.invalid>\r\nExpires: 0\r\n"),
+		_ => ("huginn".to_string(), "Accept: application/sdp\r\n"),
+	};
+	let request = format!(
+		"{method} {uri} SIP/2.0\r\n\
+		Via: SIP/2.0/{transport} huginn.invalid;branch=z9hG4bK{branch};rport\r\n\
+		Max-Forwards: 70\r\n\
+		From: <sip:{user}@{host}>;tag={tag}\r\n\
+		To: <sip:{user}@{host}>\r\n\
+		Call-ID: {call_id}\r\n\
+		CSeq: 1 {method}\r\n\
+		{extra}\
+		User-Agent: {agent}\r\n\
+		Content-Length: 0\r\n\r\n",
+		transport = transport.name(),
+		branch = token(),
+		tag = token(),
+		agent = USER_AGENT,
+	);
+	(request, call_id)
+}
+
+/// A SIP response
+#[derive(Debug)]
+struct Reply {
+	status: u16,
+	reason: String,
+	headers: Vec<(String, String)>,
+}
+
+impl Reply {
+	/// Value of a header, by its full or compact name
+	fn header(&self, names: &[&str]) -> Option<&str> {
+		self.headers
+			.iter()
+			.find(|(name, _)| names.iter().any(|wanted| name.eq_ignore_ascii_case(wanted)))
+			.map(|(_, value)| value.as_str())
+	}
+
+	/// Comma-separated values of a header, wherever it repeats
+	fn list(&self, names: &[&str]) -> Vec<String> {
+		self.headers
+			.iter()
+			.filter(|(name, _)| names.iter().any(|wanted| name.eq_ignore_ascii_case(wanted)))
+			.flat_map(|(_, value)| value.split(','))
+			.map(|value| value.trim().to_string())
+			.filter(|value| !value.is_empty())
+			.collect()
+	}
+}
+
+/// Parse the head of a SIP response, and the length of the whole message, or `None` while more
+/// of it is to come or when it is not a response
+fn parse(data: &[u8]) -> Option<(Reply, usize)> {
+	let head_end = data.windows(4).position(|window| window == b"\r\n\r\n")?;
+	let head = String::from_utf8_lossy(&data[..head_end]);
+	let mut lines = head.split("\r\n");
+	let mut status_line = lines.next()?.splitn(3, ' ');
+	status_line.next().filter(|version| version.eq_ignore_ascii_case("SIP/2.0"))?;
+	let status = status_line.next()?.parse().ok()?;
+	let reason = status_line.next().unwrap_or_default().trim().to_string();
+	let headers = lines
+		.filter_map(|line| line.split_once(':'))
+		.map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+		.collect();
+	let reply = Reply { status, reason, headers };
+	let body = reply.header(&["content-length", "l"]).and_then(|length| length.parse::<usize>().ok()).unwrap_or(0);
+	Some((reply, head_end + 4 + body))
+}
+
+/// Whether a response is the final one to the call
+fn answers(reply: &Reply, call_id: &str) -> bool {
+	reply.status >= 200 && reply.header(&["call-id", "i"]) == Some(call_id)
+}
+
+/// Send a request over a connection and read the final response to it
+async fn exchange(mut stream: impl AsyncRead + AsyncWrite + Unpin, request: &str, call_id: &str) -> io::Result<Reply> {
+	stream.write_all(request.as_bytes()).await?;
+	stream.flush().await?;
+	let mut data = Vec::new();
+	let mut buffer = [0; 8192];
+	loop {
+		// Provisional responses, such as 100 Trying, come first
+		while let Some((reply, length)) = parse(&data) {
+			if answers(&reply, call_id) {
+				return Ok(reply);
+			}
+			data.drain(..length.min(data.len()));
+		}
+		if data.len() >= 5 && !data[..5].eq_ignore_ascii_case(b"SIP/2") {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "not a SIP response"));
+		}
+		let length = stream.read(&mut buffer).await?;
+		if length == 0 || data.len() >= MAX_MESSAGE {
+			return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "no final SIP response"));
+		}
+		data.extend_from_slice(&buffer[..length]);
+	}
+}
+
+/// Send a request to a port over a transport and read the final response to it
+async fn ask(
+	target: &str,
+	port: u16,
+	transport: Transport,
+	method: &str,
+	connector: &TlsConnector,
+	context: &ScanContext,
+) -> io::Result<Reply> {
+	let (request, call_id) = request(method, target, transport);
+	match transport {
+		Transport::Udp => {
+			let address = SocketAddr::new(context.resolve(target).await.map_err(io::Error::other)?, port);
+			let socket = context.transport.udp(address, context).await?;
+			socket.send_to(request.as_bytes(), address).await?;
+			let mut buffer = vec![0; MAX_MESSAGE];
+			loop {
+				let (length, _) = socket.recv_from(&mut buffer).await?;
+				if let Some((reply, _)) = parse(&buffer[..length])
+					&& answers(&reply, &call_id)
+				{
+					return Ok(reply);
+				}
+			}
+		},
+		Transport::Tcp => exchange(context.connect(target, port).await?, &request, &call_id).await,
+		Transport::Tls => {
+			let stream = context.connect(target, port).await?;
+			exchange(connector.connect(http::server_name(target)?, stream).await?, &request, &call_id).await
+		},
+	}
+}
+
+/// Results for the SIP service on a port over a transport, if it answers
+async fn enumerate(
+	target: &str,
+	port: u16,
+	transport: Transport,
+	connector: &TlsConnector,
+	context: &ScanContext,
+) -> Vec<ScanResult> {
+	let Some(Ok(options)) = context.probe(|| ask(target, port, transport, "OPTIONS", connector, context)).await else {
+		return Vec::new();
+	};
+	let service = if transport == Transport::Tls { "sips" } else { "sip" };
+	let mut details = vec![
+		format!("SIP over {}", transport.name()),
+		format!("{} {}", options.status, options.reason).trim_end().to_string(),
+	];
+	let methods = options.list(&["allow"]);
+	if !methods.is_empty() {
+		details.push(format!("methods {}", methods.join(", ")));
+	}
+	let extensions = options.list(&["supported", "k"]);
+	if !extensions.is_empty() {
+		details.push(format!("extensions {}", extensions.join(", ")));
+	}
+
+	let register = context.probe(|| ask(target, port, transport, "REGISTER", connector, context)).await;
+	let registers = match &register {
+		Some(Ok(reply)) => match reply.status {
+			200..=299 => {
+				details.push("REGISTER accepted without authentication".to_string());
+				true
+			},
+			401 | 407 => {
+				details.push("REGISTER requires authentication".to_string());
+				false
+			},
+			status => {
+				details.push(format!("REGISTER answered {} {}", status, reply.reason).trim_end().to_string());
+				false
+			},
+		},
+		_ => {
+			details.push("REGISTER unanswered".to_string());
+			false
+		},
+	};
+
+	let mut results = vec![ScanResult {
+		target: target.to_string(),
+		scan_type: ScanType::Sip.to_string(),
+		port: Some(port),
+		status: "open".to_string(),
+		severity: None,
+		service: Some(service.to_string()),
+		version: options.header(&["server"]).or(options.header(&["user-agent"])).map(str::to_string),
+		details: Some(details.join("; ")),
+		techniques: Vec::new(),
+		screenshot: None,
+		rtt: None,
+	}];
+	if registers {
+		results.push(ScanResult {
+			target: target.to_string(),
+			scan_type: ScanType::Sip.to_string(),
+			port: Some(port),
+			status: "sip_register".to_string(),
+			severity: Some(Severity::High),
+			service: Some(service.to_string()),
+			version: None,
+			details: Some(format!(
+				"Registrar accepts REGISTER over {} for a made-up user without authentication, so anyone can register as its users and take their calls",
+				transport.name()
+			)),
+			techniques: vec!["T1190".to_string()],
+			screenshot: None,
+			rtt: None,
+		});
+	}
+	results
+}
+
+/// SIP enumeration plugin
+pub struct SipPlugin;
+
+#[async_trait]
+impl Plugin for SipPlugin {
+	fn name(&self) -> String {
+		"SIP Enumerator".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::Sip
+	}
+
+	fn description(&self) -> String {
+		"Reports SIP server agents and methods, and registrars that accept registration without authentication".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"proxy",
+		]
+	}
+
+	fn proxy_capable(&self) -> bool {
+		true
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		// OPTIONS and REGISTER over each transport
+		context.ports_or(DEFAULT_PORTS).iter().map(|&port| 2 * Transport::on_port(port).len()).sum()
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		// Resolve up front to fail early, but connect by name
+		if context.proxy.is_none() {
+			context.resolve(target).await?;
+		}
+
+		let connector = TlsConnector::from(Arc::new(http::client_config(&[])?));
+		let mut results = Vec::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			for &transport in Transport::on_port(port) {
+				if transport == Transport::Udp && context.proxy.is_some() {
+					continue;
+				}
+				results.extend(enumerate(target, port, transport, &connector, context).await);
+			}
+		}
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scanner::Scanner;
+	use std::time::Duration;
+	use tokio::net::{TcpListener, UdpSocket};
+
+	/// Answer a request as a SIP server that allows registration as configured
+	fn answer(request: &str, open_registration: bool) -> String {
+		let header = |name: &str| {
+			request
+				.lines()
+				.find_map(|line| line.strip_prefix(&format!("{}: ", name)))
+				.unwrap_or_default()
+				.to_string()
+		};
+		let (status, extra) = match request.split(' ').next() {
+			Some("OPTIONS") => (
+				"200 OK",
+				"Server: Asterisk PBX 20.5.0\r\nAllow: INVITE, ACK, CANCEL\r\nAllow: OPTIONS, BYE\r\nSupported: replaces, timer\r\n",
+			),
+			_ if open_registration => ("200 OK", ""),
+			_ => ("401 Unauthorized", "WWW-Authenticate: Digest realm=\"asterisk\", nonce=\"1\"\r\n"),
+		};
+		format!(
+			"SIP/2.0 100 Trying\r\nCall-ID: {call_id}\r\nContent-Length: 0\r\n\r\nSIP/2.0 {status}\r\nVia: {via}\r\ni: {call_id}\r\nCSeq: {cseq}\r\n{extra}l: 0\r\n\r\n",
+			call_id = header("Call-ID"),
+			via = header("Via"),
+			cseq = header("CSeq"),
+		)
+	}
+
+	#[test]
+	fn test_parse() {
+		let (request, call_id) = request("REGISTER", "2001:db8::1", Transport::Udp);
+		assert!(request.starts_with("REGISTER sip:[2001:db8::1] SIP/2.0\r\nVia: SIP/2.0/UDP huginn.invalid;branch=z9hG4bK"));
+		assert!(request.contains("\r\nExpires: 0\r\n") && request.ends_with("Content-Length: 0\r\n\r\n"));
+
+		let data = answer(&request, false);
+		let (trying, length) = parse(data.as_bytes()).unwrap();
+		assert_eq!(trying.status, 100);
+		assert!(!answers(&trying, &call_id));
+		let (reply, _) = parse(&data.as_bytes()[length..]).unwrap();
+		assert_eq!((reply.status, reply.reason.as_str()), (401, "Unauthorized"));
+		assert!(answers(&reply, &call_id));
+		assert!(parse(b"HTTP/1.1 200 OK\r\n\r\n").is_none());
+	}
+
+	#[tokio::test]
+	async fn test_enumerates_transports() {
+		let context = Scanner::builder().timeout(Duration::from_millis(500)).retries(0).context(ScanType::Sip);
+		let connector = TlsConnector::from(Arc::new(http::client_config(&[]).unwrap()));
+
+		// A server that challenges registrations, over UDP
+		let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+		let udp = server.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			let mut buffer = [0; 4096];
+			while let Ok((length, peer)) = server.recv_from(&mut buffer).await {
+				let reply = answer(&String::from_utf8_lossy(&buffer[..length]), false);
+				// Each message in a datagram of its own
+				for message in reply.split_inclusive("\r\n\r\n") {
+					server.send_to(message.as_bytes(), peer).await.unwrap();
+				}
+			}
+		});
+		let results = enumerate("127.0.0.1", udp, Transport::Udp, &connector, &context).await;
+		assert_eq!(results.len(), 1);
+		assert_eq!(results[0].version.as_deref(), Some("Asterisk PBX 20.5.0"));
+		assert_eq!(
+			results[0].details.as_deref(),
+			Some(
+				"SIP over UDP; 200 OK; methods INVITE, ACK, CANCEL, OPTIONS, BYE; extensions replaces, timer; REGISTER requires authentication"
+			)
+		);
+
+		// A server that lets anyone register, over TCP
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let tcp = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Ok((mut stream, _)) = listener.accept().await {
+				let mut buffer = [0; 4096];
+				let length = stream.read(&mut buffer).await.unwrap_or_default();
+				let reply = answer(&String::from_utf8_lossy(&buffer[..length]), true);
+				let _ = stream.write_all(reply.as_bytes()).await;
+			}
+		});
+		let results = enumerate("127.0.0.1", tcp, Transport::Tcp, &connector, &context).await;
+		let statuses: Vec<_> = results.iter().map(|r| (r.status.as_str(), r.severity)).collect();
+		assert_eq!(statuses, [("open", None), ("sip_register", Some(Severity::High))]);
+		assert!(results[0].details.as_deref().unwrap().ends_with("REGISTER accepted without authentication"));
+		assert!(enumerate("127.0.0.1", udp, Transport::Tcp, &connector, &context).await.is_empty());
+	}
+}
@@ -0,0 +1,575 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! SNMP messages, for the scan types that query agents
+//!
+//! Just enough BER to send a `GetRequest` for a few objects and read the values that the
+//! `GetResponse` carries: community-based (v2c), or SNMPv3 with the user-based security model,
+//! whose keys, MACs, and encryption come from [`usm`](super::usm).
+
+use crate::credentials::{AuthProtocol, Credential, PrivacyProtocol};
+use crate::plugins::tls::random_bytes;
+use crate::plugins::usm;
+
+/// OID of `sysDescr.0`, the agent's description of its system, in BER
+pub(crate) const SYS_DESCR: &[u8] = &[0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00];
+
+/// OID of `sysObjectID.0`, the vendor's identification of the device model, in BER
+pub(crate) const SYS_OBJECT_ID: &[u8] = &[0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x02, 0x00];
+
+/// OID of `sysUpTime.0`, hundredths of a second since the agent started, in BER
+pub(crate) const SYS_UP_TIME: &[u8] = &[0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x03, 0x00];
+
+/// OID of `sysContact.0`, in BER
+pub(crate) const SYS_CONTACT: &[u8] = &[0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x04, 0x00];
+
+/// OID of `sysName.0`, the device's administratively assigned name, in BER
+pub(crate) const SYS_NAME: &[u8] = &[0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x05, 0x00];
+
+/// OID of `sysLocation.0`, in BER
+pub(crate) const SYS_LOCATION: &[u8] = &[0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x06, 0x00];
+
+/// OID prefix of the USM statistics that reports carry, `usmStats`, in BER
+const USM_STATS: &[u8] = &[0x2b, 0x06, 0x01, 0x06, 0x03, 0x0f, 0x01, 0x01];
+
+// BER tags
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const NULL: u8 = 0x05;
+const OID: u8 = 0x06;
+const SEQUENCE: u8 = 0x30;
+const TIME_TICKS: u8 = 0x43;
+const GET_REQUEST: u8 = 0xa0;
+const GET_RESPONSE: u8 = 0xa2;
+const REPORT: u8 = 0xa8;
+
+/// Version field of an SNMPv2c message
+const V2C: i64 = 1;
+
+/// Version field of an SNMPv3 message
+const V3: i64 = 3;
+
+/// Security model number of the user-based security model
+const USM: i64 = 3;
+
+/// Largest message an agent may answer with
+const MAX_SIZE: i64 = 65507;
+
+// SNMPv3 message flags
+const AUTH: u8 = 0x01;
+const PRIV: u8 = 0x02;
+const REPORTABLE: u8 = 0x04;
+
+/// Append a BER element
+pub(crate) fn element(buffer: &mut Vec<u8>, tag: u8, contents: &[u8]) {
+	buffer.push(tag);
+	match contents.len() {
+		length @ 0..=0x7f => buffer.push(length as u8),
+		length @ 0x80..=0xff => buffer.extend_from_slice(&[0x81, length as u8]),
+		length => {
+			buffer.push(0x82);
+			buffer.extend_from_slice(&(length as u16).to_be_bytes());
+		},
+	}
+	buffer.extend_from_slice(contents);
+}
+
+/// Contents of a BER integer, in the fewest bytes
+pub(crate) fn integer(value: i64) -> Vec<u8> {
+	let bytes = value.to_be_bytes();
+	// Leading bytes are redundant while they only repeat the sign of the next one
+	let start = (0..7)
+		.find(|&i| !matches!((bytes[i], bytes[i + 1] & 0x80), (0x00, 0) | (0xff, 0x80)))
+		.unwrap_or(7);
+	bytes[start..].to_vec()
+}
+
+/// The BER element at the front of some data: its tag, its contents, and the data after it
+pub(crate) fn read(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+	let (&tag, data) = data.split_first()?;
+	let (&first, data) = data.split_first()?;
+	let (length, data) = match first {
+		0x00..=0x7f => (usize::from(first), data),
+		0x81 => (usize::from(*data.first()?), &data[1..]),
+		0x82 => (usize::from(u16::from_be_bytes([*data.first()?, *data.get(1)?])), data.get(2..)?),
+		_ => return None,
+	};
+	(data.len() >= length).then(|| (tag, &data[..length], &data[length..]))
+}
+
+/// Contents of a non-negative BER integer that fits in 32 bits
+pub(crate) fn unsigned(contents: &[u8]) -> Option<u32> {
+	if contents.is_empty() || contents.len() > 5 || contents[0] & 0x80 != 0 {
+		return None;
+	}
+	let value = contents.iter().fold(0u64, |value, &byte| value << 8 | u64::from(byte));
+	u32::try_from(value).ok()
+}
+
+/// Dotted text of an OID's contents
+pub(crate) fn oid_text(contents: &[u8]) -> String {
+	let mut arcs = Vec::new();
+	let mut arc = 0u64;
+	for &byte in contents {
+		arc = arc << 7 | u64::from(byte & 0x7f);
+		if byte & 0x80 == 0 {
+			arcs.push(arc);
+			arc = 0;
+		}
+	}
+	// The first subidentifier packs the first two arcs
+	let mut text = match arcs.first() {
+		Some(&first) if first < 80 => format!("{}.{}", first / 40, first % 40),
+		Some(&first) => format!("2.{}", first - 80),
+		None => return String::new(),
+	};
+	for arc in &arcs[1..] {
+		text.push_str(&format!(".{}", arc));
+	}
+	text
+}
+
+/// Text of a value: strings on one line, OIDs dotted, and numbers in decimal; `None` for the
+/// exceptions that stand in for missing objects
+pub(crate) fn value_text(tag: u8, contents: &[u8]) -> Option<String> {
+	match tag {
+		OCTET_STRING => Some(String::from_utf8_lossy(contents).split_whitespace().collect::<Vec<_>>().join(" ")),
+		OID => Some(oid_text(contents)),
+		INTEGER | TIME_TICKS => unsigned(contents).map(|value| value.to_string()),
+		_ => None,
+	}
+}
+
+/// A PDU asking for objects
+fn pdu(tag: u8, request_id: i32, oids: &[&[u8]]) -> Vec<u8> {
+	let mut bindings = Vec::new();
+	for oid in oids {
+		let mut binding = Vec::new();
+		element(&mut binding, OID, oid);
+		element(&mut binding, NULL, &[]);
+		element(&mut bindings, SEQUENCE, &binding);
+	}
+
+	let mut contents = Vec::new();
+	element(&mut contents, INTEGER, &integer(request_id.into()));
+	// No error status or index
+	element(&mut contents, INTEGER, &[0]);
+	element(&mut contents, INTEGER, &[0]);
+	element(&mut contents, SEQUENCE, &bindings);
+	let mut pdu = Vec::new();
+	element(&mut pdu, tag, &contents);
+	pdu
+}
+
+/// An object and its value, from a response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Binding {
+	/// OID of the object, in BER
+	pub(crate) oid: Vec<u8>,
+	/// Tag of the value
+	pub(crate) tag: u8,
+	/// Contents of the value
+	pub(crate) value: Vec<u8>,
+}
+
+/// Request ID and variable bindings of a PDU's contents, or `None` when it reports an error
+fn bindings(pdu: &[u8]) -> Option<(&[u8], Vec<Binding>)> {
+	let (INTEGER, id, pdu) = read(pdu)? else {
+		return None;
+	};
+	let (INTEGER, [0], pdu) = read(pdu)? else {
+		return None;
+	};
+	let (INTEGER, _index, pdu) = read(pdu)? else {
+		return None;
+	};
+	let (SEQUENCE, mut list, _) = read(pdu)? else {
+		return None;
+	};
+	let mut bindings = Vec::new();
+	while !list.is_empty() {
+		let (SEQUENCE, binding, rest) = read(list)? else {
+			return None;
+		};
+		let (OID, oid, binding) = read(binding)? else {
+			return None;
+		};
+		let (tag, value, _) = read(binding)?;
+		bindings.push(Binding {
+			oid: oid.to_vec(),
+			tag,
+			value: value.to_vec(),
+		});
+		list = rest;
+	}
+	Some((id, bindings))
+}
+
+/// A v2c `GetRequest` for one object
+pub(crate) fn get_request(community: &str, request_id: i32, oid: &[u8]) -> Vec<u8> {
+	let mut message = Vec::new();
+	element(&mut message, INTEGER, &integer(V2C));
+	element(&mut message, OCTET_STRING, community.as_bytes());
+	message.extend_from_slice(&pdu(GET_REQUEST, request_id, &[oid]));
+	let mut datagram = Vec::new();
+	element(&mut datagram, SEQUENCE, &message);
+	datagram
+}
+
+/// The first object that a v2c `GetResponse` to a request ID carries, or `None` when the data is
+/// not a successful response to it
+pub(crate) fn response_value(data: &[u8], request_id: i32) -> Option<Binding> {
+	let (SEQUENCE, message, _) = read(data)? else {
+		return None;
+	};
+	let (INTEGER, _version, message) = read(message)? else {
+		return None;
+	};
+	let (OCTET_STRING, _community, message) = read(message)? else {
+		return None;
+	};
+	let (GET_RESPONSE, pdu, _) = read(message)? else {
+		return None;
+	};
+	let (id, bindings) = bindings(pdu)?;
+	if id != integer(request_id.into()) {
+		return None;
+	}
+	bindings.into_iter().next()
+}
+
+/// An SNMP engine's identity and clock, as its agent reports them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Engine {
+	/// Engine ID
+	pub(crate) id: Vec<u8>,
+	/// Times the engine has restarted
+	pub(crate) boots: u32,
+	/// Seconds since the engine last restarted
+	pub(crate) time: u32,
+}
+
+impl Engine {
+	/// Private enterprise number of the engine's vendor, from an engine ID in the RFC 3411 format
+	pub(crate) fn enterprise(&self) -> Option<u32> {
+		match self.id[..] {
+			[first, second, third, fourth, ..] if first & 0x80 != 0 => {
+				Some(u32::from_be_bytes([first & 0x7f, second, third, fourth]))
+			},
+			_ => None,
+		}
+	}
+}
+
+/// An SNMPv3 user, with its keys localized to an engine
+pub(crate) struct User {
+	name: String,
+	auth: Option<(AuthProtocol, Vec<u8>)>,
+	privacy: Option<Vec<u8>>,
+}
+
+impl User {
+	/// Localize the keys of an SNMPv3 credential to an engine; `None` for other credentials
+	pub(crate) fn new(credential: &Credential, engine: &Engine) -> Option<Self> {
+		let Credential::SnmpV3 { user, auth, privacy } = credential else {
+			return None;
+		};
+		let auth = auth
+			.as_ref()
+			.map(|(protocol, password)| (*protocol, usm::localize(*protocol, password, &engine.id)));
+		// Privacy keys are hashed with the authentication protocol, so privacy needs authentication
+		let privacy = auth
+			.as_ref()
+			.zip(privacy.as_ref())
+			.map(|((protocol, _), (PrivacyProtocol::Aes, password))| usm::localize(*protocol, password, &engine.id));
+		Some(Self {
+			name: user.clone(),
+			auth,
+			privacy,
+		})
+	}
+
+	/// Security level of the user's requests
+	pub(crate) fn level(&self) -> &'static str {
+		match (&self.auth, &self.privacy) {
+			(Some(_), Some(_)) => "authPriv",
+			(Some(_), None) => "authNoPriv",
+			_ => "noAuthNoPriv",
+		}
+	}
+}
+
+/// A scoped PDU, for the engine's default context
+fn scoped_pdu(engine: &Engine, pdu: &[u8]) -> Vec<u8> {
+	let mut contents = Vec::new();
+	element(&mut contents, OCTET_STRING, &engine.id);
+	element(&mut contents, OCTET_STRING, &[]);
+	contents.extend_from_slice(pdu);
+	let mut scoped = Vec::new();
+	element(&mut scoped, SEQUENCE, &contents);
+	scoped
+}
+
+/// An SNMPv3 message carrying a scoped PDU, authenticated and encrypted as far as the user's keys
+/// allow
+fn message_v3(message_id: i32, engine: &Engine, user: Option<&User>, scoped: &[u8]) -> Vec<u8> {
+	let auth = user.and_then(|user| user.auth.as_ref());
+	let mut flags = REPORTABLE;
+	if auth.is_some() {
+		flags |= AUTH;
+	}
+	let (data, salt) = match user.and_then(|user| user.privacy.as_ref()) {
+		Some(key) => {
+			flags |= PRIV;
+			let salt = random_bytes(8);
+			let mut data = Vec::new();
+			element(&mut data, OCTET_STRING, &usm::cfb(key, engine.boots, engine.time, &salt, scoped, false));
+			(data, salt)
+		},
+		None => (scoped.to_vec(), Vec::new()),
+	};
+	let mac_length = auth.map_or(0, |&(protocol, _)| usm::mac_length(protocol));
+
+	let mut header = Vec::new();
+	element(&mut header, INTEGER, &integer(message_id.into()));
+	element(&mut header, INTEGER, &integer(MAX_SIZE));
+	element(&mut header, OCTET_STRING, &[flags]);
+	element(&mut header, INTEGER, &integer(USM));
+
+	let mut salt_element = Vec::new();
+	element(&mut salt_element, OCTET_STRING, &salt);
+	let mut security = Vec::new();
+	element(&mut security, OCTET_STRING, &engine.id);
+	element(&mut security, INTEGER, &integer(engine.boots.into()));
+	element(&mut security, INTEGER, &integer(engine.time.into()));
+	element(&mut security, OCTET_STRING, user.map_or(&[][..], |user| user.name.as_bytes()));
+	// The MAC is computed over the message with zeros in its place
+	element(&mut security, OCTET_STRING, &vec![0; mac_length]);
+	security.extend_from_slice(&salt_element);
+	let mut parameters = Vec::new();
+	element(&mut parameters, SEQUENCE, &security);
+
+	let mut contents = Vec::new();
+	element(&mut contents, INTEGER, &integer(V3));
+	element(&mut contents, SEQUENCE, &header);
+	element(&mut contents, OCTET_STRING, &parameters);
+	contents.extend_from_slice(&data);
+	let mut message = Vec::new();
+	element(&mut message, SEQUENCE, &contents);
+
+	if let Some(&(protocol, ref key)) = auth {
+		let end = message.len() - data.len() - salt_element.len();
+		let mac = usm::sign(protocol, key, &message);
+		message[end - mac_length..end].copy_from_slice(&mac);
+	}
+	message
+}
+
+/// An unauthenticated request, for no user and no objects, that makes an agent report its engine
+pub(crate) fn discovery(message_id: i32) -> Vec<u8> {
+	let engine = Engine::default();
+	message_v3(message_id, &engine, None, &scoped_pdu(&engine, &pdu(GET_REQUEST, message_id, &[])))
+}
+
+/// A v3 `GetRequest` for objects, from a user to an engine
+pub(crate) fn get_request_v3(user: &User, engine: &Engine, message_id: i32, oids: &[&[u8]]) -> Vec<u8> {
+	message_v3(message_id, engine, Some(user), &scoped_pdu(engine, &pdu(GET_REQUEST, message_id, oids)))
+}
+
+/// What an agent answered an SNMPv3 request with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Reply {
+	/// The objects asked for, with their values
+	Response(Vec<Binding>),
+	/// A report that the request failed, with the engine as the agent sees it and the `usmStats`
+	/// counter that counted the failure, by its last arc (1 to 6)
+	Report(Engine, Option<u8>),
+}
+
+/// The reply to the SNMPv3 message with an ID, authenticated and decrypted with the user's keys;
+/// `None` when the data is not such a reply, or fails authentication
+pub(crate) fn reply_v3(data: &[u8], message_id: i32, user: Option<&User>) -> Option<Reply> {
+	let (SEQUENCE, message, _) = read(data)? else {
+		return None;
+	};
+	let (INTEGER, version, message) = read(message)? else {
+		return None;
+	};
+	let (SEQUENCE, header, message) = read(message)? else {
+		return None;
+	};
+	let (INTEGER, id, header) = read(header)? else {
+		return None;
+	};
+	let (INTEGER, _max_size, header) = read(header)? else {
+		return None;
+	};
+	let (OCTET_STRING, &[flags], _) = read(header)? else {
+		return None;
+	};
+	if version != integer(V3) || id != integer(message_id.into()) {
+		return None;
+	}
+
+	let (OCTET_STRING, parameters, scoped) = read(message)? else {
+		return None;
+	};
+	let (SEQUENCE, security, _) = read(parameters)? else {
+		return None;
+	};
+	let (OCTET_STRING, engine_id, security) = read(security)? else {
+		return None;
+	};
+	let (INTEGER, boots, security) = read(security)? else {
+		return None;
+	};
+	let (INTEGER, time, security) = read(security)? else {
+		return None;
+	};
+	let (OCTET_STRING, _user, security) = read(security)? else {
+		return None;
+	};
+	let (OCTET_STRING, mac, security) = read(security)? else {
+		return None;
+	};
+	let (OCTET_STRING, salt, _) = read(security)? else {
+		return None;
+	};
+	let engine = Engine {
+		id: engine_id.to_vec(),
+		boots: unsigned(boots)?,
+		time: unsigned(time)?,
+	};
+
+	if flags & AUTH != 0 {
+		let (protocol, key) = user?.auth.as_ref()?;
+		// The MAC is a slice of the data, so its address locates it
+		let start = mac.as_ptr().addr() - data.as_ptr().addr();
+		let mut zeroed = data.to_vec();
+		zeroed[start..start + mac.len()].fill(0);
+		if usm::sign(*protocol, key, &zeroed) != mac {
+			return None;
+		}
+	}
+	let decrypted;
+	let scoped = if flags & PRIV != 0 {
+		let (OCTET_STRING, encrypted, _) = read(scoped)? else {
+			return None;
+		};
+		let key = user?.privacy.as_ref()?;
+		if salt.len() != 8 {
+			return None;
+		}
+		decrypted = usm::cfb(key, engine.boots, engine.time, salt, encrypted, true);
+		&decrypted[..]
+	} else {
+		scoped
+	};
+
+	let (SEQUENCE, scoped, _) = read(scoped)? else {
+		return None;
+	};
+	let (OCTET_STRING, _context_engine, scoped) = read(scoped)? else {
+		return None;
+	};
+	let (OCTET_STRING, _context_name, scoped) = read(scoped)? else {
+		return None;
+	};
+	let (tag, pdu, _) = read(scoped)?;
+	let (_, bindings) = bindings(pdu)?;
+	match tag {
+		GET_RESPONSE => Some(Reply::Response(bindings)),
+		REPORT => {
+			let counter = bindings.first().and_then(|binding| binding.oid.strip_prefix(USM_STATS)?.first().copied());
+			Some(Reply::Report(engine, counter))
+		},
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::credentials::Secret;
+
+	#[test]
+	fn test_integer() {
+		assert_eq!(integer(0), [0x00]);
+		assert_eq!(integer(127), [0x7f]);
+		assert_eq!(integer(128), [0x00, 0x80]);
+		assert_eq!(integer(-1), [0xff]);
+		assert_eq!(integer(0x1234_5678), [0x12, 0x34, 0x56, 0x78]);
+	}
+
+	#[test]
+	fn test_get_round_trip() {
+		let request = get_request("public", 0x1234, SYS_DESCR);
+		assert_eq!(
+			request,
+			[
+				0x30, 0x27, 0x02, 0x01, 0x01, 0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c', 0xa0, 0x1a, 0x02, 0x02, 0x12, 0x34,
+				0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30, 0x0e, 0x30, 0x0c, 0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01,
+				0x00, 0x05, 0x00,
+			]
+		);
+
+		// An agent answers with the same message, as a response carrying the value
+		let mut response = request.clone();
+		response[13] = GET_RESPONSE;
+		response.truncate(response.len() - 2);
+		response.extend_from_slice(&[OCTET_STRING, 0x05, b'L', b'i', b'n', b'u', b'x']);
+		for length in [1, 14, 26, 28] {
+			response[length] += 5;
+		}
+		let value = response_value(&response, 0x1234).map(|binding| (binding.tag, binding.value));
+		assert_eq!(value, Some((OCTET_STRING, b"Linux".to_vec())));
+		assert_eq!(response_value(&response, 0x4321), None);
+		assert_eq!(response_value(&request, 0x1234), None);
+	}
+
+	#[test]
+	fn test_oid_text() {
+		assert_eq!(oid_text(SYS_DESCR), "1.3.6.1.2.1.1.1.0");
+		assert_eq!(oid_text(&[0x2b, 0x06, 0x01, 0x04, 0x01, 0x89, 0x37]), "1.3.6.1.4.1.1207");
+		assert_eq!(value_text(TIME_TICKS, &[0x00, 0x98, 0x96, 0x80]).as_deref(), Some("10000000"));
+		assert_eq!(value_text(0x80, &[]), None);
+	}
+
+	#[test]
+	fn test_v3_round_trip() {
+		let engine = Engine {
+			id: vec![0x80, 0x00, 0x1f, 0x88, 0x80, 0x01, 0x02, 0x03, 0x04],
+			boots: 3,
+			time: 86400,
+		};
+		assert_eq!(engine.enterprise(), Some(8072));
+
+		// Agents report their engine to the discovery request, unauthenticated
+		let report = message_v3(7, &engine, None, &scoped_pdu(&engine, &pdu(REPORT, 7, &[&[USM_STATS, &[4, 0]].concat()])));
+		assert_eq!(reply_v3(&report, 7, None), Some(Reply::Report(engine.clone(), Some(4))));
+		assert_eq!(reply_v3(&report, 8, None), None);
+		assert_eq!(reply_v3(&discovery(7), 7, None), None);
+
+		let credential = |auth: &str, privacy: &str| Credential::SnmpV3 {
+			user: "monitor".to_string(),
+			auth: Some((AuthProtocol::Sha256, Secret::new(auth))),
+			privacy: Some((PrivacyProtocol::Aes, Secret::new(privacy))),
+		};
+		let user = User::new(&credential("authpass1", "privpass1"), &engine).unwrap();
+		assert_eq!(user.level(), "authPriv");
+		let request = get_request_v3(&user, &engine, 9, &[SYS_NAME]);
+		assert!(!request.windows(SYS_NAME.len()).any(|window| window == SYS_NAME), "the PDU is encrypted");
+
+		// The agent answers with its own salt, authenticated and encrypted with the same keys
+		let response = message_v3(9, &engine, Some(&user), &scoped_pdu(&engine, &pdu(GET_RESPONSE, 9, &[SYS_NAME])));
+		let Some(Reply::Response(bindings)) = reply_v3(&response, 9, Some(&user)) else {
+			panic!("the response did not authenticate");
+		};
+		assert_eq!(bindings[0].oid, SYS_NAME);
+		let stranger = User::new(&credential("authpass2", "privpass1"), &engine).unwrap();
+		assert_eq!(reply_v3(&response, 9, Some(&stranger)), None);
+		let mut tampered = response.clone();
+		*tampered.last_mut().unwrap() ^= 1;
+		assert_eq!(reply_v3(&tampered, 9, Some(&user)), None);
+	}
+}
@@ -0,0 +1,321 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! SNMPv3 inventory plugin
+//!
+//! Inventories devices that answer only SNMPv3, as they should once v1 and v2c are disabled.
+//! Each port (161 by default) is first sent an unauthenticated discovery request, which every
+//! SNMPv3 agent answers with a report of its engine: the engine ID, naming the vendor's
+//! enterprise number, and how often and how long ago the engine restarted. The SNMPv3
+//! credentials the scan uses ([`ScanContext::credentials`]) are then tried in name order, each
+//! at the security level it allows (authNoPriv or authPriv), until one reads the system group:
+//! `sysName`, `sysDescr`, `sysObjectID`, `sysLocation`, `sysContact`, and `sysUpTime`.
+//! Credentials the agent rejects are listed with the reason its report gives, such as an unknown
+//! user name or a wrong digest. Each agent is reported as one `open` result, without a finding.
+
+use crate::credentials::Credential;
+use crate::error::HuginnError;
+use crate::plugins::snmp::{self, Binding, Engine, Reply, User};
+use crate::plugins::tls::random_bytes;
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType};
+use async_trait::async_trait;
+use humantime_serde::re::humantime;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Ports probed when no port spec is configured
+const DEFAULT_PORTS: &[u16] = &[161];
+
+/// Largest reply read
+const MAX_ANSWER: usize = 65535;
+
+/// Objects of the system group read with a credential, by name
+const SYSTEM: &[(&str, &[u8])] = &[
+	("sysName", snmp::SYS_NAME),
+	("sysDescr", snmp::SYS_DESCR),
+	("sysObjectID", snmp::SYS_OBJECT_ID),
+	("sysLocation", snmp::SYS_LOCATION),
+	("sysContact", snmp::SYS_CONTACT),
+	("sysUpTime", snmp::SYS_UP_TIME),
+];
+
+/// `usmStats` counter of requests outside the engine's time window
+const NOT_IN_TIME_WINDOW: u8 = 2;
+
+/// The SNMPv3 credentials of a scan, by name
+fn credentials(context: &ScanContext) -> Vec<(&str, &Credential)> {
+	context
+		.credentials
+		.iter()
+		.filter(|(_, credential)| matches!(credential, Credential::SnmpV3 { .. }))
+		.collect()
+}
+
+/// Why an agent refused a request, by the `usmStats` counter its report names
+fn refusal(counter: Option<u8>) -> &'static str {
+	match counter {
+		Some(1) => "unsupported security level",
+		Some(NOT_IN_TIME_WINDOW) => "not in time window",
+		Some(3) => "unknown user name",
+		Some(4) => "unknown engine ID",
+		Some(5) => "wrong digest, so a wrong authentication password",
+		Some(6) => "decryption error, so a wrong privacy password",
+		_ => "refused",
+	}
+}
+
+/// A random message ID
+fn message_id() -> i32 {
+	let id = random_bytes(4);
+	i32::from_be_bytes([id[0] & 0x7f, id[1], id[2], id[3]])
+}
+
+/// Send a message to a port and read the reply to it
+async fn exchange(
+	target: &str,
+	port: u16,
+	message: &[u8],
+	id: i32,
+	user: Option<&User>,
+	context: &ScanContext,
+) -> io::Result<Reply> {
+	let address = SocketAddr::new(context.resolve(target).await.map_err(io::Error::other)?, port);
+	let socket = context.transport.udp(address, context).await?;
+	socket.send_to(message, address).await?;
+	let mut buffer = vec![0; MAX_ANSWER];
+	loop {
+		let (length, from) = socket.recv_from(&mut buffer).await?;
+		if from == address
+			&& let Some(reply) = snmp::reply_v3(&buffer[..length], id, user)
+		{
+			return Ok(reply);
+		}
+	}
+}
+
+/// Read the system group as a user, catching up once with an engine whose clock has moved on
+async fn read_system(
+	target: &str,
+	port: u16,
+	user: &User,
+	engine: &Engine,
+	context: &ScanContext,
+) -> Result<Vec<Binding>, &'static str> {
+	let oids: Vec<&[u8]> = SYSTEM.iter().map(|&(_, oid)| oid).collect();
+	let mut engine = engine.clone();
+	for _ in 0..2 {
+		let id = message_id();
+		let request = snmp::get_request_v3(user, &engine, id, &oids);
+		match context.probe(|| exchange(target, port, &request, id, Some(user), context)).await {
+			Some(Ok(Reply::Response(bindings))) => return Ok(bindings),
+			// The report carries the engine's current clock
+			Some(Ok(Reply::Report(current, Some(NOT_IN_TIME_WINDOW)))) => engine = current,
+			Some(Ok(Reply::Report(_, counter))) => return Err(refusal(counter)),
+			Some(Err(_)) | None => return Err("no answer"),
+		}
+	}
+	Err(refusal(Some(NOT_IN_TIME_WINDOW)))
+}
+
+/// Describe an engine
+fn describe(engine: &Engine) -> String {
+	let id: String = engine.id.iter().map(|byte| format!("{:02x}", byte)).collect();
+	let vendor = engine.enterprise().map_or(String::new(), |number| format!(" (enterprise {})", number));
+	format!(
+		"SNMPv3 engine {}{}, {} boots, up {}",
+		id,
+		vendor,
+		engine.boots,
+		humantime::format_duration(Duration::from_secs(engine.time.into()))
+	)
+}
+
+/// Describe the system group an agent returned
+fn system(bindings: &[Binding]) -> String {
+	SYSTEM
+		.iter()
+		.filter_map(|&(name, oid)| {
+			let binding = bindings.iter().find(|binding| binding.oid == oid)?;
+			let value = snmp::value_text(binding.tag, &binding.value)?;
+			Some(match name {
+				// Hundredths of a second
+				"sysUpTime" => {
+					let uptime = Duration::from_secs(value.parse::<u64>().ok()? / 100);
+					format!("{} {}", name, humantime::format_duration(uptime))
+				},
+				"sysObjectID" => format!("{} {}", name, value),
+				_ => format!("{} {:?}", name, value),
+			})
+		})
+		.collect::<Vec<_>>()
+		.join(", ")
+}
+
+/// The result for the SNMPv3 agent on a port, if one answers
+async fn inspect(target: &str, port: u16, context: &ScanContext) -> Option<ScanResult> {
+	let id = message_id();
+	let discovery = snmp::discovery(id);
+	let Some(Ok(Reply::Report(engine, _))) = context.probe(|| exchange(target, port, &discovery, id, None, context)).await
+	else {
+		return None;
+	};
+
+	let mut details = describe(&engine);
+	let mut refused = Vec::new();
+	for (name, credential) in credentials(context) {
+		let Some(user) = User::new(credential, &engine) else {
+			continue;
+		};
+		match read_system(target, port, &user, &engine, context).await {
+			Ok(bindings) => {
+				details.push_str(&format!("; read as {} ({}): {}", name, user.level(), system(&bindings)));
+				break;
+			},
+			Err(reason) => refused.push(format!("{} ({})", name, reason)),
+		}
+	}
+	if !refused.is_empty() {
+		details.push_str(&format!("; credentials refused: {}", refused.join(", ")));
+	}
+
+	Some(ScanResult {
+		target: target.to_string(),
+		scan_type: ScanType::SnmpInventory.to_string(),
+		port: Some(port),
+		status: "open".to_string(),
+		severity: None,
+		service: Some("snmp".to_string()),
+		version: None,
+		details: Some(details),
+		techniques: Vec::new(),
+		screenshot: None,
+		rtt: None,
+	})
+}
+
+/// SNMPv3 inventory plugin
+pub struct SnmpInventoryPlugin;
+
+#[async_trait]
+impl Plugin for SnmpInventoryPlugin {
+	fn name(&self) -> String {
+		"SNMPv3 Inventory".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::SnmpInventory
+	}
+
+	fn description(&self) -> String {
+		"Discovers SNMPv3 engines and reads each device's system group with the configured credentials".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"use_credentials",
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+		]
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		context.ports_or(DEFAULT_PORTS).len() * (1 + credentials(context).len())
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		context.resolve(target).await?;
+
+		let (target, context) = (Arc::new(target.to_string()), Arc::new(context.clone()));
+		// A probe delay paces the probes one after another
+		let width = if context.scan_delay.is_zero() { context.max_connections } else { 1 };
+		let permits = Arc::new(Semaphore::new(width));
+		let mut probes = JoinSet::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			let (target, context, permits) = (target.clone(), context.clone(), permits.clone());
+			probes.spawn(async move {
+				let _permit = permits.acquire_owned().await;
+				inspect(&target, port, &context).await
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(joined) = probes.join_next().await {
+			results.extend(joined.map_err(io::Error::other)?);
+		}
+		results.sort_by_key(|r| r.port);
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::credentials::{AuthProtocol, Credentials, PrivacyProtocol, Secret};
+	use crate::scanner::Scanner;
+	use tokio::net::UdpSocket;
+
+	#[test]
+	fn test_system() {
+		let binding = |oid: &[u8], tag, value: &[u8]| Binding {
+			oid: oid.to_vec(),
+			tag,
+			value: value.to_vec(),
+		};
+		let bindings = [
+			binding(snmp::SYS_DESCR, 0x04, b"Linux  core-sw1"),
+			binding(snmp::SYS_UP_TIME, 0x43, &[0x00, 0x98, 0x96, 0x80]),
+			binding(snmp::SYS_NAME, 0x04, b"core-sw1"),
+			// noSuchObject
+			binding(snmp::SYS_LOCATION, 0x80, &[]),
+		];
+		assert_eq!(
+			system(&bindings),
+			"sysName \"core-sw1\", sysDescr \"Linux core-sw1\", sysUpTime 1day 3h 46m 40s"
+		);
+	}
+
+	#[tokio::test]
+	async fn test_ignores_other_services() {
+		let credentials: Credentials = [(
+			"switches",
+			Credential::SnmpV3 {
+				user: "monitor".to_string(),
+				auth: Some((AuthProtocol::Sha, Secret::new("auth-password"))),
+				privacy: Some((PrivacyProtocol::Aes, Secret::new("privacy-password"))),
+			},
+		)]
+		.into_iter()
+		.collect();
+		let context = Scanner::builder()
+			.timeout(Duration::from_millis(300))
+			.retries(0)
+			.credentials(credentials)
+			.context(ScanType::SnmpInventory);
+		assert_eq!(SnmpInventoryPlugin.probes_per_target(&context), 2);
+
+		// A v2c-only agent answers the discovery request with something that is not SNMPv3
+		let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+		let port = server.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			let mut buffer = [0; 2048];
+			while let Ok((_, peer)) = server.recv_from(&mut buffer).await {
+				let reply = snmp::get_request("public", 1, snmp::SYS_DESCR);
+				server.send_to(&reply, peer).await.unwrap();
+			}
+		});
+		assert!(inspect("127.0.0.1", port, &context).await.is_none());
+	}
+}
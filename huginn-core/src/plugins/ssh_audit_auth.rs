@@ -0,0 +1,699 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Authenticated SSH configuration audit plugin
+//!
+//! A lightweight, agentless posture check: logs in to each port (22 by default) with the SSH
+//! credentials the scan uses ([`ScanContext::credentials`]), tried in name order until one
+//! works, and runs a short POSIX shell script that reads:
+//!
+//! - the OS release, from `/etc/os-release`
+//! - the SSH server's settings, effective ones from `sshd -T` where the user may run it, and
+//!   otherwise the global ones in `/etc/ssh/sshd_config`
+//! - the listening sockets, from `ss` or `netstat`
+//! - the pending security updates, from apt, dnf, yum, or zypper, without installing anything
+//!
+//! Logins go through the system's OpenSSH client ([`SshAuditConfig::ssh`]), so host keys are
+//! checked against `known_hosts` as for any other login and hosts with unknown keys are refused
+//! unless [`SshAuditConfig::accept_new_host_keys`] is set. Private keys are handed to the client
+//! in a file only the scanning user can read, and passwords and passphrases through `SSH_ASKPASS`,
+//! so secrets never appear on a command line; password logins need a Unix host.
+//!
+//! Each host is reported as one `open` result describing what was read, with a finding for each
+//! risky SSH server setting (empty passwords, root logins, password logins, X11 forwarding, and
+//! weak algorithms), one for pending security updates, and an informational one listing the
+//! sockets that listen on every interface. Like [`default_creds`](super::default_creds), it logs
+//! in, so it is left out of [`crate::plugins::builtin`]. It is skipped when a proxy is configured.
+
+use crate::credentials::{Credential, Secret};
+use crate::error::HuginnError;
+use crate::plugins::tls::random_bytes;
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Ports probed when no port spec is configured
+const DEFAULT_PORTS: &[u16] = &[22];
+
+/// Longest a login and the audit script may take; package managers can be slow to answer
+const MAX_AUDIT_TIME: Duration = Duration::from_secs(120);
+
+/// Script run on each host; each section starts with an `@@` line naming it
+const SCRIPT: &str = r#"PATH="$PATH:/usr/sbin:/sbin"
+echo '@@ os-release'
+cat /etc/os-release 2>/dev/null
+echo '@@ sshd -T'
+sshd -T 2>/dev/null
+echo '@@ sshd_config'
+cat /etc/ssh/sshd_config 2>/dev/null
+echo '@@ listening'
+ss -Htuln 2>/dev/null || netstat -tuln 2>/dev/null
+if command -v apt-get >/dev/null 2>&1; then
+	echo '@@ updates apt'
+	apt-get -s -o Debug::NoLocking=1 upgrade 2>/dev/null | grep '^Inst' | grep -i security
+elif command -v dnf >/dev/null 2>&1; then
+	echo '@@ updates dnf'
+	dnf -q updateinfo list --security 2>/dev/null
+elif command -v yum >/dev/null 2>&1; then
+	echo '@@ updates yum'
+	yum -q updateinfo list security 2>/dev/null
+elif command -v zypper >/dev/null 2>&1; then
+	echo '@@ updates zypper'
+	zypper -q -n list-patches -g security 2>/dev/null
+fi
+"#;
+
+/// Helper that answers the client's password and passphrase prompts from its environment
+const ASKPASS: &str = r#"#!/bin/sh
+case "$1" in
+*assphrase*) printf '%s\n' "$HUGINN_SSH_PASSPHRASE" ;;
+*) printf '%s\n' "$HUGINN_SSH_PASSWORD" ;;
+esac
+"#;
+
+/// Algorithm names, or parts of them, that are weak
+const WEAK_ALGORITHMS: &[&str] = &[
+	"-cbc",
+	"arcfour",
+	"3des",
+	"hmac-md5",
+	"hmac-sha1",
+	"diffie-hellman-group1-sha1",
+	"diffie-hellman-group14-sha1",
+	"diffie-hellman-group-exchange-sha1",
+];
+
+/// Settings of the `ssh_audit_auth` scan type
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SshAuditConfig {
+	/// OpenSSH client that logs in
+	pub ssh: PathBuf,
+	/// Known hosts file that host keys are checked against; the user's own when unset
+	pub known_hosts: Option<PathBuf>,
+	/// Trust and remember the keys of hosts that are not known yet, instead of refusing them
+	pub accept_new_host_keys: bool,
+}
+
+impl Default for SshAuditConfig {
+	fn default() -> Self {
+		Self {
+			ssh: PathBuf::from("ssh"),
+			known_hosts: None,
+			accept_new_host_keys: false,
+		}
+	}
+}
+
+/// The SSH credentials of a scan, by name
+fn credentials(context: &ScanContext) -> Vec<(&str, &Credential)> {
+	context
+		.credentials
+		.iter()
+		.filter(|(_, credential)| matches!(credential, Credential::Ssh { .. }))
+		.collect()
+}
+
+/// A private directory for the key file and prompt helper of one login, removed when dropped
+struct Scratch(PathBuf);
+
+impl Scratch {
+	fn new() -> io::Result<Self> {
+		let token: String = random_bytes(8).iter().map(|byte| format!("{:02x}", byte)).collect();
+		let path = std::env::temp_dir().join(format!("huginn-ssh-{}-{}", std::process::id(), token));
+		let mut builder = std::fs::DirBuilder::new();
+		#[cfg(unix)]
+		std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+		builder.create(&path)?;
+		Ok(Self(path))
+	}
+
+	/// Write a file that only the owner can read, and execute when asked
+	fn write(&self, name: &str, contents: &str, executable: bool) -> io::Result<PathBuf> {
+		let path = self.0.join(name);
+		let mut options = std::fs::OpenOptions::new();
+		options.write(true).create_new(true);
+		#[cfg(unix)]
+		std::os::unix::fs::OpenOptionsExt::mode(&mut options, if executable { 0o700 } else { 0o600 });
+		#[cfg(not(unix))]
+		let _ = executable;
+		io::Write::write_all(&mut options.open(&path)?, contents.as_bytes())?;
+		Ok(path)
+	}
+}
+
+impl Drop for Scratch {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_dir_all(&self.0);
+	}
+}
+
+/// How a login went
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Login {
+	/// Logged in and ran the script, which printed this
+	Audited(String),
+	/// The server refused the credential
+	Refused(String),
+	/// The host key is unknown or has changed
+	HostKey,
+	/// No SSH server could be reached
+	Unreachable(String),
+}
+
+/// Classify a failed login by the client's error output
+fn failure(stderr: &str) -> Login {
+	let last = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("no error output").trim();
+	if stderr.contains("Host key verification failed") || stderr.contains("IDENTIFICATION HAS CHANGED") {
+		Login::HostKey
+	} else if stderr.contains("Permission denied") || stderr.contains("Too many authentication failures") {
+		Login::Refused("permission denied".to_string())
+	} else if stderr.contains("Load key") || stderr.contains("invalid format") {
+		Login::Refused(format!("unusable key: {}", last))
+	} else {
+		Login::Unreachable(last.to_string())
+	}
+}
+
+/// Arguments of the client, without the destination
+fn arguments(
+	config: &SshAuditConfig,
+	user: &str,
+	key: Option<&Path>,
+	prompts: bool,
+	port: u16,
+	context: &ScanContext,
+) -> Vec<String> {
+	let mut arguments = vec!["-T".to_string()];
+	let mut option = |option: String| arguments.extend(["-o".to_string(), option]);
+	option(format!("BatchMode={}", if prompts { "no" } else { "yes" }));
+	option(format!("ConnectTimeout={}", context.timeout.as_secs().max(1)));
+	option("NumberOfPasswordPrompts=1".to_string());
+	option("LogLevel=ERROR".to_string());
+	option(format!("StrictHostKeyChecking={}", if config.accept_new_host_keys { "accept-new" } else { "yes" }));
+	if let Some(known_hosts) = &config.known_hosts {
+		option(format!("UserKnownHostsFile={}", known_hosts.display()));
+	}
+	match key {
+		Some(key) => {
+			option("IdentitiesOnly=yes".to_string());
+			arguments.extend(["-i".to_string(), key.display().to_string()]);
+		},
+		None => option("PubkeyAuthentication=no".to_string()),
+	}
+	if let Some(source) = context.source_ip {
+		arguments.extend(["-b".to_string(), source.to_string()]);
+	}
+	arguments.extend(["-p".to_string(), port.to_string(), "-l".to_string(), user.to_string()]);
+	arguments
+}
+
+/// Log in with a credential and run the audit script
+async fn login(
+	config: &SshAuditConfig,
+	target: &str,
+	port: u16,
+	credential: &Credential,
+	context: &ScanContext,
+) -> Result<Login, HuginnError> {
+	let Credential::Ssh {
+		user,
+		key,
+		passphrase,
+		password,
+	} = credential
+	else {
+		return Err(HuginnError::Config("not an SSH credential".to_string()));
+	};
+	let scratch = Scratch::new()?;
+	let key = key.as_ref().map(|key| scratch.write("key", key.expose(), false)).transpose()?;
+	let prompts = passphrase.is_some() || password.is_some();
+
+	let mut command = Command::new(&config.ssh);
+	command
+		.args(arguments(config, user, key.as_deref(), prompts, port, context))
+		.arg("--")
+		.arg(target)
+		.arg("sh -s")
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.kill_on_drop(true);
+	if prompts {
+		let secret = |secret: &Option<Secret>| secret.as_ref().map_or("", Secret::expose).to_string();
+		command
+			.env("SSH_ASKPASS", scratch.write("askpass", ASKPASS, true)?)
+			.env("SSH_ASKPASS_REQUIRE", "force")
+			.env("DISPLAY", std::env::var("DISPLAY").unwrap_or_else(|_| ":0".to_string()))
+			.env("HUGINN_SSH_PASSWORD", secret(password))
+			.env("HUGINN_SSH_PASSPHRASE", secret(passphrase));
+	}
+
+	let mut child = command.spawn().map_err(|e| {
+		HuginnError::Config(format!("failed to run the SSH client {}: {}", config.ssh.display(), e))
+	})?;
+	if let Some(mut stdin) = child.stdin.take() {
+		// A client that fails before reading the script closes its input
+		let _ = stdin.write_all(SCRIPT.as_bytes()).await;
+	}
+	let output = match tokio::time::timeout(MAX_AUDIT_TIME, child.wait_with_output()).await {
+		Ok(output) => output?,
+		Err(_) => return Ok(Login::Unreachable(format!("no result within {:?}", MAX_AUDIT_TIME))),
+	};
+	// The client exits with 255 when it fails itself; any other status is the script's
+	Ok(if output.status.code() == Some(255) {
+		failure(&String::from_utf8_lossy(&output.stderr))
+	} else {
+		Login::Audited(String::from_utf8_lossy(&output.stdout).into_owned())
+	})
+}
+
+/// Lines of each section of the script's output, by name
+fn sections(output: &str) -> HashMap<&str, Vec<&str>> {
+	let mut sections: HashMap<&str, Vec<&str>> = HashMap::new();
+	let mut current = None;
+	for line in output.lines() {
+		if let Some(name) = line.strip_prefix("@@ ") {
+			current = Some(name.trim());
+			sections.entry(name.trim()).or_default();
+		} else if let Some(name) = current
+			&& !line.trim().is_empty()
+		{
+			sections.entry(name).or_default().push(line);
+		}
+	}
+	sections
+}
+
+/// Name and version of the OS, from `/etc/os-release`
+fn os_release(lines: &[&str]) -> Option<String> {
+	let value = |key: &str| {
+		lines
+			.iter()
+			.find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+			.map(|value| value.trim().trim_matches(['"', '\'']).to_string())
+	};
+	value("PRETTY_NAME").or_else(|| Some(format!("{} {}", value("NAME")?, value("VERSION_ID").unwrap_or_default()).trim().to_string()))
+}
+
+/// Global SSH server settings by lowercase keyword; the first value of a keyword applies, and
+/// `Match` blocks are left out
+fn sshd_settings(lines: &[&str]) -> HashMap<String, String> {
+	let mut settings = HashMap::new();
+	for line in lines {
+		let line = line.trim();
+		if line.starts_with('#') {
+			continue;
+		}
+		let Some((keyword, value)) = line.split_once(|c: char| c.is_whitespace() || c == '=') else {
+			continue;
+		};
+		let keyword = keyword.to_ascii_lowercase();
+		if keyword == "match" {
+			break;
+		}
+		settings.entry(keyword).or_insert_with(|| value.trim_start_matches(|c: char| c.is_whitespace() || c == '=').trim().to_string());
+	}
+	settings
+}
+
+/// A finding: status, severity, details, and techniques
+type Finding = (&'static str, Severity, String, &'static [&'static str]);
+
+/// Findings for risky SSH server settings
+fn sshd_findings(settings: &HashMap<String, String>, source: &str) -> Vec<Finding> {
+	let set = |keyword: &str, value: &str| settings.get(keyword).is_some_and(|v| v.eq_ignore_ascii_case(value));
+	let mut findings = Vec::new();
+	if set("permitemptypasswords", "yes") {
+		findings.push((
+			"sshd_empty_passwords",
+			Severity::Critical,
+			format!("{} lets accounts with empty passwords log in", source),
+			&["T1021.004", "T1110"][..],
+		));
+	}
+	if set("permitrootlogin", "yes") {
+		findings.push((
+			"sshd_root_login",
+			Severity::High,
+			format!("{} lets root log in with a password", source),
+			&["T1021.004", "T1110"][..],
+		));
+	}
+	if set("passwordauthentication", "yes") {
+		findings.push((
+			"sshd_password_authentication",
+			Severity::Low,
+			format!("{} accepts password logins, which can be guessed", source),
+			&["T1021.004", "T1110"][..],
+		));
+	}
+	if set("x11forwarding", "yes") {
+		findings.push((
+			"sshd_x11_forwarding",
+			Severity::Low,
+			format!("{} forwards X11 connections to clients", source),
+			&["T1021.004"][..],
+		));
+	}
+	let weak: Vec<&str> = ["ciphers", "macs", "kexalgorithms"]
+		.iter()
+		.filter_map(|keyword| settings.get(*keyword))
+		.flat_map(|value| value.split(','))
+		.map(|algorithm| algorithm.trim().trim_start_matches(['+', '-', '^']))
+		.filter(|algorithm| WEAK_ALGORITHMS.iter().any(|weak| algorithm.contains(weak)))
+		.collect();
+	if !weak.is_empty() {
+		findings.push((
+			"sshd_weak_algorithms",
+			Severity::Medium,
+			format!("{} allows weak algorithms: {}", source, weak.join(", ")),
+			&["T1557"][..],
+		));
+	}
+	findings
+}
+
+/// Protocol and local address of each listening socket, from `ss -Htuln` or `netstat -tuln`
+fn listeners(lines: &[&str]) -> Vec<(String, String)> {
+	lines
+		.iter()
+		.filter_map(|line| {
+			let fields: Vec<&str> = line.split_whitespace().collect();
+			let protocol = fields.first()?.trim_end_matches('6');
+			if !matches!(protocol, "tcp" | "udp") {
+				return None;
+			}
+			// netstat puts queue sizes where ss puts the socket state
+			let local = if fields.get(1)?.parse::<u32>().is_ok() { fields.get(3)? } else { fields.get(4)? };
+			Some((protocol.to_string(), local.to_string()))
+		})
+		.collect()
+}
+
+/// Whether a local address listens on every interface
+fn is_wildcard(address: &str) -> bool {
+	["0.0.0.0:", "[::]:", ":::", "*:"].iter().any(|prefix| address.starts_with(prefix))
+}
+
+/// Packages with pending security updates, from a package manager's listing
+fn pending_updates(manager: &str, lines: &[&str]) -> Vec<String> {
+	let mut packages: Vec<String> = lines
+		.iter()
+		.filter_map(|line| match manager {
+			// Inst libssl3 [3.0.11-1~deb12u1] (3.0.11-1~deb12u2 Debian-Security:12/stable-security [amd64])
+			"apt" => line.strip_prefix("Inst ")?.split_whitespace().next().map(str::to_string),
+			// RHSA-2024:1234 Important/Sec. openssl-1:3.0.7-25.el9.x86_64
+			"dnf" | "yum" => line.split_whitespace().nth(2).map(str::to_string),
+			// Repository | Name | Category | Severity | Interactive | Status | Summary
+			"zypper" => {
+				let columns: Vec<&str> = line.split('|').map(str::trim).collect();
+				(columns.len() > 2 && columns[2] == "security").then(|| columns[1].to_string())
+			},
+			_ => None,
+		})
+		.collect();
+	packages.sort();
+	packages.dedup();
+	packages
+}
+
+/// Results for what the audit script printed on a host
+fn audit(target: &str, port: u16, name: &str, output: &str) -> Vec<ScanResult> {
+	let sections = sections(output);
+	let section = |name: &str| sections.get(name).map_or(&[][..], Vec::as_slice);
+	let result = |status: &str, severity, details: String, techniques: &[&str]| ScanResult {
+		target: target.to_string(),
+		scan_type: ScanType::SshAuditAuth.to_string(),
+		port: Some(port),
+		status: status.to_string(),
+		severity,
+		service: Some("ssh".to_string()),
+		version: None,
+		details: Some(details),
+		techniques: techniques.iter().map(|t| t.to_string()).collect(),
+		screenshot: None,
+		rtt: None,
+	};
+
+	let mut details = vec![format!("audited as {}", name)];
+	details.extend(os_release(section("os-release")));
+	let mut findings = Vec::new();
+
+	let (settings, source) = match section("sshd -T") {
+		[] => (sshd_settings(section("sshd_config")), "/etc/ssh/sshd_config"),
+		effective => (sshd_settings(effective), "the SSH server (sshd -T)"),
+	};
+	if settings.is_empty() {
+		details.push("SSH server settings unreadable".to_string());
+	} else {
+		findings.extend(sshd_findings(&settings, source));
+	}
+
+	let listening = listeners(section("listening"));
+	details.push(format!("{} listening sockets", listening.len()));
+	let exposed: Vec<String> = listening
+		.iter()
+		.filter(|(_, address)| is_wildcard(address))
+		.map(|(protocol, address)| format!("{} {}", protocol, address))
+		.collect();
+	if !exposed.is_empty() {
+		findings.push((
+			"listening_sockets",
+			Severity::Info,
+			format!("listening on every interface: {}", exposed.join(", ")),
+			&[][..],
+		));
+	}
+
+	match sections.keys().find_map(|name| name.strip_prefix("updates ")) {
+		Some(manager) => {
+			let packages = pending_updates(manager, section(&format!("updates {}", manager)));
+			details.push(format!("{} pending security updates ({})", packages.len(), manager));
+			if !packages.is_empty() {
+				findings.push((
+					"pending_security_updates",
+					Severity::High,
+					format!("{} packages have security updates waiting: {}", packages.len(), packages.join(", ")),
+					&["T1068", "T1190"][..],
+				));
+			}
+		},
+		None => details.push("no supported package manager".to_string()),
+	}
+
+	let mut results = vec![result("open", None, details.join("; "), &[])];
+	results.extend(findings.into_iter().map(|(status, severity, details, techniques)| {
+		result(status, Some(severity), details, techniques)
+	}));
+	results
+}
+
+/// Results for the SSH server on a port, if one is reachable
+async fn inspect(config: &SshAuditConfig, target: &str, port: u16, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+	let mut refused = Vec::new();
+	for (name, credential) in credentials(context) {
+		match login(config, target, port, credential, context).await? {
+			Login::Audited(output) => return Ok(audit(target, port, name, &output)),
+			Login::Refused(reason) => refused.push(format!("{} ({})", name, reason)),
+			Login::HostKey => {
+				refused.push(format!(
+					"{} (host key verification failed; add the host's key to known_hosts or set accept_new_host_keys)",
+					name
+				));
+				break;
+			},
+			Login::Unreachable(_) if refused.is_empty() => return Ok(Vec::new()),
+			Login::Unreachable(reason) => refused.push(format!("{} ({})", name, reason)),
+		}
+	}
+	if refused.is_empty() {
+		return Ok(Vec::new());
+	}
+	Ok(vec![ScanResult {
+		target: target.to_string(),
+		scan_type: ScanType::SshAuditAuth.to_string(),
+		port: Some(port),
+		status: "open".to_string(),
+		severity: None,
+		service: Some("ssh".to_string()),
+		version: None,
+		details: Some(format!("not audited; credentials refused: {}", refused.join(", "))),
+		techniques: Vec::new(),
+		screenshot: None,
+		rtt: None,
+	}])
+}
+
+/// Authenticated SSH configuration audit plugin
+#[derive(Default)]
+pub struct SshAuditAuthPlugin {
+	config: SshAuditConfig,
+}
+
+impl SshAuditAuthPlugin {
+	/// Create the plugin with its settings
+	pub fn new(config: SshAuditConfig) -> Self {
+		Self { config }
+	}
+}
+
+#[async_trait]
+impl Plugin for SshAuditAuthPlugin {
+	fn name(&self) -> String {
+		"SSH Configuration Auditor".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::SshAuditAuth
+	}
+
+	fn description(&self) -> String {
+		"Logs in over SSH with configured credentials and audits the server settings, listening sockets, and pending security updates"
+			.to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&["use_credentials", "ssh_audit_auth", "ports", "timeout", "source_ip", "max_connections"]
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		context.ports_or(DEFAULT_PORTS).len() * credentials(context).len()
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		if credentials(context).is_empty() {
+			return Err(HuginnError::Config(
+				"ssh_audit_auth needs an SSH credential; list one in use_credentials".to_string(),
+			));
+		}
+		context.resolve(target).await?;
+
+		let config = Arc::new(self.config.clone());
+		let (target, context) = (Arc::new(target.to_string()), Arc::new(context.clone()));
+		let permits = Arc::new(Semaphore::new(context.max_connections));
+		let mut probes = JoinSet::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			let (config, target, context, permits) = (config.clone(), target.clone(), context.clone(), permits.clone());
+			probes.spawn(async move {
+				let _permit = permits.acquire_owned().await;
+				inspect(&config, &target, port, &context).await
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(joined) = probes.join_next().await {
+			results.extend(joined.map_err(io::Error::other)??);
+		}
+		results.sort_by_key(|r| r.port);
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::credentials::Credentials;
+	use crate::scanner::Scanner;
+
+	/// What the script prints on a Debian host with a careless SSH server configuration
+	const OUTPUT: &str = "@@ os-release
+PRETTY_NAME=\"Debian GNU/Linux 12 (bookworm)\"
+NAME=\"Debian GNU/Linux\"
+@@ sshd -T
+@@ sshd_config
+# PermitRootLogin prohibit-password
+PermitRootLogin yes
+PasswordAuthentication no
+Ciphers +aes128-cbc
+Match User backup
+	PasswordAuthentication yes
+@@ listening
+tcp   LISTEN 0      128          0.0.0.0:22        0.0.0.0:*
+tcp   LISTEN 0      4096       127.0.0.1:5432      0.0.0.0:*
+udp   UNCONN 0      0               [::]:161          [::]:*
+@@ updates apt
+Inst libssl3 [3.0.11-1~deb12u1] (3.0.11-1~deb12u2 Debian-Security:12/stable-security [amd64])
+Inst openssl [3.0.11-1~deb12u1] (3.0.11-1~deb12u2 Debian-Security:12/stable-security [amd64])
+";
+
+	#[test]
+	fn test_audit() {
+		let results = audit("10.0.0.5", 22, "servers", OUTPUT);
+		let statuses: Vec<_> = results.iter().map(|r| (r.status.as_str(), r.severity)).collect();
+		assert_eq!(
+			statuses,
+			[
+				("open", None),
+				("sshd_root_login", Some(Severity::High)),
+				("sshd_weak_algorithms", Some(Severity::Medium)),
+				("listening_sockets", Some(Severity::Info)),
+				("pending_security_updates", Some(Severity::High)),
+			]
+		);
+		assert_eq!(
+			results[0].details.as_deref(),
+			Some("audited as servers; Debian GNU/Linux 12 (bookworm); 3 listening sockets; 2 pending security updates (apt)")
+		);
+		assert_eq!(results[2].details.as_deref(), Some("/etc/ssh/sshd_config allows weak algorithms: aes128-cbc"));
+		assert_eq!(results[3].details.as_deref(), Some("listening on every interface: tcp 0.0.0.0:22, udp [::]:161"));
+		assert!(results[4].details.as_deref().unwrap().ends_with("libssl3, openssl"));
+	}
+
+	#[test]
+	fn test_listings() {
+		let netstat = ["Proto Recv-Q Send-Q Local Address Foreign Address State", "tcp6 0 0 :::22 :::* LISTEN"];
+		assert_eq!(listeners(&netstat), [("tcp".to_string(), ":::22".to_string())]);
+		let dnf = ["RHSA-2024:1234 Important/Sec. openssl-1:3.0.7-25.el9.x86_64"];
+		assert_eq!(pending_updates("dnf", &dnf), ["openssl-1:3.0.7-25.el9.x86_64"]);
+		let zypper = [
+			"Repository | Name | Category | Severity | Interactive | Status | Summary",
+			"Update | SUSE-2024-1 | security | important | --- | needed | Security update for curl",
+		];
+		assert_eq!(pending_updates("zypper", &zypper), ["SUSE-2024-1"]);
+		assert_eq!(failure("audit@10.0.0.5: Permission denied (publickey,password).\n"), Login::Refused("permission denied".to_string()));
+		assert_eq!(failure("Host key verification failed.\n"), Login::HostKey);
+	}
+
+	#[cfg(unix)]
+	#[tokio::test]
+	async fn test_logs_in_with_client() {
+		// A stand-in client that checks its prompt helper and prints canned output for the script
+		let scratch = Scratch::new().unwrap();
+		let output = scratch.write("output", OUTPUT, false).unwrap();
+		let client = format!(
+			"#!/bin/sh\n[ \"$($SSH_ASKPASS 'Password:')\" = hunter22 ] || {{ echo 'Permission denied' >&2; exit 255; }}\ncat >/dev/null\ncat {}\n",
+			output.display()
+		);
+		let config = SshAuditConfig {
+			ssh: scratch.write("ssh", &client, true).unwrap(),
+			..SshAuditConfig::default()
+		};
+		let ssh = |password: &str| Credential::Ssh {
+			user: "audit".to_string(),
+			key: None,
+			passphrase: None,
+			password: Some(Secret::new(password)),
+		};
+		let credentials: Credentials = [("a_stale", ssh("hunter2")), ("b_current", ssh("hunter22"))].into_iter().collect();
+		let context = Scanner::builder().credentials(credentials).context(ScanType::SshAuditAuth);
+
+		let results = inspect(&config, "127.0.0.1", 22, &context).await.unwrap();
+		assert_eq!(results.len(), 5);
+		assert!(results[0].details.as_deref().unwrap().starts_with("audited as b_current; Debian"));
+	}
+}
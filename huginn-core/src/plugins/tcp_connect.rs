@@ -0,0 +1,208 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! TCP Connect scan plugin
+//!
+//! Establishes a full TCP connection to determine port status. Ports that accept the
+//! connection are reported as open; refused and unanswered ports are not reported.
+//!
+//! Unless a probe delay is set, ports are probed concurrently, with at most `max_connections`
+//! connections open at once so that the scan stays within the file descriptor limit. A
+//! connection that still finds no free descriptor, because something else in the process holds
+//! them, is retried once others close.
+
+use crate::attack;
+use crate::error::HuginnError;
+use crate::limits;
+use crate::plugins::{DEFAULT_TCP_PORTS, Plugin, ScanContext, ScanResult, ScanType};
+use async_trait::async_trait;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Pause before retrying a connection that found no free file descriptor
+const EXHAUSTED_PAUSE: Duration = Duration::from_millis(50);
+
+/// Retries of a connection that found no free file descriptor before the scan fails
+const EXHAUSTED_RETRIES: u32 = 100;
+
+/// Whether a port accepts a connection
+async fn probe_port(host: &str, port: u16, context: &ScanContext) -> Result<bool, HuginnError> {
+	for _ in 0..=EXHAUSTED_RETRIES {
+		match context.probe(|| context.connect(host, port)).await {
+			Some(Ok(_)) => return Ok(true),
+			Some(Err(e)) if limits::is_exhausted(&e) => tokio::time::sleep(EXHAUSTED_PAUSE).await,
+			_ => return Ok(false),
+		}
+	}
+	Err(HuginnError::Io(io::Error::other(format!(
+		"no file descriptor came free to probe port {}; lower max_connections or raise the limit",
+		port
+	))))
+}
+
+/// TCP Connect scan plugin
+pub struct TcpConnectScanPlugin;
+
+#[async_trait]
+impl Plugin for TcpConnectScanPlugin {
+	fn name(&self) -> String {
+		"TCP Connect Scanner".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::TcpConnect
+	}
+
+	fn description(&self) -> String {
+		"Reports TCP ports that accept a full connection".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"proxy",
+			"max_connections",
+			"raise_fd_limit",
+		]
+	}
+
+	fn proxy_capable(&self) -> bool {
+		true
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		context.ports_or(DEFAULT_TCP_PORTS).len()
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		// Resolve once up front, unless the proxy is left to resolve the name
+		let host = match context.proxy {
+			Some(_) => target.to_string(),
+			None => context.resolve(target).await?.to_string(),
+		};
+
+		let (host, context) = (Arc::new(host), Arc::new(context.clone()));
+		// A probe delay paces the probes one after another
+		let width = if context.scan_delay.is_zero() { context.max_connections } else { 1 };
+		let permits = Arc::new(Semaphore::new(width));
+		let mut probes = JoinSet::new();
+		for &port in context.ports_or(DEFAULT_TCP_PORTS) {
+			let (host, context, permits) = (host.clone(), context.clone(), permits.clone());
+			probes.spawn(async move {
+				let _permit = permits.acquire_owned().await;
+				(port, probe_port(&host, port, &context).await)
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(joined) = probes.join_next().await {
+			let (port, open) = joined.map_err(io::Error::other)?;
+			if open? {
+				results.push(ScanResult {
+					target: target.to_string(),
+					scan_type: self.scan_type().to_string(),
+					port: Some(port),
+					status: "open".to_string(),
+					severity: None,
+					service: None,
+					version: None,
+					details: None,
+					techniques: attack::for_port(port),
+					screenshot: None,
+					rtt: None,
+				});
+			}
+		}
+		results.sort_by_key(|r| r.port);
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ports::PortSpec;
+	use crate::scanner::Scanner;
+	use crate::transport::mock::{MockNetwork, Tcp};
+	use tokio::net::{TcpListener, TcpSocket};
+
+	#[tokio::test]
+	async fn test_reports_open_port() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let open = listener.local_addr().unwrap().port();
+		// A socket bound without listening refuses connections, and holds the port so nothing
+		// else can start listening on it during the test
+		let unlistened = TcpSocket::new_v4().unwrap();
+		unlistened.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+		let closed = unlistened.local_addr().unwrap().port();
+
+		let context = Scanner::builder()
+			.ports(format!("{},{}", open, closed).parse::<PortSpec>().unwrap())
+			.context(ScanType::TcpConnect);
+
+		let results = TcpConnectScanPlugin.scan("127.0.0.1", &context).await.unwrap();
+		assert_eq!(results.len(), 1);
+		assert_eq!(results[0].port, Some(open));
+		assert_eq!(results[0].status, "open");
+
+		let from_loopback = ScanContext {
+			source_ip: Some("127.0.0.1".parse().unwrap()),
+			..context.clone()
+		};
+		let results = TcpConnectScanPlugin.scan("127.0.0.1", &from_loopback).await.unwrap();
+		assert_eq!(results.len(), 1);
+
+		let one_at_a_time = ScanContext {
+			max_connections: 1,
+			..context.clone()
+		};
+		let results = TcpConnectScanPlugin.scan("127.0.0.1", &one_at_a_time).await.unwrap();
+		assert_eq!(results.len(), 1);
+
+		let wrong_family = ScanContext {
+			source_ip: Some("::1".parse().unwrap()),
+			..context
+		};
+		assert!(wrong_family.connect("127.0.0.1", open).await.is_err());
+	}
+
+	#[tokio::test]
+	async fn test_mock_network() {
+		let address = [192, 0, 2, 10];
+		let network = Arc::new(
+			MockNetwork::new()
+				.name("web.example.com", address)
+				.tcp_port(address, 80, Tcp::Open(Vec::new()))
+				.tcp_port(address, 443, Tcp::Filtered),
+		);
+		let context = Scanner::builder()
+			.ports("80,443,8080".parse::<PortSpec>().unwrap())
+			.timeout(Duration::from_millis(20))
+			.retries(2)
+			.transport(network.clone())
+			.context(ScanType::TcpConnect);
+
+		let results = TcpConnectScanPlugin.scan("web.example.com", &context).await.unwrap();
+		assert_eq!(results.iter().map(|r| r.port).collect::<Vec<_>>(), [Some(80)]);
+		assert_eq!(results[0].target, "web.example.com");
+		// A refused connection is an answer; only the filtered port is retried
+		assert_eq!(network.connections((address, 8080).into()), 1);
+		assert_eq!(network.connections((address, 443).into()), 3);
+
+		let error = TcpConnectScanPlugin.scan("unknown.example.com", &context).await.unwrap_err();
+		assert!(matches!(error, HuginnError::Unresolved { .. }), "{}", error);
+	}
+}
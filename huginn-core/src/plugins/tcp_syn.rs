@@ -0,0 +1,441 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! TCP SYN scan plugin
+//!
+//! A half-open scan that sends a SYN to each port over a raw socket and never completes the
+//! handshake: a SYN-ACK means the port is open (the kernel answers it with a reset), a reset means
+//! it is closed, and silence through every retry means it is filtered. Every probed port is
+//! reported with its status. Only IPv4 targets are supported.
+//!
+//! Because Huginn writes the IP header itself, the probes can be shaped with [`RawOptions`]: a
+//! fixed source port, a TTL, random padding, decoy source addresses that identical probes are
+//! sent from, and IP fragmentation, for testing whether the defenses under assessment reassemble,
+//! detect, and attribute scans.
+//!
+//! Probes go out in batches through the [`Engine`], which collects the replies as they arrive,
+//! so large port ranges are sent at the rate the interface allows.
+//!
+//! [`sweep`] is the stateless alternative for sweeps of many hosts: it probes every port of
+//! every target in one shuffled pass, encoding each probe's target and port in its sequence
+//! number with a keyed SipHash, so a SYN-ACK is recognized by its acknowledgment number alone
+//! and nothing is remembered per probe. Memory stays bounded by the open ports found, however
+//! many probes are sent, at the cost of reporting only open ports.
+
+use crate::attack;
+use crate::error::HuginnError;
+use crate::plugins::engine::{Engine, Filter};
+use crate::plugins::raw::{self, ACK, Ipv4Header, PROTOCOL_TCP, RST, SYN, TcpReply};
+use crate::plugins::{DEFAULT_TCP_PORTS, Plugin, RawOptions, ScanContext, ScanResult, ScanType};
+use async_trait::async_trait;
+use log::debug;
+use socket2::Protocol;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{BuildHasher, RandomState};
+use std::net::Ipv4Addr;
+use tokio::time::Instant;
+
+/// Where a SYN goes and what it carries
+struct Probe<'a> {
+	destination: Ipv4Addr,
+	source_port: u16,
+	sequence: u32,
+	padding: &'a [u8],
+	options: &'a RawOptions,
+}
+
+impl Probe<'_> {
+	/// Append the SYN to `port`, as sent from `from`
+	fn write(&self, buffer: &mut Vec<u8>, from: Ipv4Addr, port: u16) {
+		Ipv4Header {
+			source: from,
+			destination: self.destination,
+			protocol: PROTOCOL_TCP,
+			ttl: self.options.ttl.unwrap_or(raw::DEFAULT_TTL),
+			identification: raw::random() as u16,
+		}
+		.write(buffer, 20 + self.padding.len());
+		raw::write_tcp_segment(
+			buffer,
+			from,
+			self.destination,
+			(self.source_port, port),
+			self.sequence,
+			SYN,
+			self.padding,
+		);
+	}
+
+	/// Sources of the probes for each port: every decoy, then the real source
+	fn sources(&self, source: Ipv4Addr) -> impl Iterator<Item = Ipv4Addr> + '_ {
+		self.options.decoys.iter().copied().chain(std::iter::once(source))
+	}
+}
+
+/// Send SYNs to every port, resending to the unanswered ones, and classify each port
+async fn syn_scan(address: Ipv4Addr, context: &ScanContext) -> Result<BTreeMap<u16, &'static str>, HuginnError> {
+	let source = raw::source_address(address, context)?;
+	let mut engine = Engine::start(&[Protocol::TCP], Filter::From(address), context)?;
+	let options = &context.raw;
+	let padding = raw::random_bytes(options.data_length);
+	let probe = Probe {
+		destination: address,
+		source_port: options.source_port.unwrap_or(raw::ephemeral_port()),
+		sequence: raw::random() as u32,
+		padding: &padding,
+		options,
+	};
+	let (source_port, sequence) = (probe.source_port, probe.sequence);
+	// Resets to a SYN acknowledge its padding too, while SYN-ACKs usually ignore it
+	let acknowledgments = [sequence.wrapping_add(1), sequence.wrapping_add(1 + options.data_length as u32)];
+	debug!("SYN scanning {} from {}:{}", address, source, source_port);
+
+	let mut statuses: BTreeMap<u16, &'static str> = BTreeMap::new();
+	for _ in 0..=context.retries {
+		let pending: Vec<u16> = context.ports_or(DEFAULT_TCP_PORTS).iter().copied().filter(|p| !statuses.contains_key(p)).collect();
+		if pending.is_empty() {
+			break;
+		}
+		for &port in &pending {
+			if !context.scan_delay.is_zero() {
+				engine.flush().await?;
+				tokio::time::sleep(context.scan_delay).await;
+			}
+			for from in probe.sources(source) {
+				engine.queue(|buffer| probe.write(buffer, from, port)).await?;
+			}
+		}
+
+		let deadline = Instant::now() + context.timeout;
+		while statuses.len() < context.ports_or(DEFAULT_TCP_PORTS).len() {
+			let Some(packet) = engine.reply(deadline).await? else {
+				break;
+			};
+			let Some(reply) = TcpReply::parse(&packet) else {
+				continue;
+			};
+			if reply.source != address
+				|| reply.destination_port != source_port
+				|| !acknowledgments.contains(&reply.acknowledgment)
+				|| !pending.contains(&reply.source_port)
+			{
+				continue;
+			}
+			if reply.flags & (SYN | ACK) == SYN | ACK {
+				statuses.insert(reply.source_port, "open");
+			} else if reply.flags & RST != 0 {
+				statuses.insert(reply.source_port, "closed");
+			}
+		}
+	}
+	for &port in context.ports_or(DEFAULT_TCP_PORTS) {
+		statuses.entry(port).or_insert("filtered");
+	}
+	Ok(statuses)
+}
+
+/// Sequence numbers that identify the probe a reply answers without remembering the probe: a
+/// SipHash of the target and port, keyed randomly for each sweep
+struct Cookies(RandomState);
+
+impl Cookies {
+	/// Sequence number of the probe to a target's port
+	fn sequence(&self, target: Ipv4Addr, port: u16) -> u32 {
+		self.0.hash_one((target, port)) as u32
+	}
+}
+
+/// Greatest common divisor
+fn gcd(a: u64, b: u64) -> u64 {
+	if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Every number below `count` once, in a random order that is computed rather than stored: a
+/// random start, stepping by a random stride that shares no factor with the count
+fn shuffled(count: u64) -> impl Iterator<Item = u64> {
+	let start = raw::random() % count.max(1);
+	let mut stride = raw::random() % count.max(1) + 1;
+	while gcd(stride, count) > 1 {
+		stride += 1;
+	}
+	(0..count).map(move |i| ((u128::from(start) + u128::from(i) * u128::from(stride)) % u128::from(count)) as u64)
+}
+
+/// Open ports that a sweep has found
+struct Findings<'a> {
+	targets: &'a [String],
+	index: HashMap<Ipv4Addr, usize>,
+	cookies: Cookies,
+	source_port: u16,
+	data_length: u32,
+	found: HashSet<(Ipv4Addr, u16)>,
+	results: Vec<ScanResult>,
+}
+
+impl Findings<'_> {
+	/// Record a SYN-ACK that acknowledges one of the sweep's probes
+	fn record(&mut self, packet: &[u8]) {
+		let Some(reply) = TcpReply::parse(packet) else {
+			return;
+		};
+		let Some(&target) = self.index.get(&reply.source) else {
+			return;
+		};
+		let sequence = self.cookies.sequence(reply.source, reply.source_port);
+		if reply.destination_port != self.source_port
+			|| reply.flags & (SYN | ACK) != SYN | ACK
+			|| ![sequence.wrapping_add(1), sequence.wrapping_add(1 + self.data_length)].contains(&reply.acknowledgment)
+			|| !self.found.insert((reply.source, reply.source_port))
+		{
+			return;
+		}
+		self.results.push(ScanResult {
+			target: self.targets[target].clone(),
+			scan_type: ScanType::TcpSyn.to_string(),
+			port: Some(reply.source_port),
+			status: "open".to_string(),
+			severity: None,
+			service: None,
+			version: None,
+			details: Some("found by a stateless sweep".to_string()),
+			techniques: attack::for_port(reply.source_port),
+			screenshot: None,
+			rtt: None,
+		});
+	}
+}
+
+/// Probe every port of every target in one stateless pass, returning the open ports, and an
+/// error result for each target that does not resolve to an IPv4 address
+///
+/// Probes are sent in a shuffled order across all targets, from one source address, so that no
+/// host sees a burst; every probe is sent `retries + 1` times, and replies are collected while
+/// sending and for `timeout` after the last probe.
+pub async fn sweep(targets: &[String], context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+	let mut results = Vec::new();
+	let mut addresses = Vec::with_capacity(targets.len());
+	for target in targets {
+		let address = match target.parse::<Ipv4Addr>() {
+			Ok(address) => Ok(address),
+			Err(_) => raw::resolve(target, context).await,
+		};
+		match address {
+			Ok(address) => addresses.push(Some(address)),
+			Err(e) => {
+				results.push(ScanResult::failed(target, ScanType::TcpSyn, &e));
+				addresses.push(None);
+			},
+		}
+	}
+	let Some(&first) = addresses.iter().flatten().next() else {
+		return Ok(results);
+	};
+
+	let source = raw::source_address(first, context)?;
+	let options = &context.raw;
+	let source_port = options.source_port.unwrap_or(raw::ephemeral_port());
+	let mut engine = Engine::start(&[Protocol::TCP], Filter::ToPort(source_port), context)?;
+	let padding = raw::random_bytes(options.data_length);
+	let mut findings = Findings {
+		targets,
+		index: addresses.iter().enumerate().filter_map(|(i, address)| Some(((*address)?, i))).collect(),
+		cookies: Cookies(RandomState::new()),
+		source_port,
+		data_length: options.data_length as u32,
+		found: HashSet::new(),
+		results,
+	};
+	let ports = context.ports_or(DEFAULT_TCP_PORTS);
+	debug!("Sweeping {} targets from {}:{}", findings.index.len(), source, source_port);
+
+	for _ in 0..=context.retries {
+		for i in shuffled((addresses.len() * ports.len()) as u64) {
+			let (address, port) = (addresses[i as usize % addresses.len()], ports[i as usize / addresses.len()]);
+			let Some(address) = address.filter(|&address| !findings.found.contains(&(address, port))) else {
+				continue;
+			};
+			if !context.scan_delay.is_zero() {
+				engine.flush().await?;
+				tokio::time::sleep(context.scan_delay).await;
+			}
+			let probe = Probe {
+				destination: address,
+				source_port,
+				sequence: findings.cookies.sequence(address, port),
+				padding: &padding,
+				options,
+			};
+			for from in probe.sources(source) {
+				engine.queue(|buffer| probe.write(buffer, from, port)).await?;
+			}
+			while let Some(packet) = engine.try_reply()? {
+				findings.record(&packet);
+			}
+		}
+	}
+
+	let deadline = Instant::now() + context.timeout;
+	while let Some(packet) = engine.reply(deadline).await? {
+		findings.record(&packet);
+	}
+	Ok(findings.results)
+}
+
+/// TCP SYN scan plugin
+pub struct TcpSynScanPlugin;
+
+#[async_trait]
+impl Plugin for TcpSynScanPlugin {
+	fn name(&self) -> String {
+		"TCP SYN Scanner".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::TcpSyn
+	}
+
+	fn description(&self) -> String {
+		"Half-open TCP scan reporting each port as open, closed, or filtered".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"source_port",
+			"decoys",
+			"ttl",
+			"data_length",
+			"mtu",
+		]
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		context.ports_or(DEFAULT_TCP_PORTS).len() * (1 + context.raw.decoys.len())
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		let address = raw::resolve(target, context).await?;
+		let statuses = syn_scan(address, context).await?;
+		Ok(statuses
+			.into_iter()
+			.map(|(port, status)| ScanResult {
+				target: target.to_string(),
+				scan_type: self.scan_type().to_string(),
+				port: Some(port),
+				status: status.to_string(),
+				severity: None,
+				service: None,
+				version: None,
+				details: None,
+				techniques: if status == "open" { attack::for_port(port) } else { Vec::new() },
+				screenshot: None,
+				rtt: None,
+			})
+			.collect())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ports::PortSpec;
+	use crate::scanner::Scanner;
+	use std::time::Duration;
+	use tokio::net::TcpListener;
+
+	#[test]
+	fn test_decoy_probes() {
+		let options = RawOptions {
+			decoys: vec![Ipv4Addr::new(192, 0, 2, 50), Ipv4Addr::new(192, 0, 2, 51)],
+			ttl: Some(33),
+			data_length: 12,
+			..RawOptions::default()
+		};
+		let source = Ipv4Addr::new(192, 0, 2, 1);
+		let padding = raw::random_bytes(options.data_length);
+		let probe = Probe {
+			destination: Ipv4Addr::new(192, 0, 2, 9),
+			source_port: 53,
+			sequence: 1,
+			padding: &padding,
+			options: &options,
+		};
+		let packets: Vec<Vec<u8>> = probe
+			.sources(source)
+			.map(|from| {
+				let mut buffer = Vec::new();
+				probe.write(&mut buffer, from, 443);
+				buffer
+			})
+			.collect();
+		let sources: Vec<Ipv4Addr> = packets.iter().map(|p| TcpReply::parse(p).unwrap().source).collect();
+		assert_eq!(sources, [options.decoys[0], options.decoys[1], source]);
+		assert!(packets.iter().all(|p| p.len() == 52 && p[8] == 33 && p[20..22] == 53u16.to_be_bytes()));
+	}
+
+	#[test]
+	fn test_shuffled() {
+		for count in [0, 1, 2, 12, 97, 1000] {
+			let mut order: Vec<u64> = shuffled(count).collect();
+			order.sort_unstable();
+			assert_eq!(order, (0..count).collect::<Vec<_>>());
+		}
+		let cookies = Cookies(RandomState::new());
+		let target = Ipv4Addr::new(192, 0, 2, 9);
+		assert_eq!(cookies.sequence(target, 80), cookies.sequence(target, 80));
+		assert_ne!(cookies.sequence(target, 80), cookies.sequence(target, 81));
+	}
+
+	#[tokio::test]
+	async fn test_sweep_loopback() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let open = listener.local_addr().unwrap().port();
+		let closed = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+		let context = Scanner::builder()
+			.ports(format!("{},{}", open, closed).parse::<PortSpec>().unwrap())
+			.timeout(Duration::from_millis(500))
+			.context(ScanType::TcpSyn);
+		let targets = ["127.0.0.1".to_string(), "no-such-host.invalid".to_string()];
+
+		match sweep(&targets, &context).await {
+			Ok(results) => {
+				let open_ports: Vec<(String, Option<u16>)> =
+					results.iter().filter(|r| r.status == "open").map(|r| (r.target.clone(), r.port)).collect();
+				assert_eq!(open_ports, [(targets[0].clone(), Some(open))]);
+				assert!(results.iter().any(|r| r.target == targets[1] && r.is_error()));
+			},
+			Err(e) => assert!(matches!(e, HuginnError::PermissionDenied(_)), "{}", e),
+		}
+	}
+
+	#[tokio::test]
+	async fn test_scan_loopback() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let open = listener.local_addr().unwrap().port();
+		let closed = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+		let context = Scanner::builder()
+			.ports(format!("{},{}", open, closed).parse::<PortSpec>().unwrap())
+			.timeout(Duration::from_millis(500))
+			.context(ScanType::TcpSyn);
+
+		match TcpSynScanPlugin.scan("127.0.0.1", &context).await {
+			Ok(results) => {
+				let status = |port| results.iter().find(|r| r.port == Some(port)).unwrap().status.clone();
+				assert_eq!(status(open), "open");
+				assert_eq!(status(closed), "closed");
+			},
+			// Raw sockets are not permitted in this environment
+			Err(e) => assert!(matches!(e, HuginnError::PermissionDenied(_)), "{}", e),
+		}
+	}
+}
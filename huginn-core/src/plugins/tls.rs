@@ -0,0 +1,629 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! TLS fingerprinting plugin
+//!
+//! Fingerprints the TLS stack on each port with [JARM](https://github.com/salesforce/jarm): ten
+//! crafted ClientHellos, varying the protocol version, cipher order, GREASE, ALPN, and extension
+//! order, each sent on a connection of its own. The cipher and version each ServerHello picks,
+//! with its ALPN and extensions, are combined into a 62-character hash that is the same for
+//! every server built on the same TLS library and configuration. Servers can then be clustered
+//! by hash, and hashes looked up in published JARM lists, which include the default listeners
+//! of C2 frameworks.
+//!
+//! The hash is computed as the reference implementation computes it, so it matches other JARM
+//! tools. Each port that answered any probe with a ServerHello is reported as open, with
+//! `jarm:HASH` in its details.
+
+use crate::error::HuginnError;
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType};
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use std::io;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_rustls::rustls::crypto::ring;
+
+/// Ports probed when no port spec is configured
+const DEFAULT_PORTS: &[u16] = &[443, 465, 636, 993, 995, 8443];
+
+/// Most of a reply that is read, as the reference implementation reads it
+const MAX_REPLY: usize = 1484;
+
+/// Answer to a probe that got no ServerHello
+const NO_ANSWER: &str = "|||";
+
+/// Cipher suites offered, in their forward order
+const CIPHERS: [u16; 69] = [
+	0x0016, 0x0033, 0x0067, 0xc09e, 0xc0a2, 0x009e, 0x0039, 0x006b, 0xc09f, 0xc0a3, 0x009f, 0x0045, 0x00be, 0x0088,
+	0x00c4, 0x009a, 0xc008, 0xc009, 0xc023, 0xc0ac, 0xc0ae, 0xc02b, 0xc00a, 0xc024, 0xc0ad, 0xc0af, 0xc02c, 0xc072,
+	0xc073, 0xcca9, 0x1302, 0x1301, 0xcc14, 0xc007, 0xc012, 0xc013, 0xc027, 0xc02f, 0xc014, 0xc028, 0xc030, 0xc060,
+	0xc061, 0xc076, 0xc077, 0xcca8, 0x1305, 0x1304, 0x1303, 0xcc13, 0xc011, 0x000a, 0x002f, 0x003c, 0xc09c, 0xc0a0,
+	0x009c, 0x0035, 0x003d, 0xc09d, 0xc0a1, 0x009d, 0x0041, 0x00ba, 0x0084, 0x00c0, 0x0007, 0x0004, 0x0005,
+];
+
+/// Cipher suites in the order the hash numbers them from 1
+const CIPHER_NUMBERS: [u16; 69] = [
+	0x0004, 0x0005, 0x0007, 0x000a, 0x0016, 0x002f, 0x0033, 0x0035, 0x0039, 0x003c, 0x003d, 0x0041, 0x0045, 0x0067,
+	0x006b, 0x0084, 0x0088, 0x009a, 0x009c, 0x009d, 0x009e, 0x009f, 0x00ba, 0x00be, 0x00c0, 0x00c4, 0xc007, 0xc008,
+	0xc009, 0xc00a, 0xc011, 0xc012, 0xc013, 0xc014, 0xc023, 0xc024, 0xc027, 0xc028, 0xc02b, 0xc02c, 0xc02f, 0xc030,
+	0xc060, 0xc061, 0xc072, 0xc073, 0xc076, 0xc077, 0xc09c, 0xc09d, 0xc09e, 0xc09f, 0xc0a0, 0xc0a1, 0xc0a2, 0xc0a3,
+	0xc0ac, 0xc0ad, 0xc0ae, 0xc0af, 0xcc13, 0xcc14, 0xcca8, 0xcca9, 0x1301, 0x1302, 0x1303, 0x1304, 0x1305,
+];
+
+/// ALPN protocols offered, weakest first
+const ALPNS: &[&[u8]] = &[b"http/0.9", b"http/1.0", b"http/1.1", b"spdy/1", b"spdy/2", b"spdy/3", b"h2", b"h2c", b"hq"];
+
+/// ALPN protocols offered by the probes that leave out the common ones
+const RARE_ALPNS: &[&[u8]] = &[b"http/0.9", b"http/1.0", b"spdy/1", b"spdy/2", b"spdy/3", b"h2c", b"hq"];
+
+/// Extensions sent unchanged by every probe, after the server name: extended master secret,
+/// max fragment length, renegotiation info, supported groups, EC point formats, and session
+/// ticket
+const FIXED_EXTENSIONS: &[u8] = &[
+	0x00, 0x17, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x01, 0xff, 0x01, 0x00, 0x01, 0x00, 0x00, 0x0a, 0x00, 0x0a, 0x00,
+	0x08, 0x00, 0x1d, 0x00, 0x17, 0x00, 0x18, 0x00, 0x19, 0x00, 0x0b, 0x00, 0x02, 0x01, 0x00, 0x00, 0x23, 0x00, 0x00,
+];
+
+/// Signature algorithms extension
+const SIGNATURE_ALGORITHMS: &[u8] = &[
+	0x00, 0x0d, 0x00, 0x14, 0x00, 0x12, 0x04, 0x03, 0x08, 0x04, 0x04, 0x01, 0x05, 0x03, 0x08, 0x05, 0x05, 0x01, 0x08,
+	0x06, 0x06, 0x01, 0x02, 0x01,
+];
+
+/// PSK key exchange modes extension
+const PSK_MODES: &[u8] = &[0x00, 0x2d, 0x00, 0x02, 0x01, 0x01];
+
+/// Protocol version a probe asks for
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Version {
+	Tls11,
+	Tls12,
+	Tls13,
+}
+
+/// Order a list is sent in
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Order {
+	/// As listed
+	Forward,
+	/// Backwards
+	Reverse,
+	/// The first half, backwards, starting with the middle item
+	TopHalf,
+	/// The second half
+	BottomHalf,
+	/// From the middle outwards, alternating between the halves
+	MiddleOut,
+}
+
+/// Versions listed in the supported versions extension
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Support {
+	/// None, since the extension is left out
+	None,
+	/// Up to TLS 1.2
+	Tls12,
+	/// Up to TLS 1.3
+	Tls13,
+}
+
+/// One of the ClientHellos of a JARM fingerprint
+#[derive(Debug, Clone, Copy)]
+struct Probe {
+	version: Version,
+	/// Whether the TLS 1.3 cipher suites are offered
+	tls13_ciphers: bool,
+	cipher_order: Order,
+	grease: bool,
+	rare_alpns: bool,
+	support: Support,
+	extension_order: Order,
+}
+
+impl Probe {
+	const fn new(version: Version, cipher_order: Order, grease: bool, rare_alpns: bool, support: Support, extension_order: Order) -> Self {
+		Self {
+			version,
+			tls13_ciphers: true,
+			cipher_order,
+			grease,
+			rare_alpns,
+			support,
+			extension_order,
+		}
+	}
+}
+
+/// The probes of a fingerprint, in the order their answers are hashed
+const PROBES: [Probe; 10] = [
+	Probe::new(Version::Tls12, Order::Forward, false, false, Support::Tls12, Order::Reverse),
+	Probe::new(Version::Tls12, Order::Reverse, false, false, Support::Tls12, Order::Forward),
+	Probe::new(Version::Tls12, Order::TopHalf, false, false, Support::None, Order::Forward),
+	Probe::new(Version::Tls12, Order::BottomHalf, false, true, Support::None, Order::Forward),
+	Probe::new(Version::Tls12, Order::MiddleOut, true, true, Support::None, Order::Reverse),
+	Probe::new(Version::Tls11, Order::Forward, false, false, Support::None, Order::Forward),
+	Probe::new(Version::Tls13, Order::Forward, false, false, Support::Tls13, Order::Reverse),
+	Probe::new(Version::Tls13, Order::Reverse, false, false, Support::Tls13, Order::Forward),
+	Probe {
+		tls13_ciphers: false,
+		..Probe::new(Version::Tls13, Order::Forward, false, false, Support::Tls13, Order::Forward)
+	},
+	Probe::new(Version::Tls13, Order::MiddleOut, true, false, Support::Tls13, Order::Reverse),
+];
+
+/// Random bytes
+pub(crate) fn random_bytes(length: usize) -> Vec<u8> {
+	let mut bytes = vec![0; length];
+	// The random values only need to differ between probes; a failure leaves them zero
+	let _ = ring::default_provider().secure_random.fill(&mut bytes);
+	bytes
+}
+
+/// A random GREASE value (RFC 8701)
+fn grease() -> [u8; 2] {
+	let byte = random_bytes(1)[0] & 0xf0 | 0x0a;
+	[byte, byte]
+}
+
+/// Reorder a list
+fn reorder<T: Copy>(items: &[T], order: Order) -> Vec<T> {
+	let (length, middle) = (items.len(), items.len() / 2);
+	match order {
+		Order::Forward => items.to_vec(),
+		Order::Reverse => items.iter().rev().copied().collect(),
+		Order::BottomHalf => items[middle + length % 2..].to_vec(),
+		Order::TopHalf => {
+			let mut ordered = if length % 2 == 1 { vec![items[middle]] } else { Vec::new() };
+			ordered.extend(reorder(&reorder(items, Order::Reverse), Order::BottomHalf));
+			ordered
+		},
+		Order::MiddleOut => {
+			let mut ordered = Vec::with_capacity(length);
+			// An odd list starts with its middle item; the second half leads
+			let first = if length % 2 == 1 {
+				ordered.push(items[middle]);
+				middle
+			} else {
+				middle - 1
+			};
+			for i in 1..=middle {
+				ordered.push(items[first + i]);
+				ordered.push(items[middle - i]);
+			}
+			ordered
+		},
+	}
+}
+
+/// Append a big-endian length
+fn push_length(bytes: &mut Vec<u8>, length: usize) {
+	bytes.extend_from_slice(&(length as u16).to_be_bytes());
+}
+
+/// The extensions of a probe, with their total length in front
+fn extensions(probe: &Probe, host: &str) -> Vec<u8> {
+	let mut all = Vec::new();
+	if probe.grease {
+		all.extend_from_slice(&grease());
+		all.extend_from_slice(&[0, 0]);
+	}
+
+	// Server name, even for an address, as the reference implementation sends it
+	all.extend_from_slice(&[0, 0]);
+	push_length(&mut all, host.len() + 5);
+	push_length(&mut all, host.len() + 3);
+	all.push(0);
+	push_length(&mut all, host.len());
+	all.extend_from_slice(host.as_bytes());
+
+	all.extend_from_slice(FIXED_EXTENSIONS);
+
+	let alpns = reorder(if probe.rare_alpns { RARE_ALPNS } else { ALPNS }, probe.extension_order);
+	let alpns: Vec<u8> = alpns.iter().flat_map(|alpn| [&[alpn.len() as u8][..], alpn].concat()).collect();
+	all.extend_from_slice(&[0x00, 0x10]);
+	push_length(&mut all, alpns.len() + 2);
+	push_length(&mut all, alpns.len());
+	all.extend_from_slice(&alpns);
+
+	all.extend_from_slice(SIGNATURE_ALGORITHMS);
+
+	// Key share of an X25519 key, which is never used
+	let mut share = Vec::new();
+	if probe.grease {
+		share.extend_from_slice(&grease());
+		share.extend_from_slice(&[0, 1, 0]);
+	}
+	share.extend_from_slice(&[0x00, 0x1d, 0x00, 0x20]);
+	share.extend_from_slice(&random_bytes(32));
+	all.extend_from_slice(&[0x00, 0x33]);
+	push_length(&mut all, share.len() + 2);
+	push_length(&mut all, share.len());
+	all.extend_from_slice(&share);
+
+	all.extend_from_slice(PSK_MODES);
+
+	if probe.version == Version::Tls13 || probe.support == Support::Tls12 {
+		let newest: &[[u8; 2]] = if probe.support == Support::Tls12 {
+			&[[3, 1], [3, 2], [3, 3]]
+		} else {
+			&[[3, 1], [3, 2], [3, 3], [3, 4]]
+		};
+		let mut versions = if probe.grease { grease().to_vec() } else { Vec::new() };
+		versions.extend(reorder(newest, probe.extension_order).concat());
+		all.extend_from_slice(&[0x00, 0x2b]);
+		push_length(&mut all, versions.len() + 1);
+		all.push(versions.len() as u8);
+		all.extend_from_slice(&versions);
+	}
+
+	let mut extensions = Vec::with_capacity(all.len() + 2);
+	push_length(&mut extensions, all.len());
+	extensions.extend_from_slice(&all);
+	extensions
+}
+
+/// The TLS record holding a probe's ClientHello
+fn client_hello(probe: &Probe, host: &str) -> Vec<u8> {
+	// TLS 1.3 hellos are sent in a TLS 1.0 record, as TLS 1.3 clients send them
+	let (record, legacy) = match probe.version {
+		Version::Tls11 => ([3, 2], [3, 2]),
+		Version::Tls12 => ([3, 3], [3, 3]),
+		Version::Tls13 => ([3, 1], [3, 3]),
+	};
+	let mut hello = legacy.to_vec();
+	hello.extend_from_slice(&random_bytes(32));
+	hello.push(32);
+	hello.extend_from_slice(&random_bytes(32));
+
+	let offered = CIPHERS.iter().filter(|&&cipher| probe.tls13_ciphers || cipher >> 8 != 0x13).copied().collect::<Vec<_>>();
+	let mut ciphers = reorder(&offered, probe.cipher_order).iter().flat_map(|cipher| cipher.to_be_bytes()).collect::<Vec<_>>();
+	if probe.grease {
+		ciphers.splice(0..0, grease());
+	}
+	push_length(&mut hello, ciphers.len());
+	hello.extend_from_slice(&ciphers);
+	// The null compression method only
+	hello.extend_from_slice(&[1, 0]);
+	hello.extend_from_slice(&extensions(probe, host));
+
+	let mut handshake = vec![1, 0];
+	push_length(&mut handshake, hello.len());
+	handshake.extend_from_slice(&hello);
+	let mut bytes = vec![0x16, record[0], record[1]];
+	push_length(&mut bytes, handshake.len());
+	bytes.extend_from_slice(&handshake);
+	bytes
+}
+
+/// Lowercase hex of bytes
+fn hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Bytes from `start` to `end`, or as many of them as there are, as a Python slice gives
+fn slice(data: &[u8], start: usize, end: usize) -> &[u8] {
+	data.get(start.min(data.len())..end.min(data.len())).unwrap_or_default()
+}
+
+/// Big-endian number in bytes, or `None` when there are none
+fn number(bytes: &[u8]) -> Option<usize> {
+	(!bytes.is_empty()).then(|| bytes.iter().fold(0, |number, &byte| number << 8 | usize::from(byte)))
+}
+
+/// Why the extensions of a ServerHello could not be read
+enum Unreadable {
+	/// The reply ended early; the extensions count as empty
+	Short,
+	/// The reply is malformed; the whole answer counts as none
+	Malformed,
+}
+
+/// The ALPN and the extension types of a ServerHello, as `ALPN|TYPE-TYPE-...`
+///
+/// The checks and their failures follow the reference implementation, so that truncated and
+/// unusual replies hash the same.
+fn server_extensions(data: &[u8], counter: usize, hello_length: usize) -> Result<String, Unreadable> {
+	let first = *data.get(counter + 47).ok_or(Unreadable::Short)?;
+	if first == 11 || slice(data, counter + 50, counter + 53) == b"\x0e\xac\x0b" || slice(data, 82, 85) == b"\x0f\xf0\x0b" {
+		return Err(Unreadable::Short);
+	}
+	if counter + 42 >= hello_length {
+		return Err(Unreadable::Short);
+	}
+	let mut count = 49 + counter;
+	let length = number(slice(data, counter + 47, counter + 49)).ok_or(Unreadable::Malformed)?;
+	let maximum = length + count - 1;
+	let mut extensions = Vec::new();
+	while count < maximum {
+		let kind = slice(data, count, count + 2);
+		let length = number(slice(data, count + 2, count + 4)).ok_or(Unreadable::Malformed)?;
+		extensions.push((kind, slice(data, count + 4, count + 4 + length)));
+		count += length + 4;
+	}
+	let alpn = match extensions.iter().find(|(kind, _)| *kind == b"\x00\x10") {
+		Some((_, value)) => std::str::from_utf8(slice(value, 3, value.len())).map_err(|_| Unreadable::Malformed)?,
+		None => "",
+	};
+	let kinds: Vec<String> = extensions.iter().map(|(kind, _)| hex(kind)).collect();
+	Ok(format!("{}|{}", alpn, kinds.join("-")))
+}
+
+/// A reply to a probe, as `CIPHER|VERSION|ALPN|EXTENSIONS`, or [`NO_ANSWER`] when it is not a
+/// ServerHello
+fn answer(data: &[u8]) -> String {
+	if data.first() != Some(&22) || data.get(5) != Some(&2) {
+		return NO_ANSWER.to_string();
+	}
+	let (Some(hello_length), Some(&counter)) = (number(slice(data, 3, 5)), data.get(43)) else {
+		return NO_ANSWER.to_string();
+	};
+	let counter = usize::from(counter);
+	let cipher = hex(slice(data, counter + 44, counter + 46));
+	let version = hex(slice(data, 9, 11));
+	match server_extensions(data, counter, hello_length) {
+		Ok(extensions) => format!("{}|{}|{}", cipher, version, extensions),
+		Err(Unreadable::Short) => format!("{}|{}||", cipher, version),
+		Err(Unreadable::Malformed) => NO_ANSWER.to_string(),
+	}
+}
+
+/// JARM hash of the answers to the probes: a number for the cipher and a letter for the version
+/// of each, then the start of the SHA-256 of their ALPNs and extensions
+fn jarm(answers: &[String]) -> String {
+	if answers.iter().all(|answer| answer == NO_ANSWER) {
+		return "0".repeat(62);
+	}
+	let mut hash = String::new();
+	let mut rest = String::new();
+	for answer in answers {
+		let parts: Vec<&str> = answer.splitn(4, '|').collect();
+		let [cipher, version, alpn, extensions] = parts[..] else {
+			continue;
+		};
+		let number = match cipher {
+			"" => 0,
+			cipher => CIPHER_NUMBERS.iter().position(|known| hex(&known.to_be_bytes()) == cipher).unwrap_or(CIPHER_NUMBERS.len()) + 1,
+		};
+		hash.push_str(&format!("{:02x}", number));
+		let letter = version.chars().nth(3).and_then(|digit| digit.to_digit(10)).and_then(|digit| "abcdef".chars().nth(digit as usize));
+		hash.push(letter.unwrap_or('0'));
+		rest.push_str(alpn);
+		rest.push_str(extensions);
+	}
+	hash.push_str(&hex(&Sha256::digest(rest.as_bytes()))[..32]);
+	hash
+}
+
+/// Send a probe and read the reply, up to the end of its first record
+async fn exchange(target: &str, port: u16, hello: &[u8], context: &ScanContext) -> io::Result<Vec<u8>> {
+	let mut stream = context.connect(target, port).await?;
+	stream.write_all(hello).await?;
+	let mut reply = vec![0; MAX_REPLY];
+	let mut length = 0;
+	while length < MAX_REPLY {
+		let read = stream.read(&mut reply[length..]).await?;
+		length += read;
+		let record = number(slice(&reply[..length], 3, 5)).map(|record| record + 5);
+		// Anything but a handshake record or an alert is answered already
+		let tls = matches!(reply[0], 21 | 22);
+		if read == 0 || !tls || record.is_some_and(|record| length >= record) {
+			break;
+		}
+	}
+	reply.truncate(length);
+	Ok(reply)
+}
+
+/// JARM fingerprint of a port, or `None` when no probe got a ServerHello
+async fn fingerprint(target: &str, port: u16, context: &ScanContext) -> Option<String> {
+	let mut answers = Vec::with_capacity(PROBES.len());
+	for probe in &PROBES {
+		let hello = client_hello(probe, target);
+		let answer = match context.probe(|| exchange(target, port, &hello, context)).await {
+			Some(Ok(reply)) => answer(&reply),
+			// A closed port is not worth the other probes
+			Some(Err(e)) if e.kind() == io::ErrorKind::ConnectionRefused && answers.is_empty() => return None,
+			_ => NO_ANSWER.to_string(),
+		};
+		answers.push(answer);
+	}
+	answers.iter().any(|answer| answer != NO_ANSWER).then(|| jarm(&answers))
+}
+
+/// TLS fingerprinting plugin
+pub struct TlsPlugin;
+
+#[async_trait]
+impl Plugin for TlsPlugin {
+	fn name(&self) -> String {
+		"TLS Fingerprinter".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::Tls
+	}
+
+	fn description(&self) -> String {
+		"Fingerprints TLS servers by their JARM hash".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"proxy",
+			"max_connections",
+		]
+	}
+
+	fn proxy_capable(&self) -> bool {
+		true
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		context.ports_or(DEFAULT_PORTS).len() * PROBES.len()
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		// Resolve up front to fail early; the probes name the target in their server name
+		if context.proxy.is_none() {
+			context.resolve(target).await?;
+		}
+
+		let (target, context) = (Arc::new(target.to_string()), Arc::new(context.clone()));
+		// A probe delay paces the probes one after another
+		let width = if context.scan_delay.is_zero() { context.max_connections } else { 1 };
+		let permits = Arc::new(Semaphore::new(width));
+		let mut probes = JoinSet::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			let (target, context, permits) = (target.clone(), context.clone(), permits.clone());
+			probes.spawn(async move {
+				let _permit = permits.acquire_owned().await;
+				(port, fingerprint(&target, port, &context).await)
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(joined) = probes.join_next().await {
+			let (port, hash) = joined.map_err(io::Error::other)?;
+			if let Some(hash) = hash {
+				results.push(ScanResult {
+					target: target.to_string(),
+					scan_type: self.scan_type().to_string(),
+					port: Some(port),
+					status: "open".to_string(),
+					severity: None,
+					service: Some("tls".to_string()),
+					version: None,
+					details: Some(format!("jarm:{}", hash)),
+					techniques: Vec::new(),
+					screenshot: None,
+					rtt: None,
+				});
+			}
+		}
+		results.sort_by_key(|r| r.port);
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A ServerHello choosing a cipher, with extensions given as type and value
+	fn server_hello(cipher: u16, extensions: &[(u16, &[u8])]) -> Vec<u8> {
+		let mut hello = vec![3, 3];
+		hello.extend_from_slice(&[0x5a; 32]);
+		hello.push(32);
+		hello.extend_from_slice(&[0xa5; 32]);
+		hello.extend_from_slice(&cipher.to_be_bytes());
+		hello.push(0);
+		let mut all = Vec::new();
+		for (kind, value) in extensions {
+			all.extend_from_slice(&kind.to_be_bytes());
+			push_length(&mut all, value.len());
+			all.extend_from_slice(value);
+		}
+		push_length(&mut hello, all.len());
+		hello.extend_from_slice(&all);
+		let mut handshake = vec![2, 0];
+		push_length(&mut handshake, hello.len());
+		handshake.extend_from_slice(&hello);
+		let mut record = vec![22, 3, 3];
+		push_length(&mut record, handshake.len());
+		record.extend_from_slice(&handshake);
+		record
+	}
+
+	#[test]
+	fn test_reorder() {
+		let odd = [1, 2, 3, 4, 5];
+		assert_eq!(reorder(&odd, Order::Reverse), [5, 4, 3, 2, 1]);
+		assert_eq!(reorder(&odd, Order::BottomHalf), [4, 5]);
+		assert_eq!(reorder(&odd, Order::TopHalf), [3, 2, 1]);
+		assert_eq!(reorder(&odd, Order::MiddleOut), [3, 4, 2, 5, 1]);
+		let even = [1, 2, 3, 4];
+		assert_eq!(reorder(&even, Order::BottomHalf), [3, 4]);
+		assert_eq!(reorder(&even, Order::TopHalf), [2, 1]);
+		assert_eq!(reorder(&even, Order::MiddleOut), [3, 2, 4, 1]);
+
+		let mut sorted = CIPHERS;
+		sorted.sort_by_key(|&cipher| (cipher >> 8 == 0x13, cipher));
+		assert_eq!(sorted, CIPHER_NUMBERS);
+	}
+
+	#[test]
+	fn test_client_hello() {
+		for probe in &PROBES {
+			let hello = client_hello(probe, "example.com");
+			assert_eq!(hello[0], 0x16);
+			assert_eq!(number(&hello[3..5]), Some(hello.len() - 5));
+			assert_eq!((hello[5], number(&hello[7..9])), (1, Some(hello.len() - 9)));
+			let ciphers = number(&hello[76..78]).unwrap();
+			let extensions = 78 + ciphers + 2;
+			assert_eq!(number(&hello[extensions..extensions + 2]), Some(hello.len() - extensions - 2));
+			assert!(hello.windows(11).any(|window| window == b"example.com"));
+		}
+		assert_eq!(&client_hello(&PROBES[6], "a")[1..3], [3, 1]);
+	}
+
+	#[test]
+	fn test_answer() {
+		let hello = server_hello(0xc030, &[(0xff01, &[0]), (0x0010, &[0, 3, 2, b'h', b'2']), (0x000b, &[1, 0])]);
+		assert_eq!(answer(&hello), "c030|0303|h2|ff01-0010-000b");
+		assert_eq!(answer(&server_hello(0x1301, &[(0x002b, &[3, 4])])), "1301|0303||002b");
+		// An alert, and a reply cut short before the cipher
+		assert_eq!(answer(&[21, 3, 3, 0, 2, 2, 40]), NO_ANSWER);
+		assert_eq!(answer(&hello[..40]), NO_ANSWER);
+		assert_eq!(answer(&hello[..79]), "c030|0303||");
+	}
+
+	#[test]
+	fn test_jarm() {
+		let none = vec![NO_ANSWER.to_string(); 10];
+		assert_eq!(jarm(&none), "0".repeat(62));
+
+		let mut answers = none.clone();
+		answers[0] = "c030|0303|h2|ff01-0010-000b".to_string();
+		answers[6] = "1301|0303||002b".to_string();
+		answers[9] = "beef|0303||".to_string();
+		let hash = jarm(&answers);
+		assert_eq!(hash.len(), 62);
+		assert_eq!(&hash[..30], "2ad00000000000000041d00000046d");
+		assert_eq!(&hash[30..], &hex(&Sha256::digest(b"h2ff01-0010-000b002b"))[..32]);
+	}
+
+	#[cfg(feature = "testbed")]
+	#[tokio::test]
+	async fn test_fingerprints_testbed() {
+		use crate::ports::PortSpec;
+		use crate::scanner::Scanner;
+		use crate::testbed::{Service, Testbed};
+		use std::time::Duration;
+
+		let testbed = Testbed::start("127.0.0.1".parse().unwrap(), 0).await.unwrap();
+		let https = testbed.port(Service::Https);
+		let context = Scanner::builder()
+			.ports(format!("{},{}", https, testbed.port(Service::Http)).parse::<PortSpec>().unwrap())
+			.timeout(Duration::from_millis(500))
+			.context(ScanType::Tls);
+		let results = TlsPlugin.scan("127.0.0.1", &context).await.unwrap();
+		assert_eq!(results.iter().map(|r| r.port).collect::<Vec<_>>(), [Some(https)]);
+		let hash = results[0].details.as_deref().unwrap().strip_prefix("jarm:").unwrap();
+		assert_eq!(hash.len(), 62);
+		assert_ne!(hash, "0".repeat(62));
+	}
+}
@@ -0,0 +1,403 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! UDP scan plugin
+//!
+//! Scans for open UDP ports, which do not require a handshake. Each port gets a probe the
+//! service is likely to answer (a DNS or NTP query on their ports, an empty datagram elsewhere)
+//! over a socket connected to the port, so only datagrams from that port are received and the
+//! kernel reports ICMP port unreachable as a refused connection. A reply that matches the probe
+//! means the port is open, port unreachable means closed, and another ICMP error means filtered.
+//!
+//! UDP is lossy and hosts rate-limit their ICMP errors, so a single unanswered probe proves
+//! nothing. Probes are retransmitted `retries` times, waiting `backoff` times longer for each
+//! retransmission than for the one before, and only a port that never answers is reported as
+//! `open|filtered`. Ports are probed concurrently.
+//!
+//! With raw sockets, IPv4 targets are instead probed through the [`Engine`]: the datagrams are
+//! built by Huginn, from one reserved source port and with the configured TTL and
+//! fragmentation, and sent in batches, while replies and ICMP errors are collected as they
+//! arrive. Each round resends only the ports that have not answered yet.
+
+use crate::error::HuginnError;
+use crate::plugins::engine::{Engine, Filter};
+use crate::plugins::raw::{self, Ipv4Header, PROTOCOL_UDP, UdpReply, Unreachable};
+use crate::plugins::{ERROR_STATUS, Plugin, ScanContext, ScanResult, ScanType};
+use async_trait::async_trait;
+use log::debug;
+use socket2::Protocol;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+/// Ports probed when no port spec is configured
+const DEFAULT_PORTS: &[u16] = &[53, 67, 69, 123, 137, 161, 500, 514, 1900, 5353];
+
+/// Ports probed at the same time over connected sockets
+const CONCURRENCY: usize = 256;
+
+/// Status, service, and details of a port
+type Outcome = (&'static str, Option<&'static str>, String);
+
+/// The outcome of a port that never answered
+fn unanswered(sent: u32) -> Outcome {
+	("open|filtered", None, format!("no reply to {} probe{}", sent, if sent == 1 { "" } else { "s" }))
+}
+
+/// A datagram that the service on a port is likely to answer
+fn payload(port: u16) -> Vec<u8> {
+	match port {
+		// DNS query for the root name servers, with a random transaction ID
+		53 | 5353 => {
+			let mut query = (raw::random() as u16).to_be_bytes().to_vec();
+			query.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 1]);
+			query
+		},
+		// NTP version 3 client request
+		123 => {
+			let mut request = vec![0; 48];
+			request[0] = 0x1B;
+			request
+		},
+		_ => Vec::new(),
+	}
+}
+
+/// Whether a reply answers the probe, and the service it identifies
+fn correlate(port: u16, probe: &[u8], reply: &[u8]) -> Option<Option<&'static str>> {
+	match port {
+		// Same transaction ID, with the response flag set
+		53 | 5353 => (reply.len() >= 12 && reply[..2] == probe[..2] && reply[2] & 0x80 != 0).then_some(Some("dns")),
+		// Server mode
+		123 => (reply.len() >= 48 && reply[0] & 0x07 == 4).then_some(Some("ntp")),
+		_ => Some(None),
+	}
+}
+
+/// Probe one port, retransmitting with backoff until it answers
+async fn probe_port(address: SocketAddr, context: &ScanContext) -> io::Result<Outcome> {
+	let socket = context.transport.udp(address, context).await?;
+	let probe = payload(address.port());
+	let mut wait = context.timeout;
+	let mut buffer = [0; 1500];
+	for attempt in 0..=context.retries {
+		if !context.scan_delay.is_zero() {
+			tokio::time::sleep(context.scan_delay).await;
+		}
+		context.probes.sent();
+		socket.send_to(&probe, address).await?;
+		let deadline = Instant::now() + wait;
+		while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buffer)).await {
+			let sent = attempt + 1;
+			match received {
+				Ok((length, _)) => {
+					if let Some(service) = correlate(address.port(), &probe, &buffer[..length]) {
+						return Ok(("open", service, format!("{} byte reply to probe {}", length, sent)));
+					}
+				},
+				Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {
+					return Ok(("closed", None, format!("port unreachable in reply to probe {}", sent)));
+				},
+				Err(e) if is_unreachable(&e) => {
+					return Ok(("filtered", None, format!("{} in reply to probe {}", e, sent)));
+				},
+				Err(e) => return Err(e),
+			}
+		}
+		wait = wait.mul_f64(context.backoff);
+	}
+	Ok(unanswered(context.retries + 1))
+}
+
+/// Whether an error reports an ICMP unreachable other than port unreachable
+fn is_unreachable(error: &io::Error) -> bool {
+	matches!(
+		error.kind(),
+		io::ErrorKind::HostUnreachable | io::ErrorKind::NetworkUnreachable | io::ErrorKind::PermissionDenied
+	)
+}
+
+/// Probe every port over its own connected socket
+async fn connected_scan(address: IpAddr, context: &ScanContext) -> Result<BTreeMap<u16, Outcome>, HuginnError> {
+	let context = Arc::new(context.clone());
+	// A probe delay paces the probes one after another
+	let width = if context.scan_delay.is_zero() { CONCURRENCY } else { 1 };
+	let permits = Arc::new(Semaphore::new(width));
+	let mut probes = JoinSet::new();
+	for &port in context.ports_or(DEFAULT_PORTS) {
+		let (context, permits) = (context.clone(), permits.clone());
+		probes.spawn(async move {
+			let _permit = permits.acquire_owned().await;
+			(port, probe_port(SocketAddr::new(address, port), &context).await)
+		});
+	}
+
+	let mut outcomes = BTreeMap::new();
+	while let Some(joined) = probes.join_next().await {
+		let (port, outcome) = joined.map_err(io::Error::other)?;
+		// A socket error on one port is that port's result, not the whole scan's
+		let outcome = outcome.unwrap_or_else(|e| {
+			let error = HuginnError::from(e);
+			(ERROR_STATUS, None, format!("{}: {}", error.kind(), error))
+		});
+		outcomes.insert(port, outcome);
+	}
+	Ok(outcomes)
+}
+
+/// Probe every port with datagrams built and sent in batches by the engine, in rounds that
+/// resend to the ports yet to answer
+async fn raw_scan(address: Ipv4Addr, mut engine: Engine, context: &ScanContext) -> Result<BTreeMap<u16, Outcome>, HuginnError> {
+	let source = raw::source_address(address, context)?;
+	// Binding the source port reserves it, and keeps the kernel from answering replies to it
+	// with port unreachable
+	let reserved = std::net::UdpSocket::bind((source, context.raw.source_port.unwrap_or(0)))?;
+	let source_port = reserved.local_addr()?.port();
+	let ttl = context.raw.ttl.unwrap_or(raw::DEFAULT_TTL);
+	let probes: HashMap<u16, Vec<u8>> = context.ports_or(DEFAULT_PORTS).iter().map(|&port| (port, payload(port))).collect();
+	debug!("UDP scanning {} from {}:{} over raw sockets", address, source, source_port);
+
+	let mut outcomes = BTreeMap::new();
+	let mut wait = context.timeout;
+	for sent in 1..=context.retries + 1 {
+		let pending: Vec<u16> = context.ports_or(DEFAULT_PORTS).iter().copied().filter(|p| !outcomes.contains_key(p)).collect();
+		if pending.is_empty() {
+			break;
+		}
+		for &port in &pending {
+			if !context.scan_delay.is_zero() {
+				engine.flush().await?;
+				tokio::time::sleep(context.scan_delay).await;
+			}
+			let probe = &probes[&port];
+			engine
+				.queue(|buffer| {
+					Ipv4Header {
+						source,
+						destination: address,
+						protocol: PROTOCOL_UDP,
+						ttl,
+						identification: raw::random() as u16,
+					}
+					.write(buffer, 8 + probe.len());
+					raw::write_udp_datagram(buffer, source, address, (source_port, port), probe);
+				})
+				.await?;
+		}
+
+		let deadline = Instant::now() + wait;
+		while outcomes.len() < probes.len() {
+			let Some(packet) = engine.reply(deadline).await? else {
+				break;
+			};
+			if let Some(reply) = UdpReply::parse(&packet)
+				&& reply.source == address
+				&& reply.destination_port == source_port
+				&& let Some(probe) = probes.get(&reply.source_port)
+				&& !outcomes.contains_key(&reply.source_port)
+				&& let Some(service) = correlate(reply.source_port, probe, reply.payload)
+			{
+				let details = format!("{} byte reply to probe {}", reply.payload.len(), sent);
+				outcomes.insert(reply.source_port, ("open", service, details));
+			} else if let Some(unreachable) = Unreachable::parse(&packet)
+				&& unreachable.destination == address
+				&& unreachable.protocol == PROTOCOL_UDP
+				&& unreachable.source_port == source_port
+				&& probes.contains_key(&unreachable.destination_port)
+				&& !outcomes.contains_key(&unreachable.destination_port)
+			{
+				// Only the target itself can say that nothing listens on the port
+				let status = if unreachable.code == 3 && unreachable.reporter == address { "closed" } else { "filtered" };
+				let details = format!("{} in reply to probe {}", unreachable.reason(), sent);
+				outcomes.insert(unreachable.destination_port, (status, None, details));
+			}
+		}
+		wait = wait.mul_f64(context.backoff);
+	}
+	for &port in context.ports_or(DEFAULT_PORTS) {
+		outcomes.entry(port).or_insert_with(|| unanswered(context.retries + 1));
+	}
+	Ok(outcomes)
+}
+
+/// UDP scan plugin
+pub struct UdpScanPlugin;
+
+#[async_trait]
+impl Plugin for UdpScanPlugin {
+	fn name(&self) -> String {
+		"UDP Scanner".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::Udp
+	}
+
+	fn description(&self) -> String {
+		"UDP port scan with retransmission, reporting each port as open, closed, filtered, or open|filtered".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"ports",
+			"timeout",
+			"retries",
+			"backoff",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"source_port",
+			"ttl",
+			"mtu",
+		]
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		context.ports_or(DEFAULT_PORTS).len()
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		let address = context.resolve(target).await?;
+
+		// Raw sockets are an optimization here, so go without them when they are not permitted
+		let outcomes = match address {
+			IpAddr::V4(v4) => match Engine::start(&[Protocol::UDP, Protocol::ICMPV4], Filter::From(v4), context) {
+				Ok(engine) => raw_scan(v4, engine, context).await?,
+				Err(HuginnError::PermissionDenied(_)) => connected_scan(address, context).await?,
+				Err(e) => return Err(e),
+			},
+			IpAddr::V6(_) => connected_scan(address, context).await?,
+		};
+		Ok(outcomes
+			.into_iter()
+			.map(|(port, (status, service, details))| ScanResult {
+				target: target.to_string(),
+				scan_type: self.scan_type().to_string(),
+				port: Some(port),
+				status: status.to_string(),
+				severity: None,
+				service: service.map(str::to_string),
+				version: None,
+				details: Some(details),
+				techniques: Vec::new(),
+				screenshot: None,
+				rtt: None,
+			})
+			.collect())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ports::PortSpec;
+	use crate::scanner::Scanner;
+	use crate::transport::mock::{MockNetwork, Udp};
+	use std::time::Duration;
+	use tokio::net::UdpSocket;
+
+	#[test]
+	fn test_correlate() {
+		let query = payload(53);
+		let mut reply = query.clone();
+		reply[2] |= 0x80;
+		assert_eq!(correlate(53, &query, &reply), Some(Some("dns")));
+		reply[0] ^= 0xFF;
+		assert_eq!(correlate(53, &query, &reply), None);
+		assert_eq!(correlate(123, &payload(123), &[0x1C; 48]), Some(Some("ntp")));
+		assert_eq!(correlate(123, &payload(123), &[0x1B; 48]), None);
+		assert_eq!(correlate(9999, &[], b"hi"), Some(None));
+	}
+
+	/// A server on loopback that answers only the second probe, as over a lossy network, and a
+	/// closed port; returns the scan context for both
+	async fn lossy_server() -> (u16, u16, ScanContext) {
+		let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+		let open = server.local_addr().unwrap().port();
+		let closed = UdpSocket::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+		tokio::spawn(async move {
+			let mut buffer = [0; 64];
+			let (_, _) = server.recv_from(&mut buffer).await.unwrap();
+			let (_, peer) = server.recv_from(&mut buffer).await.unwrap();
+			server.send_to(b"pong", peer).await.unwrap();
+		});
+		let context = Scanner::builder()
+			.ports(format!("{},{}", open, closed).parse::<PortSpec>().unwrap())
+			.timeout(Duration::from_millis(100))
+			.retries(2)
+			.context(ScanType::Udp);
+		(open, closed, context)
+	}
+
+	#[tokio::test]
+	async fn test_scan_loopback() {
+		let (open, closed, context) = lossy_server().await;
+		// Over raw sockets when permitted, and connected sockets otherwise
+		let results = UdpScanPlugin.scan("127.0.0.1", &context).await.unwrap();
+		let outcome = |port| {
+			let result = results.iter().find(|r| r.port == Some(port)).unwrap();
+			(result.status.clone(), result.details.clone().unwrap())
+		};
+		assert_eq!(outcome(open), ("open".to_string(), "4 byte reply to probe 2".to_string()));
+		assert_eq!(outcome(closed).0, "closed");
+
+		let (open, closed, context) = lossy_server().await;
+		let outcomes = connected_scan(IpAddr::from([127, 0, 0, 1]), &context).await.unwrap();
+		assert_eq!(outcomes[&open], ("open", None, "4 byte reply to probe 2".to_string()));
+		assert_eq!(outcomes[&closed].0, "closed");
+	}
+
+	#[tokio::test]
+	async fn test_mock_network() {
+		let address = [192, 0, 2, 20];
+		// A DNS server that answers with the query's transaction ID, and one that gets it wrong
+		let dns = |shift: u8| {
+			Udp::Open(Arc::new(move |query: &[u8]| {
+				let mut reply = query.to_vec();
+				reply[0] ^= shift;
+				reply[2] |= 0x80;
+				Some(reply)
+			}))
+		};
+		let network = Arc::new(
+			MockNetwork::new()
+				.udp_port(address, 53, dns(0))
+				.udp_port(address, 5353, dns(0xFF))
+				.udp_port(address, 161, Udp::reply(b"snmp"))
+				.lose(address, 161, 2)
+				.udp_port(address, 500, Udp::Filtered)
+				.udp_port(address, 514, Udp::Silent)
+				.udp_port(address, 520, Udp::Broken),
+		);
+		let context = Scanner::builder()
+			.ports("53,123,161,500,514,520,5353".parse::<PortSpec>().unwrap())
+			.timeout(Duration::from_millis(20))
+			.retries(2)
+			.transport(network.clone())
+			.context(ScanType::Udp);
+
+		let results = UdpScanPlugin.scan("192.0.2.20", &context).await.unwrap();
+		let outcome = |port| {
+			let result = results.iter().find(|r| r.port == Some(port)).unwrap();
+			(result.status.as_str(), result.service.as_deref(), result.details.as_deref().unwrap())
+		};
+		assert_eq!(outcome(53), ("open", Some("dns"), "18 byte reply to probe 1"));
+		assert_eq!(outcome(123).0, "closed");
+		assert_eq!(outcome(161), ("open", None, "4 byte reply to probe 3"));
+		assert_eq!(outcome(500).0, "filtered");
+		assert_eq!(outcome(514), ("open|filtered", None, "no reply to 3 probes"));
+		assert_eq!(outcome(520).0, "error");
+		assert_eq!(outcome(5353), ("open|filtered", None, "no reply to 3 probes"));
+		assert_eq!(network.datagrams((address, 161).into()), 3);
+		assert_eq!(network.datagrams((address, 53).into()), 1);
+	}
+}
@@ -0,0 +1,539 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! UPnP Internet Gateway Device exposure plugin
+//!
+//! Finds UPnP devices on each port, on port 1900 by an SSDP `M-SEARCH` over UDP, which answers
+//! with the location of the device description, and on the other ports by the paths gateways
+//! commonly serve their description at. Each device found is reported as open, with its name,
+//! model, and type in the details.
+//!
+//! When the device is an Internet Gateway Device, its WAN connection service is asked for the
+//! external address and then for its port mappings, one index after another, which only reads
+//! them. A gateway that answers these control requests is reported as a `upnp_control` finding,
+//! with the mappings it lists, since anyone who reaches it can also add mappings that open ports
+//! through it.
+
+use crate::error::HuginnError;
+use crate::plugins::http::{self, Response, USER_AGENT};
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Ports probed when no port spec is configured
+const DEFAULT_PORTS: &[u16] = &[1900, 2869, 5000, 5431, 37215, 49152, 52869];
+
+/// Port SSDP is searched on
+const SSDP_PORT: u16 = 1900;
+
+/// Paths gateways commonly serve their device description at
+const DESCRIPTION_PATHS: &[&str] = &["/rootDesc.xml", "/description.xml", "/gatedesc.xml", "/igd.xml", "/picsdesc.xml"];
+
+/// Port mappings listed at most
+const MAX_MAPPINGS: u16 = 64;
+
+/// Largest SSDP response read
+const MAX_MESSAGE: usize = 8192;
+
+/// Replace the predefined XML entities in text
+fn unescape(text: &str) -> String {
+	text.replace("&lt;", "<")
+		.replace("&gt;", ">")
+		.replace("&quot;", "\"")
+		.replace("&apos;", "'")
+		.replace("&amp;", "&")
+}
+
+/// Local name of an XML tag, without its namespace prefix and attributes
+fn local_name(tag: &str) -> &str {
+	let name = tag.trim_start_matches('/').split_whitespace().next().unwrap_or_default();
+	name.rsplit(':').next().unwrap_or_default()
+}
+
+/// Contents of every element with a local name, in document order
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+	let mut found = Vec::new();
+	let mut position = 0;
+	while let Some(start) = xml[position..].find('<').map(|offset| position + offset) {
+		let Some(end) = xml[start..].find('>').map(|offset| start + offset) else {
+			break;
+		};
+		let tag = &xml[start + 1..end];
+		position = end + 1;
+		if tag.starts_with('/') || tag.ends_with('/') || local_name(tag) != name {
+			continue;
+		}
+		// The first closing tag with the name, past any children
+		let mut search = position;
+		while let Some(close) = xml[search..].find("</").map(|offset| search + offset) {
+			let Some(close_end) = xml[close..].find('>').map(|offset| close + offset) else {
+				break;
+			};
+			if local_name(&xml[close + 2..close_end]) == name {
+				found.push(&xml[position..close]);
+				position = close_end + 1;
+				break;
+			}
+			search = close_end + 1;
+		}
+	}
+	found
+}
+
+/// Text of the first element with a local name
+fn element(xml: &str, name: &str) -> Option<String> {
+	elements(xml, name).first().map(|text| unescape(text.trim())).filter(|text| !text.is_empty())
+}
+
+/// Port and path of an `http` URL, or of a path alone on a default port
+fn location(url: &str, port: u16) -> Option<(u16, String)> {
+	let Some(rest) = url.get(..7).filter(|scheme| scheme.eq_ignore_ascii_case("http://")).map(|_| &url[7..]) else {
+		return url.starts_with('/').then(|| (port, url.to_string()));
+	};
+	let (authority, path) = rest.find('/').map_or((rest, "/"), |slash| (&rest[..slash], &rest[slash..]));
+	// The port follows the last colon outside the brackets of an IPv6 address
+	let port = match authority.rsplit_once(':') {
+		Some((host, port)) if !host.is_empty() && !port.contains(']') => port.parse().ok()?,
+		_ => 80,
+	};
+	Some((port, path.to_string()))
+}
+
+/// A device described by a UPnP device description
+#[derive(Debug, PartialEq, Eq)]
+struct Device {
+	device_type: String,
+	name: Option<String>,
+	model: Option<String>,
+	/// Service type and control URL of each service of the device and its embedded devices
+	services: Vec<(String, String)>,
+}
+
+impl Device {
+	/// The device a description describes, if it is one
+	fn parse(xml: &str) -> Option<Device> {
+		let device_type = element(xml, "deviceType")?;
+		let model: Vec<_> =
+			["manufacturer", "modelName", "modelNumber"].iter().filter_map(|name| element(xml, name)).collect();
+		let services = elements(xml, "service")
+			.into_iter()
+			.filter_map(|service| Some((element(service, "serviceType")?, element(service, "controlURL")?)))
+			.collect();
+		Some(Device {
+			device_type,
+			name: element(xml, "friendlyName"),
+			model: (!model.is_empty()).then(|| model.join(" ")),
+			services,
+		})
+	}
+
+	/// Whether the device is an Internet Gateway Device
+	fn gateway(&self) -> bool {
+		self.device_type.contains(":InternetGatewayDevice:")
+	}
+
+	/// Service type and control URL of the WAN connection service, if the device has one
+	fn wan_connection(&self) -> Option<&(String, String)> {
+		self.services
+			.iter()
+			.find(|(service, _)| service.contains(":WANIPConnection:") || service.contains(":WANPPPConnection:"))
+	}
+}
+
+/// A port mapping listed by a gateway
+fn mapping(xml: &str) -> Option<String> {
+	let field = |name| element(xml, name).unwrap_or_default();
+	let external = element(xml, "NewExternalPort")?;
+	let remote = element(xml, "NewRemoteHost").map(|host| format!(" from {}", host)).unwrap_or_default();
+	let description = element(xml, "NewPortMappingDescription").map(|text| format!(" ({})", text)).unwrap_or_default();
+	let disabled = if field("NewEnabled") == "0" { ", disabled" } else { "" };
+	Some(format!(
+		"{} {}{} to {}:{}{}{}",
+		field("NewProtocol"),
+		external,
+		remote,
+		field("NewInternalClient"),
+		field("NewInternalPort"),
+		description,
+		disabled
+	))
+}
+
+/// Call an action of a UPnP service through its control URL
+async fn call(
+	target: &str,
+	port: u16,
+	control: &str,
+	service: &str,
+	action: &str,
+	arguments: &str,
+	context: &ScanContext,
+) -> io::Result<Response> {
+	let body = format!(
+		"<?xml version=\"1.0\"?>\r\n<s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\"><s:Body><u:{action} xmlns:u=\"{service}\">{arguments}</u:{action}></s:Body></s:Envelope>\r\n"
+	);
+	let request = format!(
+		"{}Connection: close\r\nContent-Type: text/xml; charset=\"utf-8\"\r\nSOAPAction: \"{}#{}\"\r\nContent-Length: {}\r\n\r\n{}",
+		http::head("POST", target, port, false, control),
+		service,
+		action,
+		body.len(),
+		body
+	);
+	http::round_trip(target, port, None, &request, context).await
+}
+
+/// The body of a successful answer to an action, if it succeeded
+async fn ask(
+	target: &str,
+	port: u16,
+	control: &str,
+	service: &str,
+	action: &str,
+	arguments: &str,
+	context: &ScanContext,
+) -> Option<String> {
+	match context.probe(|| call(target, port, control, service, action, arguments, context)).await {
+		Some(Ok(response)) if response.status == 200 => Some(String::from_utf8_lossy(&response.body).to_string()),
+		_ => None,
+	}
+}
+
+/// Results for a device described at a path of a port
+async fn report(target: &str, port: u16, path: &str, device: &Device, context: &ScanContext) -> Vec<ScanResult> {
+	let mut details: Vec<String> = device.name.iter().cloned().collect();
+	details.push(format!("device type {}", device.device_type.trim_start_matches("urn:schemas-upnp-org:device:")));
+	details.push(format!("description at {}", path));
+
+	let mut exposed = None;
+	if device.gateway()
+		&& let Some((service, control)) = device.wan_connection()
+		&& let Some((control_port, control)) = location(control, port)
+	{
+		match ask(target, control_port, &control, service, "GetExternalIPAddress", "", context).await {
+			Some(answer) => {
+				details.push(format!("WAN connection control at {} answers", control));
+				let mut mappings = Vec::new();
+				for index in 0..MAX_MAPPINGS {
+					let arguments = format!("<NewPortMappingIndex>{}</NewPortMappingIndex>", index);
+					// Past the last mapping, the gateway answers with a fault
+					let Some(entry) =
+						ask(target, control_port, &control, service, "GetGenericPortMappingEntry", &arguments, context).await
+					else {
+						break;
+					};
+					mappings.extend(mapping(&entry));
+				}
+				exposed = Some((element(&answer, "NewExternalIPAddress"), mappings));
+			},
+			None => details.push(format!("WAN connection control at {} refuses requests", control)),
+		}
+	}
+
+	let mut results = vec![ScanResult {
+		target: target.to_string(),
+		scan_type: ScanType::Upnp.to_string(),
+		port: Some(port),
+		status: "open".to_string(),
+		severity: None,
+		service: Some("upnp".to_string()),
+		version: device.model.clone(),
+		details: Some(details.join("; ")),
+		techniques: Vec::new(),
+		screenshot: None,
+		rtt: None,
+	}];
+	if let Some((address, mappings)) = exposed {
+		let address = address.map(|address| format!(", external address {}", address)).unwrap_or_default();
+		let listed = if mappings.is_empty() {
+			"no port mappings".to_string()
+		} else {
+			format!("{} port mappings: {}", mappings.len(), mappings.join(", "))
+		};
+		results.push(ScanResult {
+			target: target.to_string(),
+			scan_type: ScanType::Upnp.to_string(),
+			port: Some(port),
+			status: "upnp_control".to_string(),
+			severity: Some(Severity::High),
+			service: Some("upnp".to_string()),
+			version: None,
+			details: Some(format!(
+				"Gateway answers UPnP control requests without authentication{}, and lists {}; anyone who reaches it can add port mappings that open ports through it",
+				address, listed
+			)),
+			techniques: vec!["T1016".to_string(), "T1599".to_string()],
+			screenshot: None,
+			rtt: None,
+		});
+	}
+	results
+}
+
+/// The device description at a path of a port, or `None` when it is not one
+async fn describe(target: &str, port: u16, path: &str, context: &ScanContext) -> io::Result<Option<Device>> {
+	let response = context
+		.probe(|| http::get(target, port, None, path, context))
+		.await
+		.unwrap_or_else(|| Err(io::Error::from(io::ErrorKind::TimedOut)))?;
+	Ok(Device::parse(&String::from_utf8_lossy(&response.body)).filter(|_| response.status == 200))
+}
+
+/// The answer to an SSDP search for root devices on a port
+async fn search(target: &str, port: u16, context: &ScanContext) -> io::Result<Response> {
+	let request = format!(
+		"M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nMAN: \"ssdp:discover\"\r\nMX: 1\r\nST: upnp:rootdevice\r\nUSER-AGENT: {}\r\n\r\n",
+		USER_AGENT
+	);
+	let address = SocketAddr::new(context.resolve(target).await.map_err(io::Error::other)?, port);
+	let socket = context.transport.udp(address, context).await?;
+	socket.send_to(request.as_bytes(), address).await?;
+	let mut buffer = vec![0; MAX_MESSAGE];
+	loop {
+		let (length, _) = socket.recv_from(&mut buffer).await?;
+		if let Some(response) = http::parse(&buffer[..length], true).filter(|response| response.header("location").is_some()) {
+			return Ok(response);
+		}
+	}
+}
+
+/// Results for the SSDP service on a port, and the device it locates
+async fn discover(target: &str, port: u16, context: &ScanContext) -> Vec<ScanResult> {
+	let Some(Ok(answer)) = context.probe(|| search(target, port, context)).await else {
+		return Vec::new();
+	};
+	let url = answer.header("location").unwrap_or_default();
+	let mut results = vec![ScanResult {
+		target: target.to_string(),
+		scan_type: ScanType::Upnp.to_string(),
+		port: Some(port),
+		status: "open".to_string(),
+		severity: None,
+		service: Some("ssdp".to_string()),
+		version: answer.header("server").map(str::to_string),
+		details: Some(format!("SSDP answers M-SEARCH over UDP; description at {}", url)),
+		techniques: Vec::new(),
+		screenshot: None,
+		rtt: None,
+	}];
+	// The location names the device's own address, which may be internal, so the port and path
+	// are tried on the target
+	if let Some((port, path)) = location(url, 80)
+		&& let Ok(Some(device)) = describe(target, port, &path, context).await
+	{
+		results.extend(report(target, port, &path, &device, context).await);
+	}
+	results
+}
+
+/// Results for the UPnP device on a port, if there is one
+async fn inspect(target: &str, port: u16, context: &ScanContext) -> Vec<ScanResult> {
+	if port == SSDP_PORT {
+		return if context.proxy.is_none() { discover(target, port, context).await } else { Vec::new() };
+	}
+	for path in DESCRIPTION_PATHS {
+		match describe(target, port, path, context).await {
+			Ok(Some(device)) => return report(target, port, path, &device, context).await,
+			Ok(None) => {},
+			Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => break,
+			Err(_) => {},
+		}
+	}
+	Vec::new()
+}
+
+/// UPnP Internet Gateway Device exposure plugin
+pub struct UpnpPlugin;
+
+#[async_trait]
+impl Plugin for UpnpPlugin {
+	fn name(&self) -> String {
+		"UPnP Gateway Scanner".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::Upnp
+	}
+
+	fn description(&self) -> String {
+		"Finds UPnP devices, and Internet Gateway Devices that answer control requests, with their port mappings".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"proxy",
+			"max_connections",
+		]
+	}
+
+	fn proxy_capable(&self) -> bool {
+		true
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		// A search and a description on the SSDP port, and each description path on the others,
+		// with the control requests of a gateway left out
+		context.ports_or(DEFAULT_PORTS).iter().map(|&port| if port == SSDP_PORT { 2 } else { DESCRIPTION_PATHS.len() }).sum()
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		// Resolve up front to fail early, but connect by name
+		if context.proxy.is_none() {
+			context.resolve(target).await?;
+		}
+
+		let (target, context) = (Arc::new(target.to_string()), Arc::new(context.clone()));
+		// A probe delay paces the probes one after another
+		let width = if context.scan_delay.is_zero() { context.max_connections } else { 1 };
+		let permits = Arc::new(Semaphore::new(width));
+		let mut probes = JoinSet::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			let (target, context, permits) = (target.clone(), context.clone(), permits.clone());
+			probes.spawn(async move {
+				let _permit = permits.acquire_owned().await;
+				inspect(&target, port, &context).await
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(joined) = probes.join_next().await {
+			results.extend(joined.map_err(io::Error::other)?);
+		}
+		results.sort_by_key(|r| r.port);
+		// A device located through SSDP on a port that is also probed is found twice
+		let mut seen = HashSet::new();
+		results.retain(|r| seen.insert((r.port, r.service.clone(), r.status.clone())));
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scanner::Scanner;
+	use std::time::Duration;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+	use tokio::net::{TcpListener, UdpSocket};
+
+	const DESCRIPTION: &str = r#"<?xml version="1.0"?>
+<root xmlns="urn:schemas-upnp-org:device-1-0">
+<device>
+<deviceType>urn:schemas-upnp-org:device:InternetGatewayDevice:1</deviceType>
+<friendlyName>Home Router</friendlyName>
+<manufacturer>Acme</manufacturer>
+<modelName>AR-100</modelName>
+<serviceList><service>
+<serviceType>urn:schemas-upnp-org:service:Layer3Forwarding:1</serviceType>
+<controlURL>/ctl/L3F</controlURL>
+</service></serviceList>
+<deviceList><device>
+<deviceType>urn:schemas-upnp-org:device:WANDevice:1</deviceType>
+<deviceList><device>
+<deviceType>urn:schemas-upnp-org:device:WANConnectionDevice:1</deviceType>
+<serviceList><service>
+<serviceType>urn:schemas-upnp-org:service:WANIPConnection:1</serviceType>
+<controlURL>/ctl/IPConn</controlURL>
+</service></serviceList>
+</device></deviceList>
+</device></deviceList>
+</device>
+</root>"#;
+
+	/// Answer a request as a gateway with one port mapping
+	fn answer(request: &str) -> String {
+		let (status, body) = if request.starts_with("GET /rootDesc.xml ") {
+			("200 OK", DESCRIPTION.to_string())
+		} else if request.contains("#GetExternalIPAddress\"") {
+			("200 OK", "<s:Envelope><s:Body><u:GetExternalIPAddressResponse><NewExternalIPAddress>203.0.113.7</NewExternalIPAddress></u:GetExternalIPAddressResponse></s:Body></s:Envelope>".to_string())
+		} else if request.contains("<NewPortMappingIndex>0</NewPortMappingIndex>") {
+			(
+				"200 OK",
+				"<u:GetGenericPortMappingEntryResponse><NewRemoteHost></NewRemoteHost><NewExternalPort>8080</NewExternalPort><NewProtocol>TCP</NewProtocol><NewInternalPort>80</NewInternalPort><NewInternalClient>192.168.1.10</NewInternalClient><NewEnabled>1</NewEnabled><NewPortMappingDescription>camera &amp; NVR</NewPortMappingDescription></u:GetGenericPortMappingEntryResponse>".to_string(),
+			)
+		} else if request.starts_with("POST ") {
+			("500 Internal Server Error", "<errorCode>713</errorCode>".to_string())
+		} else {
+			("404 Not Found", String::new())
+		};
+		format!("HTTP/1.1 {}\r\nContent-Length: {}\r\n\r\n{}", status, body.len(), body)
+	}
+
+	#[test]
+	fn test_parse() {
+		let device = Device::parse(DESCRIPTION).unwrap();
+		assert!(device.gateway());
+		assert_eq!(device.name.as_deref(), Some("Home Router"));
+		assert_eq!(device.model.as_deref(), Some("Acme AR-100"));
+		assert_eq!(device.services.len(), 2);
+		assert_eq!(device.wan_connection().map(|(_, control)| control.as_str()), Some("/ctl/IPConn"));
+		assert!(Device::parse("<html><body>Not Found</body></html>").is_none());
+
+		assert_eq!(location("http://192.168.1.1:5000/rootDesc.xml", 80), Some((5000, "/rootDesc.xml".to_string())));
+		assert_eq!(location("HTTP://[fe80::1]/desc", 80), Some((80, "/desc".to_string())));
+		assert_eq!(location("/ctl/IPConn", 5000), Some((5000, "/ctl/IPConn".to_string())));
+		assert_eq!(location("ftp://host/", 80), None);
+	}
+
+	#[tokio::test]
+	async fn test_finds_gateway() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let web = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Ok((mut stream, _)) = listener.accept().await {
+				let mut request = vec![0; 8192];
+				let length = stream.read(&mut request).await.unwrap_or_default();
+				let _ = stream.write_all(answer(&String::from_utf8_lossy(&request[..length])).as_bytes()).await;
+			}
+		});
+		let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+		let ssdp = server.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			let mut buffer = [0; 2048];
+			while let Ok((_, peer)) = server.recv_from(&mut buffer).await {
+				let reply = format!(
+					"HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=120\r\nST: upnp:rootdevice\r\nLOCATION: http://192.168.1.1:{}/rootDesc.xml\r\nSERVER: Linux UPnP/1.1 MiniUPnPd/2.3.7\r\n\r\n",
+					web
+				);
+				server.send_to(reply.as_bytes(), peer).await.unwrap();
+			}
+		});
+
+		let context = Scanner::builder().timeout(Duration::from_millis(500)).retries(0).context(ScanType::Upnp);
+		let results = discover("127.0.0.1", ssdp, &context).await;
+		let statuses: Vec<_> = results.iter().map(|r| (r.port, r.status.as_str(), r.severity)).collect();
+		assert_eq!(
+			statuses,
+			[(Some(ssdp), "open", None), (Some(web), "open", None), (Some(web), "upnp_control", Some(Severity::High))]
+		);
+		assert_eq!(results[0].version.as_deref(), Some("Linux UPnP/1.1 MiniUPnPd/2.3.7"));
+		assert_eq!(
+			results[1].details.as_deref(),
+			Some(
+				"Home Router; device type InternetGatewayDevice:1; description at /rootDesc.xml; WAN connection control at /ctl/IPConn answers"
+			)
+		);
+		assert!(results[2].details.as_deref().unwrap().contains(
+			", external address 203.0.113.7, and lists 1 port mappings: TCP 8080 to 192.168.1.10:80 (camera & NVR);"
+		));
+
+		// The same device found on its web port directly
+		let results = inspect("127.0.0.1", web, &context).await;
+		assert_eq!(results.len(), 2);
+	}
+}
@@ -0,0 +1,105 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! SNMPv3 user-based security model
+//!
+//! Keys localized to an agent's engine from a user's passwords (RFC 3414 section 2.6), message
+//! authentication with HMAC-SHA-96 (RFC 3414) or HMAC-SHA-256-192 (RFC 7860), and privacy with
+//! AES-128 in CFB mode (RFC 3826).
+
+use crate::credentials::{AuthProtocol, Secret};
+use aes::Aes128;
+use aes::cipher::{AsyncStreamCipher, KeyIvInit};
+use ring::{digest, hmac};
+
+/// Bytes of the repeated password that a user's key is hashed from
+const PASSWORD_STRETCH: usize = 1_048_576;
+
+/// Digest and HMAC of an authentication protocol, with the length its MACs are truncated to
+fn algorithms(protocol: AuthProtocol) -> (&'static digest::Algorithm, hmac::Algorithm, usize) {
+	match protocol {
+		AuthProtocol::Sha => (&digest::SHA1_FOR_LEGACY_USE_ONLY, hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, 12),
+		AuthProtocol::Sha256 => (&digest::SHA256, hmac::HMAC_SHA256, 24),
+	}
+}
+
+/// Length of the MACs that authenticate messages
+pub(crate) fn mac_length(protocol: AuthProtocol) -> usize {
+	algorithms(protocol).2
+}
+
+/// A key localized to an engine from a password
+pub(crate) fn localize(protocol: AuthProtocol, password: &Secret, engine_id: &[u8]) -> Vec<u8> {
+	let (algorithm, ..) = algorithms(protocol);
+	let mut password = password.expose().bytes().cycle();
+	let mut context = digest::Context::new(algorithm);
+	let mut block = [0; 64];
+	for _ in 0..PASSWORD_STRETCH / block.len() {
+		block.fill_with(|| password.next().unwrap_or(0));
+		context.update(&block);
+	}
+	let key = context.finish();
+
+	let mut context = digest::Context::new(algorithm);
+	context.update(key.as_ref());
+	context.update(engine_id);
+	context.update(key.as_ref());
+	context.finish().as_ref().to_vec()
+}
+
+/// The MAC of a message whose authentication parameters are zeroed
+pub(crate) fn sign(protocol: AuthProtocol, key: &[u8], message: &[u8]) -> Vec<u8> {
+	let (_, algorithm, length) = algorithms(protocol);
+	hmac::sign(&hmac::Key::new(algorithm, key), message).as_ref()[..length].to_vec()
+}
+
+/// Run AES-128-CFB over data with a privacy key, the engine's boots and time, and a salt
+///
+/// The key is the first 16 bytes of the localized privacy key, and the IV is the boots, the time,
+/// and the salt, in that order (RFC 3826 section 3.1.2.1).
+pub(crate) fn cfb(key: &[u8], boots: u32, time: u32, salt: &[u8], data: &[u8], decrypt: bool) -> Vec<u8> {
+	let mut iv = [0; 16];
+	iv[..4].copy_from_slice(&boots.to_be_bytes());
+	iv[4..8].copy_from_slice(&time.to_be_bytes());
+	iv[8..].copy_from_slice(&salt[..8]);
+
+	let mut output = data.to_vec();
+	if decrypt {
+		cfb_mode::Decryptor::<Aes128>::new(key[..16].into(), &iv.into()).decrypt(&mut output);
+	} else {
+		cfb_mode::Encryptor::<Aes128>::new(key[..16].into(), &iv.into()).encrypt(&mut output);
+	}
+	output
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Bytes from hexadecimal
+	fn hex(text: &str) -> Vec<u8> {
+		(0..text.len()).step_by(2).map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap()).collect()
+	}
+
+	#[test]
+	fn test_cfb() {
+		// NIST SP 800-38A, F.3.13, with the IV split into boots, time, and salt as RFC 3826 builds
+		// it, and the key followed by the rest of a longer localized key, which is not used
+		let key = hex("2b7e151628aed2a6abf7158809cf4f3c0123456789abcdef");
+		let plaintext = hex("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51");
+		let ciphertext = cfb(&key, 0x00010203, 0x04050607, &hex("08090a0b0c0d0e0f"), &plaintext, false);
+		assert_eq!(ciphertext, hex("3b3fd92eb72dad20333449f8e83cfb4ac8a64537a0b3a93fcde3cdad9f1ce58b"));
+		assert_eq!(cfb(&key, 0x00010203, 0x04050607, &hex("08090a0b0c0d0e0f"), &ciphertext, true), plaintext);
+		// A partial last block is as long as the data
+		assert_eq!(cfb(&key, 0x00010203, 0x04050607, &hex("08090a0b0c0d0e0f"), &plaintext[..20], false), ciphertext[..20]);
+	}
+
+	#[test]
+	fn test_localize() {
+		// RFC 3414, appendix A.3.2
+		let key = localize(AuthProtocol::Sha, &Secret::new("maplesyrup"), &hex("000000000000000000000002"));
+		assert_eq!(key, hex("6695febc9288e36282235fc7151f128497b38f3f"));
+		assert_eq!(sign(AuthProtocol::Sha, &key, b"message").len(), 12);
+		assert_eq!(mac_length(AuthProtocol::Sha256), 24);
+	}
+}
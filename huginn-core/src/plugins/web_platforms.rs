@@ -0,0 +1,324 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Web platform fingerprinting for the HTTP plugin
+//!
+//! Recognizes the CMSs and frameworks behind a site, WordPress, Drupal, Joomla, Laravel, and
+//! Spring, from the clues they leave in its front page and in the page it serves for a path that
+//! does not exist:
+//!
+//! - headers, such as WordPress's `X-Pingback`, Drupal's `X-Generator` and `X-Drupal-Cache`, and
+//!   Spring Boot's `X-Application-Context`
+//! - the names of the cookies they set, such as `wordpress_logged_in_…`, Drupal's `SESS…`
+//!   session cookies, and `laravel_session`
+//! - the paths of their static assets, such as `/wp-content/` and `/media/jui/`
+//! - the `<meta name="generator">` tag, and error pages such as Spring Boot's Whitelabel page
+//!
+//! The version comes from the generator tag or header where it names one, or else from a file
+//! the platform serves: WordPress's RSS feed, Drupal's `CHANGELOG.txt`, and Joomla's
+//! `joomla.xml` manifest. Laravel and Spring do not expose theirs. Each platform found is
+//! reported as a `platform` result of the `http` scan type, whose version, such as
+//! `WordPress 6.4.2`, names the product so that vulnerability correlation can match it.
+
+use crate::plugins::http::{self, Response};
+use crate::plugins::tls::random_bytes;
+use crate::plugins::well_known;
+use crate::plugins::{ScanContext, ScanResult, ScanType};
+use tokio_rustls::TlsConnector;
+
+/// ATT&CK technique for identifying the software a site runs
+const TECHNIQUE: &str = "T1592.002";
+
+/// Something a platform leaves in its responses
+enum Clue {
+	/// A header, whose value contains the text when any is given
+	Header(&'static str, &'static str),
+	/// A cookie whose name starts with the text
+	Cookie(&'static str),
+	/// A cookie named by the text followed by a hex digest
+	HashedCookie(&'static str),
+	/// A path of the front page's assets
+	Asset(&'static str),
+	/// A generator tag whose content starts with the text
+	Generator(&'static str),
+	/// Text in either page
+	Text(&'static str),
+}
+
+/// A CMS or framework, and how to recognize it
+struct Platform {
+	name: &'static str,
+	clues: &'static [Clue],
+	/// Files that name the version, and the text the version follows in them
+	files: &'static [(&'static str, &'static str)],
+}
+
+/// Platforms recognized
+const PLATFORMS: &[Platform] = &[
+	Platform {
+		name: "WordPress",
+		clues: &[
+			Clue::Generator("WordPress"),
+			Clue::Header("x-pingback", ""),
+			Clue::Header("link", "api.w.org"),
+			Clue::Cookie("wordpress_"),
+			Clue::Cookie("wp-settings-"),
+			Clue::Asset("/wp-content/"),
+			Clue::Asset("/wp-includes/"),
+		],
+		files: &[("/feed/", "wordpress.org/?v=")],
+	},
+	Platform {
+		name: "Drupal",
+		clues: &[
+			Clue::Generator("Drupal"),
+			Clue::Header("x-generator", "Drupal"),
+			Clue::Header("x-drupal-cache", ""),
+			Clue::Header("x-drupal-dynamic-cache", ""),
+			Clue::HashedCookie("SESS"),
+			Clue::HashedCookie("SSESS"),
+			Clue::Asset("/sites/default/files/"),
+			Clue::Asset("/misc/drupal.js"),
+			Clue::Asset("drupal-settings-json"),
+		],
+		// Drupal 7 keeps its changelog at the root, and 8 and 9 under core
+		files: &[("/CHANGELOG.txt", "Drupal "), ("/core/CHANGELOG.txt", "Drupal ")],
+	},
+	Platform {
+		name: "Joomla",
+		clues: &[
+			Clue::Generator("Joomla!"),
+			Clue::Asset("/media/jui/"),
+			Clue::Asset("/media/system/js/"),
+			Clue::Asset("/components/com_"),
+		],
+		files: &[("/administrator/manifests/files/joomla.xml", "<version>")],
+	},
+	Platform {
+		name: "Laravel",
+		clues: &[Clue::Cookie("laravel_session"), Clue::Asset("/livewire/livewire.js")],
+		files: &[],
+	},
+	Platform {
+		name: "Spring",
+		clues: &[
+			Clue::Header("x-application-context", ""),
+			Clue::Text("Whitelabel Error Page"),
+			// Spring Boot's JSON error body
+			Clue::Text("\"status\":404,\"error\":\"Not Found\""),
+		],
+		files: &[],
+	},
+];
+
+/// Most requests sent to each web port: the missing page, and every version file, should each
+/// platform's clues be found and its generator tag name no exact version
+pub(crate) const PROBES: usize = {
+	let mut probes = 1;
+	let mut i = 0;
+	while i < PLATFORMS.len() {
+		probes += PLATFORMS[i].files.len();
+		i += 1;
+	}
+	probes
+};
+
+/// The version number that follows a marker in some text, such as `6.4.2` after
+/// `wordpress.org/?v=`
+fn version_after(text: &str, marker: &str) -> Option<String> {
+	let at = text.to_ascii_lowercase().find(&marker.to_ascii_lowercase())?;
+	let rest = text[at + marker.len()..].trim_start();
+	let version: String = rest.chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+	let version = version.trim_end_matches('.');
+	(!version.is_empty()).then(|| version.to_string())
+}
+
+/// Content of each `<meta name="generator">` tag of a page
+fn generators(html: &str) -> Vec<&str> {
+	let lower = html.to_ascii_lowercase();
+	let mut found = Vec::new();
+	let mut from = 0;
+	while let Some(start) = lower[from..].find("<meta").map(|i| from + i) {
+		let end = lower[start..].find('>').map_or(lower.len(), |i| start + i);
+		let tag = &html[start..end];
+		if http::attribute(tag, "name").is_some_and(|name| name.eq_ignore_ascii_case("generator"))
+			&& let Some(content) = http::attribute(tag, "content")
+		{
+			found.push(content.trim());
+		}
+		from = end;
+	}
+	found
+}
+
+/// Names of the cookies a response sets
+fn cookies(response: &Response) -> impl Iterator<Item = &str> {
+	response
+		.headers
+		.iter()
+		.filter(|(name, _)| name.eq_ignore_ascii_case("set-cookie"))
+		.filter_map(|(_, value)| Some(value.split_once('=')?.0.trim()))
+}
+
+/// What a platform's clues found in the front page and the missing page, and the version they
+/// name, if any
+fn recognize(platform: &Platform, page: &Response, missing: Option<&Response>) -> (Vec<String>, Option<String>) {
+	let responses: Vec<&Response> = [Some(page), missing].into_iter().flatten().collect();
+	let html = String::from_utf8_lossy(&page.body);
+	let mut evidence: Vec<String> = Vec::new();
+	let mut version = None;
+	for clue in platform.clues {
+		let found = match *clue {
+			Clue::Header(name, text) => responses
+				.iter()
+				.filter_map(|response| response.header(name))
+				.find(|value| value.contains(text))
+				.map(|value| {
+					version = version.take().or_else(|| version_after(value, text).filter(|_| !text.is_empty()));
+					format!("{} header", name)
+				}),
+			Clue::Cookie(prefix) => responses
+				.iter()
+				.flat_map(|response| cookies(response))
+				.any(|cookie| cookie.starts_with(prefix))
+				.then(|| format!("{}… cookie", prefix)),
+			Clue::HashedCookie(prefix) => responses
+				.iter()
+				.flat_map(|response| cookies(response))
+				.filter_map(|cookie| cookie.strip_prefix(prefix))
+				.any(|digest| digest.len() >= 32 && digest.chars().all(|c| c.is_ascii_hexdigit()))
+				.then(|| format!("{}… cookie", prefix)),
+			Clue::Asset(path) => html.contains(path).then(|| format!("{} assets", path)),
+			Clue::Generator(name) => generators(&html).into_iter().find(|content| content.starts_with(name)).map(|content| {
+				version = version.take().or_else(|| version_after(content, name));
+				"generator tag".to_string()
+			}),
+			Clue::Text(text) => responses
+				.iter()
+				.any(|response| String::from_utf8_lossy(&response.body).contains(text))
+				.then(|| format!("page text {:?}", text)),
+		};
+		if let Some(found) = found.filter(|found| !evidence.contains(found)) {
+			evidence.push(found);
+		}
+	}
+	(evidence, version)
+}
+
+/// The version a platform's files name, from the first that names one
+async fn version_file(
+	platform: &Platform,
+	target: &str,
+	port: u16,
+	tls: Option<&TlsConnector>,
+	context: &ScanContext,
+) -> Option<(&'static str, String)> {
+	for &(path, marker) in platform.files {
+		if let Some(Ok(response)) = context.probe(|| http::get(target, port, tls, path, context)).await
+			&& well_known::served(&response)
+			&& let Some(version) = version_after(&String::from_utf8_lossy(&response.body), marker)
+		{
+			return Some((path, version));
+		}
+	}
+	None
+}
+
+/// Results for the platforms behind the site whose front page is given
+pub(crate) async fn identify(
+	target: &str,
+	port: u16,
+	tls: Option<&TlsConnector>,
+	page: &Response,
+	context: &ScanContext,
+) -> Vec<ScanResult> {
+	let token: String = random_bytes(6).iter().map(|byte| format!("{:02x}", byte)).collect();
+	let missing = format!("/huginn-{}", token);
+	let missing = match context.probe(|| http::get(target, port, tls, &missing, context)).await {
+		Some(Ok(response)) => Some(response),
+		_ => None,
+	};
+
+	let mut results = Vec::new();
+	for platform in PLATFORMS {
+		let (mut evidence, mut version) = recognize(platform, page, missing.as_ref());
+		if evidence.is_empty() {
+			continue;
+		}
+		// A generator tag may give the major version only, as Drupal's does
+		if version.as_deref().is_none_or(|version| !version.contains('.'))
+			&& let Some((path, exact)) = version_file(platform, target, port, tls, context).await
+		{
+			evidence.push(path.to_string());
+			version = Some(exact);
+		}
+		let product = match &version {
+			Some(version) => format!("{} {}", platform.name, version),
+			None => platform.name.to_string(),
+		};
+		results.push(ScanResult {
+			target: target.to_string(),
+			scan_type: ScanType::Http.to_string(),
+			port: Some(port),
+			status: "platform".to_string(),
+			severity: None,
+			service: Some(if tls.is_some() { "https" } else { "http" }.to_string()),
+			version: Some(product.clone()),
+			details: Some(format!("{}: {}", product, evidence.join(", "))),
+			techniques: vec![TECHNIQUE.to_string()],
+			screenshot: None,
+			rtt: None,
+		});
+	}
+	results
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn response(headers: &[(&str, &str)], body: &str) -> Response {
+		Response {
+			version: "HTTP/1.1".to_string(),
+			status: 200,
+			reason: "OK".to_string(),
+			headers: headers.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+			body: body.as_bytes().to_vec(),
+		}
+	}
+
+	#[test]
+	fn test_recognize() {
+		let page = response(
+			&[("Link", "<https://example.com/wp-json/>; rel=\"https://api.w.org/\""), ("Set-Cookie", "wp-settings-1=x; path=/")],
+			"<meta name=\"generator\" content=\"WordPress 6.4.2\" /><link rel=\"stylesheet\" href=\"/wp-includes/css/dist.css?ver=6.4.2\">",
+		);
+		let (evidence, version) = recognize(&PLATFORMS[0], &page, None);
+		assert_eq!(evidence, ["generator tag", "link header", "wp-settings-… cookie", "/wp-includes/ assets"]);
+		assert_eq!(version.as_deref(), Some("6.4.2"));
+		assert!(PLATFORMS[1..].iter().all(|platform| recognize(platform, &page, None).0.is_empty()));
+		assert_eq!(PROBES, 5);
+
+		// Drupal's header names only the major version, and its session cookie is hashed
+		let page = response(
+			&[("X-Generator", "Drupal 10 (https://www.drupal.org)"), ("Set-Cookie", "SESS0123456789abcdef0123456789abcdef=1")],
+			"",
+		);
+		assert_eq!(
+			recognize(&PLATFORMS[1], &page, None),
+			(vec!["x-generator header".to_string(), "SESS… cookie".to_string()], Some("10".to_string()))
+		);
+		let page = response(&[("Set-Cookie", "SESSION=1")], "");
+		assert!(recognize(&PLATFORMS[1], &page, None).0.is_empty());
+
+		// Spring Boot gives itself away in the page for a missing path
+		let missing = response(&[], "{\"timestamp\":\"2025-01-01T00:00:00Z\",\"status\":404,\"error\":\"Not Found\",\"path\":\"/x\"}");
+		let (evidence, version) = recognize(&PLATFORMS[4], &response(&[], ""), Some(&missing));
+		assert_eq!(evidence.len(), 1);
+		assert_eq!(version, None);
+
+		assert_eq!(version_after("Drupal 7.98, 2023-06-07", "Drupal "), Some("7.98".to_string()));
+		assert_eq!(version_after("<version>4.2.8</version>", "<version>"), Some("4.2.8".to_string()));
+		assert_eq!(version_after("Joomla! - Open Source Content Management", "Joomla!"), None);
+	}
+}
@@ -0,0 +1,327 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Web service screenshot plugin
+//!
+//! Finds the web servers on each port (80, 443, 8000, 8080, and 8443 by default) the way
+//! [`http`](super::http) does, and saves a PNG screenshot of each front page into
+//! [`WebScreenshotConfig::directory`], so a reviewer can tell login pages, default install pages,
+//! and dashboards apart at a glance. Pages are captured by headless Chromium, or by an external
+//! capture tool given as [`WebScreenshotConfig::command`], with `{url}` and `{output}` replaced by
+//! the page's URL and the file to write. Certificates are not verified, as for every web check.
+//!
+//! Each web server is reported as one `open` result whose details give the URL, with the saved
+//! file in [`ScanResult::screenshot`], which the template formatter embeds as `screenshots`. Browsers are heavy, so
+//! at most [`MAX_BROWSERS`] captures run at once across every target. The browser connects
+//! directly, so this scan type is skipped when a proxy is configured; it also needs settings, so
+//! it is left out of [`crate::plugins::builtin`].
+
+use crate::error::HuginnError;
+use crate::plugins::http::{self, Clients};
+use crate::plugins::{Plugin, ScanContext, ScanResult, ScanType};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Ports probed when no port spec is configured
+const DEFAULT_PORTS: &[u16] = &[80, 443, 8000, 8080, 8443];
+
+/// Most captures running at once, across every target
+pub const MAX_BROWSERS: usize = 4;
+
+/// Longest a capture may take; pages that never finish loading are cut off
+const MAX_CAPTURE_TIME: Duration = Duration::from_secs(60);
+
+/// Settings of the `web_screenshot` scan type
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebScreenshotConfig {
+	/// Chromium or Chrome executable run headless
+	pub browser: PathBuf,
+	/// Extra browser arguments, such as `--no-sandbox` when run as root
+	pub browser_args: Vec<String>,
+	/// External capture command used instead of the browser, with `{url}` and `{output}` in its
+	/// arguments
+	pub command: Vec<String>,
+	/// Directory screenshots are saved in
+	pub directory: PathBuf,
+	/// Width of the browser window, in pixels
+	pub width: u32,
+	/// Height of the browser window, in pixels
+	pub height: u32,
+}
+
+impl Default for WebScreenshotConfig {
+	fn default() -> Self {
+		Self {
+			browser: PathBuf::from("chromium"),
+			browser_args: Vec::new(),
+			command: Vec::new(),
+			directory: PathBuf::from("screenshots"),
+			width: 1280,
+			height: 800,
+		}
+	}
+}
+
+/// Name of the screenshot of a port, safe on every file system
+fn file_name(target: &str, port: u16) -> String {
+	let target: String = target
+		.chars()
+		.map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+		.collect();
+	format!("{}_{}.png", target, port)
+}
+
+/// The program and arguments that capture a page into a file
+fn capture_command(config: &WebScreenshotConfig, url: &str, output: &Path) -> (PathBuf, Vec<String>) {
+	if let Some((program, arguments)) = config.command.split_first() {
+		let output = output.display().to_string();
+		let arguments = arguments.iter().map(|a| a.replace("{url}", url).replace("{output}", &output)).collect();
+		return (PathBuf::from(program), arguments);
+	}
+	let mut arguments = vec![
+		"--headless".to_string(),
+		"--disable-gpu".to_string(),
+		"--hide-scrollbars".to_string(),
+		"--ignore-certificate-errors".to_string(),
+		format!("--window-size={},{}", config.width, config.height),
+		format!("--screenshot={}", output.display()),
+	];
+	arguments.extend(config.browser_args.iter().cloned());
+	arguments.push(url.to_string());
+	(config.browser.clone(), arguments)
+}
+
+/// Capture a page into a file
+async fn capture(config: &WebScreenshotConfig, url: &str, output: &Path) -> Result<(), String> {
+	let (program, arguments) = capture_command(config, url, output);
+	let mut command = Command::new(&program);
+	command
+		.args(arguments)
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.stderr(Stdio::piped())
+		.kill_on_drop(true);
+	let run = command.output();
+	let result = match tokio::time::timeout(MAX_CAPTURE_TIME, run).await {
+		Ok(result) => result.map_err(|e| format!("failed to run {}: {}", program.display(), e))?,
+		Err(_) => return Err(format!("no screenshot within {:?}", MAX_CAPTURE_TIME)),
+	};
+	if !result.status.success() {
+		let stderr = String::from_utf8_lossy(&result.stderr);
+		let last = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("no error output");
+		return Err(format!("{} failed ({}): {}", program.display(), result.status, last.trim()));
+	}
+	match tokio::fs::metadata(output).await {
+		Ok(metadata) if metadata.len() > 0 => Ok(()),
+		_ => Err(format!("{} wrote no screenshot", program.display())),
+	}
+}
+
+/// The result for the web server on a port, if one answers
+async fn shoot(
+	config: &WebScreenshotConfig,
+	browsers: &Semaphore,
+	target: &str,
+	port: u16,
+	clients: &Clients,
+	context: &ScanContext,
+) -> Option<ScanResult> {
+	let (tls, page) = http::front_page(target, port, &clients.http1, context).await?;
+	let scheme = if tls.is_some() { "https" } else { "http" };
+	let url = format!("{}://{}/", scheme, http::authority(target, port, tls.is_some()));
+	let output = config.directory.join(file_name(target, port));
+
+	let captured = {
+		let _browser = browsers.acquire().await;
+		capture(config, &url, &output).await
+	};
+	let mut details = vec![url];
+	if let Some(title) = http::title(&String::from_utf8_lossy(&page.body)) {
+		details.push(format!("title {:?}", title));
+	}
+	let screenshot = match captured {
+		Ok(()) => Some(output),
+		Err(reason) => {
+			details.push(format!("no screenshot: {}", reason));
+			None
+		}
+	};
+
+	Some(ScanResult {
+		target: target.to_string(),
+		scan_type: ScanType::WebScreenshot.to_string(),
+		port: Some(port),
+		status: "open".to_string(),
+		severity: None,
+		service: Some(scheme.to_string()),
+		version: page.header("server").map(str::to_string),
+		details: Some(details.join("; ")),
+		techniques: Vec::new(),
+		screenshot,
+		rtt: None,
+	})
+}
+
+/// Web service screenshot plugin
+pub struct WebScreenshotPlugin {
+	config: Arc<WebScreenshotConfig>,
+	/// Captures allowed to run at once, shared by every target
+	browsers: Arc<Semaphore>,
+}
+
+impl WebScreenshotPlugin {
+	/// Create the plugin with its settings
+	pub fn new(config: WebScreenshotConfig) -> Self {
+		Self {
+			config: Arc::new(config),
+			browsers: Arc::new(Semaphore::new(MAX_BROWSERS)),
+		}
+	}
+}
+
+impl Default for WebScreenshotPlugin {
+	fn default() -> Self {
+		Self::new(WebScreenshotConfig::default())
+	}
+}
+
+#[async_trait]
+impl Plugin for WebScreenshotPlugin {
+	fn name(&self) -> String {
+		"Web Screenshotter".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::WebScreenshot
+	}
+
+	fn description(&self) -> String {
+		"Saves a screenshot of each web server's front page with headless Chromium or a capture tool".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&[
+			"web_screenshot",
+			"ports",
+			"timeout",
+			"retries",
+			"scan_delay",
+			"plugin_timing",
+			"interface",
+			"source_ip",
+			"max_connections",
+		]
+	}
+
+	fn scans_ports(&self) -> bool {
+		true
+	}
+
+	fn probes_per_target(&self, context: &ScanContext) -> usize {
+		// The front page over either scheme, and the browser's visit
+		context.ports_or(DEFAULT_PORTS).len() * 3
+	}
+
+	async fn scan(&self, target: &str, context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+		context.resolve(target).await?;
+		tokio::fs::create_dir_all(&self.config.directory).await.map_err(|e| {
+			HuginnError::Config(format!("failed to create {}: {}", self.config.directory.display(), e))
+		})?;
+
+		let clients = Arc::new(Clients::new()?);
+		let (target, context) = (Arc::new(target.to_string()), Arc::new(context.clone()));
+		// A probe delay paces the probes one after another
+		let width = if context.scan_delay.is_zero() { context.max_connections } else { 1 };
+		let permits = Arc::new(Semaphore::new(width));
+		let mut probes = JoinSet::new();
+		for &port in context.ports_or(DEFAULT_PORTS) {
+			let (config, browsers) = (self.config.clone(), self.browsers.clone());
+			let (target, context, clients, permits) = (target.clone(), context.clone(), clients.clone(), permits.clone());
+			probes.spawn(async move {
+				let _permit = permits.acquire_owned().await;
+				shoot(&config, &browsers, &target, port, &clients, &context).await
+			});
+		}
+
+		let mut results = Vec::new();
+		while let Some(joined) = probes.join_next().await {
+			results.extend(joined.map_err(io::Error::other)?);
+		}
+		results.sort_by_key(|r| r.port);
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ports::PortSpec;
+	use crate::scanner::Scanner;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+	use tokio::net::TcpListener;
+
+	#[test]
+	fn test_capture_command() {
+		assert_eq!(file_name("fe80::1%eth0", 8443), "fe80__1_eth0_8443.png");
+		let output = Path::new("shots/web_80.png");
+		let (program, arguments) = capture_command(&WebScreenshotConfig::default(), "http://web/", output);
+		assert_eq!(program, Path::new("chromium"));
+		assert!(arguments.contains(&"--window-size=1280,800".to_string()));
+		assert_eq!(arguments[arguments.len() - 2..], ["--screenshot=shots/web_80.png", "http://web/"]);
+
+		let config = WebScreenshotConfig {
+			command: ["wkhtmltoimage", "--quiet", "{url}", "{output}"].map(String::from).to_vec(),
+			..WebScreenshotConfig::default()
+		};
+		let (program, arguments) = capture_command(&config, "http://web/", output);
+		assert_eq!(program, Path::new("wkhtmltoimage"));
+		assert_eq!(arguments, ["--quiet", "http://web/", "shots/web_80.png"]);
+	}
+
+	#[cfg(unix)]
+	#[tokio::test]
+	async fn test_captures_web_servers() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Ok((mut stream, _)) = listener.accept().await {
+				let mut request = vec![0; 4096];
+				let _ = stream.read(&mut request).await;
+				let page = "<html><title>Printer status</title></html>";
+				let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", page.len(), page);
+				let _ = stream.write_all(response.as_bytes()).await;
+			}
+		});
+
+		// A stand-in capture tool that writes its URL as the screenshot
+		let directory = std::env::temp_dir().join(format!("huginn-screenshots-{}", std::process::id()));
+		let config = WebScreenshotConfig {
+			command: ["sh", "-c", "printf %s \"$0\" > \"$1\"", "{url}", "{output}"].map(String::from).to_vec(),
+			directory: directory.clone(),
+			..WebScreenshotConfig::default()
+		};
+		let plugin = WebScreenshotPlugin::new(config);
+		let context = Scanner::builder()
+			.timeout(Duration::from_millis(500))
+			.retries(0)
+			.ports(port.to_string().parse::<PortSpec>().unwrap())
+			.context(ScanType::WebScreenshot);
+
+		let results = plugin.scan("127.0.0.1", &context).await.unwrap();
+		let output = directory.join(format!("127.0.0.1_{}.png", port));
+		assert_eq!(results.len(), 1);
+		assert_eq!(results[0].details, Some(format!("http://127.0.0.1:{}/; title \"Printer status\"", port)));
+		assert_eq!(results[0].screenshot.as_deref(), Some(output.as_path()));
+		assert_eq!(std::fs::read_to_string(&output).unwrap(), format!("http://127.0.0.1:{}/", port));
+		std::fs::remove_dir_all(&directory).unwrap();
+	}
+}
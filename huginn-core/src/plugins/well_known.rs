@@ -0,0 +1,191 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Well-known file harvesting for the HTTP plugin
+//!
+//! Fetches the files a site publishes about itself and summarizes them as results of the `http`
+//! scan type:
+//!
+//! - `/robots.txt`, whose `Disallow` rules often name the paths a site would rather keep out of
+//!   search engines, such as admin consoles and backups, and whose `Sitemap` lines point to the
+//!   site's sitemaps
+//! - `/sitemap.xml`, listing the pages the site publishes, or the sitemaps of a sitemap index
+//! - `/.well-known/security.txt` (RFC 9116), with the contacts for reporting vulnerabilities and
+//!   the date the file expires
+//!
+//! Many servers answer every path with an HTML page, so a file only counts when it is served
+//! with a 200 status and looks like what it should be: robots and security.txt files that are
+//! not HTML, and sitemaps that are XML sitemaps.
+
+use crate::plugins::http::{self, Response};
+use crate::plugins::{ScanContext, ScanResult, ScanType};
+use chrono::{DateTime, Utc};
+use tokio_rustls::TlsConnector;
+
+/// Paths fetched from each web port
+pub(crate) const PATHS: &[&str] = &["/robots.txt", "/sitemap.xml", "/.well-known/security.txt"];
+
+/// Most paths or URLs listed in a result's details
+const MAX_LISTED: usize = 10;
+
+/// Whether a response serves a file, rather than an error or a catch-all HTML page
+pub(crate) fn served(response: &Response) -> bool {
+	let html = response.header("content-type").is_some_and(|kind| kind.contains("html"));
+	response.status == 200 && !response.body.is_empty() && !html
+}
+
+/// Values of a `Field: value` line format's fields with a name, in order and without repeats
+fn fields<'a>(text: &'a str, name: &str) -> Vec<&'a str> {
+	let mut values: Vec<&str> = Vec::new();
+	for line in text.lines() {
+		// Everything after a # is a comment
+		let line = line.split('#').next().unwrap_or_default();
+		if let Some((field, value)) = line.split_once(':')
+			&& field.trim().eq_ignore_ascii_case(name)
+		{
+			let value = value.trim();
+			if !value.is_empty() && !values.contains(&value) {
+				values.push(value);
+			}
+		}
+	}
+	values
+}
+
+/// The paths a robots.txt file disallows, for any user agent
+pub(crate) fn disallowed(robots: &str) -> Vec<&str> {
+	fields(robots, "disallow")
+}
+
+/// Up to [`MAX_LISTED`] items, and how many more there are
+fn list(items: &[&str]) -> String {
+	let mut listed = items.iter().take(MAX_LISTED).copied().collect::<Vec<_>>().join(", ");
+	if items.len() > MAX_LISTED {
+		listed.push_str(&format!(" and {} more", items.len() - MAX_LISTED));
+	}
+	listed
+}
+
+/// Summary of a robots.txt file, if it has any rules worth listing
+fn robots(text: &str) -> Option<String> {
+	let mut details = Vec::new();
+	let paths = disallowed(text);
+	if !paths.is_empty() {
+		details.push(format!("{} disallowed paths: {}", paths.len(), list(&paths)));
+	}
+	// Sitemap values are URLs, whose colons the field split keeps
+	let sitemaps = fields(text, "sitemap");
+	if !sitemaps.is_empty() {
+		details.push(format!("sitemaps: {}", list(&sitemaps)));
+	}
+	(!details.is_empty()).then(|| details.join("; "))
+}
+
+/// Summary of an XML sitemap or sitemap index
+fn sitemap(xml: &str) -> Option<String> {
+	let index = xml.contains("<sitemapindex");
+	if !index && !xml.contains("<urlset") {
+		return None;
+	}
+	let locations: Vec<&str> = xml
+		.split("<loc>")
+		.skip(1)
+		.filter_map(|rest| Some(rest.split_once("</loc>")?.0.trim()))
+		.collect();
+	let kind = if index { "sitemaps" } else { "URLs" };
+	Some(format!("{} {}: {}", locations.len(), kind, list(&locations)).trim_end_matches(": ").to_string())
+}
+
+/// Summary of a security.txt file, if it names a contact, as RFC 9116 requires
+fn security_txt(text: &str, now: DateTime<Utc>) -> Option<String> {
+	let contacts = fields(text, "contact");
+	if contacts.is_empty() {
+		return None;
+	}
+	let mut details = vec![format!("contact {}", contacts.join(", "))];
+	for field in ["policy", "encryption", "acknowledgments", "preferred-languages"] {
+		if let Some(value) = fields(text, field).first() {
+			details.push(format!("{} {}", field, value));
+		}
+	}
+	if let Some(expires) = fields(text, "expires").first() {
+		let expired = DateTime::parse_from_rfc3339(expires).is_ok_and(|expires| expires < now);
+		details.push(format!("expires {}{}", expires, if expired { " (expired)" } else { "" }));
+	}
+	Some(details.join("; "))
+}
+
+/// Results for the well-known files a site publishes
+pub(crate) async fn harvest(target: &str, port: u16, tls: Option<&TlsConnector>, context: &ScanContext) -> Vec<ScanResult> {
+	let mut results = Vec::new();
+	for &path in PATHS {
+		let Some(Ok(response)) = context.probe(|| http::get(target, port, tls, path, context)).await else {
+			continue;
+		};
+		let text = String::from_utf8_lossy(&response.body);
+		let summary = match path {
+			_ if !served(&response) => None,
+			"/robots.txt" => robots(&text).map(|summary| ("robots_txt", summary)),
+			"/sitemap.xml" => sitemap(&text).map(|summary| ("sitemap", summary)),
+			_ => security_txt(&text, Utc::now()).map(|summary| ("security_txt", summary)),
+		};
+		if let Some((status, summary)) = summary {
+			results.push(ScanResult {
+				target: target.to_string(),
+				scan_type: ScanType::Http.to_string(),
+				port: Some(port),
+				status: status.to_string(),
+				severity: None,
+				service: Some(if tls.is_some() { "https" } else { "http" }.to_string()),
+				version: None,
+				details: Some(format!("{}: {}", path, summary)),
+				techniques: Vec::new(),
+				screenshot: None,
+				rtt: None,
+			});
+		}
+	}
+	results
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_robots() {
+		let text = "User-agent: *\nDisallow: /admin/ # staff only\nDisallow:\n\nUser-agent: Googlebot\nDisallow: /admin/\nDisallow: /backup.zip\nSitemap: https://example.com/sitemap.xml\n";
+		assert_eq!(disallowed(text), ["/admin/", "/backup.zip"]);
+		assert_eq!(
+			robots(text).as_deref(),
+			Some("2 disallowed paths: /admin/, /backup.zip; sitemaps: https://example.com/sitemap.xml")
+		);
+		assert_eq!(robots("User-agent: *\nDisallow:\n"), None);
+
+		let many: String = (0..12).map(|i| format!("Disallow: /p{}\n", i)).collect();
+		assert!(robots(&many).unwrap().ends_with("/p9 and 2 more"));
+	}
+
+	#[test]
+	fn test_sitemap() {
+		let urls = "<?xml version=\"1.0\"?><urlset><url><loc>https://example.com/</loc></url><url><loc> https://example.com/about </loc></url></urlset>";
+		assert_eq!(sitemap(urls).as_deref(), Some("2 URLs: https://example.com/, https://example.com/about"));
+		let index = "<sitemapindex><sitemap><loc>https://example.com/posts.xml</loc></sitemap></sitemapindex>";
+		assert_eq!(sitemap(index).as_deref(), Some("1 sitemaps: https://example.com/posts.xml"));
+		assert_eq!(sitemap("<urlset></urlset>").as_deref(), Some("0 URLs"));
+		assert_eq!(sitemap("<html><body>Not found</body></html>"), None);
+	}
+
+	#[test]
+	fn test_security_txt() {
+		let text = "Contact: mailto:security@example.com\nContact: https://example.com/report\nExpires: 2025-01-01T00:00:00Z\nPolicy: https://example.com/policy\n";
+		let now = DateTime::parse_from_rfc3339("2025-06-01T00:00:00Z").unwrap().to_utc();
+		assert_eq!(
+			security_txt(text, now).as_deref(),
+			Some(
+				"contact mailto:security@example.com, https://example.com/report; policy https://example.com/policy; expires 2025-01-01T00:00:00Z (expired)"
+			)
+		);
+		assert_eq!(security_txt("Expires: 2030-01-01T00:00:00Z\n", now), None);
+	}
+}
@@ -0,0 +1,67 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Port selection for Huginn
+//!
+//! This module parses port specifications such as `22,80,8000-8100` into the ports that port
+//! scans probe.
+
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// A parsed port specification such as `22,80,8000-8100`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct PortSpec(Vec<u16>);
+
+impl PortSpec {
+	/// The selected ports, sorted and unique
+	pub fn ports(&self) -> &[u16] {
+		&self.0
+	}
+}
+
+impl FromStr for PortSpec {
+	type Err = String;
+
+	fn from_str(spec: &str) -> Result<Self, Self::Err> {
+		let mut ports = Vec::new();
+		for item in spec.split(',').map(str::trim) {
+			let (start, end) = item.split_once('-').unwrap_or((item, item));
+			let port = |text: &str| match text.trim().parse::<u16>() {
+				Ok(0) | Err(_) => Err(format!("invalid port {:?} in {:?}; expected 1-65535", text.trim(), item)),
+				Ok(port) => Ok(port),
+			};
+			let (start, end) = (port(start)?, port(end)?);
+			if start > end {
+				return Err(format!("port range {:?} ends before it starts", item));
+			}
+			ports.extend(start..=end);
+		}
+		ports.sort_unstable();
+		ports.dedup();
+		Ok(Self(ports))
+	}
+}
+
+impl TryFrom<String> for PortSpec {
+	type Error = String;
+
+	fn try_from(spec: String) -> Result<Self, Self::Error> {
+		spec.parse()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_port_spec() {
+		assert_eq!("80, 22,20-23".parse::<PortSpec>().unwrap().ports(), &[20, 21, 22, 23, 80]);
+		assert!("0".parse::<PortSpec>().is_err());
+		assert!("70000".parse::<PortSpec>().is_err());
+		assert!("100-90".parse::<PortSpec>().is_err());
+		assert!("22,".parse::<PortSpec>().is_err());
+	}
+}
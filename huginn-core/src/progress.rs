@@ -0,0 +1,214 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Progress reporting for Huginn
+//!
+//! A scan draws its progress on stderr, so it never mixes with results piped from stdout: an
+//! overall bar with the open ports found so far and the current probe send rate, and a bar per
+//! scan type showing the target it is on. The display is hidden when stderr is not a terminal
+//! and with `--no-progress` or `--quiet`. Log lines written to the console are printed above the
+//! bars instead of through them.
+
+use crate::plugins::ScanResult;
+use indicatif::style::ProgressTracker;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressState, ProgressStyle};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Layout of the overall bar
+const OVERALL_TEMPLATE: &str = "{spinner} [{elapsed_precise}] {wide_bar} {pos}/{len} {open} open ports, {rate}";
+
+/// Layout of each scan type's bar
+const PLUGIN_TEMPLATE: &str = "  {prefix:>12} {bar:30} {pos}/{len} {msg}";
+
+/// How often the display is redrawn while no step finishes
+const TICK: Duration = Duration::from_millis(200);
+
+/// Display currently drawn, if any
+static ACTIVE: Mutex<Option<MultiProgress>> = Mutex::new(None);
+
+/// Probes sent by one scan, across every plugin
+///
+/// Each run of a scanner counts its own, so scans running at the same time do not add to each
+/// other's counts; clones share the count.
+#[derive(Debug, Clone, Default)]
+pub struct Probes(Arc<AtomicU64>);
+
+impl Probes {
+	/// Count a probe sent
+	pub fn sent(&self) {
+		self.0.fetch_add(1, Ordering::Relaxed);
+	}
+
+	/// Probes sent so far
+	pub fn count(&self) -> u64 {
+		self.0.load(Ordering::Relaxed)
+	}
+}
+
+/// Probes per second over roughly the last second
+#[derive(Clone)]
+struct SendRate {
+	counted: Probes,
+	since: Instant,
+	probes: u64,
+	rate: f64,
+}
+
+impl ProgressTracker for SendRate {
+	fn clone_box(&self) -> Box<dyn ProgressTracker> {
+		Box::new(self.clone())
+	}
+
+	fn tick(&mut self, _: &ProgressState, now: Instant) {
+		let elapsed = now.saturating_duration_since(self.since);
+		if elapsed >= Duration::from_secs(1) {
+			let probes = self.counted.count();
+			self.rate = probes.saturating_sub(self.probes) as f64 / elapsed.as_secs_f64();
+			self.probes = probes;
+			self.since = now;
+		}
+	}
+
+	fn reset(&mut self, _: &ProgressState, now: Instant) {
+		self.since = now;
+		self.probes = self.counted.count();
+		self.rate = 0.0;
+	}
+
+	fn write(&self, _: &ProgressState, w: &mut dyn fmt::Write) {
+		let _ = write!(w, "{:.0} probes/s", self.rate);
+	}
+}
+
+/// Progress of a scan, counted in plugin runs against targets
+pub struct Progress {
+	multi: MultiProgress,
+	overall: ProgressBar,
+	plugins: Vec<ProgressBar>,
+	open: Arc<AtomicU64>,
+}
+
+impl Progress {
+	/// Start the display for scanning every target with each named scan type, showing the send
+	/// rate of the probes counted; a disabled display is never drawn
+	pub fn start(targets: usize, scan_types: &[String], probes: &Probes, enabled: bool) -> Self {
+		let target = if enabled { ProgressDrawTarget::stderr() } else { ProgressDrawTarget::hidden() };
+		let multi = MultiProgress::with_draw_target(target);
+		let open = Arc::new(AtomicU64::new(0));
+
+		let overall = multi.add(ProgressBar::new((targets * scan_types.len()) as u64));
+		let found = Arc::clone(&open);
+		let rate = SendRate {
+			counted: probes.clone(),
+			since: Instant::now(),
+			probes: probes.count(),
+			rate: 0.0,
+		};
+		if let Ok(style) = ProgressStyle::with_template(OVERALL_TEMPLATE) {
+			overall.set_style(
+				style
+					.with_key("open", move |_: &ProgressState, w: &mut dyn fmt::Write| {
+						let _ = write!(w, "{}", found.load(Ordering::Relaxed));
+					})
+					.with_key("rate", rate),
+			);
+		}
+
+		let plugins = scan_types
+			.iter()
+			.map(|scan_type| {
+				let bar = multi.add(ProgressBar::new(targets as u64));
+				if let Ok(style) = ProgressStyle::with_template(PLUGIN_TEMPLATE) {
+					bar.set_style(style);
+				}
+				bar.set_prefix(scan_type.clone());
+				bar
+			})
+			.collect();
+
+		if enabled {
+			overall.enable_steady_tick(TICK);
+			*ACTIVE.lock().unwrap_or_else(PoisonError::into_inner) = Some(multi.clone());
+		}
+		Self {
+			multi,
+			overall,
+			plugins,
+			open,
+		}
+	}
+
+	/// Show the target a scan type is working on
+	pub fn step(&self, plugin: usize, target: &str) {
+		if let Some(bar) = self.plugins.get(plugin) {
+			bar.set_message(target.to_string());
+		}
+	}
+
+	/// Count another target, found while scanning, for the scan types at these positions
+	pub fn extend(&self, plugins: &[usize]) {
+		for bar in plugins.iter().filter_map(|&plugin| self.plugins.get(plugin)) {
+			bar.inc_length(1);
+		}
+		self.overall.inc_length(plugins.len() as u64);
+	}
+
+	/// Count a finished plugin run and the open ports it found
+	pub fn advance(&self, plugin: usize, results: &[ScanResult]) {
+		let open = results.iter().filter(|r| r.port.is_some() && r.status == "open").count();
+		self.open.fetch_add(open as u64, Ordering::Relaxed);
+		if let Some(bar) = self.plugins.get(plugin) {
+			bar.inc(1);
+			if bar.position() == bar.length().unwrap_or(0) {
+				bar.finish_with_message("done");
+			}
+		}
+		self.overall.inc(1);
+	}
+}
+
+impl Drop for Progress {
+	fn drop(&mut self) {
+		for bar in &self.plugins {
+			bar.finish_and_clear();
+		}
+		self.overall.finish_and_clear();
+		let _ = self.multi.clear();
+		*ACTIVE.lock().unwrap_or_else(PoisonError::into_inner) = None;
+	}
+}
+
+/// Run a write to the console with the progress display cleared, redrawing it afterwards
+pub fn suspend<R>(write: impl FnOnce() -> R) -> R {
+	let active = ACTIVE.lock().unwrap_or_else(PoisonError::into_inner).clone();
+	match active {
+		Some(multi) => multi.suspend(write),
+		None => write(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_hidden_progress() {
+		let scan_types = ["ping".to_string(), "tcp_connect".to_string()];
+		let probes = Probes::default();
+		let progress = Progress::start(2, &scan_types, &probes, false);
+		probes.sent();
+		probes.clone().sent();
+		assert_eq!(probes.count(), 2);
+		let open = ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22);
+		progress.step(1, "10.0.0.1");
+		progress.advance(1, &[open.clone(), open]);
+		progress.advance(0, &[]);
+		assert_eq!(progress.open.load(Ordering::Relaxed), 2);
+		assert_eq!(progress.overall.position(), 2);
+		assert_eq!(progress.plugins[1].position(), 1);
+		assert_eq!(suspend(|| 7), 7);
+	}
+}
@@ -0,0 +1,1006 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Scanner core functionality
+//!
+//! This module implements the main scanner logic that orchestrates different scan types, and the
+//! builder that configures it.
+
+use crate::attack;
+use crate::credentials::Credentials;
+#[cfg(feature = "core-scan")]
+use crate::discovery::{self, Method};
+#[cfg(feature = "core-scan")]
+use crate::plugins::tcp_syn;
+use crate::error::HuginnError;
+use crate::limits;
+use crate::normalize;
+use crate::plugins::{FOUND_STATUS, Plugin, RawOptions, ScanContext, ScanResult, ScanType, TimingOverride};
+use crate::ports::PortSpec;
+use crate::progress::{Probes, Progress};
+use crate::proxy::Proxy;
+use crate::sinks::ResultSink;
+use crate::spool::Spool;
+use crate::statistics::Statistics;
+use crate::transport::{Network, Transport};
+use log::{error, info, warn};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Something that happened during a scan, delivered to every [`Observer`] as it happens
+#[derive(Debug, Clone)]
+pub enum ScanEvent {
+	/// The scanner started probing a target
+	HostStarted {
+		/// Target being scanned
+		target: String,
+	},
+	/// A plugin found an open port
+	PortFound {
+		/// Result reporting the open port
+		result: ScanResult,
+	},
+	/// A plugin finished scanning a target
+	PluginFinished {
+		/// Plugin name
+		plugin: String,
+		/// Target that was scanned
+		target: String,
+		/// Time the plugin took
+		elapsed: Duration,
+		/// Results the plugin reported
+		results: Vec<ScanResult>,
+	},
+	/// A plugin failed to scan a target
+	PluginError {
+		/// Plugin name
+		plugin: String,
+		/// Target that was scanned
+		target: String,
+		/// Time until the plugin failed
+		elapsed: Duration,
+		/// Why the plugin failed
+		error: Arc<HuginnError>,
+	},
+	/// Plugin runs completed so far, out of the total for the scan
+	Progress {
+		/// Plugin runs finished or failed
+		completed: usize,
+		/// Plugin runs in the whole scan
+		total: usize,
+	},
+	/// The scanner finished probing a target
+	HostFinished {
+		/// Target that was scanned
+		target: String,
+	},
+	/// The scan finished
+	Finished {
+		/// Statistics of the run, with its duration and the probes it sent
+		statistics: Statistics,
+	},
+}
+
+/// Receives every [`ScanEvent`] as it happens, e.g. to record metrics or update a display
+///
+/// Observers are called from the scan itself, so they should return quickly. To handle events
+/// elsewhere, register the sending half of a channel: `UnboundedSender<ScanEvent>` is an observer.
+pub trait Observer: Send + Sync {
+	/// Handle an event
+	fn event(&self, event: &ScanEvent);
+}
+
+impl Observer for UnboundedSender<ScanEvent> {
+	fn event(&self, event: &ScanEvent) {
+		// A receiver that has gone away has stopped listening
+		let _ = self.send(event.clone());
+	}
+}
+
+/// Probe settings shared by every plugin, with per-scan-type overrides
+#[derive(Debug, Clone)]
+struct Settings {
+	ports: Option<PortSpec>,
+	timeout: Duration,
+	retries: u32,
+	scan_delay: Duration,
+	backoff: f64,
+	timing: HashMap<ScanType, TimingOverride>,
+	interface: Option<String>,
+	source_ip: Option<IpAddr>,
+	proxy: Option<Proxy>,
+	max_connections: Option<usize>,
+	capture_duration: Duration,
+	capture_file: Option<PathBuf>,
+	raw: RawOptions,
+	zombie: Option<String>,
+	canary: String,
+	credentials: Arc<Credentials>,
+	transport: Arc<dyn Transport>,
+	#[cfg(feature = "core-scan")]
+	discovery: Vec<Method>,
+	#[cfg(feature = "core-scan")]
+	stateless: bool,
+}
+
+impl Settings {
+	/// Connections the pool holds: the configured number, within the file descriptor limit
+	fn max_connections(&self) -> usize {
+		let budget = limits::connection_budget(limits::descriptor_limit());
+		self.max_connections.map_or(budget, |max| max.clamp(1, budget))
+	}
+
+	/// Resolve the settings for a scan type, applying its timing overrides and default timing
+	fn context(&self, scan_type: ScanType, probes: &Probes) -> ScanContext {
+		let timing = self.timing.get(&scan_type);
+		let defaults = scan_type.default_timing();
+		ScanContext {
+			ports: self.ports.clone(),
+			timeout: timing.and_then(|t| t.timeout).or(defaults.timeout).unwrap_or(self.timeout),
+			retries: timing.and_then(|t| t.retries).or(defaults.retries).unwrap_or(self.retries),
+			scan_delay: timing.and_then(|t| t.scan_delay).or(defaults.scan_delay).unwrap_or(self.scan_delay),
+			backoff: timing.and_then(|t| t.backoff).or(defaults.backoff).unwrap_or(self.backoff),
+			interface: self.interface.clone(),
+			source_ip: self.source_ip,
+			proxy: self.proxy.clone(),
+			max_connections: self.max_connections(),
+			capture_duration: self.capture_duration,
+			capture_file: self.capture_file.clone(),
+			raw: self.raw.clone(),
+			zombie: self.zombie.clone(),
+			canary: self.canary.clone(),
+			credentials: self.credentials.clone(),
+			transport: self.transport.clone(),
+			probes: probes.clone(),
+		}
+	}
+}
+
+/// Builder for a [`Scanner`]
+///
+/// The defaults match the `huginn` command line: ping scans only, a 3 second timeout with one
+/// retry, no delay between probes, a one minute passive capture, and no progress display.
+pub struct ScannerBuilder {
+	targets: Vec<String>,
+	scan_types: Vec<ScanType>,
+	settings: Settings,
+	progress: bool,
+	plugins: Vec<Box<dyn Plugin>>,
+	sinks: Vec<Box<dyn ResultSink>>,
+	observers: Vec<Box<dyn Observer>>,
+	admit: Option<Box<Admit>>,
+}
+
+impl Default for ScannerBuilder {
+	fn default() -> Self {
+		Self {
+			targets: Vec::new(),
+			scan_types: vec![ScanType::Ping],
+			settings: Settings {
+				ports: None,
+				timeout: Duration::from_secs(3),
+				retries: 1,
+				scan_delay: Duration::ZERO,
+				backoff: 2.0,
+				timing: HashMap::new(),
+				interface: None,
+				source_ip: None,
+				proxy: None,
+				max_connections: None,
+				capture_duration: Duration::from_secs(60),
+				capture_file: None,
+				raw: RawOptions::default(),
+				zombie: None,
+				canary: "example.com".to_string(),
+				credentials: Arc::new(Credentials::default()),
+				transport: Arc::new(Network),
+				#[cfg(feature = "core-scan")]
+				discovery: Vec::new(),
+				#[cfg(feature = "core-scan")]
+				stateless: false,
+			},
+			progress: false,
+			plugins: Vec::new(),
+			sinks: Vec::new(),
+			observers: Vec::new(),
+			admit: None,
+		}
+	}
+}
+
+impl ScannerBuilder {
+	/// Hosts to scan; see [`crate::targets::expand`] for turning CIDR blocks into hosts
+	pub fn targets<T: Into<String>>(mut self, targets: impl IntoIterator<Item = T>) -> Self {
+		self.targets = targets.into_iter().map(Into::into).collect();
+		self
+	}
+
+	/// Scan types to run, each by the registered plugin of that type
+	pub fn scan_types(mut self, scan_types: impl IntoIterator<Item = ScanType>) -> Self {
+		self.scan_types = scan_types.into_iter().collect();
+		self
+	}
+
+	/// Ports to probe; plugins fall back to their own defaults when unset
+	pub fn ports(mut self, ports: impl Into<Option<PortSpec>>) -> Self {
+		self.settings.ports = ports.into();
+		self
+	}
+
+	/// Time to wait for a response to each probe
+	pub fn timeout(mut self, timeout: Duration) -> Self {
+		self.settings.timeout = timeout;
+		self
+	}
+
+	/// Extra attempts after a probe times out
+	pub fn retries(mut self, retries: u32) -> Self {
+		self.settings.retries = retries;
+		self
+	}
+
+	/// Pause before each probe
+	pub fn scan_delay(mut self, scan_delay: Duration) -> Self {
+		self.settings.scan_delay = scan_delay;
+		self
+	}
+
+	/// Factor the wait for a reply grows by with each retransmission, for plugins that retransmit
+	pub fn backoff(mut self, backoff: f64) -> Self {
+		self.settings.backoff = backoff;
+		self
+	}
+
+	/// Override the timeout, retries, delay, or backoff for one scan type
+	pub fn timing(mut self, scan_type: ScanType, timing: TimingOverride) -> Self {
+		self.settings.timing.insert(scan_type, timing);
+		self
+	}
+
+	/// Network interface that probes are sent from
+	pub fn interface(mut self, interface: impl Into<Option<String>>) -> Self {
+		self.settings.interface = interface.into();
+		self
+	}
+
+	/// Source address that probes are sent from
+	pub fn source_ip(mut self, source_ip: impl Into<Option<IpAddr>>) -> Self {
+		self.settings.source_ip = source_ip.into();
+		self
+	}
+
+	/// Proxy that TCP probes are tunneled through; scan types that cannot use it are skipped
+	pub fn proxy(mut self, proxy: impl Into<Option<Proxy>>) -> Self {
+		self.settings.proxy = proxy.into();
+		self
+	}
+
+	/// Most TCP connections held open at once; by default, and at most, as many as the file
+	/// descriptor limit leaves room for
+	pub fn max_connections(mut self, max_connections: impl Into<Option<usize>>) -> Self {
+		self.settings.max_connections = max_connections.into();
+		self
+	}
+
+	/// Time that passive plugins listen for traffic
+	pub fn capture_duration(mut self, capture_duration: Duration) -> Self {
+		self.settings.capture_duration = capture_duration;
+		self
+	}
+
+	/// Saved capture that passive plugins read instead of listening on an interface
+	pub fn capture_file(mut self, capture_file: impl Into<Option<PathBuf>>) -> Self {
+		self.settings.capture_file = capture_file.into();
+		self
+	}
+
+	/// Source port, decoys, TTL, padding, and fragmentation of the probes that raw-socket scans build
+	pub fn raw(mut self, raw: RawOptions) -> Self {
+		self.settings.raw = raw;
+		self
+	}
+
+	/// Zombie that idle scans are spoofed from, as `HOST[:PORT]`
+	pub fn zombie(mut self, zombie: impl Into<Option<String>>) -> Self {
+		self.settings.zombie = zombie.into();
+		self
+	}
+
+	/// Domain that open proxy and open resolver checks ask targets to reach; a domain whose web
+	/// and name server logs the operator can read shows every relayed request
+	pub fn canary(mut self, canary: impl Into<String>) -> Self {
+		self.settings.canary = canary.into();
+		self
+	}
+
+	/// Credentials that authenticated checks may log in with, by name
+	pub fn credentials(mut self, credentials: Credentials) -> Self {
+		self.settings.credentials = Arc::new(credentials);
+		self
+	}
+
+	/// How plugins reach the network; the host's own network stack unless set, or a
+	/// [`MockNetwork`](crate::transport::MockNetwork) to test plugins without one
+	pub fn transport(mut self, transport: Arc<dyn Transport>) -> Self {
+		self.settings.transport = transport;
+		self
+	}
+
+	/// Check which targets are up with these methods before scanning, and skip the port scans of
+	/// targets that answer none of them; no discovery runs when empty
+	#[cfg(feature = "core-scan")]
+	pub fn discovery(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+		self.settings.discovery = methods.into_iter().collect();
+		self
+	}
+
+	/// Run TCP SYN scans as one stateless sweep of every target before the other scans, which
+	/// keeps memory bounded on large sweeps but reports only open ports; see
+	/// [`tcp_syn::sweep`](crate::plugins::tcp_syn::sweep)
+	#[cfg(feature = "core-scan")]
+	pub fn stateless(mut self, enabled: bool) -> Self {
+		self.settings.stateless = enabled;
+		self
+	}
+
+	/// Draw progress bars on stderr while scanning
+	pub fn progress(mut self, enabled: bool) -> Self {
+		self.progress = enabled;
+		self
+	}
+
+	/// Add a plugin
+	pub fn plugin(mut self, plugin: Box<dyn Plugin>) -> Self {
+		self.plugins.push(plugin);
+		self
+	}
+
+	/// Add several plugins, such as [`crate::plugins::builtin`]
+	pub fn plugins(mut self, plugins: impl IntoIterator<Item = Box<dyn Plugin>>) -> Self {
+		self.plugins.extend(plugins);
+		self
+	}
+
+	/// Add a streaming sink that receives each result as it completes
+	pub fn sink(mut self, sink: Box<dyn ResultSink>) -> Self {
+		self.sinks.push(sink);
+		self
+	}
+
+	/// Add several streaming sinks
+	pub fn sinks(mut self, sinks: impl IntoIterator<Item = Box<dyn ResultSink>>) -> Self {
+		self.sinks.extend(sinks);
+		self
+	}
+
+	/// Add an observer of every scan event
+	pub fn observer(mut self, observer: Box<dyn Observer>) -> Self {
+		self.observers.push(observer);
+		self
+	}
+
+	/// Decide which hosts that plugins find are scanned, such as only those within an authorized
+	/// scope; every host found is scanned when unset
+	pub fn admit(mut self, admit: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+		self.admit = Some(Box::new(admit));
+		self
+	}
+
+	/// Settings a plugin of the scan type would receive
+	pub fn context(&self, scan_type: ScanType) -> ScanContext {
+		self.settings.context(scan_type, &Probes::default())
+	}
+
+	/// Build the scanner
+	pub fn build(self) -> Scanner {
+		let mut scanner = Scanner {
+			targets: self.targets,
+			scan_types: self.scan_types,
+			settings: self.settings,
+			progress: self.progress,
+			plugins: Vec::new(),
+			sinks: Vec::new(),
+			observers: self.observers,
+			admit: self.admit,
+		};
+		for plugin in self.plugins {
+			scanner.register_plugin(plugin);
+		}
+		for sink in self.sinks {
+			scanner.register_sink(sink);
+		}
+		scanner
+	}
+}
+
+/// Receives each batch of results as a run finds them
+type Collect<'a> = dyn FnMut(Vec<ScanResult>) + Send + 'a;
+
+/// Decides whether a host that a plugin found is scanned
+type Admit = dyn Fn(&str) -> bool + Send + Sync;
+
+/// Main scanner structure
+pub struct Scanner {
+	targets: Vec<String>,
+	scan_types: Vec<ScanType>,
+	settings: Settings,
+	progress: bool,
+	plugins: Vec<Box<dyn Plugin>>,
+	sinks: Vec<Box<dyn ResultSink>>,
+	observers: Vec<Box<dyn Observer>>,
+	admit: Option<Box<Admit>>,
+}
+
+impl Scanner {
+	/// Start building a scanner
+	pub fn builder() -> ScannerBuilder {
+		ScannerBuilder::default()
+	}
+
+	/// Register a plugin
+	pub fn register_plugin(&mut self, plugin: Box<dyn Plugin>) {
+		info!("Registering plugin: {}", plugin.name());
+		self.plugins.push(plugin);
+	}
+
+	/// Register a streaming sink that receives each result as it completes
+	pub fn register_sink(&mut self, sink: Box<dyn ResultSink>) {
+		info!("Registering result sink: {}", sink.name());
+		self.sinks.push(sink);
+	}
+
+	/// Deliver an event to every observer
+	fn emit(&self, event: ScanEvent) {
+		for observer in &self.observers {
+			observer.event(&event);
+		}
+	}
+
+	/// Publish results to every streaming sink; sink failures never abort the scan
+	async fn publish(&self, results: &[ScanResult]) {
+		for sink in &self.sinks {
+			for result in results {
+				if let Err(e) = sink.publish(result).await {
+					warn!("Failed to publish result to {}: {}", sink.name(), e);
+				}
+			}
+		}
+	}
+
+	/// Run host discovery on the target when it is enabled, recording the outcome as a result;
+	/// returns whether the target's port scans should run
+	#[cfg(feature = "core-scan")]
+	async fn discover(&self, target: &str, probes: &Probes, collect: &mut Collect<'_>) -> bool {
+		let methods = &self.settings.discovery;
+		if methods.is_empty() || self.settings.proxy.is_some() {
+			return true;
+		}
+		let context = self.settings.context(ScanType::Ping, probes);
+		let (status, details, up) = match discovery::discover(target, methods, &context).await {
+			Ok(answered) => {
+				let status = if answered.is_some() { "up" } else { "down" };
+				(status, discovery::details(answered, methods), answered.is_some())
+			},
+			// A host is only skipped when discovery ran and found it down
+			Err(e) => {
+				warn!("Host discovery of {} failed, so its ports are scanned anyway: {}", target, e);
+				(crate::plugins::ERROR_STATUS, format!("{}: {}", e.kind(), e), true)
+			},
+		};
+		let result = ScanResult {
+			target: target.to_string(),
+			scan_type: "discovery".to_string(),
+			port: None,
+			status: status.to_string(),
+			severity: None,
+			service: None,
+			version: None,
+			details: Some(details),
+			techniques: Vec::new(),
+			screenshot: None,
+			rtt: None,
+		};
+		self.publish(std::slice::from_ref(&result)).await;
+		collect(vec![result]);
+		up
+	}
+
+	/// Without the built-in scans there is no discovery, so every target is scanned
+	#[cfg(not(feature = "core-scan"))]
+	async fn discover(&self, _target: &str, _probes: &Probes, _collect: &mut Collect<'_>) -> bool {
+		true
+	}
+
+	/// Sweep every target with stateless TCP SYN probes, recording the open ports found, or a
+	/// failure for every target when the sweep cannot run
+	#[cfg(feature = "core-scan")]
+	async fn sweep(&self, probes: &Probes, collect: &mut Collect<'_>) {
+		info!("Sweeping {} targets with stateless TCP SYN probes", self.targets.len());
+		let found = match tcp_syn::sweep(&self.targets, &self.settings.context(ScanType::TcpSyn, probes)).await {
+			Ok(found) => found,
+			Err(e) => {
+				error!("Stateless sweep failed: {}", e);
+				self.targets.iter().map(|target| ScanResult::failed(target, ScanType::TcpSyn, &e)).collect()
+			},
+		};
+		for result in found.iter().filter(|r| r.port.is_some() && r.status == "open") {
+			self.emit(ScanEvent::PortFound { result: result.clone() });
+		}
+		self.publish(&found).await;
+		collect(found);
+	}
+
+	/// The hosts a plugin found while scanning a target that are new to the run and admitted
+	fn admitted(&self, target: &str, results: &[ScanResult], known: &mut HashSet<String>) -> Vec<String> {
+		let mut admitted = Vec::new();
+		for result in results.iter().filter(|r| r.status == FOUND_STATUS && r.target != target) {
+			if !known.insert(result.target.clone()) {
+				continue;
+			}
+			if self.admit.as_ref().is_some_and(|admit| !admit(&result.target)) {
+				info!(host = result.target.as_str(); "Not scanning {}, found on {}: not admitted", result.target, target);
+				continue;
+			}
+			info!(host = result.target.as_str(); "Queueing {}, found on {}", result.target, target);
+			admitted.push(result.target.clone());
+		}
+		admitted
+	}
+
+	/// Run all configured scans and collect their results
+	///
+	/// A plugin that fails on a target does not stop the scan; the failure is reported as an
+	/// `error` result (see [`ScanResult::failed`]) alongside the other results. The results are
+	/// normalized as described in [`normalize`] before they are returned.
+	pub async fn run(&self) -> Result<Vec<ScanResult>, HuginnError> {
+		self.run_with_statistics().await.map(|(results, _)| results)
+	}
+
+	/// Run all configured scans, returning their results and the statistics of the run
+	pub async fn run_with_statistics(&self) -> Result<(Vec<ScanResult>, Statistics), HuginnError> {
+		let started = Instant::now();
+		let probes = Probes::default();
+		let mut results = Vec::new();
+		let Some(aliases) = self.execute(&probes, &mut |found| results.extend(found)).await else {
+			return Ok((results, Statistics::default()));
+		};
+		let results = normalize::normalize(results, &aliases);
+		let statistics = self.finish(started, &probes, Statistics::from_results(&results));
+		Ok((results, statistics))
+	}
+
+	/// Run all configured scans, appending each result to a spool as it is found instead of
+	/// holding them in memory, and return the statistics of the run
+	///
+	/// Host names are reported under their aliased addresses when the spool is read back, but
+	/// identical findings are not merged. The first failure to write to the spool is returned
+	/// once the scan finishes.
+	pub async fn run_spooled(&self, spool: &mut Spool) -> Result<Statistics, HuginnError> {
+		let started = Instant::now();
+		let probes = Probes::default();
+		let mut failure = None;
+		let aliases = self
+			.execute(&probes, &mut |found| {
+				for result in &found {
+					if failure.is_none()
+						&& let Err(e) = spool.push(result)
+					{
+						failure = Some(e);
+					}
+				}
+			})
+			.await;
+		if let Some(e) = failure {
+			return Err(e);
+		}
+		let Some(aliases) = aliases else {
+			return Ok(Statistics::default());
+		};
+		spool.set_aliases(aliases);
+		Ok(self.finish(started, &probes, spool.statistics()))
+	}
+
+	/// Run all configured scans, handing each batch of results to `collect` as it is found;
+	/// returns the host names to report under their addresses, or `None` when there was nothing
+	/// to scan
+	async fn execute(&self, probes: &Probes, collect: &mut Collect<'_>) -> Option<HashMap<String, String>> {
+		info!("Starting scan execution");
+		if self.targets.is_empty() {
+			warn!("No targets configured for scanning");
+			return None;
+		}
+
+		if let Some(ports) = &self.settings.ports {
+			info!("Probing {} ports per target", ports.ports().len());
+		}
+		let connections = self.settings.max_connections();
+		if self.settings.max_connections.is_some_and(|max| max > connections) {
+			warn!("Holding at most {} TCP connections open: the file descriptor limit leaves no room for more", connections);
+		}
+
+		// Scan types that cannot use the proxy are skipped rather than sent directly
+		let proxied = self.settings.proxy.is_some();
+		if let Some(proxy) = &self.settings.proxy {
+			info!("Tunneling TCP probes through {}", proxy);
+			for plugin in &self.plugins {
+				if self.scan_types.contains(&plugin.scan_type()) && !plugin.proxy_capable() {
+					warn!("Skipping {} scans: they cannot be sent through a proxy", plugin.scan_type());
+				}
+			}
+			#[cfg(feature = "core-scan")]
+			if !self.settings.discovery.is_empty() {
+				warn!("Skipping host discovery: its probes cannot be sent through a proxy");
+			}
+		}
+
+		#[cfg_attr(not(feature = "core-scan"), allow(unused_mut))]
+		let mut enabled: Vec<&dyn Plugin> = self
+			.plugins
+			.iter()
+			.map(|plugin| plugin.as_ref())
+			.filter(|plugin| self.scan_types.contains(&plugin.scan_type()) && (!proxied || plugin.proxy_capable()))
+			.collect();
+
+		// A run of passive plugins only listens, so nothing is sent on their behalf either
+		let silent = !enabled.is_empty() && enabled.iter().all(|plugin| plugin.passive());
+		#[cfg(feature = "core-scan")]
+		if silent && !proxied && !self.settings.discovery.is_empty() {
+			warn!("Skipping host discovery: only passive scan types are enabled, and they send nothing");
+		}
+
+		// A stateless sweep replaces the per-target SYN scans
+		#[cfg(feature = "core-scan")]
+		let sweeping = self.settings.stateless && enabled.iter().any(|plugin| plugin.scan_type() == ScanType::TcpSyn);
+		#[cfg(feature = "core-scan")]
+		if sweeping {
+			enabled.retain(|plugin| plugin.scan_type() != ScanType::TcpSyn);
+		}
+		let scan_types: Vec<String> = enabled.iter().map(|plugin| plugin.scan_type().to_string()).collect();
+		let progress = Progress::start(self.targets.len(), &scan_types, probes, self.progress);
+		#[cfg(feature = "core-scan")]
+		if sweeping {
+			self.sweep(probes, collect).await;
+		}
+		let mut total = self.targets.len() * enabled.len();
+		let mut completed = 0;
+
+		// Hosts that plugins find are scanned after the targets by the plugins that do not find
+		// hosts themselves, so finding never recurses; each plugin is paired with its progress bar
+		let configured: Vec<(usize, &dyn Plugin)> = enabled.iter().copied().enumerate().collect();
+		#[cfg_attr(not(feature = "core-scan"), allow(unused_mut))]
+		let mut followups: Vec<(usize, &dyn Plugin)> =
+			configured.iter().copied().filter(|(_, plugin)| !plugin.finds_hosts()).collect();
+		// The sweep is over by the time hosts are found, so they get ordinary SYN scans
+		#[cfg(feature = "core-scan")]
+		if sweeping && let Some(plugin) = self.plugins.iter().find(|plugin| plugin.scan_type() == ScanType::TcpSyn) {
+			followups.push((enabled.len(), plugin.as_ref()));
+		}
+		let bars: Vec<usize> = followups.iter().map(|&(bar, _)| bar).collect();
+
+		let mut queue: VecDeque<String> = self.targets.iter().cloned().collect();
+		let mut known: HashSet<String> = self.targets.iter().cloned().collect();
+		let mut scanned: Vec<String> = Vec::with_capacity(self.targets.len());
+		while let Some(target) = queue.pop_front() {
+			let plugins = if scanned.len() < self.targets.len() { &configured } else { &followups };
+			info!("Scanning target: {}", target);
+			self.emit(ScanEvent::HostStarted { target: target.clone() });
+			let up = silent || self.discover(&target, probes, collect).await;
+			if !up {
+				info!(host = target.as_str(); "Skipping port scans of {}: host discovery found it down", target);
+			}
+
+			for &(index, plugin) in plugins {
+				let scan_type = plugin.scan_type();
+				progress.step(index, &target);
+				if !up && plugin.scans_ports() {
+					progress.advance(index, &[]);
+					completed += 1;
+					self.emit(ScanEvent::Progress { completed, total });
+					continue;
+				}
+				info!(scan_type:%, host = target.as_str(); "Running {} scan on {}", scan_type, target);
+				let started = Instant::now();
+				let context = self.settings.context(scan_type, probes);
+				match plugin.scan(&target, &context).await {
+					Ok(mut results) => {
+						for result in &mut results {
+							result.techniques.retain(|technique| {
+								let valid = attack::is_technique(technique);
+								if !valid {
+									warn!("Ignoring invalid ATT&CK technique {:?} from {}", technique, plugin.name());
+								}
+								valid
+							});
+						}
+						info!(
+							scan_type:%, host = target.as_str(), results = results.len();
+							"Scan completed: {} results found", results.len()
+						);
+						for result in results.iter().filter(|r| r.port.is_some() && r.status == "open") {
+							self.emit(ScanEvent::PortFound { result: result.clone() });
+						}
+						if plugin.finds_hosts() {
+							for host in self.admitted(&target, &results, &mut known) {
+								progress.extend(&bars);
+								total += followups.len();
+								queue.push_back(host);
+							}
+						}
+						self.emit(ScanEvent::PluginFinished {
+							plugin: plugin.name(),
+							target: target.clone(),
+							elapsed: started.elapsed(),
+							results: results.clone(),
+						});
+						self.publish(&results).await;
+						progress.advance(index, &results);
+						collect(results);
+					},
+					Err(e) => {
+						error!(scan_type:%, host = target.as_str(); "Scan failed: {}", e);
+						let failure = ScanResult::failed(&target, scan_type, &e);
+						self.emit(ScanEvent::PluginError {
+							plugin: plugin.name(),
+							target: target.clone(),
+							elapsed: started.elapsed(),
+							error: Arc::new(e),
+						});
+						self.publish(std::slice::from_ref(&failure)).await;
+						progress.advance(index, &[]);
+						collect(vec![failure]);
+					},
+				}
+				completed += 1;
+				self.emit(ScanEvent::Progress { completed, total });
+			}
+			self.emit(ScanEvent::HostFinished { target: target.clone() });
+			scanned.push(target);
+		}
+
+		for sink in &self.sinks {
+			if let Err(e) = sink.flush().await {
+				warn!("Failed to flush result sink {}: {}", sink.name(), e);
+			}
+		}
+
+		// Host names are left alone when proxied or silent, so no lookup is sent from the scanning host
+		if proxied || silent { Some(HashMap::new()) } else { Some(normalize::aliases(&scanned).await) }
+	}
+
+	/// Complete the statistics counted from a run's results, log them, and tell the observers
+	fn finish(&self, started: Instant, probes: &Probes, counted: Statistics) -> Statistics {
+		let statistics = Statistics {
+			duration: Some(started.elapsed()),
+			probes_sent: Some(probes.count()),
+			..counted
+		};
+		info!(
+			"Scan execution completed in {:.1?}: {} hosts up, {} open ports, {} errors",
+			started.elapsed(),
+			statistics.hosts_up,
+			statistics.ports_open,
+			statistics.errors
+		);
+		self.emit(ScanEvent::Finished {
+			statistics: statistics.clone(),
+		});
+		statistics
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use async_trait::async_trait;
+	use tokio::sync::mpsc;
+
+	/// Reports port 22 open on every target except `fail`
+	struct Fixed;
+
+	#[async_trait]
+	impl Plugin for Fixed {
+		fn name(&self) -> String {
+			"Fixed".to_string()
+		}
+
+		fn scan_type(&self) -> ScanType {
+			ScanType::TcpConnect
+		}
+
+		fn description(&self) -> String {
+			"Fixed results for tests".to_string()
+		}
+
+		fn scans_ports(&self) -> bool {
+			true
+		}
+
+		async fn scan(&self, target: &str, _context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+			if target == "fail" {
+				return Err(HuginnError::Unresolved { target: target.to_string() });
+			}
+			Ok(vec![ScanResult::new(target, "tcp_connect", "open").with_port(22)])
+		}
+	}
+
+	/// Finds two hosts under every target
+	struct Finder;
+
+	#[async_trait]
+	impl Plugin for Finder {
+		fn name(&self) -> String {
+			"Finder".to_string()
+		}
+
+		fn scan_type(&self) -> ScanType {
+			ScanType::Subdomains
+		}
+
+		fn description(&self) -> String {
+			"Found hosts for tests".to_string()
+		}
+
+		fn finds_hosts(&self) -> bool {
+			true
+		}
+
+		async fn scan(&self, target: &str, _context: &ScanContext) -> Result<Vec<ScanResult>, HuginnError> {
+			let found = |host: String| ScanResult::new(host, "subdomains", FOUND_STATUS);
+			Ok(vec![found(format!("www.{}", target)), found(format!("vpn.{}", target))])
+		}
+	}
+
+	#[test]
+	fn test_scanner_creation() {
+		let scanner = Scanner::builder().build();
+		assert_eq!(scanner.plugins.len(), 0);
+		assert_eq!(scanner.sinks.len(), 0);
+		assert_eq!(scanner.scan_types, [ScanType::Ping]);
+	}
+
+	#[test]
+	fn test_timing_overrides() {
+		let builder = Scanner::builder().timeout(Duration::from_secs(2)).retries(3).timing(
+			ScanType::Udp,
+			TimingOverride {
+				timeout: Some(Duration::from_secs(10)),
+				retries: None,
+				scan_delay: Some(Duration::from_millis(50)),
+				backoff: None,
+			},
+		);
+
+		let tcp = builder.context(ScanType::TcpConnect);
+		assert_eq!(tcp.timeout, Duration::from_secs(2));
+		assert_eq!(tcp.retries, 3);
+		assert_eq!(tcp.scan_delay, Duration::ZERO);
+
+		let udp = builder.context(ScanType::Udp);
+		assert_eq!(udp.timeout, Duration::from_secs(10));
+		assert_eq!(udp.retries, 3);
+		assert_eq!(udp.scan_delay, Duration::from_millis(50));
+	}
+
+	#[cfg(feature = "core-scan")]
+	#[tokio::test]
+	async fn test_discovery_skips_down_hosts() {
+		let scanner = Scanner::builder()
+			.targets(["198.51.100.1"])
+			.scan_types([ScanType::TcpConnect])
+			.timeout(Duration::from_millis(200))
+			.discovery([Method::Arp])
+			.plugin(Box::new(Fixed))
+			.build();
+		let (results, statistics) = scanner.run_with_statistics().await.unwrap();
+		assert_eq!(results.len(), 1);
+		assert_eq!((results[0].scan_type.as_str(), results[0].status.as_str()), ("discovery", "down"));
+		assert_eq!(statistics.hosts_down, 1);
+	}
+
+	#[tokio::test]
+	async fn test_events() {
+		let (sender, mut events) = mpsc::unbounded_channel();
+		let scanner = Scanner::builder()
+			.targets(["10.0.0.1", "fail"])
+			.scan_types([ScanType::TcpConnect])
+			.plugin(Box::new(Fixed))
+			.observer(Box::new(sender))
+			.build();
+		let (results, statistics) = scanner.run_with_statistics().await.unwrap();
+		assert_eq!((statistics.ports_open, statistics.errors), (1, 1));
+		assert!(statistics.duration.is_some());
+		assert_eq!(results.len(), 2);
+		assert!(results[1].is_error());
+		assert_eq!(results[1].details.as_deref(), Some("unresolved: fail did not resolve to an address"));
+		drop(scanner);
+
+		let mut names = Vec::new();
+		while let Some(event) = events.recv().await {
+			names.push(match event {
+				ScanEvent::HostStarted { .. } => "started",
+				ScanEvent::PortFound { result } => {
+					assert_eq!(result.port, Some(22));
+					"port"
+				},
+				ScanEvent::PluginFinished { results, .. } => {
+					assert_eq!(results.len(), 1);
+					"finished"
+				},
+				ScanEvent::PluginError { target, error, .. } => {
+					assert_eq!(target, "fail");
+					assert!(matches!(error.as_ref(), HuginnError::Unresolved { .. }));
+					"error"
+				},
+				ScanEvent::Progress { completed, total } => {
+					assert_eq!(total, 2);
+					["", "progress 1", "progress 2"][completed]
+				},
+				ScanEvent::HostFinished { .. } => "done",
+				ScanEvent::Finished { statistics } => {
+					assert_eq!(statistics.ports_open, 1);
+					"end"
+				},
+			});
+		}
+		assert_eq!(
+			names,
+			["started", "port", "finished", "progress 1", "done", "started", "error", "progress 2", "done", "end"]
+		);
+	}
+
+	#[tokio::test]
+	async fn test_found_hosts() {
+		let (sender, mut events) = mpsc::unbounded_channel();
+		let scanner = Scanner::builder()
+			.targets(["example.com", "www.example.com"])
+			.scan_types([ScanType::Subdomains, ScanType::TcpConnect])
+			.plugins([Box::new(Finder) as Box<dyn Plugin>, Box::new(Fixed)])
+			.admit(|host| !host.starts_with("vpn.www."))
+			.observer(Box::new(sender))
+			.build();
+		let results = scanner.run().await.unwrap();
+		let summary: Vec<_> = results.iter().map(|r| (r.target.as_str(), r.status.as_str())).collect();
+		assert_eq!(
+			summary,
+			[
+				("www.example.com", "found"),
+				("vpn.example.com", "found"),
+				("example.com", "open"),
+				("www.www.example.com", "found"),
+				("vpn.www.example.com", "found"),
+				("www.example.com", "open"),
+				("vpn.example.com", "open"),
+				("www.www.example.com", "open"),
+			]
+		);
+		drop(scanner);
+
+		let mut last = None;
+		while let Some(event) = events.recv().await {
+			if let ScanEvent::Progress { completed, total } = event {
+				last = Some((completed, total));
+			}
+		}
+		assert_eq!(last, Some((6, 6)));
+	}
+
+	#[tokio::test]
+	async fn test_run_spooled() {
+		let scanner = Scanner::builder()
+			.targets(["10.0.0.1", "fail"])
+			.scan_types([ScanType::TcpConnect])
+			.plugin(Box::new(Fixed))
+			.build();
+		let mut spool = Spool::create(&std::env::temp_dir()).unwrap();
+		let statistics = scanner.run_spooled(&mut spool).await.unwrap();
+		assert_eq!((statistics.ports_open, statistics.errors), (1, 1));
+		let results: Vec<ScanResult> = spool.read().unwrap().collect::<Result<_, _>>().unwrap();
+		assert_eq!(results.len(), 2);
+		assert_eq!(results[0].port, Some(22));
+		assert!(results[1].is_error());
+	}
+}
@@ -0,0 +1,27 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Streaming result sinks for Huginn
+//!
+//! This module defines the sink trait used to publish each scan result as soon as it completes,
+//! so long-running scans can feed stream-processing pipelines instead of waiting for the final
+//! report.
+
+use crate::plugins::ScanResult;
+use async_trait::async_trait;
+use std::error::Error;
+
+/// Sink trait that all streaming destinations must implement
+#[async_trait]
+pub trait ResultSink: Send + Sync {
+	/// Get the name of the sink
+	fn name(&self) -> String;
+
+	/// Publish a single result
+	async fn publish(&self, result: &ScanResult) -> Result<(), Box<dyn Error>>;
+
+	/// Wait for buffered results to be delivered
+	async fn flush(&self) -> Result<(), Box<dyn Error>> {
+		Ok(())
+	}
+}
@@ -0,0 +1,161 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Results spilled to disk
+//!
+//! A scan of millions of ports would hold millions of results in memory until its report is
+//! written. A [`Spool`] keeps them in a temporary NDJSON file instead, one result per line, as
+//! they are found, and counts their statistics on the way in. Once the scan finishes, the
+//! results are read back one at a time for the formatters to write out with
+//! [`Formatter::write_stream`](crate::formatters::Formatter::write_stream), so memory stays flat
+//! however large the scan.
+//!
+//! Host names that resolve to a scanned address are reported under the address as they are read
+//! back, as [`normalize`](crate::normalize) does, but identical findings are not merged, since
+//! that needs every result at once.
+
+use crate::error::HuginnError;
+use crate::normalize;
+use crate::plugins::ScanResult;
+use crate::statistics::{Statistics, Tally};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Lines, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Scan results kept in a temporary NDJSON file, which is removed when the spool is dropped
+pub struct Spool {
+	path: PathBuf,
+	writer: BufWriter<File>,
+	tally: Tally,
+	count: usize,
+	aliases: HashMap<String, String>,
+}
+
+impl Spool {
+	/// Create an empty spool file in a directory, which only the owner can read since the results
+	/// describe the scanned hosts' weaknesses
+	pub fn create(dir: &Path) -> Result<Self, HuginnError> {
+		let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_nanos());
+		let path = dir.join(format!("huginn-spool-{}-{}.ndjson", std::process::id(), nanos));
+		let mut options = OpenOptions::new();
+		options.write(true).create_new(true);
+		#[cfg(unix)]
+		std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+		let file = options.open(&path)?;
+		Ok(Self {
+			path,
+			writer: BufWriter::new(file),
+			tally: Tally::default(),
+			count: 0,
+			aliases: HashMap::new(),
+		})
+	}
+
+	/// Path of the spool file
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Append a result
+	pub fn push(&mut self, result: &ScanResult) -> Result<(), HuginnError> {
+		serde_json::to_writer(&mut self.writer, result).map_err(io::Error::from)?;
+		self.writer.write_all(b"\n")?;
+		self.tally.add(result);
+		self.count += 1;
+		Ok(())
+	}
+
+	/// Results appended so far
+	pub fn len(&self) -> usize {
+		self.count
+	}
+
+	/// Whether no results have been appended
+	pub fn is_empty(&self) -> bool {
+		self.count == 0
+	}
+
+	/// Statistics of the results appended so far; the duration and probes sent are left unset
+	pub fn statistics(&self) -> Statistics {
+		self.tally.statistics()
+	}
+
+	/// Report the results for each host name under the address it maps to when they are read
+	pub fn set_aliases(&mut self, aliases: HashMap<String, String>) {
+		self.aliases = aliases;
+	}
+
+	/// Read the results back from the start, in the order they were appended
+	pub fn read(&mut self) -> Result<Reader<'_>, HuginnError> {
+		self.writer.flush()?;
+		Ok(Reader {
+			lines: BufReader::new(File::open(&self.path)?).lines(),
+			aliases: &self.aliases,
+		})
+	}
+}
+
+impl Drop for Spool {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_file(&self.path);
+	}
+}
+
+/// Results read back from a [`Spool`], one at a time
+pub struct Reader<'a> {
+	lines: Lines<BufReader<File>>,
+	aliases: &'a HashMap<String, String>,
+}
+
+impl Iterator for Reader<'_> {
+	type Item = Result<ScanResult, HuginnError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let line = match self.lines.next()? {
+			Ok(line) => line,
+			Err(e) => return Some(Err(e.into())),
+		};
+		let mut result: ScanResult = match serde_json::from_str(&line) {
+			Ok(result) => result,
+			Err(e) => return Some(Err(io::Error::from(e).into())),
+		};
+		if let Some(address) = self.aliases.get(&result.target) {
+			let name = std::mem::replace(&mut result.target, address.clone());
+			normalize::add_details(&mut result, format!("scanned as {}", name));
+		}
+		Some(Ok(result))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_spool_round_trip() {
+		let mut spool = Spool::create(&std::env::temp_dir()).unwrap();
+		let path = spool.path().to_path_buf();
+		#[cfg(unix)]
+		assert_eq!(std::os::unix::fs::PermissionsExt::mode(&path.metadata().unwrap().permissions()) & 0o777, 0o600);
+		spool.push(&ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22)).unwrap();
+		spool.push(&ScanResult::new("db.example.com", "tcp_connect", "closed").with_port(443)).unwrap();
+		spool.set_aliases(HashMap::from([("db.example.com".to_string(), "10.0.0.1".to_string())]));
+		assert_eq!(spool.len(), 2);
+		assert_eq!(spool.statistics().ports_open, 1);
+		assert_eq!(spool.statistics().hosts_up, 1);
+
+		let results: Vec<ScanResult> = spool.read().unwrap().collect::<Result<_, _>>().unwrap();
+		assert_eq!((results[0].target.as_str(), results[0].port), ("10.0.0.1", Some(22)));
+		assert_eq!(results[1].target, "10.0.0.1");
+		assert_eq!(results[1].details.as_deref(), Some("scanned as db.example.com"));
+
+		// Reading again starts over, and results can still be appended
+		spool.push(&ScanResult::new("10.0.0.2", "tcp_connect", "open").with_port(80)).unwrap();
+		assert_eq!(spool.read().unwrap().count(), 3);
+
+		drop(spool);
+		assert!(!path.exists());
+	}
+}
@@ -0,0 +1,195 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Scan statistics for Huginn
+//!
+//! A summary of a run: hosts found up or down, ports by status, failed scans, and the average
+//! ping round trip, all counted from the results. The scan's duration and the probes it sent are
+//! only known to the scanner, so they are absent from statistics counted for saved results.
+
+use crate::plugins::ScanResult;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::time::Duration;
+
+/// Summary of a scan run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Statistics {
+	/// Time the scan took
+	#[serde(rename = "duration_ms", with = "millis", skip_serializing_if = "Option::is_none")]
+	pub duration: Option<Duration>,
+	/// Hosts that answered a ping or have an open port
+	pub hosts_up: usize,
+	/// Hosts that did not answer a ping and have no open port
+	pub hosts_down: usize,
+	/// Ports found open
+	pub ports_open: usize,
+	/// Ports found closed
+	pub ports_closed: usize,
+	/// Ports found filtered
+	pub ports_filtered: usize,
+	/// Probes the scan sent
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub probes_sent: Option<u64>,
+	/// Scans that failed
+	pub errors: usize,
+	/// Average round-trip time of the ping replies
+	#[serde(rename = "average_rtt_ms", with = "millis", skip_serializing_if = "Option::is_none")]
+	pub average_rtt: Option<Duration>,
+}
+
+/// Durations written as fractional milliseconds
+pub(crate) mod millis {
+	use serde::{Deserialize, Deserializer, Serializer};
+	use std::time::Duration;
+
+	pub fn serialize<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+		match duration {
+			Some(duration) => serializer.serialize_f64(duration.as_micros() as f64 / 1000.0),
+			None => serializer.serialize_none(),
+		}
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+		let millis = Option::<f64>::deserialize(deserializer)?;
+		Ok(millis.filter(|ms| ms.is_finite() && *ms >= 0.0).map(|ms| Duration::from_secs_f64(ms / 1000.0)))
+	}
+}
+
+impl Statistics {
+	/// Count statistics from results; the duration and probes sent are left unset
+	pub fn from_results(results: &[ScanResult]) -> Self {
+		let mut tally = Tally::default();
+		for result in results {
+			tally.add(result);
+		}
+		tally.statistics()
+	}
+}
+
+/// Statistics counted one result at a time, for results that are not all held in memory
+///
+/// Only the hosts seen are remembered, not the results.
+#[derive(Debug, Clone, Default)]
+pub struct Tally {
+	up: HashSet<String>,
+	down: HashSet<String>,
+	ports_open: usize,
+	ports_closed: usize,
+	ports_filtered: usize,
+	errors: usize,
+	rtt_total: Duration,
+	rtt_count: u32,
+}
+
+impl Tally {
+	/// Count a result
+	pub fn add(&mut self, result: &ScanResult) {
+		if result.status == "up" || (result.port.is_some() && result.status == "open") {
+			self.up.insert(result.target.clone());
+		} else if result.status == "down" {
+			self.down.insert(result.target.clone());
+		}
+		if result.port.is_some() {
+			match result.status.as_str() {
+				"open" => self.ports_open += 1,
+				"closed" => self.ports_closed += 1,
+				"filtered" => self.ports_filtered += 1,
+				_ => {},
+			}
+		}
+		if result.is_error() {
+			self.errors += 1;
+		}
+		if result.status == "up"
+			&& let Some(rtt) = result.rtt
+		{
+			self.rtt_total += rtt;
+			self.rtt_count += 1;
+		}
+	}
+
+	/// The statistics of the results counted so far; the duration and probes sent are left unset
+	pub fn statistics(&self) -> Statistics {
+		Statistics {
+			duration: None,
+			hosts_up: self.up.len(),
+			hosts_down: self.down.difference(&self.up).count(),
+			ports_open: self.ports_open,
+			ports_closed: self.ports_closed,
+			ports_filtered: self.ports_filtered,
+			probes_sent: None,
+			errors: self.errors,
+			average_rtt: (self.rtt_count > 0).then(|| self.rtt_total / self.rtt_count),
+		}
+	}
+}
+
+impl fmt::Display for Statistics {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "Summary")?;
+		if let Some(duration) = self.duration {
+			writeln!(f, "  {:<12} {:.1?}", "Duration", duration)?;
+		}
+		writeln!(f, "  {:<12} {} up, {} down", "Hosts", self.hosts_up, self.hosts_down)?;
+		writeln!(
+			f,
+			"  {:<12} {} open, {} closed, {} filtered",
+			"Ports", self.ports_open, self.ports_closed, self.ports_filtered
+		)?;
+		if let Some(probes) = self.probes_sent {
+			writeln!(f, "  {:<12} {}", "Probes sent", probes)?;
+		}
+		writeln!(f, "  {:<12} {}", "Errors", self.errors)?;
+		if let Some(rtt) = self.average_rtt {
+			writeln!(f, "  {:<12} {:.3} ms", "Average RTT", rtt.as_secs_f64() * 1000.0)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_from_results() {
+		let results = [
+			ScanResult::new("10.0.0.1", "ping", "up").with_rtt(Duration::from_millis(1)),
+			ScanResult::new("10.0.0.2", "ping", "up").with_rtt(Duration::from_millis(3)),
+			ScanResult::new("10.0.0.3", "ping", "down").with_details("no echo reply (raw socket)"),
+			ScanResult::new("10.0.0.4", "ping", "down"),
+			ScanResult::new("10.0.0.4", "tcp_connect", "open").with_port(22),
+			ScanResult::new("10.0.0.1", "tcp_syn", "closed").with_port(23),
+			ScanResult::new("10.0.0.1", "tcp_syn", "filtered").with_port(25),
+			ScanResult::new("10.0.0.5", "ping", "error").with_details("unresolved: 10.0.0.5 did not resolve to an address"),
+		];
+		let statistics = Statistics::from_results(&results);
+		assert_eq!(
+			statistics,
+			Statistics {
+				duration: None,
+				hosts_up: 3,
+				hosts_down: 1,
+				ports_open: 1,
+				ports_closed: 1,
+				ports_filtered: 1,
+				probes_sent: None,
+				errors: 1,
+				average_rtt: Some(Duration::from_millis(2)),
+			}
+		);
+
+		let text = Statistics { probes_sent: Some(40), ..statistics }.to_string();
+		assert!(text.starts_with("Summary\n  Hosts        3 up, 1 down\n"));
+		assert!(text.contains("  Probes sent  40\n"));
+		assert!(text.ends_with("  Average RTT  2.000 ms\n"));
+
+		let json = serde_json::to_value(&statistics).unwrap();
+		assert_eq!(json["average_rtt_ms"], 2.0);
+		assert!(json.get("duration_ms").is_none());
+		assert_eq!(serde_json::from_value::<Statistics>(json).unwrap(), statistics);
+	}
+}
@@ -0,0 +1,214 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Target expansion for Huginn
+//!
+//! This module turns the configured targets into the individual hosts that are probed: CIDR
+//! blocks are expanded into their addresses, while addresses and host names pass through.
+
+use crate::error::HuginnError;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+/// Largest block that is expanded, as a number of prefix bits left for hosts
+const MAX_HOST_BITS: u8 = 16;
+
+/// An IP network: a CIDR block, or a single address
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Network {
+	/// Network address, with the host bits cleared
+	address: IpAddr,
+	/// Prefix length
+	prefix: u8,
+}
+
+impl Network {
+	/// Total bits in an address of this network's family
+	fn bits(&self) -> u8 {
+		if self.address.is_ipv4() { 32 } else { 128 }
+	}
+
+	/// Mask covering the network bits of an address of this family
+	fn mask(&self) -> u128 {
+		match self.prefix {
+			0 => 0,
+			prefix => (u128::MAX << (self.bits() - prefix)) & (u128::MAX >> (128 - self.bits())),
+		}
+	}
+
+	/// Whether an address falls inside the network
+	pub fn contains(&self, address: IpAddr) -> bool {
+		address.is_ipv4() == self.address.is_ipv4() && to_bits(address) & self.mask() == to_bits(self.address)
+	}
+
+	/// Prefix length
+	pub fn prefix(&self) -> u8 {
+		self.prefix
+	}
+
+	/// Whether another network lies entirely inside this one
+	pub fn covers(&self, other: &Network) -> bool {
+		other.prefix >= self.prefix && self.contains(other.address)
+	}
+
+	/// Whether the network is small enough for [`Network::hosts`] to expand
+	pub fn is_expandable(&self) -> bool {
+		self.bits() - self.prefix <= MAX_HOST_BITS
+	}
+
+	/// Every host address in the network
+	///
+	/// IPv4 networks larger than /31 exclude their network and broadcast addresses.
+	pub fn hosts(&self) -> Result<Vec<IpAddr>, String> {
+		let host_bits = self.bits() - self.prefix;
+		if !self.is_expandable() {
+			return Err(format!(
+				"{} is too large to expand; split it into blocks of at most {} addresses",
+				self,
+				1u32 << MAX_HOST_BITS
+			));
+		}
+		let first = to_bits(self.address);
+		let last = first + ((1u128 << host_bits) - 1);
+		let (first, last) = if self.address.is_ipv4() && host_bits >= 2 { (first + 1, last - 1) } else { (first, last) };
+		Ok((first..=last).map(|bits| from_bits(bits, self.address.is_ipv4())).collect())
+	}
+}
+
+fn to_bits(address: IpAddr) -> u128 {
+	match address {
+		IpAddr::V4(address) => u32::from(address) as u128,
+		IpAddr::V6(address) => u128::from(address),
+	}
+}
+
+fn from_bits(bits: u128, ipv4: bool) -> IpAddr {
+	if ipv4 { IpAddr::V4(Ipv4Addr::from(bits as u32)) } else { IpAddr::V6(Ipv6Addr::from(bits)) }
+}
+
+impl FromStr for Network {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (address, prefix) = match s.split_once('/') {
+			Some((address, prefix)) => (address, Some(prefix)),
+			None => (s, None),
+		};
+		let address: IpAddr = address.parse().map_err(|_| match prefix {
+			Some(_) => format!("{:?} is not a valid CIDR block", s),
+			None => format!("{:?} is not a valid IP address or CIDR block", s),
+		})?;
+		let max = if address.is_ipv4() { 32 } else { 128 };
+		let prefix = match prefix.map(str::parse::<u8>) {
+			None => max,
+			Some(Ok(prefix)) if prefix <= max => prefix,
+			Some(_) => return Err(format!("{:?} has an invalid prefix length; expected 0-{}", s, max)),
+		};
+
+		let mut network = Self { address, prefix };
+		network.address = from_bits(to_bits(address) & network.mask(), address.is_ipv4());
+		Ok(network)
+	}
+}
+
+impl TryFrom<String> for Network {
+	type Error = String;
+
+	fn try_from(s: String) -> Result<Self, Self::Error> {
+		s.parse()
+	}
+}
+
+impl fmt::Display for Network {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}/{}", self.address, self.prefix)
+	}
+}
+
+/// Whether a host falls under a pattern: an address, a CIDR block, or a domain and its subdomains
+pub fn matches(pattern: &str, host: &str) -> bool {
+	match (pattern.parse::<Network>(), host.parse::<IpAddr>()) {
+		(Ok(network), Ok(address)) => network.contains(address),
+		(Err(_), Err(_)) => {
+			let pattern = pattern.trim_end_matches('.').to_ascii_lowercase();
+			let host = host.trim_end_matches('.').to_ascii_lowercase();
+			host == pattern || host.ends_with(&format!(".{}", pattern))
+		},
+		_ => false,
+	}
+}
+
+/// Expand targets into individual hosts, dropping duplicates
+pub fn expand(targets: &[String]) -> Result<Vec<String>, HuginnError> {
+	let mut hosts: Vec<String> = Vec::new();
+	let mut seen = HashSet::new();
+	for target in targets {
+		let expanded = if target.contains('/') {
+			let invalid = |reason| HuginnError::TargetParse { target: target.clone(), reason };
+			let network: Network = target.parse().map_err(invalid)?;
+			network.hosts().map_err(invalid)?.iter().map(ToString::to_string).collect()
+		} else {
+			vec![target.clone()]
+		};
+		for host in expanded {
+			if seen.insert(host.clone()) {
+				hosts.push(host);
+			}
+		}
+	}
+	Ok(hosts)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_network() {
+		let network: Network = "10.1.2.77/24".parse().unwrap();
+		assert_eq!(network.to_string(), "10.1.2.0/24");
+		assert!(network.contains("10.1.2.255".parse().unwrap()));
+		assert!(!network.contains("10.1.3.0".parse().unwrap()));
+		assert!(!network.contains("::ffff:10.1.2.1".parse().unwrap()));
+
+		let network: Network = "2001:db8::/32".parse().unwrap();
+		assert!(network.contains("2001:db8:ffff::1".parse().unwrap()));
+		assert!("0.0.0.0/0".parse::<Network>().unwrap().contains("192.0.2.1".parse().unwrap()));
+		assert_eq!("192.0.2.1".parse::<Network>().unwrap().to_string(), "192.0.2.1/32");
+
+		let block: Network = "203.0.112.0/22".parse().unwrap();
+		assert_eq!(block.prefix(), 22);
+		assert!(block.covers(&"203.0.113.0/24".parse().unwrap()));
+		assert!(block.covers(&block));
+		assert!(!block.covers(&"203.0.112.0/21".parse().unwrap()));
+		assert!(!block.covers(&"2001:db8::/48".parse().unwrap()));
+
+		assert!("10.0.0.0/33".parse::<Network>().is_err());
+		assert!("example.com/24".parse::<Network>().is_err());
+	}
+
+	#[test]
+	fn test_expand() {
+		let targets = vec![
+			"192.0.2.0/30".to_string(),
+			"192.0.2.1".to_string(),
+			"198.51.100.7/32".to_string(),
+			"2001:db8::/127".to_string(),
+			"scanme.example.com".to_string(),
+		];
+		assert_eq!(
+			expand(&targets).unwrap(),
+			vec!["192.0.2.1", "192.0.2.2", "198.51.100.7", "2001:db8::", "2001:db8::1", "scanme.example.com"]
+		);
+
+		assert_eq!(expand(&["10.0.0.0/16".to_string()]).unwrap().len(), 65534);
+		let error = expand(&["10.0.0.0/8".to_string()]).unwrap_err();
+		assert!(matches!(&error, HuginnError::TargetParse { target, .. } if target == "10.0.0.0/8"));
+		assert!(error.to_string().contains("too large"));
+		assert!(expand(&["2001:db8::/64".to_string()]).is_err());
+	}
+}
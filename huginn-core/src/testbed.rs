@@ -0,0 +1,395 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Canned services for integration tests and demos
+//!
+//! A [`Testbed`] serves a small, fixed set of services on one local address: SSH, FTP, and SMTP
+//! greetings with realistic banners, a web site over HTTP and over HTTPS with a bundled
+//! self-signed [`CERTIFICATE`], and a DNS server over UDP that refuses every query. Scans, and
+//! the plugins that identify services, can then be run end to end against localhost, with
+//! known answers and no outside network.
+//!
+//! The web site serves a page at `/`, a `robots.txt`, and an `/admin/` area that asks for
+//! credentials; every other path is not found. Every response carries an nginx `Server` header.
+
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::task::JoinHandle;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+/// Self-signed certificate of the HTTPS service, for `localhost`, `127.0.0.1`, and `::1`, for
+/// clients that should trust it
+pub const CERTIFICATE: &str = include_str!("testbed/testbed.crt");
+
+/// Private key of the certificate; it protects nothing, since it is published here
+const KEY: &str = include_str!("testbed/testbed.key");
+
+/// Longest request head the web site reads
+const MAX_REQUEST: usize = 8192;
+
+/// Web server software the web site claims to be
+const SERVER: &str = "nginx/1.24.0 (Ubuntu)";
+
+/// A service the testbed serves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Service {
+	/// OpenSSH greeting
+	Ssh,
+	/// vsftpd greeting, refusing every command but `QUIT`
+	Ftp,
+	/// Postfix greeting, accepting `EHLO` and `HELO`
+	Smtp,
+	/// Web site over HTTP
+	Http,
+	/// Web site over HTTPS
+	Https,
+	/// DNS server over UDP that refuses every query
+	Dns,
+}
+
+impl Service {
+	/// Every service, in port order
+	pub const ALL: [Service; 6] = [Service::Ftp, Service::Ssh, Service::Smtp, Service::Dns, Service::Http, Service::Https];
+
+	/// Well-known port of the service
+	pub fn port(self) -> u16 {
+		match self {
+			Service::Ftp => 21,
+			Service::Ssh => 22,
+			Service::Smtp => 25,
+			Service::Dns => 53,
+			Service::Http => 80,
+			Service::Https => 443,
+		}
+	}
+
+	/// Whether the service answers over UDP rather than TCP
+	pub fn is_udp(self) -> bool {
+		self == Service::Dns
+	}
+
+	/// Greeting sent as soon as a client connects
+	fn banner(self) -> &'static str {
+		match self {
+			Service::Ssh => "SSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13.5\r\n",
+			Service::Ftp => "220 (vsFTPd 3.0.5)\r\n",
+			Service::Smtp => "220 testbed.localdomain ESMTP Postfix (Ubuntu)\r\n",
+			_ => "",
+		}
+	}
+
+	/// Answer to a command line, and whether the connection stays open after it
+	fn answer(self, line: &str) -> (&'static str, bool) {
+		let command = line.split_whitespace().next().unwrap_or_default().to_ascii_uppercase();
+		match (self, command.as_str()) {
+			(Service::Ftp, "QUIT") => ("221 Goodbye.\r\n", false),
+			(Service::Ftp, _) => ("530 Please login with USER and PASS.\r\n", true),
+			(Service::Smtp, "EHLO" | "HELO") => ("250 testbed.localdomain\r\n", true),
+			(Service::Smtp, "QUIT") => ("221 2.0.0 Bye\r\n", false),
+			(Service::Smtp, _) => ("502 5.5.2 Error: command not recognized\r\n", true),
+			_ => ("", false),
+		}
+	}
+}
+
+impl fmt::Display for Service {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let name = match self {
+			Service::Ssh => "ssh",
+			Service::Ftp => "ftp",
+			Service::Smtp => "smtp",
+			Service::Http => "http",
+			Service::Https => "https",
+			Service::Dns => "dns",
+		};
+		f.write_str(name)
+	}
+}
+
+/// Canned services on one address, until the testbed is dropped
+pub struct Testbed {
+	address: IpAddr,
+	ports: Vec<(Service, u16)>,
+	tasks: Vec<JoinHandle<()>>,
+}
+
+impl Testbed {
+	/// Start every service on `address`, each on `base` plus its well-known port, or on a free
+	/// port when `base` is 0
+	pub async fn start(address: IpAddr, base: u16) -> io::Result<Self> {
+		let acceptor = TlsAcceptor::from(Arc::new(tls_config()?));
+		let mut testbed = Self {
+			address,
+			ports: Vec::with_capacity(Service::ALL.len()),
+			tasks: Vec::with_capacity(Service::ALL.len()),
+		};
+		for service in Service::ALL {
+			let port = match base {
+				0 => 0,
+				base => base.checked_add(service.port()).ok_or_else(|| {
+					io::Error::new(io::ErrorKind::InvalidInput, format!("base port {} leaves no room for {}", base, service))
+				})?,
+			};
+			let bound = |e: io::Error| {
+				let message = format!("cannot serve {} on {}: {}", service, SocketAddr::new(address, port), e);
+				io::Error::new(e.kind(), message)
+			};
+			let (port, task) = if service.is_udp() {
+				let socket = UdpSocket::bind((address, port)).await.map_err(bound)?;
+				(socket.local_addr()?.port(), tokio::spawn(dns(socket)))
+			} else {
+				let listener = TcpListener::bind((address, port)).await.map_err(bound)?;
+				(listener.local_addr()?.port(), tokio::spawn(accept(listener, service, acceptor.clone())))
+			};
+			testbed.ports.push((service, port));
+			testbed.tasks.push(task);
+		}
+		Ok(testbed)
+	}
+
+	/// Address the services listen on
+	pub fn address(&self) -> IpAddr {
+		self.address
+	}
+
+	/// Port a service listens on
+	pub fn port(&self, service: Service) -> u16 {
+		self.ports.iter().find(|(s, _)| *s == service).map_or(0, |&(_, port)| port)
+	}
+
+	/// Every service with the port it listens on
+	pub fn services(&self) -> &[(Service, u16)] {
+		&self.ports
+	}
+}
+
+impl Drop for Testbed {
+	fn drop(&mut self) {
+		for task in &self.tasks {
+			task.abort();
+		}
+	}
+}
+
+/// TLS settings of the HTTPS service, with the bundled certificate
+pub(crate) fn tls_config() -> io::Result<ServerConfig> {
+	let certificate = CertificateDer::from_pem_slice(CERTIFICATE.as_bytes()).map_err(io::Error::other)?;
+	let key = PrivateKeyDer::from_pem_slice(KEY.as_bytes()).map_err(io::Error::other)?;
+	let mut config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+		.with_safe_default_protocol_versions()
+		.map_err(io::Error::other)?
+		.with_no_client_auth()
+		.with_single_cert(vec![certificate], key)
+		.map_err(io::Error::other)?;
+	config.alpn_protocols = vec![b"http/1.1".to_vec()];
+	Ok(config)
+}
+
+/// Accept connections to a TCP service, serving each on its own task
+async fn accept(listener: TcpListener, service: Service, acceptor: TlsAcceptor) {
+	while let Ok((stream, _)) = listener.accept().await {
+		let acceptor = acceptor.clone();
+		tokio::spawn(async move {
+			let _ = serve(stream, service, acceptor).await;
+		});
+	}
+}
+
+/// Serve one connection
+async fn serve(stream: TcpStream, service: Service, acceptor: TlsAcceptor) -> io::Result<()> {
+	match service {
+		Service::Http => http(stream).await,
+		Service::Https => http(acceptor.accept(stream).await?).await,
+		Service::Ssh => {
+			let mut stream = stream;
+			stream.write_all(service.banner().as_bytes()).await?;
+			// Hold the connection until the client gives up, as a server awaiting key exchange would
+			let mut buffer = [0; 1024];
+			while stream.read(&mut buffer).await? > 0 {}
+			Ok(())
+		},
+		_ => {
+			let mut stream = BufReader::new(stream);
+			stream.write_all(service.banner().as_bytes()).await?;
+			let mut line = String::new();
+			while stream.read_line(&mut line).await? > 0 {
+				let (answer, open) = service.answer(&line);
+				stream.write_all(answer.as_bytes()).await?;
+				if !open {
+					break;
+				}
+				line.clear();
+			}
+			Ok(())
+		},
+	}
+}
+
+/// Answer one HTTP request and close the connection
+async fn http(stream: impl AsyncRead + AsyncWrite + Unpin) -> io::Result<()> {
+	let mut stream = BufReader::new(stream);
+	let mut head = String::new();
+	while !head.ends_with("\r\n\r\n") && !head.ends_with("\n\n") {
+		if stream.read_line(&mut head).await? == 0 || head.len() > MAX_REQUEST {
+			return Ok(());
+		}
+	}
+	let path = head.split_whitespace().nth(1).unwrap_or("/");
+	let path = path.split('?').next().unwrap_or(path);
+	let (status, headers, body) = match path {
+		"/" => (
+			"200 OK",
+			"Content-Type: text/html; charset=utf-8\r\n",
+			"<!DOCTYPE html>\n<html><head><title>Huginn testbed</title></head><body><h1>Huginn testbed</h1></body></html>\n",
+		),
+		"/robots.txt" => ("200 OK", "Content-Type: text/plain\r\n", "User-agent: *\nDisallow: /admin/\n"),
+		"/admin" | "/admin/" => (
+			"401 Unauthorized",
+			"Content-Type: text/plain\r\nWWW-Authenticate: Basic realm=\"testbed\"\r\n",
+			"Authentication required\n",
+		),
+		_ => ("404 Not Found", "Content-Type: text/plain\r\n", "Not found\n"),
+	};
+	let response = format!(
+		"HTTP/1.1 {}\r\nServer: {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+		status,
+		SERVER,
+		headers,
+		body.len(),
+		if head.starts_with("HEAD ") { "" } else { body }
+	);
+	stream.write_all(response.as_bytes()).await?;
+	stream.shutdown().await
+}
+
+/// Refuse every DNS query, echoing its question
+async fn dns(socket: UdpSocket) {
+	let mut buffer = [0; 512];
+	while let Ok((length, from)) = socket.recv_from(&mut buffer).await {
+		if let Some(reply) = refusal(&buffer[..length]) {
+			let _ = socket.send_to(&reply, from).await;
+		}
+	}
+}
+
+/// A REFUSED response to a DNS query, or `None` when it is not one
+fn refusal(query: &[u8]) -> Option<Vec<u8>> {
+	if query.len() < 12 || query[2] & 0x80 != 0 || query[4..6] != [0, 1] {
+		return None;
+	}
+	// The question is the name's labels, up to the empty root label, then type and class
+	let mut end = 12;
+	while *query.get(end)? != 0 {
+		end += 1 + usize::from(query[end]);
+	}
+	let end = end + 5;
+	let mut reply = query.get(..end)?.to_vec();
+	reply[2] = 0x80 | (query[2] & 0x79);
+	reply[3] = 0x80 | 5;
+	reply[6..12].fill(0);
+	Some(reply)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tokio_rustls::TlsConnector;
+	use tokio_rustls::rustls::pki_types::ServerName;
+	use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+
+	/// Send a request and read the whole response
+	async fn request(mut stream: impl AsyncRead + AsyncWrite + Unpin, request: &str) -> String {
+		stream.write_all(request.as_bytes()).await.unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).await.unwrap();
+		response
+	}
+
+	#[test]
+	fn test_refusal() {
+		// Query for example.com A with a transaction ID of 0x1234
+		let mut query = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+		query.extend_from_slice(b"\x07example\x03com\x00\x00\x01\x00\x01");
+		let reply = refusal(&query).unwrap();
+		assert_eq!(reply[..4], [0x12, 0x34, 0x81, 0x85]);
+		assert_eq!(reply[12..], query[12..]);
+		assert!(refusal(&reply).is_none());
+		assert!(refusal(&query[..20]).is_none());
+	}
+
+	#[tokio::test]
+	async fn test_testbed() {
+		let testbed = Testbed::start(IpAddr::from([127, 0, 0, 1]), 0).await.unwrap();
+		let address = |service| SocketAddr::new(testbed.address(), testbed.port(service));
+
+		let mut ssh = BufReader::new(TcpStream::connect(address(Service::Ssh)).await.unwrap());
+		let mut banner = String::new();
+		ssh.read_line(&mut banner).await.unwrap();
+		assert!(banner.starts_with("SSH-2.0-OpenSSH_9.6p1"));
+
+		let ftp = TcpStream::connect(address(Service::Ftp)).await.unwrap();
+		assert_eq!(request(ftp, "QUIT\r\n").await, "220 (vsFTPd 3.0.5)\r\n221 Goodbye.\r\n");
+
+		let http = TcpStream::connect(address(Service::Http)).await.unwrap();
+		let response = request(http, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+		assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+		assert!(response.contains("Server: nginx/1.24.0") && response.contains("<title>Huginn testbed</title>"));
+
+		let mut roots = RootCertStore::empty();
+		roots.add(CertificateDer::from_pem_slice(CERTIFICATE.as_bytes()).unwrap()).unwrap();
+		let config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+			.with_safe_default_protocol_versions()
+			.unwrap()
+			.with_root_certificates(roots)
+			.with_no_client_auth();
+		let https = TlsConnector::from(Arc::new(config))
+			.connect(ServerName::try_from("localhost").unwrap(), TcpStream::connect(address(Service::Https)).await.unwrap())
+			.await
+			.unwrap();
+		let response = request(https, "GET /robots.txt HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+		assert!(response.ends_with("Disallow: /admin/\n"), "{}", response);
+
+		let dns = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+		let query = [0xAB, 0xCD, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 1];
+		dns.send_to(&query, address(Service::Dns)).await.unwrap();
+		let mut reply = [0; 512];
+		let length = dns.recv(&mut reply).await.unwrap();
+		assert_eq!(reply[..4], [0xAB, 0xCD, 0x81, 0x85]);
+		assert_eq!(length, query.len());
+	}
+
+	#[cfg(feature = "core-scan")]
+	#[tokio::test]
+	async fn test_scan_testbed() {
+		use crate::ports::PortSpec;
+		use crate::{ScanType, Scanner, plugins};
+
+		let testbed = Testbed::start(IpAddr::from([127, 0, 0, 1]), 0).await.unwrap();
+		let ports: Vec<String> = testbed.services().iter().map(|(_, port)| port.to_string()).collect();
+		let results = Scanner::builder()
+			.targets(["127.0.0.1"])
+			.scan_types([ScanType::TcpConnect, ScanType::Udp])
+			.ports(ports.join(",").parse::<PortSpec>().unwrap())
+			.timeout(std::time::Duration::from_millis(200))
+			.plugins(plugins::builtin())
+			.build()
+			.run()
+			.await
+			.unwrap();
+
+		// Off port 53 the UDP scan sends an empty datagram, which a DNS server leaves unanswered
+		for &(service, port) in testbed.services() {
+			let (scan_type, status) = if service.is_udp() { ("udp", "open|filtered") } else { ("tcp_connect", "open") };
+			let result = results.iter().find(|r| r.scan_type == scan_type && r.port == Some(port));
+			assert_eq!(result.map(|r| r.status.as_str()), Some(status), "{} on {}", service, port);
+		}
+	}
+}
@@ -0,0 +1,211 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Network access for plugins
+//!
+//! Plugins resolve targets, open TCP connections, and send UDP and ICMP datagrams through the
+//! [`Transport`] in their [`ScanContext`], rather than opening sockets themselves. The scanner
+//! uses [`Network`], the host's own network stack, unless another transport is set with
+//! [`ScannerBuilder::transport`](crate::ScannerBuilder::transport). [`MockNetwork`] answers
+//! from hosts described in memory instead, so a plugin's handling of open, closed, filtered,
+//! and lossy ports can be tested deterministically without network access.
+//!
+//! Raw-socket scans build and capture whole packets, which no transport can stand in for, so
+//! they open their raw sockets directly when [`Transport::raw_sockets`] allows it, and fail as
+//! if unprivileged otherwise.
+
+pub mod mock;
+
+pub use mock::MockNetwork;
+
+use crate::error::HuginnError;
+use crate::plugins::ScanContext;
+use async_trait::async_trait;
+use socket2::{Domain, Protocol, SockRef, Socket, Type};
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpSocket, UdpSocket, lookup_host};
+
+/// An open TCP connection
+pub trait Stream: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Stream for T {}
+
+/// A socket that sends and receives datagrams
+#[async_trait]
+pub trait Datagram: Send + Sync {
+	/// Send a datagram, returning the bytes sent
+	async fn send_to(&self, datagram: &[u8], to: SocketAddr) -> io::Result<usize>;
+
+	/// Receive the next datagram into the buffer, returning its length and sender
+	///
+	/// ICMP errors reported for a connected UDP socket are returned as errors, port unreachable
+	/// as [`io::ErrorKind::ConnectionRefused`].
+	async fn recv_from(&self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)>;
+}
+
+#[async_trait]
+impl Datagram for UdpSocket {
+	async fn send_to(&self, datagram: &[u8], to: SocketAddr) -> io::Result<usize> {
+		UdpSocket::send_to(self, datagram, to).await
+	}
+
+	async fn recv_from(&self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+		UdpSocket::recv_from(self, buffer).await
+	}
+}
+
+/// Kind of ICMP socket echo requests are sent over
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SocketMode {
+	/// Raw socket; needs root or CAP_NET_RAW
+	Raw,
+	/// Unprivileged datagram socket; the kernel manages the identifier and checksum
+	Datagram,
+}
+
+impl fmt::Display for SocketMode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			SocketMode::Raw => write!(f, "raw"),
+			SocketMode::Datagram => write!(f, "datagram"),
+		}
+	}
+}
+
+/// How plugins reach the network
+///
+/// Implementations apply the context's interface and source address where they can; host name
+/// resolution, address family checks, proxies, timeouts, and retries are left to the caller.
+#[async_trait]
+pub trait Transport: fmt::Debug + Send + Sync {
+	/// Addresses a host name resolves to; an address resolves to itself
+	async fn resolve(&self, host: &str) -> io::Result<Vec<IpAddr>>;
+
+	/// Open a TCP connection to an address
+	async fn connect(&self, address: SocketAddr, context: &ScanContext) -> io::Result<Box<dyn Stream>>;
+
+	/// Open a UDP socket connected to an address, so only datagrams from it are received and ICMP
+	/// errors about it are reported
+	async fn udp(&self, address: SocketAddr, context: &ScanContext) -> io::Result<Box<dyn Datagram>>;
+
+	/// Open a UDP socket for an address's family that is not connected, so datagrams from any
+	/// port of it are received, as a TFTP server answers from a port of its own; datagrams from
+	/// other hosts are received too, and are left to the caller to ignore
+	async fn udp_unconnected(&self, address: SocketAddr, context: &ScanContext) -> io::Result<Box<dyn Datagram>> {
+		self.udp(address, context).await
+	}
+
+	/// Open an ICMP socket for the address's family, preferring a raw socket; datagrams are sent
+	/// to port 0 of the address
+	async fn icmp(&self, address: IpAddr, context: &ScanContext) -> Result<(Box<dyn Datagram>, SocketMode), HuginnError>;
+
+	/// Open a UDP socket for the address's family that QUIC can take over, bound to the context's
+	/// interface and source address
+	///
+	/// Transports that cannot hand over a real socket return [`io::ErrorKind::Unsupported`], and
+	/// QUIC is not tried over them.
+	fn quic_socket(&self, _address: SocketAddr, _context: &ScanContext) -> io::Result<std::net::UdpSocket> {
+		Err(io::Error::new(io::ErrorKind::Unsupported, "QUIC needs a UDP socket of the host's network stack"))
+	}
+
+	/// Whether raw-socket scans may open raw sockets, which bypass the transport
+	fn raw_sockets(&self) -> bool {
+		true
+	}
+}
+
+/// The host's network stack
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Network;
+
+#[async_trait]
+impl Transport for Network {
+	async fn resolve(&self, host: &str) -> io::Result<Vec<IpAddr>> {
+		Ok(lookup_host((host, 0)).await?.map(|a| a.ip()).collect())
+	}
+
+	async fn connect(&self, address: SocketAddr, context: &ScanContext) -> io::Result<Box<dyn Stream>> {
+		let socket = if address.is_ipv4() { TcpSocket::new_v4()? } else { TcpSocket::new_v6()? };
+		if let Some(source) = context.source_ip {
+			socket.bind(SocketAddr::new(source, 0))?;
+		}
+		if let Some(interface) = &context.interface {
+			bind_device(SockRef::from(&socket), interface)?;
+		}
+		Ok(Box::new(socket.connect(address).await?))
+	}
+
+	async fn udp(&self, address: SocketAddr, context: &ScanContext) -> io::Result<Box<dyn Datagram>> {
+		let socket = udp_socket(address, context)?;
+		socket.connect(address).await?;
+		Ok(Box::new(socket))
+	}
+
+	async fn udp_unconnected(&self, address: SocketAddr, context: &ScanContext) -> io::Result<Box<dyn Datagram>> {
+		Ok(Box::new(udp_socket(address, context)?))
+	}
+
+	async fn icmp(&self, address: IpAddr, context: &ScanContext) -> Result<(Box<dyn Datagram>, SocketMode), HuginnError> {
+		let (domain, protocol) = match address {
+			IpAddr::V4(_) => (Domain::IPV4, Protocol::ICMPV4),
+			IpAddr::V6(_) => (Domain::IPV6, Protocol::ICMPV6),
+		};
+		let (socket, mode) = match Socket::new(domain, Type::RAW, Some(protocol)) {
+			Ok(socket) => (socket, SocketMode::Raw),
+			Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+				let socket = Socket::new(domain, Type::DGRAM, Some(protocol)).map_err(|_| {
+					HuginnError::PermissionDenied(
+						"ICMP needs a raw socket, or on Linux a group in net.ipv4.ping_group_range".to_string(),
+					)
+				})?;
+				(socket, SocketMode::Datagram)
+			},
+			Err(e) => return Err(e.into()),
+		};
+
+		if let Some(source) = context.source_ip {
+			socket.bind(&SocketAddr::new(source, 0).into())?;
+		}
+		if let Some(interface) = &context.interface {
+			bind_device(SockRef::from(&socket), interface)?;
+		}
+		socket.set_nonblocking(true)?;
+		Ok((Box::new(UdpSocket::from_std(std::net::UdpSocket::from(socket))?), mode))
+	}
+
+	fn quic_socket(&self, address: SocketAddr, context: &ScanContext) -> io::Result<std::net::UdpSocket> {
+		udp_socket(address, context)?.into_std()
+	}
+}
+
+/// A UDP socket for an address's family, bound to the context's interface and source address
+fn udp_socket(address: SocketAddr, context: &ScanContext) -> io::Result<UdpSocket> {
+	let domain = if address.is_ipv4() { Domain::IPV4 } else { Domain::IPV6 };
+	let socket = Socket::new(domain, Type::DGRAM, Some(Protocol::UDP))?;
+	if let Some(interface) = &context.interface {
+		bind_device(SockRef::from(&socket), interface)?;
+	}
+	if let Some(source) = context.source_ip {
+		socket.bind(&SocketAddr::new(source, 0).into())?;
+	}
+	socket.set_nonblocking(true)?;
+	UdpSocket::from_std(std::net::UdpSocket::from(socket))
+}
+
+/// Bind a socket to a network interface
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn bind_device(socket: SockRef<'_>, interface: &str) -> io::Result<()> {
+	socket.bind_device(Some(interface.as_bytes()))
+}
+
+/// Bind a socket to a network interface
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub(crate) fn bind_device(_socket: SockRef<'_>, _interface: &str) -> io::Result<()> {
+	Err(io::Error::new(
+		io::ErrorKind::Unsupported,
+		"binding to an interface is only supported on Linux; use a source address instead",
+	))
+}
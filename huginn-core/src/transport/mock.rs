@@ -0,0 +1,363 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! In-memory network for testing plugins
+//!
+//! A [`MockNetwork`] holds hosts described up front: the names that resolve to them, and how
+//! each of their TCP and UDP ports answers. Hosts that were not described are down, and never
+//! answer. Hosts that were answer ICMP echo requests, and refuse TCP connections and UDP
+//! datagrams on every port not described otherwise. Nothing is sent over the network, so tests
+//! of plugin logic run the same everywhere and need no privileges.
+//!
+//! ```
+//! use huginn_core::transport::mock::{MockNetwork, Tcp};
+//! use huginn_core::{ScanType, Scanner};
+//! use std::sync::Arc;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let network = MockNetwork::new()
+//!     .name("db.example.com", [192, 0, 2, 7])
+//!     .tcp_port([192, 0, 2, 7], 5432, Tcp::Open(Vec::new()));
+//! let context = Scanner::builder().transport(Arc::new(network)).context(ScanType::TcpConnect);
+//! assert!(context.connect("db.example.com", 5432).await.is_ok());
+//! assert!(context.connect("db.example.com", 5433).await.is_err());
+//! # }
+//! ```
+
+use crate::error::HuginnError;
+use crate::plugins::ScanContext;
+use crate::transport::{Datagram, SocketMode, Stream, Transport};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+/// Answer to a UDP datagram, or `None` to leave it unanswered
+pub type Responder = Arc<dyn Fn(&[u8]) -> Option<Vec<u8>> + Send + Sync>;
+
+/// How a TCP port answers
+#[derive(Debug, Clone)]
+pub enum Tcp {
+	/// Accepts connections, sends the banner, and reads whatever it is sent
+	Open(Vec<u8>),
+	/// Refuses connections with a reset
+	Closed,
+	/// Never answers, so connections time out
+	Filtered,
+}
+
+/// How a UDP port answers
+#[derive(Clone)]
+pub enum Udp {
+	/// Answers datagrams with the responder
+	Open(Responder),
+	/// Answers with ICMP port unreachable
+	Closed,
+	/// Answers with ICMP host unreachable, as a filtering firewall would
+	Filtered,
+	/// Never answers
+	Silent,
+	/// Fails the receive with an error other than an ICMP unreachable
+	Broken,
+}
+
+impl Udp {
+	/// An open port that answers every datagram with the same reply
+	pub fn reply(reply: &[u8]) -> Self {
+		let reply = reply.to_vec();
+		Udp::Open(Arc::new(move |_| Some(reply.clone())))
+	}
+}
+
+impl fmt::Debug for Udp {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Udp::Open(_) => write!(f, "Open"),
+			Udp::Closed => write!(f, "Closed"),
+			Udp::Filtered => write!(f, "Filtered"),
+			Udp::Silent => write!(f, "Silent"),
+			Udp::Broken => write!(f, "Broken"),
+		}
+	}
+}
+
+/// Ports of a host that is up
+#[derive(Debug, Default, Clone)]
+struct Host {
+	tcp: HashMap<u16, Tcp>,
+	udp: HashMap<u16, Udp>,
+	lost: HashMap<u16, usize>,
+}
+
+/// What a probe was sent over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Protocol {
+	Tcp,
+	Udp,
+}
+
+/// A datagram and its sender, or the ICMP error received instead
+type Reply = io::Result<(Vec<u8>, SocketAddr)>;
+
+/// Probes sent to each port
+type Sent = Arc<Mutex<HashMap<(Protocol, SocketAddr), usize>>>;
+
+/// Hosts described in memory, standing in for the network
+#[derive(Debug, Default)]
+pub struct MockNetwork {
+	names: HashMap<String, Vec<IpAddr>>,
+	hosts: HashMap<IpAddr, Host>,
+	sent: Sent,
+}
+
+impl MockNetwork {
+	/// A network where every host is down
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Resolve a host name to an address, in addition to any it already resolves to
+	pub fn name(mut self, name: &str, address: impl Into<IpAddr>) -> Self {
+		self.names.entry(name.to_string()).or_default().push(address.into());
+		self
+	}
+
+	/// Bring a host up, with every port closed
+	pub fn host(mut self, address: impl Into<IpAddr>) -> Self {
+		self.hosts.entry(address.into()).or_default();
+		self
+	}
+
+	/// Set how a TCP port answers, bringing its host up
+	pub fn tcp_port(mut self, address: impl Into<IpAddr>, port: u16, answer: Tcp) -> Self {
+		self.hosts.entry(address.into()).or_default().tcp.insert(port, answer);
+		self
+	}
+
+	/// Set how a UDP port answers, bringing its host up
+	pub fn udp_port(mut self, address: impl Into<IpAddr>, port: u16, answer: Udp) -> Self {
+		self.hosts.entry(address.into()).or_default().udp.insert(port, answer);
+		self
+	}
+
+	/// Drop the first `count` datagrams sent to a UDP port, as a lossy network would
+	pub fn lose(mut self, address: impl Into<IpAddr>, port: u16, count: usize) -> Self {
+		self.hosts.entry(address.into()).or_default().lost.insert(port, count);
+		self
+	}
+
+	/// TCP connections attempted to an address so far
+	pub fn connections(&self, address: SocketAddr) -> usize {
+		self.sent.lock().unwrap().get(&(Protocol::Tcp, address)).copied().unwrap_or(0)
+	}
+
+	/// UDP datagrams sent to an address so far, including those lost
+	pub fn datagrams(&self, address: SocketAddr) -> usize {
+		self.sent.lock().unwrap().get(&(Protocol::Udp, address)).copied().unwrap_or(0)
+	}
+
+	/// Count a probe, returning how many have now been sent to the address
+	fn count(&self, protocol: Protocol, address: SocketAddr) -> usize {
+		count(&self.sent, protocol, address)
+	}
+}
+
+/// Count a probe, returning how many have now been sent to the address
+fn count(sent: &Sent, protocol: Protocol, address: SocketAddr) -> usize {
+	let mut sent = sent.lock().unwrap();
+	let count = sent.entry((protocol, address)).or_default();
+	*count += 1;
+	*count
+}
+
+#[async_trait]
+impl Transport for MockNetwork {
+	async fn resolve(&self, host: &str) -> io::Result<Vec<IpAddr>> {
+		if let Ok(address) = host.parse::<IpAddr>() {
+			return Ok(vec![address]);
+		}
+		self.names
+			.get(host)
+			.cloned()
+			.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} is not a known host name", host)))
+	}
+
+	async fn connect(&self, address: SocketAddr, _context: &ScanContext) -> io::Result<Box<dyn Stream>> {
+		self.count(Protocol::Tcp, address);
+		let Some(host) = self.hosts.get(&address.ip()) else {
+			return std::future::pending().await;
+		};
+		match host.tcp.get(&address.port()).unwrap_or(&Tcp::Closed) {
+			Tcp::Open(banner) => {
+				let (client, mut server) = tokio::io::duplex(banner.len().max(4096));
+				server.write_all(banner).await?;
+				tokio::spawn(async move {
+					let mut buffer = [0; 4096];
+					while server.read(&mut buffer).await.is_ok_and(|length| length > 0) {}
+				});
+				Ok(Box::new(client))
+			},
+			Tcp::Closed => Err(io::ErrorKind::ConnectionRefused.into()),
+			Tcp::Filtered => std::future::pending().await,
+		}
+	}
+
+	async fn udp(&self, address: SocketAddr, _context: &ScanContext) -> io::Result<Box<dyn Datagram>> {
+		let answer = self.hosts.get(&address.ip()).map(|host| {
+			let udp = host.udp.get(&address.port()).cloned().unwrap_or(Udp::Closed);
+			(udp, host.lost.get(&address.port()).copied().unwrap_or(0))
+		});
+		Ok(Box::new(MockSocket::new(self.sent.clone(), Answer::Udp(address, answer))))
+	}
+
+	async fn icmp(&self, address: IpAddr, _context: &ScanContext) -> Result<(Box<dyn Datagram>, SocketMode), HuginnError> {
+		let up = self.hosts.contains_key(&address);
+		Ok((Box::new(MockSocket::new(self.sent.clone(), Answer::Echo(up))), SocketMode::Datagram))
+	}
+
+	fn raw_sockets(&self) -> bool {
+		false
+	}
+}
+
+/// How the far end of a mock socket answers
+enum Answer {
+	/// A UDP port, and the datagrams it loses; `None` when its host is down
+	Udp(SocketAddr, Option<(Udp, usize)>),
+	/// Echo requests, answered when the host is up
+	Echo(bool),
+}
+
+/// Datagram socket whose replies come from a [`MockNetwork`]
+struct MockSocket {
+	sent: Sent,
+	answer: Answer,
+	replies: UnboundedSender<Reply>,
+	received: tokio::sync::Mutex<UnboundedReceiver<Reply>>,
+}
+
+impl MockSocket {
+	fn new(sent: Sent, answer: Answer) -> Self {
+		let (replies, received) = mpsc::unbounded_channel();
+		Self {
+			sent,
+			answer,
+			replies,
+			received: tokio::sync::Mutex::new(received),
+		}
+	}
+
+	/// The reply to a datagram, if any
+	fn reply(&self, datagram: &[u8], to: SocketAddr) -> Option<io::Result<Vec<u8>>> {
+		match &self.answer {
+			Answer::Udp(address, answer) => {
+				let sent = count(&self.sent, Protocol::Udp, *address);
+				let (udp, lost) = answer.as_ref()?;
+				if sent <= *lost {
+					return None;
+				}
+				match udp {
+					Udp::Open(responder) => responder(datagram).map(Ok),
+					Udp::Closed => Some(Err(io::ErrorKind::ConnectionRefused.into())),
+					Udp::Filtered => Some(Err(io::ErrorKind::HostUnreachable.into())),
+					Udp::Silent => None,
+					Udp::Broken => Some(Err(io::ErrorKind::ConnectionReset.into())),
+				}
+			},
+			Answer::Echo(up) => {
+				let request = if to.is_ipv4() { 8 } else { 128 };
+				if !up || datagram.len() < 8 || datagram[0] != request {
+					return None;
+				}
+				let mut reply = datagram.to_vec();
+				reply[0] = if to.is_ipv4() { 0 } else { 129 };
+				if to.is_ipv4() {
+					// Only the type fell, by 8 in the high byte, so the checksum rises by as much
+					let sum = u32::from(u16::from_be_bytes([reply[2], reply[3]])) + 0x0800;
+					let sum = (sum & 0xFFFF) + (sum >> 16);
+					reply[2..4].copy_from_slice(&(sum as u16).to_be_bytes());
+				}
+				Some(Ok(reply))
+			},
+		}
+	}
+}
+
+#[async_trait]
+impl Datagram for MockSocket {
+	async fn send_to(&self, datagram: &[u8], to: SocketAddr) -> io::Result<usize> {
+		let from = match self.answer {
+			Answer::Udp(address, _) => address,
+			Answer::Echo(_) => to,
+		};
+		if let Some(reply) = self.reply(datagram, to) {
+			let _ = self.replies.send(reply.map(|reply| (reply, from)));
+		}
+		Ok(datagram.len())
+	}
+
+	async fn recv_from(&self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+		let Some(received) = self.received.lock().await.recv().await else {
+			return std::future::pending().await;
+		};
+		let (reply, from) = received?;
+		let length = reply.len().min(buffer.len());
+		buffer[..length].copy_from_slice(&reply[..length]);
+		Ok((length, from))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ScanType;
+	use crate::scanner::Scanner;
+	use std::net::Ipv4Addr;
+
+	#[tokio::test]
+	async fn test_mock_network() {
+		let address = Ipv4Addr::new(192, 0, 2, 1);
+		let network = MockNetwork::new()
+			.name("www.example.com", address)
+			.tcp_port(address, 22, Tcp::Open(b"SSH-2.0-mock\r\n".to_vec()))
+			.udp_port(address, 53, Udp::reply(b"answer"))
+			.lose(address, 53, 1);
+		let context = Scanner::builder().context(ScanType::TcpConnect);
+
+		assert_eq!(network.resolve("www.example.com").await.unwrap(), [IpAddr::from(address)]);
+		assert_eq!(network.resolve("192.0.2.9").await.unwrap(), [IpAddr::from([192, 0, 2, 9])]);
+		assert!(network.resolve("other.example.com").await.is_err());
+
+		let ssh = SocketAddr::from((address, 22));
+		let mut stream = network.connect(ssh, &context).await.unwrap();
+		let mut banner = [0; 14];
+		stream.read_exact(&mut banner).await.unwrap();
+		assert_eq!(&banner, b"SSH-2.0-mock\r\n");
+		stream.write_all(b"SSH-2.0-client\r\n").await.unwrap();
+		let refused = network.connect(SocketAddr::from((address, 23)), &context).await.err().unwrap();
+		assert_eq!(refused.kind(), io::ErrorKind::ConnectionRefused);
+		let down = network.connect(SocketAddr::from(([192, 0, 2, 2], 22)), &context);
+		assert!(tokio::time::timeout(std::time::Duration::from_millis(10), down).await.is_err());
+		assert_eq!(network.connections(ssh), 1);
+
+		// The first datagram is lost, and the second answered
+		let dns = SocketAddr::from((address, 53));
+		let socket = network.udp(dns, &context).await.unwrap();
+		let mut buffer = [0; 16];
+		socket.send_to(b"query", dns).await.unwrap();
+		socket.send_to(b"query", dns).await.unwrap();
+		assert_eq!(socket.recv_from(&mut buffer).await.unwrap(), (6, dns));
+		assert_eq!(&buffer[..6], b"answer");
+		assert_eq!(network.datagrams(dns), 2);
+
+		let closed = SocketAddr::from((address, 54));
+		let socket = network.udp(closed, &context).await.unwrap();
+		socket.send_to(b"query", closed).await.unwrap();
+		assert_eq!(socket.recv_from(&mut buffer).await.unwrap_err().kind(), io::ErrorKind::ConnectionRefused);
+	}
+}
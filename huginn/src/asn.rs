@@ -0,0 +1,289 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! ASN and BGP prefix targets for Huginn
+//!
+//! A target such as `asn:64500` expands into every prefix that autonomous system announces, and
+//! `prefix:203.0.112.0/22` into the announced prefixes within that block, so an external scan
+//! covers an organization's routed address space and nothing else. Both need their prefix, so a
+//! host named like an AS, such as `as400`, is scanned as the host it is. Announcements too large
+//! to expand are skipped with a warning. Announcements are read from
+//! the BGP table in `rib_file`: `bgpdump -m` output of an MRT RIB dump, or a bgp.tools table as
+//! `PREFIX ASN` lines or JSON lines. Without one, the table at `rib_url` is downloaded into
+//! `db_dir` and reused for a day.
+
+use crate::config::Config;
+use huginn_core::targets::Network;
+use log::{info, warn};
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+
+/// File the downloaded BGP table is cached in, inside `db_dir`
+#[cfg(feature = "intel-enrichment")]
+const RIB_FILE: &str = "bgp-table.txt";
+
+/// How long a downloaded BGP table is reused before it is fetched again
+#[cfg(feature = "intel-enrichment")]
+const RIB_MAX_AGE: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+/// A target that selects announced prefixes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selector {
+	/// Every prefix an autonomous system originates
+	Asn(u32),
+	/// The announced prefixes within a block
+	Prefix(Network),
+}
+
+impl Selector {
+	/// The selector a target names, or `None` for an address, block, or host name
+	pub fn parse(target: &str) -> Option<Result<Self, String>> {
+		if let Some(block) = target.strip_prefix("prefix:") {
+			return Some(block.parse().map(Selector::Prefix));
+		}
+		let asn = target.strip_prefix("asn:")?;
+		// The AS number may be written as in AS64500
+		let digits = asn.get(..2).filter(|head| head.eq_ignore_ascii_case("as")).map_or(asn, |_| &asn[2..]);
+		Some(digits.parse().map(Selector::Asn).map_err(|_| format!("{:?} is not a valid AS number", asn)))
+	}
+
+	/// The part of an announced prefix this selector selects
+	fn select(&self, network: &Network, origin: u32) -> Option<Network> {
+		match self {
+			Selector::Asn(asn) => (*asn == origin).then_some(*network),
+			Selector::Prefix(block) if block.covers(network) => Some(*network),
+			// A block inside a larger announcement is routed as a whole
+			Selector::Prefix(block) => network.covers(block).then_some(*block),
+		}
+	}
+}
+
+impl fmt::Display for Selector {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Selector::Asn(asn) => write!(f, "asn:{}", asn),
+			Selector::Prefix(block) => write!(f, "prefix:{}", block),
+		}
+	}
+}
+
+/// The prefix and origin AS of a route in a BGP table, or `None` for a line that is not one
+fn route(line: &str) -> Option<(Network, u32)> {
+	let line = line.trim();
+	if line.starts_with('{') {
+		let route: Value = serde_json::from_str(line).ok()?;
+		return Some((route["CIDR"].as_str()?.parse().ok()?, u32::try_from(route["ASN"].as_u64()?).ok()?));
+	}
+	if line.contains('|') {
+		// bgpdump -m: TYPE|TIME|B|PEER_IP|PEER_AS|PREFIX|AS_PATH|...; routes originated by an AS
+		// set have no single origin and are skipped
+		let fields: Vec<&str> = line.split('|').collect();
+		let origin = fields.get(6)?.split_whitespace().last()?;
+		return Some((fields.get(5)?.parse().ok()?, origin.parse().ok()?));
+	}
+	let mut fields = line.split_whitespace();
+	Some((fields.next()?.parse().ok()?, fields.next()?.parse().ok()?))
+}
+
+/// The prefixes each selector selects in a BGP table, without those another of them covers
+fn select(table: &str, selectors: &[Selector]) -> Vec<Vec<Network>> {
+	let mut selected = vec![Vec::new(); selectors.len()];
+	for (network, origin) in table.lines().filter_map(route) {
+		for (selector, networks) in selectors.iter().zip(&mut selected) {
+			if let Some(network) = selector.select(&network, origin) {
+				networks.push(network);
+			}
+		}
+	}
+	for networks in &mut selected {
+		networks.sort_by_key(Network::prefix);
+		let mut kept: Vec<Network> = Vec::new();
+		for network in networks.drain(..) {
+			if !kept.iter().any(|k| k.covers(&network)) {
+				kept.push(network);
+			}
+		}
+		*networks = kept;
+	}
+	selected
+}
+
+/// Download the BGP table into `db_dir`, unless the cached copy is recent
+#[cfg(feature = "intel-enrichment")]
+async fn download(config: &Config, offline: bool) -> Result<String, Box<dyn Error>> {
+	let path = config.db_dir.join(RIB_FILE);
+	let fresh = std::fs::metadata(&path)
+		.and_then(|metadata| metadata.modified())
+		.is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age < RIB_MAX_AGE));
+	if offline && !path.exists() {
+		return Err(format!(
+			"No BGP table is cached in {}; set rib_file to expand ASN and prefix targets offline",
+			config.db_dir.display()
+		)
+		.into());
+	}
+	if !fresh && !offline {
+		info!("Downloading the BGP table from {}", config.rib_url);
+		// bgp.tools refuses clients that do not identify themselves
+		let client = reqwest::Client::builder().user_agent(concat!("huginn/", env!("CARGO_PKG_VERSION"))).build()?;
+		match crate::db::fetch(&client, &config.rib_url).await {
+			Ok(table) => {
+				std::fs::create_dir_all(&config.db_dir)?;
+				std::fs::write(&path, table)?;
+			},
+			Err(e) if path.exists() => warn!("Failed to refresh the BGP table; using the cached copy: {}", e),
+			Err(e) => return Err(format!("Failed to download the BGP table from {}: {}", config.rib_url, e).into()),
+		}
+	}
+	Ok(std::fs::read_to_string(&path)?)
+}
+
+/// The BGP table is only downloaded in builds with intel-enrichment
+#[cfg(not(feature = "intel-enrichment"))]
+async fn download(_config: &Config, _offline: bool) -> Result<String, Box<dyn Error>> {
+	Err("ASN and prefix targets need rib_file in builds without the intel-enrichment feature".into())
+}
+
+/// The configured targets, with ASN and prefix targets replaced by the prefixes they select
+///
+/// When `offline`, the BGP table is never downloaded: a cached copy is used however old it is.
+pub async fn expand(config: &Config, offline: bool) -> Result<Vec<String>, Box<dyn Error>> {
+	let mut selectors = Vec::new();
+	for target in &config.targets {
+		if let Some(selector) = Selector::parse(target) {
+			selectors.push(selector.map_err(|e| format!("Invalid target {}: {}", target, e))?);
+		}
+	}
+	if selectors.is_empty() {
+		return Ok(config.targets.clone());
+	}
+
+	let table = match &config.rib_file {
+		Some(path) => std::fs::read_to_string(path)
+			.map_err(|e| format!("Failed to read BGP table {}: {}", path.display(), e))?,
+		None => download(config, offline).await?,
+	};
+	let mut selected = selectors.iter().zip(select(&table, &selectors));
+	let mut targets = Vec::new();
+	for target in &config.targets {
+		match Selector::parse(target) {
+			Some(_) => {
+				let Some((selector, networks)) = selected.next() else {
+					continue;
+				};
+				let (expandable, oversized): (Vec<&Network>, Vec<&Network>) =
+					networks.iter().partition(|network| network.is_expandable());
+				for network in &oversized {
+					warn!("Skipping {} from {}: it is too large to expand; add its blocks as targets instead", network, selector);
+				}
+				if networks.is_empty() {
+					warn!("{} selects no announced prefixes", selector);
+				} else {
+					info!("Expanded {} into {} announced prefixes", selector, expandable.len());
+				}
+				targets.extend(expandable.iter().map(ToString::to_string));
+			},
+			None => targets.push(target.clone()),
+		}
+	}
+	Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const TABLE: &str = "\
+203.0.112.0/22 64500
+203.0.113.0/24 64500
+198.51.100.0/24 64501
+{\"CIDR\":\"2001:db8::/32\",\"ASN\":64500,\"Hits\":310}
+TABLE_DUMP2|1700000000|B|192.0.2.1|64496|192.0.2.0/24|64496 64511 64500|IGP|192.0.2.1|0|0||NAG||
+TABLE_DUMP2|1700000000|B|192.0.2.1|64496|100.64.0.0/24|64496 {64502,64503}|IGP|192.0.2.1|0|0||NAG||
+not a route
+";
+
+	fn network(text: &str) -> Network {
+		text.parse().unwrap()
+	}
+
+	#[test]
+	fn test_selector_parse() {
+		assert_eq!(Selector::parse("asn:64500"), Some(Ok(Selector::Asn(64500))));
+		assert_eq!(Selector::parse("asn:AS64500"), Some(Ok(Selector::Asn(64500))));
+		assert_eq!(Selector::parse("asn:as64500"), Some(Ok(Selector::Asn(64500))));
+		// Hosts named like an AS are hosts
+		assert_eq!(Selector::parse("AS64500"), None);
+		assert_eq!(Selector::parse("as400"), None);
+		assert_eq!(Selector::parse("prefix:203.0.113.7/22"), Some(Ok(Selector::Prefix(network("203.0.112.0/22")))));
+		assert!(Selector::parse("asn:AS99999999999").unwrap().is_err());
+		assert!(Selector::parse("asn:").unwrap().is_err());
+		assert!(Selector::parse("prefix:example.com").unwrap().is_err());
+		assert_eq!(Selector::parse("asgard.example.com"), None);
+		assert_eq!(Selector::Asn(64500).to_string(), "asn:64500");
+		assert_eq!(Selector::parse("10.0.0.0/24"), None);
+		assert_eq!(Selector::Prefix(network("203.0.112.0/22")).to_string(), "prefix:203.0.112.0/22");
+	}
+
+	#[test]
+	fn test_route() {
+		let routes: Vec<_> = TABLE.lines().filter_map(route).map(|(n, asn)| (n.to_string(), asn)).collect();
+		assert_eq!(
+			routes,
+			[
+				("203.0.112.0/22".to_string(), 64500),
+				("203.0.113.0/24".to_string(), 64500),
+				("198.51.100.0/24".to_string(), 64501),
+				("2001:db8::/32".to_string(), 64500),
+				("192.0.2.0/24".to_string(), 64500),
+			]
+		);
+	}
+
+	#[test]
+	fn test_select() {
+		let selectors = [
+			Selector::Asn(64500),
+			Selector::Prefix(network("198.51.100.128/25")),
+			Selector::Prefix(network("203.0.0.0/16")),
+			Selector::Asn(64510),
+		];
+		let selected = select(TABLE, &selectors);
+		let names: Vec<Vec<String>> =
+			selected.iter().map(|networks| networks.iter().map(ToString::to_string).collect()).collect();
+		assert_eq!(
+			names,
+			[
+				vec!["203.0.112.0/22", "192.0.2.0/24", "2001:db8::/32"],
+				vec!["198.51.100.128/25"],
+				vec!["203.0.112.0/22"],
+				vec![],
+			]
+		);
+	}
+
+	#[tokio::test]
+	async fn test_expand() {
+		let rib = std::env::temp_dir().join(format!("huginn-rib-{}.txt", std::process::id()));
+		std::fs::write(&rib, "198.51.100.0/30 64501\n2001:db8::/32 64501\n").unwrap();
+		let config = Config {
+			targets: vec!["10.0.0.1".to_string(), "asn:64501".to_string(), "scanme.example.com".to_string()],
+			rib_file: Some(rib.clone()),
+			..Config::default()
+		};
+		assert_eq!(expand(&config, false).await.unwrap(), ["10.0.0.1", "198.51.100.0/30", "scanme.example.com"]);
+		std::fs::remove_file(&rib).unwrap();
+		assert!(expand(&config, false).await.unwrap_err().to_string().contains("Failed to read BGP table"));
+
+		// Offline, a missing table is an error rather than a download
+		let db_dir = std::env::temp_dir().join(format!("huginn-rib-db-{}", std::process::id()));
+		let config = Config {
+			rib_file: None,
+			db_dir: db_dir.clone(),
+			..config
+		};
+		assert!(expand(&config, true).await.is_err());
+		assert!(!db_dir.exists());
+	}
+}
@@ -0,0 +1,52 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Audit log for Huginn
+//!
+//! Guardrail decisions, such as refusing an out-of-scope target, are always logged and are
+//! appended as JSON lines to the configured audit log so they can be reviewed after a run.
+
+use crate::config::Config;
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::Serialize;
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+/// An audit log entry
+#[derive(Debug, Serialize)]
+pub struct Event<'a> {
+	/// When the decision was made
+	pub timestamp: DateTime<Utc>,
+	/// Kind of decision, e.g. `scope_refused`
+	pub event: &'a str,
+	/// Target the decision applies to
+	pub target: &'a str,
+	/// Why the decision was made
+	pub reason: &'a str,
+}
+
+/// Record an audit event
+///
+/// Fails when the audit log cannot be written, so a guardrail never acts unrecorded.
+pub fn record(config: &Config, event: &str, target: &str, reason: &str) -> Result<(), Box<dyn Error>> {
+	let entry = Event {
+		timestamp: Utc::now(),
+		event,
+		target,
+		reason,
+	};
+	let line = serde_json::to_string(&entry)?;
+	info!(target: "huginn::audit", event, host = target, reason; "{}", line);
+
+	if let Some(path) = &config.audit_log {
+		let mut file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(path)
+			.map_err(|e| format!("Failed to open audit log {}: {}", path.display(), e))?;
+		writeln!(file, "{}", line).map_err(|e| format!("Failed to write audit log {}: {}", path.display(), e))?;
+	}
+	Ok(())
+}
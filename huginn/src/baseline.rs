@@ -0,0 +1,85 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Baseline comparison for Huginn
+//!
+//! This module loads a previous run's JSON results and computes drift against the current run.
+//! Two results describe the same observation when their target, scan type, port, and status
+//! match. Failed scans are not observations, so they never count as drift.
+
+use huginn_core::formatters::json;
+use huginn_core::plugins::ScanResult;
+use std::error::Error;
+use std::path::Path;
+
+/// Differences between a baseline and the current results
+#[derive(Debug, Default)]
+pub struct Drift {
+	/// Observations present now but not in the baseline
+	pub added: Vec<ScanResult>,
+	/// Observations in the baseline that are no longer present
+	pub removed: Vec<ScanResult>,
+}
+
+impl Drift {
+	/// Whether anything changed
+	pub fn is_empty(&self) -> bool {
+		self.added.is_empty() && self.removed.is_empty()
+	}
+}
+
+/// Load a baseline from a JSON results file
+pub fn load(path: &Path) -> Result<Vec<ScanResult>, Box<dyn Error>> {
+	let content = std::fs::read_to_string(path)
+		.map_err(|e| format!("Failed to read baseline {}: {}", path.display(), e))?;
+	Ok(json::parse(&content)?)
+}
+
+/// Whether two results describe the same observation
+fn same(a: &ScanResult, b: &ScanResult) -> bool {
+	a.target == b.target && a.scan_type == b.scan_type && a.port == b.port && a.status == b.status
+}
+
+/// Compute the drift from a baseline to the current results
+pub fn diff(baseline: &[ScanResult], current: &[ScanResult]) -> Drift {
+	Drift {
+		added: current
+			.iter()
+			.filter(|c| !c.is_error() && !baseline.iter().any(|b| same(b, c)))
+			.cloned()
+			.collect(),
+		removed: baseline
+			.iter()
+			.filter(|b| !b.is_error() && !current.iter().any(|c| same(b, c)))
+			.cloned()
+			.collect(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_diff() {
+		let baseline = vec![
+			ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22),
+			ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(80),
+		];
+		let current = vec![
+			ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22),
+			ScanResult::new("10.0.0.1", "tcp_connect", "closed").with_port(80),
+			ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(443),
+		];
+		let drift = diff(&baseline, &current);
+
+		assert_eq!(drift.added.len(), 2);
+		assert_eq!(drift.removed.len(), 1);
+		assert_eq!(drift.removed[0].port, Some(80));
+		assert!(diff(&current, &current).is_empty());
+
+		let failed = [ScanResult::new("10.0.0.2", "tcp_connect", "error").with_details("unresolved: no address")];
+		assert!(diff(&failed, &[]).is_empty());
+		assert!(diff(&[], &failed).is_empty());
+	}
+}
@@ -0,0 +1,204 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Throughput benchmark against a mock target farm
+//!
+//! `huginn bench`, hidden from the help, starts a [`Farm`] of loopback hosts with known open
+//! ports and scans it with each requested scan type, using the configuration's timing and
+//! concurrency settings, reporting the median time and throughput of several scans. The
+//! measurements can be saved, and later runs compared against them to fail on a throughput
+//! regression, so the effect of a change to the engine or its settings can be measured. The
+//! criterion suite in `huginn-core/benches` times the same scans with fixed settings.
+
+use crate::config::{BenchArgs, Config};
+use huginn_core::farm::Farm;
+use huginn_core::plugins::{self, ScanType};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::time::Duration;
+
+/// Throughput of the scans of one scan type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Measurement {
+	/// Scan type timed
+	pub scan_type: ScanType,
+	/// Hosts scanned
+	pub hosts: usize,
+	/// Ports scanned on each host
+	pub ports: usize,
+	/// Median time of a scan, in milliseconds
+	pub median_ms: f64,
+	/// Ports scanned per second, across every host
+	pub ports_per_second: f64,
+	/// Probes sent per second
+	pub probes_per_second: f64,
+}
+
+/// `count` ports spread evenly over the scanned ports
+fn spread(ports: &[u16], count: usize) -> Vec<u16> {
+	let step = ports.len() / count.max(1);
+	ports.iter().copied().step_by(step.max(1)).take(count).collect()
+}
+
+/// The middle value, or the mean of the two middle values
+fn median(mut values: Vec<f64>) -> f64 {
+	values.sort_by(f64::total_cmp);
+	let middle = values.len() / 2;
+	if values.len().is_multiple_of(2) { (values[middle - 1] + values[middle]) / 2.0 } else { values[middle] }
+}
+
+/// Time scans of the farm with one scan type
+async fn measure(config: &Config, args: &BenchArgs, farm: &Farm, scan_type: ScanType) -> Result<Measurement, Box<dyn Error>> {
+	let scanner = config
+		.scanner()
+		.targets(farm.targets().iter().cloned())
+		.scan_types([scan_type])
+		.ports(args.ports.clone())
+		.plugins(plugins::builtin())
+		.progress(false)
+		.build();
+	let expected = farm.targets().len() * farm.open().len();
+
+	let mut durations = Vec::with_capacity(args.iterations);
+	let mut probes = Vec::with_capacity(args.iterations);
+	for iteration in 1..=args.iterations {
+		let (results, statistics) = scanner.run_with_statistics().await?;
+		if let Some(failed) = results.iter().find(|result| result.is_error()) {
+			return Err(format!("The {} scan failed: {}", scan_type, failed.details.as_deref().unwrap_or("no details")).into());
+		}
+		let found = results.iter().filter(|result| result.port.is_some() && result.status == "open").count();
+		if found != expected {
+			warn!("The {} scan found {} of the {} open ports", scan_type, found, expected);
+		}
+		let duration = statistics.duration.unwrap_or(Duration::ZERO).as_secs_f64();
+		info!("{} scan {} of {}: {:.3}s", scan_type, iteration, args.iterations, duration);
+		durations.push(duration);
+		probes.push(statistics.probes_sent.unwrap_or(0) as f64);
+	}
+
+	let seconds = median(durations).max(f64::EPSILON);
+	let ports = args.ports.ports().len();
+	Ok(Measurement {
+		scan_type,
+		hosts: farm.targets().len(),
+		ports,
+		median_ms: seconds * 1000.0,
+		ports_per_second: (farm.targets().len() * ports) as f64 / seconds,
+		probes_per_second: median(probes) / seconds,
+	})
+}
+
+/// Describe each scan type whose throughput fell by more than `tolerance` percent from the saved
+/// measurements; scan types measured only once are not compared
+pub fn regressions(saved: &[Measurement], current: &[Measurement], tolerance: f64) -> Vec<String> {
+	current
+		.iter()
+		.filter_map(|now| {
+			let before = saved.iter().find(|before| before.scan_type == now.scan_type)?;
+			let drop = (1.0 - now.ports_per_second / before.ports_per_second) * 100.0;
+			(drop > tolerance).then(|| {
+				format!(
+					"{} throughput fell {:.1}% ({:.0} to {:.0} ports/s)",
+					now.scan_type, drop, before.ports_per_second, now.ports_per_second
+				)
+			})
+		})
+		.collect()
+}
+
+/// Time scans of a mock target farm and report, save, or compare their throughput
+pub async fn run(config: &Config, args: &BenchArgs) -> Result<(), Box<dyn Error>> {
+	let ports = args.ports.ports();
+	if args.open > ports.len() {
+		return Err(format!("Cannot open {} of the {} scanned ports", args.open, ports.len()).into());
+	}
+	if args.iterations == 0 {
+		return Err("At least one iteration is needed".into());
+	}
+	let saved: Option<Vec<Measurement>> = match &args.compare {
+		Some(path) => Some(
+			serde_json::from_str(&std::fs::read_to_string(path)?)
+				.map_err(|e| format!("Failed to read measurements from {}: {}", path.display(), e))?,
+		),
+		None => None,
+	};
+
+	let farm = Farm::start(args.hosts, &spread(ports, args.open))
+		.await
+		.map_err(|e| format!("Failed to start the target farm: {}", e))?;
+	info!("Started {} hosts with {} open ports each", farm.targets().len(), farm.open().len());
+
+	let mut measurements = Vec::with_capacity(args.scan_types.len());
+	for &scan_type in &args.scan_types {
+		measurements.push(measure(config, args, &farm, scan_type).await?);
+	}
+	drop(farm);
+
+	println!("{:<12} {:>6} {:>7} {:>10} {:>12} {:>12}", "Scan type", "Hosts", "Ports", "Median", "Ports/s", "Probes/s");
+	for m in &measurements {
+		println!(
+			"{:<12} {:>6} {:>7} {:>9.1}ms {:>12.0} {:>12.0}",
+			m.scan_type.to_string(),
+			m.hosts,
+			m.ports,
+			m.median_ms,
+			m.ports_per_second,
+			m.probes_per_second
+		);
+	}
+
+	if let Some(path) = &args.save {
+		std::fs::write(path, serde_json::to_string_pretty(&measurements)?)?;
+		info!("Measurements saved to {}", path.display());
+	}
+	if let Some(saved) = saved {
+		let regressions = regressions(&saved, &measurements, args.max_regression);
+		if !regressions.is_empty() {
+			return Err(regressions.join("; ").into());
+		}
+		info!("No throughput regression beyond {}%", args.max_regression);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn measurement(scan_type: ScanType, ports_per_second: f64) -> Measurement {
+		Measurement {
+			scan_type,
+			hosts: 4,
+			ports: 1000,
+			median_ms: 4000.0 / ports_per_second * 1000.0,
+			ports_per_second,
+			probes_per_second: ports_per_second,
+		}
+	}
+
+	#[test]
+	fn test_regressions() {
+		let saved = [measurement(ScanType::TcpConnect, 10000.0), measurement(ScanType::TcpSyn, 50000.0)];
+		let current = [
+			measurement(ScanType::TcpConnect, 8000.0),
+			measurement(ScanType::TcpSyn, 48000.0),
+			measurement(ScanType::Udp, 100.0),
+		];
+		assert_eq!(
+			regressions(&saved, &current, 10.0),
+			["tcp_connect throughput fell 20.0% (10000 to 8000 ports/s)"]
+		);
+		assert!(regressions(&saved, &current, 25.0).is_empty());
+	}
+
+	#[test]
+	fn test_spread_and_median() {
+		let ports: Vec<u16> = (1000..2000).collect();
+		assert_eq!(spread(&ports, 4), [1000, 1250, 1500, 1750]);
+		assert!(spread(&ports, 0).is_empty());
+		assert_eq!(spread(&ports[..3], 3), [1000, 1001, 1002]);
+		assert_eq!(median(vec![3.0, 1.0, 2.0]), 2.0);
+		assert_eq!(median(vec![4.0, 1.0, 2.0, 3.0]), 2.5);
+	}
+}
@@ -0,0 +1,132 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Plugin catalog
+//!
+//! Describes the plugins available to this build for `huginn plugins list` and `huginn plugins
+//! info`: the built-in plugins with their scan types, required privileges, and the settings they
+//! read, and any packages installed from the plugin index. The built-in plugins are those of
+//! huginn-core and the ones this executable adds, such as subdomain enumeration and
+//! mail posture grading.
+
+use crate::config::Config;
+use crate::privileges;
+use huginn_core::plugins::{self, Plugin, ScanType};
+use std::error::Error;
+
+/// Privileges a scan type needs on this host
+fn privileges(scan_type: ScanType) -> &'static str {
+	if scan_type == ScanType::Passive {
+		"none to read capture_file; raw sockets (root or CAP_NET_RAW; Npcap on Windows) to listen on an interface"
+	} else if privileges::needs_raw_sockets(scan_type) {
+		"raw sockets (root or CAP_NET_RAW; Npcap on Windows)"
+	} else if scan_type == ScanType::Ping {
+		"none; unprivileged ICMP datagram sockets are allowed"
+	} else {
+		"none"
+	}
+}
+
+/// Create every built-in plugin enabled in this build, with the settings it takes from the
+/// configuration
+#[cfg_attr(not(any(feature = "web-checks", feature = "subdomains", feature = "dns-checks")), allow(unused_variables))]
+pub fn builtin(config: &Config) -> Vec<Box<dyn Plugin>> {
+	#[cfg_attr(not(any(feature = "web-checks", feature = "subdomains", feature = "dns-checks")), allow(unused_mut))]
+	let mut builtin = plugins::builtin();
+	#[cfg(feature = "web-checks")]
+	builtin.push(Box::new(plugins::default_creds::DefaultCredsPlugin::new(config.default_creds.clone())));
+	#[cfg(feature = "web-checks")]
+	builtin.push(Box::new(plugins::ssh_audit_auth::SshAuditAuthPlugin::new(config.ssh_audit_auth.clone())));
+	#[cfg(feature = "web-checks")]
+	builtin.push(Box::new(plugins::web_screenshot::WebScreenshotPlugin::new(config.web_screenshot.clone())));
+	#[cfg(feature = "web-checks")]
+	builtin.push(Box::new(plugins::content_discovery::ContentDiscoveryPlugin::new(config.content_discovery.clone())));
+	#[cfg(feature = "subdomains")]
+	builtin.push(Box::new(crate::subdomains::SubdomainsPlugin::new(config.subdomains.clone()).admit(crate::admit(config))));
+	#[cfg(feature = "dns-checks")]
+	builtin.push(Box::new(crate::mail_posture::MailPosturePlugin::new(config.mail_posture.clone())));
+	#[cfg(feature = "dns-checks")]
+	builtin.push(Box::new(crate::dnssec::DnssecPlugin));
+	#[cfg(feature = "dns-checks")]
+	builtin.push(Box::new(crate::dns_hygiene::DnsHygienePlugin::from_config(config)));
+	builtin
+}
+
+/// Find a built-in plugin by scan type or name, ignoring case
+fn find<'a>(plugins: &'a [Box<dyn Plugin>], name: &str) -> Option<&'a dyn Plugin> {
+	plugins
+		.iter()
+		.find(|plugin| plugin.scan_type().to_string().eq_ignore_ascii_case(name) || plugin.name().eq_ignore_ascii_case(name))
+		.map(|plugin| plugin.as_ref())
+}
+
+/// Print every available plugin
+pub fn list(config: &Config) -> Result<(), Box<dyn Error>> {
+	let builtin = builtin(config);
+	if builtin.is_empty() {
+		println!("No built-in plugins; this build does not include the core-scan feature");
+	}
+	for plugin in &builtin {
+		let access = if privileges::needs_raw_access(config, plugin.scan_type()) { "raw sockets" } else { "unprivileged" };
+		println!("{:<12} {:<20} built-in  {}", plugin.scan_type().to_string(), plugin.name(), access);
+	}
+
+	#[cfg(feature = "plugin-index")]
+	for package in crate::marketplace::installed(config)? {
+		let pin = if package.pinned { " (pinned)" } else { "" };
+		println!("{:<12} {:<20} {}  installed{}", package.name, package.version, package.kind, pin);
+	}
+	Ok(())
+}
+
+/// Print the details of one plugin
+pub fn info(config: &Config, name: &str) -> Result<(), Box<dyn Error>> {
+	let builtin = builtin(config);
+	if let Some(plugin) = find(&builtin, name) {
+		let settings = match plugin.settings() {
+			[] => "none".to_string(),
+			settings => settings.join(", "),
+		};
+		println!("{}", plugin.name());
+		println!("  Scan type:   {}", plugin.scan_type());
+		println!("  Kind:        built-in");
+		println!("  Description: {}", plugin.description());
+		println!("  Privileges:  {}", privileges(plugin.scan_type()));
+		println!("  Proxy:       {}", if plugin.proxy_capable() { "supported" } else { "skipped when a proxy is set" });
+		println!("  Settings:    {}", settings);
+		return Ok(());
+	}
+
+	#[cfg(feature = "plugin-index")]
+	if let Some(package) = crate::marketplace::installed(config)?.into_iter().find(|p| p.name == name) {
+		println!("{}", package.name);
+		println!("  Version:     {}{}", package.version, if package.pinned { " (pinned)" } else { "" });
+		println!("  Kind:        {} package in {}", package.kind, config.plugins_dir);
+		println!("  SHA-256:     {}", package.sha256);
+		return Ok(());
+	}
+
+	Err(format!("No plugin named {:?}; run `huginn plugins list` to see the available plugins", name).into())
+}
+
+#[cfg(all(test, feature = "core-scan"))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_find() {
+		let plugins = builtin(&Config::default());
+		assert_eq!(find(&plugins, "tcp_connect").unwrap().scan_type(), ScanType::TcpConnect);
+		assert_eq!(find(&plugins, "ping scanner").unwrap().scan_type(), ScanType::Ping);
+		assert!(find(&plugins, "telnet").is_none());
+
+		let config = Config {
+			#[cfg(feature = "plugin-index")]
+			plugins_dir: std::env::temp_dir().join("huginn-no-plugins").display().to_string(),
+			..Config::default()
+		};
+		assert!(info(&config, "udp").is_ok());
+		assert!(info(&config, "telnet").is_err());
+		assert!(list(&config).is_ok());
+	}
+}
@@ -3,25 +3,867 @@
 
 //! Configuration management for Huginn
 //!
-//! This module handles loading and parsing configuration from files and environment variables.
+//! This module handles parsing the command line and loading configuration from files and
+//! environment variables.
 
-use config::{Config as ConfigBuilder, ConfigError, Environment, File};
+mod interpolate;
+pub mod validate;
+
+#[cfg(feature = "email")]
+use crate::email::EmailConfig;
+#[cfg(feature = "integrations")]
+use crate::integrations::IntegrationConfig;
+#[cfg(feature = "dns-checks")]
+use crate::dns_hygiene::DnsHygieneConfig;
+#[cfg(feature = "dns-checks")]
+use crate::mail_posture::MailPostureConfig;
+#[cfg(feature = "notifications")]
+use crate::notifications::NotificationConfig;
+#[cfg(feature = "intel-enrichment")]
+use crate::reputation::ReputationConfig;
+#[cfg(feature = "subdomains")]
+use crate::subdomains::SubdomainsConfig;
+#[cfg(feature = "encryption")]
+use crate::output::encrypt::Recipient;
+use crate::credentials::CredentialConfig;
+use crate::gate::FailOn;
+use crate::import::Format;
+use crate::logging::{ByteSize, LogFormat, LogLevel, Rotation};
+use crate::merge::Conflict;
+use crate::scope::Scope;
+use crate::security::MaskingConfig;
+use crate::sinks::StreamConfig;
+use chrono::NaiveDate;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use config::builder::{ConfigBuilder as SourceBuilder, DefaultState};
+use config::{Config as ConfigBuilder, ConfigError, Environment, File, Source, Value, ValueKind};
+#[cfg(feature = "core-scan")]
+use huginn_core::discovery;
+#[cfg(feature = "web-checks")]
+use huginn_core::plugins::content_discovery::ContentDiscoveryConfig;
+#[cfg(feature = "web-checks")]
+use huginn_core::plugins::default_creds::DefaultCredsConfig;
+#[cfg(feature = "web-checks")]
+use huginn_core::plugins::ssh_audit_auth::SshAuditConfig;
+#[cfg(feature = "web-checks")]
+use huginn_core::plugins::web_screenshot::WebScreenshotConfig;
+use huginn_core::plugins::{RawOptions, ScanType, Severity, TimingOverride};
+use huginn_core::ports::PortSpec;
+use huginn_core::proxy::Proxy;
+use huginn_core::scanner::{Scanner, ScannerBuilder};
+use interpolate::Interpolated;
+use log::{debug, info, warn};
 use serde::Deserialize;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Command-line interface for Huginn
+#[derive(Debug, Parser)]
+#[command(name = "huginn", version, about)]
+pub struct Cli {
+	/// Configuration file path; overrides the standard search locations
+	#[arg(short, long, global = true, value_name = "FILE")]
+	pub config: Option<PathBuf>,
+
+	/// Increase log verbosity (-v for debug, -vv for trace, -vvv for trace from every crate)
+	#[arg(short, long, global = true, action = clap::ArgAction::Count)]
+	pub verbose: u8,
+
+	/// Only print results: hide the progress bar and log nothing below warnings
+	#[arg(short, long, global = true, conflicts_with = "verbose")]
+	pub quiet: bool,
+
+	/// Operation to perform; defaults to running a scan
+	#[command(subcommand)]
+	pub command: Option<Command>,
+}
+
+/// Top-level subcommands
+#[derive(Debug, Subcommand)]
+#[allow(clippy::large_enum_variant)] // parsed once at startup
+pub enum Command {
+	/// Perform scanning operations
+	Scan(ScanArgs),
+	/// Create or check configuration files
+	Config {
+		#[command(subcommand)]
+		command: ConfigCommand,
+	},
+	/// Scan on a fixed interval and serve Prometheus metrics
+	#[cfg(feature = "daemon")]
+	Daemon(DaemonArgs),
+	/// Manage the do-not-scan list
+	Exclude {
+		#[command(subcommand)]
+		command: ExcludeCommand,
+	},
+	/// Describe the available plugins, and manage plugins from the configured plugin index
+	Plugins {
+		#[command(subcommand)]
+		command: PluginsCommand,
+	},
+	/// Render saved results again with any format and filters, without rescanning
+	Report(ReportArgs),
+	/// Combine result sets from several files or runs into one deduplicated set
+	Merge(MergeArgs),
+	/// Convert results from another scanner, storing them as a run when `history_dir` is set
+	Import(ImportArgs),
+	/// Scan on an interval and print only what changed since the previous scan
+	Watch(WatchArgs),
+	/// Print a shell completion script (bash, zsh, fish, powershell, or elvish)
+	Completions {
+		/// Shell to complete for
+		shell: Shell,
+	},
+	/// Print the manual page in roff format
+	Man,
+	/// Browse the results and logs of stored runs
+	History {
+		#[command(subcommand)]
+		command: HistoryCommand,
+	},
+	/// Download and inspect the cached vulnerability, fingerprint, and OUI datasets
+	#[cfg(feature = "intel-enrichment")]
+	Db {
+		#[command(subcommand)]
+		command: DbCommand,
+	},
+	/// Check a report against its detached signature
+	#[cfg(feature = "signing")]
+	Verify {
+		/// Report file to check
+		file: PathBuf,
+		/// Signature file; defaults to FILE.sig
+		#[arg(long, value_name = "FILE")]
+		signature: Option<PathBuf>,
+		/// Trusted hex-encoded Ed25519 public key; may be repeated, and replaces `report_keys`
+		#[arg(long = "key", value_name = "HEX")]
+		keys: Vec<String>,
+	},
+	/// Time scans of a local mock target farm, to measure throughput and catch regressions
+	#[cfg(feature = "bench")]
+	#[command(hide = true)]
+	Bench(BenchArgs),
+	/// Serve canned SSH, FTP, SMTP, HTTP, HTTPS, and DNS services locally for tests and demos
+	#[cfg(feature = "testbed")]
+	#[command(hide = true)]
+	Testbed {
+		/// Address the services listen on
+		#[arg(long, value_name = "ADDR", default_value = "127.0.0.1")]
+		address: IpAddr,
+		/// Each service listens on this plus its well-known port, such as 10022 for SSH; 0 picks
+		/// free ports
+		#[arg(long, value_name = "PORT", default_value_t = 10000)]
+		base_port: u16,
+	},
+}
+
+/// Options for the `scan` subcommand; these override the configuration file
+#[derive(Debug, Default, Args)]
+pub struct ScanArgs {
+	/// Apply the named profile from the configuration file
+	#[arg(short, long, value_name = "NAME")]
+	pub profile: Option<String>,
+	/// Output sink as PATH[:FORMAT], where PATH `-` is stdout; may be repeated
+	#[arg(short, long = "output", value_name = "SPEC")]
+	pub outputs: Vec<String>,
+	/// Default result format (e.g. json, text, cyclonedx, dot, hosts)
+	#[arg(short, long, value_name = "FORMAT")]
+	pub format: Option<String>,
+	/// Render results through a Tera template file instead of a built-in format
+	#[arg(long, value_name = "FILE")]
+	pub template: Option<PathBuf>,
+	/// Keep results in a temporary file instead of memory while scanning, for very large scans
+	#[arg(long)]
+	pub spill: bool,
+	/// Encrypt written reports to an age recipient (age1...); may be repeated
+	#[cfg(feature = "encryption")]
+	#[arg(long, value_name = "RECIPIENT")]
+	pub encrypt_output: Vec<Recipient>,
+	/// Previous JSON results to compare this run against
+	#[arg(long, value_name = "FILE")]
+	pub baseline: Option<PathBuf>,
+	/// Exit with status 3 when results meet a condition (a severity, open-port, or drift); may be repeated
+	#[arg(long, value_name = "CONDITION")]
+	pub fail_on: Vec<FailOn>,
+	/// Time to wait for a response to each probe (e.g. 500ms, 3s)
+	#[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+	pub timeout: Option<Duration>,
+	/// Extra attempts after a probe times out
+	#[arg(long, value_name = "COUNT")]
+	pub retries: Option<u32>,
+	/// Pause before each probe (e.g. 100ms)
+	#[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+	pub scan_delay: Option<Duration>,
+	/// Factor the wait for a reply grows by with each UDP retransmission
+	#[arg(long, value_name = "FACTOR")]
+	pub backoff: Option<f64>,
+	/// Time that passive scans listen for traffic (e.g. 5m)
+	#[cfg(feature = "passive")]
+	#[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+	pub capture_duration: Option<Duration>,
+	/// Saved pcap capture that passive scans read instead of listening on the interface
+	#[cfg(feature = "passive")]
+	#[arg(long, value_name = "FILE")]
+	pub capture_file: Option<PathBuf>,
+	/// Network interface to send probes from (Linux only)
+	#[arg(long, value_name = "NAME")]
+	pub interface: Option<String>,
+	/// Source address to send probes from
+	#[arg(long, value_name = "ADDR")]
+	pub source_ip: Option<IpAddr>,
+	/// Source port of raw-socket probes (e.g. 53)
+	#[arg(long, value_name = "PORT")]
+	pub source_port: Option<u16>,
+	/// Decoy addresses that raw-socket probes are also sent from (comma-separated)
+	#[arg(long, value_name = "ADDR,...", value_delimiter = ',')]
+	pub decoys: Vec<Ipv4Addr>,
+	/// Time to live of raw-socket probes
+	#[arg(long, value_name = "HOPS")]
+	pub ttl: Option<u8>,
+	/// Random bytes appended to raw-socket probes
+	#[arg(long, value_name = "BYTES")]
+	pub data_length: Option<usize>,
+	/// Check which hosts are up first, and skip the port scans of hosts that are down
+	#[cfg(feature = "core-scan")]
+	#[arg(long)]
+	pub discovery: bool,
+	/// Host discovery methods, comma-separated (echo, syn, arp, ack, udp, timestamp, netmask); implies --discovery
+	#[cfg(feature = "core-scan")]
+	#[arg(long, value_name = "METHOD,...", value_delimiter = ',', conflicts_with = "no_discovery")]
+	pub discovery_methods: Vec<discovery::Method>,
+	/// Skip host discovery and port scan every host (-Pn)
+	#[cfg(feature = "core-scan")]
+	#[arg(short = 'P', value_name = "n", value_parser = ["n"], conflicts_with = "discovery")]
+	pub no_discovery: Option<String>,
+	/// Zombie host that idle scans are spoofed from (HOST[:PORT], port 80 by default)
+	#[arg(long, value_name = "HOST[:PORT]")]
+	pub zombie: Option<String>,
+	/// Domain that open proxy and open resolver checks ask targets to reach (example.com by default)
+	#[arg(long, value_name = "DOMAIN")]
+	pub canary: Option<String>,
+	/// Sweep every host with stateless SYN probes in one pass, reporting only open ports
+	#[cfg(feature = "core-scan")]
+	#[arg(long)]
+	pub stateless: bool,
+	/// Split raw-socket probes into 8-byte IP fragments (same as --mtu 8)
+	#[arg(long)]
+	pub fragment: bool,
+	/// Split raw-socket probes into IP fragments carrying this many bytes each (a multiple of 8)
+	#[arg(long, value_name = "BYTES")]
+	pub mtu: Option<usize>,
+	/// Tunnel TCP probes through a proxy (socks5://[USER:PASS@]HOST:PORT or http://...)
+	#[arg(long, value_name = "URL")]
+	pub proxy: Option<Proxy>,
+	/// Most TCP connections held open at once; defaults to what the file descriptor limit allows
+	#[arg(long, value_name = "COUNT")]
+	pub max_connections: Option<usize>,
+	/// Raise the file descriptor limit to the hard limit before scanning
+	#[arg(long)]
+	pub raise_fd_limit: bool,
+	/// Scan targets outside the configured scope instead of refusing them (audited)
+	#[arg(long)]
+	pub force_out_of_scope: bool,
+	/// Abort instead of warning when a target fails reverse DNS verification
+	#[arg(long)]
+	pub strict_scope: bool,
+	/// Print the probe plan without sending anything
+	#[arg(long)]
+	pub dry_run: bool,
+	/// Hide the progress bar
+	#[arg(long)]
+	pub no_progress: bool,
+}
+
+impl ScanArgs {
+	/// Apply command-line overrides to the loaded configuration
+	pub fn apply(&self, config: &mut Config) {
+		if !self.outputs.is_empty() {
+			config.outputs = self.outputs.clone();
+		}
+		if let Some(format) = &self.format {
+			config.output_format = format.clone();
+		}
+		if let Some(template) = &self.template {
+			config.template = Some(template.clone());
+		}
+		if self.spill {
+			config.spill = true;
+		}
+		#[cfg(feature = "encryption")]
+		if !self.encrypt_output.is_empty() {
+			config.encrypt_output = self.encrypt_output.clone();
+		}
+		if let Some(baseline) = &self.baseline {
+			config.baseline = Some(baseline.clone());
+		}
+		if !self.fail_on.is_empty() {
+			config.fail_on = self.fail_on.clone();
+		}
+		if let Some(timeout) = self.timeout {
+			config.timeout = timeout;
+		}
+		if let Some(retries) = self.retries {
+			config.retries = retries;
+		}
+		if let Some(scan_delay) = self.scan_delay {
+			config.scan_delay = scan_delay;
+		}
+		if let Some(backoff) = self.backoff {
+			config.backoff = backoff;
+		}
+		#[cfg(feature = "passive")]
+		if let Some(capture_duration) = self.capture_duration {
+			config.capture_duration = capture_duration;
+		}
+		#[cfg(feature = "passive")]
+		if let Some(capture_file) = &self.capture_file {
+			config.capture_file = Some(capture_file.clone());
+		}
+		if let Some(interface) = &self.interface {
+			config.interface = Some(interface.clone());
+		}
+		if let Some(source_ip) = self.source_ip {
+			config.source_ip = Some(source_ip);
+		}
+		if let Some(source_port) = self.source_port {
+			config.source_port = Some(source_port);
+		}
+		if !self.decoys.is_empty() {
+			config.decoys = self.decoys.clone();
+		}
+		if let Some(ttl) = self.ttl {
+			config.ttl = Some(ttl);
+		}
+		if let Some(data_length) = self.data_length {
+			config.data_length = data_length;
+		}
+		#[cfg(feature = "core-scan")]
+		if self.discovery {
+			config.discovery = true;
+		}
+		#[cfg(feature = "core-scan")]
+		if !self.discovery_methods.is_empty() {
+			config.discovery = true;
+			config.discovery_methods = self.discovery_methods.clone();
+		}
+		#[cfg(feature = "core-scan")]
+		if self.no_discovery.is_some() {
+			config.discovery = false;
+		}
+		if let Some(zombie) = &self.zombie {
+			config.zombie = Some(zombie.clone());
+		}
+		if let Some(canary) = &self.canary {
+			config.canary = Some(canary.clone());
+		}
+		#[cfg(feature = "core-scan")]
+		if self.stateless {
+			config.stateless = true;
+		}
+		if self.fragment {
+			config.mtu = Some(8);
+		}
+		if let Some(mtu) = self.mtu {
+			config.mtu = Some(mtu);
+		}
+		if let Some(proxy) = &self.proxy {
+			config.proxy = Some(proxy.clone());
+		}
+		if let Some(max_connections) = self.max_connections {
+			config.max_connections = Some(max_connections);
+		}
+		if self.raise_fd_limit {
+			config.raise_fd_limit = true;
+		}
+		if self.force_out_of_scope {
+			config.force_out_of_scope = true;
+		}
+		if self.strict_scope {
+			config.strict_scope = true;
+		}
+		if self.no_progress {
+			config.no_progress = true;
+		}
+	}
+}
+
+/// Output options of the subcommands that write saved results
+#[derive(Debug, Args)]
+pub struct OutputArgs {
+	/// Output sink as PATH[:FORMAT], where PATH `-` is stdout; may be repeated
+	#[arg(short, long = "output", value_name = "SPEC")]
+	pub outputs: Vec<String>,
+	/// Result format (e.g. json, text, cyclonedx, dot, hosts)
+	#[arg(short, long, value_name = "FORMAT")]
+	pub format: Option<String>,
+	/// Render results through a Tera template file instead of a built-in format
+	#[arg(long, value_name = "FILE")]
+	pub template: Option<PathBuf>,
+}
+
+impl OutputArgs {
+	/// Apply command-line overrides to the loaded configuration
+	pub fn apply(&self, config: &mut Config) {
+		if !self.outputs.is_empty() {
+			config.outputs = self.outputs.clone();
+		}
+		if let Some(format) = &self.format {
+			config.output_format = format.clone();
+		}
+		if let Some(template) = &self.template {
+			config.template = Some(template.clone());
+		}
+	}
+}
+
+/// Options for the `report` subcommand
+#[derive(Debug, Args)]
+pub struct ReportArgs {
+	/// Results to render: a JSON or NDJSON results file, or a run ID from `huginn history list`
+	pub source: String,
+	/// Where and how to write the results
+	#[command(flatten)]
+	pub output: OutputArgs,
+	/// Only include results for this target; may be repeated
+	#[arg(long = "target", value_name = "HOST")]
+	pub targets: Vec<String>,
+	/// Only include results of this scan type; may be repeated
+	#[arg(long = "scan-type", value_name = "TYPE")]
+	pub scan_types: Vec<String>,
+	/// Only include results with this status (e.g. open, up); may be repeated
+	#[arg(long = "status", value_name = "STATUS")]
+	pub statuses: Vec<String>,
+	/// Only include findings at or above this severity
+	#[arg(long, value_name = "SEVERITY")]
+	pub min_severity: Option<Severity>,
+}
+
+/// Options for the `merge` subcommand
+#[derive(Debug, Args)]
+pub struct MergeArgs {
+	/// Results to combine, in order: JSON or NDJSON results files, or run IDs from `huginn history list`
+	#[arg(required = true, num_args = 2.., value_name = "SOURCE")]
+	pub sources: Vec<String>,
+	/// How to resolve differing statuses for the same target, scan type, and port: last (later
+	/// sources win), first, open (open or up wins), or all (keep each status)
+	#[arg(long, value_name = "RULE", default_value_t = Conflict::Last)]
+	pub on_conflict: Conflict,
+	/// Where and how to write the merged results
+	#[command(flatten)]
+	pub output: OutputArgs,
+}
+
+/// Options for the `import` subcommand
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+	/// File written by the other scanner
+	pub file: PathBuf,
+	/// Format of the file (nmap-xml, masscan-json, or masscan-list)
+	#[arg(long, value_name = "FORMAT")]
+	pub format: Format,
+	/// Scan the open ports found on each imported host with the configured scan types
+	#[arg(long)]
+	pub scan: bool,
+	/// Output sink as PATH[:FORMAT], where PATH `-` is stdout; may be repeated
+	#[arg(short, long = "output", value_name = "SPEC")]
+	pub outputs: Vec<String>,
+	/// Render results through a Tera template file instead of a built-in format
+	#[arg(long, value_name = "FILE")]
+	pub template: Option<PathBuf>,
+}
+
+impl ImportArgs {
+	/// Apply command-line overrides to the loaded configuration
+	pub fn apply(&self, config: &mut Config) {
+		if !self.outputs.is_empty() {
+			config.outputs = self.outputs.clone();
+		}
+		if let Some(template) = &self.template {
+			config.template = Some(template.clone());
+		}
+	}
+}
+
+/// Options for the `watch` subcommand; these override the configuration file
+#[derive(Debug, Args)]
+pub struct WatchArgs {
+	/// Time between scans (e.g. 5m, 1h)
+	#[arg(long, value_name = "DURATION", default_value = "5m", value_parser = humantime::parse_duration)]
+	pub interval: Duration,
+	/// Options applied to every scan
+	#[command(flatten)]
+	pub scan: ScanArgs,
+}
+
+/// Options for the `daemon` subcommand; these override the configuration file
+#[cfg(feature = "daemon")]
+#[derive(Debug, Default, Args)]
+pub struct DaemonArgs {
+	/// Time between scans (e.g. 30m, 6h)
+	#[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+	pub interval: Option<Duration>,
+	/// Options applied to every scheduled scan
+	#[command(flatten)]
+	pub scan: ScanArgs,
+}
+
+#[cfg(feature = "daemon")]
+impl DaemonArgs {
+	/// Apply command-line overrides to the loaded configuration
+	pub fn apply(&self, config: &mut Config) {
+		self.scan.apply(config);
+		if let Some(interval) = self.interval {
+			config.daemon_interval = interval;
+		}
+	}
+}
+
+/// Options for the hidden `bench` subcommand
+#[cfg(feature = "bench")]
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+	/// Scan type to time; may be repeated
+	#[arg(long = "scan-type", value_name = "TYPE", default_value = "tcp_connect")]
+	pub scan_types: Vec<ScanType>,
+	/// Hosts in the mock target farm, on 127.0.0.1 onward
+	#[arg(long, value_name = "COUNT", default_value_t = 4)]
+	pub hosts: usize,
+	/// Ports scanned on each host
+	#[arg(long, value_name = "PORTS", default_value = "31000-31999")]
+	pub ports: PortSpec,
+	/// Ports open on each host, spread evenly over the scanned ports
+	#[arg(long, value_name = "COUNT", default_value_t = 10)]
+	pub open: usize,
+	/// Scans timed for each scan type; the median is reported
+	#[arg(long, value_name = "COUNT", default_value_t = 3)]
+	pub iterations: usize,
+	/// Save the measurements as JSON
+	#[arg(long, value_name = "FILE")]
+	pub save: Option<PathBuf>,
+	/// Measurements saved by an earlier run to compare against, failing on a regression
+	#[arg(long, value_name = "FILE")]
+	pub compare: Option<PathBuf>,
+	/// Largest drop in ports per second, in percent, that --compare tolerates
+	#[arg(long, value_name = "PERCENT", default_value_t = 10.0)]
+	pub max_regression: f64,
+	/// Settings applied to every timed scan
+	#[command(flatten)]
+	pub scan: ScanArgs,
+}
+
+/// Configuration file subcommands
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+	/// Write a commented example configuration file
+	Init {
+		/// Destination file; `-` writes to stdout
+		#[arg(default_value = "config.toml")]
+		path: PathBuf,
+		/// Overwrite an existing file
+		#[arg(long)]
+		force: bool,
+	},
+	/// Check a configuration file without running a scan
+	Validate {
+		/// File to check; defaults to the file a scan would load
+		path: Option<PathBuf>,
+	},
+}
+
+/// Plugin subcommands
+#[derive(Debug, Subcommand)]
+pub enum PluginsCommand {
+	/// List every available plugin with its scan type and required privileges
+	List,
+	/// Describe a plugin: what it checks, the privileges it needs, and the settings it reads
+	Info {
+		/// Plugin name or scan type
+		name: String,
+	},
+	#[cfg(feature = "plugin-index")]
+	#[command(flatten)]
+	Index(PluginIndexCommand),
+}
+
+/// Plugin index subcommands
+#[cfg(feature = "plugin-index")]
+#[derive(Debug, Subcommand)]
+pub enum PluginIndexCommand {
+	/// Search the plugin index
+	Search {
+		/// Text to match against plugin names and descriptions
+		query: Option<String>,
+	},
+	/// Install a plugin, pinning it when a version is given
+	Install {
+		/// Plugin name
+		name: String,
+		/// Exact version to install and pin
+		#[arg(long)]
+		version: Option<String>,
+	},
+	/// Update installed, unpinned plugins to their latest versions
+	Update {
+		/// Only update this plugin
+		name: Option<String>,
+	},
+}
+
+/// Do-not-scan list subcommands
+#[derive(Debug, Subcommand)]
+pub enum ExcludeCommand {
+	/// Exclude a target from every subsequent scan
+	Add {
+		/// Address, CIDR block, or domain to exclude
+		target: String,
+		/// Last day the exclusion applies (YYYY-MM-DD); it never expires when omitted
+		#[arg(long, value_name = "DATE")]
+		until: Option<NaiveDate>,
+		/// Why the target is excluded
+		#[arg(long)]
+		reason: Option<String>,
+	},
+	/// List the active exclusions
+	List,
+	/// Remove every exclusion for a target
+	Remove {
+		/// Target as it was added
+		target: String,
+	},
+}
+
+/// Run history subcommands
+#[derive(Debug, Subcommand)]
+pub enum HistoryCommand {
+	/// List the stored runs, oldest first
+	List,
+	/// Print a stored run's summary and results
+	Show {
+		/// Run ID, as listed by `huginn history list`
+		id: String,
+		/// Print the log captured during the run instead
+		#[arg(long)]
+		logs: bool,
+	},
+}
+
+/// Dataset cache subcommands
+#[cfg(feature = "intel-enrichment")]
+#[derive(Debug, Subcommand)]
+pub enum DbCommand {
+	/// Download every dataset in the manifest at `db_url` that is missing or out of date
+	Update,
+	/// Show the age of each cached dataset and whether it is intact
+	Status,
+}
 
 /// Main configuration structure for Huginn
+///
+/// Unknown keys are rejected so that misspelled settings fail loudly.
 #[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
 	/// API key for authentication
 	pub api_key: Option<String>,
 	/// Enable debug mode
 	pub debug_mode: bool,
-	/// Server port number
+	/// Port the daemon serves metrics on
+	#[cfg_attr(not(feature = "daemon"), allow(dead_code))]
 	pub port: u16,
 	/// Target hosts to scan
 	pub targets: Vec<String>,
+	/// BGP table that ASN and prefix targets are expanded from
+	pub rib_file: Option<PathBuf>,
+	/// URL of the BGP table downloaded when `rib_file` is unset (https:// or file://)
+	#[cfg(feature = "intel-enrichment")]
+	pub rib_url: String,
+	/// Networks and domains authorized for scanning; targets outside it are refused when set
+	pub scope: Option<Scope>,
+	/// Scan out-of-scope targets instead of refusing them; set only by `--force-out-of-scope`
+	#[serde(skip)]
+	pub force_out_of_scope: bool,
+	/// Domain suffixes that the PTR names of address targets must match; checked when set
+	pub ptr_suffixes: Vec<String>,
+	/// Abort instead of warning when a target fails reverse DNS verification
+	pub strict_scope: bool,
+	/// Hide the progress bar; set only by `--no-progress` or `--quiet`
+	#[serde(skip)]
+	pub no_progress: bool,
+	/// File that guardrail decisions are appended to as JSON lines
+	pub audit_log: Option<PathBuf>,
+	/// Patterns masked in log messages
+	pub masking: MaskingConfig,
+	/// Log thresholds in RUST_LOG syntax (e.g. `info,huginn_core::plugins::tcp_syn=trace`); raised by -v
+	pub log_level: LogLevel,
+	/// How log records are written (text or json)
+	pub log_format: LogFormat,
+	/// File that logs are written to instead of the console
+	pub log_file: Option<PathBuf>,
+	/// When the log file is rotated (hourly, daily, or never)
+	pub log_rotation: Rotation,
+	/// Size at which the log file is rotated (e.g. 10MiB)
+	pub log_max_size: Option<ByteSize>,
+	/// Rotated log files kept before the oldest is deleted
+	pub log_keep: usize,
+	/// Do-not-scan list maintained with `huginn exclude`
+	pub exclusions_file: PathBuf,
+	/// Directory each run's results and captured log are stored in for `huginn history`
+	pub history_dir: Option<PathBuf>,
 	/// Enabled scan types
-	pub scan_types: Vec<String>,
+	pub scan_types: Vec<ScanType>,
+	/// Ports probed by port-scanning plugins (e.g. 22,80,8000-8100); plugin defaults apply when unset
+	pub ports: Option<PortSpec>,
+	/// Time to wait for a response to each probe (e.g. 500ms, 3s)
+	#[serde(with = "humantime_serde")]
+	pub timeout: Duration,
+	/// Extra attempts after a probe times out
+	pub retries: u32,
+	/// Pause before each probe (e.g. 100ms)
+	#[serde(with = "humantime_serde")]
+	pub scan_delay: Duration,
+	/// Factor the wait for a reply grows by with each retransmission (UDP scans)
+	pub backoff: f64,
+	/// Timing overrides for individual scan types
+	pub plugin_timing: HashMap<ScanType, TimingOverride>,
+	/// Time that passive scans listen for traffic
+	#[cfg(feature = "passive")]
+	#[serde(with = "humantime_serde")]
+	pub capture_duration: Duration,
+	/// Saved pcap capture that passive scans read instead of listening on the interface
+	#[cfg(feature = "passive")]
+	pub capture_file: Option<PathBuf>,
+	/// Network interface to send probes from (Linux only)
+	pub interface: Option<String>,
+	/// Source address to send probes from
+	pub source_ip: Option<IpAddr>,
+	/// Source port of raw-socket probes; random when unset
+	pub source_port: Option<u16>,
+	/// Decoy addresses that raw-socket probes are also sent from
+	pub decoys: Vec<Ipv4Addr>,
+	/// Time to live of raw-socket probes; 64 when unset
+	pub ttl: Option<u8>,
+	/// Random bytes appended to raw-socket probes
+	pub data_length: usize,
+	/// Bytes of each IP fragment that raw-socket probes are split into; unfragmented when unset
+	pub mtu: Option<usize>,
+	/// Zombie host that idle scans are spoofed from, as `HOST[:PORT]`
+	pub zombie: Option<String>,
+	/// Domain that open proxy and open resolver checks ask targets to reach; example.com when unset
+	pub canary: Option<String>,
+	/// Named credentials, with where each secret is read from
+	pub credentials: HashMap<String, CredentialConfig>,
+	/// Credentials that authenticated checks may log in with, by name; usually set per profile
+	pub use_credentials: Vec<String>,
+	/// Check which hosts are up before port scanning, and skip the port scans of hosts that are down
+	#[cfg(feature = "core-scan")]
+	pub discovery: bool,
+	/// Probes host discovery tries: echo, syn, arp, ack, udp, timestamp, and netmask
+	#[cfg(feature = "core-scan")]
+	pub discovery_methods: Vec<discovery::Method>,
+	/// Run TCP SYN scans as one stateless sweep of every host, reporting only open ports
+	#[cfg(feature = "core-scan")]
+	pub stateless: bool,
+	/// Sources, wordlist, and certificate-transparency service of subdomain enumeration
+	#[cfg(feature = "subdomains")]
+	pub subdomains: SubdomainsConfig,
+	/// DKIM selectors looked up by the email security posture grading
+	#[cfg(feature = "dns-checks")]
+	pub mail_posture: MailPostureConfig,
+	/// Takeover fingerprints of the DNS hygiene checks
+	#[cfg(feature = "dns-checks")]
+	pub dns_hygiene: DnsHygieneConfig,
+	/// Authorization and attempt limit of the default credential checks
+	#[cfg(feature = "web-checks")]
+	pub default_creds: DefaultCredsConfig,
+	/// SSH client and host key checking of the authenticated SSH audit
+	#[cfg(feature = "web-checks")]
+	pub ssh_audit_auth: SshAuditConfig,
+	/// Capture tool and output directory of the web screenshots
+	#[cfg(feature = "web-checks")]
+	pub web_screenshot: WebScreenshotConfig,
+	/// Wordlists and request rate of content and virtual host discovery
+	#[cfg(feature = "web-checks")]
+	pub content_discovery: ContentDiscoveryConfig,
+	/// Proxy that TCP probes are tunneled through; scan types that cannot use it are skipped
+	pub proxy: Option<Proxy>,
+	/// Most TCP connections held open at once; as many as the file descriptor limit allows when unset
+	pub max_connections: Option<usize>,
+	/// Raise the soft file descriptor limit to the hard limit before scanning (Unix only)
+	pub raise_fd_limit: bool,
+	/// Unprivileged user to switch to after startup when run as root (Unix only)
+	pub user: Option<String>,
+	/// Output format identifier (e.g. json, text, cyclonedx, dot, hosts)
+	pub output_format: String,
+	/// File to write results to; results are printed to stdout when unset
+	pub output_path: Option<String>,
+	/// Tera template used to render results; takes precedence over `output_format`
+	pub template: Option<PathBuf>,
+	/// Output sink specs (PATH[:FORMAT]); replaces `output_path` and `output_format` when set
+	pub outputs: Vec<String>,
+	/// Keep results in a temporary file instead of memory while scanning, and write reports from it
+	pub spill: bool,
+	/// Directory the temporary results file is created in; the system temporary directory when unset
+	pub spill_dir: Option<PathBuf>,
+	/// Age recipients that written and emailed reports are encrypted to
+	#[cfg(feature = "encryption")]
+	pub encrypt_output: Vec<Recipient>,
+	/// File holding the hex-encoded Ed25519 secret key that file and S3 reports are signed with
+	#[cfg(feature = "signing")]
+	pub signing_key: Option<PathBuf>,
+	/// Hex-encoded Ed25519 public keys that `huginn verify` trusts
+	#[cfg(feature = "signing")]
+	pub report_keys: Vec<String>,
+	/// Streaming sinks that receive each result as it completes
+	pub streams: Vec<StreamConfig>,
+	/// Previous JSON results that drift is measured against
+	pub baseline: Option<PathBuf>,
+	/// Vulnerability dataset that detected service versions are matched against; defaults to the
+	/// `vulns` dataset in `db_dir`, if one has been downloaded
+	#[cfg(feature = "intel-enrichment")]
+	pub vuln_db: Option<PathBuf>,
+	/// Directory that `huginn db update` caches datasets in; defaults to `db` in the [`data_dir`]
+	#[cfg(feature = "intel-enrichment")]
+	pub db_dir: PathBuf,
+	/// URL of the dataset manifest (https:// or file://)
+	#[cfg(feature = "intel-enrichment")]
+	pub db_url: Option<String>,
+	/// Hex-encoded Ed25519 public keys trusted to sign the dataset manifest
+	#[cfg(feature = "intel-enrichment")]
+	pub db_keys: Vec<String>,
+	/// Reputation feeds and blocklists that scanned addresses are checked against
+	#[cfg(feature = "intel-enrichment")]
+	pub reputation: Option<ReputationConfig>,
+	/// Conditions that make a scan exit with status 3: a severity, open-port, or drift
+	pub fail_on: Vec<FailOn>,
+	/// Time between scans in daemon mode (e.g. 30m, 6h)
+	#[cfg(feature = "daemon")]
+	#[serde(with = "humantime_serde")]
+	pub daemon_interval: Duration,
+	/// Address the daemon's metrics endpoint binds to
+	#[cfg(feature = "daemon")]
+	pub bind_address: IpAddr,
+	/// Chat webhooks notified when a scan completes or drifts from the baseline
+	#[cfg(feature = "notifications")]
+	pub notifications: Vec<NotificationConfig>,
+	/// SMTP delivery of the report after each run
+	#[cfg(feature = "email")]
+	pub email: Option<EmailConfig>,
+	/// Vulnerability management platforms and issue trackers that findings are pushed to after each run
+	#[cfg(feature = "integrations")]
+	pub integrations: Vec<IntegrationConfig>,
+	/// Directory that installed plugin packages are placed in
+	#[cfg(feature = "plugin-index")]
+	pub plugins_dir: String,
+	/// URL of the plugin index (https:// or file://)
+	#[cfg(feature = "plugin-index")]
+	pub plugin_index_url: Option<String>,
+	/// Hex-encoded Ed25519 public keys trusted to sign plugin packages
+	#[cfg(feature = "plugin-index")]
+	pub plugin_index_keys: Vec<String>,
 }
 
 impl Default for Config {
@@ -31,34 +873,827 @@ impl Default for Config {
 			debug_mode: false,
 			port: 3000,
 			targets: Vec::new(),
-			scan_types: vec!["ping".to_string()],
+			rib_file: None,
+			#[cfg(feature = "intel-enrichment")]
+			rib_url: DEFAULT_RIB_URL.to_string(),
+			scope: None,
+			force_out_of_scope: false,
+			ptr_suffixes: Vec::new(),
+			strict_scope: false,
+			no_progress: false,
+			audit_log: None,
+			masking: MaskingConfig::default(),
+			log_level: LogLevel::default(),
+			log_format: LogFormat::Text,
+			log_file: None,
+			log_rotation: Rotation::Daily,
+			log_max_size: None,
+			log_keep: 7,
+			exclusions_file: PathBuf::from("exclusions.json"),
+			history_dir: None,
+			scan_types: vec![ScanType::Ping],
+			ports: None,
+			timeout: Duration::from_secs(3),
+			retries: 1,
+			scan_delay: Duration::ZERO,
+			backoff: 2.0,
+			#[cfg(feature = "passive")]
+			capture_duration: Duration::from_secs(60),
+			#[cfg(feature = "passive")]
+			capture_file: None,
+			plugin_timing: HashMap::new(),
+			interface: None,
+			source_ip: None,
+			source_port: None,
+			decoys: Vec::new(),
+			ttl: None,
+			data_length: 0,
+			mtu: None,
+			zombie: None,
+			canary: None,
+			credentials: HashMap::new(),
+			use_credentials: Vec::new(),
+			#[cfg(feature = "core-scan")]
+			discovery: false,
+			#[cfg(feature = "core-scan")]
+			discovery_methods: discovery::DEFAULT_METHODS.to_vec(),
+			#[cfg(feature = "core-scan")]
+			stateless: false,
+			#[cfg(feature = "subdomains")]
+			subdomains: SubdomainsConfig::default(),
+			#[cfg(feature = "dns-checks")]
+			mail_posture: MailPostureConfig::default(),
+			#[cfg(feature = "dns-checks")]
+			dns_hygiene: DnsHygieneConfig::default(),
+			#[cfg(feature = "web-checks")]
+			default_creds: DefaultCredsConfig::default(),
+			#[cfg(feature = "web-checks")]
+			ssh_audit_auth: SshAuditConfig::default(),
+			#[cfg(feature = "web-checks")]
+			web_screenshot: WebScreenshotConfig::default(),
+			#[cfg(feature = "web-checks")]
+			content_discovery: ContentDiscoveryConfig::default(),
+			proxy: None,
+			max_connections: None,
+			raise_fd_limit: false,
+			user: None,
+			output_format: "json".to_string(),
+			output_path: None,
+			template: None,
+			outputs: Vec::new(),
+			spill: false,
+			spill_dir: None,
+			#[cfg(feature = "encryption")]
+			encrypt_output: Vec::new(),
+			#[cfg(feature = "signing")]
+			signing_key: None,
+			#[cfg(feature = "signing")]
+			report_keys: Vec::new(),
+			streams: Vec::new(),
+			baseline: None,
+			#[cfg(feature = "intel-enrichment")]
+			vuln_db: None,
+			#[cfg(feature = "intel-enrichment")]
+			db_dir: data_dir().join("db"),
+			#[cfg(feature = "intel-enrichment")]
+			db_url: None,
+			#[cfg(feature = "intel-enrichment")]
+			db_keys: Vec::new(),
+			#[cfg(feature = "intel-enrichment")]
+			reputation: None,
+			fail_on: Vec::new(),
+			#[cfg(feature = "daemon")]
+			daemon_interval: Duration::from_secs(3600),
+			#[cfg(feature = "daemon")]
+			bind_address: IpAddr::from([127, 0, 0, 1]),
+			#[cfg(feature = "notifications")]
+			notifications: Vec::new(),
+			#[cfg(feature = "email")]
+			email: None,
+			#[cfg(feature = "integrations")]
+			integrations: Vec::new(),
+			#[cfg(feature = "plugin-index")]
+			plugins_dir: "plugins".to_string(),
+			#[cfg(feature = "plugin-index")]
+			plugin_index_url: None,
+			#[cfg(feature = "plugin-index")]
+			plugin_index_keys: Vec::new(),
+		}
+	}
+}
+
+impl Config {
+	/// Shaping of the probes that raw-socket scans build
+	pub fn raw_options(&self) -> RawOptions {
+		RawOptions {
+			source_port: self.source_port,
+			decoys: self.decoys.clone(),
+			ttl: self.ttl,
+			data_length: self.data_length,
+			mtu: self.mtu,
+		}
+	}
+
+	/// A scanner builder with this configuration's scan settings; targets, plugins, and sinks are
+	/// added by the caller
+	pub fn scanner(&self) -> ScannerBuilder {
+		let mut builder = Scanner::builder()
+			.scan_types(self.scan_types.iter().copied())
+			.ports(self.ports.clone())
+			.timeout(self.timeout)
+			.retries(self.retries)
+			.scan_delay(self.scan_delay)
+			.backoff(self.backoff)
+			.interface(self.interface.clone())
+			.source_ip(self.source_ip)
+			.raw(self.raw_options())
+			.zombie(self.zombie.clone())
+			.proxy(self.proxy.clone())
+			.max_connections(self.max_connections)
+			.progress(!self.no_progress);
+		if let Some(canary) = &self.canary {
+			builder = builder.canary(canary.clone());
+		}
+		#[cfg(feature = "passive")]
+		{
+			builder = builder.capture_duration(self.capture_duration).capture_file(self.capture_file.clone());
+		}
+		#[cfg(feature = "core-scan")]
+		if self.discovery {
+			builder = builder.discovery(self.discovery_methods.iter().copied());
+		}
+		#[cfg(feature = "core-scan")]
+		{
+			builder = builder.stateless(self.stateless);
+		}
+		for (&scan_type, timing) in &self.plugin_timing {
+			builder = builder.timing(scan_type, timing.clone());
+		}
+		builder
+	}
+
+	/// Settings that need every result of a scan in memory at once, which a spilled scan does not
+	/// keep
+	pub fn unspillable(&self) -> Vec<&'static str> {
+		let mut settings = Vec::new();
+		if self.baseline.is_some() {
+			settings.push("baseline");
+		}
+		if !self.fail_on.is_empty() {
+			settings.push("fail_on");
+		}
+		if self.history_dir.is_some() {
+			settings.push("history_dir");
+		}
+		#[cfg(feature = "intel-enrichment")]
+		if self.vuln_db.is_some() {
+			settings.push("vuln_db");
+		}
+		#[cfg(feature = "intel-enrichment")]
+		if self.reputation.is_some() {
+			settings.push("reputation");
+		}
+		#[cfg(feature = "notifications")]
+		if !self.notifications.is_empty() {
+			settings.push("notifications");
+		}
+		#[cfg(feature = "email")]
+		if self.email.is_some() {
+			settings.push("email");
+		}
+		#[cfg(feature = "integrations")]
+		if !self.integrations.is_empty() {
+			settings.push("integrations");
+		}
+		settings
+	}
+}
+
+/// BGP table downloaded for ASN and prefix targets when `rib_file` is unset
+#[cfg(feature = "intel-enrichment")]
+const DEFAULT_RIB_URL: &str = "https://bgp.tools/table.txt";
+
+/// Configuration file extensions, in the order they are tried in each directory
+const CONFIG_EXTENSIONS: &[&str] = &["json", "toml", "ini"];
+
+/// Directories searched for `config.*`, highest precedence first
+///
+/// 1. The current directory
+/// 2. `$XDG_CONFIG_HOME/huginn`, or `~/.config/huginn` when it is unset
+/// 3. `%APPDATA%\huginn` (Windows)
+/// 4. `/etc/huginn` (Unix)
+fn search_dirs() -> Vec<PathBuf> {
+	let mut dirs = vec![PathBuf::from(".")];
+	let non_empty = |name| std::env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+	if let Some(xdg) = non_empty("XDG_CONFIG_HOME") {
+		dirs.push(xdg.join("huginn"));
+	} else if let Some(home) = non_empty("HOME") {
+		dirs.push(home.join(".config").join("huginn"));
+	}
+	#[cfg(windows)]
+	if let Some(appdata) = non_empty("APPDATA") {
+		dirs.push(appdata.join("huginn"));
+	}
+	#[cfg(unix)]
+	dirs.push(PathBuf::from("/etc/huginn"));
+	dirs
+}
+
+/// Directory that downloaded datasets and other state are kept in, when not configured
+///
+/// `$XDG_DATA_HOME/huginn`, or `~/.local/share/huginn` when it is unset; `%LOCALAPPDATA%\huginn`
+/// on Windows; or the current directory when none of these is set.
+pub fn data_dir() -> PathBuf {
+	let non_empty = |name| std::env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+	#[cfg(windows)]
+	if let Some(local) = non_empty("LOCALAPPDATA") {
+		return local.join("huginn");
+	}
+	if let Some(xdg) = non_empty("XDG_DATA_HOME") {
+		xdg.join("huginn")
+	} else if let Some(home) = non_empty("HOME") {
+		home.join(".local").join("share").join("huginn")
+	} else {
+		PathBuf::from(".")
+	}
+}
+
+/// Find the first `config.*` file in the given directories
+fn find_in(dirs: &[PathBuf]) -> Option<PathBuf> {
+	dirs.iter()
+		.flat_map(|dir| CONFIG_EXTENSIONS.iter().map(move |ext| dir.join("config").with_extension(ext)))
+		.find(|path| path.is_file())
+}
+
+/// The configuration file a scan would load when no path is given
+pub fn discover() -> Option<PathBuf> {
+	find_in(&search_dirs())
+}
+
+/// Add a configuration file to a builder, preceded by every file it includes
+///
+/// `${NAME}` placeholders in the file are resolved from the environment first. Included files are
+/// resolved relative to the including file and applied in the order listed, so later files, and
+/// finally the including file itself, take precedence. Tables merge key by key; any other value
+/// replaces the earlier one. `chain` holds the files currently being included, to reject cycles.
+fn add_file(
+	mut builder: SourceBuilder<DefaultState>,
+	path: &Path,
+	chain: &mut Vec<PathBuf>,
+) -> Result<SourceBuilder<DefaultState>, ConfigError> {
+	let raw = ConfigBuilder::builder().add_source(File::from(path)).build()?;
+	let file = ConfigBuilder::builder().add_source(Interpolated::new(&raw)?).build()?;
+	let canonical = path
+		.canonicalize()
+		.map_err(|e| ConfigError::Message(format!("Failed to resolve {}: {}", path.display(), e)))?;
+	if chain.contains(&canonical) {
+		return Err(ConfigError::Message(format!("{} includes itself", path.display())));
+	}
+
+	let includes = match file.get_array("include") {
+		Err(ConfigError::NotFound(_)) => Vec::new(),
+		includes => includes?
+			.into_iter()
+			.map(|include| include.into_string())
+			.collect::<Result<Vec<_>, _>>()?,
+	};
+
+	chain.push(canonical);
+	let dir = path.parent().unwrap_or(Path::new("."));
+	for include in includes {
+		builder = add_file(builder, &dir.join(include), chain)
+			.map_err(|e| ConfigError::Message(format!("{} (included from {})", e, path.display())))?;
+	}
+	chain.pop();
+
+	Ok(builder.add_source(file))
+}
+
+/// Read a configuration file and its includes on their own, or an empty configuration when
+/// there is none
+fn read_file(path: Option<PathBuf>) -> Result<ConfigBuilder, ConfigError> {
+	let mut builder = ConfigBuilder::builder();
+	if let Some(path) = path {
+		builder = add_file(builder, &path, &mut Vec::new())?;
+	}
+	builder.build()
+}
+
+/// The `profiles` table of a configuration; absent is empty, but any other error is reported
+fn profile_table(file: &ConfigBuilder) -> Result<config::Map<String, config::Value>, ConfigError> {
+	match file.get_table("profiles") {
+		Err(ConfigError::NotFound(_)) => Ok(config::Map::new()),
+		table => table,
+	}
+}
+
+/// Names of the profiles defined in a configuration file, sorted
+pub fn profiles(path: &Path) -> Result<Vec<String>, ConfigError> {
+	let mut names: Vec<String> = profile_table(&read_file(Some(path.to_path_buf()))?)?.into_keys().collect();
+	names.sort();
+	Ok(names)
+}
+
+/// Build a source holding the settings of the named profile
+fn profile_source(file: &ConfigBuilder, name: &str) -> Result<ConfigBuilder, ConfigError> {
+	let mut table = profile_table(file)?;
+	let Some(settings) = table.remove(name) else {
+		let mut names: Vec<String> = table.into_keys().collect();
+		names.sort();
+		return Err(ConfigError::Message(format!(
+			"Unknown profile {:?}; available: {}",
+			name,
+			names.join(", ")
+		)));
+	};
+
+	let mut builder = ConfigBuilder::builder();
+	for (key, value) in settings.into_table()? {
+		builder = builder.set_override(key, value)?;
+	}
+	builder.build()
+}
+
+/// Defaults for settings that may be omitted from the file and environment
+fn defaults() -> Vec<(&'static str, Value)> {
+	vec![
+		("debug_mode", false.into()),
+		("port", 3000.into()),
+		("timeout", "3s".into()),
+		("retries", 1.into()),
+		("scan_delay", "0s".into()),
+		("backoff", 2.0.into()),
+		#[cfg(feature = "passive")]
+		("capture_duration", "1m".into()),
+		("decoys", Vec::<String>::new().into()),
+		("data_length", 0.into()),
+		("credentials", config::Map::<String, Value>::new().into()),
+		("use_credentials", Vec::<String>::new().into()),
+		#[cfg(feature = "core-scan")]
+		("discovery", false.into()),
+		#[cfg(feature = "core-scan")]
+		("discovery_methods", vec!["echo", "syn", "arp"].into()),
+		#[cfg(feature = "core-scan")]
+		("stateless", false.into()),
+		#[cfg(feature = "subdomains")]
+		("subdomains", config::Map::<String, Value>::new().into()),
+		#[cfg(feature = "dns-checks")]
+		("mail_posture", config::Map::<String, Value>::new().into()),
+		#[cfg(feature = "dns-checks")]
+		("dns_hygiene", config::Map::<String, Value>::new().into()),
+		#[cfg(feature = "web-checks")]
+		("default_creds", config::Map::<String, Value>::new().into()),
+		#[cfg(feature = "web-checks")]
+		("ssh_audit_auth", config::Map::<String, Value>::new().into()),
+		#[cfg(feature = "web-checks")]
+		("web_screenshot", config::Map::<String, Value>::new().into()),
+		#[cfg(feature = "web-checks")]
+		("content_discovery", config::Map::<String, Value>::new().into()),
+		("raise_fd_limit", false.into()),
+		("plugin_timing", config::Map::<String, Value>::new().into()),
+		("exclusions_file", "exclusions.json".into()),
+		("ptr_suffixes", Vec::<String>::new().into()),
+		("strict_scope", false.into()),
+		("masking", config::Map::<String, Value>::new().into()),
+		("log_level", "info".into()),
+		("log_format", "text".into()),
+		("log_rotation", "daily".into()),
+		("log_keep", 7.into()),
+		("output_format", "json".into()),
+		("outputs", Vec::<String>::new().into()),
+		("spill", false.into()),
+		#[cfg(feature = "encryption")]
+		("encrypt_output", Vec::<String>::new().into()),
+		#[cfg(feature = "signing")]
+		("report_keys", Vec::<String>::new().into()),
+		("streams", Vec::<String>::new().into()),
+		("fail_on", Vec::<String>::new().into()),
+		#[cfg(feature = "daemon")]
+		("daemon_interval", "1h".into()),
+		#[cfg(feature = "daemon")]
+		("bind_address", "127.0.0.1".into()),
+		#[cfg(feature = "notifications")]
+		("notifications", Vec::<String>::new().into()),
+		#[cfg(feature = "integrations")]
+		("integrations", Vec::<String>::new().into()),
+		#[cfg(feature = "plugin-index")]
+		("plugins_dir", "plugins".into()),
+		#[cfg(feature = "plugin-index")]
+		("plugin_index_keys", Vec::<String>::new().into()),
+		#[cfg(feature = "intel-enrichment")]
+		("db_dir", data_dir().join("db").display().to_string().into()),
+		#[cfg(feature = "intel-enrichment")]
+		("db_keys", Vec::<String>::new().into()),
+		#[cfg(feature = "intel-enrichment")]
+		("rib_url", DEFAULT_RIB_URL.into()),
+	]
+}
+
+/// Settings of features left out of this build, with the feature that provides each
+const DISABLED_SETTINGS: &[(&str, &str)] = &[
+	#[cfg(not(feature = "passive"))]
+	("capture_duration", "passive"),
+	#[cfg(not(feature = "passive"))]
+	("capture_file", "passive"),
+	#[cfg(not(feature = "core-scan"))]
+	("discovery", "core-scan"),
+	#[cfg(not(feature = "core-scan"))]
+	("discovery_methods", "core-scan"),
+	#[cfg(not(feature = "core-scan"))]
+	("stateless", "core-scan"),
+	#[cfg(not(feature = "subdomains"))]
+	("subdomains", "subdomains"),
+	#[cfg(not(feature = "dns-checks"))]
+	("mail_posture", "dns-checks"),
+	#[cfg(not(feature = "dns-checks"))]
+	("dns_hygiene", "dns-checks"),
+	#[cfg(not(feature = "web-checks"))]
+	("default_creds", "web-checks"),
+	#[cfg(not(feature = "web-checks"))]
+	("ssh_audit_auth", "web-checks"),
+	#[cfg(not(feature = "web-checks"))]
+	("web_screenshot", "web-checks"),
+	#[cfg(not(feature = "web-checks"))]
+	("content_discovery", "web-checks"),
+	#[cfg(not(feature = "daemon"))]
+	("daemon_interval", "daemon"),
+	#[cfg(not(feature = "daemon"))]
+	("bind_address", "daemon"),
+	#[cfg(not(feature = "notifications"))]
+	("notifications", "notifications"),
+	#[cfg(not(feature = "email"))]
+	("email", "email"),
+	#[cfg(not(feature = "integrations"))]
+	("integrations", "integrations"),
+	#[cfg(not(feature = "encryption"))]
+	("encrypt_output", "encryption"),
+	#[cfg(not(feature = "signing"))]
+	("signing_key", "signing"),
+	#[cfg(not(feature = "signing"))]
+	("report_keys", "signing"),
+	#[cfg(not(feature = "plugin-index"))]
+	("plugins_dir", "plugin-index"),
+	#[cfg(not(feature = "plugin-index"))]
+	("plugin_index_url", "plugin-index"),
+	#[cfg(not(feature = "plugin-index"))]
+	("plugin_index_keys", "plugin-index"),
+	#[cfg(not(feature = "intel-enrichment"))]
+	("vuln_db", "intel-enrichment"),
+	#[cfg(not(feature = "intel-enrichment"))]
+	("db_dir", "intel-enrichment"),
+	#[cfg(not(feature = "intel-enrichment"))]
+	("db_url", "intel-enrichment"),
+	#[cfg(not(feature = "intel-enrichment"))]
+	("db_keys", "intel-enrichment"),
+	#[cfg(not(feature = "intel-enrichment"))]
+	("reputation", "intel-enrichment"),
+	#[cfg(not(feature = "intel-enrichment"))]
+	("rib_url", "intel-enrichment"),
+];
+
+/// Names of the settings [`Config`] accepts, as its deserializer asks for them
+fn setting_names() -> &'static [&'static str] {
+	use serde::de::{Error, Visitor, value};
+
+	/// A deserializer that records the field names it is asked for, and deserializes nothing
+	struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+	impl<'de> serde::Deserializer<'de> for FieldNames<'_> {
+		type Error = value::Error;
+
+		fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+			Err(Error::custom("only structs have field names"))
+		}
+
+		fn deserialize_struct<V: Visitor<'de>>(
+			self,
+			_: &'static str,
+			fields: &'static [&'static str],
+			_: V,
+		) -> Result<V::Value, Self::Error> {
+			*self.0 = fields;
+			Err(Error::custom("only the field names are read"))
+		}
+
+		serde::forward_to_deserialize_any! {
+			bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit
+			unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
 		}
 	}
+
+	let mut names: &'static [&'static str] = &[];
+	let _ = Config::deserialize(FieldNames(&mut names));
+	names
+}
+
+/// Settings given by `HUGINN_`-prefixed environment variables
+///
+/// Other tools share the prefix, such as `HUGINN_SMTP_PASSWORD` read by credential references, so
+/// variables that do not name a setting are skipped rather than rejected as unknown settings.
+fn environment_settings(environment: Environment) -> Result<config::Map<String, Value>, ConfigError> {
+	let names = setting_names();
+	let mut settings = environment.collect()?;
+	settings.retain(|key, _| {
+		let known = names.contains(&key.as_str());
+		if !known {
+			debug!("Ignoring HUGINN_{}, which is not a setting", key.to_uppercase());
+		}
+		known
+	});
+	Ok(settings)
 }
 
 /// Load configuration from file and environment variables
-pub fn load() -> Result<Config, ConfigError> {
-	let config_file = PathBuf::from("config.json");
+///
+/// An explicit path must exist; otherwise the first file found in [`search_dirs`] is used, and
+/// defaults plus environment variables apply when there is none. A selected profile's settings
+/// replace the matching top-level settings from the file; environment variables still override
+/// both.
+pub fn load(path: Option<&Path>, profile: Option<&str>) -> Result<Config, ConfigError> {
+	load_with(path, profile, Environment::with_prefix("HUGINN"))
+}
+
+/// Load configuration from file and the given environment
+fn load_with(path: Option<&Path>, profile: Option<&str>, environment: Environment) -> Result<Config, ConfigError> {
+	let config_file = match path {
+		Some(path) => Some(path.to_path_buf()),
+		None => discover(),
+	};
+	match &config_file {
+		Some(file) => debug!("Loading configuration from {}", file.display()),
+		None => debug!("No configuration file found; using defaults and environment"),
+	}
+
+	let file = read_file(config_file)?;
+	let mut builder = ConfigBuilder::builder().add_source(file.clone());
+	if let Some(name) = profile {
+		debug!("Applying profile {}", name);
+		builder = builder.add_source(profile_source(&file, name)?);
+	}
+	for (key, value) in environment_settings(environment)? {
+		builder = builder.set_override(key, value)?;
+	}
+	for (key, value) in defaults() {
+		builder = builder.set_default(key, value)?;
+	}
 
-	let builder = ConfigBuilder::builder()
-		.add_source(File::from(config_file).required(false))
-		.add_source(Environment::with_prefix("HUGINN"))
-		.set_default("debug_mode", false)?
-		.set_default("port", 3000)?;
+	let mut settings = builder.build()?.collect()?;
+	// Directives are consumed while reading files and are not settings themselves
+	settings.remove("include");
+	settings.remove("profiles");
+	for (key, feature) in DISABLED_SETTINGS {
+		if settings.remove(*key).is_some() {
+			warn!("Ignoring {}: this build does not include the {} feature", key, feature);
+		}
+	}
+	Config::deserialize(Value::new(None, ValueKind::Table(settings)))
+}
+
+/// Commented example configuration written by `huginn config init`
+pub const EXAMPLE: &str = include_str!("../config.example.toml");
 
-	let config = builder.build()?;
-	config.try_deserialize()
+/// Write the example configuration to a file, or to stdout when the path is `-`
+pub fn init(path: &Path, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+	if path == Path::new("-") {
+		print!("{}", EXAMPLE);
+		return Ok(());
+	}
+	if path.exists() && !force {
+		return Err(format!("{} already exists; use --force to overwrite it", path.display()).into());
+	}
+	std::fs::write(path, EXAMPLE).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+	info!("Wrote example configuration to {}", path.display());
+	Ok(())
+}
+
+/// Print the completion script for a shell, generated from the command-line definitions
+pub fn completions(shell: Shell) {
+	clap_complete::generate(shell, &mut Cli::command(), "huginn", &mut std::io::stdout());
+}
+
+/// Print the manual page, generated from the command-line definitions
+pub fn man() -> Result<(), Box<dyn std::error::Error>> {
+	clap_mangen::Man::new(Cli::command()).render(&mut std::io::stdout())?;
+	Ok(())
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
 
+	#[test]
+	fn test_cli_docs() {
+		Cli::command().debug_assert();
+		let mut script = Vec::new();
+		clap_complete::generate(Shell::Bash, &mut Cli::command(), "huginn", &mut script);
+		let script = String::from_utf8(script).unwrap();
+		assert!(script.contains("--no-progress") && script.contains("history"));
+
+		let mut page = Vec::new();
+		clap_mangen::Man::new(Cli::command()).render(&mut page).unwrap();
+		assert!(String::from_utf8(page).unwrap().contains(".TH huginn 1"));
+	}
+
 	#[test]
 	fn test_default_config() {
 		let config = Config::default();
 		assert!(!config.debug_mode);
 		assert_eq!(config.port, 3000);
 		assert!(config.api_key.is_none());
+		assert_eq!(config.output_format, "json");
+	}
+
+	#[test]
+	fn test_find_in_respects_precedence() {
+		let root = std::env::temp_dir().join(format!("huginn-config-{}", std::process::id()));
+		let (first, second) = (root.join("first"), root.join("second"));
+		std::fs::create_dir_all(&first).unwrap();
+		std::fs::create_dir_all(&second).unwrap();
+		std::fs::write(second.join("config.json"), "{}").unwrap();
+		std::fs::write(second.join("config.ini"), "").unwrap();
+		std::fs::write(second.join("config.toml"), "").unwrap();
+
+		let dirs = vec![first.clone(), second.clone()];
+		assert_eq!(find_in(&dirs), Some(second.join("config.json")));
+		std::fs::write(first.join("config.ini"), "").unwrap();
+		assert_eq!(find_in(&dirs), Some(first.join("config.ini")));
+		assert_eq!(find_in(&[root.join("missing")]), None);
+
+		std::fs::remove_dir_all(&root).unwrap();
+	}
+
+	#[test]
+	fn test_profile_overrides_file() {
+		let path = std::env::temp_dir().join(format!("huginn-profiles-{}.toml", std::process::id()));
+		std::fs::write(
+			&path,
+			r#"
+targets = ["10.0.0.1"]
+scan_types = ["ping"]
+output_format = "text"
+
+[profiles.full_audit]
+targets = ["10.0.0.0/24"]
+scan_types = ["ping", "tcp_connect"]
+ports = "1-1024"
+"#,
+		)
+		.unwrap();
+
+		let base = load(Some(&path), None).unwrap();
+		assert_eq!(base.targets, vec!["10.0.0.1"]);
+		assert!(base.ports.is_none());
+
+		let audit = load(Some(&path), Some("full_audit")).unwrap();
+		assert_eq!(audit.targets, vec!["10.0.0.0/24"]);
+		assert_eq!(audit.scan_types, vec![ScanType::Ping, ScanType::TcpConnect]);
+		assert_eq!(audit.ports.unwrap().ports().len(), 1024);
+		assert_eq!(audit.output_format, "text");
+
+		let error = load(Some(&path), Some("quick")).unwrap_err().to_string();
+		assert!(error.contains("available: full_audit"));
+		assert_eq!(profiles(&path).unwrap(), vec!["full_audit"]);
+
+		std::fs::remove_file(&path).unwrap();
+	}
+
+	#[test]
+	fn test_includes_merge_in_order() {
+		let root = std::env::temp_dir().join(format!("huginn-include-{}", std::process::id()));
+		std::fs::create_dir_all(root.join("org")).unwrap();
+		std::fs::write(
+			root.join("org/base.toml"),
+			"targets = [\"10.0.0.1\"]\nscan_types = [\"ping\"]\noutput_format = \"text\"\nports = \"22\"\n",
+		)
+		.unwrap();
+		std::fs::write(root.join("org/tuning.toml"), "include = [\"base.toml\"]\nports = \"1-1024\"\n").unwrap();
+		std::fs::write(
+			root.join("site.toml"),
+			"include = [\"org/base.toml\", \"org/tuning.toml\"]\ntargets = [\"192.168.1.0/24\"]\n",
+		)
+		.unwrap();
+
+		let config = load(Some(&root.join("site.toml")), None).unwrap();
+		assert_eq!(config.targets, vec!["192.168.1.0/24"]);
+		assert_eq!(config.scan_types, vec![ScanType::Ping]);
+		assert_eq!(config.output_format, "text");
+		assert_eq!(config.ports.unwrap().ports().len(), 1024);
+
+		std::fs::write(root.join("org/base.toml"), "include = [\"../site.toml\"]\n").unwrap();
+		let error = load(Some(&root.join("site.toml")), None).unwrap_err().to_string();
+		assert!(error.contains("includes itself"), "{}", error);
+
+		std::fs::remove_dir_all(&root).unwrap();
+	}
+
+	/// Write `content` to a temporary file with the given extension and try to load it
+	fn load_str(name: &str, extension: &str, content: &str, profile: Option<&str>) -> Result<Config, String> {
+		let path = std::env::temp_dir().join(format!("huginn-{}-{}.{}", name, std::process::id(), extension));
+		std::fs::write(&path, content).unwrap();
+		let config = load(Some(&path), profile).map_err(|e| e.to_string());
+		std::fs::remove_file(&path).unwrap();
+		config
+	}
+
+	#[test]
+	fn test_malformed_files_are_errors() {
+		let error = load_str("bad-toml", "toml", "targets = [\"10.0.0.1\"]\nport = = 3\n", None).unwrap_err();
+		assert!(error.contains("huginn-bad-toml"), "{}", error);
+		assert!(error.contains("line 2"), "{}", error);
+
+		let error = load_str("bad-json", "json", "{\n  \"targets\": [,\n}", None).unwrap_err();
+		assert!(error.contains("huginn-bad-json"), "{}", error);
+		assert!(error.contains("line 2"), "{}", error);
+
+		let error = load_str("bad-type", "toml", "targets = []\nscan_types = []\nport = \"http\"\n", None).unwrap_err();
+		assert!(error.contains("port"), "{}", error);
+
+		let error = load_str("bad-profiles", "toml", "targets = []\nscan_types = []\nprofiles = 3\n", Some("quick"))
+			.unwrap_err();
+		assert!(error.contains("profiles"), "{}", error);
+
+		assert!(load(Some(Path::new("/nonexistent/huginn.toml")), None).is_err());
+	}
+
+	#[test]
+	fn test_strict_schema() {
+		let error = load_str("typo", "toml", "targets = []\nscan_types = []\nout_format = \"text\"\n", None).unwrap_err();
+		assert!(error.contains("out_format"), "{}", error);
+
+		let error = load_str("scan-type", "toml", "targets = []\nscan_types = [\"pign\"]\n", None).unwrap_err();
+		assert!(error.contains("pign"), "{}", error);
+
+		let error = load_str("ports", "toml", "targets = []\nscan_types = []\nports = \"22,99999\"\n", None).unwrap_err();
+		assert!(error.contains("99999"), "{}", error);
+
+		let config = load_str(
+			"typed",
+			"toml",
+			"targets = []\nscan_types = [\"tcp_syn\", \"udp\"]\nports = \"443, 20-22\"\n",
+			None,
+		)
+		.unwrap();
+		assert_eq!(config.scan_types, vec![ScanType::TcpSyn, ScanType::Udp]);
+		assert_eq!(config.ports.unwrap().ports(), &[20, 21, 22, 443]);
+
+		let error = load_str("proxy", "toml", "targets = []\nscan_types = []\nproxy = \"ftp://proxy:21\"\n", None).unwrap_err();
+		assert!(error.contains("ftp"), "{}", error);
+	}
+
+	#[test]
+	fn test_environment_overrides() {
+		let path = std::env::temp_dir().join(format!("huginn-environment-{}.toml", std::process::id()));
+		std::fs::write(&path, "targets = [\"10.0.0.1\"]\nscan_types = [\"ping\"]\noutput_format = \"text\"\n").unwrap();
+		let environment = |variables: &[(&str, &str)]| {
+			let variables = variables.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+			Environment::with_prefix("HUGINN").source(Some(variables))
+		};
+
+		let config = load_with(
+			Some(&path),
+			None,
+			environment(&[("HUGINN_OUTPUT_FORMAT", "json"), ("HUGINN_SMTP_PASSWORD", "hunter2")]),
+		)
+		.unwrap();
+		assert_eq!(config.output_format, "json");
+		assert_eq!(config.targets, vec!["10.0.0.1"]);
+
+		std::fs::remove_file(&path).unwrap();
+	}
+
+	#[test]
+	fn test_malformed_environment_override() {
+		let path = std::env::temp_dir().join(format!("huginn-malformed-environment-{}.toml", std::process::id()));
+		std::fs::write(&path, "targets = [\"10.0.0.1\"]\nscan_types = [\"ping\"]\n").unwrap();
+		let variables = [("HUGINN_PORT".to_string(), "not-a-port".to_string())].into_iter().collect();
+
+		let error = load_with(Some(&path), None, Environment::with_prefix("HUGINN").source(Some(variables))).unwrap_err();
+		assert!(error.to_string().contains("for key `port`"), "{}", error);
+
+		std::fs::remove_file(&path).unwrap();
+	}
+
+	#[test]
+	fn test_plugin_timing_overrides() {
+		let config = load_str(
+			"timing",
+			"toml",
+			"targets = []\nscan_types = []\ntimeout = \"2s\"\nretries = 3\n\n[plugin_timing.udp]\ntimeout = \"10s\"\nscan_delay = \"50ms\"\n",
+			None,
+		)
+		.unwrap();
+
+		let scanner = config.scanner();
+		let tcp = scanner.context(ScanType::TcpConnect);
+		assert_eq!(tcp.timeout, Duration::from_secs(2));
+		assert_eq!(tcp.retries, 3);
+		assert_eq!(tcp.scan_delay, Duration::ZERO);
+
+		let udp = scanner.context(ScanType::Udp);
+		assert_eq!(udp.timeout, Duration::from_secs(10));
+		assert_eq!(udp.retries, 3);
+		assert_eq!(udp.scan_delay, Duration::from_millis(50));
+
+		assert!(load_str("bad-timing", "toml", "targets = []\nscan_types = []\n[plugin_timing.smtp]\nretries = 1\n", None).is_err());
 	}
 }
@@ -0,0 +1,135 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Environment variable interpolation for Huginn configuration
+//!
+//! `${NAME}` placeholders in configuration file strings are replaced with the value of the
+//! environment variable `NAME` when the file is loaded, so secrets such as webhook URLs and SMTP
+//! passwords can stay out of checked-in files. `$${NAME}` produces a literal `${NAME}`.
+
+use config::{ConfigError, Map, Source, Value, ValueKind};
+
+/// Resolves a variable name to its value
+type Lookup<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+/// Replace the placeholders in one string
+fn expand(text: &str, lookup: Lookup) -> Result<String, String> {
+	let mut expanded = String::with_capacity(text.len());
+	let mut rest = text;
+	while let Some(start) = rest.find('$') {
+		expanded.push_str(&rest[..start]);
+		let candidate = &rest[start..];
+		if let Some(escaped) = candidate.strip_prefix("$${") {
+			expanded.push_str("${");
+			rest = escaped;
+			continue;
+		}
+		let Some(placeholder) = candidate.strip_prefix("${") else {
+			expanded.push('$');
+			rest = &candidate[1..];
+			continue;
+		};
+
+		let end = placeholder
+			.find('}')
+			.ok_or_else(|| format!("unterminated placeholder in {:?}", text))?;
+		let name = &placeholder[..end];
+		if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+			return Err(format!("invalid environment variable name {:?}", name));
+		}
+		let value = lookup(name).ok_or_else(|| format!("environment variable {} is not set", name))?;
+		expanded.push_str(&value);
+		rest = &placeholder[end + 1..];
+	}
+	expanded.push_str(rest);
+	Ok(expanded)
+}
+
+/// Replace the placeholders in every string of a value; `key` locates it in error messages
+fn interpolate(value: &mut Value, key: &str, lookup: Lookup) -> Result<(), ConfigError> {
+	let origin = value.origin().map(|origin| format!(" in {}", origin)).unwrap_or_default();
+	match &mut value.kind {
+		ValueKind::String(text) => {
+			*text = expand(text, lookup).map_err(|e| ConfigError::Message(format!("{} for key `{}`{}", e, key, origin)))?;
+		},
+		ValueKind::Table(table) => {
+			for (name, value) in table.iter_mut() {
+				interpolate(value, &format!("{}.{}", key, name), lookup)?;
+			}
+		},
+		ValueKind::Array(items) => {
+			for (i, value) in items.iter_mut().enumerate() {
+				interpolate(value, &format!("{}[{}]", key, i), lookup)?;
+			}
+		},
+		_ => {},
+	}
+	Ok(())
+}
+
+/// A configuration source with its environment variable placeholders resolved
+#[derive(Debug, Clone)]
+pub struct Interpolated(Map<String, Value>);
+
+impl Interpolated {
+	/// Resolve every placeholder in a source from the process environment
+	pub fn new(source: &impl Source) -> Result<Self, ConfigError> {
+		Self::with_lookup(source, &|name| std::env::var(name).ok())
+	}
+
+	fn with_lookup(source: &impl Source, lookup: Lookup) -> Result<Self, ConfigError> {
+		let mut values = source.collect()?;
+		for (key, value) in values.iter_mut() {
+			interpolate(value, key, lookup)?;
+		}
+		Ok(Self(values))
+	}
+}
+
+impl Source for Interpolated {
+	fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+		Box::new(self.clone())
+	}
+
+	fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+		Ok(self.0.clone())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use config::{File, FileFormat};
+
+	fn lookup(name: &str) -> Option<String> {
+		match name {
+			"SLACK_TOKEN" => Some("T000/B000".to_string()),
+			"REPORTS" => Some("/var/lib/huginn".to_string()),
+			_ => None,
+		}
+	}
+
+	#[test]
+	fn test_expand() {
+		assert_eq!(
+			expand("https://hooks.slack.com/services/${SLACK_TOKEN}", &lookup).unwrap(),
+			"https://hooks.slack.com/services/T000/B000"
+		);
+		assert_eq!(expand("${REPORTS}/scan.json:json", &lookup).unwrap(), "/var/lib/huginn/scan.json:json");
+		assert_eq!(expand("cost: $5, literal $${REPORTS}", &lookup).unwrap(), "cost: $5, literal ${REPORTS}");
+		assert!(expand("${MISSING}", &lookup).unwrap_err().contains("MISSING is not set"));
+		assert!(expand("${REPORTS", &lookup).is_err());
+		assert!(expand("${}", &lookup).is_err());
+	}
+
+	#[test]
+	fn test_missing_variable_names_key() {
+		let source = File::from_str(
+			"[[notifications]]\ntype = \"slack\"\nurl = \"https://hooks.slack.com/services/${WEBHOOK}\"\n",
+			FileFormat::Toml,
+		);
+		let error = Interpolated::with_lookup(&source, &lookup).unwrap_err().to_string();
+		assert!(error.contains("WEBHOOK is not set"), "{}", error);
+		assert!(error.contains("notifications[0].url"), "{}", error);
+	}
+}
@@ -0,0 +1,468 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Configuration validation for Huginn
+//!
+//! This module checks a configuration file without running a scan. Syntax errors are reported
+//! with their line and column, type errors with the offending key, and the loaded values are
+//! checked for valid targets, available scan types, output sinks, and plugin settings.
+
+use crate::catalog;
+use crate::config::{self, Config};
+#[cfg(feature = "web-checks")]
+use crate::credentials::CredentialConfig;
+use crate::output::OutputDispatcher;
+use crate::security::Masker;
+use huginn_core::plugins::ScanType;
+use huginn_core::targets::{self, Network};
+use std::error::Error;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// A problem found in a configuration file
+#[derive(Debug, PartialEq)]
+pub struct Issue {
+	/// Where the problem is: `LINE:COLUMN` for syntax errors, otherwise the key
+	pub location: Option<String>,
+	/// What is wrong
+	pub message: String,
+}
+
+impl Issue {
+	fn at(location: impl Into<String>, message: impl Into<String>) -> Self {
+		Self {
+			location: Some(location.into()),
+			message: message.into(),
+		}
+	}
+}
+
+/// Convert a byte offset into a 1-based line and column
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+	let before = &text[..offset.min(text.len())];
+	let line = before.matches('\n').count() + 1;
+	let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+	(line, column)
+}
+
+/// Check the file's syntax for the formats that report positions
+fn syntax(path: &Path, text: &str) -> Option<Issue> {
+	match path.extension().and_then(|e| e.to_str()) {
+		Some("json") => serde_json::from_str::<serde_json::Value>(text).err().map(|e| {
+			let message = e.to_string();
+			let message = message.split_once(" at line ").map_or(message.as_str(), |(m, _)| m);
+			Issue::at(format!("{}:{}", e.line(), e.column()), message)
+		}),
+		Some("toml") => toml::from_str::<toml::Table>(text).err().map(|e| {
+			let (line, column) = e.span().map_or((1, 1), |span| line_column(text, span.start));
+			Issue::at(format!("{}:{}", line, column), e.message().trim())
+		}),
+		_ => None,
+	}
+}
+
+/// Check a target: an IP address, CIDR block, or host name
+pub fn check_target(target: &str) -> Result<(), String> {
+	if target.contains('/') {
+		return target.parse::<Network>().map(|_| ());
+	}
+	if target.parse::<IpAddr>().is_ok() {
+		return Ok(());
+	}
+
+	// RFC 1123 host names; all-numeric dotted names are malformed addresses instead
+	let labels: Vec<&str> = target.strip_suffix('.').unwrap_or(target).split('.').collect();
+	let hostname = target.len() <= 253
+		&& labels.iter().all(|label| {
+			!label.is_empty()
+				&& label.len() <= 63
+				&& !label.starts_with('-')
+				&& !label.ends_with('-')
+				&& label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+		}) && !labels.iter().all(|label| label.chars().all(|c| c.is_ascii_digit()));
+	if hostname {
+		Ok(())
+	} else {
+		Err(format!("{:?} is not an IP address, CIDR block, or host name", target))
+	}
+}
+
+/// Check the loaded configuration values
+pub fn check(config: &Config) -> Vec<Issue> {
+	let mut issues = Vec::new();
+
+	for (i, target) in config.targets.iter().enumerate() {
+		if let Some(selector) = crate::asn::Selector::parse(target) {
+			if let Err(e) = selector {
+				issues.push(Issue::at(format!("targets[{}]", i), e));
+			}
+		} else if let Err(e) = check_target(target) {
+			issues.push(Issue::at(format!("targets[{}]", i), e));
+		} else if let Some(scope) = &config.scope
+			&& let Ok(hosts) = targets::expand(std::slice::from_ref(target))
+			&& let Some(host) = hosts.iter().find(|host| !scope.allows(host))
+		{
+			issues.push(Issue::at(
+				format!("targets[{}]", i),
+				format!("{} is outside the authorized scope", host),
+			));
+		}
+	}
+
+	let available: Vec<ScanType> = catalog::builtin(config).iter().map(|p| p.scan_type()).collect();
+	for (i, scan_type) in config.scan_types.iter().enumerate() {
+		if !available.contains(scan_type) {
+			issues.push(Issue::at(
+				format!("scan_types[{}]", i),
+				format!("scan type {} is not available in this build", scan_type),
+			));
+		}
+	}
+
+	if let Err(e) = crate::gate::validate(&config.fail_on, config.baseline.is_some()) {
+		issues.push(Issue::at("fail_on", e));
+	}
+
+	if config.scan_types.contains(&ScanType::Idle) && config.zombie.is_none() {
+		issues.push(Issue::at("zombie", "idle scans need a zombie host"));
+	}
+
+	if let Some(canary) = &config.canary
+		&& (canary.contains('/') || canary.parse::<IpAddr>().is_ok() || check_target(canary).is_err())
+	{
+		issues.push(Issue::at("canary", format!("{:?} is not a domain name", canary)));
+	}
+
+	let mut names: Vec<&String> = config.credentials.keys().collect();
+	names.sort();
+	for name in names {
+		if let Err(e) = config.credentials[name].check() {
+			issues.push(Issue::at(format!("credentials.{}", name), e));
+		}
+	}
+	for (i, name) in config.use_credentials.iter().enumerate() {
+		if !config.credentials.contains_key(name) {
+			issues.push(Issue::at(
+				format!("use_credentials[{}]", i),
+				format!("credential {:?} is not defined in the credentials section", name),
+			));
+		}
+	}
+
+	#[cfg(feature = "core-scan")]
+	if config.discovery && config.discovery_methods.is_empty() {
+		issues.push(Issue::at("discovery_methods", "host discovery is enabled without any methods"));
+	}
+
+	#[cfg(feature = "core-scan")]
+	if config.stateless && !config.scan_types.contains(&ScanType::TcpSyn) {
+		issues.push(Issue::at("stateless", "stateless sweeps only apply to tcp_syn scans"));
+	}
+
+	if config.spill {
+		for setting in config.unspillable() {
+			issues.push(Issue::at(setting, "needs every result in memory, so it cannot be used with spill"));
+		}
+	}
+
+	if let Err(e) = Masker::new(&config.masking) {
+		issues.push(Issue::at("masking", e));
+	}
+
+	let backoffs = std::iter::once(("backoff".to_string(), config.backoff)).chain(
+		config
+			.plugin_timing
+			.iter()
+			.filter_map(|(scan_type, timing)| Some((format!("plugin_timing.{}.backoff", scan_type), timing.backoff?))),
+	);
+	for (key, backoff) in backoffs {
+		if !(1.0..=10.0).contains(&backoff) {
+			issues.push(Issue::at(key, "must be from 1 (no backoff) to 10"));
+		}
+	}
+
+	if config.max_connections == Some(0) {
+		issues.push(Issue::at("max_connections", "at least one connection must be allowed"));
+	}
+
+	for (key, problem) in config.raw_options().problems() {
+		issues.push(Issue::at(key, problem));
+	}
+
+	if let Err(e) = OutputDispatcher::from_config(config) {
+		let key = if config.outputs.is_empty() { "output_path" } else { "outputs" };
+		issues.push(Issue::at(key, e.to_string()));
+	}
+
+	if let Some(baseline) = &config.baseline
+		&& !baseline.is_file()
+	{
+		issues.push(Issue::at("baseline", format!("{} does not exist", baseline.display())));
+	}
+
+	if let Some(rib_file) = &config.rib_file
+		&& !rib_file.is_file()
+	{
+		issues.push(Issue::at("rib_file", format!("{} does not exist", rib_file.display())));
+	}
+
+	#[cfg(feature = "subdomains")]
+	if let Some(wordlist) = &config.subdomains.wordlist
+		&& !wordlist.is_file()
+	{
+		issues.push(Issue::at("subdomains.wordlist", format!("{} does not exist", wordlist.display())));
+	}
+
+	#[cfg(feature = "subdomains")]
+	if config.scan_types.contains(&ScanType::Subdomains) && config.subdomains.sources.is_empty() {
+		issues.push(Issue::at("subdomains.sources", "subdomain enumeration is enabled without any sources"));
+	}
+
+	#[cfg(feature = "dns-checks")]
+	if config.scan_types.contains(&ScanType::MailPosture) && config.mail_posture.dkim_selectors.is_empty() {
+		issues.push(Issue::at("mail_posture.dkim_selectors", "mail posture grading is enabled without any DKIM selectors"));
+	}
+
+	#[cfg(feature = "dns-checks")]
+	if let Some(fingerprints) = &config.dns_hygiene.fingerprints
+		&& !fingerprints.is_file()
+	{
+		issues.push(Issue::at("dns_hygiene.fingerprints", format!("{} does not exist", fingerprints.display())));
+	}
+
+	#[cfg(feature = "web-checks")]
+	if config.scan_types.contains(&ScanType::DefaultCreds) && !config.default_creds.authorized {
+		issues.push(Issue::at(
+			"default_creds.authorized",
+			"default_creds tries logins on its targets; set authorized = true once you are authorized to",
+		));
+	}
+
+	#[cfg(feature = "web-checks")]
+	if config.default_creds.max_attempts == 0 {
+		issues.push(Issue::at("default_creds.max_attempts", "must be at least 1"));
+	}
+
+	#[cfg(feature = "web-checks")]
+	if config.scan_types.contains(&ScanType::SshAuditAuth)
+		&& !config
+			.use_credentials
+			.iter()
+			.any(|name| matches!(config.credentials.get(name), Some(CredentialConfig::Ssh { .. })))
+	{
+		issues.push(Issue::at("use_credentials", "ssh_audit_auth logs in with an ssh credential, but none is used"));
+	}
+
+	#[cfg(feature = "web-checks")]
+	if let Some(known_hosts) = &config.ssh_audit_auth.known_hosts
+		&& !known_hosts.is_file()
+		&& !config.ssh_audit_auth.accept_new_host_keys
+	{
+		issues.push(Issue::at(
+			"ssh_audit_auth.known_hosts",
+			format!("{} does not exist, so every host key would be refused", known_hosts.display()),
+		));
+	}
+
+	#[cfg(feature = "web-checks")]
+	if config.web_screenshot.width == 0 || config.web_screenshot.height == 0 {
+		issues.push(Issue::at("web_screenshot", "width and height must be at least 1"));
+	}
+
+	#[cfg(feature = "web-checks")]
+	if !config.web_screenshot.command.is_empty()
+		&& !config.web_screenshot.command.iter().any(|argument| argument.contains("{output}"))
+	{
+		issues.push(Issue::at(
+			"web_screenshot.command",
+			"the capture command needs an {output} argument to know where to save the screenshot",
+		));
+	}
+
+	#[cfg(feature = "web-checks")]
+	{
+		let discovery = &config.content_discovery;
+		if config.scan_types.contains(&ScanType::ContentDiscovery) && discovery.wordlist.is_none() && discovery.vhosts.is_none() {
+			issues.push(Issue::at("content_discovery", "content_discovery needs a wordlist of paths or vhosts"));
+		}
+		for (key, list) in [("wordlist", &discovery.wordlist), ("vhosts", &discovery.vhosts)] {
+			if let Some(list) = list
+				&& !list.is_file()
+			{
+				issues.push(Issue::at(format!("content_discovery.{}", key), format!("{} does not exist", list.display())));
+			}
+		}
+		if discovery.requests_per_second == 0 {
+			issues.push(Issue::at("content_discovery.requests_per_second", "must be at least 1"));
+		}
+	}
+
+	#[cfg(feature = "integrations")]
+	for (i, integration) in config.integrations.iter().enumerate() {
+		if let Err(e) = integration.check() {
+			issues.push(Issue::at(format!("integrations[{}]", i), e));
+		}
+	}
+
+	#[cfg(feature = "signing")]
+	if let Some(path) = &config.signing_key
+		&& let Err(e) = crate::output::sign::load_key(path)
+	{
+		issues.push(Issue::at("signing_key", e.to_string()));
+	}
+
+	#[cfg(feature = "signing")]
+	for (i, key) in config.report_keys.iter().enumerate() {
+		if let Err(e) = crate::output::sign::parse_key(key) {
+			issues.push(Issue::at(format!("report_keys[{}]", i), e.to_string()));
+		}
+	}
+
+	#[cfg(feature = "plugin-index")]
+	if config.plugin_index_url.is_some()
+		&& let Err(e) = crate::marketplace::Marketplace::new(config)
+	{
+		issues.push(Issue::at("plugin_index_keys", e.to_string()));
+	}
+
+	#[cfg(feature = "intel-enrichment")]
+	if config.db_url.is_some()
+		&& let Err(e) = crate::db::trusted_keys(config)
+	{
+		issues.push(Issue::at("db_keys", e.to_string()));
+	}
+
+	#[cfg(feature = "intel-enrichment")]
+	if let Some(path) = &config.vuln_db
+		&& let Err(e) = crate::vulns::Database::load(path)
+	{
+		issues.push(Issue::at("vuln_db", e.to_string()));
+	}
+
+	#[cfg(feature = "intel-enrichment")]
+	if let Some(reputation) = &config.reputation
+		&& let Err(e) = crate::reputation::Checker::new(reputation)
+	{
+		issues.push(Issue::at("reputation.blocklists", e.to_string()));
+	}
+
+	issues
+}
+
+/// Load and check the configuration with an optional profile applied
+fn check_file(path: &Path, profile: Option<&str>) -> Vec<Issue> {
+	match config::load(Some(path), profile) {
+		Ok(config) => check(&config),
+		Err(e) => vec![Issue {
+			location: None,
+			message: e.to_string(),
+		}],
+	}
+}
+
+/// Validate a configuration file: syntax first, then types, then values, then each profile
+pub fn validate(path: &Path) -> Result<Vec<Issue>, Box<dyn Error>> {
+	let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+	if let Some(issue) = syntax(path, &text) {
+		return Ok(vec![issue]);
+	}
+
+	let mut issues = check_file(path, None);
+	// Report problems a profile inherits from the top level only once
+	let inherited = issues.len();
+	for name in config::profiles(path)? {
+		let prefix = format!("profiles.{}", name);
+		for issue in check_file(path, Some(&name)) {
+			if issues[..inherited].contains(&issue) {
+				continue;
+			}
+			let location = match issue.location {
+				Some(location) => format!("{}.{}", prefix, location),
+				None => prefix.clone(),
+			};
+			issues.push(Issue::at(location, issue.message));
+		}
+	}
+	Ok(issues)
+}
+
+/// Validate a configuration file and print every problem found
+pub fn run(path: &Path) -> Result<(), Box<dyn Error>> {
+	let issues = validate(path)?;
+	if issues.is_empty() {
+		println!("{}: OK", path.display());
+		return Ok(());
+	}
+	for issue in &issues {
+		match &issue.location {
+			Some(location) => println!("{}:{}: {}", path.display(), location, issue.message),
+			None => println!("{}: {}", path.display(), issue.message),
+		}
+	}
+	Err(format!("{} problems found in {}", issues.len(), path.display()).into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_check_target() {
+		assert!(check_target("10.0.0.1").is_ok());
+		assert!(check_target("10.0.0.0/24").is_ok());
+		assert!(check_target("2001:db8::/32").is_ok());
+		assert!(check_target("scanme.example.com").is_ok());
+		assert!(check_target("10.0.0.300").is_err());
+		assert!(check_target("10.0.0.0/33").is_err());
+		assert!(check_target("bad_host!").is_err());
+		assert!(check_target("").is_err());
+	}
+
+	#[test]
+	fn test_out_of_scope_targets() {
+		let config = Config {
+			targets: vec!["10.0.0.0/30".to_string(), "10.0.0.0/29".to_string()],
+			scope: Some(crate::scope::Scope {
+				networks: vec!["10.0.0.0/30".parse().unwrap()],
+				domains: Vec::new(),
+			}),
+			..Config::default()
+		};
+		let issues: Vec<Issue> = check(&config).into_iter().filter(|i| i.message.contains("scope")).collect();
+		assert_eq!(issues, vec![Issue::at("targets[1]", "10.0.0.4 is outside the authorized scope")]);
+	}
+
+	#[test]
+	fn test_raw_options() {
+		let config = Config {
+			data_length: 1401,
+			ttl: Some(0),
+			mtu: Some(3),
+			..Config::default()
+		};
+		let issues = check(&config);
+		let keys: Vec<&str> = issues.iter().filter_map(|i| i.location.as_deref()).collect();
+		assert!(["data_length", "ttl", "mtu"].iter().all(|key| keys.contains(key)), "{:?}", keys);
+		assert!(config.raw_options().check().unwrap_err().to_string().contains("data_length"));
+		assert!(Config::default().raw_options().check().is_ok());
+	}
+
+	#[test]
+	fn test_syntax_error_location() {
+		let issue = syntax(Path::new("config.toml"), "targets = []\nport = = 3\n").unwrap();
+		assert!(issue.location.unwrap().starts_with("2:"));
+		let issue = syntax(Path::new("config.json"), "{\n  \"port\": ,\n}").unwrap();
+		assert_eq!(issue.location.unwrap(), "2:11");
+		assert!(syntax(Path::new("config.toml"), config::EXAMPLE).is_none());
+	}
+
+	#[cfg(feature = "core-scan")]
+	#[test]
+	fn test_example_is_valid() {
+		let path = std::env::temp_dir().join(format!("huginn-example-{}.toml", std::process::id()));
+		std::fs::write(&path, config::EXAMPLE).unwrap();
+		let issues = validate(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		assert!(issues.is_empty(), "{:?}", issues);
+	}
+}
@@ -0,0 +1,295 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Named credentials for Huginn
+//!
+//! The `credentials` section defines credentials by name, and `use_credentials`, usually set in a
+//! profile, picks the ones a scan may log in with. Secrets are never written in the
+//! configuration: each is read, when a scan starts, from an environment variable, a file, or the
+//! system keyring (`secret-tool` on Linux, the login keychain on macOS). Only the credentials a
+//! scan uses are read, so a profile that needs none runs without access to any secret.
+
+use crate::config::Config;
+use huginn_core::credentials::{AuthProtocol, Credential, Credentials, PrivacyProtocol, Secret};
+use log::info;
+#[cfg(unix)]
+use log::warn;
+use serde::Deserialize;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Where a secret is read from
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum SecretSource {
+	/// Environment variable, by name
+	Env(String),
+	/// File holding only the secret
+	File(PathBuf),
+	/// System keyring entry, as `SERVICE/ACCOUNT`
+	Keyring(String),
+}
+
+impl SecretSource {
+	/// Check the source without reading it
+	fn check(&self) -> Result<(), String> {
+		match self {
+			SecretSource::Env(name) if name.is_empty() => Err("empty environment variable name".to_string()),
+			SecretSource::Keyring(entry) if keyring_entry(entry).is_none() => {
+				Err(format!("keyring entry {:?} is not SERVICE/ACCOUNT", entry))
+			},
+			_ => Ok(()),
+		}
+	}
+
+	/// Read the secret; a password keeps no line ending, a key is kept as written
+	fn read(&self, key: bool) -> Result<Secret, String> {
+		let value = match self {
+			SecretSource::Env(name) => {
+				std::env::var(name).map_err(|_| format!("environment variable {} is not set", name))?
+			},
+			SecretSource::File(path) => read_file(path)?,
+			SecretSource::Keyring(entry) => {
+				let (service, account) =
+					keyring_entry(entry).ok_or_else(|| format!("keyring entry {:?} is not SERVICE/ACCOUNT", entry))?;
+				keyring(service, account)?
+			},
+		};
+		let value = if key { value } else { value.trim_end_matches(['\r', '\n']).to_string() };
+		if value.is_empty() {
+			return Err("the secret is empty".to_string());
+		}
+		Ok(Secret::new(value))
+	}
+}
+
+/// Service and account of a keyring entry
+fn keyring_entry(entry: &str) -> Option<(&str, &str)> {
+	entry.split_once('/').filter(|(service, account)| !service.is_empty() && !account.is_empty())
+}
+
+/// Read a secret file, warning when others can read it too
+fn read_file(path: &Path) -> Result<String, String> {
+	let value = std::fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+		if let Ok(metadata) = std::fs::metadata(path)
+			&& metadata.permissions().mode() & 0o077 != 0
+		{
+			warn!("{} can be read by other users; restrict it to its owner (chmod 600)", path.display());
+		}
+	}
+	Ok(value)
+}
+
+/// Look up a password in the system keyring with the platform's command line tool
+fn keyring(service: &str, account: &str) -> Result<String, String> {
+	let mut command = if cfg!(target_os = "macos") {
+		let mut command = Command::new("security");
+		command.args(["find-generic-password", "-s", service, "-a", account, "-w"]);
+		command
+	} else if cfg!(unix) {
+		let mut command = Command::new("secret-tool");
+		command.args(["lookup", "service", service, "account", account]);
+		command
+	} else {
+		return Err("the system keyring is only supported on Linux and macOS".to_string());
+	};
+	let output = command
+		.output()
+		.map_err(|e| format!("failed to run {:?}: {}", command.get_program(), e))?;
+	if !output.status.success() {
+		return Err(format!("no keyring entry for service {:?} and account {:?}", service, account));
+	}
+	String::from_utf8(output.stdout).map_err(|_| "the keyring entry is not UTF-8".to_string())
+}
+
+/// A named credential, with the sources of its secrets
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum CredentialConfig {
+	/// SSH login, with a private key, a password, or both
+	Ssh {
+		/// User name
+		user: String,
+		/// Private key, in OpenSSH or PEM format
+		key: Option<SecretSource>,
+		/// Passphrase the private key is encrypted with
+		passphrase: Option<SecretSource>,
+		/// Password
+		password: Option<SecretSource>,
+	},
+	/// Bearer token for an API
+	Token {
+		/// Token value
+		token: SecretSource,
+	},
+	/// SNMPv3 user
+	Snmpv3 {
+		/// User name
+		user: String,
+		/// Authentication protocol (sha or sha256); sha when a password is given
+		auth_protocol: Option<AuthProtocol>,
+		/// Authentication password; noAuthNoPriv when unset
+		auth_password: Option<SecretSource>,
+		/// Privacy protocol (aes); aes when a password is given
+		privacy_protocol: Option<PrivacyProtocol>,
+		/// Privacy password; authNoPriv when unset
+		privacy_password: Option<SecretSource>,
+	},
+}
+
+impl CredentialConfig {
+	/// Check that the credential is complete, without reading its secrets
+	pub fn check(&self) -> Result<(), String> {
+		let sources = match self {
+			CredentialConfig::Ssh {
+				key, passphrase, password, ..
+			} => {
+				if key.is_none() && password.is_none() {
+					return Err("an SSH credential needs a key or a password".to_string());
+				}
+				if key.is_none() && passphrase.is_some() {
+					return Err("a passphrase needs a key".to_string());
+				}
+				vec![key, passphrase, password]
+			},
+			CredentialConfig::Token { token } => return token.check(),
+			CredentialConfig::Snmpv3 {
+				auth_protocol,
+				auth_password,
+				privacy_protocol,
+				privacy_password,
+				..
+			} => {
+				if auth_password.is_none() && auth_protocol.is_some() {
+					return Err("auth_protocol needs an auth_password".to_string());
+				}
+				if privacy_password.is_none() && privacy_protocol.is_some() {
+					return Err("privacy_protocol needs a privacy_password".to_string());
+				}
+				if auth_password.is_none() && privacy_password.is_some() {
+					return Err("privacy needs authentication; set an auth_password too".to_string());
+				}
+				vec![auth_password, privacy_password]
+			},
+		};
+		sources.into_iter().flatten().try_for_each(SecretSource::check)
+	}
+
+	/// Read the secrets and build the credential
+	fn resolve(&self) -> Result<Credential, String> {
+		let read = |source: &Option<SecretSource>, key| source.as_ref().map(|s| s.read(key)).transpose();
+		Ok(match self {
+			CredentialConfig::Ssh {
+				user,
+				key,
+				passphrase,
+				password,
+			} => Credential::Ssh {
+				user: user.clone(),
+				key: read(key, true)?,
+				passphrase: read(passphrase, false)?,
+				password: read(password, false)?,
+			},
+			CredentialConfig::Token { token } => Credential::Token { token: token.read(false)? },
+			CredentialConfig::Snmpv3 {
+				user,
+				auth_protocol,
+				auth_password,
+				privacy_protocol,
+				privacy_password,
+			} => Credential::SnmpV3 {
+				user: user.clone(),
+				auth: read(auth_password, false)?.map(|password| (auth_protocol.unwrap_or(AuthProtocol::Sha), password)),
+				privacy: read(privacy_password, false)?
+					.map(|password| (privacy_protocol.unwrap_or(PrivacyProtocol::Aes), password)),
+			},
+		})
+	}
+}
+
+/// Read the secrets of the credentials a scan uses
+pub fn resolve(config: &Config) -> Result<Credentials, Box<dyn Error>> {
+	let mut credentials = Credentials::default();
+	for name in &config.use_credentials {
+		let definition = config
+			.credentials
+			.get(name)
+			.ok_or_else(|| format!("Credential {:?} is not defined in the credentials section", name))?;
+		definition.check().map_err(|e| format!("Credential {}: {}", name, e))?;
+		let credential = definition.resolve().map_err(|e| format!("Credential {}: {}", name, e))?;
+		info!("Using {} credential {}", credential.kind(), name);
+		credentials.insert(name.clone(), credential);
+	}
+	Ok(credentials)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A credential definition parsed from TOML
+	fn parse(text: &str) -> CredentialConfig {
+		toml::from_str(text).unwrap()
+	}
+
+	#[test]
+	fn test_check() {
+		let ssh = parse("kind = \"ssh\"\nuser = \"audit\"\nkey = { file = \"/etc/huginn/id_ed25519\" }\n");
+		assert!(ssh.check().is_ok());
+		let ssh = parse("kind = \"ssh\"\nuser = \"audit\"\n");
+		assert!(ssh.check().unwrap_err().contains("key or a password"));
+
+		let snmp = parse("kind = \"snmpv3\"\nuser = \"monitor\"\nprivacy_password = { env = \"PRIV\" }\n");
+		assert!(snmp.check().unwrap_err().contains("needs authentication"));
+		let token = parse("kind = \"token\"\ntoken = { keyring = \"huginn\" }\n");
+		assert!(token.check().unwrap_err().contains("SERVICE/ACCOUNT"));
+		assert!(toml::from_str::<CredentialConfig>("kind = \"token\"\ntoken = \"inline\"\n").is_err());
+	}
+
+	#[test]
+	fn test_resolve() {
+		let path = std::env::temp_dir().join(format!("huginn-secret-{}", std::process::id()));
+		std::fs::write(&path, "s3cret\n").unwrap();
+		let mut config = Config::default();
+		config.credentials.insert(
+			"switches".to_string(),
+			CredentialConfig::Snmpv3 {
+				user: "monitor".to_string(),
+				auth_protocol: None,
+				auth_password: Some(SecretSource::File(path.clone())),
+				privacy_protocol: None,
+				privacy_password: None,
+			},
+		);
+		config.credentials.insert(
+			"api".to_string(),
+			CredentialConfig::Token {
+				token: SecretSource::Env("HUGINN_TEST_UNSET_TOKEN".to_string()),
+			},
+		);
+
+		// Credentials a scan does not use are never read
+		config.use_credentials = vec!["switches".to_string()];
+		let credentials = resolve(&config).unwrap();
+		assert_eq!(
+			credentials.get("switches"),
+			Some(&Credential::SnmpV3 {
+				user: "monitor".to_string(),
+				auth: Some((AuthProtocol::Sha, Secret::new("s3cret"))),
+				privacy: None,
+			})
+		);
+		assert!(credentials.get("api").is_none());
+
+		config.use_credentials.push("api".to_string());
+		let error = resolve(&config).unwrap_err().to_string();
+		assert!(error.contains("HUGINN_TEST_UNSET_TOKEN is not set"), "{}", error);
+		config.use_credentials = vec!["missing".to_string()];
+		assert!(resolve(&config).unwrap_err().to_string().contains("not defined"));
+		std::fs::remove_file(&path).unwrap();
+	}
+}
@@ -0,0 +1,121 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Daemon mode for Huginn
+//!
+//! This module runs the configured scan on a fixed interval and serves Prometheus metrics on
+//! `GET /metrics` until interrupted.
+
+use crate::config::Config;
+use crate::metrics;
+use log::{debug, error, info, warn};
+use std::error::Error;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Largest request head the metrics endpoint will read
+const MAX_REQUEST: usize = 8192;
+
+/// How long a client may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Build the HTTP response for a request head
+fn respond(request: &str) -> String {
+	let (status, content_type, body) = match request.split_whitespace().take(2).collect::<Vec<_>>()[..] {
+		["GET", "/metrics"] => match metrics::encode() {
+			Ok(body) => ("200 OK", "text/plain; version=0.0.4", body),
+			Err(e) => ("500 Internal Server Error", "text/plain", format!("{}\n", e)),
+		},
+		["GET", _] => ("404 Not Found", "text/plain", "Not Found\n".to_string()),
+		_ => ("405 Method Not Allowed", "text/plain", "Method Not Allowed\n".to_string()),
+	};
+	format!(
+		"HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+		status,
+		content_type,
+		body.len(),
+		body
+	)
+}
+
+/// Answer a single metrics request
+async fn handle(mut stream: TcpStream) -> Result<(), Box<dyn Error>> {
+	let mut buffer = vec![0; MAX_REQUEST];
+	let mut length = 0;
+	tokio::time::timeout(REQUEST_TIMEOUT, async {
+		while length < MAX_REQUEST && !buffer[..length].windows(4).any(|w| w == b"\r\n\r\n") {
+			match stream.read(&mut buffer[length..]).await? {
+				0 => break,
+				n => length += n,
+			}
+		}
+		Ok::<_, std::io::Error>(())
+	})
+	.await??;
+
+	let request = String::from_utf8_lossy(&buffer[..length]);
+	stream.write_all(respond(&request).as_bytes()).await?;
+	stream.shutdown().await?;
+	Ok(())
+}
+
+/// Serve metrics until the listener fails
+async fn serve(listener: TcpListener) {
+	loop {
+		match listener.accept().await {
+			Ok((stream, peer)) => {
+				tokio::spawn(async move {
+					if let Err(e) = handle(stream).await {
+						debug!("Metrics request from {} failed: {}", peer, e);
+					}
+				});
+			},
+			Err(e) => warn!("Failed to accept metrics connection: {}", e),
+		}
+	}
+}
+
+/// Run scans on the configured interval until interrupted
+pub async fn run(config: Config) -> Result<(), Box<dyn Error>> {
+	let interval = config.daemon_interval;
+
+	let address = SocketAddr::new(config.bind_address, config.port);
+	let listener = TcpListener::bind(address).await?;
+	info!("Serving metrics on http://{}/metrics", address);
+	tokio::spawn(serve(listener));
+	crate::privileges::drop_root(&config)?;
+
+	loop {
+		let started = Instant::now();
+		match crate::scan(&config).await {
+			Ok(met) if !met.is_empty() => warn!("Scheduled scan met fail_on conditions: {}", met.join("; ")),
+			Ok(_) => {},
+			Err(e) => error!("Scheduled scan failed: {}", e),
+		}
+		metrics::observe_run(started.elapsed());
+
+		info!("Next scan in {}", humantime::format_duration(interval));
+		tokio::select! {
+			_ = tokio::time::sleep(interval) => {},
+			_ = tokio::signal::ctrl_c() => {
+				info!("Interrupted; stopping daemon");
+				return Ok(());
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_respond() {
+		assert!(respond("GET /metrics HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 200 OK"));
+		assert!(respond("GET / HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 404"));
+		assert!(respond("POST /metrics HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 405"));
+		assert!(respond("").starts_with("HTTP/1.1 405"));
+	}
+}
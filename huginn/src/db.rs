@@ -0,0 +1,347 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Offline dataset cache for Huginn
+//!
+//! `huginn db update` downloads the datasets listed in the manifest at `db_url` (vulnerability,
+//! service fingerprint, and OUI databases) into `db_dir`, so scans can be enriched on hosts
+//! without network access. The manifest must carry a detached Ed25519 signature, `<manifest>.sig`,
+//! by one of the keys in `db_keys`, and each download is checked against the SHA-256 digest the
+//! manifest publishes before it replaces the cached copy. For air-gapped hosts, the manifest and
+//! datasets can be copied over on removable media and read through a `file://` URL; dataset URLs
+//! in the manifest may be relative to it, and must use the manifest's own scheme. `huginn db status` shows the age of each cached dataset and
+//! whether its file still matches the recorded digest.
+
+use crate::config::{Config, DbCommand};
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use log::info;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Name of the lock file kept in the dataset directory
+const LOCK_FILE: &str = "datasets.lock.json";
+
+/// Name of the vulnerability dataset, used when `vuln_db` is unset
+pub const VULNS: &str = "vulns";
+
+/// Name of the takeover fingerprint dataset, used when `dns_hygiene.fingerprints` is unset
+#[cfg(feature = "dns-checks")]
+pub const TAKEOVER: &str = "takeover";
+
+/// Dataset entry published in the manifest
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestEntry {
+	/// Dataset name, such as `vulns`, `fingerprints`, or `oui`
+	pub name: String,
+	/// File name the dataset is cached under
+	pub file: String,
+	/// Download URL of the dataset, absolute or relative to the manifest
+	pub url: String,
+	/// Hex-encoded SHA-256 digest of the dataset
+	pub sha256: String,
+	/// When the publisher built the dataset
+	#[serde(default)]
+	pub published: Option<DateTime<Utc>>,
+}
+
+/// Dataset manifest document
+#[derive(Debug, Deserialize)]
+struct Manifest {
+	datasets: Vec<ManifestEntry>,
+}
+
+/// Cached dataset record kept in the lock file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedDataset {
+	/// Dataset name
+	pub name: String,
+	/// File name in the dataset directory
+	pub file: String,
+	/// Hex-encoded SHA-256 digest of the cached file
+	pub sha256: String,
+	/// When the publisher built the dataset, if the manifest said
+	pub published: Option<DateTime<Utc>>,
+	/// When the dataset was downloaded
+	pub updated: DateTime<Utc>,
+}
+
+impl CachedDataset {
+	/// When the data was current: its publication time, or else its download time
+	fn as_of(&self) -> DateTime<Utc> {
+		self.published.unwrap_or(self.updated)
+	}
+}
+
+/// Fetch raw bytes from an https:// or file:// URL
+pub async fn fetch(client: &reqwest::Client, url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+	if let Some(path) = url.strip_prefix("file://") {
+		return Ok(tokio::fs::read(path).await.map_err(|e| format!("Failed to read {}: {}", path, e))?);
+	}
+	if !url.starts_with("https://") {
+		return Err(format!("Refusing to fetch from non-HTTPS URL: {}", url).into());
+	}
+	let response = client.get(url).send().await?.error_for_status()?;
+	Ok(response.bytes().await?.to_vec())
+}
+
+/// Resolve a dataset URL against the manifest URL it was listed in
+fn resolve(manifest_url: &str, url: &str) -> String {
+	if url.contains("://") {
+		return url.to_string();
+	}
+	match manifest_url.rfind('/') {
+		Some(end) => format!("{}/{}", &manifest_url[..end], url),
+		None => url.to_string(),
+	}
+}
+
+/// Scheme of a URL, such as `https`
+fn scheme(url: &str) -> Option<&str> {
+	url.split_once("://").map(|(scheme, _)| scheme)
+}
+
+/// The Ed25519 keys trusted to sign the dataset manifest
+pub fn trusted_keys(config: &Config) -> Result<Vec<VerifyingKey>, Box<dyn Error>> {
+	let keys = config
+		.db_keys
+		.iter()
+		.map(|key| {
+			let bytes: [u8; 32] = hex::decode(key.trim())?
+				.try_into()
+				.map_err(|_| "Ed25519 public keys must be 32 bytes")?;
+			Ok(VerifyingKey::from_bytes(&bytes)?)
+		})
+		.collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+	if keys.is_empty() {
+		return Err("db_keys must contain at least one trusted key".into());
+	}
+	Ok(keys)
+}
+
+/// Check the detached signature of a manifest against the trusted keys
+fn verify_manifest(keys: &[VerifyingKey], manifest: &[u8], signature: &[u8]) -> Result<(), Box<dyn Error>> {
+	let signature = hex::decode(String::from_utf8_lossy(signature).trim())?;
+	let signature = Signature::from_slice(&signature)?;
+	if keys.iter().any(|key| key.verify(manifest, &signature).is_ok()) {
+		Ok(())
+	} else {
+		Err("No trusted key verifies the signature of the dataset manifest".into())
+	}
+}
+
+/// Ensure a manifest entry names a plain file in the dataset directory
+fn validate_entry(entry: &ManifestEntry) -> Result<(), Box<dyn Error>> {
+	let plain = |s: &str| {
+		!s.is_empty() && s != "." && s != ".." && !s.contains(['/', '\\']) && s.chars().all(|c| !c.is_control())
+	};
+	if !plain(&entry.name) || !plain(&entry.file) || entry.file == LOCK_FILE {
+		return Err(format!("Invalid dataset entry {:?} ({:?})", entry.name, entry.file).into());
+	}
+	Ok(())
+}
+
+/// Hex-encoded SHA-256 digest of a file, or `None` when it cannot be read
+fn digest_file(path: &Path) -> Option<String> {
+	std::fs::read(path).ok().map(|bytes| hex::encode(Sha256::digest(bytes)))
+}
+
+/// Read the lock file of a dataset directory
+fn read_lock(dir: &Path) -> Result<Vec<CachedDataset>, Box<dyn Error>> {
+	let path = dir.join(LOCK_FILE);
+	if !path.exists() {
+		return Ok(Vec::new());
+	}
+	Ok(serde_json::from_slice(&std::fs::read(&path)?)?)
+}
+
+/// Path of a cached dataset, if it has been downloaded
+pub fn path(config: &Config, name: &str) -> Option<PathBuf> {
+	let cached = read_lock(&config.db_dir).ok()?.into_iter().find(|d| d.name == name)?;
+	Some(config.db_dir.join(cached.file))
+}
+
+/// Download every dataset in the manifest whose cached copy is missing or out of date
+pub async fn update(config: &Config) -> Result<Vec<CachedDataset>, Box<dyn Error>> {
+	let manifest_url = config.db_url.as_deref().ok_or("db_url is not configured")?;
+	let keys = trusted_keys(config)?;
+	let client = reqwest::Client::new();
+	let bytes = fetch(&client, manifest_url).await?;
+	let signature = fetch(&client, &format!("{}.sig", manifest_url)).await?;
+	verify_manifest(&keys, &bytes, &signature)?;
+	let manifest: Manifest =
+		serde_json::from_slice(&bytes).map_err(|e| format!("Invalid dataset manifest {}: {}", manifest_url, e))?;
+
+	std::fs::create_dir_all(&config.db_dir)?;
+	let mut lock = read_lock(&config.db_dir)?;
+	let mut updated = Vec::new();
+	for entry in &manifest.datasets {
+		validate_entry(entry)?;
+		let sha256 = entry.sha256.to_lowercase();
+		let path = config.db_dir.join(&entry.file);
+		if lock.iter().any(|d| d.name == entry.name && d.sha256 == sha256)
+			&& digest_file(&path).as_ref() == Some(&sha256)
+		{
+			continue;
+		}
+
+		let url = resolve(manifest_url, &entry.url);
+		if scheme(&url) != scheme(manifest_url) {
+			return Err(format!("Dataset {} is at {}, which does not use the manifest's scheme", entry.name, url).into());
+		}
+		info!("Downloading dataset {} from {}", entry.name, url);
+		let bytes = fetch(&client, &url).await?;
+		if hex::encode(Sha256::digest(&bytes)) != sha256 {
+			return Err(format!("Digest mismatch for dataset {}", entry.name).into());
+		}
+		// Write beside the cached copy and rename, so a failed write never leaves a partial file
+		let partial = config.db_dir.join(format!("{}.partial", entry.file));
+		std::fs::write(&partial, &bytes)?;
+		std::fs::rename(&partial, &path)?;
+
+		let cached = CachedDataset {
+			name: entry.name.clone(),
+			file: entry.file.clone(),
+			sha256,
+			published: entry.published,
+			updated: Utc::now(),
+		};
+		lock.retain(|d| d.name != cached.name);
+		lock.push(cached.clone());
+		std::fs::write(config.db_dir.join(LOCK_FILE), serde_json::to_string_pretty(&lock)?)?;
+		updated.push(cached);
+	}
+	Ok(updated)
+}
+
+/// Describe a duration in its largest whole units, such as `3d 4h`
+fn age(duration: chrono::Duration) -> String {
+	let hours = duration.num_hours().max(0);
+	match (hours / 24, hours % 24) {
+		(0, 0) => format!("{}m", duration.num_minutes().max(0)),
+		(0, h) => format!("{}h", h),
+		(d, h) => format!("{}d {}h", d, h),
+	}
+}
+
+/// Run a dataset subcommand, printing results to stdout
+pub async fn run(config: &Config, command: DbCommand) -> Result<(), Box<dyn Error>> {
+	match command {
+		DbCommand::Update => {
+			let updated = update(config).await?;
+			if updated.is_empty() {
+				println!("All datasets are up to date");
+			}
+			for cached in updated {
+				println!("Updated {} ({})", cached.name, cached.file);
+			}
+		},
+		DbCommand::Status => {
+			let lock = read_lock(&config.db_dir)?;
+			if lock.is_empty() {
+				println!("No datasets in {}; run `huginn db update`", config.db_dir.display());
+			}
+			let now = Utc::now();
+			for cached in lock {
+				let state = match digest_file(&config.db_dir.join(&cached.file)) {
+					Some(digest) if digest == cached.sha256 => "ok",
+					Some(_) => "modified",
+					None => "missing",
+				};
+				println!(
+					"{:<14} {:<24} {:>8} old  (as of {})  {}",
+					cached.name,
+					cached.file,
+					age(now - cached.as_of()),
+					cached.as_of().format("%Y-%m-%d %H:%M UTC"),
+					state
+				);
+			}
+		},
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_resolve() {
+		assert_eq!(resolve("file:///media/huginn/manifest.json", "vulns.json"), "file:///media/huginn/vulns.json");
+		assert_eq!(
+			resolve("https://db.example.com/v1/manifest.json", "https://cdn.example.com/oui.txt"),
+			"https://cdn.example.com/oui.txt"
+		);
+	}
+
+	#[test]
+	fn test_validate_entry() {
+		let entry = |name: &str, file: &str| ManifestEntry {
+			name: name.to_string(),
+			file: file.to_string(),
+			url: file.to_string(),
+			sha256: String::new(),
+			published: None,
+		};
+		assert!(validate_entry(&entry("vulns", "vulns.json")).is_ok());
+		assert!(validate_entry(&entry("vulns", "../vulns.json")).is_err());
+		assert!(validate_entry(&entry("lock", LOCK_FILE)).is_err());
+		assert!(validate_entry(&entry("", "oui.txt")).is_err());
+	}
+
+	#[test]
+	fn test_age() {
+		assert_eq!(age(chrono::Duration::minutes(42)), "42m");
+		assert_eq!(age(chrono::Duration::hours(5)), "5h");
+		assert_eq!(age(chrono::Duration::hours(76)), "3d 4h");
+	}
+
+	#[tokio::test]
+	async fn test_update() {
+		use ed25519_dalek::{Signer, SigningKey};
+
+		let source = std::env::temp_dir().join(format!("huginn-db-source-{}", std::process::id()));
+		let db_dir = std::env::temp_dir().join(format!("huginn-db-{}", std::process::id()));
+		std::fs::create_dir_all(&source).unwrap();
+		let key = SigningKey::from_bytes(&[7; 32]);
+		let data = b"[]";
+		let publish = |url: &str, sha256: &str| {
+			let manifest = format!(
+				r#"{{"datasets": [{{"name": "vulns", "file": "vulns.json", "url": "{}", "sha256": "{}"}}]}}"#,
+				url, sha256
+			);
+			std::fs::write(source.join("manifest.json"), &manifest).unwrap();
+			std::fs::write(source.join("manifest.json.sig"), hex::encode(key.sign(manifest.as_bytes()).to_bytes())).unwrap();
+		};
+		std::fs::write(source.join("vulns.json"), data).unwrap();
+		publish("vulns.json", &hex::encode(Sha256::digest(data)));
+		let mut config = Config {
+			db_dir: db_dir.clone(),
+			db_url: Some(format!("file://{}", source.join("manifest.json").display())),
+			..Config::default()
+		};
+
+		assert!(update(&config).await.unwrap_err().to_string().contains("db_keys"));
+		config.db_keys = vec![hex::encode(SigningKey::from_bytes(&[9; 32]).verifying_key().as_bytes())];
+		assert!(update(&config).await.unwrap_err().to_string().contains("signature"));
+		config.db_keys = vec![hex::encode(key.verifying_key().as_bytes())];
+
+		assert_eq!(update(&config).await.unwrap().len(), 1);
+		assert!(update(&config).await.unwrap().is_empty());
+		assert_eq!(path(&config, VULNS), Some(db_dir.join("vulns.json")));
+
+		publish("vulns.json", &"0".repeat(64));
+		assert!(update(&config).await.is_err());
+		assert_eq!(std::fs::read(db_dir.join("vulns.json")).unwrap(), data);
+
+		// A manifest read from one scheme cannot send downloads to another
+		publish("https://db.example.com/vulns.json", &"0".repeat(64));
+		assert!(update(&config).await.unwrap_err().to_string().contains("scheme"));
+
+		std::fs::remove_dir_all(&source).unwrap();
+		std::fs::remove_dir_all(&db_dir).unwrap();
+	}
+}
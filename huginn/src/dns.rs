@@ -0,0 +1,80 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! DNS messages over TCP, for the scan types that query name servers directly, and the issues
+//! the domain checks report
+
+use hickory_resolver::proto::op::{Message, MessageType, OpCode, Query};
+use hickory_resolver::proto::rr::{Name, RecordType};
+use huginn_core::error::BoxError;
+#[cfg(feature = "dns-checks")]
+use huginn_core::plugins::{ScanResult, ScanType, Severity};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Send a DNS message over a TCP connection, prefixed with its length
+pub(crate) async fn send<S: AsyncWrite + Unpin>(stream: &mut S, message: &Message) -> Result<(), BoxError> {
+	let bytes = message.to_vec()?;
+	let length = u16::try_from(bytes.len())?;
+	stream.write_all(&length.to_be_bytes()).await?;
+	stream.write_all(&bytes).await?;
+	Ok(())
+}
+
+/// Receive the next DNS message from a TCP connection
+pub(crate) async fn receive<S: AsyncRead + Unpin>(stream: &mut S, timeout: Duration) -> Result<Message, BoxError> {
+	let read = async {
+		let mut length = [0; 2];
+		stream.read_exact(&mut length).await?;
+		let mut bytes = vec![0; usize::from(u16::from_be_bytes(length))];
+		stream.read_exact(&mut bytes).await?;
+		Ok::<_, BoxError>(Message::from_vec(&bytes)?)
+	};
+	tokio::time::timeout(timeout, read).await.map_err(|_| "no answer from the name server")?
+}
+
+/// A query for a name's records of one type, sent without recursion
+pub(crate) fn query(id: u16, name: &Name, record_type: RecordType) -> Message {
+	let mut message = Message::new();
+	message
+		.set_id(id)
+		.set_message_type(MessageType::Query)
+		.set_op_code(OpCode::Query)
+		.add_query(Query::query(name.clone(), record_type));
+	message
+}
+
+/// A weakness a domain check found in the domain's records
+#[cfg(feature = "dns-checks")]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Issue {
+	pub(crate) status: &'static str,
+	pub(crate) severity: Severity,
+	pub(crate) details: String,
+	pub(crate) techniques: &'static [&'static str],
+}
+
+#[cfg(feature = "dns-checks")]
+impl Issue {
+	pub(crate) fn new(
+		status: &'static str,
+		severity: Severity,
+		details: impl Into<String>,
+		techniques: &'static [&'static str],
+	) -> Self {
+		Self {
+			status,
+			severity,
+			details: details.into(),
+			techniques,
+		}
+	}
+
+	/// The issue as a finding of a scan type against the domain
+	pub(crate) fn result(self, domain: &str, scan_type: ScanType) -> ScanResult {
+		ScanResult::new(domain, scan_type.to_string(), self.status)
+			.with_severity(self.severity)
+			.with_details(self.details)
+			.with_techniques(self.techniques.iter().copied())
+	}
+}
@@ -0,0 +1,703 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! DNS hygiene for Huginn
+//!
+//! The `dns_hygiene` scan type checks two ways a domain target's own DNS records can be used
+//! against it. Its CAA records, or those of the nearest parent domain that has them, decide which
+//! certificate authorities may issue certificates for it; without them, any authority may. And
+//! its records may point at something nobody holds any more: a CNAME to a resource deleted at a
+//! cloud service, or to a name that does not exist, and name servers that do not exist or do not
+//! serve the zone. Whoever claims the resource, registers the name, or creates the zone at the
+//! DNS provider then controls what the name serves.
+//!
+//! CNAME targets and name servers are matched against a list of services where anyone can claim
+//! an abandoned resource, each with the text the service answers with for a resource nobody has
+//! claimed, or a note that the CNAME target then does not exist at all. The list is built in, and
+//! can be replaced by a JSON file in the format of the can-i-take-over-xyz fingerprints, named by
+//! `fingerprints` in the `dns_hygiene` section, or else by the `takeover` dataset cached by
+//! `huginn db update`. Address targets, and names that do not exist, are skipped.
+
+use crate::config::Config;
+use crate::dns::{Issue, query, receive, send};
+use async_trait::async_trait;
+use hickory_resolver::proto::op::ResponseCode;
+use hickory_resolver::proto::rr::rdata::CAA;
+use hickory_resolver::proto::rr::{Name, RData, RecordType};
+use hickory_resolver::{ResolveError, TokioResolver};
+use huginn_core::error::{BoxError, HuginnError};
+use huginn_core::plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+use log::debug;
+use serde::Deserialize;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// ATT&CK technique for a subdomain taken over through a dangling record
+const DOMAIN_TAKEOVER: &str = "T1584.001";
+
+/// ATT&CK technique for a zone taken over through its name servers
+const DNS_TAKEOVER: &str = "T1584.002";
+
+/// ATT&CK technique for certificates obtained for the victim's names
+const CERTIFICATES: &str = "T1588.004";
+
+/// Most CNAME records followed from a name
+const MAX_ALIASES: usize = 8;
+
+/// Services where anyone can claim an abandoned resource: their name, the CNAME targets or name
+/// servers that point at them, the text of their answer for an unclaimed resource, and whether an
+/// unclaimed resource leaves the CNAME target without any records
+const FINGERPRINTS: &[(&str, &[&str], &str, bool)] = &[
+	("AWS Elastic Beanstalk", &["elasticbeanstalk.com"], "", true),
+	("AWS Route 53", &["awsdns-"], "", false),
+	(
+		"Microsoft Azure",
+		&[
+			"cloudapp.net",
+			"cloudapp.azure.com",
+			"azurewebsites.net",
+			"blob.core.windows.net",
+			"azure-api.net",
+			"azurehdinsight.net",
+			"azureedge.net",
+			"azurecontainer.io",
+			"database.windows.net",
+			"azuredatalakestore.net",
+			"search.windows.net",
+			"azurecr.io",
+			"redis.cache.windows.net",
+			"servicebus.windows.net",
+			"trafficmanager.net",
+			"visualstudio.com",
+		],
+		"",
+		true,
+	),
+	("Azure DNS", &["azure-dns."], "", false),
+	("Agile CRM", &["agilecrm.com"], "Sorry, this page is no longer available.", false),
+	("Bitbucket", &["bitbucket.io"], "Repository not found", false),
+	("Canny", &["cname.canny.io"], "Company Not Found", false),
+	(
+		"DigitalOcean DNS",
+		&["ns1.digitalocean.com", "ns2.digitalocean.com", "ns3.digitalocean.com"],
+		"",
+		false,
+	),
+	(
+		"Ghost",
+		&["ghost.io"],
+		"The thing you were looking for is no longer here, or never was",
+		false,
+	),
+	("GitHub Pages", &["github.io"], "There isn't a GitHub Pages site here.", false),
+	("Help Scout", &["helpscoutdocs.com"], "No settings were found for this company:", false),
+	("Helpjuice", &["helpjuice.com"], "We could not find what you're looking for.", false),
+	("JetBrains YouTrack", &["myjetbrains.com"], "is not a registered InCloud YouTrack", false),
+	("Ngrok", &["ngrok.io"], "ngrok.io not found", false),
+	("Pantheon", &["pantheonsite.io"], "404 error unknown site!", false),
+	(
+		"Readme.io",
+		&["readme.io"],
+		"The creators of this project are still working on making everything perfect!",
+		false,
+	),
+	("Short.io", &["short.io"], "Link does not exist", false),
+	("Strikingly", &["s.strikinglydns.com"], "PAGE NOT FOUND.", false),
+	("Surge.sh", &["surge.sh"], "project not found", false),
+	(
+		"Tumblr",
+		&["domains.tumblr.com"],
+		"Whatever you were looking for doesn't currently exist at this address",
+		false,
+	),
+	("Uberflip", &["read.uberflip.com"], "The URL you've accessed does not provide a hub.", false),
+	("WordPress.com", &["wordpress.com"], "Do you want to register", false),
+];
+
+/// Settings of the `dns_hygiene` scan type
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DnsHygieneConfig {
+	/// JSON file of takeover fingerprints; the `takeover` dataset, or else a built-in list, when
+	/// unset
+	pub fingerprints: Option<PathBuf>,
+}
+
+/// A service where anyone can claim an abandoned resource, as the can-i-take-over-xyz
+/// fingerprints describe it
+#[derive(Debug, Clone, Deserialize)]
+pub struct Fingerprint {
+	/// Name of the service
+	pub service: String,
+	/// Starts of the CNAME targets or name servers that point at the service, from a label on
+	#[serde(default)]
+	pub cname: Vec<String>,
+	/// Text of the service's answer for a resource nobody has claimed
+	#[serde(default)]
+	pub fingerprint: String,
+	/// Whether an unclaimed resource leaves the CNAME target without any records
+	#[serde(default)]
+	pub nxdomain: bool,
+	/// Whether anyone can claim the service's resources; other entries are ignored
+	#[serde(default = "claimable")]
+	pub vulnerable: bool,
+}
+
+/// Entries of a fingerprint file are takeover-prone unless they say otherwise
+fn claimable() -> bool {
+	true
+}
+
+impl Fingerprint {
+	/// Whether a CNAME target or name server points at the service
+	fn matches(&self, name: &str) -> bool {
+		let name = name.to_ascii_lowercase();
+		let starts = std::iter::once(0).chain(name.match_indices('.').map(|(dot, _)| dot + 1));
+		starts.map(|start| &name[start..]).any(|rest| {
+			self.cname.iter().any(|pattern| {
+				!pattern.is_empty() && rest.starts_with(&pattern.to_ascii_lowercase())
+			})
+		})
+	}
+}
+
+/// The built-in takeover fingerprints
+fn builtin() -> Vec<Fingerprint> {
+	FINGERPRINTS
+		.iter()
+		.map(|(service, cname, fingerprint, nxdomain)| Fingerprint {
+			service: service.to_string(),
+			cname: cname.iter().map(ToString::to_string).collect(),
+			fingerprint: fingerprint.to_string(),
+			nxdomain: *nxdomain,
+			vulnerable: true,
+		})
+		.collect()
+}
+
+/// Summarize the CAA records that apply to a name, found at the name itself or the parent named,
+/// with their weaknesses
+fn caa(records: &[CAA], owner: &str) -> (String, Vec<Issue>) {
+	if records.is_empty() {
+		let issue = Issue::new(
+			"caa_missing",
+			Severity::Low,
+			"no CAA records at the name or any parent domain, so any certificate authority may \
+			issue certificates for it",
+			&[CERTIFICATES],
+		);
+		return ("no CAA records".to_string(), vec![issue]);
+	}
+
+	let mut issues = Vec::new();
+	let properties: Vec<String> = records
+		.iter()
+		.map(|record| {
+			let value = String::from_utf8_lossy(record.raw_value());
+			let value = value.split(';').next().unwrap_or_default().trim();
+			format!("{} {}", record.tag(), if value.is_empty() { "none" } else { value })
+		})
+		.collect();
+	if !records.iter().any(|record| record.tag().is_issue() || record.tag().is_issuewild()) {
+		issues.push(Issue::new(
+			"caa_weak",
+			Severity::Low,
+			format!(
+				"CAA records at {} have no issue property, so any certificate authority may still \
+				issue certificates",
+				owner
+			),
+			&[CERTIFICATES],
+		));
+	}
+	for record in
+		records.iter().filter(|record| record.issuer_critical() && record.tag().is_unknown())
+	{
+		issues.push(Issue::new(
+			"caa_invalid",
+			Severity::Medium,
+			format!(
+				"CAA records at {} mark the unknown property {} critical, so certificate \
+				authorities refuse to issue any certificate",
+				owner,
+				record.tag()
+			),
+			&[],
+		));
+	}
+	(format!("CAA at {}: {}", owner, properties.join(", ")), issues)
+}
+
+/// Where a name's CNAME records lead
+#[derive(Debug, Default, PartialEq)]
+struct Aliases {
+	/// Each name the CNAME records point at, in order
+	targets: Vec<String>,
+	/// Whether the last of them does not exist
+	dangling: bool,
+}
+
+/// The takeover finding for a name whose CNAME records lead to a service, given the page the
+/// name serves
+fn takeover(
+	domain: &str,
+	aliases: &Aliases,
+	fingerprints: &[Fingerprint],
+	page: Option<&str>,
+) -> Option<Issue> {
+	let last = aliases.targets.last()?;
+	let service = fingerprints
+		.iter()
+		.filter(|fingerprint| fingerprint.vulnerable)
+		.find(|fingerprint| aliases.targets.iter().any(|target| fingerprint.matches(target)));
+	match service {
+		Some(service) if aliases.dangling && service.nxdomain => Some(Issue::new(
+			"takeover",
+			Severity::High,
+			format!(
+				"{} is a CNAME for {} at {}, which does not exist, so anyone who creates it there \
+				serves content as {}",
+				domain, last, service.service, domain
+			),
+			&[DOMAIN_TAKEOVER],
+		)),
+		Some(service)
+			if !service.fingerprint.is_empty()
+				&& page.is_some_and(|page| page.contains(&service.fingerprint)) =>
+		{
+			Some(Issue::new(
+				"takeover",
+				Severity::High,
+				format!(
+					"{} is a CNAME for {} at {}, which answers \"{}\", so anyone who claims it \
+					there serves content as {}",
+					domain, last, service.service, service.fingerprint, domain
+				),
+				&[DOMAIN_TAKEOVER],
+			))
+		},
+		_ if aliases.dangling => Some(Issue::new(
+			"dangling_cname",
+			Severity::Medium,
+			format!(
+				"{} is a CNAME for {}, which does not exist, so whoever registers or creates it \
+				serves content as {}",
+				domain, last, domain
+			),
+			&[DOMAIN_TAKEOVER],
+		)),
+		_ => None,
+	}
+}
+
+/// Whether a name server answers authoritatively for the zone
+async fn serves<S: AsyncRead + AsyncWrite + Unpin>(
+	stream: &mut S,
+	apex: &Name,
+	timeout: Duration,
+) -> Result<bool, BoxError> {
+	send(stream, &query(1, apex, RecordType::SOA)).await?;
+	let message = receive(stream, timeout).await?;
+	Ok(message.response_code() == ResponseCode::NoError
+		&& message.authoritative()
+		&& message.answers().iter().any(|record| record.record_type() == RecordType::SOA))
+}
+
+/// Records of a lookup, with a name that has none of the type as empty, or `None` when the name
+/// does not exist
+fn records<T>(
+	lookup: Option<Result<T, ResolveError>>,
+	name: &str,
+	context: &ScanContext,
+) -> Result<Option<Option<T>>, HuginnError> {
+	match lookup {
+		Some(Ok(records)) => Ok(Some(Some(records))),
+		Some(Err(e)) if e.is_nx_domain() => Ok(None),
+		Some(Err(e)) if e.is_no_records_found() => Ok(Some(None)),
+		Some(Err(e)) => {
+			Err(HuginnError::plugin("dns_hygiene", format!("lookup of {} failed: {}", name, e)))
+		},
+		None => Err(context.timed_out(name)),
+	}
+}
+
+/// Follow a name's CNAME records, or `None` when the name does not exist
+async fn aliases(
+	resolver: &TokioResolver,
+	domain: &str,
+	context: &ScanContext,
+) -> Result<Option<Aliases>, HuginnError> {
+	let mut aliases = Aliases::default();
+	let mut current = domain.to_string();
+	while aliases.targets.len() < MAX_ALIASES {
+		let lookup = match records(
+			context.probe(|| resolver.lookup(current.as_str(), RecordType::CNAME)).await,
+			&current,
+			context,
+		)? {
+			None if aliases.targets.is_empty() => return Ok(None),
+			None => {
+				aliases.dangling = true;
+				break;
+			},
+			Some(lookup) => lookup,
+		};
+		let next =
+			lookup.into_iter().flat_map(|lookup| lookup.into_iter()).find_map(
+				|rdata| match rdata {
+					RData::CNAME(cname) => {
+						Some(cname.0.to_ascii().trim_end_matches('.').to_ascii_lowercase())
+					},
+					_ => None,
+				},
+			);
+		match next {
+			Some(next) if !aliases.targets.contains(&next) && next != domain => {
+				aliases.targets.push(next.clone());
+				current = next;
+			},
+			_ => break,
+		}
+	}
+	Ok(Some(aliases))
+}
+
+/// The CAA records that apply to a name, from the name itself or its nearest parent that has
+/// any, with the name they were found at
+async fn relevant_caa(
+	resolver: &TokioResolver,
+	domain: &str,
+	context: &ScanContext,
+) -> Result<(Vec<CAA>, String), HuginnError> {
+	let names =
+		std::iter::once(domain).chain(domain.match_indices('.').map(|(dot, _)| &domain[dot + 1..]));
+	for name in names.filter(|name| name.contains('.')) {
+		let Some(Some(lookup)) =
+			records(context.probe(|| resolver.lookup(name, RecordType::CAA)).await, name, context)?
+		else {
+			continue;
+		};
+		let found: Vec<CAA> = lookup
+			.into_iter()
+			.filter_map(|rdata| match rdata {
+				RData::CAA(caa) => Some(caa),
+				_ => None,
+			})
+			.collect();
+		if !found.is_empty() {
+			return Ok((found, name.to_string()));
+		}
+	}
+	Ok((Vec::new(), domain.to_string()))
+}
+
+/// The page a name serves over HTTP, if any
+async fn page(domain: &str, context: &ScanContext) -> Option<String> {
+	let client = reqwest::Client::builder().timeout(context.timeout).build().ok()?;
+	let fetch = || async { client.get(format!("http://{}/", domain)).send().await?.text().await };
+	match context.probe(fetch).await? {
+		Ok(page) => Some(page),
+		Err(e) => {
+			debug!("Failed to fetch the page of {}: {}", domain, e);
+			None
+		},
+	}
+}
+
+/// Name server findings for a zone: servers that do not exist, and servers that do not serve
+/// the zone at a DNS provider where anyone can create it
+async fn delegation(
+	resolver: &TokioResolver,
+	domain: &str,
+	servers: &[String],
+	fingerprints: &[Fingerprint],
+	context: &ScanContext,
+) -> Result<Vec<Issue>, HuginnError> {
+	let apex = Name::from_ascii(format!("{}.", domain))
+		.map_err(|e| HuginnError::plugin("dns_hygiene", e))?;
+	let mut issues = Vec::new();
+	for server in servers {
+		// A server inside the zone that does not exist is broken, but cannot be registered
+		let inside = server == domain || server.ends_with(&format!(".{}", domain));
+		if records(context.probe(|| resolver.lookup_ip(server.as_str())).await, server, context)?
+			.is_none()
+		{
+			if !inside {
+				issues.push(Issue::new(
+					"dangling_ns",
+					Severity::High,
+					format!(
+						"name server {} of {} does not exist, so whoever registers it answers for \
+						the zone",
+						server, domain
+					),
+					&[DNS_TAKEOVER],
+				));
+			}
+			continue;
+		}
+
+		let served = match context.connect(server, 53).await {
+			Ok(mut stream) => serves(&mut stream, &apex, context.timeout).await,
+			Err(e) => Err(e.into()),
+		};
+		match served {
+			Ok(true) => {},
+			Ok(false) => {
+				let service = fingerprints
+					.iter()
+					.filter(|fingerprint| fingerprint.vulnerable)
+					.find(|fingerprint| fingerprint.matches(server));
+				issues.push(match service {
+					Some(service) => Issue::new(
+						"ns_takeover",
+						Severity::High,
+						format!(
+							"name server {} at {} does not serve {}, so anyone who creates the \
+							zone there answers for it",
+							server, service.service, domain
+						),
+						&[DNS_TAKEOVER],
+					),
+					None => Issue::new(
+						"lame_delegation",
+						Severity::Low,
+						format!(
+							"name server {} does not serve {}, so resolvers asking it get no \
+							answer",
+							server, domain
+						),
+						&[],
+					),
+				});
+			},
+			Err(e) => debug!("Failed to ask name server {} about {}: {}", server, domain, e),
+		}
+	}
+	Ok(issues)
+}
+
+/// DNS hygiene plugin
+pub struct DnsHygienePlugin {
+	config: DnsHygieneConfig,
+}
+
+impl DnsHygienePlugin {
+	/// A plugin matching against the configured fingerprints, or else the cached `takeover`
+	/// dataset
+	#[cfg_attr(not(feature = "intel-enrichment"), allow(unused_variables))]
+	pub fn from_config(config: &Config) -> Self {
+		#[cfg_attr(not(feature = "intel-enrichment"), allow(unused_mut))]
+		let mut settings = config.dns_hygiene.clone();
+		#[cfg(feature = "intel-enrichment")]
+		if settings.fingerprints.is_none() {
+			settings.fingerprints = crate::db::path(config, crate::db::TAKEOVER);
+		}
+		Self { config: settings }
+	}
+
+	/// Takeover fingerprints: the configured file, or the built-in list
+	fn fingerprints(&self) -> Result<Vec<Fingerprint>, HuginnError> {
+		let Some(path) = &self.config.fingerprints else {
+			return Ok(builtin());
+		};
+		let text = std::fs::read_to_string(path).map_err(|e| {
+			HuginnError::plugin(
+				"dns_hygiene",
+				format!("Failed to read takeover fingerprints {}: {}", path.display(), e),
+			)
+		})?;
+		serde_json::from_str(&text).map_err(|e| {
+			HuginnError::plugin(
+				"dns_hygiene",
+				format!("Invalid takeover fingerprints {}: {}", path.display(), e),
+			)
+		})
+	}
+}
+
+#[async_trait]
+impl Plugin for DnsHygienePlugin {
+	fn name(&self) -> String {
+		"DNS Hygiene Checker".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::DnsHygiene
+	}
+
+	fn description(&self) -> String {
+		"Checks the CAA records of domain targets and finds dangling CNAME and NS records open to \
+		takeover".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&["dns_hygiene", "timeout", "retries", "scan_delay", "plugin_timing"]
+	}
+
+	fn probes_per_target(&self, _context: &ScanContext) -> usize {
+		// The CNAME, CAA, and NS lookups and the page fetch, and about two for each name server
+		8
+	}
+
+	async fn scan(
+		&self,
+		target: &str,
+		context: &ScanContext,
+	) -> Result<Vec<ScanResult>, HuginnError> {
+		if target.parse::<IpAddr>().is_ok() {
+			debug!("Skipping DNS hygiene of {}: not a domain", target);
+			return Ok(Vec::new());
+		}
+		let domain = target.trim_end_matches('.').to_ascii_lowercase();
+		let fingerprints = self.fingerprints()?;
+		let resolver = TokioResolver::builder_tokio()
+			.map_err(|e| HuginnError::plugin("dns_hygiene", e))?
+			.build();
+
+		let Some(aliases) = aliases(&resolver, &domain, context).await? else {
+			debug!("Skipping DNS hygiene of {}: the name does not exist", domain);
+			return Ok(Vec::new());
+		};
+		let (authorizations, owner) = relevant_caa(&resolver, &domain, context).await?;
+		let (summary, mut issues) = caa(&authorizations, &owner);
+
+		if !aliases.targets.is_empty() {
+			// Only a name that leads to a service with a fingerprint needs its page read
+			let fetch = !aliases.dangling
+				&& fingerprints.iter().any(|fingerprint| {
+					fingerprint.vulnerable
+						&& !fingerprint.fingerprint.is_empty()
+						&& aliases.targets.iter().any(|target| fingerprint.matches(target))
+				});
+			let page = if fetch { page(&domain, context).await } else { None };
+			issues.extend(takeover(&domain, &aliases, &fingerprints, page.as_deref()));
+		} else if let Some(Some(lookup)) =
+			records(context.probe(|| resolver.ns_lookup(domain.as_str())).await, &domain, context)?
+		{
+			let servers: Vec<String> = lookup
+				.iter()
+				.map(|ns| ns.0.to_ascii().trim_end_matches('.').to_ascii_lowercase())
+				.collect();
+			issues.extend(delegation(&resolver, &domain, &servers, &fingerprints, context).await?);
+		}
+
+		let mut details = vec![summary];
+		if !aliases.targets.is_empty() {
+			details.push(format!("CNAME for {}", aliases.targets.join(" -> ")));
+		}
+		let checked = ScanResult::new(&domain, ScanType::DnsHygiene.to_string(), "checked")
+			.with_details(details.join("; "));
+		let mut results = vec![checked];
+		for issue in issues {
+			results.push(issue.result(&domain, ScanType::DnsHygiene));
+		}
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hickory_resolver::proto::op::{Message, MessageType};
+	use hickory_resolver::proto::rr::Record;
+	use hickory_resolver::proto::rr::rdata::SOA;
+
+	fn statuses(issues: &[Issue]) -> Vec<(&str, Severity)> {
+		issues.iter().map(|issue| (issue.status, issue.severity)).collect()
+	}
+
+	#[test]
+	fn test_caa() {
+		let issuer =
+			CAA::new_issue(false, Some(Name::from_ascii("letsencrypt.org").unwrap()), Vec::new());
+		let wildcard = CAA::new_issuewild(false, None, Vec::new());
+		let (summary, issues) = caa(&[issuer, wildcard], "example.com");
+		assert_eq!(summary, "CAA at example.com: issue letsencrypt.org, issuewild none");
+		assert!(issues.is_empty());
+
+		assert_eq!(statuses(&caa(&[], "example.com").1), [("caa_missing", Severity::Low)]);
+		let iodef = CAA::new_iodef(false, "mailto:security@example.com".parse().unwrap());
+		assert_eq!(statuses(&caa(&[iodef], "example.com").1), [("caa_weak", Severity::Low)]);
+	}
+
+	#[test]
+	fn test_takeover() {
+		let fingerprints = builtin();
+		let github =
+			fingerprints.iter().find(|fingerprint| fingerprint.service == "GitHub Pages").unwrap();
+		assert!(github.matches("Octocat.GitHub.io"));
+		assert!(!github.matches("notgithub.io"));
+		assert!(
+			fingerprints.iter().any(|fingerprint| fingerprint.matches("ns-1536.awsdns-00.co.uk"))
+		);
+
+		let pages = Aliases { targets: vec!["octocat.github.io".to_string()], dangling: false };
+		let unclaimed = "<h1>404</h1><p>There isn't a GitHub Pages site here.</p>";
+		let issue = takeover("docs.example.com", &pages, &fingerprints, Some(unclaimed)).unwrap();
+		assert_eq!((issue.status, issue.severity), ("takeover", Severity::High));
+		assert!(issue.details.starts_with(
+			"docs.example.com is a CNAME for octocat.github.io at GitHub Pages, which answers"
+		));
+		assert_eq!(
+			takeover("docs.example.com", &pages, &fingerprints, Some("<h1>Docs</h1>")),
+			None
+		);
+
+		let azure = Aliases { targets: vec!["shop.cloudapp.net".to_string()], dangling: true };
+		assert_eq!(
+			takeover("shop.example.com", &azure, &fingerprints, None).unwrap().status,
+			"takeover"
+		);
+		let unknown = Aliases { targets: vec!["old.example.net".to_string()], dangling: true };
+		assert_eq!(
+			takeover("www.example.com", &unknown, &fingerprints, None).unwrap().status,
+			"dangling_cname"
+		);
+	}
+
+	#[test]
+	fn test_fingerprint_file() {
+		let json = r#"[
+			{"cname": ["github.io"], "fingerprint": "There isn't a GitHub Pages site here.",
+				"nxdomain": false, "service": "Github", "status": "Vulnerable", "vulnerable": true},
+			{"cname": ["cloudfront.net"], "fingerprint": "Bad request", "nxdomain": false,
+				"service": "AWS/CloudFront", "status": "Not vulnerable", "vulnerable": false}
+		]"#;
+		let fingerprints: Vec<Fingerprint> = serde_json::from_str(json).unwrap();
+		let cloudfront =
+			Aliases { targets: vec!["d111111abcdef8.cloudfront.net".to_string()], dangling: false };
+		assert_eq!(
+			takeover("cdn.example.com", &cloudfront, &fingerprints, Some("Bad request")),
+			None
+		);
+	}
+
+	#[tokio::test]
+	async fn test_serves() {
+		let apex = Name::from_ascii("example.com.").unwrap();
+		for (code, authoritative, expected) in
+			[(ResponseCode::NoError, true, true), (ResponseCode::Refused, false, false)]
+		{
+			let (mut client, mut server) = tokio::io::duplex(4096);
+			let zone = apex.clone();
+			tokio::spawn(async move {
+				let request = receive(&mut server, Duration::from_secs(1)).await.unwrap();
+				let mut response = Message::new();
+				response
+					.set_id(request.id())
+					.set_message_type(MessageType::Response)
+					.set_response_code(code)
+					.set_authoritative(authoritative);
+				if code == ResponseCode::NoError {
+					let soa = SOA::new(zone.clone(), zone.clone(), 1, 3600, 600, 86400, 300);
+					response.add_answer(Record::from_rdata(zone, 300, RData::SOA(soa)));
+				}
+				send(&mut server, &response).await.unwrap();
+			});
+			assert_eq!(serves(&mut client, &apex, Duration::from_secs(1)).await.unwrap(), expected);
+		}
+	}
+}
@@ -0,0 +1,652 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! DNSSEC posture for Huginn
+//!
+//! The `dnssec` scan type checks whether each domain target that is a zone of its own is signed,
+//! and whether resolvers can trust what it signs. The zone's keys and the signatures over its
+//! keys and SOA record are read from its own name servers, and the DS records that delegate trust
+//! to it from its parent, so each link of the chain can be checked on its own: a DS record that
+//! matches one of the zone's keys, a key set signed by that key, and zone data signed by one of
+//! the keys, each signature verified and within its validity period. The zone's SOA record is
+//! also looked up through a validating resolver, which follows the chain of trust from the root.
+//! The zone is reported as `signed` or `unsigned` with a summary of its keys and the resolver's
+//! verdict, along with a finding for each broken link. Address targets, names that are not zones,
+//! and domains that do not exist are skipped.
+
+use crate::dns::{Issue, query, receive, send};
+use async_trait::async_trait;
+use chrono::DateTime;
+use hickory_resolver::proto::dnssec::rdata::{DNSKEY, DNSSECRData, DS, RRSIG};
+use hickory_resolver::proto::dnssec::{Proof, Verifier};
+use hickory_resolver::proto::op::{Edns, Message, ResponseCode};
+use hickory_resolver::proto::rr::{DNSClass, Name, RData, Record, RecordType};
+use hickory_resolver::{ResolveError, TokioResolver};
+use huginn_core::error::{BoxError, HuginnError};
+use huginn_core::plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+use log::debug;
+use std::net::IpAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// ATT&CK technique for answers forged on their way to a resolver
+const INTERCEPTION: &str = "T1557";
+
+/// How long before it expires a signature is worth warning about, in seconds
+const EXPIRY_WARNING: i64 = 3 * 24 * 60 * 60;
+
+/// What the zone's name servers and its parent publish about its signing
+#[derive(Debug, Default)]
+struct Evidence {
+	/// The zone's DNSKEY records
+	keys: Vec<Record>,
+	/// The zone's SOA records
+	soa: Vec<Record>,
+	/// Signatures over the zone's DNSKEY and SOA records
+	signatures: Vec<RRSIG>,
+	/// The DS records for the zone at its parent
+	delegation: Vec<DS>,
+}
+
+/// Seconds from `now` to a time in DNSSEC serial arithmetic, negative when it has passed
+fn offset(now: u32, time: u32) -> i64 {
+	i64::from(time.wrapping_sub(now) as i32)
+}
+
+/// A time `seconds` from `now`, for details
+fn date(now: u32, seconds: i64) -> String {
+	DateTime::from_timestamp(i64::from(now) + seconds, 0)
+		.map_or_else(String::new, |time| time.format("%Y-%m-%d %H:%M UTC").to_string())
+}
+
+/// Key tag of a DNSKEY, as its signatures and DS records name it
+fn tag(key: &DNSKEY) -> u16 {
+	key.calculate_key_tag().unwrap_or_default()
+}
+
+/// The DNSKEYs among the zone's records
+fn keys(records: &[Record]) -> Vec<&DNSKEY> {
+	records
+		.iter()
+		.filter_map(|record| match record.data() {
+			RData::DNSSEC(DNSSECRData::DNSKEY(key)) => Some(key),
+			_ => None,
+		})
+		.collect()
+}
+
+/// The signatures among a response's records
+fn signatures(records: &[Record]) -> impl Iterator<Item = RRSIG> + '_ {
+	records.iter().filter_map(|record| match record.data() {
+		RData::DNSSEC(DNSSECRData::RRSIG(rrsig)) => Some(rrsig.clone()),
+		_ => None,
+	})
+}
+
+/// Whether one of the keys made a signature over the records
+fn verifies(apex: &Name, keys: &[&DNSKEY], signature: &RRSIG, records: &[Record]) -> bool {
+	keys.iter()
+		.filter(|key| tag(key) == signature.key_tag() && key.algorithm() == signature.algorithm())
+		.any(|key| key.verify_rrsig(apex, DNSClass::IN, signature, records.iter()).is_ok())
+}
+
+/// Check the signatures over one of the zone's record sets, returning the latest expiry of those
+/// that are valid now, in seconds from now
+fn check_signed(
+	apex: &Name,
+	keys: &[&DNSKEY],
+	evidence: &Evidence,
+	record_type: RecordType,
+	now: u32,
+	issues: &mut Vec<Issue>,
+) -> Option<i64> {
+	let records = if record_type == RecordType::DNSKEY { &evidence.keys } else { &evidence.soa };
+	let covering: Vec<&RRSIG> =
+		evidence.signatures.iter().filter(|rrsig| rrsig.type_covered() == record_type).collect();
+	if covering.is_empty() {
+		issues.push(Issue::new(
+			"rrsig_missing",
+			Severity::High,
+			format!(
+				"the zone's {} records are not signed, so validating resolvers reject them",
+				record_type
+			),
+			&[],
+		));
+		return None;
+	}
+
+	let verified: Vec<&RRSIG> =
+		covering.into_iter().filter(|rrsig| verifies(apex, keys, rrsig, records)).collect();
+	let current = verified
+		.iter()
+		.filter(|rrsig| offset(now, rrsig.sig_inception().get()) <= 0)
+		.map(|rrsig| offset(now, rrsig.sig_expiration().get()))
+		.filter(|&expiry| expiry > 0)
+		.max();
+	if current.is_some() {
+		return current;
+	}
+	let issue = match verified.iter().max_by_key(|rrsig| offset(now, rrsig.sig_expiration().get()))
+	{
+		Some(rrsig) if offset(now, rrsig.sig_expiration().get()) <= 0 => Issue::new(
+			"rrsig_expired",
+			Severity::High,
+			format!(
+				"the signature over the zone's {} records by key {} expired on {}, so validating \
+				resolvers reject them",
+				record_type,
+				rrsig.key_tag(),
+				date(now, offset(now, rrsig.sig_expiration().get()))
+			),
+			&[],
+		),
+		Some(rrsig) => Issue::new(
+			"rrsig_not_yet_valid",
+			Severity::High,
+			format!(
+				"the signature over the zone's {} records by key {} is not valid until {}, so \
+				validating resolvers reject them",
+				record_type,
+				rrsig.key_tag(),
+				date(now, offset(now, rrsig.sig_inception().get()))
+			),
+			&[],
+		),
+		None => Issue::new(
+			"rrsig_invalid",
+			Severity::High,
+			format!(
+				"no signature over the zone's {} records verifies against its keys, so validating \
+				resolvers reject them",
+				record_type
+			),
+			&[],
+		),
+	};
+	issues.push(issue);
+	None
+}
+
+/// Check each link of the zone's chain of trust, returning a summary of its signing and the
+/// broken links
+fn assess(apex: &Name, evidence: &Evidence, now: u32) -> (String, Vec<Issue>) {
+	let keys = keys(&evidence.keys);
+	let mut issues = Vec::new();
+	if keys.is_empty() {
+		if evidence.delegation.is_empty() {
+			issues.push(Issue::new(
+				"unsigned",
+				Severity::Low,
+				"zone is not signed with DNSSEC, so resolvers cannot tell its answers from forged \
+				ones",
+				&[INTERCEPTION],
+			));
+		} else {
+			issues.push(Issue::new(
+				"ds_mismatch",
+				Severity::High,
+				format!(
+					"the parent publishes {} DS records for the zone, but the zone has no keys, so \
+					validating resolvers reject every answer from it",
+					evidence.delegation.len()
+				),
+				&[],
+			));
+		}
+		return ("not signed".to_string(), issues);
+	}
+
+	let mut summary: Vec<String> = keys
+		.iter()
+		.map(|key| {
+			format!(
+				"{} {} {}",
+				if key.is_key_signing_key() { "KSK" } else { "ZSK" },
+				tag(key),
+				key.algorithm()
+			)
+		})
+		.collect();
+	let anchors: Vec<&DNSKEY> = keys
+		.iter()
+		.copied()
+		.filter(|key| evidence.delegation.iter().any(|ds| ds.covers(apex, key).unwrap_or(false)))
+		.collect();
+	if evidence.delegation.is_empty() {
+		summary.push("no DS at the parent".to_string());
+		issues.push(Issue::new(
+			"ds_missing",
+			Severity::Medium,
+			"zone is signed, but its parent publishes no DS record for it, so no resolver can \
+			validate its signatures and its answers can still be forged",
+			&[INTERCEPTION],
+		));
+	} else if anchors.is_empty() {
+		let tags: Vec<String> =
+			evidence.delegation.iter().map(|ds| ds.key_tag().to_string()).collect();
+		summary.push(format!("DS {} at the parent", tags.join(", ")));
+		issues.push(Issue::new(
+			"ds_mismatch",
+			Severity::High,
+			format!(
+				"none of the DS records at the parent, for keys {}, matches a key of the zone, so \
+				validating resolvers reject every answer from it",
+				tags.join(", ")
+			),
+			&[],
+		));
+	} else {
+		let tags: Vec<String> = anchors.iter().map(|key| tag(key).to_string()).collect();
+		summary.push(format!("DS {} at the parent", tags.join(", ")));
+		// Trust passes from the DS record only through the key set signed by the key it matches
+		let signed_by_anchor = evidence
+			.signatures
+			.iter()
+			.filter(|rrsig| rrsig.type_covered() == RecordType::DNSKEY)
+			.any(|rrsig| verifies(apex, &anchors, rrsig, &evidence.keys));
+		if !signed_by_anchor {
+			issues.push(Issue::new(
+				"chain_broken",
+				Severity::High,
+				format!(
+					"the zone's keys are not signed by key {}, which the DS record at the parent \
+					points to, so validating resolvers reject every answer from it",
+					tags.join(", ")
+				),
+				&[],
+			));
+		}
+	}
+
+	let expiries: Vec<i64> = [RecordType::DNSKEY, RecordType::SOA]
+		.into_iter()
+		.filter_map(|record_type| {
+			check_signed(apex, &keys, evidence, record_type, now, &mut issues)
+		})
+		.collect();
+	if let Some(&expiry) = expiries.iter().min() {
+		summary.push(format!("signatures valid until {}", date(now, expiry)));
+		if expiry < EXPIRY_WARNING && expiries.len() == 2 {
+			issues.push(Issue::new(
+				"rrsig_expiring",
+				Severity::Medium,
+				format!(
+					"the zone's signatures expire on {}, and validating resolvers will reject its \
+					answers unless it is signed again before then",
+					date(now, expiry)
+				),
+				&[],
+			));
+		}
+	}
+	(format!("signed with {}", summary.join(", ")), issues)
+}
+
+/// Records of a lookup, with a name that has none of the type as empty, or `None` when the name
+/// does not exist
+fn records<T>(
+	lookup: Option<Result<T, ResolveError>>,
+	name: &str,
+	context: &ScanContext,
+) -> Result<Option<Option<T>>, HuginnError> {
+	match lookup {
+		Some(Ok(records)) => Ok(Some(Some(records))),
+		Some(Err(e)) if e.is_nx_domain() => Ok(None),
+		Some(Err(e)) if e.is_no_records_found() => Ok(Some(None)),
+		Some(Err(e)) => {
+			Err(HuginnError::plugin("dnssec", format!("lookup of {} failed: {}", name, e)))
+		},
+		None => Err(context.timed_out(name)),
+	}
+}
+
+/// A query for a name's records of one type and the signatures over them
+fn signed_query(id: u16, name: &Name, record_type: RecordType) -> Message {
+	let mut message = query(id, name, record_type);
+	let mut edns = Edns::new();
+	edns.set_dnssec_ok(true).set_max_payload(4096);
+	message.set_edns(edns);
+	message
+}
+
+/// The zone's keys and SOA records, with the signatures over them, from the first of its name
+/// servers that answers
+async fn fetch(
+	apex: &Name,
+	servers: &[String],
+	context: &ScanContext,
+) -> Result<Evidence, BoxError> {
+	let mut last = None;
+	for server in servers {
+		let fetched = async {
+			let mut stream = context.connect(server, 53).await?;
+			let mut evidence = Evidence::default();
+			for (id, record_type) in [(1, RecordType::DNSKEY), (2, RecordType::SOA)] {
+				send(&mut stream, &signed_query(id, apex, record_type)).await?;
+				let message = receive(&mut stream, context.timeout).await?;
+				if message.response_code() != ResponseCode::NoError {
+					return Err(format!(
+						"name server {} answered {}",
+						server,
+						message.response_code()
+					)
+					.into());
+				}
+				let records: Vec<Record> = message
+					.answers()
+					.iter()
+					.filter(|record| record.name() == apex)
+					.cloned()
+					.collect();
+				evidence.signatures.extend(signatures(&records));
+				let found =
+					records.into_iter().filter(|record| record.record_type() == record_type);
+				if record_type == RecordType::DNSKEY {
+					evidence.keys.extend(found)
+				} else {
+					evidence.soa.extend(found)
+				}
+			}
+			Ok::<_, BoxError>(evidence)
+		};
+		match fetched.await {
+			Ok(evidence) => return Ok(evidence),
+			Err(e) => {
+				debug!("DNSSEC records of {} from {} failed: {}", apex, server, e);
+				last = Some(e);
+			},
+		}
+	}
+	Err(last.unwrap_or_else(|| "the zone has no name servers".into()))
+}
+
+/// DNSSEC posture plugin
+pub struct DnssecPlugin;
+
+#[async_trait]
+impl Plugin for DnssecPlugin {
+	fn name(&self) -> String {
+		"DNSSEC Validator".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::Dnssec
+	}
+
+	fn description(&self) -> String {
+		"Checks whether domain targets are signed with DNSSEC and validates their chain of trust"
+			.to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&["timeout", "retries", "scan_delay", "plugin_timing"]
+	}
+
+	fn probes_per_target(&self, _context: &ScanContext) -> usize {
+		// The NS and DS lookups, the DNSKEY and SOA queries, and the validated SOA lookup
+		5
+	}
+
+	async fn scan(
+		&self,
+		target: &str,
+		context: &ScanContext,
+	) -> Result<Vec<ScanResult>, HuginnError> {
+		if target.parse::<IpAddr>().is_ok() {
+			debug!("Skipping DNSSEC of {}: not a domain", target);
+			return Ok(Vec::new());
+		}
+		let domain = target.trim_end_matches('.').to_ascii_lowercase();
+		let apex = Name::from_ascii(format!("{}.", domain))
+			.map_err(|e| HuginnError::plugin("dnssec", e))?;
+		let resolver =
+			TokioResolver::builder_tokio().map_err(|e| HuginnError::plugin("dnssec", e))?.build();
+
+		let servers: Vec<String> = match records(
+			context.probe(|| resolver.ns_lookup(apex.clone())).await,
+			&domain,
+			context,
+		)? {
+			None => {
+				debug!("Skipping DNSSEC of {}: the domain does not exist", domain);
+				return Ok(Vec::new());
+			},
+			Some(None) => {
+				debug!("Skipping DNSSEC of {}: not a zone", domain);
+				return Ok(Vec::new());
+			},
+			Some(Some(lookup)) => {
+				lookup.iter().map(|ns| ns.0.to_ascii().trim_end_matches('.').to_string()).collect()
+			},
+		};
+		let mut evidence =
+			fetch(&apex, &servers, context).await.map_err(|e| HuginnError::plugin("dnssec", e))?;
+		if let Some(Some(lookup)) = records(
+			context.probe(|| resolver.lookup(apex.clone(), RecordType::DS)).await,
+			&domain,
+			context,
+		)? {
+			evidence.delegation = lookup
+				.record_iter()
+				.filter_map(|record| match record.data() {
+					RData::DNSSEC(DNSSECRData::DS(ds)) => Some(ds.clone()),
+					_ => None,
+				})
+				.collect();
+		}
+
+		// The validating resolver marks the answer with its verdict on the whole chain from the
+		// root
+		let mut validating =
+			TokioResolver::builder_tokio().map_err(|e| HuginnError::plugin("dnssec", e))?;
+		validating.options_mut().validate = true;
+		let validating = validating.build();
+		let proof =
+			match context.probe(|| validating.lookup(apex.clone(), RecordType::SOA)).await {
+				Some(Ok(lookup)) => lookup
+					.record_iter()
+					.find(|record| record.record_type() == RecordType::SOA)
+					.map(Record::proof),
+				Some(Err(e)) => {
+					debug!("Validated lookup of {} failed: {}", domain, e);
+					None
+				},
+				None => None,
+			}
+			.unwrap_or(Proof::Indeterminate);
+
+		let now = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map(|d| d.as_secs() as u32)
+			.unwrap_or_default();
+		let (summary, mut issues) = assess(&apex, &evidence, now);
+		if proof == Proof::Bogus && !issues.iter().any(|issue| issue.severity == Severity::High) {
+			issues.push(Issue::new(
+				"bogus",
+				Severity::High,
+				"a validating resolver rejects the zone's answers as bogus, so resolvers that \
+				validate cannot reach it",
+				&[],
+			));
+		}
+		let status = if keys(&evidence.keys).is_empty() { "unsigned" } else { "signed" };
+		let details =
+			format!("{}; chain of trust {}", summary, proof.to_string().to_ascii_lowercase());
+
+		let mut results = vec![
+			ScanResult::new(&domain, ScanType::Dnssec.to_string(), status).with_details(details),
+		];
+		for issue in issues {
+			results.push(issue.result(&domain, ScanType::Dnssec));
+		}
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hickory_resolver::proto::dnssec::crypto::Ed25519SigningKey;
+	use hickory_resolver::proto::dnssec::{DigestType, SigningKey, TBS};
+	use hickory_resolver::proto::rr::rdata::SOA;
+
+	/// Seconds since the epoch the tests run at
+	const NOW: u32 = 1_800_000_000;
+
+	/// A signed zone: a key signing key, a zone signing key, and the signatures over its keys and
+	/// SOA record, valid from `inception` to `expiration`
+	fn zone(apex: &Name, inception: u32, expiration: u32) -> (Evidence, DNSKEY) {
+		let signer = |key: &Ed25519SigningKey, flags: bool| {
+			DNSKEY::new(true, flags, false, key.to_public_key().unwrap())
+		};
+		let ksk =
+			Ed25519SigningKey::from_pkcs8(&Ed25519SigningKey::generate_pkcs8().unwrap()).unwrap();
+		let zsk =
+			Ed25519SigningKey::from_pkcs8(&Ed25519SigningKey::generate_pkcs8().unwrap()).unwrap();
+		let (ksk_key, zsk_key) = (signer(&ksk, true), signer(&zsk, false));
+		let keys = vec![
+			Record::from_rdata(
+				apex.clone(),
+				3600,
+				RData::DNSSEC(DNSSECRData::DNSKEY(ksk_key.clone())),
+			),
+			Record::from_rdata(
+				apex.clone(),
+				3600,
+				RData::DNSSEC(DNSSECRData::DNSKEY(zsk_key.clone())),
+			),
+		];
+		let soa = SOA::new(
+			Name::from_ascii("ns1.example.com.").unwrap(),
+			Name::from_ascii("hostmaster.example.com.").unwrap(),
+			1,
+			3600,
+			600,
+			86400,
+			300,
+		);
+		let soa = vec![Record::from_rdata(apex.clone(), 3600, RData::SOA(soa))];
+		let sign = |key: &Ed25519SigningKey, public: &DNSKEY, kind, records: &[Record]| {
+			let unsigned = RRSIG::new(
+				kind,
+				public.algorithm(),
+				2,
+				3600,
+				expiration,
+				inception,
+				tag(public),
+				apex.clone(),
+				Vec::new(),
+			);
+			let tbs = TBS::from_sig(apex, DNSClass::IN, &unsigned, records.iter()).unwrap();
+			let signature = key.sign(&tbs).unwrap();
+			RRSIG::new(
+				kind,
+				public.algorithm(),
+				2,
+				3600,
+				expiration,
+				inception,
+				tag(public),
+				apex.clone(),
+				signature,
+			)
+		};
+		let signatures = vec![
+			sign(&ksk, &ksk_key, RecordType::DNSKEY, &keys),
+			sign(&zsk, &zsk_key, RecordType::SOA, &soa),
+		];
+		let evidence = Evidence { keys, soa, signatures, delegation: Vec::new() };
+		(evidence, ksk_key)
+	}
+
+	fn statuses(issues: &[Issue]) -> Vec<(&str, Severity)> {
+		issues.iter().map(|issue| (issue.status, issue.severity)).collect()
+	}
+
+	#[test]
+	fn test_assess() {
+		let apex = Name::from_ascii("example.com.").unwrap();
+		let (mut evidence, ksk) = zone(&apex, NOW - 86400, NOW + 30 * 86400);
+		let (summary, issues) = assess(&apex, &evidence, NOW);
+		assert!(
+			summary.starts_with(&format!("signed with KSK {} ED25519", tag(&ksk))),
+			"{}",
+			summary
+		);
+		assert_eq!(statuses(&issues), [("ds_missing", Severity::Medium)]);
+
+		evidence.delegation = vec![DS::new(
+			tag(&ksk),
+			ksk.algorithm(),
+			DigestType::SHA256,
+			ksk.to_digest(&apex, DigestType::SHA256).unwrap().as_ref().to_vec(),
+		)];
+		let (summary, issues) = assess(&apex, &evidence, NOW);
+		assert!(summary.contains(&format!("DS {} at the parent", tag(&ksk))));
+		assert!(summary.ends_with("signatures valid until 2027-02-14 08:00 UTC"), "{}", summary);
+		assert!(issues.is_empty());
+
+		// A DS record that matches none of the zone's keys breaks the chain at the parent
+		evidence.delegation =
+			vec![DS::new(tag(&ksk), ksk.algorithm(), DigestType::SHA256, vec![0; 32])];
+		assert_eq!(statuses(&assess(&apex, &evidence, NOW).1), [("ds_mismatch", Severity::High)]);
+
+		// A tampered signature no longer verifies
+		evidence.delegation.clear();
+		let soa = &evidence.signatures[1];
+		let mut forged = soa.sig().to_vec();
+		forged[0] ^= 1;
+		evidence.signatures[1] = RRSIG::new(
+			RecordType::SOA,
+			soa.algorithm(),
+			2,
+			3600,
+			soa.sig_expiration().get(),
+			soa.sig_inception().get(),
+			soa.key_tag(),
+			apex.clone(),
+			forged,
+		);
+		assert_eq!(
+			statuses(&assess(&apex, &evidence, NOW).1),
+			[("ds_missing", Severity::Medium), ("rrsig_invalid", Severity::High)]
+		);
+
+		let unsigned = Evidence::default();
+		assert_eq!(statuses(&assess(&apex, &unsigned, NOW).1), [("unsigned", Severity::Low)]);
+	}
+
+	#[test]
+	fn test_expiry() {
+		let apex = Name::from_ascii("example.com.").unwrap();
+		let (evidence, _) = zone(&apex, NOW - 30 * 86400, NOW - 86400);
+		let issues = assess(&apex, &evidence, NOW).1;
+		assert_eq!(
+			statuses(&issues)[1..],
+			[("rrsig_expired", Severity::High), ("rrsig_expired", Severity::High)]
+		);
+		assert!(
+			issues[1]
+				.details
+				.ends_with("expired on 2027-01-14 08:00 UTC, so validating resolvers reject them"),
+			"{}",
+			issues[1].details
+		);
+
+		let (evidence, _) = zone(&apex, NOW + 86400, NOW + 30 * 86400);
+		assert_eq!(
+			statuses(&assess(&apex, &evidence, NOW).1)[1],
+			("rrsig_not_yet_valid", Severity::High)
+		);
+
+		let (evidence, _) = zone(&apex, NOW - 86400, NOW + 86400);
+		assert_eq!(
+			statuses(&assess(&apex, &evidence, NOW).1)[1],
+			("rrsig_expiring", Severity::Medium)
+		);
+
+		assert_eq!(offset(u32::MAX - 10, 5), 16);
+		assert_eq!(offset(100, 40), -60);
+	}
+}
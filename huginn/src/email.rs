@@ -0,0 +1,219 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Email report delivery for Huginn
+//!
+//! This module sends the run report to the configured recipients over SMTP. The message body
+//! carries the plain text report inline (with an equivalent HTML part), and the report rendered
+//! in the configured format is attached. When `encrypt_output` is set, only the attachment is
+//! sent, encrypted to the configured age recipients.
+
+use crate::config::Config;
+use crate::output;
+use huginn_core::formatters::{Formatter, text::TextFormatter};
+use huginn_core::plugins::ScanResult;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart, header::ContentType};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use log::info;
+use serde::{Deserialize, Deserializer};
+use std::error::Error;
+
+/// How the SMTP connection is secured
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+	/// Upgrade a plain connection with STARTTLS (port 587 by default)
+	#[default]
+	Starttls,
+	/// Connect with implicit TLS (port 465 by default)
+	Tls,
+	/// No encryption; only for local relays
+	None,
+}
+
+/// Email delivery configuration
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct EmailConfig {
+	/// SMTP server host name
+	pub smtp_host: String,
+	/// SMTP server port; defaults to the standard port for the security mode
+	pub smtp_port: Option<u16>,
+	/// Connection security
+	#[serde(default)]
+	pub security: SmtpSecurity,
+	/// SMTP user name
+	pub username: Option<String>,
+	/// SMTP password
+	pub password: Option<String>,
+	/// Sender address
+	pub from: String,
+	/// Recipient addresses, of which there must be at least one
+	#[serde(deserialize_with = "recipients")]
+	pub to: Vec<String>,
+	/// Subject line
+	#[serde(default = "default_subject")]
+	pub subject: String,
+	/// Format of the attached report
+	#[serde(default = "default_attachment_format")]
+	pub attachment_format: String,
+}
+
+/// Refuse an empty recipient list when the configuration is loaded, rather than after the scan
+fn recipients<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+	let to = Vec::<String>::deserialize(deserializer)?;
+	if to.is_empty() {
+		return Err(serde::de::Error::custom("email delivery needs at least one recipient in `to`"));
+	}
+	Ok(to)
+}
+
+fn default_subject() -> String {
+	"Huginn scan report".to_string()
+}
+
+fn default_attachment_format() -> String {
+	"json".to_string()
+}
+
+/// Escape text for inclusion in HTML
+fn escape_html(text: &str) -> String {
+	text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// MIME type of an attached report
+fn content_type(format: &str) -> &'static str {
+	match format {
+		"json" | "json-statistics" => "application/json",
+		"cyclonedx" => "application/vnd.cyclonedx+json",
+		"hosts" => "application/json",
+		"hosts-yaml" => "application/yaml",
+		"dot" => "text/vnd.graphviz",
+		_ => "text/plain; charset=utf-8",
+	}
+}
+
+/// Build the attached report, encrypted when recipients are configured
+fn attachment(email: &EmailConfig, config: &Config, results: &[ScanResult]) -> Result<SinglePart, Box<dyn Error>> {
+	let formatter = output::create(&email.attachment_format, config)?;
+	let filename = format!("huginn-report.{}", output::extension(&formatter.name()));
+	let report = formatter.format(results)?;
+
+	#[cfg(feature = "encryption")]
+	if !config.encrypt_output.is_empty() {
+		let encrypted = output::encrypt::encrypt(&config.encrypt_output, report.as_bytes())?;
+		return Ok(Attachment::new(format!("{}.age", filename))
+			.body(encrypted, ContentType::parse("application/octet-stream")?));
+	}
+	Ok(Attachment::new(filename).body(report, ContentType::parse(content_type(&formatter.name()))?))
+}
+
+/// Build the report message
+fn message(email: &EmailConfig, config: &Config, results: &[ScanResult]) -> Result<Message, Box<dyn Error>> {
+	#[cfg(feature = "encryption")]
+	let encrypted = !config.encrypt_output.is_empty();
+	#[cfg(not(feature = "encryption"))]
+	let encrypted = false;
+
+	let text = if encrypted {
+		"The report is attached, encrypted with age. Decrypt it with `age -d -i <identity file>`.".to_string()
+	} else {
+		TextFormatter.format(results)?
+	};
+	let html = format!("<html><body><pre>{}</pre></body></html>", escape_html(&text));
+	let attachment = attachment(email, config, results)?;
+
+	let mut builder = Message::builder().from(email.from.parse::<Mailbox>()?).subject(&email.subject);
+	for recipient in &email.to {
+		builder = builder.to(recipient.parse::<Mailbox>()?);
+	}
+
+	Ok(builder.multipart(
+		MultiPart::mixed()
+			.multipart(
+				MultiPart::alternative()
+					.singlepart(SinglePart::plain(text))
+					.singlepart(SinglePart::html(html)),
+			)
+			.singlepart(attachment),
+	)?)
+}
+
+/// Send the report to the configured recipients
+pub async fn send(config: &Config, results: &[ScanResult]) -> Result<(), Box<dyn Error>> {
+	let Some(email) = &config.email else {
+		return Ok(());
+	};
+
+	let mut transport = match email.security {
+		SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&email.smtp_host)?,
+		SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&email.smtp_host)?,
+		SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&email.smtp_host),
+	};
+	if let Some(port) = email.smtp_port {
+		transport = transport.port(port);
+	}
+	if let (Some(username), Some(password)) = (&email.username, &email.password) {
+		transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+	}
+
+	transport.build().send(message(email, config, results)?).await?;
+	info!("Emailed report to {} recipients", email.to.len());
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_message_has_attachment() {
+		let email: EmailConfig = serde_json::from_str(
+			r#"{ "smtp_host": "smtp.example.com", "from": "huginn@example.com", "to": ["soc@example.com"] }"#,
+		)
+		.unwrap();
+		assert_eq!(email.security, SmtpSecurity::Starttls);
+
+		let results = vec![ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22).with_details("<banner>")];
+		let formatted = String::from_utf8(message(&email, &Config::default(), &results).unwrap().formatted()).unwrap();
+
+		assert!(formatted.contains("Subject: Huginn scan report"));
+		assert!(formatted.contains("filename=\"huginn-report.json\""));
+		assert!(formatted.contains("&lt;banner&gt;"));
+	}
+
+	#[test]
+	fn test_invalid_address() {
+		let email: EmailConfig =
+			serde_json::from_str(r#"{ "smtp_host": "smtp.example.com", "from": "not an address", "to": ["soc@example.com"] }"#)
+				.unwrap();
+		assert!(message(&email, &Config::default(), &[]).is_err());
+
+		let error = serde_json::from_str::<EmailConfig>(
+			r#"{ "smtp_host": "smtp.example.com", "from": "huginn@example.com", "to": [] }"#,
+		)
+		.unwrap_err();
+		assert!(error.to_string().contains("at least one recipient"));
+	}
+
+	#[cfg(feature = "encryption")]
+	#[test]
+	fn test_encrypted_attachment() {
+		let email: EmailConfig = serde_json::from_str(
+			r#"{ "smtp_host": "smtp.example.com", "from": "huginn@example.com", "to": ["soc@example.com"] }"#,
+		)
+		.unwrap();
+		let identity = age::x25519::Identity::generate();
+		let config = Config {
+			encrypt_output: vec![identity.to_public().to_string().parse().unwrap()],
+			..Config::default()
+		};
+		let results = vec![ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22)];
+		let formatted = String::from_utf8(message(&email, &config, &results).unwrap().formatted()).unwrap();
+
+		assert!(formatted.contains("filename=\"huginn-report.json.age\""));
+		assert!(formatted.contains("encrypted with age"));
+		assert!(!formatted.contains("10.0.0.1"));
+	}
+}
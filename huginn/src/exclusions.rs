@@ -0,0 +1,188 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Do-not-scan registry for Huginn
+//!
+//! Operators add addresses, CIDR blocks, or domains to a persistent exclusion list with
+//! `huginn exclude add`, optionally until a date. Every run skips excluded targets, and records
+//! each skip in the audit log, until the entry expires. A relative `exclusions_file` is kept in
+//! the data directory, so every run finds the same list wherever it is started from.
+
+use crate::audit;
+use crate::config::{self, Config, ExcludeCommand, validate};
+use chrono::{DateTime, NaiveDate, Utc};
+use huginn_core::targets;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// An exclusion list entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exclusion {
+	/// Excluded address, CIDR block, or domain; a domain also excludes its subdomains
+	pub target: String,
+	/// Last day, in UTC, that the exclusion applies; it never expires when unset
+	pub until: Option<NaiveDate>,
+	/// Why the target is excluded
+	pub reason: Option<String>,
+	/// When the entry was added
+	pub added: DateTime<Utc>,
+}
+
+impl Exclusion {
+	/// Whether the exclusion still applies on a day
+	pub fn active_on(&self, day: NaiveDate) -> bool {
+		self.until.is_none_or(|until| day <= until)
+	}
+
+	/// Describe how long the exclusion lasts and why
+	fn describe(&self) -> String {
+		let until = match self.until {
+			Some(until) => format!("until {}", until),
+			None => "indefinitely".to_string(),
+		};
+		match &self.reason {
+			Some(reason) => format!("{} ({})", until, reason),
+			None => until,
+		}
+	}
+}
+
+/// Path of the exclusion list, with a relative `exclusions_file` resolved against the data
+/// directory
+pub fn path(config: &Config) -> PathBuf {
+	config::data_dir().join(&config.exclusions_file)
+}
+
+/// Read the exclusion list, treating a missing file as an empty list
+pub fn load(path: &Path) -> Result<Vec<Exclusion>, Box<dyn Error>> {
+	if !path.exists() {
+		return Ok(Vec::new());
+	}
+	let text = std::fs::read_to_string(path)?;
+	serde_json::from_str(&text).map_err(|e| format!("Invalid exclusion list {}: {}", path.display(), e).into())
+}
+
+/// Write the exclusion list
+fn save(path: &Path, exclusions: &[Exclusion]) -> Result<(), Box<dyn Error>> {
+	if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+		std::fs::create_dir_all(parent)?;
+	}
+	std::fs::write(path, serde_json::to_string_pretty(exclusions)?)?;
+	Ok(())
+}
+
+/// Remove the expanded targets covered by an active exclusion
+pub fn apply(config: &Config, hosts: Vec<String>) -> Result<Vec<String>, Box<dyn Error>> {
+	let today = Utc::now().date_naive();
+	let active: Vec<Exclusion> = load(&path(config))?
+		.into_iter()
+		.filter(|e| e.active_on(today))
+		.collect();
+	if active.is_empty() {
+		return Ok(hosts);
+	}
+
+	let mut allowed = Vec::with_capacity(hosts.len());
+	for host in hosts {
+		match active.iter().find(|e| targets::matches(&e.target, &host)) {
+			Some(exclusion) => {
+				info!("Skipping {}: excluded {}", host, exclusion.describe());
+				let reason = format!("excluded by {} {}", exclusion.target, exclusion.describe());
+				audit::record(config, "excluded", &host, &reason)?;
+			},
+			None => allowed.push(host),
+		}
+	}
+	Ok(allowed)
+}
+
+/// Run an `exclude` subcommand, printing results to stdout
+pub fn run(config: &Config, command: ExcludeCommand) -> Result<(), Box<dyn Error>> {
+	let path = &path(config);
+	let today = Utc::now().date_naive();
+	// Expired entries are dropped whenever the list is rewritten
+	let mut exclusions: Vec<Exclusion> = load(path)?.into_iter().filter(|e| e.active_on(today)).collect();
+
+	match command {
+		ExcludeCommand::Add { target, until, reason } => {
+			validate::check_target(&target)?;
+			if let Some(until) = until
+				&& until < today
+			{
+				return Err(format!("{} is already in the past", until).into());
+			}
+			let exclusion = Exclusion {
+				target,
+				until,
+				reason,
+				added: Utc::now(),
+			};
+			// A target already on the list has its entry replaced rather than listed twice
+			let before = exclusions.len();
+			exclusions.retain(|e| e.target != exclusion.target);
+			let verb = if exclusions.len() < before { "Re-excluded" } else { "Excluded" };
+			println!("{} {} {}", verb, exclusion.target, exclusion.describe());
+			exclusions.push(exclusion);
+			save(path, &exclusions)?;
+		},
+		ExcludeCommand::List => {
+			if exclusions.is_empty() {
+				println!("No active exclusions");
+			}
+			for exclusion in &exclusions {
+				println!("{} {}", exclusion.target, exclusion.describe());
+			}
+		},
+		ExcludeCommand::Remove { target } => {
+			let before = exclusions.len();
+			exclusions.retain(|e| e.target != target);
+			if exclusions.len() == before {
+				return Err(format!("{} is not excluded", target).into());
+			}
+			save(path, &exclusions)?;
+			println!("Removed the exclusion for {}", target);
+		},
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_exclusions_expire() {
+		let path = std::env::temp_dir().join(format!("huginn-exclusions-{}.json", std::process::id()));
+		let _ = std::fs::remove_file(&path);
+		let config = Config {
+			exclusions_file: path.clone(),
+			..Config::default()
+		};
+		let hosts = vec!["10.1.2.3".to_string(), "10.9.0.1".to_string(), "db.example.com".to_string()];
+
+		let add = |target: &str, until: Option<&str>| ExcludeCommand::Add {
+			target: target.to_string(),
+			until: until.map(|u| u.parse().unwrap()),
+			reason: Some("prod freeze".to_string()),
+		};
+		run(&config, add("10.1.2.0/24", None)).unwrap();
+		run(&config, add("example.com", None)).unwrap();
+		run(&config, add("example.com", Some("2999-12-31"))).unwrap();
+		assert_eq!(load(&path).unwrap().len(), 2);
+		assert!(run(&config, add("10.9.0.0/16", Some("2000-01-01"))).is_err());
+		assert_eq!(apply(&config, hosts.clone()).unwrap(), vec!["10.9.0.1"]);
+
+		// Entries that expired since they were added are ignored, then pruned
+		let mut exclusions = load(&path).unwrap();
+		exclusions[1].until = Some("2000-01-01".parse().unwrap());
+		save(&path, &exclusions).unwrap();
+		assert_eq!(apply(&config, hosts.clone()).unwrap(), vec!["10.9.0.1", "db.example.com"]);
+
+		run(&config, ExcludeCommand::Remove { target: "10.1.2.0/24".to_string() }).unwrap();
+		assert!(load(&path).unwrap().is_empty());
+		assert_eq!(apply(&config, hosts.clone()).unwrap(), hosts);
+		std::fs::remove_file(&path).unwrap();
+	}
+}
@@ -0,0 +1,142 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Exit-code policy for Huginn
+//!
+//! `fail_on` (or `--fail-on`) lists conditions that make a scan exit with [`EXIT_CODE`] once its
+//! results are delivered: findings at or above a severity, any open port, or drift from the
+//! baseline. This lets a scan gate a CI/CD pipeline or alert from cron without parsing reports.
+
+use crate::baseline::Drift;
+use huginn_core::plugins::{ScanResult, Severity};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Exit status of a scan whose results meet a `fail_on` condition; errors exit with 1
+pub const EXIT_CODE: i32 = 3;
+
+/// A condition that fails the scan
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum FailOn {
+	/// Any finding at or above the severity
+	Severity(Severity),
+	/// Any open port
+	OpenPort,
+	/// Any difference from the baseline
+	Drift,
+}
+
+impl FromStr for FailOn {
+	type Err = String;
+
+	fn from_str(condition: &str) -> Result<Self, Self::Err> {
+		match condition.trim().to_ascii_lowercase().as_str() {
+			"open-port" | "open_port" => Ok(FailOn::OpenPort),
+			"drift" => Ok(FailOn::Drift),
+			other => other.parse().map(FailOn::Severity).map_err(|_| {
+				format!(
+					"invalid fail-on condition {:?}; expected a severity (info, low, medium, high, critical), open-port, or drift",
+					condition
+				)
+			}),
+		}
+	}
+}
+
+impl TryFrom<String> for FailOn {
+	type Error = String;
+
+	fn try_from(condition: String) -> Result<Self, Self::Error> {
+		condition.parse()
+	}
+}
+
+impl fmt::Display for FailOn {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			FailOn::Severity(severity) => write!(f, "{}", severity),
+			FailOn::OpenPort => write!(f, "open-port"),
+			FailOn::Drift => write!(f, "drift"),
+		}
+	}
+}
+
+/// Refuse conditions that could never be met: drift is only measured against a baseline
+pub fn validate(conditions: &[FailOn], baseline: bool) -> Result<(), String> {
+	if conditions.contains(&FailOn::Drift) && !baseline {
+		return Err("fail_on drift needs a baseline to compare against; set baseline or --baseline".to_string());
+	}
+	Ok(())
+}
+
+/// Describe each condition the results meet
+///
+/// Drift is only measured when a baseline is configured, which [`validate`] requires for it.
+pub fn check(conditions: &[FailOn], results: &[ScanResult], drift: Option<&Drift>) -> Vec<String> {
+	conditions
+		.iter()
+		.filter_map(|condition| match condition {
+			FailOn::Severity(threshold) => {
+				let count = results.iter().filter(|r| r.severity.is_some_and(|s| s >= *threshold)).count();
+				(count > 0).then(|| format!("{} findings at or above {} severity", count, threshold))
+			},
+			FailOn::OpenPort => {
+				let count = results.iter().filter(|r| r.port.is_some() && r.status == "open").count();
+				(count > 0).then(|| format!("{} open ports", count))
+			},
+			FailOn::Drift => drift.filter(|drift| !drift.is_empty()).map(|drift| {
+				format!(
+					"drift from baseline: {} new, {} no longer present",
+					drift.added.len(),
+					drift.removed.len()
+				)
+			}),
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse() {
+		assert_eq!("High".parse::<FailOn>().unwrap(), FailOn::Severity(Severity::High));
+		assert_eq!("open-port".parse::<FailOn>().unwrap(), FailOn::OpenPort);
+		assert_eq!("drift".parse::<FailOn>().unwrap(), FailOn::Drift);
+		assert!("loud".parse::<FailOn>().unwrap_err().contains("open-port"));
+		assert_eq!(FailOn::OpenPort.to_string(), "open-port");
+	}
+
+	#[test]
+	fn test_validate() {
+		assert!(validate(&[FailOn::Drift], true).is_ok());
+		assert!(validate(&[FailOn::OpenPort], false).is_ok());
+		assert!(validate(&[FailOn::OpenPort, FailOn::Drift], false).unwrap_err().contains("needs a baseline"));
+	}
+
+	#[test]
+	fn test_check() {
+		let results = [
+			ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22),
+			ScanResult::new("10.0.0.1", "tcp_connect", "vulnerable").with_severity(Severity::Medium),
+		];
+		assert_eq!(check(&[FailOn::OpenPort], &results, None), ["1 open ports"]);
+		assert_eq!(check(&[FailOn::Severity(Severity::Low)], &results, None).len(), 1);
+		assert!(check(&[FailOn::Severity(Severity::High)], &results, None).is_empty());
+		assert!(check(&[FailOn::OpenPort], &results[1..], None).is_empty());
+
+		let drift = Drift {
+			added: results[..1].to_vec(),
+			removed: Vec::new(),
+		};
+		assert!(check(&[FailOn::Drift], &results, None).is_empty());
+		assert!(check(&[FailOn::Drift], &results, Some(&Drift::default())).is_empty());
+		assert_eq!(
+			check(&[FailOn::Drift], &results, Some(&drift)),
+			["drift from baseline: 1 new, 0 no longer present"]
+		);
+	}
+}
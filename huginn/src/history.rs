@@ -0,0 +1,218 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Run history for Huginn
+//!
+//! When `history_dir` is set, every scan, including each scheduled daemon scan, is stored in a
+//! directory named by its run ID: `run.json` describes the run, `results.json` holds its results,
+//! and `run.log` holds the log lines written while it ran. `huginn history` lists the stored runs
+//! and shows a run's results or log.
+
+use crate::config::{Config, HistoryCommand};
+use chrono::{DateTime, Utc};
+use huginn_core::formatters::Formatter;
+use huginn_core::formatters::text::TextFormatter;
+use huginn_core::plugins::ScanResult;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Summary of a stored run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Run {
+	/// Run ID; also the name of the run's directory
+	pub id: String,
+	/// When the run started
+	pub started: DateTime<Utc>,
+	/// When the run finished
+	pub finished: DateTime<Utc>,
+	/// Targets the run was configured with
+	pub targets: Vec<String>,
+	/// Number of results
+	pub results: usize,
+	/// Why the run failed, if it did
+	pub error: Option<String>,
+}
+
+impl Run {
+	/// Describe the outcome of the run
+	fn outcome(&self) -> String {
+		match &self.error {
+			Some(error) => format!("failed: {}", error),
+			None => format!("{} results", self.results),
+		}
+	}
+}
+
+/// Create a new run directory, named by the start time and numbered when that name is taken
+fn create_run_dir(dir: &Path, started: DateTime<Utc>) -> io::Result<(String, PathBuf)> {
+	fs::create_dir_all(dir)?;
+	let stamp = started.format("%Y%m%dT%H%M%SZ").to_string();
+	for n in 0.. {
+		let id = if n == 0 { stamp.clone() } else { format!("{}-{}", stamp, n) };
+		let path = dir.join(&id);
+		match fs::create_dir(&path) {
+			Ok(()) => return Ok((id, path)),
+			Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+			Err(e) => return Err(e),
+		}
+	}
+	unreachable!("run IDs are never exhausted")
+}
+
+/// Store a finished run with the log lines captured while it ran
+pub fn save(
+	dir: &Path,
+	started: DateTime<Utc>,
+	targets: &[String],
+	outcome: Result<&[ScanResult], String>,
+	log: &[String],
+) -> Result<Run, Box<dyn Error>> {
+	let (id, path) = create_run_dir(dir, started)?;
+	let (results, error) = match outcome {
+		Ok(results) => (results, None),
+		Err(error) => (&[][..], Some(error)),
+	};
+	let run = Run {
+		id,
+		started,
+		finished: Utc::now(),
+		targets: targets.to_vec(),
+		results: results.len(),
+		error,
+	};
+	fs::write(path.join("results.json"), serde_json::to_string_pretty(results)?)?;
+	let mut text = log.join("\n");
+	if !text.is_empty() {
+		text.push('\n');
+	}
+	fs::write(path.join("run.log"), text)?;
+	// Written last, so a run is only listed once it is complete
+	fs::write(path.join("run.json"), serde_json::to_string_pretty(&run)?)?;
+	Ok(run)
+}
+
+/// Directory of a stored run, refusing IDs that would leave the history directory
+fn run_dir(dir: &Path, id: &str) -> Result<PathBuf, Box<dyn Error>> {
+	if id.is_empty() || id.starts_with('.') || id.contains(['/', '\\']) {
+		return Err(format!("Invalid run ID {:?}", id).into());
+	}
+	let path = dir.join(id);
+	if !path.join("run.json").is_file() {
+		return Err(format!("No stored run {}", id).into());
+	}
+	Ok(path)
+}
+
+/// Read a stored run's summary
+fn load(path: &Path) -> Result<Run, Box<dyn Error>> {
+	let file = path.join("run.json");
+	let text = fs::read_to_string(&file)?;
+	serde_json::from_str(&text).map_err(|e| format!("Invalid run {}: {}", file.display(), e).into())
+}
+
+/// Results of a stored run
+pub fn results(dir: &Path, id: &str) -> Result<Vec<ScanResult>, Box<dyn Error>> {
+	let file = run_dir(dir, id)?.join("results.json");
+	Ok(serde_json::from_str(&fs::read_to_string(file)?)?)
+}
+
+/// Every stored run, oldest first; a missing history directory has none
+///
+/// Runs whose summary cannot be read are warned about and left out, so one damaged run does not
+/// hide the others.
+pub fn list(dir: &Path) -> Result<Vec<Run>, Box<dyn Error>> {
+	if !dir.exists() {
+		return Ok(Vec::new());
+	}
+	let mut runs = Vec::new();
+	for entry in fs::read_dir(dir)? {
+		let path = entry?.path();
+		if path.join("run.json").is_file() {
+			match load(&path) {
+				Ok(run) => runs.push(run),
+				Err(e) => warn!("Skipping stored run {}: {}", path.display(), e),
+			}
+		}
+	}
+	runs.sort_by(|a, b| a.started.cmp(&b.started).then_with(|| a.id.cmp(&b.id)));
+	Ok(runs)
+}
+
+/// Run a `history` subcommand, printing results to stdout
+pub fn run(config: &Config, command: HistoryCommand) -> Result<(), Box<dyn Error>> {
+	let dir = config
+		.history_dir
+		.as_deref()
+		.ok_or("No run history; set history_dir to store runs")?;
+
+	match command {
+		HistoryCommand::List => {
+			let runs = list(dir)?;
+			if runs.is_empty() {
+				println!("No stored runs");
+			}
+			for run in &runs {
+				println!("{}  {}  {}", run.id, run.targets.join(","), run.outcome());
+			}
+		},
+		HistoryCommand::Show { id, logs } => {
+			let path = run_dir(dir, &id)?;
+			if logs {
+				print!("{}", fs::read_to_string(path.join("run.log"))?);
+				return Ok(());
+			}
+			let run = load(&path)?;
+			let results = results(dir, &id)?;
+			println!("Run {}", run.id);
+			println!("Started {}", run.started.to_rfc3339());
+			println!("Finished {}", run.finished.to_rfc3339());
+			println!("Targets {}", run.targets.join(", "));
+			println!("Outcome {}", run.outcome());
+			print!("{}", TextFormatter.format(&results)?);
+		},
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_save_and_show() {
+		let dir = std::env::temp_dir().join(format!("huginn-history-{}", std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		let config = Config {
+			history_dir: Some(dir.clone()),
+			..Config::default()
+		};
+		let started = Utc::now();
+		let targets = vec!["10.0.0.1".to_string()];
+		let results = vec![ScanResult::new("10.0.0.1", "ping", "up")];
+		let log = ["[INFO huginn::scanner] Scan completed".to_string()];
+
+		let first = save(&dir, started, &targets, Ok(&results), &log).unwrap();
+		let second = save(&dir, started, &targets, Err("Scanner error".to_string()), &[]).unwrap();
+		assert_eq!(second.id, format!("{}-1", first.id));
+		assert_eq!(list(&dir).unwrap(), vec![first.clone(), second.clone()]);
+		fs::create_dir(dir.join("corrupt")).unwrap();
+		fs::write(dir.join("corrupt").join("run.json"), "{").unwrap();
+		assert_eq!(list(&dir).unwrap(), vec![first.clone(), second.clone()]);
+		assert_eq!(fs::read_to_string(dir.join(&first.id).join("run.log")).unwrap(), format!("{}\n", log[0]));
+		assert_eq!(second.outcome(), "failed: Scanner error");
+
+		let show = |id: &str| HistoryCommand::Show {
+			id: id.to_string(),
+			logs: true,
+		};
+		assert!(run(&config, show(&first.id)).is_ok());
+		assert!(run(&config, show("../etc")).is_err());
+		assert!(run(&config, show("19990101T000000Z")).is_err());
+		assert!(run(&Config::default(), HistoryCommand::List).is_err());
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
@@ -0,0 +1,331 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Result import for Huginn
+//!
+//! `huginn import` converts the output of another scanner into Huginn results, enriches them as a
+//! scan's results would be, and writes them to the configured outputs. When `history_dir` is set
+//! they are also stored as a run, dated by when the other scanner ran, so older scans can be
+//! rendered again, merged, and compared with native runs.
+//!
+//! With `--scan`, the configured scan types then probe only the open ports the import found on
+//! each host, so a fast sweep by another scanner can be followed by Huginn's slower scans without
+//! probing every port of every host again.
+
+mod masscan;
+mod nessus;
+mod nmap;
+mod openvas;
+
+use crate::config::{Config, ImportArgs};
+use crate::history;
+use crate::logging;
+use crate::output::OutputDispatcher;
+#[cfg(feature = "intel-enrichment")]
+use crate::reputation;
+#[cfg(feature = "intel-enrichment")]
+use crate::vulns;
+use chrono::{DateTime, Utc};
+use huginn_core::normalize;
+use huginn_core::plugins::{ScanResult, Severity};
+use huginn_core::statistics::Statistics;
+use log::{error, info};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Format of a file to import
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+	/// Nmap XML output (`nmap -oX`)
+	NmapXml,
+	/// Masscan JSON output (`masscan -oJ`), or one JSON object per line (`-oD`)
+	MasscanJson,
+	/// Masscan list output (`masscan -oL`)
+	MasscanList,
+	/// Nessus v2 XML export (`.nessus`)
+	Nessus,
+	/// OpenVAS or Greenbone XML report
+	OpenVas,
+}
+
+impl FromStr for Format {
+	type Err = String;
+
+	fn from_str(format: &str) -> Result<Self, Self::Err> {
+		match format.trim().to_ascii_lowercase().as_str() {
+			"nmap-xml" => Ok(Format::NmapXml),
+			"masscan-json" => Ok(Format::MasscanJson),
+			"masscan-list" => Ok(Format::MasscanList),
+			"nessus" => Ok(Format::Nessus),
+			"openvas" => Ok(Format::OpenVas),
+			_ => Err(format!(
+				"unknown import format {:?}; expected nmap-xml, masscan-json, masscan-list, nessus, or openvas",
+				format
+			)),
+		}
+	}
+}
+
+impl fmt::Display for Format {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Format::NmapXml => "nmap-xml",
+			Format::MasscanJson => "masscan-json",
+			Format::MasscanList => "masscan-list",
+			Format::Nessus => "nessus",
+			Format::OpenVas => "openvas",
+		})
+	}
+}
+
+/// Results read from another scanner's output
+#[derive(Debug, Default)]
+pub struct Imported {
+	/// When the other scanner started, if its output says
+	pub started: Option<DateTime<Utc>>,
+	/// Hosts the results are for, in the order they first appear
+	pub targets: Vec<String>,
+	/// Results converted to Huginn's model
+	pub results: Vec<ScanResult>,
+	/// Targets already listed
+	known: HashSet<String>,
+	/// Index in `results` of each result added by [`Imported::entry`], by target, scan type, and port
+	entries: HashMap<(String, String, Option<u16>), usize>,
+}
+
+/// Parse a file's contents in the given format
+pub fn parse(format: Format, text: &str) -> Result<Imported, Box<dyn Error>> {
+	match format {
+		Format::NmapXml => nmap::parse(text),
+		Format::MasscanJson => masscan::parse_json(text),
+		Format::MasscanList => masscan::parse_list(text),
+		Format::Nessus => nessus::parse(text),
+		Format::OpenVas => openvas::parse(text),
+	}
+}
+
+impl Imported {
+	/// Record when a result was seen, keeping the earliest time as the start of the run
+	fn seen(&mut self, time: Option<DateTime<Utc>>) {
+		if let Some(time) = time {
+			self.started = Some(self.started.map_or(time, |started| started.min(time)));
+		}
+	}
+
+	/// List a target, unless it already is
+	fn target(&mut self, target: &str) {
+		if self.known.insert(target.to_string()) {
+			self.targets.push(target.to_string());
+		}
+	}
+
+	/// The result for a target's port, or the target itself when the port is `None`, adding one
+	/// with the given status if there is none yet
+	fn entry(&mut self, target: &str, scan_type: String, port: Option<u16>, status: &str) -> &mut ScanResult {
+		self.target(target);
+		let results = &mut self.results;
+		let index = *self.entries.entry((target.to_string(), scan_type.clone(), port)).or_insert_with(|| {
+			results.push(result(target, scan_type, port, status.to_string()));
+			results.len() - 1
+		});
+		&mut self.results[index]
+	}
+
+	/// Attach a vulnerability scanner's finding to the open port it was found on, or to the
+	/// host's `discovery` result when it is not about a port
+	fn finding(&mut self, target: &str, protocol: &str, port: Option<u16>, severity: Severity, finding: String) {
+		let found = match port {
+			Some(port) => self.entry(target, scan_type(protocol), Some(port), "open"),
+			None => self.entry(target, "discovery".to_string(), None, "up"),
+		};
+		found.severity = found.severity.max(Some(severity));
+		normalize::add_details(found, finding);
+	}
+}
+
+/// Scan type for a port found over a protocol by an unknown technique
+fn scan_type(protocol: &str) -> String {
+	match protocol {
+		"tcp" => "tcp_connect".to_string(),
+		_ => protocol.to_string(),
+	}
+}
+
+/// A finding's CVEs with its CVSS score, as vulnerability enrichment describes them, or its name
+/// when it has no CVEs
+fn describe(name: &str, cves: &[String], cvss: Option<f64>) -> String {
+	let ids: Vec<String> = if cves.is_empty() { vec![name.to_string()] } else { cves.to_vec() };
+	ids.iter()
+		.map(|id| match cvss {
+			Some(cvss) => format!("{} (CVSS {:.1})", id, cvss),
+			None => id.clone(),
+		})
+		.collect::<Vec<_>>()
+		.join(", ")
+}
+
+/// Join non-empty detail parts, or none when there are none
+fn details(parts: Vec<String>) -> Option<String> {
+	(!parts.is_empty()).then(|| parts.join("; "))
+}
+
+/// A result with only the fields every imported result sets
+fn result(target: &str, scan_type: String, port: Option<u16>, status: String) -> ScanResult {
+	ScanResult {
+		target: target.to_string(),
+		scan_type,
+		port,
+		status,
+		severity: None,
+		service: None,
+		version: None,
+		details: None,
+		techniques: Vec::new(),
+		screenshot: None,
+		rtt: None,
+	}
+}
+
+/// Group the hosts with open ports by the set of ports open on them, so hosts with the same open
+/// ports are scanned together
+fn endpoints(results: &[ScanResult]) -> BTreeMap<Vec<u16>, Vec<String>> {
+	let mut open: Vec<(&str, Vec<u16>)> = Vec::new();
+	let mut hosts: HashMap<&str, usize> = HashMap::new();
+	for result in results.iter().filter(|r| r.status == "open") {
+		let Some(port) = result.port else {
+			continue;
+		};
+		let index = *hosts.entry(&result.target).or_insert_with(|| {
+			open.push((&result.target, Vec::new()));
+			open.len() - 1
+		});
+		open[index].1.push(port);
+	}
+
+	let mut groups: BTreeMap<Vec<u16>, Vec<String>> = BTreeMap::new();
+	for (target, mut ports) in open {
+		ports.sort_unstable();
+		ports.dedup();
+		groups.entry(ports).or_default().push(target.to_string());
+	}
+	groups
+}
+
+/// Scan each host's open ports with the configured scan types
+async fn scan(config: &Config, results: &[ScanResult]) -> Result<Vec<ScanResult>, Box<dyn Error>> {
+	let mut scanned = Vec::new();
+	for (ports, targets) in endpoints(results) {
+		let spec = ports.iter().map(u16::to_string).collect::<Vec<_>>().join(",");
+		info!("Scanning ports {} of {} imported hosts", spec, targets.len());
+		let mut config = config.clone();
+		config.targets = targets;
+		config.ports = Some(spec.parse()?);
+		scanned.extend(crate::collect(&config).await?.0);
+	}
+	Ok(scanned)
+}
+
+/// Run the `import` subcommand, writing the converted results to every configured output
+pub async fn run(config: &Config, args: &ImportArgs) -> Result<(), Box<dyn Error>> {
+	let dispatcher =
+		OutputDispatcher::from_config(config).map_err(|e| format!("Failed to set up output: {}", e))?;
+	#[cfg(feature = "intel-enrichment")]
+	let vulns = vulns::Database::from_config(config)?;
+	#[cfg(feature = "intel-enrichment")]
+	let reputation = config.reputation.as_ref().map(reputation::Checker::new).transpose()?;
+
+	let capture = config.history_dir.is_some().then(logging::capture);
+	let text = std::fs::read_to_string(&args.file)
+		.map_err(|e| format!("Failed to read {}: {}", args.file.display(), e))?;
+	#[cfg_attr(not(feature = "intel-enrichment"), allow(unused_mut))]
+	let mut imported =
+		parse(args.format, &text).map_err(|e| format!("Invalid {} in {}: {}", args.format, args.file.display(), e))?;
+	info!(
+		"Imported {} results for {} hosts from {}",
+		imported.results.len(),
+		imported.targets.len(),
+		args.file.display()
+	);
+	#[cfg(feature = "intel-enrichment")]
+	if let Some(vulns) = &vulns {
+		vulns.enrich(&mut imported.results);
+	}
+	#[cfg(feature = "intel-enrichment")]
+	if let Some(reputation) = &reputation {
+		reputation.enrich(&mut imported.results).await;
+	}
+	if args.scan {
+		let scanned = scan(config, &imported.results).await?;
+		info!("Scanning the imported hosts found {} results", scanned.len());
+		imported.results.extend(scanned);
+	}
+
+	if let (Some(dir), Some(capture)) = (&config.history_dir, capture) {
+		let started = imported.started.unwrap_or_else(Utc::now);
+		match history::save(dir, started, &imported.targets, Ok(&imported.results), &capture.finish()) {
+			Ok(run) => info!("Stored run {}", run.id),
+			Err(e) => error!("Failed to store run in {}: {}", dir.display(), e),
+		}
+	}
+	dispatcher
+		.write(&imported.results, &Statistics::from_results(&imported.results))
+		.await
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_format() {
+		assert_eq!("nmap-xml".parse::<Format>(), Ok(Format::NmapXml));
+		assert_eq!(" NMAP-XML ".parse::<Format>(), Ok(Format::NmapXml));
+		assert_eq!("masscan-list".parse::<Format>(), Ok(Format::MasscanList));
+		assert!("nmap".parse::<Format>().unwrap_err().contains("expected nmap-xml"));
+		assert_eq!(Format::MasscanJson.to_string(), "masscan-json");
+		assert_eq!("OpenVAS".parse::<Format>(), Ok(Format::OpenVas));
+	}
+
+	#[test]
+	fn test_finding() {
+		let mut imported = Imported::default();
+		let cves = ["CVE-2023-38408".to_string()];
+		imported.finding("10.0.0.1", "tcp", Some(22), Severity::Critical, describe("OpenSSH", &cves, Some(9.8)));
+		imported.finding("10.0.0.1", "tcp", Some(22), Severity::Low, describe("Weak MACs", &[], None));
+		imported.finding("10.0.0.1", "tcp", Some(22), Severity::Critical, describe("OpenSSH", &cves, Some(9.8)));
+		imported.finding("10.0.0.1", "tcp", None, Severity::Medium, describe("ICMP Timestamp", &[], Some(4.0)));
+
+		assert_eq!(imported.targets, ["10.0.0.1"]);
+		let results = &imported.results;
+		assert_eq!(results.len(), 2);
+		assert_eq!((results[0].scan_type.as_str(), results[0].status.as_str()), ("tcp_connect", "open"));
+		assert_eq!(results[0].severity, Some(Severity::Critical));
+		assert_eq!(results[0].details.as_deref(), Some("CVE-2023-38408 (CVSS 9.8); Weak MACs"));
+		assert_eq!((results[1].scan_type.as_str(), results[1].status.as_str()), ("discovery", "up"));
+		assert_eq!(results[1].details.as_deref(), Some("ICMP Timestamp (CVSS 4.0)"));
+	}
+
+	#[test]
+	fn test_endpoints() {
+		let results = [
+			result("10.0.0.1", "tcp_syn".to_string(), Some(443), "open".to_string()),
+			result("10.0.0.1", "tcp_syn".to_string(), Some(80), "open".to_string()),
+			result("10.0.0.2", "tcp_syn".to_string(), Some(22), "closed".to_string()),
+			result("10.0.0.2", "discovery".to_string(), None, "up".to_string()),
+			result("10.0.0.3", "tcp_syn".to_string(), Some(80), "open".to_string()),
+			result("10.0.0.3", "tcp_syn".to_string(), Some(443), "open".to_string()),
+			result("10.0.0.3", "tcp_syn".to_string(), Some(80), "open".to_string()),
+			result("10.0.0.4", "udp".to_string(), Some(53), "open".to_string()),
+		];
+		let groups: Vec<(Vec<u16>, Vec<String>)> = endpoints(&results).into_iter().collect();
+		assert_eq!(
+			groups,
+			[
+				(vec![53], vec!["10.0.0.4".to_string()]),
+				(vec![80, 443], vec!["10.0.0.1".to_string(), "10.0.0.3".to_string()]),
+			]
+		);
+	}
+}
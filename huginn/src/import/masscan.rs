@@ -0,0 +1,254 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Masscan import
+//!
+//! Masscan sends SYN probes, so each TCP port it reports becomes a `tcp_syn` result, and each UDP
+//! port a `udp` result, with Masscan's status. Banners grabbed with `--banners` arrive as separate
+//! records; the first one for a port names its service, and each banner is added to the port's
+//! details. Hosts found with `--ping` become `discovery` results. The JSON reader accepts both
+//! `-oJ` output, including the unterminated files older versions write, and `-oD` output.
+
+use super::Imported;
+use chrono::DateTime;
+use huginn_core::normalize;
+use serde::Deserialize;
+use serde_json::Value;
+use std::error::Error;
+
+/// One record of JSON output
+#[derive(Debug, Deserialize)]
+struct Record {
+	ip: String,
+	/// Seconds since the epoch, usually written as a string
+	timestamp: Option<Value>,
+	#[serde(default)]
+	ports: Vec<Port>,
+	// `-oD` writes one port per record, with its status or banner under `data`
+	port: Option<u16>,
+	proto: Option<String>,
+	rec_type: Option<String>,
+	data: Option<Data>,
+}
+
+/// Status or banner of an `-oD` record
+#[derive(Debug, Deserialize)]
+struct Data {
+	status: Option<String>,
+	reason: Option<String>,
+	service_name: Option<String>,
+	banner: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Port {
+	port: u16,
+	proto: String,
+	status: Option<String>,
+	reason: Option<String>,
+	service: Option<Banner>,
+}
+
+/// Banner grabbed from a port
+#[derive(Debug, Deserialize)]
+struct Banner {
+	name: String,
+	#[serde(default)]
+	banner: String,
+}
+
+/// Huginn scan type for a protocol Masscan probed
+fn scan_type(proto: &str) -> String {
+	match proto {
+		"tcp" => "tcp_syn".to_string(),
+		_ => proto.to_string(),
+	}
+}
+
+impl Imported {
+	/// Record a port's status, or a banner from it when `status` is `None`
+	fn port(
+		&mut self,
+		ip: &str,
+		port: u16,
+		proto: &str,
+		status: Option<String>,
+		reason: Option<String>,
+		banner: Option<Banner>,
+	) {
+		// Masscan reports pings as ICMP on port 0, and a banner implies the port was open even if
+		// its status line is missing
+		let found = match proto {
+			"icmp" => self.entry(ip, "discovery".to_string(), None, "up"),
+			_ => self.entry(ip, scan_type(proto), Some(port), status.as_deref().unwrap_or("open")),
+		};
+		if status.is_some()
+			&& let Some(reason) = reason
+		{
+			normalize::add_details(found, format!("reason {}", reason));
+		}
+		if let Some(banner) = banner {
+			let text = banner.banner.split_whitespace().collect::<Vec<_>>().join(" ");
+			if found.service.is_none() {
+				found.service = Some(banner.name.clone());
+			}
+			if !text.is_empty() {
+				normalize::add_details(found, format!("{}: {}", banner.name, text));
+			}
+		}
+	}
+}
+
+/// Seconds since the epoch from a JSON timestamp
+fn timestamp(value: Option<&Value>) -> Option<i64> {
+	match value? {
+		Value::String(text) => text.trim().parse().ok(),
+		value => value.as_i64(),
+	}
+}
+
+/// Convert Masscan JSON output into Huginn results
+pub fn parse_json(text: &str) -> Result<Imported, Box<dyn Error>> {
+	let mut imported = Imported::default();
+	for (n, line) in text.lines().enumerate() {
+		// `-oJ` puts each record on its own line between the brackets, separated by commas
+		let line = line.trim().trim_end_matches(',');
+		if line.is_empty() || line == "[" || line == "]" || line.starts_with("{finished") {
+			continue;
+		}
+		let record: Record = serde_json::from_str(line).map_err(|e| format!("line {}: {}", n + 1, e))?;
+		imported.seen(timestamp(record.timestamp.as_ref()).and_then(|t| DateTime::from_timestamp(t, 0)));
+		if let (Some(port), Some(proto), Some(data)) = (record.port, &record.proto, record.data) {
+			match record.rec_type.as_deref() {
+				Some("banner") => {
+					let banner = Banner {
+						name: data.service_name.unwrap_or_default(),
+						banner: data.banner.unwrap_or_default(),
+					};
+					imported.port(&record.ip, port, proto, None, None, Some(banner));
+				},
+				_ => imported.port(&record.ip, port, proto, data.status, data.reason, None),
+			}
+		}
+		for port in record.ports {
+			imported.port(&record.ip, port.port, &port.proto, port.status, port.reason, port.service);
+		}
+	}
+	Ok(imported)
+}
+
+/// Convert Masscan list output into Huginn results
+pub fn parse_list(text: &str) -> Result<Imported, Box<dyn Error>> {
+	let mut imported = Imported::default();
+	for (n, line) in text.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		let invalid = || format!("line {}: expected STATUS PROTO PORT IP TIMESTAMP", n + 1);
+		let mut fields = line.splitn(7, ' ');
+		let (Some(status), Some(proto), Some(port), Some(ip), Some(time)) =
+			(fields.next(), fields.next(), fields.next(), fields.next(), fields.next())
+		else {
+			return Err(invalid().into());
+		};
+		let port: u16 = port.parse().map_err(|_| invalid())?;
+		imported.seen(time.parse().ok().and_then(|t| DateTime::from_timestamp(t, 0)));
+
+		if status == "banner" {
+			let name = fields.next().ok_or_else(invalid)?;
+			let banner = Banner {
+				name: name.to_string(),
+				banner: fields.next().unwrap_or_default().to_string(),
+			};
+			imported.port(ip, port, proto, None, None, Some(banner));
+		} else {
+			imported.port(ip, port, proto, Some(status.to_string()), None, None);
+		}
+	}
+	Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Target, scan type, port, status, service, and details of a result
+	type Row<'a> = (&'a str, &'a str, Option<u16>, &'a str, Option<&'a str>, Option<&'a str>);
+
+	fn summary(imported: &Imported) -> Vec<Row<'_>> {
+		imported
+			.results
+			.iter()
+			.map(|r| {
+				let (service, details) = (r.service.as_deref(), r.details.as_deref());
+				(r.target.as_str(), r.scan_type.as_str(), r.port, r.status.as_str(), service, details)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn test_parse_json() {
+		let json = r#"[
+{   "ip": "10.0.0.1",   "timestamp": "1700000005", "ports": [ {"port": 80, "proto": "tcp", "status": "open", "reason": "syn-ack", "ttl": 64} ] }
+,
+{   "ip": "10.0.0.2",   "timestamp": "1700000003", "ports": [ {"port": 53, "proto": "udp", "status": "open", "reason": "none", "ttl": 64} ] }
+,
+{   "ip": "10.0.0.1",   "timestamp": "1700000009", "ports": [ {"port": 80, "proto": "tcp", "service": {"name": "http", "banner": "HTTP/1.1 200 OK\r\nServer: nginx"} } ] }
+,
+{   "ip": "10.0.0.3",   "timestamp": "1700000004", "ports": [ {"port": 0, "proto": "icmp", "status": "open", "reason": "none", "ttl": 64} ] }
+,
+{finished: 1}
+"#;
+		let imported = parse_json(json).unwrap();
+		assert_eq!(imported.started, DateTime::from_timestamp(1_700_000_003, 0));
+		assert_eq!(imported.targets, ["10.0.0.1", "10.0.0.2", "10.0.0.3"]);
+		assert_eq!(
+			summary(&imported),
+			[
+				(
+					"10.0.0.1",
+					"tcp_syn",
+					Some(80),
+					"open",
+					Some("http"),
+					Some("reason syn-ack; http: HTTP/1.1 200 OK Server: nginx")
+				),
+				("10.0.0.2", "udp", Some(53), "open", None, Some("reason none")),
+				("10.0.0.3", "discovery", None, "up", None, Some("reason none")),
+			]
+		);
+
+		let ndjson = r#"{"ip":"10.0.0.1","timestamp":"1700000005","port":443,"proto":"tcp","rec_type":"status","data":{"status":"closed","reason":"rst-ack","ttl":64}}
+{"ip":"10.0.0.1","timestamp":"1700000006","port":22,"proto":"tcp","rec_type":"banner","data":{"service_name":"ssh","banner":"SSH-2.0-dropbear"}}"#;
+		assert_eq!(
+			summary(&parse_json(ndjson).unwrap()),
+			[
+				("10.0.0.1", "tcp_syn", Some(443), "closed", None, Some("reason rst-ack")),
+				("10.0.0.1", "tcp_syn", Some(22), "open", Some("ssh"), Some("ssh: SSH-2.0-dropbear")),
+			]
+		);
+		assert!(parse_json("[\n{\"ip\": 1}\n]").unwrap_err().to_string().starts_with("line 2:"));
+	}
+
+	#[test]
+	fn test_parse_list() {
+		let list = "#masscan
+open tcp 22 10.0.0.1 1700000002
+banner tcp 22 10.0.0.1 1700000003 ssh SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.4
+banner tcp 8080 10.0.0.2 1700000004 http HTTP/1.0 404 Not Found
+# end
+";
+		let imported = parse_list(list).unwrap();
+		assert_eq!(imported.started, DateTime::from_timestamp(1_700_000_002, 0));
+		assert_eq!(
+			summary(&imported),
+			[
+				("10.0.0.1", "tcp_syn", Some(22), "open", Some("ssh"), Some("ssh: SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.4")),
+				("10.0.0.2", "tcp_syn", Some(8080), "open", Some("http"), Some("http: HTTP/1.0 404 Not Found")),
+			]
+		);
+		assert!(parse_list("open tcp 22").unwrap_err().to_string().starts_with("line 1:"));
+		assert!(parse_list("open tcp port 10.0.0.1 0").is_err());
+	}
+}
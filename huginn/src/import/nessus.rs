@@ -0,0 +1,202 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Nessus import
+//!
+//! Every port a `.nessus` report mentions becomes an open port result, named with the service
+//! Nessus identified. Findings of low severity or above are attached to their port, or to the
+//! host's `discovery` result when they are not about a port, raising its severity to theirs and
+//! adding their CVEs and CVSS score, or their plugin name when they have no CVE, to its details.
+//! Findings are described the way vulnerability enrichment describes them, so a finding both
+//! scanners report is kept once when the results are merged.
+
+use super::{Imported, describe};
+use chrono::DateTime;
+use huginn_core::plugins::Severity;
+use serde::Deserialize;
+use std::error::Error;
+
+/// Root `NessusClientData_v2` element
+#[derive(Debug, Deserialize)]
+struct Data {
+	#[serde(rename = "Report")]
+	report: Report,
+}
+
+#[derive(Debug, Deserialize)]
+struct Report {
+	#[serde(default, rename = "ReportHost")]
+	hosts: Vec<Host>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Host {
+	#[serde(rename = "@name")]
+	name: String,
+	#[serde(rename = "HostProperties")]
+	properties: Option<Properties>,
+	#[serde(default, rename = "ReportItem")]
+	items: Vec<Item>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Properties {
+	#[serde(default)]
+	tag: Vec<Tag>,
+}
+
+/// Named host property
+#[derive(Debug, Deserialize)]
+struct Tag {
+	#[serde(rename = "@name")]
+	name: String,
+	#[serde(default, rename = "$text")]
+	value: String,
+}
+
+/// Output of one plugin for one port
+#[derive(Debug, Deserialize)]
+struct Item {
+	#[serde(rename = "@port")]
+	port: u16,
+	#[serde(rename = "@svc_name")]
+	service: Option<String>,
+	#[serde(rename = "@protocol")]
+	protocol: String,
+	#[serde(rename = "@severity")]
+	severity: u8,
+	#[serde(rename = "@pluginName")]
+	name: String,
+	#[serde(default)]
+	cve: Vec<String>,
+	cvss3_base_score: Option<f64>,
+	cvss_base_score: Option<f64>,
+}
+
+/// Huginn severity of a Nessus severity, from 0 (informational) to 4 (critical)
+fn severity(level: u8) -> Severity {
+	match level {
+		0 => Severity::Info,
+		1 => Severity::Low,
+		2 => Severity::Medium,
+		3 => Severity::High,
+		_ => Severity::Critical,
+	}
+}
+
+impl Host {
+	/// Value of a host property
+	fn property(&self, name: &str) -> Option<&str> {
+		let tags = &self.properties.as_ref()?.tag;
+		tags.iter().find(|tag| tag.name == name).map(|tag| tag.value.trim())
+	}
+}
+
+/// Convert a Nessus v2 report into Huginn results
+pub fn parse(text: &str) -> Result<Imported, Box<dyn Error>> {
+	let data: Data = quick_xml::de::from_str(text)?;
+	let mut imported = Imported::default();
+
+	for host in data.report.hosts {
+		// The report names hosts as they were targeted; prefer the address they were scanned at
+		let target = host.property("host-ip").unwrap_or(&host.name).to_string();
+		let started = host.property("HOST_START_TIMESTAMP").and_then(|t| t.parse().ok());
+		imported.seen(started.and_then(|t| DateTime::from_timestamp(t, 0)));
+		imported.entry(&target, "discovery".to_string(), None, "up");
+
+		for item in &host.items {
+			let port = (item.port != 0).then_some(item.port);
+			if let Some(port) = port {
+				let found = imported.entry(&target, super::scan_type(&item.protocol), Some(port), "open");
+				if found.service.is_none() {
+					// Nessus names services it could not identify `unknown`, and host-wide checks `general`
+					found.service = item.service.clone().filter(|name| name != "unknown" && name != "general");
+				}
+			}
+			if item.severity > 0 {
+				let cvss = item.cvss3_base_score.or(item.cvss_base_score);
+				let finding = describe(&item.name, &item.cve, cvss);
+				imported.finding(&target, &item.protocol, port, severity(item.severity), finding);
+			}
+		}
+	}
+	Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const REPORT: &str = r#"<?xml version="1.0" ?>
+<NessusClientData_v2>
+<Policy><policyName>Basic Network Scan</policyName></Policy>
+<Report name="Weekly" xmlns:cm="http://www.nessus.org/cm">
+<ReportHost name="gateway.example.com"><HostProperties>
+<tag name="HOST_END">Tue Nov 14 22:20:00 2023</tag>
+<tag name="host-ip">10.0.0.1</tag>
+<tag name="HOST_START_TIMESTAMP">1700000000</tag>
+</HostProperties>
+<ReportItem port="0" svc_name="general" protocol="icmp" severity="2" pluginID="10114" pluginName="ICMP Timestamp Request Remote Date Disclosure" pluginFamily="General">
+<cvss_base_score>2.1</cvss_base_score>
+<cve>CVE-1999-0524</cve>
+<risk_factor>Low</risk_factor>
+</ReportItem>
+<ReportItem port="22" svc_name="ssh" protocol="tcp" severity="0" pluginID="11219" pluginName="Nessus SYN scanner" pluginFamily="Port scanners">
+<risk_factor>None</risk_factor>
+</ReportItem>
+<ReportItem port="22" svc_name="ssh" protocol="tcp" severity="4" pluginID="179555" pluginName="OpenSSH &lt; 9.3p2 Vulnerability" pluginFamily="Misc.">
+<cvss3_base_score>9.8</cvss3_base_score>
+<cvss_base_score>10.0</cvss_base_score>
+<cve>CVE-2023-38408</cve>
+</ReportItem>
+<ReportItem port="443" svc_name="www" protocol="tcp" severity="1" pluginID="57582" pluginName="SSL Self-Signed Certificate" pluginFamily="General">
+</ReportItem>
+<ReportItem port="161" svc_name="snmp" protocol="udp" severity="3" pluginID="41028" pluginName="SNMP Agent Default Community Name (public)" pluginFamily="SNMP">
+<cvss_base_score>7.5</cvss_base_score>
+<cve>CVE-1999-0517</cve>
+<cve>CVE-1999-0186</cve>
+</ReportItem>
+</ReportHost>
+<ReportHost name="10.0.0.2"><HostProperties></HostProperties></ReportHost>
+</Report>
+</NessusClientData_v2>
+"#;
+
+	#[test]
+	fn test_parse() {
+		let imported = parse(REPORT).unwrap();
+		assert_eq!(imported.started, DateTime::from_timestamp(1_700_000_000, 0));
+		assert_eq!(imported.targets, ["10.0.0.1", "10.0.0.2"]);
+
+		let summary: Vec<_> = imported
+			.results
+			.iter()
+			.map(|r| (r.target.as_str(), r.scan_type.as_str(), r.port, r.status.as_str(), r.severity))
+			.collect();
+		assert_eq!(
+			summary,
+			[
+				("10.0.0.1", "discovery", None, "up", Some(Severity::Medium)),
+				("10.0.0.1", "tcp_connect", Some(22), "open", Some(Severity::Critical)),
+				("10.0.0.1", "tcp_connect", Some(443), "open", Some(Severity::Low)),
+				("10.0.0.1", "udp", Some(161), "open", Some(Severity::High)),
+				("10.0.0.2", "discovery", None, "up", None),
+			]
+		);
+
+		let results = &imported.results;
+		assert_eq!(results[0].details.as_deref(), Some("CVE-1999-0524 (CVSS 2.1)"));
+		assert_eq!(results[1].service.as_deref(), Some("ssh"));
+		assert_eq!(results[1].details.as_deref(), Some("CVE-2023-38408 (CVSS 9.8)"));
+		assert_eq!(results[2].details.as_deref(), Some("SSL Self-Signed Certificate"));
+		assert_eq!(results[3].details.as_deref(), Some("CVE-1999-0517 (CVSS 7.5), CVE-1999-0186 (CVSS 7.5)"));
+	}
+
+	#[test]
+	fn test_parse_invalid() {
+		assert!(parse("<NessusClientData_v2><Report><ReportHost>").is_err());
+		assert!(parse("<NessusClientData_v2><Report><ReportHost name=\"h\"><ReportItem port=\"x\"/>").is_err());
+		assert!(parse("<nmaprun scanner=\"nmap\"/>").is_err());
+		assert!(parse("<NessusClientData_v2><Report/></NessusClientData_v2>").unwrap().results.is_empty());
+	}
+}
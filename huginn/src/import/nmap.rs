@@ -0,0 +1,319 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Nmap XML import
+//!
+//! Each host becomes a `discovery` result saying whether Nmap found it up, with the reason and
+//! the host's names and host script output in its details. Each port becomes a result of the scan
+//! type Nmap used for its protocol, such as `tcp_syn` for `-sS`, with Nmap's port state as its
+//! status, so `open|filtered` and `closed` compare equal to Huginn's own. A detected service gives
+//! the service name, with `ssl/` in front when it was tunneled, and the product and version, such
+//! as `OpenSSH 8.9p1`, which vulnerability enrichment matches against. The closest operating
+//! system match becomes an `os_detection` result naming it.
+
+use super::{Imported, details, result};
+use chrono::DateTime;
+use serde::Deserialize;
+use std::error::Error;
+
+/// Root `nmaprun` element
+#[derive(Debug, Deserialize)]
+struct Run {
+	#[serde(rename = "@scanner")]
+	scanner: String,
+	#[serde(rename = "@start")]
+	start: Option<i64>,
+	#[serde(default)]
+	scaninfo: Vec<ScanInfo>,
+	#[serde(default)]
+	host: Vec<Host>,
+}
+
+/// Scan technique used for a protocol
+#[derive(Debug, Deserialize)]
+struct ScanInfo {
+	#[serde(rename = "@type")]
+	kind: String,
+	#[serde(rename = "@protocol")]
+	protocol: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Host {
+	status: Option<State>,
+	#[serde(default)]
+	address: Vec<Address>,
+	hostnames: Option<Hostnames>,
+	ports: Option<Ports>,
+	os: Option<Os>,
+	hostscript: Option<Scripts>,
+}
+
+/// Host status or port state, with Nmap's reason for it
+#[derive(Debug, Deserialize)]
+struct State {
+	#[serde(rename = "@state")]
+	state: String,
+	#[serde(rename = "@reason")]
+	reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Address {
+	#[serde(rename = "@addr")]
+	addr: String,
+	#[serde(rename = "@addrtype")]
+	kind: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Hostnames {
+	#[serde(default)]
+	hostname: Vec<Hostname>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Hostname {
+	#[serde(rename = "@name")]
+	name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Ports {
+	#[serde(default)]
+	port: Vec<Port>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Port {
+	#[serde(rename = "@protocol")]
+	protocol: String,
+	#[serde(rename = "@portid")]
+	id: u16,
+	state: State,
+	service: Option<Service>,
+	#[serde(default)]
+	script: Vec<Script>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Service {
+	#[serde(rename = "@name")]
+	name: String,
+	#[serde(rename = "@product")]
+	product: Option<String>,
+	#[serde(rename = "@version")]
+	version: Option<String>,
+	#[serde(rename = "@extrainfo")]
+	extrainfo: Option<String>,
+	#[serde(rename = "@tunnel")]
+	tunnel: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Scripts {
+	#[serde(default)]
+	script: Vec<Script>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Script {
+	#[serde(rename = "@id")]
+	id: String,
+	#[serde(rename = "@output")]
+	output: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Os {
+	#[serde(default)]
+	osmatch: Vec<OsMatch>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsMatch {
+	#[serde(rename = "@name")]
+	name: String,
+	#[serde(rename = "@accuracy")]
+	accuracy: u8,
+}
+
+impl Script {
+	/// Describe the script's output on one line
+	fn describe(&self) -> String {
+		format!("{}: {}", self.id, self.output.split_whitespace().collect::<Vec<_>>().join(" "))
+	}
+}
+
+impl Service {
+	/// Product and version, such as `OpenSSH 8.9p1`
+	fn version(&self) -> Option<String> {
+		let parts: Vec<&str> = [&self.product, &self.version].into_iter().flatten().map(String::as_str).collect();
+		(!parts.is_empty()).then(|| parts.join(" "))
+	}
+}
+
+/// Huginn scan type for an Nmap scan technique
+fn scan_type(kind: &str) -> String {
+	match kind {
+		"syn" => "tcp_syn".to_string(),
+		"connect" => "tcp_connect".to_string(),
+		"udp" | "idle" => kind.to_string(),
+		_ => format!("nmap_{}", kind),
+	}
+}
+
+/// Convert Nmap XML output into Huginn results
+pub fn parse(text: &str) -> Result<Imported, Box<dyn Error>> {
+	let run: Run = quick_xml::de::from_str(text)?;
+	if run.scanner != "nmap" {
+		return Err(format!("written by {}, not nmap", run.scanner).into());
+	}
+	let mut imported = Imported {
+		started: run.start.and_then(|start| DateTime::from_timestamp(start, 0)),
+		..Imported::default()
+	};
+
+	for host in run.host {
+		let names: Vec<String> =
+			host.hostnames.map(|h| h.hostname.into_iter().map(|n| n.name).collect()).unwrap_or_default();
+		// Prefer the IP address over a MAC address or a name
+		let Some(target) = host
+			.address
+			.iter()
+			.find(|a| a.kind == "ipv4" || a.kind == "ipv6")
+			.map(|a| a.addr.clone())
+			.or_else(|| names.first().cloned())
+		else {
+			continue;
+		};
+		imported.target(&target);
+
+		if let Some(status) = host.status {
+			let mut parts: Vec<String> = status.reason.iter().map(|r| format!("reason {}", r)).collect();
+			parts.extend(names.iter().map(|name| format!("hostname {}", name)));
+			parts.extend(host.hostscript.iter().flat_map(|s| &s.script).map(Script::describe));
+			let mut discovery = result(&target, "discovery".to_string(), None, status.state);
+			discovery.details = details(parts);
+			imported.results.push(discovery);
+		}
+
+		for port in host.ports.map(|p| p.port).unwrap_or_default() {
+			let kind = match run.scaninfo.iter().find(|info| info.protocol == port.protocol) {
+				Some(info) => scan_type(&info.kind),
+				// Without scan information, the port was found by Nmap's default TCP scan, a SYN scan
+				None if port.protocol == "tcp" => scan_type("syn"),
+				None => super::scan_type(&port.protocol),
+			};
+			let mut parts: Vec<String> = port.state.reason.iter().map(|r| format!("reason {}", r)).collect();
+			let mut found = result(&target, kind, Some(port.id), port.state.state);
+			if let Some(service) = port.service {
+				found.version = service.version();
+				found.service = Some(match service.tunnel {
+					Some(tunnel) => format!("{}/{}", tunnel, service.name),
+					None => service.name,
+				});
+				parts.extend(service.extrainfo);
+			}
+			parts.extend(port.script.iter().map(Script::describe));
+			found.details = details(parts);
+			imported.results.push(found);
+		}
+
+		// Nmap lists operating system matches from the most to the least likely
+		if let Some(best) = host.os.and_then(|os| os.osmatch.into_iter().next()) {
+			let mut os = result(&target, "os_detection".to_string(), None, "detected".to_string());
+			os.details = Some(format!("{}; accuracy {}%", best.name, best.accuracy));
+			imported.results.push(os);
+		}
+	}
+	Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SCAN: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE nmaprun>
+<?xml-stylesheet href="file:///usr/bin/../share/nmap/nmap.xsl" type="text/xsl"?>
+<nmaprun scanner="nmap" args="nmap -sS -sU -sV -O -oX scan.xml 10.0.0.1 10.0.0.2" start="1700000000" version="7.94" xmloutputversion="1.05">
+<scaninfo type="syn" protocol="tcp" numservices="1000" services="1-1000"/>
+<scaninfo type="udp" protocol="udp" numservices="1000" services="1-1000"/>
+<verbose level="0"/>
+<debugging level="0"/>
+<host starttime="1700000001" endtime="1700000030"><status state="up" reason="arp-response" reason_ttl="0"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<address addr="00:11:22:33:44:55" addrtype="mac" vendor="Example"/>
+<hostnames>
+<hostname name="gateway.example.com" type="PTR"/>
+</hostnames>
+<ports><extraports state="closed" count="997">
+<extrareasons reason="reset" count="997" proto="tcp" ports="1-21,23-79"/>
+</extraports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack" reason_ttl="64"/><service name="ssh" product="OpenSSH" version="8.9p1 Ubuntu 3ubuntu0.4" extrainfo="Ubuntu Linux; protocol 2.0" ostype="Linux" method="probed" conf="10"><cpe>cpe:/a:openbsd:openssh:8.9p1</cpe></service><script id="ssh-hostkey" output="&#xa;  256 aa:bb (ECDSA)&#xa;  256 cc:dd (ED25519)"/></port>
+<port protocol="tcp" portid="443"><state state="open" reason="syn-ack" reason_ttl="64"/><service name="http" product="nginx" tunnel="ssl" method="probed" conf="10"/></port>
+<port protocol="udp" portid="53"><state state="open|filtered" reason="no-response" reason_ttl="0"/><service name="domain" method="table" conf="3"/></port>
+</ports>
+<os><portused state="open" proto="tcp" portid="22"/>
+<osmatch name="Linux 5.0 - 5.14" accuracy="98" line="67000"><osclass type="general purpose" vendor="Linux" osfamily="Linux" osgen="5.X" accuracy="98"/></osmatch>
+<osmatch name="Linux 4.15" accuracy="90" line="66000"/>
+</os>
+</host>
+<taskprogress task="SYN Stealth Scan" time="1700000010" percent="50.00"/>
+<host><status state="down" reason="no-response" reason_ttl="0"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+</host>
+<runstats><finished time="1700000031" elapsed="31.00" exit="success"/><hosts up="1" down="1" total="2"/></runstats>
+</nmaprun>
+"#;
+
+	#[test]
+	fn test_parse() {
+		let imported = parse(SCAN).unwrap();
+		assert_eq!(imported.started, DateTime::from_timestamp(1_700_000_000, 0));
+		assert_eq!(imported.targets, ["10.0.0.1", "10.0.0.2"]);
+
+		let summary: Vec<(&str, &str, Option<u16>, &str)> = imported
+			.results
+			.iter()
+			.map(|r| (r.target.as_str(), r.scan_type.as_str(), r.port, r.status.as_str()))
+			.collect();
+		assert_eq!(
+			summary,
+			[
+				("10.0.0.1", "discovery", None, "up"),
+				("10.0.0.1", "tcp_syn", Some(22), "open"),
+				("10.0.0.1", "tcp_syn", Some(443), "open"),
+				("10.0.0.1", "udp", Some(53), "open|filtered"),
+				("10.0.0.1", "os_detection", None, "detected"),
+				("10.0.0.2", "discovery", None, "down"),
+			]
+		);
+
+		let results = &imported.results;
+		assert_eq!(results[0].details.as_deref(), Some("reason arp-response; hostname gateway.example.com"));
+		assert_eq!(results[1].service.as_deref(), Some("ssh"));
+		assert_eq!(results[1].version.as_deref(), Some("OpenSSH 8.9p1 Ubuntu 3ubuntu0.4"));
+		assert_eq!(
+			results[1].details.as_deref(),
+			Some("reason syn-ack; Ubuntu Linux; protocol 2.0; ssh-hostkey: 256 aa:bb (ECDSA) 256 cc:dd (ED25519)")
+		);
+		assert_eq!((results[2].service.as_deref(), results[2].version.as_deref()), (Some("ssl/http"), Some("nginx")));
+		assert_eq!((results[3].service.as_deref(), results[3].version.as_deref()), (Some("domain"), None));
+		assert_eq!(results[4].details.as_deref(), Some("Linux 5.0 - 5.14; accuracy 98%"));
+	}
+
+	#[test]
+	fn test_parse_invalid() {
+		assert!(parse("<nmaprun scanner=\"masscan\"/>").unwrap_err().to_string().contains("not nmap"));
+		assert!(parse("<nmaprun scanner=\"nmap\"><host>").is_err());
+		assert!(parse("not xml").is_err());
+
+		let empty = parse("<nmaprun scanner=\"nmap\"></nmaprun>").unwrap();
+		assert!(empty.results.is_empty() && empty.started.is_none());
+
+		let unlabeled = SCAN.replace("<scaninfo type=\"syn\" protocol=\"tcp\"", "<scaninfo type=\"syn\" protocol=\"sctp\"");
+		assert_eq!(parse(&unlabeled).unwrap().results[1].scan_type, "tcp_syn");
+	}
+}
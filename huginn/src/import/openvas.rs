@@ -0,0 +1,201 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! OpenVAS import
+//!
+//! Reads the XML reports OpenVAS and Greenbone export, whether as a downloaded report file or a
+//! `get_reports` response. Each result with a severity above zero is a finding: it is attached to
+//! the port it names, such as `22/tcp`, or to the host's `discovery` result for `general/tcp` and
+//! other results not about a port, with its severity rated from its score. Its CVEs and score, or
+//! its name when it has no CVE, are added to the details the way vulnerability enrichment adds
+//! them, so a finding both scanners report is kept once when the results are merged. Log results,
+//! with no severity, only record that their port is open.
+
+use super::{Imported, describe};
+use chrono::DateTime;
+use huginn_core::plugins::Severity;
+use serde::Deserialize;
+use std::error::Error;
+
+/// A `report` element; exported files nest the report in one with the same name, and
+/// `get_reports` responses wrap it in another element
+#[derive(Debug, Deserialize)]
+struct Report {
+	report: Option<Box<Report>>,
+	scan_start: Option<String>,
+	results: Option<Results>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Results {
+	#[serde(default)]
+	result: Vec<Finding>,
+}
+
+/// A `result` element
+#[derive(Debug, Deserialize)]
+struct Finding {
+	name: String,
+	host: Host,
+	port: String,
+	nvt: Option<Nvt>,
+	severity: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Host {
+	#[serde(rename = "$text")]
+	address: String,
+}
+
+/// The check that produced a result
+#[derive(Debug, Deserialize)]
+struct Nvt {
+	refs: Option<Refs>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Refs {
+	#[serde(default, rename = "ref")]
+	refs: Vec<Ref>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Ref {
+	#[serde(rename = "@type")]
+	kind: String,
+	#[serde(rename = "@id")]
+	id: String,
+}
+
+/// Port number and protocol of a port such as `443/tcp`; `general/tcp` and other results not
+/// about a port have no number
+fn port(port: &str) -> (Option<u16>, &str) {
+	match port.trim().split_once('/') {
+		Some((number, protocol)) => (number.parse().ok().filter(|&n| n != 0), protocol),
+		None => (None, port.trim()),
+	}
+}
+
+/// Convert an OpenVAS XML report into Huginn results
+pub fn parse(text: &str) -> Result<Imported, Box<dyn Error>> {
+	let mut report: Report = quick_xml::de::from_str(text)?;
+	while report.results.is_none() {
+		match report.report {
+			Some(inner) => report = *inner,
+			None => return Err("no results element in the report".into()),
+		}
+	}
+	let mut imported = Imported::default();
+	let started = report.scan_start.as_deref().and_then(|start| DateTime::parse_from_rfc3339(start.trim()).ok());
+	imported.seen(started.map(|start| start.to_utc()));
+
+	for finding in report.results.map(|results| results.result).unwrap_or_default() {
+		let target = finding.host.address.trim();
+		let (number, protocol) = port(&finding.port);
+		let score = finding.severity.unwrap_or_default();
+		if score <= 0.0 {
+			match number {
+				Some(number) => imported.entry(target, super::scan_type(protocol), Some(number), "open"),
+				None => imported.entry(target, "discovery".to_string(), None, "up"),
+			};
+			continue;
+		}
+		let cves: Vec<String> = finding
+			.nvt
+			.and_then(|nvt| nvt.refs)
+			.map(|refs| refs.refs.into_iter().filter(|r| r.kind == "cve").map(|r| r.id).collect())
+			.unwrap_or_default();
+		let description = describe(finding.name.trim(), &cves, Some(score));
+		imported.finding(target, protocol, number, Severity::from_cvss(score), description);
+	}
+	Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const REPORT: &str = r#"<report id="6f0d" format_id="a994" extension="xml" content_type="text/xml">
+<owner><name>admin</name></owner>
+<name>2023-11-14T22:13:20Z</name>
+<report id="6f0d">
+<gmp><version>22.4</version></gmp>
+<scan_run_status>Done</scan_run_status>
+<scan_start>2023-11-14T22:13:20Z</scan_start>
+<results start="1" max="-1">
+<result id="r1">
+<name>OpenSSH Remote Code Execution Vulnerability</name>
+<host>10.0.0.1<asset asset_id="a1"/><hostname>gateway.example.com</hostname></host>
+<port>22/tcp</port>
+<nvt oid="1.3.6.1.4.1.25623.1.0.104859"><type>nvt</type><name>OpenSSH Remote Code Execution Vulnerability</name><cvss_base>9.8</cvss_base>
+<refs><ref type="cve" id="CVE-2023-38408"/><ref type="url" id="https://www.openssh.com/txt/release-9.3p2"/></refs></nvt>
+<threat>High</threat>
+<severity>9.8</severity>
+<qod><value>80</value></qod>
+</result>
+<result id="r2">
+<name>TCP Timestamps Information Disclosure</name>
+<host>10.0.0.1<asset asset_id="a1"/></host>
+<port>general/tcp</port>
+<nvt oid="1.3.6.1.4.1.25623.1.0.80091"><type>nvt</type><name>TCP Timestamps Information Disclosure</name></nvt>
+<threat>Low</threat>
+<severity>2.6</severity>
+</result>
+<result id="r3">
+<name>HTTP Server Banner Enumeration</name>
+<host>10.0.0.2<asset asset_id="a2"/></host>
+<port>80/tcp</port>
+<threat>Log</threat>
+<severity>0.0</severity>
+</result>
+</results>
+<result_count>3</result_count>
+</report>
+</report>
+"#;
+
+	#[test]
+	fn test_port() {
+		assert_eq!(port("22/tcp"), (Some(22), "tcp"));
+		assert_eq!(port("general/tcp"), (None, "tcp"));
+		assert_eq!(port("package"), (None, "package"));
+	}
+
+	#[test]
+	fn test_parse() {
+		let imported = parse(REPORT).unwrap();
+		assert_eq!(imported.started, DateTime::from_timestamp(1_700_000_000, 0));
+		assert_eq!(imported.targets, ["10.0.0.1", "10.0.0.2"]);
+
+		let summary: Vec<_> = imported
+			.results
+			.iter()
+			.map(|r| (r.target.as_str(), r.scan_type.as_str(), r.port, r.severity, r.details.as_deref()))
+			.collect();
+		assert_eq!(
+			summary,
+			[
+				("10.0.0.1", "tcp_connect", Some(22), Some(Severity::Critical), Some("CVE-2023-38408 (CVSS 9.8)")),
+				(
+					"10.0.0.1",
+					"discovery",
+					None,
+					Some(Severity::Low),
+					Some("TCP Timestamps Information Disclosure (CVSS 2.6)")
+				),
+				("10.0.0.2", "tcp_connect", Some(80), None, None),
+			]
+		);
+
+		// A get_reports response wraps the report in another element
+		let response = format!("<get_reports_response status=\"200\">{}</get_reports_response>", REPORT);
+		assert_eq!(parse(&response).unwrap().results.len(), 3);
+	}
+
+	#[test]
+	fn test_parse_invalid() {
+		assert!(parse("<report><report></report></report>").unwrap_err().to_string().contains("no results"));
+		assert!(parse("<report><results><result><name>x</name></result></results></report>").is_err());
+	}
+}
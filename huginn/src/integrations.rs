@@ -0,0 +1,564 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Vulnerability management integrations for Huginn
+//!
+//! After each scan, the findings (results with a severity at or above each integration's
+//! threshold) are pushed to DefectDojo or Faraday through their APIs. DefectDojo receives them as
+//! a Generic Findings Import, either as a new test in the configured engagement or as a reimport
+//! into an existing test, so findings that are gone are closed there. Faraday receives the scanned
+//! hosts and their open ports along with the findings, in one bulk create call for the configured
+//! workspace. New findings can also be filed as Jira tickets or GitHub issues (see [`issues`]),
+//! and handed to a SOC as TheHive alerts or MISP events (see [`alerts`]).
+
+pub mod alerts;
+pub mod issues;
+
+use crate::baseline::Drift;
+use crate::config::Config;
+use huginn_core::plugins::{ScanResult, Severity};
+use log::{error, info};
+use regex::Regex;
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::LazyLock;
+
+/// CVE identifiers in a result's details, as vulnerability enrichment and imports write them
+static CVE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"CVE-\d{4}-\d{4,}").expect("CVE pattern compiles"));
+
+/// Vulnerability management platform configuration entry
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum IntegrationConfig {
+	/// Import findings into DefectDojo
+	DefectDojo {
+		/// Base URL, e.g. https://defectdojo.example.com
+		url: String,
+		/// API v2 key
+		token: String,
+		/// Engagement ID that each run is imported into as a new test
+		engagement: Option<u64>,
+		/// Test ID that each run is reimported into instead, closing findings no longer present
+		test: Option<u64>,
+		/// Title of the tests created in the engagement
+		#[serde(default = "default_test_title")]
+		test_title: String,
+		/// Push findings at or above this severity; all findings when unset
+		min_severity: Option<Severity>,
+	},
+	/// Create hosts, services, and vulnerabilities in Faraday
+	Faraday {
+		/// Base URL, e.g. https://faraday.example.com
+		url: String,
+		/// API token
+		token: String,
+		/// Workspace the results are added to
+		workspace: String,
+		/// Push findings at or above this severity; all findings when unset
+		min_severity: Option<Severity>,
+	},
+	/// Open a Jira ticket for each new finding
+	Jira {
+		/// Base URL, e.g. https://example.atlassian.net
+		url: String,
+		/// Account email for Jira Cloud API tokens; a personal access token is sent as a bearer
+		/// token when unset
+		user: Option<String>,
+		/// API token or personal access token
+		token: String,
+		/// Key of the project tickets are opened in
+		project: String,
+		/// Issue type of the tickets
+		#[serde(default = "default_issue_type")]
+		issue_type: String,
+		/// Tera template of the ticket summary
+		#[serde(default = "default_issue_title")]
+		title: String,
+		/// Tera template of the ticket description
+		#[serde(default = "default_issue_body")]
+		body: String,
+		/// Labels added to every ticket
+		#[serde(default)]
+		labels: Vec<String>,
+		/// File findings at or above this severity; critical findings when unset
+		min_severity: Option<Severity>,
+	},
+	/// Open a GitHub issue for each new finding
+	GitHub {
+		/// API URL; set for GitHub Enterprise Server, e.g. https://github.example.com/api/v3
+		#[serde(default = "default_github_url")]
+		url: String,
+		/// Token allowed to read and write the repository's issues
+		token: String,
+		/// Repository issues are opened in, as OWNER/NAME
+		repository: String,
+		/// Tera template of the issue title
+		#[serde(default = "default_issue_title")]
+		title: String,
+		/// Tera template of the issue body
+		#[serde(default = "default_issue_body")]
+		body: String,
+		/// Labels added to every issue
+		#[serde(default)]
+		labels: Vec<String>,
+		/// File findings at or above this severity; critical findings when unset
+		min_severity: Option<Severity>,
+	},
+	/// Create a TheHive alert for each host with findings
+	TheHive {
+		/// Base URL, e.g. https://thehive.example.com
+		url: String,
+		/// API key
+		token: String,
+		/// Organisation the alerts are created in, when the key's user belongs to several
+		organisation: Option<String>,
+		/// Tags added to every alert
+		#[serde(default)]
+		tags: Vec<String>,
+		/// Report findings at or above this severity; high and critical findings when unset
+		min_severity: Option<Severity>,
+	},
+	/// Create a MISP event for each host with findings
+	Misp {
+		/// Base URL, e.g. https://misp.example.com
+		url: String,
+		/// Automation key
+		token: String,
+		/// Who the events are shared with, from 0 (this organisation only) to 3 (all communities)
+		#[serde(default)]
+		distribution: u8,
+		/// Tags added to every event
+		#[serde(default)]
+		tags: Vec<String>,
+		/// Report findings at or above this severity; high and critical findings when unset
+		min_severity: Option<Severity>,
+	},
+}
+
+fn default_test_title() -> String {
+	"Huginn scan".to_string()
+}
+
+fn default_issue_type() -> String {
+	"Bug".to_string()
+}
+
+fn default_issue_title() -> String {
+	issues::DEFAULT_TITLE.to_string()
+}
+
+fn default_issue_body() -> String {
+	issues::DEFAULT_BODY.to_string()
+}
+
+fn default_github_url() -> String {
+	"https://api.github.com".to_string()
+}
+
+impl fmt::Display for IntegrationConfig {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			IntegrationConfig::DefectDojo { url, test: Some(test), .. } => write!(f, "DefectDojo test {} at {}", test, url),
+			IntegrationConfig::DefectDojo { url, engagement: Some(engagement), .. } => {
+				write!(f, "DefectDojo engagement {} at {}", engagement, url)
+			},
+			IntegrationConfig::DefectDojo { url, .. } => write!(f, "DefectDojo at {}", url),
+			IntegrationConfig::Faraday { url, workspace, .. } => write!(f, "Faraday workspace {} at {}", workspace, url),
+			IntegrationConfig::Jira { url, project, .. } => write!(f, "Jira project {} at {}", project, url),
+			IntegrationConfig::GitHub { repository, .. } => write!(f, "GitHub repository {}", repository),
+			IntegrationConfig::TheHive { url, .. } => write!(f, "TheHive at {}", url),
+			IntegrationConfig::Misp { url, .. } => write!(f, "MISP at {}", url),
+		}
+	}
+}
+
+impl IntegrationConfig {
+	fn min_severity(&self) -> Severity {
+		match self {
+			IntegrationConfig::DefectDojo { min_severity, .. } | IntegrationConfig::Faraday { min_severity, .. } => {
+				min_severity.unwrap_or(Severity::Info)
+			},
+			IntegrationConfig::Jira { min_severity, .. } | IntegrationConfig::GitHub { min_severity, .. } => {
+				min_severity.unwrap_or(Severity::Critical)
+			},
+			IntegrationConfig::TheHive { min_severity, .. } | IntegrationConfig::Misp { min_severity, .. } => {
+				min_severity.unwrap_or(Severity::High)
+			},
+		}
+	}
+
+	/// Check the settings that would make every push fail
+	pub fn check(&self) -> Result<(), String> {
+		match self {
+			IntegrationConfig::DefectDojo { engagement: None, test: None, .. } => {
+				Err("set the engagement or test to import into".to_string())
+			},
+			IntegrationConfig::Jira { title, body, .. } | IntegrationConfig::GitHub { title, body, .. } => {
+				issues::check(title, body)
+			},
+			IntegrationConfig::Misp { distribution, .. } if *distribution > 3 => {
+				Err("distribution must be from 0 (this organisation only) to 3 (all communities)".to_string())
+			},
+			_ => Ok(()),
+		}
+	}
+}
+
+/// Whether a result is a finding to push at a threshold
+fn is_finding(result: &ScanResult, threshold: Severity) -> bool {
+	result.severity.is_some_and(|s| s >= threshold)
+}
+
+/// Short name of a finding: what was found, and where
+fn title(result: &ScanResult) -> String {
+	let what = result.version.as_deref().or(result.service.as_deref()).unwrap_or(&result.scan_type);
+	match result.port {
+		Some(port) => format!("{} on port {}", what, port),
+		None => format!("{} on {}", what, result.target),
+	}
+}
+
+/// Full description of a finding, one field per line
+fn description(result: &ScanResult) -> String {
+	let mut lines = vec![
+		format!("Target: {}", result.target),
+		format!("Scan type: {}", result.scan_type),
+		format!("Status: {}", result.status),
+	];
+	if let Some(port) = result.port {
+		lines.push(format!("Port: {}", port));
+	}
+	if let Some(service) = &result.service {
+		lines.push(format!("Service: {}", service));
+	}
+	if let Some(version) = &result.version {
+		lines.push(format!("Version: {}", version));
+	}
+	if let Some(details) = &result.details {
+		lines.push(format!("Details: {}", details));
+	}
+	if !result.techniques.is_empty() {
+		lines.push(format!("ATT&CK techniques: {}", result.techniques.join(", ")));
+	}
+	lines.join("\n")
+}
+
+/// Distinct CVEs named in a finding's details
+fn cves(result: &ScanResult) -> Vec<String> {
+	let mut ids: Vec<String> = Vec::new();
+	for id in CVE.find_iter(result.details.as_deref().unwrap_or_default()) {
+		if !ids.iter().any(|seen| seen == id.as_str()) {
+			ids.push(id.as_str().to_string());
+		}
+	}
+	ids
+}
+
+/// DefectDojo's name for a severity
+fn defectdojo_severity(severity: Severity) -> &'static str {
+	match severity {
+		Severity::Info => "Info",
+		Severity::Low => "Low",
+		Severity::Medium => "Medium",
+		Severity::High => "High",
+		Severity::Critical => "Critical",
+	}
+}
+
+/// Faraday's name for a severity
+fn faraday_severity(severity: Severity) -> &'static str {
+	match severity {
+		Severity::Info => "informational",
+		Severity::Low => "low",
+		Severity::Medium => "medium",
+		Severity::High => "high",
+		Severity::Critical => "critical",
+	}
+}
+
+/// Findings in DefectDojo's Generic Findings Import format
+fn defectdojo_report(results: &[ScanResult], threshold: Severity, date: &str) -> Value {
+	let findings: Vec<Value> = results
+		.iter()
+		.filter(|r| is_finding(r, threshold))
+		.map(|result| {
+			let mut endpoint = json!({ "host": result.target });
+			if let Some(port) = result.port {
+				endpoint["port"] = port.into();
+			}
+			let ids: Vec<Value> = cves(result).into_iter().map(|id| json!({ "vulnerability_id": id })).collect();
+			json!({
+				"title": title(result),
+				"severity": defectdojo_severity(result.severity.unwrap_or(Severity::Info)),
+				"description": description(result),
+				"date": date,
+				"active": true,
+				"verified": false,
+				"dynamic_finding": true,
+				"static_finding": false,
+				"vulnerability_ids": ids,
+				"endpoints": [endpoint],
+			})
+		})
+		.collect();
+	json!({ "findings": findings })
+}
+
+/// Protocol of the port a result is about
+fn protocol(result: &ScanResult) -> &'static str {
+	if result.scan_type == "udp" { "udp" } else { "tcp" }
+}
+
+/// A finding as a Faraday vulnerability
+fn faraday_vuln(result: &ScanResult) -> Value {
+	json!({
+		"name": title(result),
+		"desc": description(result),
+		"severity": faraday_severity(result.severity.unwrap_or(Severity::Info)),
+		"type": "Vulnerability",
+		"status": "open",
+		"confirmed": false,
+		"cve": cves(result),
+		"refs": [],
+	})
+}
+
+/// A host's services by port and protocol, each with the findings on it
+type Services = BTreeMap<(u16, &'static str), (Value, Vec<Value>)>;
+
+/// Hosts, their open ports, and findings in the shape of Faraday's bulk create call
+fn faraday_payload(results: &[ScanResult], threshold: Severity) -> Value {
+	let mut hosts: BTreeMap<&str, (Vec<Value>, Services)> = BTreeMap::new();
+	for result in results {
+		let finding = is_finding(result, threshold);
+		let open = result.status == "open" || result.status == "up";
+		if !finding && !open {
+			continue;
+		}
+		let (vulns, services) = hosts.entry(&result.target).or_default();
+		let Some(port) = result.port else {
+			if finding {
+				vulns.push(faraday_vuln(result));
+			}
+			continue;
+		};
+		// Several scan types may have found the same port; they are one service to Faraday
+		let (service, service_vulns) = services.entry((port, protocol(result))).or_insert_with(|| {
+			let status = if open { "open" } else { "filtered" };
+			(json!({ "name": "unknown", "port": port, "protocol": protocol(result), "status": status, "version": "" }), Vec::new())
+		});
+		if let Some(name) = &result.service {
+			service["name"] = name.as_str().into();
+		}
+		if let Some(version) = &result.version {
+			service["version"] = version.as_str().into();
+		}
+		if open {
+			service["status"] = "open".into();
+		}
+		if finding {
+			service_vulns.push(faraday_vuln(result));
+		}
+	}
+
+	let hosts: Vec<Value> = hosts
+		.into_iter()
+		.map(|(target, (vulns, services))| {
+			let services: Vec<Value> = services
+				.into_values()
+				.map(|(mut service, vulns)| {
+					service["vulns"] = vulns.into();
+					service
+				})
+				.collect();
+			json!({ "ip": target, "description": "Scanned by Huginn", "hostnames": [], "services": services, "vulns": vulns })
+		})
+		.collect();
+	json!({ "hosts": hosts })
+}
+
+/// Import or reimport the findings into DefectDojo
+async fn defectdojo(
+	client: &reqwest::Client,
+	integration: &IntegrationConfig,
+	results: &[ScanResult],
+) -> Result<(), Box<dyn Error>> {
+	let IntegrationConfig::DefectDojo { url, token, engagement, test, test_title, .. } = integration else {
+		return Ok(());
+	};
+	let date = chrono::Utc::now().format("%Y-%m-%d").to_string();
+	let report = defectdojo_report(results, integration.min_severity(), &date);
+	let file = Part::bytes(serde_json::to_vec(&report)?).file_name("huginn.json").mime_str("application/json")?;
+	let form = Form::new()
+		.text("scan_type", "Generic Findings Import")
+		.text("scan_date", date)
+		.text("minimum_severity", defectdojo_severity(integration.min_severity()))
+		.text("active", "true")
+		.text("verified", "false")
+		.text("close_old_findings", "true")
+		.part("file", file);
+	let (endpoint, form) = match (test, engagement) {
+		(Some(test), _) => ("reimport-scan", form.text("test", test.to_string())),
+		(None, Some(engagement)) => (
+			"import-scan",
+			form.text("engagement", engagement.to_string()).text("test_title", test_title.clone()),
+		),
+		(None, None) => return Err("DefectDojo integration needs an engagement or a test".into()),
+	};
+	client
+		.post(format!("{}/api/v2/{}/", url.trim_end_matches('/'), endpoint))
+		.header("Authorization", format!("Token {}", token))
+		.multipart(form)
+		.send()
+		.await?
+		.error_for_status()?;
+	Ok(())
+}
+
+/// Create the hosts, services, and findings in a Faraday workspace
+async fn faraday(
+	client: &reqwest::Client,
+	integration: &IntegrationConfig,
+	results: &[ScanResult],
+) -> Result<(), Box<dyn Error>> {
+	let IntegrationConfig::Faraday { url, token, workspace, .. } = integration else {
+		return Ok(());
+	};
+	client
+		.post(format!("{}/_api/v3/ws/{}/bulk_create", url.trim_end_matches('/'), workspace))
+		.header("Authorization", format!("Token {}", token))
+		.json(&faraday_payload(results, integration.min_severity()))
+		.send()
+		.await?
+		.error_for_status()?;
+	Ok(())
+}
+
+/// Push the findings to every configured platform; issues are only filed for findings that
+/// are not in the baseline
+pub async fn push(config: &Config, results: &[ScanResult], drift: Option<&Drift>) -> Result<(), Box<dyn Error>> {
+	let client = reqwest::Client::new();
+	let new = drift.map_or(results, |drift| &drift.added);
+	let new_findings = |threshold| -> Vec<&ScanResult> { new.iter().filter(|r| is_finding(r, threshold)).collect() };
+	let mut failures = 0;
+	for integration in &config.integrations {
+		let pushed = match integration {
+			IntegrationConfig::DefectDojo { .. } => defectdojo(&client, integration, results).await,
+			IntegrationConfig::Faraday { .. } => faraday(&client, integration, results).await,
+			IntegrationConfig::Jira { .. } => {
+				issues::jira(&client, integration, &new_findings(integration.min_severity())).await
+			},
+			IntegrationConfig::GitHub { .. } => {
+				issues::github(&client, integration, &new_findings(integration.min_severity())).await
+			},
+			IntegrationConfig::TheHive { .. } => alerts::thehive(&client, integration, results).await,
+			IntegrationConfig::Misp { .. } => alerts::misp(&client, integration, results).await,
+		};
+		match pushed {
+			Ok(()) => info!("Pushed findings to {}", integration),
+			Err(e) => {
+				error!("Failed to push findings to {}: {}", integration, e);
+				failures += 1;
+			},
+		}
+	}
+	if failures > 0 {
+		return Err(format!("{} integrations failed", failures).into());
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_integration_config_deserialize() {
+		let integrations: Vec<IntegrationConfig> = serde_json::from_str(
+			r#"[
+				{ "type": "defectdojo", "url": "https://dojo.example.com", "token": "t", "engagement": 7 },
+				{ "type": "faraday", "url": "https://faraday.example.com", "token": "t", "workspace": "dmz", "min_severity": "high" }
+			]"#,
+		)
+		.unwrap();
+		assert_eq!(integrations[0].to_string(), "DefectDojo engagement 7 at https://dojo.example.com");
+		assert!(matches!(&integrations[0], IntegrationConfig::DefectDojo { test_title, .. } if test_title == "Huginn scan"));
+		assert_eq!(integrations[1].min_severity(), Severity::High);
+		assert!(serde_json::from_str::<IntegrationConfig>(r#"{ "type": "faraday", "url": "u", "token": "t" }"#).is_err());
+
+		let github: IntegrationConfig =
+			serde_json::from_str(r#"{ "type": "github", "token": "t", "repository": "acme/infra" }"#).unwrap();
+		assert!(matches!(&github, IntegrationConfig::GitHub { url, .. } if url == "https://api.github.com"));
+		assert_eq!(github.min_severity(), Severity::Critical);
+		assert!(github.check().is_ok());
+		let jira: IntegrationConfig = serde_json::from_str(
+			r#"{ "type": "jira", "url": "https://acme.atlassian.net", "token": "t", "project": "SEC", "title": "{% if %}" }"#,
+		)
+		.unwrap();
+		assert!(jira.check().is_err());
+
+		let misp: IntegrationConfig =
+			serde_json::from_str(r#"{ "type": "misp", "url": "https://misp.example.com", "token": "t", "distribution": 4 }"#).unwrap();
+		assert_eq!(misp.min_severity(), Severity::High);
+		assert!(misp.check().is_err());
+	}
+
+	#[test]
+	fn test_defectdojo_report() {
+		let results = vec![
+			ScanResult::new("10.0.0.1", "tcp_connect", "open")
+				.with_port(22)
+				.with_severity(Severity::Critical)
+				.with_service("ssh")
+				.with_details("CVE-2023-38408 (CVSS 9.8), CVE-2023-38408 (CVSS 9.8)"),
+			ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(80).with_service("ssh"),
+			ScanResult::new("10.0.0.1", "tcp_connect", "up")
+				.with_severity(Severity::Low)
+				.with_details("TCP timestamps"),
+		];
+		let report = defectdojo_report(&results, Severity::Info, "2025-06-01");
+		let findings = report["findings"].as_array().unwrap();
+		assert_eq!(findings.len(), 2);
+		assert_eq!(findings[0]["title"], "ssh on port 22");
+		assert_eq!(findings[0]["severity"], "Critical");
+		assert_eq!(findings[0]["vulnerability_ids"], json!([{ "vulnerability_id": "CVE-2023-38408" }]));
+		assert_eq!(findings[0]["endpoints"], json!([{ "host": "10.0.0.1", "port": 22 }]));
+		assert_eq!(findings[1]["title"], "tcp_connect on 10.0.0.1");
+		assert_eq!(findings[1]["endpoints"], json!([{ "host": "10.0.0.1" }]));
+
+		assert_eq!(defectdojo_report(&results, Severity::High, "2025-06-01")["findings"].as_array().unwrap().len(), 1);
+	}
+
+	#[test]
+	fn test_faraday_payload() {
+		let mut syn = ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22).with_service("ssh");
+		syn.scan_type = "tcp_syn".to_string();
+		syn.version = Some("OpenSSH 8.9".to_string());
+		let results = vec![
+			ScanResult::new("10.0.0.1", "tcp_connect", "open")
+				.with_port(22)
+				.with_severity(Severity::High)
+				.with_service("ssh")
+				.with_details("CVE-2023-38408 (CVSS 9.8)"),
+			syn,
+			ScanResult::new("10.0.0.1", "tcp_connect", "closed").with_port(23).with_service("ssh"),
+			ScanResult::new("10.0.0.1", "tcp_connect", "up").with_severity(Severity::Low),
+		];
+		let payload = faraday_payload(&results, Severity::Info);
+		let host = &payload["hosts"][0];
+		assert_eq!(host["ip"], "10.0.0.1");
+		assert_eq!(host["vulns"][0]["severity"], "low");
+
+		let services = host["services"].as_array().unwrap();
+		assert_eq!(services.len(), 1);
+		assert_eq!(services[0]["name"], "ssh");
+		assert_eq!(services[0]["version"], "OpenSSH 8.9");
+		assert_eq!(services[0]["protocol"], "tcp");
+		assert_eq!(services[0]["vulns"][0]["severity"], "high");
+		assert_eq!(services[0]["vulns"][0]["cve"], json!(["CVE-2023-38408"]));
+	}
+}
@@ -0,0 +1,392 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! SOC alert integrations
+//!
+//! Hands findings at or above the integration's threshold, high by default, to a SOC as a
+//! TheHive alert or MISP event for each host that has any. Each alert lists the host's findings
+//! and carries their observables: the host's address, its open ports, the banners of the services
+//! on them, the CVEs in the findings, and the certificate fingerprints found in result details,
+//! such as those Nmap's `ssl-cert` script reports.
+
+use super::{IntegrationConfig, cves, description, is_finding};
+use huginn_core::plugins::{ScanResult, Severity};
+use log::info;
+use regex::Regex;
+use serde_json::{Value, json};
+use std::error::Error;
+use std::net::IpAddr;
+use std::sync::LazyLock;
+
+/// Certificate fingerprints in details, as `SHA-256: AB:CD:...` or Nmap's `SHA-1: abcd ef01 ...`
+static FINGERPRINT: LazyLock<Regex> = LazyLock::new(|| {
+	Regex::new(r"(?i)\b(sha-?256|sha-?1|md5)(?: fingerprint)?:\s*([0-9a-f][0-9a-f: ]*)").expect("fingerprint pattern compiles")
+});
+
+/// A certificate fingerprint
+#[derive(Debug, PartialEq)]
+struct Fingerprint {
+	/// Digest name as MISP spells it: `sha256`, `sha1`, or `md5`
+	kind: &'static str,
+	/// Lowercase hex digest
+	hash: String,
+}
+
+/// Observables of one host
+#[derive(Debug, Default)]
+struct Observables {
+	/// Open ports with their protocol
+	ports: Vec<(u16, &'static str)>,
+	/// Product and version banners
+	banners: Vec<String>,
+	/// CVEs named in the findings
+	cves: Vec<String>,
+	/// Certificate fingerprints
+	fingerprints: Vec<Fingerprint>,
+}
+
+/// Add a value to a list unless it is already there
+fn add<T: PartialEq>(list: &mut Vec<T>, value: T) {
+	if !list.contains(&value) {
+		list.push(value);
+	}
+}
+
+/// Certificate fingerprints in a result's details
+fn fingerprints(result: &ScanResult) -> Vec<Fingerprint> {
+	let details = result.details.as_deref().unwrap_or_default();
+	FINGERPRINT
+		.captures_iter(details)
+		.filter_map(|captures| {
+			let (kind, length) = match captures[1].to_ascii_lowercase().replace('-', "").as_str() {
+				"sha256" => ("sha256", 64),
+				"sha1" => ("sha1", 40),
+				_ => ("md5", 32),
+			};
+			let hex: String = captures[2].chars().filter(char::is_ascii_hexdigit).take(length).collect();
+			(hex.len() == length).then(|| Fingerprint { kind, hash: hex.to_ascii_lowercase() })
+		})
+		.collect()
+}
+
+/// Observables of a host's results, with CVEs taken from its findings only
+fn observables(results: &[&ScanResult], threshold: Severity) -> Observables {
+	let mut observables = Observables::default();
+	for result in results {
+		if let Some(port) = result.port
+			&& result.status == "open"
+		{
+			add(&mut observables.ports, (port, super::protocol(result)));
+		}
+		if let Some(version) = &result.version {
+			add(&mut observables.banners, version.clone());
+		}
+		if is_finding(result, threshold) {
+			for cve in cves(result) {
+				add(&mut observables.cves, cve);
+			}
+		}
+		for fingerprint in fingerprints(result) {
+			add(&mut observables.fingerprints, fingerprint);
+		}
+	}
+	observables
+}
+
+/// The results of each host with a finding at or above the threshold, in scan order
+fn hosts(results: &[ScanResult], threshold: Severity) -> Vec<(&str, Vec<&ScanResult>)> {
+	let mut hosts: Vec<(&str, Vec<&ScanResult>)> = Vec::new();
+	for result in results {
+		match hosts.iter_mut().find(|(target, _)| *target == result.target) {
+			Some((_, host)) => host.push(result),
+			None => hosts.push((&result.target, vec![result])),
+		}
+	}
+	hosts.retain(|(_, host)| host.iter().any(|r| is_finding(r, threshold)));
+	hosts
+}
+
+/// Title and Markdown description of a host's alert
+fn summary(target: &str, results: &[&ScanResult], threshold: Severity) -> (String, String) {
+	let findings: Vec<&&ScanResult> = results.iter().filter(|r| is_finding(r, threshold)).collect();
+	let title = match findings.len() {
+		1 => format!("Huginn: {} on {}", super::title(findings[0]), target),
+		n => format!("Huginn: {} findings on {}", n, target),
+	};
+	let description = findings
+		.iter()
+		.map(|finding| {
+			let severity = finding.severity.unwrap_or(Severity::Info);
+			let fields = description(finding).replace('\n', "\n  - ");
+			format!("- **[{}] {}**\n  - {}", severity, super::title(finding), fields)
+		})
+		.collect::<Vec<_>>()
+		.join("\n");
+	(title, description)
+}
+
+/// TheHive's severity scale, from 1 (low) to 4 (critical)
+fn thehive_severity(severity: Severity) -> u8 {
+	match severity {
+		Severity::Info | Severity::Low => 1,
+		Severity::Medium => 2,
+		Severity::High => 3,
+		Severity::Critical => 4,
+	}
+}
+
+/// A TheHive alert for a host
+fn thehive_alert(target: &str, results: &[&ScanResult], threshold: Severity, run: &str, tags: &[String]) -> Value {
+	let observables = observables(results, threshold);
+	let severity = results.iter().filter_map(|r| r.severity).max().unwrap_or(Severity::Info);
+	let (title, description) = summary(target, results, threshold);
+
+	let address = if target.parse::<IpAddr>().is_ok() { "ip" } else { "fqdn" };
+	let mut items = vec![json!({ "dataType": address, "data": target, "message": "Scanned host" })];
+	for (port, protocol) in &observables.ports {
+		items.push(json!({ "dataType": "other", "data": format!("{}/{}", port, protocol), "message": "Open port", "tags": ["port"] }));
+	}
+	for banner in &observables.banners {
+		items.push(json!({ "dataType": "other", "data": banner, "message": "Service banner", "tags": ["banner"] }));
+	}
+	for cve in &observables.cves {
+		items.push(json!({ "dataType": "other", "data": cve, "message": "Vulnerability", "tags": ["cve"] }));
+	}
+	for fingerprint in &observables.fingerprints {
+		let message = format!("Certificate {} fingerprint", fingerprint.kind);
+		items.push(json!({ "dataType": "hash", "data": fingerprint.hash, "message": message, "tags": ["certificate"] }));
+	}
+
+	let mut all_tags = vec!["huginn".to_string()];
+	all_tags.extend(tags.iter().cloned());
+	json!({
+		"type": "huginn-scan",
+		"source": "huginn",
+		"sourceRef": format!("{}-{}", run, target),
+		"title": title,
+		"description": description,
+		"severity": thehive_severity(severity),
+		"tags": all_tags,
+		"observables": items,
+	})
+}
+
+/// MISP's threat level, from 1 (high) to 3 (low)
+fn misp_threat_level(severity: Severity) -> u8 {
+	match severity {
+		Severity::Critical | Severity::High => 1,
+		Severity::Medium => 2,
+		Severity::Low | Severity::Info => 3,
+	}
+}
+
+/// A MISP event for a host
+fn misp_event(
+	target: &str,
+	results: &[&ScanResult],
+	threshold: Severity,
+	date: &str,
+	distribution: u8,
+	tags: &[String],
+) -> Value {
+	let observables = observables(results, threshold);
+	let severity = results.iter().filter_map(|r| r.severity).max().unwrap_or(Severity::Info);
+	let (title, description) = summary(target, results, threshold);
+
+	let address = if target.parse::<IpAddr>().is_ok() { "ip-dst" } else { "hostname" };
+	let attribute = |kind: &str, category: &str, value: &str, comment: &str| {
+		json!({ "type": kind, "category": category, "value": value, "comment": comment, "to_ids": false })
+	};
+	let mut attributes = vec![
+		attribute(address, "Network activity", target, "Scanned host"),
+		attribute("text", "Other", &description, "Findings"),
+	];
+	for (port, protocol) in &observables.ports {
+		let value = format!("{}|{}", target, port);
+		let kind = if address == "ip-dst" { "ip-dst|port" } else { "hostname|port" };
+		attributes.push(attribute(kind, "Network activity", &value, &format!("Open {} port", protocol)));
+	}
+	for banner in &observables.banners {
+		attributes.push(attribute("text", "Network activity", banner, "Service banner"));
+	}
+	for cve in &observables.cves {
+		attributes.push(attribute("vulnerability", "External analysis", cve, "Found by Huginn"));
+	}
+	for fingerprint in &observables.fingerprints {
+		let kind = format!("x509-fingerprint-{}", fingerprint.kind);
+		attributes.push(attribute(&kind, "Network activity", &fingerprint.hash, "Certificate fingerprint"));
+	}
+
+	let all_tags: Vec<Value> = std::iter::once("huginn")
+		.chain(tags.iter().map(String::as_str))
+		.map(|name| json!({ "name": name }))
+		.collect();
+	json!({
+		"Event": {
+			"info": title,
+			"date": date,
+			"threat_level_id": misp_threat_level(severity).to_string(),
+			"analysis": "0",
+			"distribution": distribution.to_string(),
+			"Tag": all_tags,
+			"Attribute": attributes,
+		}
+	})
+}
+
+/// Create a TheHive alert for each host with findings
+pub async fn thehive(
+	client: &reqwest::Client,
+	integration: &IntegrationConfig,
+	results: &[ScanResult],
+) -> Result<(), Box<dyn Error>> {
+	let IntegrationConfig::TheHive { url, token, organisation, tags, .. } = integration else {
+		return Ok(());
+	};
+	let threshold = integration.min_severity();
+	// TheHive rejects a second alert with the same source reference, so each run gets its own
+	let run = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+	for (target, host) in hosts(results, threshold) {
+		let mut request = client
+			.post(format!("{}/api/v1/alert", url.trim_end_matches('/')))
+			.bearer_auth(token)
+			.json(&thehive_alert(target, &host, threshold, &run, tags));
+		if let Some(organisation) = organisation {
+			request = request.header("X-Organisation", organisation);
+		}
+		let created: Value = request.send().await?.error_for_status()?.json().await?;
+		info!("Created TheHive alert {} for {}", created["_id"].as_str().unwrap_or_default(), target);
+	}
+	Ok(())
+}
+
+/// Create a MISP event for each host with findings
+pub async fn misp(
+	client: &reqwest::Client,
+	integration: &IntegrationConfig,
+	results: &[ScanResult],
+) -> Result<(), Box<dyn Error>> {
+	let IntegrationConfig::Misp { url, token, distribution, tags, .. } = integration else {
+		return Ok(());
+	};
+	let threshold = integration.min_severity();
+	let date = chrono::Utc::now().format("%Y-%m-%d").to_string();
+	for (target, host) in hosts(results, threshold) {
+		let created: Value = client
+			.post(format!("{}/events/add", url.trim_end_matches('/')))
+			.header("Authorization", token)
+			.header("Accept", "application/json")
+			.json(&misp_event(target, &host, threshold, &date, *distribution, tags))
+			.send()
+			.await?
+			.error_for_status()?
+			.json()
+			.await?;
+		info!("Created MISP event {} for {}", created["Event"]["id"].as_str().unwrap_or_default(), target);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn results() -> Vec<ScanResult> {
+		vec![
+			ScanResult::new("10.0.0.1", "tcp_connect", "open")
+				.with_port(443)
+				.with_severity(Severity::High)
+				.with_service("https")
+				.with_version("nginx 1.18.0")
+				.with_details("CVE-2021-23017 (CVSS 7.7)"),
+			ScanResult::new("10.0.0.1", "tcp_connect", "open")
+				.with_port(8443)
+				.with_service("https")
+				.with_version("nginx 1.18.0")
+				.with_details("ssl-cert: Subject: commonName=www; SHA-1: 2a4c 1f3e 09b8 77d4 5a61 0c2e 9f3b 4d1a 6e7f 8a9b"),
+			ScanResult::new("10.0.0.2", "tcp_connect", "open")
+				.with_port(22)
+				.with_severity(Severity::Low)
+				.with_service("https")
+				.with_version("nginx 1.18.0"),
+			ScanResult::new("www.example.com", "tcp_connect", "open").with_severity(Severity::Critical),
+		]
+	}
+
+	#[test]
+	fn test_fingerprints() {
+		let cert = ScanResult::new("h", "tcp_connect", "open")
+			.with_details("SHA-256 Fingerprint: AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89; MD5: 12");
+		assert_eq!(
+			fingerprints(&cert),
+			[Fingerprint { kind: "sha256", hash: "abcdef0123456789".repeat(4) }]
+		);
+		assert_eq!(fingerprints(&results()[1])[0].hash, "2a4c1f3e09b877d45a610c2e9f3b4d1a6e7f8a9b");
+	}
+
+	#[test]
+	fn test_hosts() {
+		let results = results();
+		let hosts = hosts(&results, Severity::High);
+		let summary: Vec<_> = hosts.iter().map(|(target, host)| (*target, host.len())).collect();
+		assert_eq!(summary, [("10.0.0.1", 2), ("www.example.com", 1)]);
+
+		let observables = observables(&hosts[0].1, Severity::High);
+		assert_eq!(observables.ports, [(443, "tcp"), (8443, "tcp")]);
+		assert_eq!(observables.banners, ["nginx 1.18.0"]);
+		assert_eq!(observables.cves, ["CVE-2021-23017"]);
+		assert_eq!(observables.fingerprints.len(), 1);
+	}
+
+	#[test]
+	fn test_thehive_alert() {
+		let results = results();
+		let hosts = hosts(&results, Severity::High);
+		let alert = thehive_alert("10.0.0.1", &hosts[0].1, Severity::High, "20250601T020000Z", &["dmz".to_string()]);
+		assert_eq!(alert["title"], "Huginn: nginx 1.18.0 on port 443 on 10.0.0.1");
+		assert_eq!(alert["sourceRef"], "20250601T020000Z-10.0.0.1");
+		assert_eq!(alert["severity"], 3);
+		assert_eq!(alert["tags"], json!(["huginn", "dmz"]));
+		let observables: Vec<_> =
+			alert["observables"].as_array().unwrap().iter().map(|o| (o["dataType"].as_str().unwrap(), o["data"].as_str().unwrap())).collect();
+		assert_eq!(
+			observables,
+			[
+				("ip", "10.0.0.1"),
+				("other", "443/tcp"),
+				("other", "8443/tcp"),
+				("other", "nginx 1.18.0"),
+				("other", "CVE-2021-23017"),
+				("hash", "2a4c1f3e09b877d45a610c2e9f3b4d1a6e7f8a9b"),
+			]
+		);
+
+		let alert = thehive_alert("www.example.com", &hosts[1].1, Severity::High, "r", &[]);
+		assert_eq!(alert["observables"][0]["dataType"], "fqdn");
+		assert_eq!(alert["severity"], 4);
+	}
+
+	#[test]
+	fn test_misp_event() {
+		let results = results();
+		let hosts = hosts(&results, Severity::High);
+		let event = misp_event("10.0.0.1", &hosts[0].1, Severity::High, "2025-06-01", 0, &[]);
+		let event = &event["Event"];
+		assert_eq!(event["threat_level_id"], "1");
+		assert_eq!(event["Tag"], json!([{ "name": "huginn" }]));
+		let attributes: Vec<_> =
+			event["Attribute"].as_array().unwrap().iter().map(|a| (a["type"].as_str().unwrap(), a["value"].as_str().unwrap())).collect();
+		assert_eq!(attributes[0], ("ip-dst", "10.0.0.1"));
+		assert!(attributes[1].1.starts_with("- **[high] nginx 1.18.0 on port 443**\n  - Target: 10.0.0.1"));
+		assert_eq!(
+			attributes[2..],
+			[
+				("ip-dst|port", "10.0.0.1|443"),
+				("ip-dst|port", "10.0.0.1|8443"),
+				("text", "nginx 1.18.0"),
+				("vulnerability", "CVE-2021-23017"),
+				("x509-fingerprint-sha1", "2a4c1f3e09b877d45a610c2e9f3b4d1a6e7f8a9b"),
+			]
+		);
+	}
+}
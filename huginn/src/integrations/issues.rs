@@ -0,0 +1,303 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Issue tracker integrations
+//!
+//! Opens a Jira ticket or GitHub issue for each new finding at or above the integration's
+//! threshold, critical by default, where new means not in the baseline when one is configured.
+//! Titles and bodies are Tera templates rendered for each finding, and every issue is labeled
+//! `huginn` and with the finding's severity. Each issue also carries a key made from the
+//! finding's target, scan type, and port, and the CVEs it names or else a hash of its details:
+//! as a label in Jira, and as a hidden comment in the body on GitHub. Open issues are searched
+//! for the key before filing, so a finding gets one issue however many runs report it, and a
+//! new one if it comes back after its issue was resolved.
+
+use super::{IntegrationConfig, cves, description, title};
+use huginn_core::plugins::{ScanResult, Severity};
+use log::info;
+use regex::Regex;
+use reqwest::{RequestBuilder, StatusCode};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::Write;
+use std::sync::LazyLock;
+use tera::{Context, Tera};
+
+/// Default issue title template
+pub const DEFAULT_TITLE: &str = "[{{ severity }}] {{ finding.target }}: {{ title }}";
+
+/// Default issue body template
+pub const DEFAULT_BODY: &str = "Huginn found a new {{ severity }} finding.\n\n{{ description }}\n";
+
+/// Label on every issue Huginn files
+const LABEL: &str = "huginn";
+
+/// Longest label Jira accepts
+const MAX_LABEL: usize = 255;
+
+/// Hex digits of a digest kept in a key
+const DIGEST_LENGTH: usize = 12;
+
+/// GitHub's largest page of issues
+const PAGE_SIZE: usize = 100;
+
+/// Finding key in the body of a GitHub issue
+static MARKER: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r"<!-- (huginn-\S+) -->").expect("issue marker pattern compiles"));
+
+/// Key identifying a finding across runs; Jira labels cannot contain spaces
+fn key(result: &ScanResult) -> String {
+	let mut key = format!("{}-{}-{}", LABEL, result.target, result.scan_type);
+	if let Some(port) = result.port {
+		let _ = write!(key, "-{}", port);
+	}
+	let mut ids = cves(result);
+	ids.sort();
+	let identity = match (ids.is_empty(), &result.details) {
+		(false, _) => ids.join("_"),
+		(true, Some(details)) => digest(details),
+		(true, None) => String::new(),
+	};
+	if !identity.is_empty() {
+		let _ = write!(key, "-{}", identity);
+	}
+	if key.len() > MAX_LABEL {
+		key.truncate(key.len() - identity.len());
+		key.push_str(&digest(&identity));
+	}
+	key.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+/// Short hex digest of a finding's text
+fn digest(text: &str) -> String {
+	let mut digest = hex::encode(Sha256::digest(text.as_bytes()));
+	digest.truncate(DIGEST_LENGTH);
+	digest
+}
+
+/// A JQL string literal
+fn jql_string(value: &str) -> String {
+	format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Render a title or body template for a finding
+fn render(template: &str, result: &ScanResult) -> Result<String, Box<dyn Error>> {
+	let mut context = Context::new();
+	context.insert("finding", result);
+	context.insert("title", &title(result));
+	context.insert("description", &description(result));
+	context.insert("severity", &result.severity.unwrap_or(Severity::Info).to_string());
+	Tera::one_off(template, &context, false).map_err(|e| chain(&e).into())
+}
+
+/// A Tera error with its causes, which carry the position and reason of a syntax error
+fn chain(error: &tera::Error) -> String {
+	let mut message = error.to_string();
+	let mut source = std::error::Error::source(error);
+	while let Some(cause) = source {
+		let _ = write!(message, ": {}", cause);
+		source = cause.source();
+	}
+	message
+}
+
+/// Check that the title and body templates parse
+pub fn check(title: &str, body: &str) -> Result<(), String> {
+	let mut tera = Tera::default();
+	tera.add_raw_templates([("title", title), ("body", body)]).map_err(|e| chain(&e))
+}
+
+/// Open a Jira ticket for each finding that has none
+pub async fn jira(
+	client: &reqwest::Client,
+	integration: &IntegrationConfig,
+	findings: &[&ScanResult],
+) -> Result<(), Box<dyn Error>> {
+	let IntegrationConfig::Jira { url, user, token, project, issue_type, title, body, labels, .. } = integration else {
+		return Ok(());
+	};
+	let url = url.trim_end_matches('/');
+	// Jira Cloud takes an email and API token; Data Center takes a personal access token
+	let authorize = |request: RequestBuilder| match user {
+		Some(user) => request.basic_auth(user, Some(token)),
+		None => request.bearer_auth(token),
+	};
+
+	for finding in findings {
+		let key = key(finding);
+		// Resolved tickets do not count, so a finding that comes back is filed again
+		let jql = format!(
+			"project = {} AND labels = {} AND statusCategory != Done",
+			jql_string(project),
+			jql_string(&key)
+		);
+		let query = [("jql", jql.as_str()), ("maxResults", "1"), ("fields", "summary")];
+		// Jira Cloud replaced `search` with `search/jql`, which Data Center does not have
+		let mut response = authorize(client.get(format!("{}/rest/api/2/search/jql", url)).query(&query)).send().await?;
+		if response.status() == StatusCode::NOT_FOUND {
+			response = authorize(client.get(format!("{}/rest/api/2/search", url)).query(&query)).send().await?;
+		}
+		let found: Value = response.error_for_status()?.json().await?;
+		if found["issues"].as_array().is_some_and(|issues| !issues.is_empty()) {
+			continue;
+		}
+
+		let severity = finding.severity.unwrap_or(Severity::Info);
+		let mut all_labels = vec![LABEL.to_string(), format!("severity-{}", severity), key.clone()];
+		all_labels.extend(labels.iter().cloned());
+		let issue = json!({
+			"fields": {
+				"project": { "key": project },
+				"issuetype": { "name": issue_type },
+				"summary": render(title, finding)?,
+				"description": render(body, finding)?,
+				"labels": all_labels,
+			}
+		});
+		let created: Value = authorize(client.post(format!("{}/rest/api/2/issue", url)))
+			.json(&issue)
+			.send()
+			.await?
+			.error_for_status()?
+			.json()
+			.await?;
+		info!("Filed {} for {}", created["key"].as_str().unwrap_or("an issue"), key);
+	}
+	Ok(())
+}
+
+/// Authenticate a GitHub API request
+fn github_request(request: RequestBuilder, token: &str) -> RequestBuilder {
+	// GitHub rejects requests without a user agent
+	request
+		.bearer_auth(token)
+		.header("Accept", "application/vnd.github+json")
+		.header("User-Agent", concat!("huginn/", env!("CARGO_PKG_VERSION")))
+}
+
+/// Keys of the findings in the bodies of issues
+fn filed_keys(issues: &[Value]) -> impl Iterator<Item = String> + '_ {
+	issues
+		.iter()
+		.filter_map(|issue| issue["body"].as_str())
+		.flat_map(|body| MARKER.captures_iter(body).map(|c| c[1].to_string()))
+}
+
+/// Open a GitHub issue for each finding that has none
+pub async fn github(
+	client: &reqwest::Client,
+	integration: &IntegrationConfig,
+	findings: &[&ScanResult],
+) -> Result<(), Box<dyn Error>> {
+	let IntegrationConfig::GitHub { url, token, repository, title, body, labels, .. } = integration else {
+		return Ok(());
+	};
+	if findings.is_empty() {
+		return Ok(());
+	}
+	let issues_url = format!("{}/repos/{}/issues", url.trim_end_matches('/'), repository);
+
+	// Closed issues do not count, so a finding that comes back is filed again
+	let mut filed = HashSet::new();
+	for page in 1.. {
+		let query = [("labels", LABEL.to_string()), ("state", "open".to_string()), ("per_page", PAGE_SIZE.to_string()), ("page", page.to_string())];
+		let issues: Vec<Value> =
+			github_request(client.get(&issues_url), token).query(&query).send().await?.error_for_status()?.json().await?;
+		filed.extend(filed_keys(&issues));
+		if issues.len() < PAGE_SIZE {
+			break;
+		}
+	}
+
+	for finding in findings {
+		let key = key(finding);
+		if !filed.insert(key.clone()) {
+			continue;
+		}
+		let severity = finding.severity.unwrap_or(Severity::Info);
+		let mut all_labels = vec![LABEL.to_string(), format!("severity:{}", severity)];
+		all_labels.extend(labels.iter().cloned());
+		let issue = json!({
+			"title": render(title, finding)?,
+			"body": format!("{}\n<!-- {} -->\n", render(body, finding)?.trim_end(), key),
+			"labels": all_labels,
+		});
+		let created: Value = github_request(client.post(&issues_url), token)
+			.json(&issue)
+			.send()
+			.await?
+			.error_for_status()?
+			.json()
+			.await?;
+		info!("Filed {} for {}", created["html_url"].as_str().unwrap_or("an issue"), key);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn finding(port: Option<u16>) -> ScanResult {
+		ScanResult::new("db.example.com", "tcp_connect", "open")
+			.with_port(port)
+			.with_severity(Severity::Critical)
+			.with_service("mysql")
+			.with_details("CVE-2012-2122 (CVSS 9.8)")
+	}
+
+	#[test]
+	fn test_key() {
+		assert_eq!(key(&finding(Some(3306))), "huginn-db.example.com-tcp_connect-3306-CVE-2012-2122");
+		let mut portless = finding(None);
+		portless.target = "my host".to_string();
+		portless.details = None;
+		assert_eq!(key(&portless), "huginn-my_host-tcp_connect");
+
+		// Different findings on one port get different keys
+		let mut other = finding(Some(3306));
+		other.details = Some("CVE-2016-6662 (CVSS 9.8)".to_string());
+		assert_ne!(key(&other), key(&finding(Some(3306))));
+		other.details = Some("weak root password".to_string());
+		let hashed = key(&other);
+		assert_eq!(hashed.len(), "huginn-db.example.com-tcp_connect-3306-".len() + DIGEST_LENGTH);
+		other.details = Some("no root password".to_string());
+		assert_ne!(key(&other), hashed);
+
+		let mut many = finding(Some(3306));
+		many.details = Some((1000..1100).map(|id| format!("CVE-2020-{}", id)).collect::<Vec<_>>().join(" "));
+		assert!(key(&many).len() <= MAX_LABEL);
+	}
+
+	#[test]
+	fn test_jql_string() {
+		assert_eq!(jql_string("SEC"), "\"SEC\"");
+		assert_eq!(jql_string("a\" OR project = \"b"), r#""a\" OR project = \"b""#);
+		assert_eq!(jql_string("a\\"), r#""a\\""#);
+	}
+
+	#[test]
+	fn test_render() {
+		let result = finding(Some(3306));
+		assert_eq!(render(DEFAULT_TITLE, &result).unwrap(), "[critical] db.example.com: mysql on port 3306");
+		let body = render(DEFAULT_BODY, &result).unwrap();
+		assert!(body.starts_with("Huginn found a new critical finding.\n\nTarget: db.example.com\n"));
+		assert!(body.contains("Details: CVE-2012-2122 (CVSS 9.8)"));
+		assert_eq!(render("{{ finding.service | upper }} {{ finding.port }}", &result).unwrap(), "MYSQL 3306");
+
+		assert!(check(DEFAULT_TITLE, DEFAULT_BODY).is_ok());
+		assert!(check("{{ title", DEFAULT_BODY).is_err());
+	}
+
+	#[test]
+	fn test_filed_keys() {
+		let issues = vec![
+			json!({ "body": "Huginn found...\n<!-- huginn-10.0.0.1-tcp_connect-22 -->\n" }),
+			json!({ "body": null }),
+			json!({ "body": "Filed by hand" }),
+		];
+		assert_eq!(filed_keys(&issues).collect::<Vec<_>>(), ["huginn-10.0.0.1-tcp_connect-22"]);
+	}
+}
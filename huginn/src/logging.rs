@@ -3,27 +3,617 @@
 
 //! Logging configuration for Huginn
 //!
-//! This module sets up structured logging using the fern crate.
+//! This module sets up structured logging using the fern crate. Every message is passed through
+//! secret masking before it is written. Logs go to stderr, or to `log_file` once the
+//! configuration is loaded, rotated by time and size with a retention count, so stdout only ever
+//! carries results. Records are written as text lines or, with `log_format = "json"`, as JSON
+//! objects that carry the record's key-value fields for log shippers. Which records are written is
+//! set by `log_level`, in `RUST_LOG` syntax, raised by `-v` flags, and lowered to warnings by
+//! `--quiet`. The lines written during a scan can also be captured, so they are stored with the
+//! run's results.
 
-use log::LevelFilter;
-use std::io;
+use crate::config::Config;
+use crate::security;
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use huginn_core::progress;
+use log::kv::{self, Key, Value, VisitSource};
+use log::{LevelFilter, Metadata, Record};
+use serde::Deserialize;
+use serde_json::{Map, json};
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
 
-/// Initialize the logging system
-pub fn init() -> Result<(), fern::InitError> {
-	fern::Dispatch::new()
-		.format(|out, message, record| {
-			out.finish(format_args!(
-				"[{} {} {}] {}",
-				chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-				record.level(),
-				record.target(),
-				message
-			))
+/// How log records are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+	/// `[timestamp LEVEL target] message` lines
+	#[default]
+	Text,
+	/// One JSON object per record with timestamp, level, target, message, and fields
+	Json,
+}
+
+/// Crates whose records `-v` makes more verbose
+const CRATES: [&str; 2] = ["huginn", "huginn_core"];
+
+/// Log thresholds in `RUST_LOG` syntax: a default level and per-module overrides
+///
+/// For example, `warn,huginn=info,huginn_core::plugins::tcp_syn=trace`. The most specific module
+/// prefix that matches a record's target applies.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct LogLevel {
+	default: LevelFilter,
+	modules: Vec<(String, LevelFilter)>,
+}
+
+impl LogLevel {
+	/// The `info` threshold for every module
+	const INFO: Self = Self {
+		default: LevelFilter::Info,
+		modules: Vec::new(),
+	};
+
+	/// Raise the thresholds for `-v` (Huginn debug), `-vv` (Huginn trace), or `-vvv` (all trace)
+	pub fn with_verbosity(mut self, verbosity: u8) -> Self {
+		let huginn = match verbosity {
+			0 => return self,
+			1 => LevelFilter::Debug,
+			2 => LevelFilter::Trace,
+			_ => {
+				self.default = LevelFilter::Trace;
+				LevelFilter::Trace
+			},
+		};
+		for (module, level) in &mut self.modules {
+			if CRATES.iter().any(|krate| is_within(module, krate)) {
+				*level = (*level).max(huginn);
+			}
+		}
+		for krate in CRATES {
+			if !self.modules.iter().any(|(module, _)| module == krate) {
+				self.modules.push((krate.to_string(), huginn.max(self.default)));
+			}
+		}
+		self
+	}
+
+	/// Lower every threshold to at most `max`
+	fn capped(mut self, max: LevelFilter) -> Self {
+		self.default = self.default.min(max);
+		for (_, level) in &mut self.modules {
+			*level = (*level).min(max);
+		}
+		self
+	}
+
+	/// Threshold for a record target
+	fn level(&self, target: &str) -> LevelFilter {
+		self.modules
+			.iter()
+			.filter(|(module, _)| is_within(target, module))
+			.max_by_key(|(module, _)| module.len())
+			.map_or(self.default, |(_, level)| *level)
+	}
+
+	/// Whether a record passes its threshold
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		metadata.level() <= self.level(metadata.target())
+	}
+
+	/// Most verbose threshold of any module
+	fn max(&self) -> LevelFilter {
+		self.modules.iter().map(|(_, level)| *level).fold(self.default, Ord::max)
+	}
+}
+
+/// Whether a target is a module or one of its submodules
+fn is_within(target: &str, module: &str) -> bool {
+	target
+		.strip_prefix(module)
+		.is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+impl Default for LogLevel {
+	fn default() -> Self {
+		Self::INFO
+	}
+}
+
+impl FromStr for LogLevel {
+	type Err = String;
+
+	fn from_str(spec: &str) -> Result<Self, Self::Err> {
+		let mut log_level = Self::INFO;
+		for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+			let level = |text: &str| {
+				text.trim()
+					.parse::<LevelFilter>()
+					.map_err(|_| format!("invalid log level {:?}; expected off, error, warn, info, debug, or trace", text.trim()))
+			};
+			match directive.split_once('=') {
+				Some((module, value)) if !module.trim().is_empty() => {
+					let module = module.trim().to_string();
+					let value = level(value)?;
+					log_level.modules.retain(|(m, _)| *m != module);
+					log_level.modules.push((module, value));
+				},
+				Some(_) => return Err(format!("missing module name in {:?}", directive)),
+				None => log_level.default = level(directive)?,
+			}
+		}
+		Ok(log_level)
+	}
+}
+
+impl TryFrom<String> for LogLevel {
+	type Error = String;
+
+	fn try_from(spec: String) -> Result<Self, Self::Error> {
+		spec.parse()
+	}
+}
+
+/// When the log file is rotated, in addition to reaching `log_max_size`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rotation {
+	/// Start a new file every hour
+	Hourly,
+	/// Start a new file every day
+	#[default]
+	Daily,
+	/// Only rotate on size
+	Never,
+}
+
+impl Rotation {
+	/// Label of the rotation period a time falls in
+	fn period(self, time: DateTime<Local>) -> String {
+		match self {
+			Rotation::Hourly => time.format("%Y%m%d%H").to_string(),
+			Rotation::Daily => time.format("%Y%m%d").to_string(),
+			Rotation::Never => String::new(),
+		}
+	}
+}
+
+/// A size in bytes, written as a number with an optional binary unit (e.g. `512K`, `10MiB`)
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ByteSize(pub u64);
+
+impl FromStr for ByteSize {
+	type Err = String;
+
+	fn from_str(text: &str) -> Result<Self, Self::Err> {
+		let text = text.trim();
+		let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+		let (number, unit) = text.split_at(split);
+		let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+			"" | "b" => 1,
+			"k" | "kb" | "kib" => 1 << 10,
+			"m" | "mb" | "mib" => 1 << 20,
+			"g" | "gb" | "gib" => 1 << 30,
+			_ => return Err(format!("invalid size {:?}; expected a number with B, KiB, MiB, or GiB", text)),
+		};
+		number
+			.parse::<u64>()
+			.ok()
+			.and_then(|n| n.checked_mul(multiplier))
+			.filter(|&bytes| bytes > 0)
+			.map(Self)
+			.ok_or_else(|| format!("invalid size {:?}; expected a positive number", text))
+	}
+}
+
+impl TryFrom<String> for ByteSize {
+	type Error = String;
+
+	fn try_from(text: String) -> Result<Self, Self::Error> {
+		text.parse()
+	}
+}
+
+/// A log file that is rotated by time and size, keeping a fixed number of old files
+///
+/// Rotated files are renamed to `<path>.<timestamp>`, so they sort by age.
+struct RotatingFile {
+	path: PathBuf,
+	file: File,
+	size: u64,
+	period: String,
+	rotation: Rotation,
+	max_size: Option<u64>,
+	keep: usize,
+}
+
+impl RotatingFile {
+	/// Open the log file for appending
+	fn open(path: &Path, rotation: Rotation, max_size: Option<u64>, keep: usize) -> io::Result<Self> {
+		let file = OpenOptions::new().create(true).append(true).open(path)?;
+		let metadata = file.metadata()?;
+		// A file left over from an earlier period is rotated on the first write
+		let modified: DateTime<Local> = metadata.modified()?.into();
+		Ok(Self {
+			path: path.to_path_buf(),
+			file,
+			size: metadata.len(),
+			period: rotation.period(modified),
+			rotation,
+			max_size,
+			keep,
 		})
-		.level(LevelFilter::Info)
-		.level_for("huginn", LevelFilter::Debug)
-		.chain(io::stdout())
+	}
+
+	/// Append a line, rotating first when the period is over or the file would grow too large
+	fn write_line(&mut self, line: &str) -> io::Result<()> {
+		let len = line.len() as u64 + 1;
+		let period_over = self.rotation.period(Local::now()) != self.period;
+		let full = self.max_size.is_some_and(|max| self.size > 0 && self.size + len > max);
+		if period_over || full {
+			self.rotate()?;
+		}
+		writeln!(self.file, "{}", line)?;
+		self.size += len;
+		Ok(())
+	}
+
+	/// Move the current file aside, start a new one, and prune old files
+	fn rotate(&mut self) -> io::Result<()> {
+		let mut stamp = OsString::from(self.path.as_os_str());
+		stamp.push(Local::now().format(".%Y%m%dT%H%M%S%.9f").to_string());
+		// Never overwrite a file rotated within the same instant
+		let mut rotated = PathBuf::from(&stamp);
+		for n in 1.. {
+			if !rotated.exists() {
+				break;
+			}
+			let mut numbered = stamp.clone();
+			numbered.push(format!("-{}", n));
+			rotated = PathBuf::from(numbered);
+		}
+		fs::rename(&self.path, rotated)?;
+		self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+		self.size = 0;
+		self.period = self.rotation.period(Local::now());
+		self.prune()
+	}
+
+	/// Delete the oldest rotated files beyond the retention count
+	fn prune(&self) -> io::Result<()> {
+		let dir = self
+			.path
+			.parent()
+			.filter(|dir| !dir.as_os_str().is_empty())
+			.unwrap_or(Path::new("."));
+		let prefix = format!("{}.", self.path.file_name().unwrap_or_default().to_string_lossy());
+		let mut rotated: Vec<PathBuf> = fs::read_dir(dir)?
+			.filter_map(Result::ok)
+			.map(|entry| entry.path())
+			.filter(|path| {
+				path.file_name()
+					.and_then(|name| name.to_str())
+					.and_then(|name| name.strip_prefix(&prefix))
+					.is_some_and(|stamp| stamp.starts_with(|c: char| c.is_ascii_digit()))
+			})
+			.collect();
+		rotated.sort();
+		let excess = rotated.len().saturating_sub(self.keep);
+		for path in &rotated[..excess] {
+			fs::remove_file(path)?;
+		}
+		Ok(())
+	}
+}
+
+/// Where log lines are written
+enum Target {
+	Stderr,
+	File(RotatingFile),
+}
+
+/// Destination, format, and thresholds of log records
+struct Sink {
+	target: Target,
+	format: LogFormat,
+	level: LogLevel,
+	verbosity: u8,
+	quiet: bool,
+	/// Lines written since each live [`Capture`] started, by capture
+	captures: Vec<(u64, Vec<String>)>,
+}
+
+/// Current log sink; swapped once the configuration is loaded
+static SINK: Mutex<Sink> = Mutex::new(Sink {
+	target: Target::Stderr,
+	format: LogFormat::Text,
+	level: LogLevel::INFO,
+	verbosity: 0,
+	quiet: false,
+	captures: Vec::new(),
+});
+
+/// Identifier of the next [`Capture`]
+static NEXT_CAPTURE: AtomicU64 = AtomicU64::new(0);
+
+/// Collects a record's key-value fields into a JSON object, masking string values
+struct Fields(Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+	fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+		let value = match serde_json::to_value(&value) {
+			Ok(serde_json::Value::String(text)) => serde_json::Value::String(security::mask_sensitive(&text).into_owned()),
+			Ok(value) => value,
+			Err(_) => serde_json::Value::String(security::mask_sensitive(&value.to_string()).into_owned()),
+		};
+		self.0.insert(key.to_string(), value);
+		Ok(())
+	}
+}
+
+/// Render a record in the given format
+fn render(format: LogFormat, record: &Record) -> String {
+	let message = record.args().to_string();
+	let message = security::mask_sensitive(&message);
+	match format {
+		LogFormat::Text => format!(
+			"[{} {} {}] {}",
+			Local::now().format("%Y-%m-%d %H:%M:%S"),
+			record.level(),
+			record.target(),
+			message
+		),
+		LogFormat::Json => {
+			let mut fields = Fields(Map::new());
+			let _ = record.key_values().visit(&mut fields);
+			json!({
+				"timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+				"level": record.level().as_str(),
+				"target": record.target(),
+				"message": message,
+				"fields": fields.0,
+			})
+			.to_string()
+		},
+	}
+}
+
+/// Write a record to the current sink
+fn write_record(record: &Record) {
+	let mut sink = SINK.lock().unwrap_or_else(PoisonError::into_inner);
+	if !sink.level.enabled(record.metadata()) {
+		return;
+	}
+	let line = render(sink.format, record);
+	for (_, captured) in &mut sink.captures {
+		captured.push(line.clone());
+	}
+	let result = match &mut sink.target {
+		Target::Stderr => progress::suspend(|| writeln!(io::stderr(), "{}", line)),
+		Target::File(file) => file.write_line(&line),
+	};
+	if let Err(e) = result {
+		eprintln!("Failed to write log: {}", e);
+	}
+}
+
+/// Apply thresholds, skipping records that no module would write before they are formatted
+fn set_level(sink: &mut Sink, level: LogLevel) {
+	let level = if sink.quiet {
+		level.capped(LevelFilter::Warn)
+	} else {
+		level.with_verbosity(sink.verbosity)
+	};
+	log::set_max_level(level.max());
+	sink.level = level;
+}
+
+/// Initialize the logging system; each level of verbosity lowers Huginn's log threshold, and
+/// quiet mode only lets warnings and errors through
+pub fn init(verbosity: u8, quiet: bool) -> Result<(), fern::InitError> {
+	// Records are filtered by the sink, whose thresholds change once the configuration is loaded
+	fern::Dispatch::new()
+		.level(LevelFilter::Trace)
+		.chain(fern::Output::call(write_record))
 		.apply()?;
 
+	let mut sink = SINK.lock().unwrap_or_else(PoisonError::into_inner);
+	sink.verbosity = verbosity;
+	sink.quiet = quiet;
+	set_level(&mut sink, LogLevel::INFO);
+	Ok(())
+}
+
+/// Redirect logging as configured
+///
+/// Logs go to `log_file` when it is set and stay on stderr otherwise.
+pub fn configure(config: &Config) -> io::Result<()> {
+	let target = match &config.log_file {
+		Some(path) => Target::File(
+			RotatingFile::open(path, config.log_rotation, config.log_max_size.map(|size| size.0), config.log_keep)
+				.map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?,
+		),
+		None => Target::Stderr,
+	};
+	let mut sink = SINK.lock().unwrap_or_else(PoisonError::into_inner);
+	sink.target = target;
+	sink.format = config.log_format;
+	set_level(&mut sink, config.log_level.clone());
 	Ok(())
 }
+
+/// Collects the log lines written while it is alive, in addition to writing them as usual
+///
+/// Each capture keeps its own lines, so captures that overlap, such as those of scans run side by
+/// side, do not cut each other short.
+pub struct Capture(u64);
+
+impl Capture {
+	/// Stop capturing and return the lines written so far
+	pub fn finish(self) -> Vec<String> {
+		let mut sink = SINK.lock().unwrap_or_else(PoisonError::into_inner);
+		let index = sink.captures.iter().position(|(id, _)| *id == self.0);
+		index.map(|index| sink.captures.remove(index).1).unwrap_or_default()
+	}
+}
+
+impl Drop for Capture {
+	fn drop(&mut self) {
+		SINK.lock().unwrap_or_else(PoisonError::into_inner).captures.retain(|(id, _)| *id != self.0);
+	}
+}
+
+/// Start capturing log lines
+pub fn capture() -> Capture {
+	let id = NEXT_CAPTURE.fetch_add(1, Ordering::Relaxed);
+	SINK.lock().unwrap_or_else(PoisonError::into_inner).captures.push((id, Vec::new()));
+	Capture(id)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_render() {
+		let fields = [("host", Value::from("10.0.0.1")), ("port", Value::from(22)), ("token", Value::from("password=hunter2"))];
+		let record = Record::builder()
+			.args(format_args!("Connected with password=hunter2"))
+			.level(log::Level::Warn)
+			.target("huginn::scanner")
+			.key_values(&fields)
+			.build();
+
+		let text = render(LogFormat::Text, &record);
+		assert!(text.ends_with(" WARN huginn::scanner] Connected with password=[REDACTED:credential]"), "{}", text);
+
+		let json: serde_json::Value = serde_json::from_str(&render(LogFormat::Json, &record)).unwrap();
+		assert_eq!(json["level"], "WARN");
+		assert_eq!(json["target"], "huginn::scanner");
+		assert_eq!(json["message"], "Connected with password=[REDACTED:credential]");
+		assert_eq!(json["fields"]["host"], "10.0.0.1");
+		assert_eq!(json["fields"]["port"], 22);
+		assert_eq!(json["fields"]["token"], "password=[REDACTED:credential]");
+		assert!(json["timestamp"].as_str().unwrap().ends_with('Z'));
+	}
+
+	#[test]
+	fn test_capture() {
+		let record = |message| {
+			write_record(
+				&Record::builder()
+					.args(format_args!("{}", message))
+					.level(log::Level::Warn)
+					.target("huginn::scanner")
+					.build(),
+			)
+		};
+		record("before");
+		let outer = capture();
+		let inner = capture();
+		record("during");
+		let inner_id = inner.0;
+		let inner = inner.finish();
+		record("after");
+		let outer_id = outer.0;
+		let outer = outer.finish();
+		assert_eq!(inner.len(), 1);
+		assert!(inner[0].ends_with("] during"), "{:?}", inner);
+		assert_eq!(outer.len(), 2);
+		assert!(outer[1].ends_with("] after"), "{:?}", outer);
+		let sink = SINK.lock().unwrap_or_else(PoisonError::into_inner);
+		assert!(sink.captures.iter().all(|(id, _)| *id != inner_id && *id != outer_id));
+	}
+
+	#[test]
+	fn test_log_level() {
+		let level: LogLevel = "warn, huginn=info,huginn::plugins::tcp_syn=trace".parse().unwrap();
+		assert_eq!(level.level("hyper::client"), LevelFilter::Warn);
+		assert_eq!(level.level("huginn"), LevelFilter::Info);
+		assert_eq!(level.level("huginn::scanner"), LevelFilter::Info);
+		assert_eq!(level.level("huginn::plugins::tcp_syn"), LevelFilter::Trace);
+		assert_eq!(level.level("huginn::plugins::tcp_synx"), LevelFilter::Info);
+		assert_eq!(level.level("huginnx"), LevelFilter::Warn);
+		assert_eq!(level.max(), LevelFilter::Trace);
+
+		assert_eq!("".parse::<LogLevel>().unwrap(), LogLevel::default());
+		assert!("loud".parse::<LogLevel>().is_err());
+		assert!("huginn=loud".parse::<LogLevel>().is_err());
+		assert!("=debug".parse::<LogLevel>().is_err());
+	}
+
+	#[test]
+	fn test_verbosity() {
+		let level = LogLevel::default().with_verbosity(1);
+		assert_eq!(level.level("huginn::scanner"), LevelFilter::Debug);
+		assert_eq!(level.level("reqwest"), LevelFilter::Info);
+
+		let level: LogLevel = "error,huginn::plugins=warn".parse().unwrap();
+		let level = level.with_verbosity(2);
+		assert_eq!(level.level("huginn::plugins::ping"), LevelFilter::Trace);
+		assert_eq!(level.level("huginn::scanner"), LevelFilter::Trace);
+		assert_eq!(level.level("huginn_core::scanner"), LevelFilter::Trace);
+		assert_eq!(level.level("rustls"), LevelFilter::Error);
+
+		let level = LogLevel::default().with_verbosity(3);
+		assert_eq!(level.level("rustls"), LevelFilter::Trace);
+		assert_eq!(LogLevel::default().with_verbosity(0), LogLevel::default());
+
+		let level: LogLevel = "error,huginn=debug".parse().unwrap();
+		let level = level.capped(LevelFilter::Warn);
+		assert_eq!(level.level("huginn::scanner"), LevelFilter::Warn);
+		assert_eq!(level.level("rustls"), LevelFilter::Error);
+	}
+
+	#[test]
+	fn test_byte_size() {
+		assert_eq!("512".parse::<ByteSize>().unwrap(), ByteSize(512));
+		assert_eq!("10MiB".parse::<ByteSize>().unwrap(), ByteSize(10 << 20));
+		assert_eq!("64 k".parse::<ByteSize>().unwrap(), ByteSize(64 << 10));
+		assert_eq!("1GB".parse::<ByteSize>().unwrap(), ByteSize(1 << 30));
+		assert!("0".parse::<ByteSize>().is_err());
+		assert!("10 parsecs".parse::<ByteSize>().is_err());
+		assert!("MiB".parse::<ByteSize>().is_err());
+	}
+
+	#[test]
+	fn test_rotation_by_size() {
+		let dir = std::env::temp_dir().join(format!("huginn-logs-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("huginn.log");
+		fs::write(dir.join("huginn.log.sig"), "unrelated").unwrap();
+
+		let mut file = RotatingFile::open(&path, Rotation::Never, Some(32), 2).unwrap();
+		for i in 0..10 {
+			file.write_line(&format!("line {:02} of the log", i)).unwrap();
+		}
+
+		let mut names: Vec<String> = fs::read_dir(&dir)
+			.unwrap()
+			.map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+			.collect();
+		names.sort();
+		// The current file, two rotated files, and the unrelated file
+		assert_eq!(names.len(), 4, "{:?}", names);
+		assert_eq!(names[0], "huginn.log");
+		assert_eq!(names[3], "huginn.log.sig");
+		assert_eq!(fs::read_to_string(&path).unwrap(), "line 09 of the log\n");
+		assert_eq!(fs::read_to_string(dir.join(&names[2])).unwrap(), "line 08 of the log\n");
+		fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn test_period() {
+		let time = DateTime::parse_from_rfc3339("2025-06-01T13:45:00Z").unwrap().with_timezone(&Local);
+		assert_eq!(Rotation::Daily.period(time).len(), 8);
+		assert_eq!(Rotation::Hourly.period(time).len(), 10);
+		assert_eq!(Rotation::Never.period(time), "");
+	}
+}
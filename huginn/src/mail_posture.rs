@@ -0,0 +1,654 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Email security posture for Huginn
+//!
+//! The `mail_posture` scan type reads the DNS records that decide who may send mail as a domain
+//! target and how mail to it is protected: its SPF record, its DMARC policy, or the one of the
+//! nearest parent domain that has one, DKIM keys at a list of common selectors, and, when the
+//! domain receives mail, its MTA-STS and TLS-RPT records. Each is scored, and the domain is
+//! reported as `graded` with a grade from A to F and a summary of every record, along with a
+//! finding for each weakness. Names that neither receive nor send mail, such as the hosts found
+//! by subdomain enumeration, are skipped unless they are registrable domains of two labels, which
+//! are graded whether they handle mail or not, since forged mail from an idle domain is just as
+//! convincing. Address targets, and domains that do not exist, are skipped.
+
+use crate::dns::Issue;
+use async_trait::async_trait;
+use hickory_resolver::{ResolveError, TokioResolver};
+use huginn_core::error::HuginnError;
+use huginn_core::plugins::{Plugin, ScanContext, ScanResult, ScanType, Severity};
+use log::debug;
+use serde::Deserialize;
+use std::net::IpAddr;
+use tokio::task::JoinSet;
+
+/// DKIM selectors looked up when none are configured: generic names and those of common mail
+/// providers
+const DKIM_SELECTORS: &[&str] = &[
+	"default", "dkim", "mail", "smtp", "k1", "k2", "s1", "s2", "selector1", "selector2", "google",
+	"amazonses", "mandrill", "mxvault", "pm", "sig1", "zoho", "fm1", "fm2", "fm3", "protonmail",
+	"protonmail2", "protonmail3",
+];
+
+/// ATT&CK technique for mail forged as the domain
+const SPOOFING: &str = "T1672";
+
+/// ATT&CK technique for mail intercepted in transit
+const INTERCEPTION: &str = "T1557";
+
+/// Most DNS lookups an SPF record may cause before receivers give up on it
+const SPF_LOOKUP_LIMIT: usize = 10;
+
+/// Settings of the `mail_posture` scan type
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MailPostureConfig {
+	/// DKIM selectors looked up under `_domainkey`
+	pub dkim_selectors: Vec<String>,
+}
+
+impl Default for MailPostureConfig {
+	fn default() -> Self {
+		Self { dkim_selectors: DKIM_SELECTORS.iter().map(ToString::to_string).collect() }
+	}
+}
+
+/// How one control of the domain's mail is set up
+#[derive(Debug, Clone, PartialEq)]
+struct Check {
+	/// Points scored, out of the control's most
+	points: u32,
+	/// Most points the control scores
+	most: u32,
+	/// How the control is set up, for the summary
+	summary: String,
+	issues: Vec<Issue>,
+}
+
+/// The records of a kind among a name's TXT records, by their version tag
+fn versioned<'a>(records: &'a [String], version: &str) -> Vec<&'a str> {
+	records
+		.iter()
+		.map(|record| record.trim())
+		.filter(|record| {
+			record.get(..version.len()).is_some_and(|start| start.eq_ignore_ascii_case(version))
+				&& record[version.len()..].chars().next().is_none_or(|c| c == ' ' || c == ';')
+		})
+		.collect()
+}
+
+/// Value of a tag in a `tag=value; tag=value` record, by case-insensitive name
+fn tag<'a>(record: &'a str, name: &str) -> Option<&'a str> {
+	record
+		.split(';')
+		.filter_map(|pair| pair.split_once('='))
+		.find(|(tag, _)| tag.trim().eq_ignore_ascii_case(name))
+		.map(|(_, value)| value.trim())
+}
+
+/// Score the domain's SPF record, from its TXT records
+fn spf(records: &[String]) -> Check {
+	let spf = versioned(records, "v=spf1");
+	let mut check = Check { points: 0, most: 30, summary: String::new(), issues: Vec::new() };
+	let record = match spf[..] {
+		[] => {
+			check.summary = "no SPF record".to_string();
+			check.issues.push(Issue::new(
+				"spf_missing",
+				Severity::Medium,
+				"no SPF record, so receivers cannot tell mail forged as the domain from its own",
+				&[SPOOFING],
+			));
+			return check;
+		},
+		[record] => record,
+		_ => {
+			check.summary = format!("{} SPF records", spf.len());
+			check.issues.push(Issue::new(
+				"spf_invalid",
+				Severity::Medium,
+				format!(
+					"{} SPF records, which receivers treat as a permanent error, as if there were \
+					none",
+					spf.len()
+				),
+				&[SPOOFING],
+			));
+			return check;
+		},
+	};
+
+	let terms: Vec<&str> = record.split_whitespace().skip(1).collect();
+	let mechanism = |term: &str| term.trim_start_matches(['+', '-', '~', '?']).to_ascii_lowercase();
+	let lookups = terms
+		.iter()
+		.map(|term| mechanism(term))
+		.filter(|term| {
+			["include:", "exists:", "redirect=", "a:", "a/", "mx:", "mx/", "ptr:"]
+				.iter()
+				.any(|prefix| term.starts_with(prefix))
+				|| ["a", "mx", "ptr"].contains(&term.as_str())
+		})
+		.count();
+	let all = terms
+		.iter()
+		.find(|term| mechanism(term) == "all")
+		.map(|term| term.chars().next().filter(|c| "+-~?".contains(*c)).unwrap_or('+'));
+	let redirect = terms
+		.iter()
+		.find_map(|term| term.to_ascii_lowercase().strip_prefix("redirect=").map(str::to_string));
+
+	(check.points, check.summary) = match (all, &redirect) {
+		(Some('-'), _) => (30, "SPF -all".to_string()),
+		(Some('~'), _) => (25, "SPF ~all".to_string()),
+		(None, Some(redirect)) => (25, format!("SPF redirected to {}", redirect)),
+		(Some('?'), _) => {
+			check.issues.push(Issue::new(
+				"spf_weak",
+				Severity::Medium,
+				"SPF ends in ?all, which passes no judgment on mail from senders it does not list",
+				&[SPOOFING],
+			));
+			(10, "SPF ?all".to_string())
+		},
+		(Some(_), _) => {
+			check.issues.push(Issue::new(
+				"spf_weak",
+				Severity::High,
+				"SPF ends in +all, which authorizes every host on the internet to send mail as the \
+				domain",
+				&[SPOOFING],
+			));
+			(0, "SPF +all".to_string())
+		},
+		(None, None) => {
+			check.issues.push(Issue::new(
+				"spf_weak",
+				Severity::Medium,
+				"SPF has no all mechanism, so mail from senders it does not list is neither passed \
+				nor failed",
+				&[SPOOFING],
+			));
+			(10, "SPF without all".to_string())
+		},
+	};
+	if lookups > SPF_LOOKUP_LIMIT {
+		check.points = check.points.min(5);
+		check.issues.push(Issue::new(
+			"spf_invalid",
+			Severity::Medium,
+			format!(
+				"SPF needs {} DNS lookups at its top level, over the limit of {}, so receivers \
+				treat it as a permanent error",
+				lookups, SPF_LOOKUP_LIMIT
+			),
+			&[SPOOFING],
+		));
+	}
+	check
+}
+
+/// Score the DMARC policy that applies to the domain, from the TXT records at `_dmarc` of the
+/// domain, or of the parent domain named when the policy is inherited
+fn dmarc(records: &[String], parent: Option<&str>) -> Check {
+	let dmarc = versioned(records, "v=DMARC1");
+	let mut check = Check { points: 0, most: 35, summary: String::new(), issues: Vec::new() };
+	// A subdomain is held to its parent's subdomain policy, which defaults to its own policy
+	let policy = match dmarc[..] {
+		[record] => parent
+			.and_then(|_| tag(record, "sp"))
+			.or(tag(record, "p"))
+			.map(|policy| (record, policy.to_ascii_lowercase())),
+		_ => None,
+	};
+	let Some((record, policy)) = policy else {
+		let (status, summary, details) = if dmarc.is_empty() {
+			(
+				"dmarc_missing",
+				"no DMARC record".to_string(),
+				"no DMARC record, so receivers are not told to refuse mail that fails SPF and \
+				DKIM, and forgeries go unreported".to_string(),
+			)
+		} else {
+			(
+				"dmarc_invalid",
+				format!("{} invalid DMARC records", dmarc.len()),
+				format!(
+					"{} DMARC records without one valid policy, which receivers ignore, as if \
+					there were none",
+					dmarc.len()
+				),
+			)
+		};
+		check.summary = summary;
+		check.issues.push(Issue::new(status, Severity::Medium, details, &[SPOOFING]));
+		return check;
+	};
+
+	let pct = tag(record, "pct").and_then(|pct| pct.parse::<u32>().ok()).unwrap_or(100).min(100);
+	check.points = match policy.as_str() {
+		"reject" => 35,
+		"quarantine" => 30,
+		_ => {
+			check.issues.push(Issue::new(
+				"dmarc_weak",
+				Severity::Low,
+				"DMARC policy is none, which only monitors, so mail that fails SPF and DKIM is \
+				still delivered",
+				&[SPOOFING],
+			));
+			10
+		},
+	};
+	if pct < 100 && policy != "none" {
+		check.points -= 10;
+		check.issues.push(Issue::new(
+			"dmarc_weak",
+			Severity::Low,
+			format!("DMARC policy applies to only {}% of the mail that fails SPF and DKIM", pct),
+			&[SPOOFING],
+		));
+	}
+	let mut summary = format!("DMARC p={}", policy);
+	if pct < 100 {
+		summary.push_str(&format!(" pct={}", pct));
+	}
+	if let Some(parent) = parent {
+		summary.push_str(&format!(" from {}", parent));
+	}
+	if tag(record, "rua").is_none() {
+		summary.push_str(" without aggregate reports");
+	}
+	check.summary = summary;
+	check
+}
+
+/// Score the DKIM keys found, by selector, out of the number of selectors looked up
+fn dkim(found: &[String], tried: usize) -> Check {
+	if found.is_empty() {
+		return Check {
+			points: 0,
+			most: 15,
+			summary: "no DKIM key at the common selectors".to_string(),
+			issues: vec![Issue::new(
+				"dkim_missing",
+				Severity::Info,
+				format!(
+					"no DKIM key at any of the {} common selectors, so the domain's mail may go \
+					unsigned; keys at other selectors cannot be found",
+					tried
+				),
+				&[SPOOFING],
+			)],
+		};
+	}
+	Check {
+		points: 15,
+		most: 15,
+		summary: format!("DKIM keys at {}", found.join(", ")),
+		issues: Vec::new(),
+	}
+}
+
+/// Whether a selector's TXT records hold a DKIM key that has not been revoked
+fn has_key(records: &[String]) -> bool {
+	records.iter().any(|record| tag(record, "p").is_some_and(|key| !key.is_empty()))
+}
+
+/// Score the MTA-STS record, from the TXT records at `_mta-sts`
+fn mta_sts(records: &[String]) -> Check {
+	let found = versioned(records, "v=STSv1");
+	match found.first().and_then(|record| tag(record, "id")) {
+		Some(id) => Check {
+			points: 10,
+			most: 10,
+			summary: format!("MTA-STS id {}", id),
+			issues: Vec::new(),
+		},
+		None => Check {
+			points: 0,
+			most: 10,
+			summary: "no MTA-STS record".to_string(),
+			issues: vec![Issue::new(
+				"mta_sts_missing",
+				Severity::Low,
+				"no MTA-STS policy, so servers sending mail to the domain use TLS only when it is \
+				offered, which an attacker in the path can strip",
+				&[INTERCEPTION],
+			)],
+		},
+	}
+}
+
+/// Score the TLS-RPT record, from the TXT records at `_smtp._tls`
+fn tls_rpt(records: &[String]) -> Check {
+	let found = versioned(records, "v=TLSRPTv1");
+	match found.first().and_then(|record| tag(record, "rua")) {
+		Some(rua) => Check {
+			points: 10,
+			most: 10,
+			summary: format!("TLS-RPT reports to {}", rua),
+			issues: Vec::new(),
+		},
+		None => Check {
+			points: 0,
+			most: 10,
+			summary: "no TLS-RPT record".to_string(),
+			issues: vec![Issue::new(
+				"tls_rpt_missing",
+				Severity::Info,
+				"no TLS-RPT record, so failures to deliver mail to the domain over TLS go \
+				unreported",
+				&[],
+			)],
+		},
+	}
+}
+
+/// Grade of a score out of 100
+fn grade(score: u32) -> char {
+	match score {
+		90.. => 'A',
+		75..=89 => 'B',
+		60..=74 => 'C',
+		40..=59 => 'D',
+		_ => 'F',
+	}
+}
+
+/// Parent domains of a name, nearest first, down to those of two labels
+fn parents(domain: &str) -> impl Iterator<Item = &str> {
+	domain
+		.match_indices('.')
+		.map(|(dot, _)| &domain[dot + 1..])
+		.filter(|parent| parent.contains('.'))
+}
+
+/// Records of a lookup, with a name that has none of the type, or does not exist, as empty
+fn records<T>(
+	lookup: Option<Result<T, ResolveError>>,
+	name: &str,
+	context: &ScanContext,
+) -> Result<Option<T>, HuginnError> {
+	match lookup {
+		Some(Ok(records)) => Ok(Some(records)),
+		Some(Err(e)) if e.is_no_records_found() || e.is_nx_domain() => Ok(None),
+		Some(Err(e)) => {
+			Err(HuginnError::plugin("mail_posture", format!("lookup of {} failed: {}", name, e)))
+		},
+		None => Err(context.timed_out(name)),
+	}
+}
+
+/// Text of every TXT record of a name, each record's strings joined
+async fn txt(
+	resolver: &TokioResolver,
+	name: &str,
+	context: &ScanContext,
+) -> Result<Vec<String>, HuginnError> {
+	let lookup = records(context.probe(|| resolver.txt_lookup(name)).await, name, context)?;
+	Ok(lookup
+		.map(|lookup| {
+			lookup
+				.iter()
+				.map(|txt| {
+					txt.txt_data()
+						.iter()
+						.map(|part| String::from_utf8_lossy(part))
+						.collect::<String>()
+				})
+				.collect()
+		})
+		.unwrap_or_default())
+}
+
+/// Mail exchangers of a domain, without the null MX of a domain that takes no mail, or `None`
+/// when the domain does not exist
+async fn exchangers(
+	resolver: &TokioResolver,
+	domain: &str,
+	context: &ScanContext,
+) -> Result<Option<Vec<String>>, HuginnError> {
+	let lookup = match context.probe(|| resolver.mx_lookup(domain)).await {
+		Some(Err(e)) if e.is_nx_domain() => return Ok(None),
+		lookup => records(lookup, domain, context)?,
+	};
+	Ok(Some(
+		lookup
+			.map(|lookup| {
+				lookup
+					.iter()
+					.map(|mx| mx.exchange().to_ascii().trim_end_matches('.').to_string())
+					.filter(|exchange| !exchange.is_empty())
+					.collect()
+			})
+			.unwrap_or_default(),
+	))
+}
+
+/// Email security posture plugin
+pub struct MailPosturePlugin {
+	config: MailPostureConfig,
+}
+
+impl MailPosturePlugin {
+	/// A plugin looking up DKIM keys at the configured selectors
+	pub fn new(config: MailPostureConfig) -> Self {
+		Self { config }
+	}
+
+	/// Names of the DKIM selectors of the domain that hold a key
+	async fn selectors(
+		&self,
+		resolver: &TokioResolver,
+		domain: &str,
+		context: &ScanContext,
+	) -> Result<Vec<String>, HuginnError> {
+		let mut lookups = JoinSet::new();
+		for selector in &self.config.dkim_selectors {
+			let (resolver, context, selector) =
+				(resolver.clone(), context.clone(), selector.clone());
+			let name = format!("{}._domainkey.{}", selector, domain);
+			lookups.spawn(async move { (selector, txt(&resolver, &name, &context).await) });
+		}
+		let mut found = Vec::new();
+		while let Some(lookup) = lookups.join_next().await {
+			let (selector, records) = lookup.map_err(|e| HuginnError::plugin("mail_posture", e))?;
+			if has_key(&records?) {
+				found.push(selector);
+			}
+		}
+		found.sort();
+		Ok(found)
+	}
+}
+
+#[async_trait]
+impl Plugin for MailPosturePlugin {
+	fn name(&self) -> String {
+		"Mail Posture Grader".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::MailPosture
+	}
+
+	fn description(&self) -> String {
+		"Grades the SPF, DMARC, DKIM, MTA-STS, and TLS-RPT records of domain targets".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&["mail_posture", "timeout", "retries", "scan_delay", "plugin_timing"]
+	}
+
+	fn probes_per_target(&self, _context: &ScanContext) -> usize {
+		// The MX, SPF, DMARC, MTA-STS, and TLS-RPT lookups, and one for each DKIM selector
+		5 + self.config.dkim_selectors.len()
+	}
+
+	async fn scan(
+		&self,
+		target: &str,
+		context: &ScanContext,
+	) -> Result<Vec<ScanResult>, HuginnError> {
+		if target.parse::<IpAddr>().is_ok() {
+			debug!("Skipping mail posture of {}: not a domain", target);
+			return Ok(Vec::new());
+		}
+		let domain = target.trim_end_matches('.').to_ascii_lowercase();
+		let resolver = TokioResolver::builder_tokio()
+			.map_err(|e| HuginnError::plugin("mail_posture", e))?
+			.build();
+
+		let Some(exchangers) = exchangers(&resolver, &domain, context).await? else {
+			debug!("Skipping mail posture of {}: the domain does not exist", domain);
+			return Ok(Vec::new());
+		};
+		let apex = txt(&resolver, &domain, context).await?;
+		let mut policy = (txt(&resolver, &format!("_dmarc.{}", domain), context).await?, None);
+		let own_policy = !versioned(&policy.0, "v=DMARC1").is_empty();
+		if exchangers.is_empty()
+			&& versioned(&apex, "v=spf1").is_empty()
+			&& !own_policy
+			&& domain.matches('.').count() > 1
+		{
+			debug!("Skipping mail posture of {}: it neither receives nor sends mail", domain);
+			return Ok(Vec::new());
+		}
+		if !own_policy {
+			for parent in parents(&domain) {
+				let records = txt(&resolver, &format!("_dmarc.{}", parent), context).await?;
+				if !versioned(&records, "v=DMARC1").is_empty() {
+					policy = (records, Some(parent));
+					break;
+				}
+			}
+		}
+
+		let mut checks = vec![
+			spf(&apex),
+			dmarc(&policy.0, policy.1),
+			dkim(
+				&self.selectors(&resolver, &domain, context).await?,
+				self.config.dkim_selectors.len(),
+			),
+		];
+		// Transport security only matters to a domain that receives mail
+		if !exchangers.is_empty() {
+			checks.push(mta_sts(&txt(&resolver, &format!("_mta-sts.{}", domain), context).await?));
+			checks
+				.push(tls_rpt(&txt(&resolver, &format!("_smtp._tls.{}", domain), context).await?));
+		}
+
+		let points: u32 = checks.iter().map(|check| check.points).sum();
+		let most: u32 = checks.iter().map(|check| check.most).sum();
+		let score = points * 100 / most;
+		let mut summary = vec![format!("grade {} ({}/100)", grade(score), score)];
+		summary.extend(checks.iter().map(|check| check.summary.clone()));
+		summary.push(if exchangers.is_empty() {
+			"no mail exchangers".to_string()
+		} else {
+			format!("mail exchangers {}", exchangers.join(", "))
+		});
+
+		let graded = ScanResult::new(&domain, ScanType::MailPosture.to_string(), "graded")
+			.with_details(summary.join("; "));
+		let mut results = vec![graded];
+		for issue in checks.into_iter().flat_map(|check| check.issues) {
+			results.push(issue.result(&domain, ScanType::MailPosture));
+		}
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn strings(records: &[&str]) -> Vec<String> {
+		records.iter().map(ToString::to_string).collect()
+	}
+
+	fn statuses(check: &Check) -> Vec<(&str, Severity)> {
+		check.issues.iter().map(|issue| (issue.status, issue.severity)).collect()
+	}
+
+	#[test]
+	fn test_spf() {
+		let strict =
+			spf(&strings(&["google-site-verification=abc", "v=spf1 include:_spf.google.com -all"]));
+		assert_eq!((strict.points, strict.summary.as_str()), (30, "SPF -all"));
+		assert!(strict.issues.is_empty());
+
+		let open = spf(&strings(&["v=spf1 a mx +all"]));
+		assert_eq!(open.points, 0);
+		assert_eq!(statuses(&open), [("spf_weak", Severity::High)]);
+
+		let redirected = spf(&strings(&["v=spf1 redirect=_spf.example.net"]));
+		assert_eq!(redirected.summary, "SPF redirected to _spf.example.net");
+
+		let long =
+			(0..11).map(|i| format!("include:spf{}.example.net", i)).collect::<Vec<_>>().join(" ");
+		let long = spf(&[format!("v=spf1 {} ~all", long)]);
+		assert_eq!(long.points, 5);
+		assert_eq!(statuses(&long), [("spf_invalid", Severity::Medium)]);
+
+		assert_eq!(
+			statuses(&spf(&strings(&["v=spf10 -all"]))),
+			[("spf_missing", Severity::Medium)]
+		);
+		assert_eq!(
+			statuses(&spf(&strings(&["v=spf1 -all", "v=spf1 ~all"]))),
+			[("spf_invalid", Severity::Medium)]
+		);
+	}
+
+	#[test]
+	fn test_dmarc() {
+		let reject = dmarc(&strings(&["v=DMARC1; p=reject; rua=mailto:d@example.com"]), None);
+		assert_eq!((reject.points, reject.summary.as_str()), (35, "DMARC p=reject"));
+
+		let partial = dmarc(&strings(&["v=DMARC1; p=quarantine; pct=25"]), None);
+		assert_eq!(partial.points, 20);
+		assert_eq!(partial.summary, "DMARC p=quarantine pct=25 without aggregate reports");
+		assert_eq!(statuses(&partial), [("dmarc_weak", Severity::Low)]);
+
+		// A subdomain is held to its parent's subdomain policy
+		let inherited = dmarc(
+			&strings(&["v=DMARC1; p=reject; sp=none; rua=mailto:d@example.com"]),
+			Some("example.com"),
+		);
+		assert_eq!(
+			(inherited.points, inherited.summary.as_str()),
+			(10, "DMARC p=none from example.com")
+		);
+
+		assert_eq!(statuses(&dmarc(&[], None)), [("dmarc_missing", Severity::Medium)]);
+		assert_eq!(
+			statuses(&dmarc(&strings(&["v=DMARC1; rua=mailto:d@example.com"]), None)),
+			[("dmarc_invalid", Severity::Medium)]
+		);
+	}
+
+	#[test]
+	fn test_grade() {
+		assert!(has_key(&strings(&["v=DKIM1; k=rsa; p=MIGfMA0GCSqGSIb3DQEBAQUAA4GNADCBiQKBgQC"])));
+		assert!(!has_key(&strings(&["v=DKIM1; p="])));
+		assert_eq!(mta_sts(&strings(&["v=STSv1; id=20240101T000000"])).points, 10);
+		assert_eq!(
+			statuses(&tls_rpt(&strings(&["v=TLSRPTv1;"]))),
+			[("tls_rpt_missing", Severity::Info)]
+		);
+
+		assert_eq!(
+			[grade(100), grade(80), grade(60), grade(45), grade(10)],
+			['A', 'B', 'C', 'D', 'F']
+		);
+		assert_eq!(
+			parents("mail.eu.example.com").collect::<Vec<_>>(),
+			["eu.example.com", "example.com"]
+		);
+		assert_eq!(parents("example.com").count(), 0);
+	}
+}
@@ -6,42 +6,522 @@
 //! The raven of Odin searches the world for knowledge and threats.
 //! This is the main entry point for the Huginn executable.
 
+mod asn;
+mod audit;
+mod baseline;
+#[cfg(feature = "bench")]
+mod bench;
+mod catalog;
 mod config;
+mod credentials;
+#[cfg(feature = "intel-enrichment")]
+mod db;
+#[cfg(feature = "daemon")]
+mod daemon;
+#[cfg(any(feature = "subdomains", feature = "dns-checks"))]
+mod dns;
+#[cfg(feature = "dns-checks")]
+mod dns_hygiene;
+#[cfg(feature = "dns-checks")]
+mod dnssec;
+#[cfg(feature = "email")]
+mod email;
+mod exclusions;
+mod gate;
+mod history;
+mod import;
+#[cfg(feature = "integrations")]
+mod integrations;
 mod logging;
-mod plugins;
-mod scanner;
+#[cfg(feature = "dns-checks")]
+mod mail_posture;
+#[cfg(feature = "plugin-index")]
+mod marketplace;
+mod merge;
+#[cfg(feature = "daemon")]
+mod metrics;
+#[cfg(feature = "notifications")]
+mod notifications;
+mod output;
+mod plan;
+mod privileges;
+mod rdns;
+mod report;
+#[cfg(feature = "intel-enrichment")]
+mod reputation;
+mod scope;
+mod security;
+mod sinks;
+#[cfg(feature = "subdomains")]
+mod subdomains;
+#[cfg(feature = "testbed")]
+mod testbed;
+#[cfg(feature = "intel-enrichment")]
+mod vulns;
+mod watch;
 
-use log::{error, info};
+use clap::Parser;
+use chrono::Utc;
+use config::{Cli, Command, Config, ConfigCommand, PluginsCommand, ScanArgs};
+use huginn_core::limits;
+use huginn_core::plugins::ScanResult;
+use huginn_core::scanner::ScannerBuilder;
+use huginn_core::spool::Spool;
+use huginn_core::statistics::Statistics;
+use huginn_core::targets;
+use log::{error, info, warn};
+use output::OutputDispatcher;
+use std::error::Error;
+use std::path::Path;
 
 #[tokio::main]
 async fn main() {
+	let cli = Cli::parse();
+
 	// Initialize logging
-	if let Err(e) = logging::init() {
+	if let Err(e) = logging::init(cli.verbose, cli.quiet) {
 		eprintln!("Failed to initialize logging: {}", e);
 		std::process::exit(1);
 	}
 
 	info!("Starting Huginn cyber threat scanning toolkit");
 
-	// Load configuration
-	let config = match config::load() {
-		Ok(cfg) => cfg,
+	match cli.command.unwrap_or_else(|| Command::Scan(ScanArgs::default())) {
+		Command::Scan(args) => {
+			let mut config = load_config(cli.config.as_deref(), args.profile.as_deref());
+			args.apply(&mut config);
+			config.no_progress |= cli.quiet;
+			let result = if let Err(e) = gate::validate(&config.fail_on, config.baseline.is_some()) {
+				Err(e.into())
+			} else if args.dry_run {
+				dry_run(&config).await.map(|_| Vec::new())
+			} else if let Err(e) = privileges::check(&config).and_then(|_| privileges::drop_root(&config)) {
+				Err(e)
+			} else {
+				scan(&config).await
+			};
+			match result {
+				Ok(met) if !met.is_empty() => {
+					warn!("Failing the scan: {}", met.join("; "));
+					std::process::exit(gate::EXIT_CODE);
+				},
+				Ok(_) => {},
+				Err(e) => {
+					error!("{}", e);
+					std::process::exit(1);
+				},
+			}
+		},
+		Command::Config { command } => {
+			let result = match command {
+				ConfigCommand::Init { path, force } => config::init(&path, force),
+				ConfigCommand::Validate { path } => match path.or(cli.config).or_else(config::discover) {
+					Some(path) => config::validate::run(&path),
+					None => Err("No configuration file found".into()),
+				},
+			};
+			if let Err(e) = result {
+				error!("{}", e);
+				std::process::exit(1);
+			}
+		},
+		#[cfg(feature = "daemon")]
+		Command::Daemon(args) => {
+			let mut config = load_config(cli.config.as_deref(), args.scan.profile.as_deref());
+			args.apply(&mut config);
+			config.no_progress |= cli.quiet;
+			let result = if let Err(e) = gate::validate(&config.fail_on, config.baseline.is_some()) {
+				Err(e.into())
+			} else if args.scan.dry_run {
+				dry_run(&config).await
+			} else if let Err(e) = privileges::check(&config) {
+				Err(e)
+			} else {
+				daemon::run(config).await.map_err(|e| format!("Daemon failed: {}", e).into())
+			};
+			if let Err(e) = result {
+				error!("{}", e);
+				std::process::exit(1);
+			}
+		},
+		Command::Exclude { command } => {
+			let config = load_config(cli.config.as_deref(), None);
+			if let Err(e) = exclusions::run(&config, command) {
+				error!("Exclude command failed: {}", e);
+				std::process::exit(1);
+			}
+		},
+		Command::Plugins { command } => {
+			let config = load_config(cli.config.as_deref(), None);
+			let result = match command {
+				PluginsCommand::List => catalog::list(&config),
+				PluginsCommand::Info { name } => catalog::info(&config, &name),
+				#[cfg(feature = "plugin-index")]
+				PluginsCommand::Index(command) => marketplace::run(&config, command).await,
+			};
+			if let Err(e) = result {
+				error!("Plugin command failed: {}", e);
+				std::process::exit(1);
+			}
+		},
+		Command::Report(args) => {
+			let mut config = load_config(cli.config.as_deref(), None);
+			args.output.apply(&mut config);
+			if let Err(e) = report::run(&config, &args).await {
+				error!("Report failed: {}", e);
+				std::process::exit(1);
+			}
+		},
+		Command::Merge(args) => {
+			let mut config = load_config(cli.config.as_deref(), None);
+			args.output.apply(&mut config);
+			if let Err(e) = merge::run(&config, &args).await {
+				error!("Merge failed: {}", e);
+				std::process::exit(1);
+			}
+		},
+		Command::Import(args) => {
+			let mut config = load_config(cli.config.as_deref(), None);
+			args.apply(&mut config);
+			config.no_progress |= cli.quiet;
+			if args.scan
+				&& let Err(e) = privileges::check(&config).and_then(|_| privileges::drop_root(&config))
+			{
+				error!("{}", e);
+				std::process::exit(1);
+			}
+			if let Err(e) = import::run(&config, &args).await {
+				error!("Import failed: {}", e);
+				std::process::exit(1);
+			}
+		},
+		Command::Watch(args) => {
+			let mut config = load_config(cli.config.as_deref(), args.scan.profile.as_deref());
+			args.scan.apply(&mut config);
+			config.no_progress |= cli.quiet;
+			let result = if args.scan.dry_run {
+				dry_run(&config).await
+			} else if let Err(e) = privileges::check(&config).and_then(|_| privileges::drop_root(&config)) {
+				Err(e)
+			} else {
+				watch::run(config, args.interval).await.map_err(|e| format!("Watch failed: {}", e).into())
+			};
+			if let Err(e) = result {
+				error!("{}", e);
+				std::process::exit(1);
+			}
+		},
+		Command::Completions { shell } => config::completions(shell),
+		Command::Man => {
+			if let Err(e) = config::man() {
+				error!("Failed to write manual page: {}", e);
+				std::process::exit(1);
+			}
+		},
+		Command::History { command } => {
+			let config = load_config(cli.config.as_deref(), None);
+			if let Err(e) = history::run(&config, command) {
+				error!("History command failed: {}", e);
+				std::process::exit(1);
+			}
+		},
+		#[cfg(feature = "intel-enrichment")]
+		Command::Db { command } => {
+			let config = load_config(cli.config.as_deref(), None);
+			if let Err(e) = db::run(&config, command).await {
+				error!("Dataset command failed: {}", e);
+				std::process::exit(1);
+			}
+		},
+		#[cfg(feature = "signing")]
+		Command::Verify { file, signature, keys } => {
+			// Keys on the command line make the configuration unnecessary for consumers
+			let config = keys.is_empty().then(|| load_config(cli.config.as_deref(), None));
+			if let Err(e) = output::sign::run(config.as_ref(), &file, signature.as_deref(), &keys) {
+				error!("Verification failed: {}", e);
+				std::process::exit(1);
+			}
+		},
+		#[cfg(feature = "bench")]
+		Command::Bench(args) => {
+			let mut config = load_config(cli.config.as_deref(), args.scan.profile.as_deref());
+			args.scan.apply(&mut config);
+			if let Err(e) = bench::run(&config, &args).await {
+				error!("Benchmark failed: {}", e);
+				std::process::exit(1);
+			}
+		},
+		#[cfg(feature = "testbed")]
+		Command::Testbed { address, base_port } => {
+			if let Err(e) = testbed::run(address, base_port).await {
+				error!("Testbed failed: {}", e);
+				std::process::exit(1);
+			}
+		},
+	}
+
+	info!("Huginn completed successfully");
+}
+
+/// Load the configuration, exiting when it is invalid
+fn load_config(path: Option<&Path>, profile: Option<&str>) -> Config {
+	match config::load(path, profile) {
+		Ok(config) => {
+			if let Err(e) = security::init(&config.masking) {
+				error!("Failed to load configuration: masking: {}", e);
+				std::process::exit(1);
+			}
+			if let Err(e) = logging::configure(&config) {
+				error!("Failed to open log file {}", e);
+				std::process::exit(1);
+			}
+			info!("Configuration loaded successfully");
+			config
+		},
 		Err(e) => {
 			error!("Failed to load configuration: {}", e);
 			std::process::exit(1);
 		},
+	}
+}
+
+/// Expand the configured targets into the hosts to probe
+///
+/// ASN and prefix targets are replaced by the announced prefixes they select, CIDR blocks are
+/// expanded, hosts outside the authorized scope are refused, and excluded hosts are skipped. When
+/// `offline`, the BGP table those targets need is not downloaded.
+async fn hosts(config: &Config, offline: bool) -> Result<Vec<String>, Box<dyn Error>> {
+	let hosts = targets::expand(&asn::expand(config, offline).await?)?;
+	let hosts = scope::enforce(config, hosts)?;
+	let hosts = exclusions::apply(config, hosts)?;
+	if hosts.is_empty() && !config.targets.is_empty() {
+		return Err("No targets left to scan: every target is out of scope or excluded".into());
+	}
+	Ok(hosts)
+}
+
+/// Decide whether a host that a plugin found, such as a subdomain, is scanned: like the
+/// configured targets, it must be within the scope and not excluded
+fn admit(config: &Config) -> impl Fn(&str) -> bool + Send + Sync + 'static {
+	let config = config.clone();
+	move |host| {
+		scope::enforce(&config, vec![host.to_string()])
+			.and_then(|hosts| exclusions::apply(&config, hosts))
+			.is_ok_and(|hosts| !hosts.is_empty())
+	}
+}
+
+/// Refuse an idle scan whose zombie is out of scope or excluded, since every probe goes to it
+#[cfg(feature = "core-scan")]
+fn admit_zombie(config: &Config) -> Result<(), Box<dyn Error>> {
+	let Some(zombie) = config.zombie.as_deref().filter(|_| config.scan_types.contains(&huginn_core::plugins::ScanType::Idle)) else {
+		return Ok(());
 	};
+	let (host, _) = huginn_core::plugins::idle::parse_zombie(zombie)?;
+	if !admit(config)(host) {
+		return Err(format!("The idle scan zombie {} is out of scope or excluded", host).into());
+	}
+	Ok(())
+}
 
-	info!("Configuration loaded successfully");
+/// Refuse default credential checks unless the operator has confirmed being authorized to, warn
+/// before them, and record each host that a scan type logs in to in the audit log
+#[cfg(feature = "web-checks")]
+fn authorize_logins(config: &Config, hosts: &[String]) -> Result<(), Box<dyn Error>> {
+	if config.scan_types.contains(&huginn_core::plugins::ScanType::SshAuditAuth) {
+		info!("ssh_audit_auth will log in to {} hosts with the configured SSH credentials", hosts.len());
+		for host in hosts {
+			audit::record(config, "login_attempts", host, "ssh_audit_auth logs in with configured credentials")?;
+		}
+	}
+	if !config.scan_types.contains(&huginn_core::plugins::ScanType::DefaultCreds) {
+		return Ok(());
+	}
+	if !config.default_creds.authorized {
+		return Err(
+			"default_creds tries logins on its targets; set authorized = true in the default_creds section once you are authorized to"
+				.into(),
+		);
+	}
+	warn!(
+		"default_creds will try vendor default logins on {} hosts; only scan systems you are authorized to test, as failed logins can lock accounts and raise alerts",
+		hosts.len()
+	);
+	for host in hosts {
+		audit::record(config, "login_attempts", host, "default_creds tries vendor default logins")?;
+	}
+	Ok(())
+}
+
+/// Print the probe plan without sending anything or writing the audit log
+async fn dry_run(config: &Config) -> Result<(), Box<dyn Error>> {
+	// Scope and exclusion decisions are still logged, but a plan is not a scan, so none is audited
+	let config = &Config { audit_log: None, ..config.clone() };
+	let plan = plan::Plan::new(config, hosts(config, true).await?, &catalog::builtin(config));
+	print!("{}", plan);
+	Ok(())
+}
+
+/// Run the configured scans and deliver the results, storing the run when `history_dir` is set
+///
+/// Returns a description of each `fail_on` condition that the results meet.
+async fn scan(config: &Config) -> Result<Vec<String>, Box<dyn Error>> {
+	if config.spill {
+		return spill(config).await.map(|_| Vec::new());
+	}
+	record(config, run_scan(config)).await.map(|(_, met)| met)
+}
+
+/// Scan the configured targets with the results kept in a temporary file instead of memory, and
+/// write every output from the file
+async fn spill(config: &Config) -> Result<(), Box<dyn Error>> {
+	let unspillable = config.unspillable();
+	if !unspillable.is_empty() {
+		return Err(format!("Cannot spill results: {} must see every result in memory", unspillable.join(", ")).into());
+	}
+	let dispatcher =
+		OutputDispatcher::from_config(config).map_err(|e| format!("Failed to set up output: {}", e))?;
+	let dir = config.spill_dir.clone().unwrap_or_else(std::env::temp_dir);
+	let mut spool =
+		Spool::create(&dir).map_err(|e| format!("Failed to create a spill file in {}: {}", dir.display(), e))?;
+	info!("Spilling results to {}", spool.path().display());
+
+	let scanner = prepare(config).await?.build();
+	let statistics =
+		scanner.run_spooled(&mut spool).await.map_err(|e| format!("Scanner error: {}", e))?;
+	dispatcher
+		.write_spooled(&mut spool, &statistics)
+		.await
+		.map_err(|e| format!("Failed to write results: {}", e))?;
+	Ok(())
+}
+
+/// Run a scan, storing its results and log when `history_dir` is set
+async fn record<T>(
+	config: &Config,
+	scan: impl Future<Output = Result<(Vec<ScanResult>, T), Box<dyn Error>>>,
+) -> Result<(Vec<ScanResult>, T), Box<dyn Error>> {
+	let Some(dir) = &config.history_dir else {
+		return scan.await;
+	};
+	let capture = logging::capture();
+	let started = Utc::now();
+	let outcome = scan.await;
+	let log = capture.finish();
+
+	let stored = outcome.as_ref().map(|(results, _)| results.as_slice()).map_err(|e| e.to_string());
+	match history::save(dir, started, &config.targets, stored, &log) {
+		Ok(run) => info!("Stored run {}", run.id),
+		Err(e) => error!("Failed to store run in {}: {}", dir.display(), e),
+	}
+	outcome
+}
+
+/// Scan the configured targets and deliver the results, along with the `fail_on` conditions met
+async fn run_scan(config: &Config) -> Result<(Vec<ScanResult>, Vec<String>), Box<dyn Error>> {
+	// Resolve the output sinks before scanning so a bad spec fails fast
+	let dispatcher =
+		OutputDispatcher::from_config(config).map_err(|e| format!("Failed to set up output: {}", e))?;
+	let (results, statistics) = collect(config).await?;
+
+	// Compare against the baseline, if one is configured
+	let drift = match &config.baseline {
+		Some(path) => {
+			let previous = baseline::load(path).map_err(|e| format!("Failed to load baseline: {}", e))?;
+			let drift = baseline::diff(&previous, &results);
+			if drift.is_empty() {
+				info!("No drift from baseline");
+			} else {
+				info!(
+					"Drift from baseline: {} new, {} no longer present",
+					drift.added.len(),
+					drift.removed.len()
+				);
+			}
+			Some(drift)
+		},
+		None => None,
+	};
+
+	// Write the formatted results to every sink
+	dispatcher
+		.write(&results, &statistics)
+		.await
+		.map_err(|e| format!("Failed to write results: {}", e))?;
+
+	#[cfg(feature = "notifications")]
+	if let Err(e) = notifications::notify(config, &results, drift.as_ref()).await {
+		error!("Failed to send notifications: {}", e);
+	}
+
+	#[cfg(feature = "email")]
+	if let Err(e) = email::send(config, &results).await {
+		error!("Failed to email report: {}", e);
+	}
+
+	#[cfg(feature = "integrations")]
+	if let Err(e) = integrations::push(config, &results, drift.as_ref()).await {
+		error!("Failed to push findings: {}", e);
+	}
+
+	let met = gate::check(&config.fail_on, &results, drift.as_ref());
+	Ok((results, met))
+}
 
-	// Initialize scanner
-	let scanner = scanner::Scanner::new(config);
+/// Scan the configured targets, streaming each result to any configured sinks, and return the
+/// results with the statistics of the run
+async fn collect(config: &Config) -> Result<(Vec<ScanResult>, Statistics), Box<dyn Error>> {
+	#[cfg(feature = "intel-enrichment")]
+	let vulns = vulns::Database::from_config(config)?;
+	#[cfg(feature = "intel-enrichment")]
+	let reputation = config.reputation.as_ref().map(reputation::Checker::new).transpose()?;
+	let scanner = prepare(config).await?;
 
 	// Run the scanner
-	if let Err(e) = scanner.run().await {
-		error!("Scanner error: {}", e);
-		std::process::exit(1);
+	#[cfg_attr(not(feature = "intel-enrichment"), allow(unused_mut))]
+	let (mut results, statistics) =
+		scanner.build().run_with_statistics().await.map_err(|e| format!("Scanner error: {}", e))?;
+	#[cfg(feature = "intel-enrichment")]
+	if let Some(vulns) = &vulns {
+		vulns.enrich(&mut results);
+	}
+	#[cfg(feature = "intel-enrichment")]
+	if let Some(reputation) = &reputation {
+		reputation.enrich(&mut results).await;
 	}
+	Ok((results, statistics))
+}
 
-	info!("Huginn completed successfully");
+/// Connect the streaming sinks and check the targets, returning a scanner for them with the
+/// configured settings
+async fn prepare(config: &Config) -> Result<ScannerBuilder, Box<dyn Error>> {
+	config.raw_options().check()?;
+	let sinks = sinks::connect(config)
+		.await
+		.map_err(|e| format!("Failed to connect streaming sinks: {}", e))?;
+
+	let targets = hosts(config, false).await?;
+	rdns::verify(config, &targets).await?;
+	#[cfg(feature = "core-scan")]
+	admit_zombie(config)?;
+	#[cfg(feature = "web-checks")]
+	authorize_logins(config, &targets)?;
+
+	if config.raise_fd_limit {
+		match limits::raise_descriptor_limit() {
+			Ok(limit) => info!("File descriptor limit is {}", limit),
+			Err(e) => warn!("Failed to raise the file descriptor limit: {}", e),
+		}
+	}
+
+	let scanner = config
+		.scanner()
+		.targets(targets)
+		.plugins(catalog::builtin(config))
+		.sinks(sinks)
+		.credentials(credentials::resolve(config)?)
+		.admit(admit(config));
+	#[cfg(feature = "daemon")]
+	let scanner = scanner.observer(Box::new(metrics::Recorder));
+	Ok(scanner)
 }
@@ -0,0 +1,391 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Plugin index client for Huginn
+//!
+//! This module fetches the plugin index from the configured URL and checks its detached Ed25519
+//! signature, published next to it as `<index>.sig`. Each downloaded package is checked against
+//! the SHA-256 digest in the index, and against the Ed25519 signature of its entry, which covers
+//! its name, version, kind, and digest so a signed package cannot be passed off as another plugin
+//! or an older version. Packages are installed into the plugins directory, and installed versions
+//! are recorded in a lock file so explicitly requested versions stay pinned across updates.
+
+use crate::config::{Config, PluginIndexCommand};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use log::{info, warn};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Name of the lock file kept in the plugins directory
+const LOCK_FILE: &str = "plugins.lock.json";
+
+/// Plugin package entry published in the index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+	/// Plugin name
+	pub name: String,
+	/// Package version (semantic versioning)
+	pub version: String,
+	/// Short description
+	#[serde(default)]
+	pub description: String,
+	/// Package kind: `wasm` or `script`
+	pub kind: String,
+	/// Download URL of the package
+	pub url: String,
+	/// Hex-encoded SHA-256 digest of the package
+	pub sha256: String,
+	/// Hex-encoded Ed25519 signature over the entry's [`signed_message`]
+	pub signature: String,
+}
+
+/// Plugin index document
+#[derive(Debug, Deserialize)]
+struct Index {
+	plugins: Vec<IndexEntry>,
+}
+
+/// Installed plugin record kept in the lock file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledPlugin {
+	/// Plugin name
+	pub name: String,
+	/// Installed version
+	pub version: String,
+	/// Package kind
+	pub kind: String,
+	/// Hex-encoded SHA-256 digest of the installed package
+	pub sha256: String,
+	/// Whether updates must leave this version in place
+	pub pinned: bool,
+}
+
+/// Client for the configured plugin index
+pub struct Marketplace {
+	index_url: String,
+	keys: Vec<VerifyingKey>,
+	plugins_dir: PathBuf,
+	client: reqwest::Client,
+}
+
+impl Marketplace {
+	/// Create a client from configuration
+	pub fn new(config: &Config) -> Result<Self, Box<dyn Error>> {
+		let index_url = config.plugin_index_url.clone().ok_or("plugin_index_url is not configured")?;
+		let keys = config
+			.plugin_index_keys
+			.iter()
+			.map(|key| parse_key(key))
+			.collect::<Result<Vec<_>, _>>()?;
+		if keys.is_empty() {
+			return Err("plugin_index_keys must contain at least one trusted key".into());
+		}
+
+		Ok(Self {
+			index_url,
+			keys,
+			plugins_dir: PathBuf::from(&config.plugins_dir),
+			client: reqwest::Client::new(),
+		})
+	}
+
+	/// Fetch raw bytes from an https:// or file:// URL
+	async fn fetch(&self, url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+		if let Some(path) = url.strip_prefix("file://") {
+			return Ok(tokio::fs::read(path).await?);
+		}
+		if !url.starts_with("https://") {
+			return Err(format!("Refusing to fetch from non-HTTPS URL: {}", url).into());
+		}
+		let response = self.client.get(url).send().await?.error_for_status()?;
+		Ok(response.bytes().await?.to_vec())
+	}
+
+	/// Fetch the plugin index, check its signature, and parse it
+	async fn index(&self) -> Result<Vec<IndexEntry>, Box<dyn Error>> {
+		let bytes = self.fetch(&self.index_url).await?;
+		let signature = self.fetch(&format!("{}.sig", self.index_url)).await?;
+		if !verifies(&self.keys, &bytes, String::from_utf8_lossy(&signature).trim()) {
+			return Err(format!("No trusted key verifies the signature of the plugin index {}", self.index_url).into());
+		}
+		let index: Index = serde_json::from_slice(&bytes)?;
+		Ok(index.plugins)
+	}
+
+	/// Search the index by name or description
+	pub async fn search(&self, query: Option<&str>) -> Result<Vec<IndexEntry>, Box<dyn Error>> {
+		let query = query.map(str::to_lowercase);
+		Ok(self
+			.index()
+			.await?
+			.into_iter()
+			.filter(|entry| match &query {
+				Some(q) => entry.name.to_lowercase().contains(q) || entry.description.to_lowercase().contains(q),
+				None => true,
+			})
+			.collect())
+	}
+
+	/// Install a plugin, pinning it when an exact version is requested
+	pub async fn install(&self, name: &str, version: Option<&str>) -> Result<InstalledPlugin, Box<dyn Error>> {
+		let entries = self.index().await?;
+		let entry = select(&entries, name, version)?;
+		self.install_entry(entry, version.is_some()).await
+	}
+
+	/// Update installed, unpinned plugins to the latest indexed version
+	pub async fn update(&self, name: Option<&str>) -> Result<Vec<InstalledPlugin>, Box<dyn Error>> {
+		let entries = self.index().await?;
+		let mut updated = Vec::new();
+
+		for installed in self.read_lock()? {
+			if name.is_some_and(|n| n != installed.name) {
+				continue;
+			}
+			if installed.pinned {
+				info!("Skipping pinned plugin {} {}", installed.name, installed.version);
+				continue;
+			}
+			let latest = match select(&entries, &installed.name, None) {
+				Ok(latest) => latest,
+				Err(e) => {
+					warn!("Not updating plugin {}: {}", installed.name, e);
+					continue;
+				},
+			};
+			if Version::parse(&latest.version)? > Version::parse(&installed.version)? {
+				updated.push(self.install_entry(latest, false).await?);
+			}
+		}
+
+		Ok(updated)
+	}
+
+	/// Download, verify, and install a single index entry
+	async fn install_entry(&self, entry: &IndexEntry, pinned: bool) -> Result<InstalledPlugin, Box<dyn Error>> {
+		validate_name(&entry.name)?;
+		let extension = match entry.kind.as_str() {
+			"wasm" => "wasm",
+			"script" => "bundle",
+			other => return Err(format!("Unsupported plugin package kind: {}", other).into()),
+		};
+
+		let bytes = self.fetch(&entry.url).await?;
+		verify(&self.keys, entry, &bytes)?;
+
+		tokio::fs::create_dir_all(&self.plugins_dir).await?;
+		let file = self.plugins_dir.join(format!("{}.{}", entry.name, extension));
+		tokio::fs::write(&file, &bytes).await?;
+		info!("Installed plugin {} {} to {}", entry.name, entry.version, file.display());
+
+		let record = InstalledPlugin {
+			name: entry.name.clone(),
+			version: entry.version.clone(),
+			kind: entry.kind.clone(),
+			sha256: entry.sha256.to_lowercase(),
+			pinned,
+		};
+		let mut lock = self.read_lock()?;
+		lock.retain(|p| p.name != record.name);
+		lock.push(record.clone());
+		lock.sort_by(|a, b| a.name.cmp(&b.name));
+		std::fs::write(self.plugins_dir.join(LOCK_FILE), serde_json::to_string_pretty(&lock)?)?;
+
+		Ok(record)
+	}
+
+	/// Read the lock file, treating a missing file as an empty install set
+	fn read_lock(&self) -> Result<Vec<InstalledPlugin>, Box<dyn Error>> {
+		read_lock(&self.plugins_dir)
+	}
+}
+
+/// Read the lock file in a plugins directory, treating a missing file as an empty install set
+fn read_lock(plugins_dir: &Path) -> Result<Vec<InstalledPlugin>, Box<dyn Error>> {
+	let path = plugins_dir.join(LOCK_FILE);
+	if !path.exists() {
+		return Ok(Vec::new());
+	}
+	Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/// Plugin packages installed in the configured plugins directory
+pub fn installed(config: &Config) -> Result<Vec<InstalledPlugin>, Box<dyn Error>> {
+	read_lock(Path::new(&config.plugins_dir))
+}
+
+/// Parse a hex-encoded Ed25519 public key
+fn parse_key(key: &str) -> Result<VerifyingKey, Box<dyn Error>> {
+	let bytes: [u8; 32] = hex::decode(key)?
+		.try_into()
+		.map_err(|_| "Ed25519 public keys must be 32 bytes")?;
+	Ok(VerifyingKey::from_bytes(&bytes)?)
+}
+
+/// Reject plugin names that could escape the plugins directory
+fn validate_name(name: &str) -> Result<(), Box<dyn Error>> {
+	if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+		return Err(format!("Invalid plugin name: {:?}", name).into());
+	}
+	Ok(())
+}
+
+/// Select the requested version of a plugin, or the latest one
+fn select<'a>(entries: &'a [IndexEntry], name: &str, version: Option<&str>) -> Result<&'a IndexEntry, Box<dyn Error>> {
+	let mut candidates: Vec<(&IndexEntry, Version)> = entries
+		.iter()
+		.filter(|e| e.name == name)
+		.filter_map(|e| Version::parse(&e.version).ok().map(|v| (e, v)))
+		.collect();
+	if let Some(version) = version {
+		let wanted = Version::parse(version)?;
+		candidates.retain(|(_, v)| *v == wanted);
+	}
+	candidates.sort_by(|a, b| a.1.cmp(&b.1));
+	candidates
+		.pop()
+		.map(|(entry, _)| entry)
+		.ok_or_else(|| format!("Plugin {} {} not found in index", name, version.unwrap_or("(latest)")).into())
+}
+
+/// The message an index entry's signature covers: its name, version, kind, and package digest,
+/// one per line
+fn signed_message(entry: &IndexEntry) -> String {
+	format!("{}\n{}\n{}\n{}", entry.name, entry.version, entry.kind, entry.sha256.to_lowercase())
+}
+
+/// Whether one of the trusted keys made a hex-encoded signature over a message
+fn verifies(keys: &[VerifyingKey], message: &[u8], signature: &str) -> bool {
+	let Some(signature) = hex::decode(signature).ok().and_then(|bytes| Signature::from_slice(&bytes).ok()) else {
+		return false;
+	};
+	keys.iter().any(|key| key.verify(message, &signature).is_ok())
+}
+
+/// Verify a package digest and the signature of its index entry against the trusted keys
+fn verify(keys: &[VerifyingKey], entry: &IndexEntry, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+	let digest = hex::encode(Sha256::digest(bytes));
+	if digest != entry.sha256.to_lowercase() {
+		return Err(format!("Digest mismatch for {} {}", entry.name, entry.version).into());
+	}
+
+	if verifies(keys, signed_message(entry).as_bytes(), &entry.signature) {
+		Ok(())
+	} else {
+		Err(format!("No trusted key verifies the signature of {} {}", entry.name, entry.version).into())
+	}
+}
+
+/// Run a plugin index subcommand, printing results to stdout
+pub async fn run(config: &Config, command: PluginIndexCommand) -> Result<(), Box<dyn Error>> {
+	let marketplace = Marketplace::new(config)?;
+	match command {
+		PluginIndexCommand::Search { query } => {
+			for entry in marketplace.search(query.as_deref()).await? {
+				println!("{} {} ({}) - {}", entry.name, entry.version, entry.kind, entry.description);
+			}
+		},
+		PluginIndexCommand::Install { name, version } => {
+			let installed = marketplace.install(&name, version.as_deref()).await?;
+			let pin = if installed.pinned { " (pinned)" } else { "" };
+			println!("Installed {} {}{}", installed.name, installed.version, pin);
+		},
+		PluginIndexCommand::Update { name } => {
+			let updated = marketplace.update(name.as_deref()).await?;
+			if updated.is_empty() {
+				println!("All plugins are up to date");
+			}
+			for installed in updated {
+				println!("Updated {} to {}", installed.name, installed.version);
+			}
+		},
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ed25519_dalek::{Signer, SigningKey};
+
+	fn entry(version: &str, bytes: &[u8], key: &SigningKey) -> IndexEntry {
+		let mut entry = IndexEntry {
+			name: "banner".to_string(),
+			version: version.to_string(),
+			description: String::new(),
+			kind: "wasm".to_string(),
+			url: String::new(),
+			sha256: hex::encode(Sha256::digest(bytes)),
+			signature: String::new(),
+		};
+		entry.signature = hex::encode(key.sign(signed_message(&entry).as_bytes()).to_bytes());
+		entry
+	}
+
+	#[test]
+	fn test_verify_signature() {
+		let key = SigningKey::from_bytes(&[7; 32]);
+		let other = SigningKey::from_bytes(&[9; 32]);
+		let package = entry("1.0.0", b"package", &key);
+
+		assert!(verify(&[key.verifying_key()], &package, b"package").is_ok());
+		assert!(verify(&[other.verifying_key()], &package, b"package").is_err());
+		assert!(verify(&[key.verifying_key()], &package, b"tampered").is_err());
+
+		// A signed package cannot be passed off as another plugin or version
+		let renamed = IndexEntry {
+			name: "other".to_string(),
+			..package.clone()
+		};
+		assert!(verify(&[key.verifying_key()], &renamed, b"package").is_err());
+		let downgraded = IndexEntry {
+			version: "0.9.0".to_string(),
+			..package
+		};
+		assert!(verify(&[key.verifying_key()], &downgraded, b"package").is_err());
+	}
+
+	#[tokio::test]
+	async fn test_index_signature() {
+		let key = SigningKey::from_bytes(&[7; 32]);
+		let dir = std::env::temp_dir().join(format!("huginn-index-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let index = br#"{"plugins": []}"#;
+		std::fs::write(dir.join("index.json"), index).unwrap();
+		let marketplace = Marketplace {
+			index_url: format!("file://{}", dir.join("index.json").display()),
+			keys: vec![key.verifying_key()],
+			plugins_dir: dir.join("plugins"),
+			client: reqwest::Client::new(),
+		};
+
+		assert!(marketplace.index().await.is_err());
+		std::fs::write(dir.join("index.json.sig"), hex::encode(key.sign(b"{}").to_bytes())).unwrap();
+		assert!(marketplace.index().await.is_err());
+		std::fs::write(dir.join("index.json.sig"), format!("{}\n", hex::encode(key.sign(index).to_bytes()))).unwrap();
+		assert!(marketplace.index().await.unwrap().is_empty());
+
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn test_select_version() {
+		let key = SigningKey::from_bytes(&[7; 32]);
+		let entries = vec![entry("1.2.0", b"a", &key), entry("1.10.0", b"b", &key), entry("1.3.0", b"c", &key)];
+
+		assert_eq!(select(&entries, "banner", None).unwrap().version, "1.10.0");
+		assert_eq!(select(&entries, "banner", Some("1.2.0")).unwrap().version, "1.2.0");
+		assert!(select(&entries, "banner", Some("2.0.0")).is_err());
+		assert!(select(&entries, "missing", None).is_err());
+	}
+
+	#[test]
+	fn test_validate_name() {
+		assert!(validate_name("tls-audit_2").is_ok());
+		assert!(validate_name("../escape").is_err());
+		assert!(validate_name("").is_err());
+	}
+}
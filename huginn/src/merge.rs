@@ -0,0 +1,186 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Result merging for Huginn
+//!
+//! `huginn merge` combines result sets, such as those from distributed workers or separate
+//! vantage points, into one deduplicated set. Results describe the same observation when their
+//! target, scan type, and port match. Exact duplicates are merged into one, filling in any service
+//! or version the kept result lacks and combining their details. When two results for the same
+//! observation disagree on status, the `--on-conflict` rule decides which is kept.
+
+use crate::config::{Config, MergeArgs};
+use crate::output::OutputDispatcher;
+use crate::report;
+use huginn_core::normalize;
+use huginn_core::plugins::ScanResult;
+use huginn_core::statistics::Statistics;
+use log::info;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// How to resolve results for the same observation with different statuses
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Conflict {
+	/// Keep the result from the earliest source
+	First,
+	/// Keep the result from the latest source, with the service and version of earlier ones
+	/// where it has none
+	#[default]
+	Last,
+	/// Keep a result reporting the port open or the host up, if any source saw one
+	Open,
+	/// Keep every distinct status
+	All,
+}
+
+impl FromStr for Conflict {
+	type Err = String;
+
+	fn from_str(rule: &str) -> Result<Self, Self::Err> {
+		match rule.trim().to_ascii_lowercase().as_str() {
+			"first" => Ok(Conflict::First),
+			"last" => Ok(Conflict::Last),
+			"open" => Ok(Conflict::Open),
+			"all" => Ok(Conflict::All),
+			_ => Err(format!("invalid conflict rule {:?}; expected first, last, open, or all", rule)),
+		}
+	}
+}
+
+impl fmt::Display for Conflict {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Conflict::First => "first",
+			Conflict::Last => "last",
+			Conflict::Open => "open",
+			Conflict::All => "all",
+		})
+	}
+}
+
+/// Whether a status reports something reachable
+fn reachable(status: &str) -> bool {
+	matches!(status, "open" | "up")
+}
+
+/// Counts reported after a merge
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+	/// Results dropped as exact duplicates
+	pub duplicates: usize,
+	/// Results whose status disagreed with an earlier one
+	pub conflicts: usize,
+}
+
+/// Merge result sets in order, deduplicating observations and resolving status conflicts
+pub fn merge(sets: Vec<Vec<ScanResult>>, rule: Conflict) -> (Vec<ScanResult>, Summary) {
+	let mut merged: Vec<ScanResult> = Vec::new();
+	let mut seen: HashMap<(String, String, Option<u16>), Vec<usize>> = HashMap::new();
+	let mut summary = Summary::default();
+
+	for result in sets.into_iter().flatten() {
+		let key = (result.target.clone(), result.scan_type.clone(), result.port);
+		let indices = seen.entry(key).or_default();
+		if let Some(&same) = indices.iter().find(|&&i| merged[i].status == result.status) {
+			summary.duplicates += 1;
+			normalize::merge_into(&mut merged[same], result);
+			continue;
+		}
+		let Some(&existing) = indices.first() else {
+			indices.push(merged.len());
+			merged.push(result);
+			continue;
+		};
+
+		summary.conflicts += 1;
+		match rule {
+			Conflict::First => {},
+			Conflict::Last => {
+				let earlier = std::mem::replace(&mut merged[existing], result);
+				let kept = &mut merged[existing];
+				kept.service = kept.service.take().or(earlier.service);
+				kept.version = kept.version.take().or(earlier.version);
+			},
+			Conflict::Open => {
+				if reachable(&result.status) && !reachable(&merged[existing].status) {
+					merged[existing] = result;
+				}
+			},
+			Conflict::All => {
+				indices.push(merged.len());
+				merged.push(result);
+			},
+		}
+	}
+	(merged, summary)
+}
+
+/// Run the `merge` subcommand, writing the merged results to every configured output
+pub async fn run(config: &Config, args: &MergeArgs) -> Result<(), Box<dyn Error>> {
+	let dispatcher =
+		OutputDispatcher::from_config(config).map_err(|e| format!("Failed to set up output: {}", e))?;
+	let sets = args
+		.sources
+		.iter()
+		.map(|source| report::load(config, source))
+		.collect::<Result<Vec<_>, _>>()?;
+	let total: usize = sets.iter().map(Vec::len).sum();
+
+	let (merged, summary) = merge(sets, args.on_conflict);
+	info!(
+		"Merged {} results from {} sources into {} ({} duplicates, {} status conflicts)",
+		total,
+		args.sources.len(),
+		merged.len(),
+		summary.duplicates,
+		summary.conflicts
+	);
+	dispatcher.write(&merged, &Statistics::from_results(&merged)).await
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse() {
+		assert_eq!("Open".parse::<Conflict>().unwrap(), Conflict::Open);
+		assert!("newest".parse::<Conflict>().unwrap_err().contains("first, last"));
+		assert_eq!(Conflict::default().to_string(), "last");
+	}
+
+	#[test]
+	fn test_merge() {
+		let sets = || {
+			vec![
+				vec![
+					ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22),
+					ScanResult::new("10.0.0.1", "tcp_connect", "filtered").with_port(80).with_service("http"),
+				],
+				vec![
+					ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22).with_service("ssh"),
+					ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(80),
+					ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(443),
+				],
+				vec![ScanResult::new("10.0.0.1", "tcp_connect", "closed").with_port(80)],
+			]
+		};
+
+		let (merged, summary) = merge(sets(), Conflict::Last);
+		assert_eq!(summary, Summary { duplicates: 1, conflicts: 2 });
+		assert_eq!(merged.len(), 3);
+		assert_eq!(merged[0].service.as_deref(), Some("ssh"));
+		assert_eq!(merged[1].status, "closed");
+		assert_eq!(merged[1].service.as_deref(), Some("http"));
+
+		assert_eq!(merge(sets(), Conflict::First).0[1].status, "filtered");
+		assert_eq!(merge(sets(), Conflict::Open).0[1].status, "open");
+
+		let (all, _) = merge(sets(), Conflict::All);
+		let statuses: Vec<&str> = all.iter().filter(|r| r.port == Some(80)).map(|r| r.status.as_str()).collect();
+		assert_eq!(statuses, ["filtered", "open", "closed"]);
+	}
+}
@@ -0,0 +1,146 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Prometheus metrics for Huginn
+//!
+//! This module keeps process-wide scanner health and findings counters and renders them in the
+//! Prometheus text exposition format for the daemon's `/metrics` endpoint.
+
+use huginn_core::plugins::ScanResult;
+use huginn_core::scanner::{Observer, ScanEvent};
+use prometheus::{Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts, Registry, TextEncoder};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// Buckets for scan durations, from sub-second probes to hour-long sweeps
+const DURATION_BUCKETS: &[f64] = &[0.1, 0.5, 1.0, 5.0, 15.0, 60.0, 300.0, 900.0, 3600.0];
+
+/// All Huginn collectors
+struct Collectors {
+	registry: Registry,
+	runs: IntCounter,
+	run_duration: Histogram,
+	probes_sent: IntCounter,
+	open_ports: IntCounterVec,
+	findings: IntCounterVec,
+	plugin_errors: IntCounterVec,
+	plugin_duration: HistogramVec,
+}
+
+impl Collectors {
+	fn new() -> Result<Self, prometheus::Error> {
+		let registry = Registry::new_custom(Some("huginn".to_string()), None)?;
+		let runs = IntCounter::new("scan_runs_total", "Completed scan runs")?;
+		let run_duration = Histogram::with_opts(
+			HistogramOpts::new("scan_run_duration_seconds", "Duration of complete scan runs")
+				.buckets(DURATION_BUCKETS.to_vec()),
+		)?;
+		let probes_sent = IntCounter::new("probes_sent_total", "Probes sent, counting each retry")?;
+		let open_ports = IntCounterVec::new(Opts::new("open_ports_total", "Open ports found, by plugin"), &["plugin"])?;
+		let findings = IntCounterVec::new(Opts::new("findings_total", "Findings reported, by severity"), &["severity"])?;
+		let plugin_errors = IntCounterVec::new(Opts::new("plugin_errors_total", "Failed scans, by plugin"), &["plugin"])?;
+		let plugin_duration = HistogramVec::new(
+			HistogramOpts::new("plugin_scan_duration_seconds", "Duration of a plugin scan against one target")
+				.buckets(DURATION_BUCKETS.to_vec()),
+			&["plugin"],
+		)?;
+
+		registry.register(Box::new(runs.clone()))?;
+		registry.register(Box::new(run_duration.clone()))?;
+		registry.register(Box::new(probes_sent.clone()))?;
+		registry.register(Box::new(open_ports.clone()))?;
+		registry.register(Box::new(findings.clone()))?;
+		registry.register(Box::new(plugin_errors.clone()))?;
+		registry.register(Box::new(plugin_duration.clone()))?;
+
+		Ok(Self {
+			registry,
+			runs,
+			run_duration,
+			probes_sent,
+			open_ports,
+			findings,
+			plugin_errors,
+			plugin_duration,
+		})
+	}
+}
+
+static COLLECTORS: LazyLock<Collectors> =
+	LazyLock::new(|| Collectors::new().expect("metric definitions are valid and unique"));
+
+/// Record a completed plugin scan against one target
+pub fn observe_plugin_scan(plugin: &str, duration: Duration, results: &[ScanResult]) {
+	let c = &*COLLECTORS;
+	c.plugin_duration.with_label_values(&[plugin]).observe(duration.as_secs_f64());
+	let open = results.iter().filter(|r| r.status == "open").count();
+	c.open_ports.with_label_values(&[plugin]).inc_by(open as u64);
+	for severity in results.iter().filter_map(|r| r.severity) {
+		c.findings.with_label_values(&[&severity.to_string()]).inc();
+	}
+}
+
+/// Record a failed plugin scan
+pub fn observe_plugin_error(plugin: &str, duration: Duration) {
+	let c = &*COLLECTORS;
+	c.plugin_duration.with_label_values(&[plugin]).observe(duration.as_secs_f64());
+	c.plugin_errors.with_label_values(&[plugin]).inc();
+}
+
+/// Records every plugin run the scanner observes
+pub struct Recorder;
+
+impl Observer for Recorder {
+	fn event(&self, event: &ScanEvent) {
+		match event {
+			ScanEvent::PluginFinished { plugin, elapsed, results, .. } => observe_plugin_scan(plugin, *elapsed, results),
+			ScanEvent::PluginError { plugin, elapsed, .. } => observe_plugin_error(plugin, *elapsed),
+			ScanEvent::Finished { statistics } => observe_probes(statistics.probes_sent.unwrap_or_default()),
+			_ => {},
+		}
+	}
+}
+
+/// Record the probes a scan sent
+pub fn observe_probes(probes: u64) {
+	COLLECTORS.probes_sent.inc_by(probes);
+}
+
+/// Record a complete scan run
+pub fn observe_run(duration: Duration) {
+	COLLECTORS.runs.inc();
+	COLLECTORS.run_duration.observe(duration.as_secs_f64());
+}
+
+/// Render all metrics in the Prometheus text format
+pub fn encode() -> Result<String, prometheus::Error> {
+	let mut buffer = Vec::new();
+	TextEncoder::new().encode(&COLLECTORS.registry.gather(), &mut buffer)?;
+	Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use huginn_core::plugins::Severity;
+
+	#[test]
+	fn test_encode_includes_observations() {
+		let result = ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(23).with_severity(Severity::High);
+		observe_probes(3);
+		observe_plugin_scan("test_plugin", Duration::from_millis(20), &[result]);
+		observe_plugin_error("test_plugin", Duration::from_millis(5));
+
+		let text = encode().unwrap();
+		let probes: u64 = text
+			.lines()
+			.find_map(|line| line.strip_prefix("huginn_probes_sent_total "))
+			.and_then(|count| count.parse().ok())
+			.unwrap();
+		// Other tests may record scans of their own at the same time
+		assert!(probes >= 3, "{}", probes);
+		assert!(text.contains("huginn_open_ports_total{plugin=\"test_plugin\"} 1"));
+		assert!(text.contains("huginn_plugin_errors_total{plugin=\"test_plugin\"} 1"));
+		assert!(text.contains("huginn_findings_total{severity=\"high\"}"));
+	}
+}
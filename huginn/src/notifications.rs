@@ -0,0 +1,199 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Chat notifications for Huginn
+//!
+//! This module posts a run summary, and optionally the findings at or above a severity
+//! threshold, to Slack, Microsoft Teams, or Discord incoming webhooks when a scan completes or
+//! when the results drift from the configured baseline.
+
+use crate::baseline::Drift;
+use crate::config::Config;
+use huginn_core::plugins::{ScanResult, Severity};
+use log::{error, info};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::error::Error;
+use std::fmt::Write;
+
+/// Maximum number of findings listed in a single message
+const MAX_FINDINGS: usize = 20;
+
+/// Discord rejects messages longer than this many characters
+const DISCORD_LIMIT: usize = 2000;
+
+/// Webhook flavor, which determines the payload shape
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookKind {
+	/// Slack incoming webhook
+	Slack,
+	/// Microsoft Teams incoming webhook
+	Teams,
+	/// Discord webhook
+	Discord,
+}
+
+/// When a notification is sent
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyOn {
+	/// After every completed scan
+	#[default]
+	Completion,
+	/// Only when the results differ from the baseline
+	Drift,
+}
+
+/// Notification webhook configuration entry
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct NotificationConfig {
+	/// Webhook flavor
+	#[serde(rename = "type")]
+	pub kind: WebhookKind,
+	/// Webhook URL
+	pub url: String,
+	/// When to send the notification
+	#[serde(default)]
+	pub on: NotifyOn,
+	/// List findings at or above this severity in the message
+	pub min_severity: Option<Severity>,
+}
+
+/// Build the message text for a run
+fn message(results: &[ScanResult], drift: Option<&Drift>, min_severity: Option<Severity>) -> String {
+	let mut text = String::new();
+	let mut targets: Vec<&str> = results.iter().map(|r| r.target.as_str()).collect();
+	targets.sort_unstable();
+	targets.dedup();
+
+	let _ = writeln!(text, "Huginn scan completed: {} targets, {} results", targets.len(), results.len());
+
+	let mut statuses: Vec<(&str, usize)> = Vec::new();
+	for result in results {
+		match statuses.iter_mut().find(|(s, _)| *s == result.status) {
+			Some((_, count)) => *count += 1,
+			None => statuses.push((&result.status, 1)),
+		}
+	}
+	if !statuses.is_empty() {
+		let summary: Vec<String> = statuses.iter().map(|(s, c)| format!("{} {}", s, c)).collect();
+		let _ = writeln!(text, "Status: {}", summary.join(", "));
+	}
+
+	if let Some(drift) = drift {
+		let _ = writeln!(
+			text,
+			"Drift from baseline: {} new, {} no longer present",
+			drift.added.len(),
+			drift.removed.len()
+		);
+	}
+
+	if let Some(threshold) = min_severity {
+		let mut findings: Vec<&ScanResult> = results
+			.iter()
+			.filter(|r| r.severity.is_some_and(|s| s >= threshold))
+			.collect();
+		findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+		if !findings.is_empty() {
+			let _ = writeln!(text, "Findings at or above {}:", threshold);
+		}
+		for finding in findings.iter().take(MAX_FINDINGS) {
+			let location = match finding.port {
+				Some(port) => format!("{}:{}", finding.target, port),
+				None => finding.target.clone(),
+			};
+			let severity = finding.severity.map(|s| s.to_string()).unwrap_or_default();
+			let _ = write!(text, "- [{}] {} {} {}", severity, location, finding.scan_type, finding.status);
+			if let Some(details) = &finding.details {
+				let _ = write!(text, " ({})", details);
+			}
+			let _ = writeln!(text);
+		}
+		if findings.len() > MAX_FINDINGS {
+			let _ = writeln!(text, "...and {} more", findings.len() - MAX_FINDINGS);
+		}
+	}
+
+	text
+}
+
+/// Build the webhook payload for a message
+fn payload(kind: WebhookKind, text: &str) -> Value {
+	match kind {
+		WebhookKind::Slack => json!({ "text": text }),
+		WebhookKind::Teams => json!({
+			"@type": "MessageCard",
+			"@context": "https://schema.org/extensions",
+			"summary": "Huginn scan completed",
+			"title": "Huginn scan completed",
+			"text": text.replace('\n', "  \n"),
+		}),
+		WebhookKind::Discord => {
+			let content: String = text.chars().take(DISCORD_LIMIT).collect();
+			json!({ "content": content })
+		},
+	}
+}
+
+/// Send every configured notification that applies to this run
+pub async fn notify(config: &Config, results: &[ScanResult], drift: Option<&Drift>) -> Result<(), Box<dyn Error>> {
+	let client = reqwest::Client::new();
+	let mut failures = 0;
+	for notification in &config.notifications {
+		if notification.on == NotifyOn::Drift && drift.is_none_or(Drift::is_empty) {
+			continue;
+		}
+		let body = payload(notification.kind, &message(results, drift, notification.min_severity));
+		let sent = client
+			.post(&notification.url)
+			.json(&body)
+			.send()
+			.await
+			.and_then(|r| r.error_for_status());
+		match sent {
+			Ok(_) => info!("Sent {:?} notification", notification.kind),
+			Err(e) => {
+				error!("Failed to send {:?} notification: {}", notification.kind, e);
+				failures += 1;
+			},
+		}
+	}
+	if failures > 0 {
+		return Err(format!("{} notifications failed", failures).into());
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_message_lists_findings_above_threshold() {
+		let results = vec![
+			ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22).with_severity(Severity::Low),
+			ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(23).with_severity(Severity::High),
+			ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(3389).with_severity(Severity::Critical),
+		];
+		let text = message(&results, Some(&Drift::default()), Some(Severity::High));
+
+		assert!(text.starts_with("Huginn scan completed: 1 targets, 3 results"));
+		assert!(text.contains("Status: open 3"));
+		assert!(text.contains("Drift from baseline: 0 new, 0 no longer present"));
+		assert!(text.contains("- [critical] 10.0.0.1:3389"));
+		assert!(text.contains("- [high] 10.0.0.1:23"));
+		assert!(!text.contains(":22 "));
+		assert!(text.find("3389").unwrap() < text.find(":23").unwrap());
+	}
+
+	#[test]
+	fn test_payload_shapes() {
+		assert_eq!(payload(WebhookKind::Slack, "hi")["text"], "hi");
+		assert_eq!(payload(WebhookKind::Teams, "hi")["@type"], "MessageCard");
+		let long = "x".repeat(DISCORD_LIMIT + 10);
+		assert_eq!(payload(WebhookKind::Discord, &long)["content"].as_str().unwrap().len(), DISCORD_LIMIT);
+	}
+}
@@ -0,0 +1,399 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Output dispatch for Huginn
+//!
+//! This module routes one set of scan results to any number of output sinks. Each sink pairs a
+//! destination (a file, stdout, or an S3 object) with a formatter and is described by a
+//! `PATH[:FORMAT]` spec, where `-` is stdout and `s3://bucket/key` is uploaded after the run. When
+//! the format is omitted it is the configured template, or else it is inferred from the file
+//! extension, or else it is the configured `output_format`. When `encrypt_output` is set, every
+//! report is encrypted to its age recipients before it is written, and when `signing_key` is set,
+//! file and S3 reports get a detached Ed25519 signature.
+
+#[cfg(feature = "encryption")]
+pub mod encrypt;
+pub mod s3;
+#[cfg(feature = "signing")]
+pub mod sign;
+
+use crate::config::Config;
+use huginn_core::formatters::{self, Formatter, template::TemplateFormatter};
+use huginn_core::plugins::ScanResult;
+use huginn_core::spool::Spool;
+use huginn_core::statistics::Statistics;
+use log::{error, info};
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Where a sink writes its report
+#[derive(Debug, Clone, PartialEq)]
+pub enum Destination {
+	/// Standard output
+	Stdout,
+	/// A file on disk
+	File(PathBuf),
+	/// An S3 object; a key that is empty or ends in `/` is a prefix for a generated name
+	S3 {
+		/// Bucket name
+		bucket: String,
+		/// Object key or key prefix
+		key: String,
+	},
+}
+
+/// A destination paired with the formatter that renders its report
+pub struct OutputSink {
+	/// Where the report is written
+	pub destination: Destination,
+	/// How the report is rendered
+	pub formatter: Box<dyn Formatter>,
+	/// Age recipients the report is encrypted to; it is written in the clear when empty
+	#[cfg(feature = "encryption")]
+	pub recipients: Vec<encrypt::Recipient>,
+	/// Key that file and S3 reports are signed with
+	#[cfg(feature = "signing")]
+	pub signing_key: Option<ed25519_dalek::SigningKey>,
+}
+
+impl OutputSink {
+	/// Parse a `PATH[:FORMAT]` output spec
+	pub fn parse(spec: &str, config: &Config) -> Result<Self, Box<dyn Error>> {
+		// Only split on the last colon when the suffix names a format, so paths containing
+		// colons still work
+		let (path, format) = match spec.rsplit_once(':') {
+			Some((path, format)) if !path.is_empty() && is_format(format) => (path, Some(format)),
+			_ => (spec, None),
+		};
+
+		let destination = if path == "-" {
+			Destination::Stdout
+		} else if let Some(location) = path.strip_prefix("s3://") {
+			let (bucket, key) = location.split_once('/').unwrap_or((location, ""));
+			if bucket.is_empty() {
+				return Err(format!("Missing bucket in S3 output: {}", spec).into());
+			}
+			Destination::S3 {
+				bucket: bucket.to_string(),
+				key: key.to_string(),
+			}
+		} else {
+			Destination::File(PathBuf::from(path))
+		};
+
+		let formatter = match format {
+			Some(format) => create(format, config)?,
+			None => match (&config.template, &destination) {
+				(Some(_), _) => create("template", config)?,
+				(None, Destination::File(path)) => match infer_format(path)? {
+					Some(format) => create(format, config)?,
+					None => create(&config.output_format, config)?,
+				},
+				// A key ending in `/` is a prefix that the report gets a generated name under
+				(None, Destination::S3 { key, .. }) if key.ends_with('/') => create(&config.output_format, config)?,
+				(None, Destination::S3 { key, .. }) => match infer_format(Path::new(key))? {
+					Some(format) => create(format, config)?,
+					None => create(&config.output_format, config)?,
+				},
+				(None, Destination::Stdout) => create(&config.output_format, config)?,
+			},
+		};
+
+		Ok(Self {
+			destination,
+			formatter,
+			#[cfg(feature = "encryption")]
+			recipients: config.encrypt_output.clone(),
+			#[cfg(feature = "signing")]
+			signing_key: config.signing_key.as_deref().map(sign::load_key).transpose()?,
+		})
+	}
+
+	/// Whether the report is encrypted before it is written
+	#[cfg(feature = "encryption")]
+	fn encrypted(&self) -> bool {
+		!self.recipients.is_empty()
+	}
+
+	/// Whether the report is encrypted before it is written
+	#[cfg(not(feature = "encryption"))]
+	fn encrypted(&self) -> bool {
+		false
+	}
+
+	/// Whether file and S3 reports get a signature
+	#[cfg(feature = "signing")]
+	fn signed(&self) -> bool {
+		self.signing_key.is_some()
+	}
+
+	/// Whether file and S3 reports get a signature
+	#[cfg(not(feature = "signing"))]
+	fn signed(&self) -> bool {
+		false
+	}
+
+	/// Encrypt a rendered report when recipients are configured
+	fn seal(&self, report: String) -> Result<String, Box<dyn Error>> {
+		#[cfg(feature = "encryption")]
+		if self.encrypted() {
+			return encrypt::encrypt(&self.recipients, report.as_bytes());
+		}
+		Ok(report)
+	}
+
+	/// The format of the report, as logged once it is written
+	fn description(&self) -> String {
+		if self.encrypted() {
+			format!("{} (encrypted)", self.formatter.name())
+		} else {
+			self.formatter.name()
+		}
+	}
+
+	/// Render the results and write them to the destination
+	async fn write(&self, results: &[ScanResult], statistics: &Statistics) -> Result<(), Box<dyn Error>> {
+		let report = self.formatter.format_with_statistics(results, statistics)?;
+		self.deliver(self.seal(report)?).await
+	}
+
+	/// Write results read back from a spool to the destination
+	///
+	/// Reports to stdout and to files are written a result at a time; encrypted, signed, and S3
+	/// reports are rendered in memory first, since they are sealed or uploaded whole.
+	async fn write_spooled(&self, spool: &mut Spool, statistics: &Statistics) -> Result<(), Box<dyn Error>> {
+		let mut results = spool.read()?;
+		match &self.destination {
+			Destination::Stdout if !self.encrypted() => {
+				let mut out = io::stdout().lock();
+				self.formatter.write_stream(&mut results, statistics, &mut out)?;
+				writeln!(out)?;
+			},
+			Destination::File(path) if !self.encrypted() && !self.signed() => {
+				let mut out = BufWriter::new(File::create(path)?);
+				self.formatter.write_stream(&mut results, statistics, &mut out)?;
+				out.flush()?;
+				info!("Results written to {} as {}", path.display(), self.description());
+			},
+			_ => {
+				let mut report = Vec::new();
+				self.formatter.write_stream(&mut results, statistics, &mut report)?;
+				self.deliver(self.seal(String::from_utf8(report)?)?).await?;
+			},
+		}
+		Ok(())
+	}
+
+	/// Write a rendered report to the destination
+	async fn deliver(&self, report: String) -> Result<(), Box<dyn Error>> {
+		let description = self.description();
+		match &self.destination {
+			Destination::Stdout => println!("{}", report),
+			Destination::File(path) => {
+				#[cfg(feature = "signing")]
+				if let Some(key) = &self.signing_key {
+					std::fs::write(sign::signature_path(path), sign::sign(key, report.as_bytes()))?;
+				}
+				std::fs::write(path, report)?;
+				info!("Results written to {} as {}", path.display(), description);
+			},
+			Destination::S3 { bucket, key } => {
+				let key = if key.is_empty() || key.ends_with('/') {
+					format!(
+						"{}huginn-{}.{}{}",
+						key,
+						chrono::Utc::now().format("%Y%m%dT%H%M%SZ"),
+						extension(&self.formatter.name()),
+						if self.encrypted() { ".age" } else { "" }
+					)
+				} else {
+					key.clone()
+				};
+				#[cfg(feature = "signing")]
+				if let Some(signing_key) = &self.signing_key {
+					let signature = sign::sign(signing_key, report.as_bytes());
+					s3::upload(bucket, &format!("{}.sig", key), signature.into_bytes()).await?;
+				}
+				s3::upload(bucket, &key, report.into_bytes()).await?;
+				info!("Results uploaded to s3://{}/{} as {}", bucket, key, description);
+			},
+		}
+		Ok(())
+	}
+}
+
+/// Runs every configured sink over a result set
+pub struct OutputDispatcher {
+	sinks: Vec<OutputSink>,
+}
+
+impl OutputDispatcher {
+	/// Build the sinks selected by the configuration
+	///
+	/// Explicit `outputs` specs take precedence; otherwise a single sink is built from
+	/// `output_path` and `output_format`.
+	pub fn from_config(config: &Config) -> Result<Self, Box<dyn Error>> {
+		let sinks = if config.outputs.is_empty() {
+			let spec = config.output_path.as_deref().unwrap_or("-");
+			let format = if config.template.is_some() { "template" } else { &config.output_format };
+			vec![OutputSink::parse(&format!("{}:{}", spec, format), config)?]
+		} else {
+			config
+				.outputs
+				.iter()
+				.map(|spec| OutputSink::parse(spec, config))
+				.collect::<Result<Vec<_>, _>>()?
+		};
+		Ok(Self { sinks })
+	}
+
+	/// Write the results and the statistics of their run to every sink, reporting all failures
+	pub async fn write(&self, results: &[ScanResult], statistics: &Statistics) -> Result<(), Box<dyn Error>> {
+		let mut failures = 0;
+		for sink in &self.sinks {
+			if let Err(e) = sink.write(results, statistics).await {
+				error!("Failed to write {} output to {:?}: {}", sink.formatter.name(), sink.destination, e);
+				failures += 1;
+			}
+		}
+		if failures > 0 {
+			return Err(format!("{} of {} outputs failed", failures, self.sinks.len()).into());
+		}
+		Ok(())
+	}
+
+	/// Write the results in a spool and the statistics of their run to every sink, reading the
+	/// spool once for each, and reporting all failures
+	pub async fn write_spooled(&self, spool: &mut Spool, statistics: &Statistics) -> Result<(), Box<dyn Error>> {
+		let mut failures = 0;
+		for sink in &self.sinks {
+			if let Err(e) = sink.write_spooled(spool, statistics).await {
+				error!("Failed to write {} output to {:?}: {}", sink.formatter.name(), sink.destination, e);
+				failures += 1;
+			}
+		}
+		if failures > 0 {
+			return Err(format!("{} of {} outputs failed", failures, self.sinks.len()).into());
+		}
+		Ok(())
+	}
+}
+
+/// Whether a spec suffix names an output format
+fn is_format(name: &str) -> bool {
+	name == "template" || formatters::get(name).is_some()
+}
+
+/// Create a formatter by name, including the configured template
+pub fn create(name: &str, config: &Config) -> Result<Box<dyn Formatter>, Box<dyn Error>> {
+	if name == "template" {
+		let path = config.template.as_ref().ok_or("The template format requires a template file")?;
+		let formatter = TemplateFormatter::from_file(path)?;
+		#[cfg(feature = "web-checks")]
+		let formatter = formatter.screenshots(&config.web_screenshot.directory);
+		return Ok(Box::new(formatter));
+	}
+	formatters::get(name).ok_or_else(|| format!("Unknown output format: {}", name).into())
+}
+
+/// File extension used for generated report names
+pub fn extension(format: &str) -> &'static str {
+	match format {
+		"json" | "json-statistics" => "json",
+		"cyclonedx" => "cdx",
+		"dot" => "dot",
+		"text" => "txt",
+		"hosts" => "hosts.json",
+		"hosts-yaml" => "yaml",
+		_ => "out",
+	}
+}
+
+/// Infer an output format from a file extension, looking past a trailing `.age`
+///
+/// A path without an extension gets `None`, for the configured format. An extension that names no
+/// format, such as `.html`, is an error rather than a report in a format the name belies.
+fn infer_format(path: &Path) -> Result<Option<&'static str>, String> {
+	let Some(extension) = path.extension() else {
+		return Ok(None);
+	};
+	let extension = extension.to_string_lossy().to_ascii_lowercase();
+	match extension.as_str() {
+		"age" => infer_format(Path::new(path.file_stem().unwrap_or_default())),
+		"json" => Ok(Some("json")),
+		"cdx" => Ok(Some("cyclonedx")),
+		"dot" | "gv" => Ok(Some("dot")),
+		"txt" | "log" => Ok(Some("text")),
+		"yaml" | "yml" => Ok(Some("hosts-yaml")),
+		_ => Err(format!(
+			"Cannot tell the output format of {} from its .{} extension; name one as PATH:FORMAT",
+			path.display(),
+			extension
+		)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse_spec() {
+		let config = Config::default();
+
+		let sink = OutputSink::parse("-:text", &config).unwrap();
+		assert_eq!(sink.destination, Destination::Stdout);
+		assert_eq!(sink.formatter.name(), "text");
+
+		let sink = OutputSink::parse("results.json", &config).unwrap();
+		assert_eq!(sink.destination, Destination::File(PathBuf::from("results.json")));
+		assert_eq!(sink.formatter.name(), "json");
+
+		let sink = OutputSink::parse("map.gv", &config).unwrap();
+		assert_eq!(sink.formatter.name(), "dot");
+
+		let sink = OutputSink::parse("inventory.yml", &config).unwrap();
+		assert_eq!(sink.formatter.name(), "hosts-yaml");
+
+		let sink = OutputSink::parse("results.cdx.age", &config).unwrap();
+		assert_eq!(sink.formatter.name(), "cyclonedx");
+
+		let sink = OutputSink::parse("C:report:cyclonedx", &config).unwrap();
+		assert_eq!(sink.destination, Destination::File(PathBuf::from("C:report")));
+		assert_eq!(sink.formatter.name(), "cyclonedx");
+
+		// Paths without an extension get the configured format, and unknown extensions are refused
+		let sink = OutputSink::parse("report", &config).unwrap();
+		assert_eq!(sink.formatter.name(), config.output_format);
+		assert!(OutputSink::parse("report.html", &config).is_err());
+		assert_eq!(OutputSink::parse("report.html:text", &config).unwrap().formatter.name(), "text");
+
+		let sink = OutputSink::parse("s3://reports/nightly/:text", &config).unwrap();
+		assert_eq!(
+			sink.destination,
+			Destination::S3 {
+				bucket: "reports".to_string(),
+				key: "nightly/".to_string()
+			}
+		);
+		assert_eq!(sink.formatter.name(), "text");
+
+		let sink = OutputSink::parse("s3://reports/2025.06/", &config).unwrap();
+		assert_eq!(sink.formatter.name(), config.output_format);
+
+		let sink = OutputSink::parse("s3://reports/latest.dot", &config).unwrap();
+		assert_eq!(sink.formatter.name(), "dot");
+
+		assert!(OutputSink::parse("s3:///key", &config).is_err());
+		assert!(OutputSink::parse("-:template", &config).is_err());
+	}
+
+	#[test]
+	fn test_dispatcher_defaults_to_single_sink() {
+		let dispatcher = OutputDispatcher::from_config(&Config::default()).unwrap();
+		assert_eq!(dispatcher.sinks.len(), 1);
+		assert_eq!(dispatcher.sinks[0].destination, Destination::Stdout);
+	}
+}
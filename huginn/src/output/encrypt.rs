@@ -0,0 +1,88 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Report encryption for Huginn
+//!
+//! When `encrypt_output` lists age recipients, every written report is encrypted to all of them
+//! before it leaves the process. Reports are ASCII-armored so the same output works on stdout, on
+//! disk, in object storage, and as an email attachment; `age -d` reads it directly.
+
+use age::armor::{ArmoredWriter, Format};
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+/// An age X25519 public key (`age1...`)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Recipient(age::x25519::Recipient);
+
+impl FromStr for Recipient {
+	type Err = String;
+
+	fn from_str(key: &str) -> Result<Self, Self::Err> {
+		key.trim()
+			.parse()
+			.map(Self)
+			.map_err(|_| format!("invalid age recipient {:?}; expected an age1... public key", key))
+	}
+}
+
+impl TryFrom<String> for Recipient {
+	type Error = String;
+
+	fn try_from(key: String) -> Result<Self, Self::Error> {
+		key.parse()
+	}
+}
+
+impl fmt::Display for Recipient {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.0.fmt(f)
+	}
+}
+
+/// Encrypt a report to every recipient and wrap it in ASCII armor
+pub fn encrypt(recipients: &[Recipient], report: &[u8]) -> Result<String, Box<dyn Error>> {
+	let encryptor = age::Encryptor::with_recipients(recipients.iter().map(|r| &r.0 as &dyn age::Recipient))?;
+	let mut armored = Vec::with_capacity(report.len() * 2);
+	let mut writer = encryptor.wrap_output(ArmoredWriter::wrap_output(&mut armored, Format::AsciiArmor)?)?;
+	writer.write_all(report)?;
+	writer.finish()?.finish()?;
+	Ok(String::from_utf8(armored)?)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_round_trip() {
+		let identities = [age::x25519::Identity::generate(), age::x25519::Identity::generate()];
+		let recipients: Vec<Recipient> = identities
+			.iter()
+			.map(|identity| identity.to_public().to_string().parse().unwrap())
+			.collect();
+
+		let armored = encrypt(&recipients, b"10.0.0.1 tcp_connect 22 open").unwrap();
+		assert!(armored.starts_with("-----BEGIN AGE ENCRYPTED FILE-----"));
+		assert!(!armored.contains("10.0.0.1"));
+		for identity in &identities {
+			let decrypted = age::decrypt(identity, armored.as_bytes()).unwrap();
+			assert_eq!(decrypted, b"10.0.0.1 tcp_connect 22 open");
+		}
+
+		let stranger = age::x25519::Identity::generate();
+		assert!(age::decrypt(&stranger, armored.as_bytes()).is_err());
+	}
+
+	#[test]
+	fn test_parse_recipient() {
+		let key = age::x25519::Identity::generate().to_public().to_string();
+		assert_eq!(key.parse::<Recipient>().unwrap().to_string(), key);
+		assert!("age1notakey".parse::<Recipient>().unwrap_err().contains("invalid age recipient"));
+		assert!("ssh-ed25519 AAAA".parse::<Recipient>().is_err());
+	}
+}
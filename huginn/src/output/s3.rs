@@ -0,0 +1,61 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! S3 report upload
+//!
+//! Uploads formatted reports to S3-compatible object storage. Credentials and region come from
+//! the standard AWS environment variables (including web identity and container credentials) or,
+//! failing that, from the profile named by `AWS_PROFILE` in the shared AWS config and credentials
+//! files, read with the AWS SDK's own profile loader.
+
+use std::error::Error;
+
+/// Upload a report to `s3://bucket/key`
+#[cfg(feature = "object-storage")]
+pub async fn upload(bucket: &str, key: &str, body: Vec<u8>) -> Result<(), Box<dyn Error>> {
+	use object_store::{ObjectStore, aws::AmazonS3Builder, path::Path};
+
+	let mut builder = AmazonS3Builder::from_env().with_bucket_name(bucket);
+	if std::env::var_os("AWS_ACCESS_KEY_ID").is_none() {
+		builder = shared_profile(builder).await?;
+	}
+
+	let store = builder.build()?;
+	store.put(&Path::from(key), body.into()).await?;
+	Ok(())
+}
+
+/// Upload a report to `s3://bucket/key`
+#[cfg(not(feature = "object-storage"))]
+pub async fn upload(bucket: &str, key: &str, _body: Vec<u8>) -> Result<(), Box<dyn Error>> {
+	Err(format!("Cannot upload to s3://{}/{}: this build lacks the object-storage feature", bucket, key).into())
+}
+
+/// Apply the credentials and region of the active profile in the shared AWS config and
+/// credentials files, as the AWS SDKs read them, when it has any
+#[cfg(feature = "object-storage")]
+async fn shared_profile(
+	builder: object_store::aws::AmazonS3Builder,
+) -> Result<object_store::aws::AmazonS3Builder, Box<dyn Error>> {
+	use aws_config::meta::region::ProvideRegion;
+	use aws_config::profile::{ProfileFileCredentialsProvider, ProfileFileRegionProvider};
+	use aws_credential_types::provider::ProvideCredentials;
+	use aws_credential_types::provider::error::CredentialsError;
+
+	let credentials = match ProfileFileCredentialsProvider::builder().build().provide_credentials().await {
+		Ok(credentials) => credentials,
+		// No shared files, or no profile in them with credentials
+		Err(CredentialsError::CredentialsNotLoaded(_)) => return Ok(builder),
+		Err(e) => return Err(format!("Failed to load the AWS profile: {}", e).into()),
+	};
+	let mut builder = builder
+		.with_access_key_id(credentials.access_key_id())
+		.with_secret_access_key(credentials.secret_access_key());
+	if let Some(token) = credentials.session_token() {
+		builder = builder.with_token(token);
+	}
+	if let Some(region) = ProfileFileRegionProvider::new().region().await {
+		builder = builder.with_region(region.as_ref());
+	}
+	Ok(builder)
+}
@@ -0,0 +1,130 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Report signing for Huginn
+//!
+//! When `signing_key` names a file holding a hex-encoded Ed25519 secret key, every report written
+//! to a file or S3 object gets a detached signature next to it, in a `.sig` file holding the
+//! hex-encoded signature over the exact bytes written. `huginn verify` checks a report against
+//! its signature and a set of trusted public keys.
+
+use crate::config::Config;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Load a hex-encoded Ed25519 secret key from a file
+pub fn load_key(path: &Path) -> Result<SigningKey, Box<dyn Error>> {
+	let text =
+		std::fs::read_to_string(path).map_err(|e| format!("Failed to read signing key {}: {}", path.display(), e))?;
+	let bytes: [u8; 32] = hex::decode(text.trim())?
+		.try_into()
+		.map_err(|_| "Ed25519 secret keys must be 32 bytes")?;
+	Ok(SigningKey::from_bytes(&bytes))
+}
+
+/// Parse a hex-encoded Ed25519 public key
+pub fn parse_key(key: &str) -> Result<VerifyingKey, Box<dyn Error>> {
+	let bytes: [u8; 32] = hex::decode(key.trim())?
+		.try_into()
+		.map_err(|_| "Ed25519 public keys must be 32 bytes")?;
+	Ok(VerifyingKey::from_bytes(&bytes)?)
+}
+
+/// Hex-encoded signature over a report, as written to its `.sig` file
+pub fn sign(key: &SigningKey, report: &[u8]) -> String {
+	format!("{}\n", hex::encode(key.sign(report).to_bytes()))
+}
+
+/// Path of the detached signature for a report
+pub fn signature_path(path: &Path) -> PathBuf {
+	let mut name = path.as_os_str().to_owned();
+	name.push(".sig");
+	PathBuf::from(name)
+}
+
+/// Find the trusted key that made a signature over a report
+pub fn verify(keys: &[VerifyingKey], report: &[u8], signature: &str) -> Result<VerifyingKey, Box<dyn Error>> {
+	let signature = Signature::from_slice(&hex::decode(signature.trim())?)?;
+	keys.iter()
+		.find(|key| key.verify(report, &signature).is_ok())
+		.copied()
+		.ok_or_else(|| "No trusted key verifies the signature; the report was modified or signed by another key".into())
+}
+
+/// Keys trusted by `huginn verify`: those given on the command line, or else `report_keys`, or
+/// else the public half of `signing_key`
+fn trusted_keys(config: Option<&Config>, keys: &[String]) -> Result<Vec<VerifyingKey>, Box<dyn Error>> {
+	if !keys.is_empty() {
+		return keys.iter().map(|key| parse_key(key)).collect();
+	}
+	let Some(config) = config else {
+		return Ok(Vec::new());
+	};
+	if !config.report_keys.is_empty() {
+		return config.report_keys.iter().map(|key| parse_key(key)).collect();
+	}
+	match &config.signing_key {
+		Some(path) => Ok(vec![load_key(path)?.verifying_key()]),
+		None => Ok(Vec::new()),
+	}
+}
+
+/// Run the `verify` subcommand, printing the key that signed the report
+pub fn run(config: Option<&Config>, file: &Path, signature: Option<&Path>, keys: &[String]) -> Result<(), Box<dyn Error>> {
+	let keys = trusted_keys(config, keys)?;
+	if keys.is_empty() {
+		return Err("No trusted keys; pass --key or set report_keys".into());
+	}
+	let signature_file = signature.map(Path::to_path_buf).unwrap_or_else(|| signature_path(file));
+	let report = std::fs::read(file).map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+	let signature = std::fs::read_to_string(&signature_file)
+		.map_err(|e| format!("Failed to read signature {}: {}", signature_file.display(), e))?;
+
+	let key = verify(&keys, &report, &signature)?;
+	println!("Good signature on {} from {}", file.display(), hex::encode(key.as_bytes()));
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_sign_and_verify() {
+		let key = SigningKey::from_bytes(&[7; 32]);
+		let other = SigningKey::from_bytes(&[9; 32]);
+		let signature = sign(&key, b"report");
+
+		let trusted = [other.verifying_key(), key.verifying_key()];
+		assert_eq!(verify(&trusted, b"report", &signature).unwrap(), key.verifying_key());
+		assert!(verify(&trusted, b"tampered", &signature).is_err());
+		assert!(verify(&[other.verifying_key()], b"report", &signature).is_err());
+		assert!(verify(&trusted, b"report", "zz").is_err());
+	}
+
+	#[test]
+	fn test_run() {
+		let dir = std::env::temp_dir().join(format!("huginn-sign-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let key_file = dir.join("report.key");
+		std::fs::write(&key_file, format!("{}\n", hex::encode([7; 32]))).unwrap();
+		let key = load_key(&key_file).unwrap();
+		let report = dir.join("results.json");
+		std::fs::write(&report, b"[]").unwrap();
+		std::fs::write(signature_path(&report), sign(&key, b"[]")).unwrap();
+		assert_eq!(signature_path(&report), dir.join("results.json.sig"));
+
+		let config = Config {
+			signing_key: Some(key_file),
+			..Config::default()
+		};
+		assert!(run(Some(&config), &report, None, &[]).is_ok());
+		assert!(run(None, &report, None, &[hex::encode(key.verifying_key().as_bytes())]).is_ok());
+		assert!(run(None, &report, None, &[]).is_err());
+
+		std::fs::write(&report, b"[{}]").unwrap();
+		assert!(run(Some(&config), &report, None, &[]).is_err());
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+}
@@ -0,0 +1,228 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Scan planning for Huginn
+//!
+//! A plan lists exactly what a scan would do, without sending a single packet: the expanded
+//! hosts, the probes each scan type sends, a worst-case duration, and the privileges needed.
+//! `huginn scan --dry-run` prints it for change-approval reviews.
+
+use crate::config::Config;
+use crate::privileges;
+use huginn_core::plugins::{Plugin, ScanType};
+use std::fmt;
+use std::time::Duration;
+
+/// What one scan type would do
+#[derive(Debug)]
+pub struct Step {
+	/// Scan type
+	pub scan_type: ScanType,
+	/// Probes sent to each host
+	pub probes_per_host: usize,
+	/// Probes sent to all hosts
+	pub probes: usize,
+	/// Time taken if every probe and retry times out
+	pub longest: Duration,
+	/// Why the scan type would not run, if it would not
+	pub skipped: Option<&'static str>,
+	/// Whether the scan type needs raw-socket access
+	raw: bool,
+}
+
+/// The complete probe plan for a scan
+#[derive(Debug)]
+pub struct Plan {
+	/// Hosts that would be probed, after expansion, scope, and exclusions
+	pub hosts: Vec<String>,
+	/// One step per configured scan type
+	pub steps: Vec<Step>,
+	/// Proxy that TCP probes would be tunneled through
+	proxy: Option<String>,
+	/// Host discovery methods tried before port scans
+	discovery: Vec<String>,
+	/// Those of the discovery methods that need raw sockets
+	raw_discovery: Vec<String>,
+}
+
+impl Plan {
+	/// Plan a scan of the given hosts with the available plugins
+	pub fn new(config: &Config, hosts: Vec<String>, plugins: &[Box<dyn Plugin>]) -> Self {
+		let scanner = config.scanner();
+		let steps = config
+			.scan_types
+			.iter()
+			.map(|&scan_type| {
+				let plugin = plugins.iter().find(|p| p.scan_type() == scan_type);
+				let skipped = match plugin {
+					None => Some("not available in this build"),
+					Some(plugin) if config.proxy.is_some() && !plugin.proxy_capable() => {
+						Some("cannot be sent through a proxy")
+					},
+					Some(_) => None,
+				};
+				let (probes_per_host, longest) = match plugin {
+					Some(plugin) if skipped.is_none() => {
+						let context = scanner.context(scan_type);
+						let per_host = plugin.probes_per_target(&context);
+						let per_probe = (context.scan_delay + context.timeout) * (context.retries + 1);
+						(per_host, per_probe * (per_host * hosts.len()) as u32)
+					},
+					_ => (0, Duration::ZERO),
+				};
+				Step {
+					scan_type,
+					probes_per_host,
+					probes: probes_per_host * hosts.len(),
+					longest,
+					skipped,
+					raw: privileges::needs_raw_access(config, scan_type),
+				}
+			})
+			.collect();
+
+		Self {
+			hosts,
+			steps,
+			proxy: config.proxy.as_ref().map(ToString::to_string),
+			discovery: discovery_methods(config),
+			raw_discovery: privileges::raw_discovery_methods(config).iter().map(ToString::to_string).collect(),
+		}
+	}
+
+	/// Probes sent in total
+	pub fn probes(&self) -> usize {
+		self.steps.iter().map(|step| step.probes).sum()
+	}
+
+	/// Time taken if every probe and retry times out
+	pub fn longest(&self) -> Duration {
+		self.steps.iter().map(|step| step.longest).sum()
+	}
+
+	/// Scan types that would run, and discovery methods, that need raw sockets
+	fn raw_needs(&self) -> Vec<String> {
+		let scans = self.steps.iter().filter(|step| step.skipped.is_none() && step.raw);
+		scans
+			.map(|step| step.scan_type.to_string())
+			.chain(self.raw_discovery.iter().map(|method| format!("{} discovery", method)))
+			.collect()
+	}
+}
+
+/// Host discovery methods the configuration enables; discovery never runs through a proxy
+#[cfg(feature = "core-scan")]
+fn discovery_methods(config: &Config) -> Vec<String> {
+	if !config.discovery || config.proxy.is_some() {
+		return Vec::new();
+	}
+	config.discovery_methods.iter().map(ToString::to_string).collect()
+}
+
+/// Host discovery needs the built-in scans
+#[cfg(not(feature = "core-scan"))]
+fn discovery_methods(_config: &Config) -> Vec<String> {
+	Vec::new()
+}
+
+/// Format a duration to whole seconds
+fn seconds(duration: Duration) -> humantime::FormattedDuration {
+	humantime::format_duration(Duration::from_secs(duration.as_secs()))
+}
+
+impl fmt::Display for Plan {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "Scan plan (dry run; nothing is sent)")?;
+		writeln!(f, "  Hosts: {}", self.hosts.len())?;
+		for step in &self.steps {
+			match step.skipped {
+				Some(reason) => writeln!(f, "  {}: skipped, {}", step.scan_type, reason)?,
+				None => writeln!(
+					f,
+					"  {}: {} probes per host, {} probes, up to {}",
+					step.scan_type,
+					step.probes_per_host,
+					step.probes,
+					seconds(step.longest)
+				)?,
+			}
+		}
+		writeln!(f, "  Total: {} probes, up to {} if every probe times out", self.probes(), seconds(self.longest()))?;
+		if let Some(proxy) = &self.proxy {
+			writeln!(f, "  Proxy: {}", proxy)?;
+		}
+		if !self.discovery.is_empty() {
+			writeln!(
+				f,
+				"  Discovery: {} per host first; port scans skip hosts found down",
+				self.discovery.join(", ")
+			)?;
+		}
+
+		let raw = self.raw_needs();
+		if raw.is_empty() {
+			writeln!(f, "  Privileges: none required")?;
+		} else {
+			let status = if privileges::raw_sockets_available() { "available" } else { "missing" };
+			writeln!(f, "  Privileges: raw sockets for {} ({})", raw.join(", "), status)?;
+		}
+
+		writeln!(f, "Hosts:")?;
+		for host in &self.hosts {
+			writeln!(f, "  {}", host)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(all(test, feature = "core-scan"))]
+mod tests {
+	use super::*;
+	use crate::catalog;
+
+	#[test]
+	fn test_plan() {
+		let config = Config {
+			scan_types: vec![ScanType::Ping, ScanType::TcpConnect],
+			ports: Some("22,80".parse().unwrap()),
+			timeout: Duration::from_secs(2),
+			retries: 1,
+			..Config::default()
+		};
+		let hosts = vec!["192.0.2.1".to_string(), "192.0.2.2".to_string(), "192.0.2.3".to_string()];
+		let plan = Plan::new(&config, hosts, &catalog::builtin(&config));
+
+		assert_eq!(plan.steps[0].probes, 3);
+		assert_eq!(plan.steps[1].probes_per_host, 2);
+		assert_eq!(plan.probes(), 9);
+		assert_eq!(plan.longest(), Duration::from_secs(9 * 2 * 2));
+
+		let text = plan.to_string();
+		assert!(text.contains("tcp_connect: 2 probes per host, 6 probes, up to 24s"), "{}", text);
+		if !privileges::icmp_datagram_available() {
+			assert!(text.contains("raw sockets for ping"), "{}", text);
+		}
+		assert!(text.ends_with("  192.0.2.3\n"), "{}", text);
+
+		let proxied = Config {
+			proxy: Some("socks5://127.0.0.1:9050".parse().unwrap()),
+			..config
+		};
+		let plan = Plan::new(&proxied, vec!["192.0.2.1".to_string()], &catalog::builtin(&proxied));
+		assert_eq!(plan.steps[0].skipped, Some("cannot be sent through a proxy"));
+		assert_eq!(plan.probes(), 2);
+		assert!(plan.to_string().contains("Privileges: none required"));
+
+		#[cfg(feature = "core-scan")]
+		{
+			let discovering = Config {
+				proxy: None,
+				discovery: true,
+				discovery_methods: vec![huginn_core::discovery::Method::Ack],
+				..proxied
+			};
+			let plan = Plan::new(&discovering, vec!["192.0.2.1".to_string()], &catalog::builtin(&discovering));
+			assert!(plan.to_string().contains("ack discovery"), "{}", plan);
+		}
+	}
+}
@@ -1,34 +0,0 @@
-// Copyright (c) 2025 JEleniel
-// Licensed under the Apache License, Version 2.0 or the MIT License
-
-//! Ping scan plugin
-//!
-//! Checks which hosts are up by sending ICMP echo requests.
-
-use crate::plugins::{Plugin, ScanResult};
-use async_trait::async_trait;
-use std::error::Error;
-
-/// Ping scan plugin
-pub struct PingScanPlugin;
-
-#[async_trait]
-impl Plugin for PingScanPlugin {
-	fn name(&self) -> String {
-		"Ping Scanner".to_string()
-	}
-
-	fn scan_type(&self) -> String {
-		"ping".to_string()
-	}
-
-	async fn scan(&self, target: &str) -> Result<Vec<ScanResult>, Box<dyn Error>> {
-		// Placeholder implementation
-		Ok(vec![ScanResult {
-			target: target.to_string(),
-			scan_type: self.scan_type(),
-			status: "not_implemented".to_string(),
-			details: Some("Ping scan not yet implemented".to_string()),
-		}])
-	}
-}
@@ -1,34 +0,0 @@
-// Copyright (c) 2025 JEleniel
-// Licensed under the Apache License, Version 2.0 or the MIT License
-
-//! TCP Connect scan plugin
-//!
-//! Establishes a full TCP connection to determine port status.
-
-use crate::plugins::{Plugin, ScanResult};
-use async_trait::async_trait;
-use std::error::Error;
-
-/// TCP Connect scan plugin
-pub struct TcpConnectScanPlugin;
-
-#[async_trait]
-impl Plugin for TcpConnectScanPlugin {
-	fn name(&self) -> String {
-		"TCP Connect Scanner".to_string()
-	}
-
-	fn scan_type(&self) -> String {
-		"tcp_connect".to_string()
-	}
-
-	async fn scan(&self, target: &str) -> Result<Vec<ScanResult>, Box<dyn Error>> {
-		// Placeholder implementation
-		Ok(vec![ScanResult {
-			target: target.to_string(),
-			scan_type: self.scan_type(),
-			status: "not_implemented".to_string(),
-			details: Some("TCP Connect scan not yet implemented".to_string()),
-		}])
-	}
-}
@@ -1,34 +0,0 @@
-// Copyright (c) 2025 JEleniel
-// Licensed under the Apache License, Version 2.0 or the MIT License
-
-//! TCP SYN scan plugin
-//!
-//! A stealth scan that sends SYN packets to check if ports are open, closed, or filtered.
-
-use crate::plugins::{Plugin, ScanResult};
-use async_trait::async_trait;
-use std::error::Error;
-
-/// TCP SYN scan plugin
-pub struct TcpSynScanPlugin;
-
-#[async_trait]
-impl Plugin for TcpSynScanPlugin {
-	fn name(&self) -> String {
-		"TCP SYN Scanner".to_string()
-	}
-
-	fn scan_type(&self) -> String {
-		"tcp_syn".to_string()
-	}
-
-	async fn scan(&self, target: &str) -> Result<Vec<ScanResult>, Box<dyn Error>> {
-		// Placeholder implementation
-		Ok(vec![ScanResult {
-			target: target.to_string(),
-			scan_type: self.scan_type(),
-			status: "not_implemented".to_string(),
-			details: Some("TCP SYN scan not yet implemented".to_string()),
-		}])
-	}
-}
@@ -0,0 +1,307 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Privilege management for Huginn
+//!
+//! Raw-socket scans (ping, TCP SYN, idle) and passive scans that listen on an interface need root
+//! or CAP_NET_RAW on Unix, or Npcap on Windows; ping can do without when unprivileged ICMP
+//! datagram sockets are allowed, and passive scans that read a saved capture need none. This module
+//! checks for that access before scanning, so a run that lacks it fails with guidance instead of
+//! one error per probe, and switches a process started as root to an unprivileged user once its
+//! privileged setup is done.
+
+use crate::catalog;
+use crate::config::Config;
+#[cfg(feature = "core-scan")]
+use huginn_core::discovery::Method;
+use huginn_core::plugins::ScanType;
+#[cfg(unix)]
+use log::info;
+use log::warn;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::error::Error;
+
+/// Whether a scan type sends its probes over raw sockets
+///
+/// Ping only does when unprivileged ICMP datagram sockets are not allowed.
+pub fn needs_raw_sockets(scan_type: ScanType) -> bool {
+	match scan_type {
+		ScanType::Ping => !icmp_datagram_available(),
+		ScanType::TcpSyn | ScanType::Idle => true,
+		_ => false,
+	}
+}
+
+/// Whether a scan type needs raw-socket access under a configuration
+///
+/// Passive scans send nothing, but need the same access to listen on an interface; reading a
+/// saved capture needs none.
+#[cfg_attr(not(feature = "passive"), allow(unused_variables))]
+pub fn needs_raw_access(config: &Config, scan_type: ScanType) -> bool {
+	#[cfg(feature = "passive")]
+	if scan_type == ScanType::Passive {
+		return config.capture_file.is_none();
+	}
+	needs_raw_sockets(scan_type)
+}
+
+/// Whether this process can open an unprivileged ICMP datagram socket
+///
+/// On Linux this depends on the process's group being in `net.ipv4.ping_group_range`.
+pub fn icmp_datagram_available() -> bool {
+	Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::ICMPV4)).is_ok()
+}
+
+/// Configured scan types, available in this build, that need raw sockets
+fn raw_scan_types(config: &Config) -> Vec<ScanType> {
+	let available: Vec<ScanType> = catalog::builtin(config).iter().map(|p| p.scan_type()).collect();
+	config
+		.scan_types
+		.iter()
+		.copied()
+		.filter(|t| needs_raw_access(config, *t) && available.contains(t))
+		.collect()
+}
+
+/// Whether a host discovery method needs raw sockets
+///
+/// Echo only does when unprivileged ICMP datagram sockets are not allowed.
+#[cfg(feature = "core-scan")]
+pub fn discovery_needs_raw_sockets(method: Method) -> bool {
+	match method {
+		Method::Echo => !icmp_datagram_available(),
+		method => method.needs_raw_sockets(),
+	}
+}
+
+/// Configured host discovery methods that need raw sockets; discovery never runs through a proxy
+#[cfg(feature = "core-scan")]
+pub fn raw_discovery_methods(config: &Config) -> Vec<Method> {
+	if !config.discovery || config.proxy.is_some() {
+		return Vec::new();
+	}
+	config.discovery_methods.iter().copied().filter(|method| discovery_needs_raw_sockets(*method)).collect()
+}
+
+/// Host discovery needs the built-in scans
+#[cfg(not(feature = "core-scan"))]
+pub fn raw_discovery_methods(_config: &Config) -> Vec<String> {
+	Vec::new()
+}
+
+/// What needs raw sockets under a configuration, such as `tcp_syn scans and ack discovery`, or
+/// `None` when nothing does
+fn raw_needs(config: &Config) -> Option<String> {
+	let names = |names: Vec<String>| (!names.is_empty()).then(|| names.join(", "));
+	let scans = names(raw_scan_types(config).iter().map(ToString::to_string).collect());
+	let discovery = names(raw_discovery_methods(config).iter().map(ToString::to_string).collect());
+	match (scans, discovery) {
+		(Some(scans), Some(discovery)) => Some(format!("{} scans and {} discovery", scans, discovery)),
+		(Some(scans), None) => Some(format!("{} scans", scans)),
+		(None, Some(discovery)) => Some(format!("{} discovery", discovery)),
+		(None, None) => None,
+	}
+}
+
+/// Whether this process can open raw sockets
+#[cfg(target_os = "linux")]
+pub fn raw_sockets_available() -> bool {
+	use caps::{CapSet, Capability};
+
+	nix::unistd::geteuid().is_root() || caps::has_cap(None, CapSet::Effective, Capability::CAP_NET_RAW).unwrap_or(false)
+}
+
+/// Whether this process can open raw sockets
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn raw_sockets_available() -> bool {
+	nix::unistd::geteuid().is_root()
+}
+
+/// Whether this process can open raw sockets
+#[cfg(not(unix))]
+pub fn raw_sockets_available() -> bool {
+	std::env::var_os("SystemRoot")
+		.map(|root| std::path::Path::new(&root).join("System32").join("Npcap").is_dir())
+		.unwrap_or(false)
+}
+
+/// How to gain raw-socket access on this platform
+fn guidance() -> String {
+	if cfg!(target_os = "linux") {
+		let exe = std::env::current_exe()
+			.map(|path| path.display().to_string())
+			.unwrap_or_else(|_| "huginn".to_string());
+		format!("run as root or grant the capability with `sudo setcap cap_net_raw+ep {}`", exe)
+	} else if cfg!(unix) {
+		"run as root".to_string()
+	} else {
+		"install Npcap from https://npcap.com and run from an elevated prompt".to_string()
+	}
+}
+
+/// Fail early when configured scan types or discovery methods need raw sockets this process cannot
+/// open
+pub fn check(config: &Config) -> Result<(), Box<dyn Error>> {
+	require(config, raw_sockets_available())
+}
+
+/// Fail when anything configured needs raw sockets and they are not `available`
+fn require(config: &Config, available: bool) -> Result<(), Box<dyn Error>> {
+	let Some(raw) = raw_needs(config).filter(|_| !available) else {
+		return Ok(());
+	};
+	#[cfg(feature = "core-scan")]
+	let echo = raw_discovery_methods(config).contains(&Method::Echo);
+	#[cfg(not(feature = "core-scan"))]
+	let echo = false;
+	let ping = if cfg!(target_os = "linux") && (raw_scan_types(config).contains(&ScanType::Ping) || echo) {
+		"; ping also works without either once `sysctl net.ipv4.ping_group_range` includes your group"
+	} else {
+		""
+	};
+	Err(format!("{} need raw sockets, which this process cannot open; {}{}", raw, guidance(), ping).into())
+}
+
+/// Switch a process started as root to the configured unprivileged user
+///
+/// Call once privileged setup, such as binding a low port, is done. Raw-socket plugins open a
+/// socket for each probe, so while any raw-socket scan type or discovery method is configured,
+/// CAP_NET_RAW is kept across the switch on Linux, and root is kept on other platforms.
+#[cfg(unix)]
+pub fn drop_root(config: &Config) -> Result<(), Box<dyn Error>> {
+	use nix::unistd::{Uid, User, geteuid, setgid, setuid};
+
+	if !geteuid().is_root() {
+		return Ok(());
+	}
+	let Some(name) = &config.user else {
+		warn!("Running as root; set `user` to switch to an unprivileged user after startup");
+		return Ok(());
+	};
+	let raw = raw_needs(config);
+	let keep_net_raw = raw.is_some();
+	if let Some(raw) = raw.filter(|_| !cfg!(target_os = "linux")) {
+		warn!("Keeping root privileges: {} need raw sockets", raw);
+		return Ok(());
+	}
+
+	let user = User::from_name(name)?.ok_or_else(|| format!("user {} does not exist", name))?;
+	#[cfg(target_os = "linux")]
+	if keep_net_raw {
+		caps::securebits::set_keepcaps(true)?;
+	}
+	#[cfg(not(target_vendor = "apple"))]
+	nix::unistd::setgroups(&[user.gid])?;
+	setgid(user.gid)?;
+	setuid(user.uid)?;
+	#[cfg(target_os = "linux")]
+	if keep_net_raw {
+		retain_net_raw()?;
+	}
+
+	// Make sure root cannot be regained
+	if !user.uid.is_root() && setuid(Uid::from_raw(0)).is_ok() {
+		return Err(format!("failed to drop privileges to user {}", name).into());
+	}
+	if keep_net_raw {
+		info!("Dropped privileges to user {}, keeping CAP_NET_RAW for raw-socket probes", name);
+	} else {
+		info!("Dropped privileges to user {}", name);
+	}
+	Ok(())
+}
+
+/// Narrow the capabilities kept across the switch to another user down to CAP_NET_RAW
+#[cfg(target_os = "linux")]
+fn retain_net_raw() -> Result<(), Box<dyn Error>> {
+	use caps::{CapSet, Capability, CapsHashSet};
+
+	let net_raw: CapsHashSet = [Capability::CAP_NET_RAW].into_iter().collect();
+	caps::set(None, CapSet::Permitted, &net_raw)?;
+	caps::set(None, CapSet::Effective, &net_raw)?;
+	caps::clear(None, CapSet::Inheritable)?;
+	caps::securebits::set_keepcaps(false)?;
+	Ok(())
+}
+
+/// Switch a process started as root to the configured unprivileged user
+#[cfg(not(unix))]
+pub fn drop_root(config: &Config) -> Result<(), Box<dyn Error>> {
+	if config.user.is_some() {
+		warn!("Ignoring `user`: switching users is only supported on Unix");
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_raw_socket_scan_types() {
+		assert_eq!(needs_raw_sockets(ScanType::Ping), !icmp_datagram_available());
+		assert!(needs_raw_sockets(ScanType::TcpSyn));
+		assert!(!needs_raw_sockets(ScanType::Udp));
+		assert!(!needs_raw_sockets(ScanType::TcpConnect));
+		assert!(!needs_raw_sockets(ScanType::Passive));
+	}
+
+	#[cfg(feature = "passive")]
+	#[test]
+	fn test_passive_access() {
+		let mut config = Config::default();
+		assert!(needs_raw_access(&config, ScanType::Passive));
+		config.capture_file = Some("traffic.pcap".into());
+		assert!(!needs_raw_access(&config, ScanType::Passive));
+		assert!(needs_raw_access(&config, ScanType::TcpSyn));
+	}
+
+	#[test]
+	fn test_check() {
+		let config = Config {
+			scan_types: vec![ScanType::TcpConnect],
+			..Config::default()
+		};
+		assert!(check(&config).is_ok());
+
+		let config = Config {
+			scan_types: vec![ScanType::TcpSyn, ScanType::Idle],
+			..Config::default()
+		};
+		match check(&config) {
+			Ok(()) => assert!(raw_sockets_available() || cfg!(not(feature = "core-scan"))),
+			Err(e) => assert!(e.to_string().contains("tcp_syn, idle scans need raw sockets"), "{}", e),
+		}
+
+		let config = Config {
+			scan_types: vec![ScanType::Ping],
+			..Config::default()
+		};
+		if icmp_datagram_available() {
+			assert!(check(&config).is_ok());
+		}
+	}
+
+	#[cfg(feature = "core-scan")]
+	#[test]
+	fn test_check_discovery() {
+		let mut config = Config {
+			scan_types: vec![ScanType::TcpConnect],
+			discovery: true,
+			discovery_methods: vec![Method::Syn, Method::Ack, Method::Timestamp],
+			..Config::default()
+		};
+		let error = require(&config, false).unwrap_err();
+		assert!(error.to_string().starts_with("ack, timestamp discovery need raw sockets"), "{}", error);
+		assert!(require(&config, true).is_ok());
+
+		config.scan_types = vec![ScanType::TcpSyn];
+		let error = require(&config, false).unwrap_err();
+		assert!(error.to_string().starts_with("tcp_syn scans and ack, timestamp discovery need"), "{}", error);
+
+		// Discovery does not run without being enabled, or through a proxy
+		config.scan_types = vec![ScanType::TcpConnect];
+		config.discovery = false;
+		assert!(require(&config, false).is_ok());
+	}
+}
@@ -0,0 +1,110 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Reverse DNS verification for Huginn
+//!
+//! When `ptr_suffixes` is set, every address target is reverse-resolved before any probe is sent
+//! and its PTR names are compared against the expected domain suffixes. Mismatches, which are
+//! often mistyped addresses, are warned about, or abort the run under `--strict-scope`. Runs of
+//! passive scans only, which send nothing, are not checked.
+
+use crate::audit;
+use crate::config::Config;
+use hickory_resolver::TokioResolver;
+use huginn_core::plugins::ScanType;
+use huginn_core::targets;
+use log::{info, warn};
+use std::error::Error;
+use std::net::IpAddr;
+use tokio::task::JoinSet;
+
+/// Reverse lookups in flight at once
+const CONCURRENCY: usize = 32;
+
+/// Whether any PTR name falls under one of the expected suffixes
+fn matches_suffix(names: &[String], suffixes: &[String]) -> bool {
+	names
+		.iter()
+		.any(|name| suffixes.iter().any(|suffix| targets::matches(suffix, name)))
+}
+
+/// Reverse-resolve an address, treating lookup failures as having no PTR names
+async fn ptr_names(resolver: TokioResolver, address: IpAddr) -> (IpAddr, Vec<String>) {
+	let names = match resolver.reverse_lookup(address).await {
+		Ok(lookup) => lookup.iter().map(|ptr| ptr.to_string()).collect(),
+		Err(_) => Vec::new(),
+	};
+	(address, names)
+}
+
+/// Check the PTR names of every address target against the expected suffixes
+///
+/// Host name targets are not checked. Each mismatch is warned about and recorded in the audit
+/// log; with `strict_scope` set, any mismatch is an error.
+pub async fn verify(config: &Config, hosts: &[String]) -> Result<(), Box<dyn Error>> {
+	if config.ptr_suffixes.is_empty() {
+		return Ok(());
+	}
+	if config.scan_types.iter().all(|&scan_type| scan_type == ScanType::Passive) {
+		info!("Skipping reverse DNS verification: passive scans send nothing");
+		return Ok(());
+	}
+	let addresses: Vec<IpAddr> = hosts.iter().filter_map(|host| host.parse().ok()).collect();
+	info!("Verifying reverse DNS of {} addresses", addresses.len());
+	let resolver = TokioResolver::builder_tokio()?.build();
+
+	let mut mismatches = Vec::new();
+	for chunk in addresses.chunks(CONCURRENCY) {
+		let mut lookups = JoinSet::new();
+		for &address in chunk {
+			lookups.spawn(ptr_names(resolver.clone(), address));
+		}
+		while let Some(lookup) = lookups.join_next().await {
+			let (address, names) = lookup?;
+			if matches_suffix(&names, &config.ptr_suffixes) {
+				continue;
+			}
+			let reason = if names.is_empty() {
+				"no PTR record".to_string()
+			} else {
+				format!("PTR {} does not match the expected suffixes", names.join(", "))
+			};
+			warn!("Reverse DNS check failed for {}: {}", address, reason);
+			audit::record(config, "ptr_mismatch", &address.to_string(), &reason)?;
+			mismatches.push(address);
+		}
+	}
+
+	if config.strict_scope && !mismatches.is_empty() {
+		return Err(format!(
+			"{} targets failed reverse DNS verification; fix the targets or drop --strict-scope",
+			mismatches.len()
+		)
+		.into());
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_matches_suffix() {
+		let suffixes = vec!["corp.example.com".to_string(), "Example.NET.".to_string()];
+		let names = |names: &[&str]| names.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+		assert!(matches_suffix(&names(&["web01.corp.example.com."]), &suffixes));
+		assert!(matches_suffix(&names(&["cpe-1.isp.test.", "mail.example.net."]), &suffixes));
+		assert!(matches_suffix(&names(&["corp.example.com"]), &suffixes));
+		assert!(!matches_suffix(&names(&["web01.notcorp.example.com."]), &suffixes));
+		assert!(!matches_suffix(&names(&["corp.example.com.evil.test."]), &suffixes));
+		assert!(!matches_suffix(&[], &suffixes));
+	}
+
+	#[tokio::test]
+	async fn test_verify_is_off_without_suffixes() {
+		let hosts = vec!["192.0.2.1".to_string()];
+		assert!(verify(&Config::default(), &hosts).await.is_ok());
+	}
+}
@@ -0,0 +1,107 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Report regeneration for Huginn
+//!
+//! `huginn report` loads results saved by an earlier scan, from a JSON or NDJSON file or a run
+//! stored in `history_dir`, filters them, and writes them to the configured outputs again, so a
+//! different format or subset of an old run never needs a rescan.
+
+use crate::config::{Config, ReportArgs};
+use crate::history;
+use crate::output::OutputDispatcher;
+use huginn_core::formatters::json;
+use huginn_core::plugins::ScanResult;
+use huginn_core::statistics::Statistics;
+use log::info;
+use std::error::Error;
+use std::path::Path;
+
+/// Parse results saved as JSON output, a JSON array, or one JSON object per line
+fn parse(text: &str) -> Result<Vec<ScanResult>, Box<dyn Error>> {
+	// NDJSON lines are objects too, so fall back to them when the text is not one JSON document
+	if text.trim_start().starts_with('[') {
+		return Ok(json::parse(text)?);
+	}
+	if let Ok(results) = json::parse(text) {
+		return Ok(results);
+	}
+	text.lines()
+		.enumerate()
+		.filter(|(_, line)| !line.trim().is_empty())
+		.map(|(n, line)| serde_json::from_str(line).map_err(|e| format!("line {}: {}", n + 1, e).into()))
+		.collect()
+}
+
+/// Load results from a file or, when no such file exists, a stored run
+pub fn load(config: &Config, source: &str) -> Result<Vec<ScanResult>, Box<dyn Error>> {
+	let path = Path::new(source);
+	if path.is_file() {
+		let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", source, e))?;
+		return parse(&text).map_err(|e| format!("Invalid results in {}: {}", source, e).into());
+	}
+	match &config.history_dir {
+		Some(dir) => history::results(dir, source),
+		None => Err(format!("{} is not a results file, and no history_dir is set to look up runs", source).into()),
+	}
+}
+
+/// Whether a result passes the report filters
+fn selected(args: &ReportArgs, result: &ScanResult) -> bool {
+	(args.targets.is_empty() || args.targets.contains(&result.target))
+		&& (args.scan_types.is_empty() || args.scan_types.contains(&result.scan_type))
+		&& (args.statuses.is_empty() || args.statuses.contains(&result.status))
+		&& args
+			.min_severity
+			.is_none_or(|min| result.severity.is_some_and(|severity| severity >= min))
+}
+
+/// Run the `report` subcommand, writing the selected results to every configured output
+pub async fn run(config: &Config, args: &ReportArgs) -> Result<(), Box<dyn Error>> {
+	let dispatcher =
+		OutputDispatcher::from_config(config).map_err(|e| format!("Failed to set up output: {}", e))?;
+	let results: Vec<ScanResult> = load(config, &args.source)?
+		.into_iter()
+		.filter(|result| selected(args, result))
+		.collect();
+	info!("Rendering {} results from {}", results.len(), args.source);
+	dispatcher.write(&results, &Statistics::from_results(&results)).await
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use huginn_core::plugins::Severity;
+	use clap::Parser;
+
+	fn args(flags: &[&str]) -> ReportArgs {
+		#[derive(Parser)]
+		struct Wrapper {
+			#[command(flatten)]
+			args: ReportArgs,
+		}
+		Wrapper::parse_from(["report", "results.json"].iter().chain(flags)).args
+	}
+
+	#[test]
+	fn test_parse() {
+		let array = r#"[{"target":"10.0.0.1","scan_type":"ping","port":null,"status":"up","severity":null,"service":null,"version":null,"details":null}]"#;
+		assert_eq!(parse(array).unwrap().len(), 1);
+		assert_eq!(parse(&format!("{{\"results\": {}, \"statistics\": {{}}}}", array)).unwrap().len(), 1);
+
+		let lines = format!("{}\n\n{}\n", &array[1..array.len() - 1], &array[1..array.len() - 1]);
+		assert_eq!(parse(&lines).unwrap().len(), 2);
+		assert!(parse("{\"target\":1}").unwrap_err().to_string().starts_with("line 1:"));
+	}
+
+	#[test]
+	fn test_selected() {
+		let result = ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22).with_severity(Severity::Medium);
+		assert!(selected(&args(&[]), &result));
+		assert!(selected(&args(&["--target", "10.0.0.1", "--status", "open"]), &result));
+		assert!(selected(&args(&["--min-severity", "low"]), &result));
+		assert!(!selected(&args(&["--min-severity", "high"]), &result));
+		assert!(!selected(&args(&["--scan-type", "ping"]), &result));
+		assert!(!selected(&args(&["--target", "10.0.0.2"]), &result));
+	}
+}
@@ -0,0 +1,446 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Threat-intelligence reputation lookups for Huginn
+//!
+//! After a scan, every address target with results is checked against the local blocklist files
+//! and the reputation feeds configured in the `reputation` section: AbuseIPDB's confidence score
+//! and the number of AlienVault OTX pulses naming the address. Each host's results get its
+//! reputation score, from 0 (nothing known) to 100, and the sources that list it added to their
+//! details. Host names are not looked up, and a feed that cannot be reached is logged and skipped.
+//!
+//! Only globally routable addresses are sent to the feeds, so private, loopback, and link-local
+//! addresses, which would reveal the internal network to a third party, are only checked against
+//! the blocklists. Feed lookups run [`MAX_LOOKUPS`] at a time, spaced out to each feed's
+//! configured rate; a feed that answers that its quota is spent is skipped for the rest of the run.
+
+use huginn_core::normalize;
+use huginn_core::plugins::ScanResult;
+use huginn_core::targets::Network;
+use log::{debug, info, warn};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore};
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+/// Score that each OTX pulse naming an address adds, up to 100
+const OTX_PULSE_SCORE: u64 = 20;
+
+/// Most addresses looked up in the feeds at once
+pub const MAX_LOOKUPS: usize = 8;
+
+/// Reputation feeds and blocklists that scanned addresses are checked against
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReputationConfig {
+	/// Files listing one address or CIDR block per line; `#` starts a comment
+	pub blocklists: Vec<PathBuf>,
+	/// AbuseIPDB API key
+	pub abuseipdb_key: Option<String>,
+	/// AlienVault OTX API key
+	pub otx_key: Option<String>,
+	/// Most AbuseIPDB lookups per minute
+	pub abuseipdb_rate: u32,
+	/// Most OTX lookups per minute
+	pub otx_rate: u32,
+}
+
+impl Default for ReputationConfig {
+	fn default() -> Self {
+		Self {
+			blocklists: Vec::new(),
+			abuseipdb_key: None,
+			otx_key: None,
+			abuseipdb_rate: 30,
+			otx_rate: 150,
+		}
+	}
+}
+
+/// Whether an address is routed on the public internet, and so may be sent to a third party
+fn is_global(address: IpAddr) -> bool {
+	match address {
+		IpAddr::V4(v4) => {
+			let [a, b, ..] = v4.octets();
+			!(v4.is_private()
+				|| v4.is_loopback()
+				|| v4.is_link_local()
+				|| v4.is_unspecified()
+				|| v4.is_broadcast()
+				|| v4.is_multicast()
+				// Shared address space for carrier-grade NAT (RFC 6598)
+				|| (a == 100 && (64..128).contains(&b)))
+		},
+		IpAddr::V6(v6) => {
+			if let Some(v4) = v6.to_ipv4_mapped() {
+				return is_global(IpAddr::V4(v4));
+			}
+			let first = v6.segments()[0];
+			!(v6.is_loopback()
+				|| v6.is_unspecified()
+				|| v6.is_multicast()
+				|| v6.is_unique_local()
+				|| v6.is_unicast_link_local()
+				// Site-local, deprecated but still seen
+				|| first & 0xffc0 == 0xfec0)
+		},
+	}
+}
+
+/// Spaces out the requests to a feed so they stay within its quota
+struct Limiter {
+	feed: &'static str,
+	/// Time between requests
+	interval: Duration,
+	/// When the next request may be sent
+	next: Mutex<Instant>,
+	/// Set once the feed says its quota is spent
+	exhausted: AtomicBool,
+}
+
+impl Limiter {
+	/// A limiter allowing a number of requests per minute
+	fn per_minute(feed: &'static str, requests: u32) -> Self {
+		Self {
+			feed,
+			interval: Duration::from_secs(60) / requests.max(1),
+			next: Mutex::new(Instant::now()),
+			exhausted: AtomicBool::new(false),
+		}
+	}
+
+	/// Wait for the next request's turn; false once the feed's quota is spent
+	async fn wait(&self) -> bool {
+		let at = {
+			let mut next = self.next.lock().await;
+			let at = (*next).max(Instant::now());
+			*next = at + self.interval;
+			at
+		};
+		tokio::time::sleep_until(at).await;
+		!self.exhausted.load(Ordering::Relaxed)
+	}
+
+	/// Stop using the feed once it answers that its quota is spent
+	fn check(&self, error: &reqwest::Error) {
+		if error.status() == Some(reqwest::StatusCode::TOO_MANY_REQUESTS) && !self.exhausted.swap(true, Ordering::Relaxed) {
+			warn!("{} quota is spent; skipping its lookups for the rest of the run", self.feed);
+		}
+	}
+}
+
+/// A local blocklist file
+struct Blocklist {
+	/// Source name reported for listed addresses: the file name without its extension
+	name: String,
+	networks: Vec<Network>,
+}
+
+impl Blocklist {
+	/// Parse a blocklist, skipping blank lines, comments, and anything after the first word
+	fn parse(name: &str, text: &str) -> Result<Self, Box<dyn Error>> {
+		let mut networks = Vec::new();
+		for (number, line) in text.lines().enumerate() {
+			let Some(entry) = line.split('#').next().and_then(|l| l.split_whitespace().next()) else {
+				continue;
+			};
+			let network = entry
+				.parse()
+				.map_err(|e| format!("Invalid entry in blocklist {} on line {}: {}", name, number + 1, e))?;
+			networks.push(network);
+		}
+		Ok(Self {
+			name: name.to_string(),
+			networks,
+		})
+	}
+
+	/// Load a blocklist file
+	fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+		let text = std::fs::read_to_string(path)
+			.map_err(|e| format!("Failed to read blocklist {}: {}", path.display(), e))?;
+		let name = path.file_stem().unwrap_or(path.as_os_str()).to_string_lossy();
+		Self::parse(&name, &text)
+	}
+}
+
+/// Reputation of one address
+#[derive(Debug, Default, PartialEq)]
+struct Reputation {
+	/// Highest score any source gave, 0 to 100
+	score: u64,
+	/// Sources that list the address
+	sources: Vec<String>,
+}
+
+impl Reputation {
+	/// Record a source's score, listing it when the score is above zero
+	fn add(&mut self, source: &str, score: u64) {
+		let score = score.min(100);
+		if score > 0 {
+			self.score = self.score.max(score);
+			self.sources.push(source.to_string());
+		}
+	}
+
+	/// Text added to the host's result details
+	fn describe(&self) -> String {
+		format!("reputation {} (listed by {})", self.score, self.sources.join(", "))
+	}
+}
+
+/// AbuseIPDB check response
+#[derive(Deserialize)]
+struct AbuseIpDbResponse {
+	data: AbuseIpDbData,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AbuseIpDbData {
+	abuse_confidence_score: u64,
+}
+
+/// OTX general indicator response
+#[derive(Deserialize)]
+struct OtxResponse {
+	pulse_info: OtxPulseInfo,
+}
+
+#[derive(Deserialize)]
+struct OtxPulseInfo {
+	count: u64,
+}
+
+/// The configured reputation feeds, shared by concurrent lookups
+struct Feeds {
+	abuseipdb_key: Option<String>,
+	otx_key: Option<String>,
+	abuseipdb_rate: Limiter,
+	otx_rate: Limiter,
+	client: reqwest::Client,
+}
+
+/// Checks addresses against the configured blocklists and feeds
+pub struct Checker {
+	blocklists: Vec<Blocklist>,
+	feeds: Arc<Feeds>,
+}
+
+impl Checker {
+	/// Load the configured blocklists
+	pub fn new(config: &ReputationConfig) -> Result<Self, Box<dyn Error>> {
+		let blocklists = config
+			.blocklists
+			.iter()
+			.map(|path| Blocklist::load(path))
+			.collect::<Result<Vec<_>, _>>()?;
+		Ok(Self {
+			blocklists,
+			feeds: Arc::new(Feeds::new(config)),
+		})
+	}
+
+	/// The blocklists listing an address
+	fn listed(&self, address: IpAddr) -> Reputation {
+		let mut reputation = Reputation::default();
+		for blocklist in &self.blocklists {
+			if blocklist.networks.iter().any(|network| network.contains(address)) {
+				reputation.add(&blocklist.name, 100);
+			}
+		}
+		reputation
+	}
+
+	/// Add each listed address's reputation to its results
+	pub async fn enrich(&self, results: &mut [ScanResult]) {
+		let mut reputations: BTreeMap<IpAddr, Reputation> = BTreeMap::new();
+		for address in results.iter().filter_map(|r| r.target.parse::<IpAddr>().ok()) {
+			reputations.entry(address).or_insert_with(|| self.listed(address));
+		}
+
+		if self.feeds.abuseipdb_key.is_some() || self.feeds.otx_key.is_some() {
+			let permits = Arc::new(Semaphore::new(MAX_LOOKUPS));
+			let mut lookups = JoinSet::new();
+			for &address in reputations.keys().filter(|address| is_global(**address)) {
+				let (feeds, permits) = (self.feeds.clone(), permits.clone());
+				lookups.spawn(async move {
+					let _permit = permits.acquire_owned().await;
+					(address, feeds.check(address).await)
+				});
+			}
+			while let Some(joined) = lookups.join_next().await {
+				let Ok((address, scores)) = joined else {
+					continue;
+				};
+				if let Some(reputation) = reputations.get_mut(&address) {
+					for (source, score) in scores {
+						reputation.add(source, score);
+					}
+				}
+			}
+		}
+		for (address, reputation) in &reputations {
+			debug!("Reputation of {}: {:?}", address, reputation);
+		}
+
+		let listed: BTreeMap<IpAddr, String> = reputations
+			.into_iter()
+			.filter(|(_, reputation)| reputation.score > 0)
+			.map(|(address, reputation)| (address, reputation.describe()))
+			.collect();
+		if !listed.is_empty() {
+			info!("{} scanned addresses have a poor reputation", listed.len());
+		}
+		for result in results.iter_mut() {
+			if let Some(description) = result.target.parse().ok().and_then(|address| listed.get(&address)) {
+				normalize::add_details(result, description.clone());
+			}
+		}
+	}
+}
+
+impl Feeds {
+	fn new(config: &ReputationConfig) -> Self {
+		Self {
+			abuseipdb_key: config.abuseipdb_key.clone(),
+			otx_key: config.otx_key.clone(),
+			abuseipdb_rate: Limiter::per_minute("AbuseIPDB", config.abuseipdb_rate),
+			otx_rate: Limiter::per_minute("OTX", config.otx_rate),
+			client: reqwest::Client::new(),
+		}
+	}
+
+	/// AbuseIPDB confidence score of an address
+	async fn abuseipdb(&self, key: &str, address: IpAddr) -> Result<u64, reqwest::Error> {
+		let response: AbuseIpDbResponse = self
+			.client
+			.get("https://api.abuseipdb.com/api/v2/check")
+			.query(&[("ipAddress", address.to_string()), ("maxAgeInDays", "90".to_string())])
+			.header("Key", key)
+			.header("Accept", "application/json")
+			.send()
+			.await?
+			.error_for_status()?
+			.json()
+			.await?;
+		Ok(response.data.abuse_confidence_score)
+	}
+
+	/// Score from the number of OTX pulses naming an address
+	async fn otx(&self, key: &str, address: IpAddr) -> Result<u64, reqwest::Error> {
+		let family = if address.is_ipv4() { "IPv4" } else { "IPv6" };
+		let url = format!("https://otx.alienvault.com/api/v1/indicators/{}/{}/general", family, address);
+		let response: OtxResponse =
+			self.client.get(url).header("X-OTX-API-KEY", key).send().await?.error_for_status()?.json().await?;
+		Ok(response.pulse_info.count.saturating_mul(OTX_PULSE_SCORE))
+	}
+
+	/// Look a globally routable address up in every feed, giving each listing source's score
+	async fn check(&self, address: IpAddr) -> Vec<(&'static str, u64)> {
+		let mut scores = Vec::new();
+		if let Some(key) = &self.abuseipdb_key
+			&& self.abuseipdb_rate.wait().await
+		{
+			match self.abuseipdb(key, address).await {
+				Ok(score) => scores.push(("abuseipdb", score)),
+				Err(e) => {
+					self.abuseipdb_rate.check(&e);
+					warn!("AbuseIPDB lookup of {} failed: {}", address, e);
+				},
+			}
+		}
+		if let Some(key) = &self.otx_key
+			&& self.otx_rate.wait().await
+		{
+			match self.otx(key, address).await {
+				Ok(score) => scores.push(("otx", score)),
+				Err(e) => {
+					self.otx_rate.check(&e);
+					warn!("OTX lookup of {} failed: {}", address, e);
+				},
+			}
+		}
+		scores
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse_blocklist() {
+		let blocklist = Blocklist::parse("drop", "# Spamhaus DROP\n\n192.0.2.0/24 ; SBL1\n198.51.100.7 # single\n").unwrap();
+		assert_eq!(blocklist.networks.len(), 2);
+		assert!(Blocklist::parse("bad", "not-an-address\n").is_err());
+	}
+
+	#[test]
+	fn test_parse_responses() {
+		let abuse: AbuseIpDbResponse =
+			serde_json::from_str(r#"{"data": {"ipAddress": "192.0.2.1", "abuseConfidenceScore": 87}}"#).unwrap();
+		assert_eq!(abuse.data.abuse_confidence_score, 87);
+		let otx: OtxResponse = serde_json::from_str(r#"{"indicator": "192.0.2.1", "pulse_info": {"count": 3}}"#).unwrap();
+		assert_eq!(otx.pulse_info.count, 3);
+	}
+
+	#[test]
+	fn test_is_global() {
+		for address in ["8.8.8.8", "2001:4860:4860::8888", "::ffff:8.8.8.8"] {
+			assert!(is_global(address.parse().unwrap()), "{}", address);
+		}
+		let internal = [
+			"10.1.2.3",
+			"172.16.0.1",
+			"192.168.1.1",
+			"127.0.0.1",
+			"169.254.169.254",
+			"100.64.0.1",
+			"0.0.0.0",
+			"::1",
+			"fe80::1",
+			"fd00::1",
+			"::ffff:10.0.0.1",
+		];
+		for address in internal {
+			assert!(!is_global(address.parse().unwrap()), "{}", address);
+		}
+	}
+
+	#[tokio::test]
+	async fn test_limiter_spaces_requests() {
+		let limiter = Limiter::per_minute("test", 1200);
+		let start = Instant::now();
+		for _ in 0..3 {
+			assert!(limiter.wait().await);
+		}
+		assert!(start.elapsed() >= Duration::from_millis(100));
+		limiter.exhausted.store(true, Ordering::Relaxed);
+		assert!(!limiter.wait().await);
+	}
+
+	#[tokio::test]
+	async fn test_enrich() {
+		let checker = Checker {
+			blocklists: vec![Blocklist::parse("drop", "192.0.2.0/24\n").unwrap()],
+			feeds: Arc::new(Feeds::new(&ReputationConfig::default())),
+		};
+		let mut results = vec![
+			ScanResult::new("192.0.2.9", "ping", "up"),
+			ScanResult::new("198.51.100.1", "ping", "up"),
+			ScanResult::new("host.example.com", "ping", "up"),
+		];
+		checker.enrich(&mut results).await;
+		assert_eq!(results[0].details.as_deref(), Some("reputation 100 (listed by drop)"));
+		assert!(results[1].details.is_none());
+		assert!(results[2].details.is_none());
+	}
+}
@@ -0,0 +1,111 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Scope enforcement for Huginn
+//!
+//! The `scope` section lists the networks and domains that are authorized for scanning. Once
+//! it is set, every expanded target outside it is refused and the refusal is written to the
+//! audit log; `--force-out-of-scope` scans such targets anyway, and that is audited too.
+
+use crate::audit;
+use crate::config::Config;
+use huginn_core::targets::{self, Network};
+use log::{error, warn};
+use serde::Deserialize;
+use std::error::Error;
+use std::net::IpAddr;
+
+/// Networks and domains authorized for scanning
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scope {
+	/// Authorized CIDR blocks and addresses
+	pub networks: Vec<Network>,
+	/// Authorized domains; each also authorizes its subdomains
+	pub domains: Vec<String>,
+}
+
+impl Scope {
+	/// Whether a single host, an address or host name, is authorized
+	pub fn allows(&self, host: &str) -> bool {
+		match host.parse::<IpAddr>() {
+			Ok(address) => self.networks.iter().any(|network| network.contains(address)),
+			Err(_) => self.domains.iter().any(|domain| targets::matches(domain, host)),
+		}
+	}
+}
+
+/// Remove the expanded targets that are outside the configured scope
+///
+/// Every refusal, and every target scanned out of scope because of `--force-out-of-scope`, is
+/// recorded in the audit log.
+pub fn enforce(config: &Config, targets: Vec<String>) -> Result<Vec<String>, Box<dyn Error>> {
+	let Some(scope) = &config.scope else {
+		return Ok(targets);
+	};
+
+	let mut allowed = Vec::with_capacity(targets.len());
+	for target in targets {
+		if scope.allows(&target) {
+			allowed.push(target);
+		} else if config.force_out_of_scope {
+			warn!("Scanning {} outside the authorized scope (--force-out-of-scope)", target);
+			audit::record(config, "scope_forced", &target, "outside the authorized scope")?;
+			allowed.push(target);
+		} else {
+			error!("Refusing to scan {}: outside the authorized scope", target);
+			audit::record(config, "scope_refused", &target, "outside the authorized scope")?;
+		}
+	}
+	Ok(allowed)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn scope() -> Scope {
+		Scope {
+			networks: vec!["10.0.0.0/24".parse().unwrap(), "2001:db8::1".parse().unwrap()],
+			domains: vec!["example.com".to_string()],
+		}
+	}
+
+	#[test]
+	fn test_allows() {
+		let scope = scope();
+		assert!(scope.allows("10.0.0.42"));
+		assert!(scope.allows("2001:db8::1"));
+		assert!(scope.allows("example.com"));
+		assert!(scope.allows("WWW.Example.com."));
+		assert!(!scope.allows("10.0.1.1"));
+		assert!(!scope.allows("2001:db8::2"));
+		assert!(!scope.allows("badexample.com"));
+		assert!(!scope.allows("example.com.evil.net"));
+	}
+
+	#[test]
+	fn test_enforce() {
+		let path = std::env::temp_dir().join(format!("huginn-audit-{}.log", std::process::id()));
+		let _ = std::fs::remove_file(&path);
+		let targets = vec!["10.0.0.1".to_string(), "10.9.9.9".to_string(), "mail.example.com".to_string()];
+
+		assert_eq!(enforce(&Config::default(), targets.clone()).unwrap(), targets);
+
+		let mut config = Config {
+			scope: Some(scope()),
+			audit_log: Some(path.clone()),
+			..Config::default()
+		};
+		assert_eq!(enforce(&config, targets.clone()).unwrap(), vec!["10.0.0.1", "mail.example.com"]);
+		config.force_out_of_scope = true;
+		assert_eq!(enforce(&config, targets.clone()).unwrap(), targets);
+
+		let log = std::fs::read_to_string(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		let lines: Vec<&str> = log.lines().collect();
+		assert_eq!(lines.len(), 2);
+		assert!(lines[0].contains(r#""event":"scope_refused","target":"10.9.9.9""#), "{}", lines[0]);
+		assert!(lines[1].contains(r#""event":"scope_forced","target":"10.9.9.9""#), "{}", lines[1]);
+	}
+}
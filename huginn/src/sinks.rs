@@ -0,0 +1,98 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Streaming result sinks for Huginn
+//!
+//! This module configures and connects the streaming destinations that receive each scan result
+//! as soon as it completes, so long-running scans can feed stream-processing pipelines instead of
+//! waiting for the final report.
+
+#[cfg(feature = "streaming")]
+pub mod kafka;
+#[cfg(feature = "streaming")]
+pub mod nats;
+
+use crate::config::Config;
+use huginn_core::sinks::ResultSink;
+#[cfg(feature = "streaming")]
+use log::info;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+
+/// Streaming sink configuration entry
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum StreamConfig {
+	/// Publish to a Kafka topic
+	Kafka {
+		/// Comma-separated bootstrap brokers
+		brokers: String,
+		/// Topic to publish to
+		topic: String,
+	},
+	/// Publish to a NATS subject
+	Nats {
+		/// Server URL, e.g. nats://localhost:4222
+		url: String,
+		/// Subject to publish to
+		subject: String,
+	},
+}
+
+impl fmt::Display for StreamConfig {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			StreamConfig::Kafka { brokers, topic } => write!(f, "kafka topic {} on {}", topic, brokers),
+			StreamConfig::Nats { url, subject } => write!(f, "nats subject {} on {}", subject, url),
+		}
+	}
+}
+
+/// Connect every streaming sink in the configuration
+#[cfg(feature = "streaming")]
+pub async fn connect(config: &Config) -> Result<Vec<Box<dyn ResultSink>>, Box<dyn Error>> {
+	let mut sinks: Vec<Box<dyn ResultSink>> = Vec::new();
+	for stream in &config.streams {
+		info!("Connecting streaming sink: {}", stream);
+		match stream {
+			StreamConfig::Kafka { brokers, topic } => sinks.push(Box::new(kafka::KafkaSink::new(brokers, topic)?)),
+			StreamConfig::Nats { url, subject } => sinks.push(Box::new(nats::NatsSink::connect(url, subject).await?)),
+		}
+	}
+	Ok(sinks)
+}
+
+/// Connect every streaming sink in the configuration
+#[cfg(not(feature = "streaming"))]
+pub async fn connect(config: &Config) -> Result<Vec<Box<dyn ResultSink>>, Box<dyn Error>> {
+	match config.streams.first() {
+		Some(stream) => Err(format!("Cannot connect {}: this build lacks the streaming feature", stream).into()),
+		None => Ok(Vec::new()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_stream_config_deserialize() {
+		let streams: Vec<StreamConfig> = serde_json::from_str(
+			r#"[
+				{ "type": "kafka", "brokers": "localhost:9092", "topic": "huginn.results" },
+				{ "type": "nats", "url": "nats://localhost:4222", "subject": "huginn.results" }
+			]"#,
+		)
+		.unwrap();
+
+		assert!(matches!(
+			&streams[0],
+			StreamConfig::Kafka { brokers, topic } if brokers == "localhost:9092" && topic == "huginn.results"
+		));
+		assert!(matches!(
+			&streams[1],
+			StreamConfig::Nats { url, subject } if url == "nats://localhost:4222" && subject == "huginn.results"
+		));
+	}
+}
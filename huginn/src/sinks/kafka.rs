@@ -0,0 +1,55 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Kafka streaming sink
+//!
+//! Publishes each result as a JSON message keyed by target.
+
+use async_trait::async_trait;
+use huginn_core::plugins::ScanResult;
+use huginn_core::sinks::ResultSink;
+use rdkafka::ClientConfig;
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use std::error::Error;
+use std::time::Duration;
+
+/// How long to wait for a broker to accept a message
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Kafka streaming sink
+pub struct KafkaSink {
+	producer: FutureProducer,
+	topic: String,
+}
+
+impl KafkaSink {
+	/// Create a producer for the given brokers and topic
+	pub fn new(brokers: &str, topic: &str) -> Result<Self, Box<dyn Error>> {
+		let producer = ClientConfig::new().set("bootstrap.servers", brokers).create()?;
+		Ok(Self {
+			producer,
+			topic: topic.to_string(),
+		})
+	}
+}
+
+#[async_trait]
+impl ResultSink for KafkaSink {
+	fn name(&self) -> String {
+		format!("kafka:{}", self.topic)
+	}
+
+	async fn publish(&self, result: &ScanResult) -> Result<(), Box<dyn Error>> {
+		let payload = serde_json::to_vec(result)?;
+		let record = FutureRecord::to(&self.topic).key(&result.target).payload(&payload);
+		self.producer
+			.send(record, DELIVERY_TIMEOUT)
+			.await
+			.map_err(|(e, _)| e)?;
+		Ok(())
+	}
+
+	async fn flush(&self) -> Result<(), Box<dyn Error>> {
+		Ok(self.producer.flush(DELIVERY_TIMEOUT)?)
+	}
+}
@@ -0,0 +1,44 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! NATS streaming sink
+//!
+//! Publishes each result as a JSON message on a NATS subject.
+
+use async_trait::async_trait;
+use huginn_core::plugins::ScanResult;
+use huginn_core::sinks::ResultSink;
+use std::error::Error;
+
+/// NATS streaming sink
+pub struct NatsSink {
+	client: async_nats::Client,
+	subject: String,
+}
+
+impl NatsSink {
+	/// Connect to a NATS server
+	pub async fn connect(url: &str, subject: &str) -> Result<Self, Box<dyn Error>> {
+		Ok(Self {
+			client: async_nats::connect(url).await?,
+			subject: subject.to_string(),
+		})
+	}
+}
+
+#[async_trait]
+impl ResultSink for NatsSink {
+	fn name(&self) -> String {
+		format!("nats:{}", self.subject)
+	}
+
+	async fn publish(&self, result: &ScanResult) -> Result<(), Box<dyn Error>> {
+		let payload = serde_json::to_vec(result)?;
+		self.client.publish(self.subject.clone(), payload.into()).await?;
+		Ok(())
+	}
+
+	async fn flush(&self) -> Result<(), Box<dyn Error>> {
+		Ok(self.client.flush().await?)
+	}
+}
@@ -0,0 +1,609 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Subdomain enumeration for Huginn
+//!
+//! The `subdomains` scan type lists the hosts under each domain target from three sources: the
+//! names in certificate-transparency logs, searched through crt.sh; the names in a wordlist,
+//! looked up in DNS; and the zone itself, transferred from one of its name servers when they
+//! allow AXFR, or walked along its NSEC chain when it is signed without NSEC3. Every name that
+//! resolves is reported as `found`, and the scanner then scans it with the other configured scan
+//! types, as long as it is within the scope and not excluded. Name servers outside the scope or
+//! excluded are not asked for the zone. A name server that allows zone transfers and a zone that
+//! can be walked are findings of their own about the domain. Address targets are skipped.
+
+use crate::dns::{query, receive, send};
+use async_trait::async_trait;
+use hickory_resolver::TokioResolver;
+use hickory_resolver::proto::op::ResponseCode;
+#[cfg(feature = "dns-checks")]
+use hickory_resolver::proto::dnssec::rdata::DNSSECRData;
+use hickory_resolver::proto::rr::{Name, RData, RecordType};
+use hickory_resolver::proto::serialize::binary::BinDecodable;
+use huginn_core::error::{BoxError, HuginnError};
+use huginn_core::plugins::{FOUND_STATUS, Plugin, ScanContext, ScanResult, ScanType, Severity};
+use huginn_core::targets;
+use log::{debug, info, warn};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::task::JoinSet;
+
+/// Certificate-transparency search service queried by default
+pub const DEFAULT_CT_URL: &str = "https://crt.sh";
+
+/// Time allowed for a certificate-transparency search, which can be slow for large domains
+const CT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Name lookups in flight at once
+const CONCURRENCY: usize = 32;
+
+/// Most names followed along an NSEC chain
+const MAX_WALK: usize = 10_000;
+
+/// ATT&CK technique for gathering DNS information about a victim's network
+const TECHNIQUE: &str = "T1590.002";
+
+/// Labels looked up when no wordlist is configured
+const WORDLIST: &[&str] = &[
+	"www", "mail", "webmail", "smtp", "imap", "pop", "mx", "mx1", "mx2", "ns1", "ns2", "ns3",
+	"dns", "vpn", "remote", "gateway", "gw", "portal", "intranet", "extranet", "admin", "api",
+	"app", "apps", "dev", "test", "staging", "stage", "uat", "qa", "demo", "beta", "cdn", "static",
+	"assets", "media", "files", "ftp", "sftp", "git", "gitlab", "jenkins", "ci", "jira",
+	"confluence", "wiki", "docs", "support", "help", "status", "monitor", "grafana", "kibana",
+	"auth", "sso", "login", "owa", "exchange", "autodiscover", "sip", "shop", "store", "blog", "m",
+	"mobile", "db", "sql", "backup", "proxy", "internal", "old", "new", "secure",
+];
+
+/// Where subdomain names are found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+	/// Certificate-transparency logs
+	Ct,
+	/// DNS lookups of the names in a wordlist
+	Wordlist,
+	/// A zone transfer, or a walk of the zone's NSEC chain
+	Zone,
+}
+
+impl fmt::Display for Source {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Source::Ct => "certificate transparency",
+			Source::Wordlist => "wordlist",
+			Source::Zone => "zone",
+		})
+	}
+}
+
+/// Settings of the `subdomains` scan type
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SubdomainsConfig {
+	/// Sources that names are gathered from
+	pub sources: Vec<Source>,
+	/// File of labels to look up, one per line; a built-in list of common labels when unset
+	pub wordlist: Option<PathBuf>,
+	/// Certificate-transparency search service, queried as crt.sh is
+	pub ct_url: String,
+}
+
+impl Default for SubdomainsConfig {
+	fn default() -> Self {
+		Self {
+			sources: vec![Source::Ct, Source::Wordlist, Source::Zone],
+			wordlist: None,
+			ct_url: DEFAULT_CT_URL.to_string(),
+		}
+	}
+}
+
+/// Subdomain enumeration plugin
+pub struct SubdomainsPlugin {
+	config: SubdomainsConfig,
+	admit: Option<Box<Admit>>,
+}
+
+/// Decides whether a host may be sent queries
+type Admit = dyn Fn(&str) -> bool + Send + Sync;
+
+impl SubdomainsPlugin {
+	/// A plugin gathering names from the configured sources
+	pub fn new(config: SubdomainsConfig) -> Self {
+		Self { config, admit: None }
+	}
+
+	/// Decide which of the domain's name servers are asked for the zone, such as only those within
+	/// an authorized scope; every name server is asked when unset
+	pub fn admit(mut self, admit: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+		self.admit = Some(Box::new(admit));
+		self
+	}
+
+	/// Labels to look up: the configured wordlist without blank lines and comments, or the
+	/// built-in list
+	fn words(&self) -> Result<Vec<String>, HuginnError> {
+		let Some(path) = &self.config.wordlist else {
+			return Ok(WORDLIST.iter().map(ToString::to_string).collect());
+		};
+		let text = std::fs::read_to_string(path).map_err(|e| {
+			let message = format!("Failed to read wordlist {}: {}", path.display(), e);
+			HuginnError::plugin("subdomains", message)
+		})?;
+		Ok(text
+			.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty() && !line.starts_with('#'))
+			.map(str::to_ascii_lowercase)
+			.collect())
+	}
+}
+
+/// A name as a host under the domain: lowercase, without a trailing dot or a leading wildcard
+/// label, and made only of letters, digits, and hyphens; `None` for anything else
+fn host_name(name: &str, domain: &str) -> Option<String> {
+	let name = name.trim().trim_end_matches('.').to_ascii_lowercase();
+	let name = name.strip_prefix("*.").unwrap_or(&name);
+	let valid = name.split('.').all(|label| {
+		!label.is_empty()
+			&& label.len() <= 63
+			&& label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+	});
+	(valid && name != domain && targets::matches(domain, name)).then(|| name.to_string())
+}
+
+/// Names in a crt.sh search result: each certificate's common name and the names in its subject
+/// alternative name, one per line
+fn ct_names(entries: &[Value]) -> impl Iterator<Item = &str> {
+	entries
+		.iter()
+		.flat_map(|entry| [entry["common_name"].as_str(), entry["name_value"].as_str()])
+		.flatten()
+		.flat_map(str::lines)
+}
+
+/// Search certificate-transparency logs for the names of certificates issued under the domain
+async fn ct(url: &str, domain: &str) -> Result<Vec<String>, BoxError> {
+	let client = reqwest::Client::builder()
+		.timeout(CT_TIMEOUT)
+		.user_agent(concat!("huginn/", env!("CARGO_PKG_VERSION")))
+		.build()?;
+	let query = [("q", format!("%.{}", domain)), ("output", "json".to_string())];
+	let entries: Vec<Value> = client
+		.get(url.trim_end_matches('/'))
+		.query(&query)
+		.send()
+		.await?
+		.error_for_status()?
+		.json()
+		.await?;
+	Ok(ct_names(&entries).filter_map(|name| host_name(name, domain)).collect())
+}
+
+/// Resolve every name, keeping those that resolve with their addresses
+async fn resolve(names: Vec<String>, context: &ScanContext) -> BTreeMap<String, Vec<IpAddr>> {
+	let context = Arc::new(context.clone());
+	let mut resolved = BTreeMap::new();
+	for chunk in names.chunks(CONCURRENCY) {
+		let mut lookups = JoinSet::new();
+		for name in chunk {
+			let (context, name) = (context.clone(), name.clone());
+			lookups.spawn(async move {
+				let addresses = context.probe(|| context.transport.resolve(&name)).await;
+				(name, addresses.and_then(Result::ok).unwrap_or_default())
+			});
+		}
+		while let Some(lookup) = lookups.join_next().await {
+			if let Ok((name, addresses)) = lookup
+				&& !addresses.is_empty()
+			{
+				resolved.insert(name, addresses);
+			}
+		}
+	}
+	resolved
+}
+
+/// Addresses that a name nobody would register resolves to, which wordlist names resolving only
+/// to them are answered by a wildcard record
+async fn wildcard(domain: &str, context: &ScanContext) -> HashSet<IpAddr> {
+	let nonce =
+		SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+	let name = format!("huginn-{:x}.{}", nonce, domain);
+	resolve(vec![name], context).await.into_values().flatten().collect()
+}
+
+/// Transfer the zone, returning the owner names of its records; empty when the transfer is refused
+async fn transfer<S: AsyncRead + AsyncWrite + Unpin>(
+	stream: &mut S,
+	apex: &Name,
+	timeout: Duration,
+) -> Result<Vec<Name>, BoxError> {
+	send(stream, &query(1, apex, RecordType::AXFR)).await?;
+	let mut names = Vec::new();
+	let mut soa = 0;
+	// A transfer starts and ends with the zone's SOA record, over as many messages as it needs
+	while soa < 2 {
+		let message = receive(stream, timeout).await?;
+		if message.response_code() != ResponseCode::NoError || message.answers().is_empty() {
+			break;
+		}
+		for record in message.answers() {
+			if record.record_type() == RecordType::SOA {
+				soa += 1;
+			}
+			names.push(record.name().clone());
+		}
+	}
+	Ok(if soa == 0 { Vec::new() } else { names })
+}
+
+/// The next owner name in an NSEC record's data, which starts with it uncompressed
+fn next_name(rdata: &[u8]) -> Option<Name> {
+	Name::from_bytes(rdata).ok()
+}
+
+/// Follow the zone's NSEC chain from its apex, returning the names on it; empty when the zone is
+/// not signed with NSEC
+async fn walk<S: AsyncRead + AsyncWrite + Unpin>(
+	stream: &mut S,
+	apex: &Name,
+	timeout: Duration,
+) -> Result<Vec<Name>, BoxError> {
+	let mut names = Vec::new();
+	let mut current = apex.clone();
+	for id in 1..=MAX_WALK {
+		send(stream, &query(id as u16, &current, RecordType::NSEC)).await?;
+		let message = receive(stream, timeout).await?;
+		let next = message.answers().iter().find_map(|record| match record.data() {
+			RData::Unknown { code: RecordType::NSEC, rdata } if record.name() == &current => {
+				next_name(rdata.anything())
+			},
+			// With DNSSEC support built in, NSEC records are decoded rather than left as raw data
+			#[cfg(feature = "dns-checks")]
+			RData::DNSSEC(DNSSECRData::NSEC(nsec)) if record.name() == &current => {
+				Some(nsec.next_domain_name().clone())
+			},
+			_ => None,
+		});
+		// The chain ends where it wraps around to the apex
+		match next {
+			Some(next) if apex.zone_of(&next) && &next != apex && !names.contains(&next) => {
+				names.push(next.clone());
+				current = next;
+			},
+			_ => break,
+		}
+	}
+	Ok(names)
+}
+
+/// A zone-level finding about the domain
+fn finding(domain: &str, status: &str, severity: Severity, details: String) -> ScanResult {
+	ScanResult::new(domain, ScanType::Subdomains.to_string(), status)
+		.with_severity(severity)
+		.with_details(details)
+		.with_techniques([TECHNIQUE])
+}
+
+/// The domain's name servers that may be queried, each with the address it resolves to; a server
+/// whose name or address is out of scope or excluded is left out, since the zone's owner may not
+/// run it
+async fn name_servers(
+	domain: &str,
+	context: &ScanContext,
+	admit: Option<&Admit>,
+) -> Result<Vec<(String, String)>, BoxError> {
+	let resolver = TokioResolver::builder_tokio()?.build();
+	let mut servers = Vec::new();
+	for ns in resolver.ns_lookup(domain).await?.iter() {
+		let server = ns.0.to_ascii().trim_end_matches('.').to_string();
+		let address = match context.resolve(&server).await {
+			Ok(address) => address.to_string(),
+			Err(e) => {
+				debug!("Skipping name server {} of {}: {}", server, domain, e);
+				continue;
+			},
+		};
+		if admit.is_some_and(|admit| !admit(&server) || !admit(&address)) {
+			info!(
+				"Not asking name server {} ({}) for the zone of {}: out of scope or excluded",
+				server, address, domain
+			);
+			continue;
+		}
+		servers.push((server, address));
+	}
+	Ok(servers)
+}
+
+/// The names in the domain's zone, transferred from a name server that allows it or walked along
+/// its NSEC chain, with findings about the servers and zone that gave them away
+async fn zone(
+	domain: &str,
+	context: &ScanContext,
+	admit: Option<&Admit>,
+) -> Result<(Vec<String>, Vec<ScanResult>), BoxError> {
+	let apex = Name::from_ascii(format!("{}.", domain))?;
+	let servers = name_servers(domain, context, admit).await?;
+	let hosts = |names: Vec<Name>| {
+		names.iter().filter_map(|name| host_name(&name.to_ascii(), domain)).collect::<Vec<_>>()
+	};
+
+	for (server, address) in &servers {
+		let mut stream = match context.connect(address, 53).await {
+			Ok(stream) => stream,
+			Err(e) => {
+				debug!("Failed to connect to name server {}: {}", server, e);
+				continue;
+			},
+		};
+		match transfer(&mut stream, &apex, context.timeout).await {
+			Ok(names) if !names.is_empty() => {
+				let details = format!(
+					"name server {} ({}) allows zone transfers (AXFR), giving away {} records",
+					server,
+					address,
+					names.len()
+				);
+				let found = finding(domain, "zone_transfer", Severity::Medium, details);
+				return Ok((hosts(names), vec![found]));
+			},
+			Ok(_) => debug!("Name server {} refused a zone transfer of {}", server, domain),
+			Err(e) => debug!("Zone transfer of {} from {} failed: {}", domain, server, e),
+		}
+	}
+
+	// Every server holds the same chain, so the first one that answers is enough
+	for (server, address) in &servers {
+		let walked = match context.connect(address, 53).await {
+			Ok(mut stream) => walk(&mut stream, &apex, context.timeout).await,
+			Err(e) => Err(e.into()),
+		};
+		match walked {
+			Ok(names) if !names.is_empty() => {
+				let details = format!(
+					"zone is signed with NSEC, so {} names were walked from {}",
+					names.len(),
+					server
+				);
+				let found = finding(domain, "zone_walk", Severity::Low, details);
+				return Ok((hosts(names), vec![found]));
+			},
+			Ok(_) => break,
+			Err(e) => debug!("NSEC walk of {} from {} failed: {}", domain, server, e),
+		}
+	}
+	Ok((Vec::new(), Vec::new()))
+}
+
+/// A host found under the domain
+fn found(name: String, domain: &str, sources: &[Source], addresses: &[IpAddr]) -> ScanResult {
+	let sources: Vec<String> = sources.iter().map(ToString::to_string).collect();
+	let addresses: Vec<String> = addresses.iter().map(ToString::to_string).collect();
+	ScanResult {
+		target: name,
+		scan_type: ScanType::Subdomains.to_string(),
+		port: None,
+		status: FOUND_STATUS.to_string(),
+		severity: None,
+		service: None,
+		version: None,
+		details: Some(format!(
+			"subdomain of {} from {}; resolves to {}",
+			domain,
+			sources.join(", "),
+			addresses.join(", ")
+		)),
+		techniques: Vec::new(),
+		screenshot: None,
+		rtt: None,
+	}
+}
+
+#[async_trait]
+impl Plugin for SubdomainsPlugin {
+	fn name(&self) -> String {
+		"Subdomain Enumerator".to_string()
+	}
+
+	fn scan_type(&self) -> ScanType {
+		ScanType::Subdomains
+	}
+
+	fn description(&self) -> String {
+		"Finds the hosts under domain targets in certificate-transparency logs, a wordlist, and \
+		the zone".to_string()
+	}
+
+	fn settings(&self) -> &'static [&'static str] {
+		&["subdomains", "timeout", "retries", "scan_delay", "plugin_timing"]
+	}
+
+	fn probes_per_target(&self, _context: &ScanContext) -> usize {
+		// Each wordlist name and the wildcard check is one lookup
+		if self.config.sources.contains(&Source::Wordlist) {
+			self.words().map_or(0, |words| words.len() + 1)
+		} else {
+			0
+		}
+	}
+
+	fn finds_hosts(&self) -> bool {
+		true
+	}
+
+	async fn scan(
+		&self,
+		target: &str,
+		context: &ScanContext,
+	) -> Result<Vec<ScanResult>, HuginnError> {
+		if target.parse::<IpAddr>().is_ok() {
+			debug!("Skipping subdomain enumeration of {}: not a domain", target);
+			return Ok(Vec::new());
+		}
+		let domain = target.trim_end_matches('.').to_ascii_lowercase();
+		let mut names: BTreeMap<String, Vec<Source>> = BTreeMap::new();
+		let mut results = Vec::new();
+		let mut add = |found: Vec<String>, source: Source| {
+			for name in found {
+				let sources = names.entry(name).or_default();
+				if !sources.contains(&source) {
+					sources.push(source);
+				}
+			}
+		};
+
+		for &source in &self.config.sources {
+			match source {
+				Source::Ct => match ct(&self.config.ct_url, &domain).await {
+					Ok(found) => add(found, source),
+					Err(e) => warn!("Certificate-transparency search for {} failed: {}", domain, e),
+				},
+				Source::Wordlist => {
+					let words = self.words()?;
+					add(
+						words
+							.iter()
+							.filter_map(|word| host_name(&format!("{}.{}", word, domain), &domain))
+							.collect(),
+						source,
+					);
+				},
+				Source::Zone => match zone(&domain, context, self.admit.as_deref()).await {
+					Ok((found, findings)) => {
+						add(found, source);
+						results.extend(findings);
+					},
+					Err(e) => warn!("Failed to read the zone of {}: {}", domain, e),
+				},
+			}
+		}
+
+		let wildcard = if self.config.sources.contains(&Source::Wordlist) {
+			wildcard(&domain, context).await
+		} else {
+			HashSet::new()
+		};
+		let resolved = resolve(names.keys().cloned().collect(), context).await;
+		for (name, sources) in names {
+			let Some(addresses) = resolved.get(&name) else {
+				continue;
+			};
+			// A wordlist name answered by a wildcard record only proves the wildcard exists
+			if sources == [Source::Wordlist]
+				&& addresses.iter().all(|address| wildcard.contains(address))
+			{
+				continue;
+			}
+			results.push(found(name, &domain, &sources, addresses));
+		}
+		info!(
+			"Found {} subdomains of {}",
+			results.iter().filter(|r| r.status == FOUND_STATUS).count(),
+			domain
+		);
+		Ok(results)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hickory_resolver::proto::op::{Message, MessageType};
+	use hickory_resolver::proto::rr::Record;
+	use hickory_resolver::proto::rr::rdata::{A, NULL, SOA};
+	use hickory_resolver::proto::serialize::binary::BinEncodable;
+	use serde_json::json;
+
+	#[test]
+	fn test_host_name() {
+		assert_eq!(
+			host_name("WWW.Example.com.", "example.com").as_deref(),
+			Some("www.example.com")
+		);
+		assert_eq!(
+			host_name("*.dev.example.com", "example.com").as_deref(),
+			Some("dev.example.com")
+		);
+		assert_eq!(host_name("example.com", "example.com"), None);
+		assert_eq!(host_name("*.example.com", "example.com"), None);
+		assert_eq!(host_name("www.example.org", "example.com"), None);
+		assert_eq!(host_name("admin@example.com", "example.com"), None);
+		assert_eq!(host_name("_sip._tcp.example.com", "example.com"), None);
+	}
+
+	#[test]
+	fn test_ct_names() {
+		let entries = vec![
+			json!({ "common_name": "example.com", "name_value": "example.com\nwww.example.com" }),
+			json!({
+				"common_name": "*.api.example.com",
+				"name_value": "*.api.example.com\nsecurity@example.com"
+			}),
+		];
+		let names: Vec<_> =
+			ct_names(&entries).filter_map(|name| host_name(name, "example.com")).collect();
+		assert_eq!(names, ["www.example.com", "api.example.com", "api.example.com"]);
+	}
+
+	#[tokio::test]
+	async fn test_transfer() {
+		let apex = Name::from_ascii("example.com.").unwrap();
+		let (mut client, mut server) = tokio::io::duplex(4096);
+		let zone = apex.clone();
+		tokio::spawn(async move {
+			let request = receive(&mut server, Duration::from_secs(1)).await.unwrap();
+			assert_eq!(request.queries()[0].query_type(), RecordType::AXFR);
+			let soa = SOA::new(zone.clone(), zone.clone(), 1, 3600, 600, 86400, 300);
+			let mut response = Message::new();
+			response.set_id(request.id()).set_message_type(MessageType::Response);
+			response.add_answer(Record::from_rdata(zone.clone(), 300, RData::SOA(soa.clone())));
+			let www = Name::from_ascii("www.example.com.").unwrap();
+			response.add_answer(Record::from_rdata(www, 300, RData::A(A::new(192, 0, 2, 1))));
+			response.add_answer(Record::from_rdata(zone, 300, RData::SOA(soa)));
+			send(&mut server, &response).await.unwrap();
+		});
+		let names = transfer(&mut client, &apex, Duration::from_secs(1)).await.unwrap();
+		let hosts: Vec<_> =
+			names.iter().filter_map(|name| host_name(&name.to_ascii(), "example.com")).collect();
+		assert_eq!(hosts, ["www.example.com"]);
+	}
+
+	#[tokio::test]
+	async fn test_walk() {
+		let apex = Name::from_ascii("example.com.").unwrap();
+		let (mut client, mut server) = tokio::io::duplex(4096);
+		tokio::spawn(async move {
+			// example.com -> mail.example.com -> www.example.com -> back to example.com
+			for (owner, next) in [
+				("example.com.", "mail.example.com."),
+				("mail.example.com.", "www.example.com."),
+				("www.example.com.", "example.com."),
+			] {
+				let request = receive(&mut server, Duration::from_secs(1)).await.unwrap();
+				let owner = Name::from_ascii(owner).unwrap();
+				assert_eq!(request.queries()[0].name(), &owner);
+				let mut rdata = Name::from_ascii(next).unwrap().to_bytes().unwrap();
+				rdata.extend([0, 1, 0x40]);
+				let nsec = RData::Unknown { code: RecordType::NSEC, rdata: NULL::with(rdata) };
+				let mut response = Message::new();
+				response.set_id(request.id()).set_message_type(MessageType::Response);
+				response.add_answer(Record::from_rdata(owner, 300, nsec));
+				send(&mut server, &response).await.unwrap();
+			}
+		});
+		let names = walk(&mut client, &apex, Duration::from_secs(1)).await.unwrap();
+		assert_eq!(
+			names.iter().map(Name::to_ascii).collect::<Vec<_>>(),
+			["mail.example.com.", "www.example.com."]
+		);
+	}
+}
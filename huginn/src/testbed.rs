@@ -0,0 +1,30 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Local test target server
+//!
+//! `huginn testbed`, hidden from the help, serves the canned services of a
+//! [`Testbed`] until interrupted, so scans and demos can run entirely against localhost.
+
+use huginn_core::testbed::Testbed;
+use log::info;
+use std::error::Error;
+use std::net::IpAddr;
+
+/// Serve the canned services until Ctrl-C
+pub async fn run(address: IpAddr, base_port: u16) -> Result<(), Box<dyn Error>> {
+	let testbed = Testbed::start(address, base_port).await?;
+	println!("{:<8} {:<8} {:>5}", "Service", "Protocol", "Port");
+	for &(service, port) in testbed.services() {
+		println!("{:<8} {:<8} {:>5}", service.to_string(), if service.is_udp() { "udp" } else { "tcp" }, port);
+	}
+	let ports: Vec<String> = testbed.services().iter().map(|(_, port)| port.to_string()).collect();
+	println!();
+	println!("Scan it with this configuration:");
+	println!("targets = [\"{}\"]", testbed.address());
+	println!("scan_types = [\"tcp_connect\", \"udp\"]");
+	println!("ports = \"{}\"", ports.join(","));
+	info!("Serving the testbed on {}; press Ctrl-C to stop", testbed.address());
+	tokio::signal::ctrl_c().await?;
+	Ok(())
+}
@@ -0,0 +1,202 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Vulnerability correlation for Huginn
+//!
+//! When a scan identifies a service version, such as `OpenSSH 7.2` or `nginx 1.14.0`, it is
+//! looked up in the local vulnerability dataset named by `vuln_db`. Each advisory names a product
+//! and the range of versions it affects, from `introduced` (inclusive) up to `fixed` (exclusive).
+//! The dataset is in Huginn's own flat format, so NVD or OSV data has to be converted to it first.
+//! Matching results have the advisory IDs and CVSS scores added to their details, and their
+//! severity raised to that of the highest score. Without `vuln_db`, the `vulns` dataset cached by
+//! `huginn db update` is used.
+
+use crate::config::Config;
+use crate::db;
+use huginn_core::normalize;
+use huginn_core::plugins::{ScanResult, Severity};
+use log::{debug, info};
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::error::Error;
+use std::path::Path;
+
+/// A known vulnerability in a range of versions of a product
+#[derive(Debug, Clone, Deserialize)]
+pub struct Advisory {
+	/// Advisory identifier, such as a CVE ID
+	pub id: String,
+	/// Affected product, matched case-insensitively against the service name or the first word
+	/// of the detected version (e.g. `openssh`)
+	pub product: String,
+	/// First affected version; every version before `fixed` is affected when unset
+	#[serde(default)]
+	pub introduced: Option<String>,
+	/// First version that is no longer affected; every later version is affected when unset
+	#[serde(default)]
+	pub fixed: Option<String>,
+	/// CVSS base score
+	#[serde(default)]
+	pub cvss: Option<f64>,
+}
+
+/// Numeric parts of the first version number in a string: `OpenSSH 7.2p2` gives `[7, 2]`
+fn numbers(version: &str) -> Vec<u64> {
+	let Some(word) = version.split_whitespace().find(|w| w.starts_with(|c: char| c.is_ascii_digit())) else {
+		return Vec::new();
+	};
+	let mut parts = Vec::new();
+	for part in word.split('.') {
+		let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+		match digits.parse() {
+			Ok(number) => parts.push(number),
+			Err(_) => break,
+		}
+		if digits.len() < part.len() {
+			break;
+		}
+	}
+	parts
+}
+
+/// Compare version numbers, treating missing parts as zero
+fn compare(a: &[u64], b: &[u64]) -> Ordering {
+	(0..a.len().max(b.len()))
+		.map(|i| a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0)))
+		.find(|ordering| ordering.is_ne())
+		.unwrap_or(Ordering::Equal)
+}
+
+impl Advisory {
+	/// Whether the advisory affects a detected service and version
+	fn affects(&self, service: Option<&str>, version: &str) -> bool {
+		let product = self.product.to_ascii_lowercase();
+		let named = service.is_some_and(|s| s.eq_ignore_ascii_case(&product))
+			|| version.split_whitespace().next().is_some_and(|w| w.eq_ignore_ascii_case(&product));
+		let detected = numbers(version);
+		if !named || detected.is_empty() {
+			return false;
+		}
+		let after_introduced = self.introduced.as_deref().is_none_or(|v| compare(&detected, &numbers(v)).is_ge());
+		let before_fixed = self.fixed.as_deref().is_none_or(|v| compare(&detected, &numbers(v)).is_lt());
+		after_introduced && before_fixed
+	}
+}
+
+/// Vulnerability dataset loaded from `vuln_db`
+pub struct Database {
+	advisories: Vec<Advisory>,
+}
+
+impl Database {
+	/// Load a dataset: a JSON array of advisories
+	pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+		let text = std::fs::read_to_string(path)
+			.map_err(|e| format!("Failed to read vulnerability database {}: {}", path.display(), e))?;
+		let advisories: Vec<Advisory> = serde_json::from_str(&text)
+			.map_err(|e| format!("Invalid vulnerability database {}: {}", path.display(), e))?;
+		debug!("Loaded {} advisories from {}", advisories.len(), path.display());
+		Ok(Self { advisories })
+	}
+
+	/// Load the configured dataset, or else the one cached by `huginn db update`, if any
+	pub fn from_config(config: &Config) -> Result<Option<Self>, Box<dyn Error>> {
+		let path = config.vuln_db.clone().or_else(|| db::path(config, db::VULNS));
+		path.as_deref().map(Self::load).transpose()
+	}
+
+	/// Advisories affecting a result's detected service version
+	fn matching(&self, result: &ScanResult) -> Vec<&Advisory> {
+		let Some(version) = result.version.as_deref() else {
+			return Vec::new();
+		};
+		self.advisories
+			.iter()
+			.filter(|advisory| advisory.affects(result.service.as_deref(), version))
+			.collect()
+	}
+
+	/// Attach the advisories affecting each result's detected version
+	pub fn enrich(&self, results: &mut [ScanResult]) {
+		let mut enriched = 0;
+		for result in results.iter_mut() {
+			let advisories = self.matching(result);
+			if advisories.is_empty() {
+				continue;
+			}
+			let ids: Vec<String> = advisories
+				.iter()
+				.map(|advisory| match advisory.cvss {
+					Some(cvss) => format!("{} (CVSS {:.1})", advisory.id, cvss),
+					None => advisory.id.clone(),
+				})
+				.collect();
+			normalize::add_details(result, ids.join(", "));
+			let highest = advisories.iter().filter_map(|advisory| advisory.cvss).map(Severity::from_cvss).max();
+			result.severity = result.severity.max(highest.or(Some(Severity::Info)));
+			enriched += 1;
+		}
+		if enriched > 0 {
+			info!("Matched known vulnerabilities to {} results", enriched);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn advisory(id: &str, product: &str, introduced: Option<&str>, fixed: Option<&str>, cvss: Option<f64>) -> Advisory {
+		Advisory {
+			id: id.to_string(),
+			product: product.to_string(),
+			introduced: introduced.map(str::to_string),
+			fixed: fixed.map(str::to_string),
+			cvss,
+		}
+	}
+
+	#[test]
+	fn test_numbers() {
+		assert_eq!(numbers("OpenSSH 7.2p2"), [7, 2]);
+		assert_eq!(numbers("1.14.0"), [1, 14, 0]);
+		assert_eq!(numbers("nginx/1.14"), Vec::<u64>::new());
+		assert_eq!(compare(&[7, 2], &[7, 2, 0]), Ordering::Equal);
+		assert_eq!(compare(&[1, 14], &[1, 9]), Ordering::Greater);
+	}
+
+	#[test]
+	fn test_enrich() {
+		let database = Database {
+			advisories: vec![
+				advisory("CVE-A", "openssh", None, Some("7.3"), Some(5.9)),
+				advisory("CVE-B", "OpenSSH", Some("7.0"), Some("7.2.1"), Some(9.8)),
+				advisory("CVE-C", "openssh", Some("8.0"), None, Some(7.5)),
+				advisory("CVE-D", "nginx", Some("1.0"), Some("1.16"), None),
+			],
+		};
+		let mut results =
+			vec![
+				ScanResult::new("10.0.0.1", "tcp_connect", "open")
+					.with_port(22)
+					.with_service("ssh")
+					.with_version("OpenSSH 7.2p2"),
+				ScanResult::new("10.0.0.1", "tcp_connect", "open")
+					.with_port(22)
+					.with_service("ssh")
+					.with_version("OpenSSH 9.6"),
+				ScanResult::new("10.0.0.1", "tcp_connect", "open")
+					.with_port(22)
+					.with_service("http")
+					.with_version("nginx 1.14.0"),
+			];
+		database.enrich(&mut results);
+
+		assert_eq!(results[0].details.as_deref(), Some("CVE-A (CVSS 5.9), CVE-B (CVSS 9.8)"));
+		assert_eq!(results[0].severity, Some(Severity::Critical));
+		assert_eq!(results[1].details.as_deref(), Some("CVE-C (CVSS 7.5)"));
+		assert_eq!(results[1].severity, Some(Severity::High));
+		assert_eq!(results[2].details.as_deref(), Some("CVE-D"));
+		assert_eq!(results[2].severity, Some(Severity::Info));
+	}
+}
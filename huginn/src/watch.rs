@@ -0,0 +1,106 @@
+// Copyright (c) 2025 JEleniel
+// Licensed under the Apache License, Version 2.0 or the MIT License
+
+//! Watch mode for Huginn
+//!
+//! `huginn watch` runs the configured scan on an interval and prints only what changed since the
+//! previous pass: observations that appeared (`+`) or disappeared (`-`). The previous results are
+//! kept in memory; when `history_dir` is set, every pass is stored and the first pass compares
+//! against the latest stored run. Configured notifications are sent when something changed.
+
+use crate::baseline::{self, Drift};
+use crate::config::Config;
+use crate::history;
+use huginn_core::plugins::ScanResult;
+#[cfg(feature = "notifications")]
+use log::error;
+use log::{info, warn};
+use std::error::Error;
+use std::time::Duration;
+
+/// Describe one observation in a change line
+fn describe(result: &ScanResult) -> String {
+	match result.port {
+		Some(port) => format!("{} {} {} {}", result.target, result.scan_type, port, result.status),
+		None => format!("{} {} {}", result.target, result.scan_type, result.status),
+	}
+}
+
+/// One line per change, additions first
+fn changes(drift: &Drift) -> Vec<String> {
+	let added = drift.added.iter().map(|r| format!("+ {}", describe(r)));
+	let removed = drift.removed.iter().map(|r| format!("- {}", describe(r)));
+	added.chain(removed).collect()
+}
+
+/// Results of the latest successful stored run, if any
+fn stored(config: &Config) -> Result<Option<Vec<ScanResult>>, Box<dyn Error>> {
+	let Some(dir) = &config.history_dir else {
+		return Ok(None);
+	};
+	match history::list(dir)?.into_iter().rev().find(|run| run.error.is_none()) {
+		Some(run) => {
+			info!("Comparing against stored run {}", run.id);
+			history::results(dir, &run.id).map(Some)
+		},
+		None => Ok(None),
+	}
+}
+
+/// Scan every `interval`, printing changes, until interrupted
+pub async fn run(config: Config, interval: Duration) -> Result<(), Box<dyn Error>> {
+	let mut previous = stored(&config)?;
+
+	loop {
+		let pass = crate::record(&config, async { crate::collect(&config).await.map(|(results, _)| (results, ())) });
+		match pass.await {
+			Ok((results, ())) => {
+				match &previous {
+					Some(previous) => {
+						let drift = baseline::diff(previous, &results);
+						if drift.is_empty() {
+							info!("No changes");
+						} else {
+							info!("{} new, {} no longer present", drift.added.len(), drift.removed.len());
+							for line in changes(&drift) {
+								println!("{}", line);
+							}
+							#[cfg(feature = "notifications")]
+							if let Err(e) = crate::notifications::notify(&config, &results, Some(&drift)).await {
+								error!("Failed to send notifications: {}", e);
+							}
+						}
+					},
+					None => info!("Watching {} results for changes", results.len()),
+				}
+				previous = Some(results);
+			},
+			Err(e) => warn!("Scan failed; keeping the previous results: {}", e),
+		}
+
+		info!("Next scan in {}", humantime::format_duration(interval));
+		tokio::select! {
+			_ = tokio::time::sleep(interval) => {},
+			_ = tokio::signal::ctrl_c() => {
+				info!("Interrupted; stopping watch");
+				return Ok(());
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_changes() {
+		let up = ScanResult::new("10.0.0.1", "tcp_connect", "up");
+		let drift = baseline::diff(
+			&[ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(22), up.clone()],
+			&[up, ScanResult::new("10.0.0.1", "tcp_connect", "open").with_port(443)],
+		);
+		assert_eq!(changes(&drift), ["+ 10.0.0.1 tcp_connect 443 open", "- 10.0.0.1 tcp_connect 22 open"]);
+		assert!(changes(&Drift::default()).is_empty());
+	}
+}